@@ -1,3 +1,75 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Name of the integrity manifest written into `resources/`.
+const MANIFEST_NAME: &str = "manifest.json";
+
 fn main() {
+    write_asset_manifest(Path::new("resources"));
     tauri_build::build()
 }
+
+/// Hashes every file under `resources/` into `resources/manifest.json`
+/// (`path -> { sha256, size }`), which `verify_assets` checks installs against.
+///
+/// The file is only rewritten when its contents change so it doesn't keep
+/// retriggering this build script.
+fn write_asset_manifest(root: &Path) {
+    println!("cargo:rerun-if-changed={}", root.display());
+
+    let mut files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root).unwrap();
+        let relative: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let relative = relative.join("/");
+        if relative == MANIFEST_NAME {
+            continue;
+        }
+
+        let (sha256, size) = hash_file(entry.path());
+        files.insert(
+            relative,
+            serde_json::json!({ "sha256": sha256, "size": size }),
+        );
+    }
+
+    let manifest = serde_json::json!({ "version": 1, "files": files });
+    let contents = serde_json::to_string_pretty(&manifest).unwrap() + "\n";
+    let target = root.join(MANIFEST_NAME);
+    if fs::read_to_string(&target).ok().as_deref() != Some(contents.as_str()) {
+        fs::write(&target, contents).expect("failed to write resources/manifest.json");
+    }
+}
+
+fn hash_file(path: &Path) -> (String, u64) {
+    let mut file = fs::File::open(path).unwrap();
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    (hex, size)
+}
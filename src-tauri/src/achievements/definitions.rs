@@ -0,0 +1,74 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AchievementDef {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Hidden achievements should only be shown once unlocked.
+    pub hidden: bool,
+    /// Counter value that unlocks the achievement; `None` for one-shot ones
+    /// unlocked directly by the game.
+    pub target: Option<u64>,
+}
+
+const fn one_shot(
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+) -> AchievementDef {
+    AchievementDef {
+        id,
+        name,
+        description,
+        hidden: false,
+        target: None,
+    }
+}
+
+const fn counter(
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    target: u64,
+) -> AchievementDef {
+    AchievementDef {
+        id,
+        name,
+        description,
+        hidden: false,
+        target: Some(target),
+    }
+}
+
+/// Every achievement the game knows about, in display order. Ids are stored
+/// in player profiles, so never rename one.
+pub const ACHIEVEMENTS: &[AchievementDef] = &[
+    one_shot("firstBlood", "First Blood", "Defeat your first enemy."),
+    counter("headshots100", "Marksman", "Land 100 headshots.", 100),
+    counter(
+        "grenadeKills25",
+        "Fire in the Hole",
+        "Defeat 25 enemies with grenades.",
+        25,
+    ),
+    counter("pickups50", "Scavenger", "Collect 50 pickups.", 50),
+    one_shot(
+        "level3Flawless",
+        "Untouchable",
+        "Finish level 3 without dying.",
+    ),
+    AchievementDef {
+        hidden: true,
+        ..one_shot(
+            "weatherWatcher",
+            "Weather Watcher",
+            "Cycle through every kind of weather.",
+        )
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static AchievementDef> {
+    ACHIEVEMENTS.iter().find(|a| a.id == id)
+}
@@ -0,0 +1,55 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the achievement commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum AchievementError {
+    #[error("Could not locate the profile directory: {0}")]
+    NoProfileDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Unknown achievement {0:?}")]
+    Unknown(String),
+
+    #[error("Achievement {0:?} has no progress counter")]
+    NoProgress(String),
+
+    #[error("{0} is only available in debug builds")]
+    DebugOnly(&'static str),
+}
+
+impl AchievementError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        AchievementError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AchievementError::NoProfileDir(_) => "noProfileDir",
+            AchievementError::Io { .. } => "io",
+            AchievementError::Unknown(_) => "unknown",
+            AchievementError::NoProgress(_) => "noProgress",
+            AchievementError::DebugOnly(_) => "debugOnly",
+        }
+    }
+}
+
+impl Serialize for AchievementError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AchievementError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,113 @@
+mod definitions;
+mod error;
+mod store;
+
+pub use definitions::AchievementDef;
+pub use error::AchievementError;
+pub use store::{Achievement, AchievementStore};
+
+use crate::time::now_ms;
+use definitions::ACHIEVEMENTS;
+use store::file_path;
+use tauri::{Emitter, State};
+
+pub const UNLOCKED_EVENT: &str = "achievement-unlocked";
+
+/// Every achievement with the active profile's unlock time and progress.
+#[tauri::command]
+pub async fn get_achievements(
+    app: tauri::AppHandle,
+    store: State<'_, AchievementStore>,
+) -> Result<Vec<Achievement>, AchievementError> {
+    let path = file_path(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    let file = store::read(&path)?;
+    Ok(ACHIEVEMENTS
+        .iter()
+        .map(|def| Achievement::new(def, file.records.get(def.id).copied().unwrap_or_default()))
+        .collect())
+}
+
+/// Unlocks `id`. Unlocking again is a no-op and doesn't re-emit the event.
+#[tauri::command]
+pub async fn unlock_achievement(
+    app: tauri::AppHandle,
+    store: State<'_, AchievementStore>,
+    id: String,
+) -> Result<Achievement, AchievementError> {
+    update(&app, &store, &id, |record, _| {
+        if record.unlocked_at_ms.is_none() {
+            record.unlocked_at_ms = Some(now_ms());
+        }
+        Ok(())
+    })
+}
+
+/// Adds `amount` to a counter achievement, unlocking it once the counter
+/// reaches its target. Progress is capped at the target.
+#[tauri::command]
+pub async fn increment_achievement_progress(
+    app: tauri::AppHandle,
+    store: State<'_, AchievementStore>,
+    id: String,
+    amount: u64,
+) -> Result<Achievement, AchievementError> {
+    update(&app, &store, &id, |record, def| {
+        let target = def
+            .target
+            .ok_or_else(|| AchievementError::NoProgress(def.id.to_string()))?;
+        record.progress = record.progress.saturating_add(amount).min(target);
+        if record.progress >= target && record.unlocked_at_ms.is_none() {
+            record.unlocked_at_ms = Some(now_ms());
+        }
+        Ok(())
+    })
+}
+
+/// Clears every unlock and counter for the active profile. Debug builds only.
+#[tauri::command]
+pub async fn reset_achievements(
+    app: tauri::AppHandle,
+    store: State<'_, AchievementStore>,
+) -> Result<(), AchievementError> {
+    if !cfg!(debug_assertions) {
+        return Err(AchievementError::DebugOnly("reset_achievements"));
+    }
+    let path = file_path(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    store::write(&path, &mut Default::default())
+}
+
+/// Applies `change` to the record for `id` and saves it, emitting
+/// `achievement-unlocked` if this call is what unlocked it.
+fn update(
+    app: &tauri::AppHandle,
+    store: &AchievementStore,
+    id: &str,
+    change: impl FnOnce(&mut store::AchievementRecord, &AchievementDef) -> Result<(), AchievementError>,
+) -> Result<Achievement, AchievementError> {
+    let def = definitions::find(id).ok_or_else(|| AchievementError::Unknown(id.to_string()))?;
+    let path = file_path(app)?;
+
+    let (achievement, newly_unlocked) = {
+        let _guard = store.lock.lock().unwrap();
+        let mut file = store::read(&path)?;
+        let record = file.records.entry(def.id.to_string()).or_default();
+        let before = *record;
+        change(record, def)?;
+        let after = *record;
+
+        if after.unlocked_at_ms != before.unlocked_at_ms || after.progress != before.progress {
+            store::write(&path, &mut file)?;
+        }
+        (
+            Achievement::new(def, after),
+            before.unlocked_at_ms.is_none() && after.unlocked_at_ms.is_some(),
+        )
+    };
+
+    if newly_unlocked {
+        let _ = app.emit(UNLOCKED_EVENT, achievement.clone());
+    }
+    Ok(achievement)
+}
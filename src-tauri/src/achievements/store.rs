@@ -0,0 +1,82 @@
+use super::definitions::AchievementDef;
+use super::AchievementError;
+use crate::fs_atomic::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Runtime;
+
+pub const FILE_NAME: &str = "achievements.json";
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Serializes read-modify-write cycles on `achievements.json`.
+#[derive(Default)]
+pub struct AchievementStore {
+    pub(super) lock: Mutex<()>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AchievementRecord {
+    pub unlocked_at_ms: Option<u64>,
+    #[serde(default)]
+    pub progress: u64,
+}
+
+/// Records keyed by achievement id. Ids no longer in the definition table
+/// are kept so a later build that re-adds them doesn't lose the unlock.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AchievementFile {
+    pub version: u32,
+    pub records: BTreeMap<String, AchievementRecord>,
+}
+
+/// An achievement definition with the player's state, as sent to the UI.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Achievement {
+    #[serde(flatten)]
+    pub def: &'static AchievementDef,
+    pub unlocked_at_ms: Option<u64>,
+    pub progress: u64,
+}
+
+impl Achievement {
+    pub fn new(def: &'static AchievementDef, record: AchievementRecord) -> Self {
+        Self {
+            def,
+            unlocked_at_ms: record.unlocked_at_ms,
+            progress: record.progress,
+        }
+    }
+}
+
+pub fn file_path<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, AchievementError> {
+    crate::profiles::active_dir(app)
+        .map(|dir| dir.join(FILE_NAME))
+        .map_err(|e| AchievementError::NoProfileDir(e.to_string()))
+}
+
+/// A missing file means nothing is unlocked yet. An unreadable one is an
+/// error rather than a silent reset, so a bad disk can't wipe progress.
+pub fn read(path: &Path) -> Result<AchievementFile, AchievementError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AchievementFile::default()),
+        Err(e) => return Err(AchievementError::io(path, e)),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| {
+        AchievementError::io(
+            path,
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+pub fn write(path: &Path, file: &mut AchievementFile) -> Result<(), AchievementError> {
+    file.version = FORMAT_VERSION;
+    let json = serde_json::to_vec_pretty(file).expect("achievements serialize");
+    write_atomic(path, &json).map_err(|e| AchievementError::io(path, e))
+}
@@ -0,0 +1,206 @@
+use lru::LruCache;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_BUDGET_BYTES: usize = 64 * 1024 * 1024;
+
+/// Byte-budgeted LRU cache of loaded asset contents, keyed by resolved path.
+pub struct AssetCache {
+    inner: Mutex<CacheInner>,
+}
+
+struct CacheInner {
+    entries: LruCache<PathBuf, Arc<[u8]>>,
+    bytes_used: usize,
+    budget_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes_used: usize,
+    pub budget_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Default for AssetCache {
+    fn default() -> Self {
+        Self::with_budget(DEFAULT_BUDGET_BYTES)
+    }
+}
+
+impl AssetCache {
+    pub fn with_budget(budget_bytes: usize) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                entries: LruCache::unbounded(),
+                bytes_used: 0,
+                budget_bytes,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Looks up `path`, counting a hit or miss and refreshing its recency.
+    pub fn get(&self, path: &Path) -> Option<Arc<[u8]>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.entries.get(path).cloned() {
+            Some(bytes) => {
+                inner.hits += 1;
+                Some(bytes)
+            }
+            None => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Inserts `bytes`, evicting least recently used entries until the budget
+    /// fits. Files larger than the whole budget are not cached at all.
+    pub fn insert(&self, path: PathBuf, bytes: Arc<[u8]>) {
+        let mut inner = self.inner.lock().unwrap();
+        if bytes.len() > inner.budget_bytes {
+            return;
+        }
+
+        if let Some(old) = inner.entries.pop(&path) {
+            inner.bytes_used -= old.len();
+        }
+        inner.bytes_used += bytes.len();
+        inner.entries.put(path, bytes);
+        inner.evict_to_budget();
+    }
+
+    /// Drops a single entry, e.g. after the file changed on disk.
+    pub fn remove(&self, path: &Path) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.entries.pop(path) {
+            Some(old) => {
+                inner.bytes_used -= old.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drops every entry and resets the hit/miss counters.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.bytes_used = 0;
+        inner.hits = 0;
+        inner.misses = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap();
+        CacheStats {
+            entries: inner.entries.len(),
+            bytes_used: inner.bytes_used,
+            budget_bytes: inner.budget_bytes,
+            hits: inner.hits,
+            misses: inner.misses,
+        }
+    }
+}
+
+impl CacheInner {
+    fn evict_to_budget(&mut self) {
+        while self.bytes_used > self.budget_bytes {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => self.bytes_used -= evicted.len(),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(len: usize) -> Arc<[u8]> {
+        vec![0u8; len].into()
+    }
+
+    fn cached(cache: &AssetCache, name: &str) -> bool {
+        cache
+            .inner
+            .lock()
+            .unwrap()
+            .entries
+            .contains(Path::new(name))
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let cache = AssetCache::with_budget(100);
+        cache.insert("a".into(), bytes(40));
+        cache.insert("b".into(), bytes(40));
+        // Touching `a` leaves `b` as the oldest.
+        assert!(cache.get(Path::new("a")).is_some());
+        cache.insert("c".into(), bytes(40));
+
+        assert!(cached(&cache, "a"));
+        assert!(!cached(&cache, "b"));
+        assert!(cached(&cache, "c"));
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.entries, stats.bytes_used, stats.budget_bytes),
+            (2, 80, 100)
+        );
+
+        // One big entry can push out several.
+        cache.insert("d".into(), bytes(90));
+        assert!(cached(&cache, "d"));
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(cache.stats().bytes_used, 90);
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let cache = AssetCache::with_budget(100);
+        assert!(cache.get(Path::new("a")).is_none());
+        cache.insert("a".into(), bytes(10));
+        assert!(cache.get(Path::new("a")).is_some());
+        assert!(cache.get(Path::new("a")).is_some());
+        assert!(cache.get(Path::new("b")).is_none());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+
+        cache.clear();
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.entries, stats.bytes_used, stats.hits, stats.misses),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn skips_files_larger_than_the_budget() {
+        let cache = AssetCache::with_budget(100);
+        cache.insert("a".into(), bytes(50));
+        cache.insert("huge".into(), bytes(101));
+        assert!(!cached(&cache, "huge"));
+        assert!(cached(&cache, "a"));
+        assert_eq!(cache.stats().bytes_used, 50);
+    }
+
+    #[test]
+    fn replacing_and_removing_keep_the_byte_count() {
+        let cache = AssetCache::with_budget(100);
+        cache.insert("a".into(), bytes(30));
+        cache.insert("a".into(), bytes(60));
+        assert_eq!(cache.stats().bytes_used, 60);
+        assert!(cache.remove(Path::new("a")));
+        assert!(!cache.remove(Path::new("a")));
+        assert_eq!(cache.stats().bytes_used, 0);
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Sanity cap on how large a single `.zst` asset may decompress to, so a
+/// malformed or malicious file can't exhaust memory.
+pub const DEFAULT_MAX_DECOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+pub const ZSTD_SUFFIX: &str = ".zst";
+
+pub struct AssetLimits {
+    max_decompressed_bytes: AtomicU64,
+}
+
+impl Default for AssetLimits {
+    fn default() -> Self {
+        Self {
+            max_decompressed_bytes: AtomicU64::new(DEFAULT_MAX_DECOMPRESSED_BYTES),
+        }
+    }
+}
+
+impl AssetLimits {
+    pub fn max_decompressed_bytes(&self) -> u64 {
+        self.max_decompressed_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn set_max_decompressed_bytes(&self, bytes: u64) {
+        self.max_decompressed_bytes.store(bytes, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::loader::read_cancellable;
+    use crate::assets::pak::build_pak;
+    use crate::assets::source::PakBlob;
+    use crate::assets::{AssetError, AssetSource};
+    use std::path::Path;
+    use std::sync::atomic::AtomicBool;
+
+    fn music() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i / 7 % 256) as u8).collect()
+    }
+
+    fn zstd_file(dir: &Path, data: &[u8], max_bytes: u64) -> AssetSource {
+        let path = dir.join("music.ogg.zst");
+        std::fs::write(&path, zstd::encode_all(data, 3).unwrap()).unwrap();
+        AssetSource::Zstd {
+            inner: Box::new(AssetSource::File(path)),
+            max_bytes,
+        }
+    }
+
+    fn read(source: &AssetSource) -> Result<Vec<u8>, AssetError> {
+        read_cancellable(source, &AtomicBool::new(false))
+    }
+
+    #[test]
+    fn round_trips_loose_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = zstd_file(dir.path(), &music(), DEFAULT_MAX_DECOMPRESSED_BYTES);
+        assert_eq!(read(&source).unwrap(), music());
+        assert_eq!(source.path(), dir.path().join("music.ogg"));
+        assert_eq!(source.mime_type(), "audio/ogg");
+    }
+
+    #[test]
+    fn round_trips_pak_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let compressed = zstd::encode_all(&music()[..], 3).unwrap();
+        let bytes = build_pak(&[
+            ("audio/a.ogg", b"first"),
+            ("audio/music.ogg.zst", &compressed),
+        ]);
+        let archive = dir.path().join("base.pak");
+        std::fs::write(&archive, &bytes).unwrap();
+        let source = AssetSource::Zstd {
+            inner: Box::new(AssetSource::Pak(PakBlob {
+                archive,
+                entry: "audio/music.ogg.zst".into(),
+                offset: (bytes.len() - compressed.len()) as u64,
+                size: compressed.len() as u64,
+            })),
+            max_bytes: DEFAULT_MAX_DECOMPRESSED_BYTES,
+        };
+        assert_eq!(read(&source).unwrap(), music());
+    }
+
+    #[test]
+    fn enforces_the_decompressed_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let len = music().len() as u64;
+
+        let at_cap = zstd_file(dir.path(), &music(), len);
+        assert_eq!(read(&at_cap).unwrap().len() as u64, len);
+
+        let over_cap = zstd_file(dir.path(), &music(), len - 1);
+        match read(&over_cap) {
+            Err(AssetError::Decompression { reason, .. }) => {
+                assert!(reason.contains("limit"), "{}", reason)
+            }
+            other => panic!(
+                "expected a decompression error, got {:?}",
+                other.map(|b| b.len())
+            ),
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_streams() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("music.ogg.zst");
+        std::fs::write(&path, b"definitely not zstd").unwrap();
+        let source = AssetSource::Zstd {
+            inner: Box::new(AssetSource::File(path)),
+            max_bytes: DEFAULT_MAX_DECOMPRESSED_BYTES,
+        };
+        assert!(matches!(
+            read(&source),
+            Err(AssetError::Decompression { .. })
+        ));
+    }
+
+    #[test]
+    fn limits_can_be_changed() {
+        let limits = AssetLimits::default();
+        assert_eq!(
+            limits.max_decompressed_bytes(),
+            DEFAULT_MAX_DECOMPRESSED_BYTES
+        );
+        limits.set_max_decompressed_bytes(1024);
+        assert_eq!(limits.max_decompressed_bytes(), 1024);
+    }
+}
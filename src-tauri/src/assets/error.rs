@@ -0,0 +1,92 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced to the frontend by the asset commands.
+///
+/// Serialized as `{ kind, message }` so the webview can branch on `kind`
+/// (e.g. show "missing file" differently from "rejected path").
+#[derive(Debug, thiserror::Error)]
+pub enum AssetError {
+    #[error("Invalid asset path {filename:?}: {reason}")]
+    InvalidPath { filename: String, reason: String },
+
+    #[error("Could not find asset {0} in resources")]
+    NotFound(String),
+
+    #[error("Failed to read {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Loading {0} was cancelled")]
+    Cancelled(String),
+
+    #[error("Invalid pak archive {path:?}: {reason}")]
+    InvalidPak { path: PathBuf, reason: String },
+
+    #[error("Failed to decompress {path:?}: {reason}")]
+    Decompression { path: PathBuf, reason: String },
+
+    #[error("Asset loader busy: {0}")]
+    Busy(String),
+}
+
+impl AssetError {
+    pub(crate) fn invalid(filename: &str, reason: impl Into<String>) -> Self {
+        AssetError::InvalidPath {
+            filename: filename.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        AssetError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn invalid_pak(path: impl Into<PathBuf>, reason: impl Into<String>) -> Self {
+        AssetError::InvalidPak {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn decompression(path: impl Into<PathBuf>, reason: impl Into<String>) -> Self {
+        AssetError::Decompression {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// A blocking task working on `path` panicked or was aborted.
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        AssetError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AssetError::InvalidPath { .. } => "invalidPath",
+            AssetError::NotFound(_) => "notFound",
+            AssetError::Io { .. } => "io",
+            AssetError::Cancelled(_) => "cancelled",
+            AssetError::InvalidPak { .. } => "invalidPak",
+            AssetError::Decompression { .. } => "decompression",
+            AssetError::Busy(_) => "busy",
+        }
+    }
+}
+
+impl Serialize for AssetError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AssetError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
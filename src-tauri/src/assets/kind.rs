@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// Top-level asset categories, each mapped to a subdirectory of `resources/`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AssetKind {
+    Audio,
+    Texture,
+    Model,
+    Level,
+    Locale,
+    /// Game data tables such as weapon definitions.
+    Data,
+    Misc,
+}
+
+impl AssetKind {
+    pub fn dir(self) -> &'static str {
+        match self {
+            AssetKind::Audio => "audio",
+            AssetKind::Texture => "textures",
+            AssetKind::Model => "models",
+            AssetKind::Level => "levels",
+            AssetKind::Locale => "locales",
+            AssetKind::Data => "data",
+            AssetKind::Misc => "misc",
+        }
+    }
+
+    /// Inverse of [`AssetKind::dir`], also accepting the singular kind name
+    /// (`gameasset://texture/...` and `gameasset://textures/...` both work).
+    pub fn from_dir(dir: &str) -> Option<Self> {
+        match dir {
+            "audio" => Some(AssetKind::Audio),
+            "texture" | "textures" => Some(AssetKind::Texture),
+            "model" | "models" => Some(AssetKind::Model),
+            "level" | "levels" => Some(AssetKind::Level),
+            "locale" | "locales" => Some(AssetKind::Locale),
+            "data" => Some(AssetKind::Data),
+            "misc" => Some(AssetKind::Misc),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::compression::ZSTD_SUFFIX;
+use super::mime::is_audio_extension;
+use super::path::slash_path;
+use super::AssetError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetEntry {
+    /// Path relative to the asset root, always `/`-separated.
+    pub path: String,
+    pub size: u64,
+    pub extension: String,
+    /// Milliseconds since the Unix epoch, if the platform reports it.
+    pub modified_ms: Option<u64>,
+    /// Stored as `<path>.zst`; `size` is the compressed size on disk.
+    pub compressed: bool,
+}
+
+/// Walks every root (earlier roots win on duplicates) and returns audio files
+/// sorted by relative path. A plain file wins over a `.zst` copy in the same root.
+///
+/// `filter` is a glob matched against the relative path; `*` does not cross
+/// `/`, so `weapons/*.ogg` only matches direct children of `weapons/`.
+pub fn list_audio_files(
+    roots: &[PathBuf],
+    filter: Option<&str>,
+) -> Result<Vec<AssetEntry>, AssetError> {
+    let pattern = filter.map(glob::Pattern::new).transpose().map_err(|e| {
+        AssetError::invalid(filter.unwrap_or_default(), format!("bad filter: {}", e))
+    })?;
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    // Value carries the index of the root the entry came from.
+    let mut found: BTreeMap<String, (usize, AssetEntry)> = BTreeMap::new();
+    for (root_index, root) in roots.iter().enumerate() {
+        if !root.is_dir() {
+            continue;
+        }
+
+        // Symlinks are neither followed nor listed, so nothing outside the root shows up.
+        for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(stored) = entry.path().strip_prefix(root).ok().and_then(slash_path) else {
+                continue;
+            };
+            // `.ogg.zst` is listed under its logical name, the way loads resolve it.
+            let (relative, compressed) = match stored.strip_suffix(ZSTD_SUFFIX) {
+                Some(logical) => (logical.to_string(), true),
+                None => (stored, false),
+            };
+            let Some(extension) = Path::new(&relative).extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            if !is_audio_extension(extension) {
+                continue;
+            }
+            let extension = extension.to_ascii_lowercase();
+            if let Some((seen_in, seen)) = found.get(&relative) {
+                let replaces_compressed = *seen_in == root_index && seen.compressed && !compressed;
+                if !replaces_compressed {
+                    continue;
+                }
+            }
+            if let Some(pattern) = &pattern {
+                if !pattern.matches_with(&relative, options) {
+                    continue;
+                }
+            }
+
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64);
+
+            found.insert(
+                relative.clone(),
+                (
+                    root_index,
+                    AssetEntry {
+                        path: relative,
+                        size: metadata.len(),
+                        extension,
+                        modified_ms,
+                        compressed,
+                    },
+                ),
+            );
+        }
+    }
+
+    Ok(found.into_values().map(|(_, entry)| entry).collect())
+}
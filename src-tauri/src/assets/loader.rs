@@ -0,0 +1,337 @@
+use super::{AssetError, AssetSource};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Reads are done in chunks so a cancelled load stops within one chunk.
+const READ_CHUNK_BYTES: usize = 256 * 1024;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedAsset {
+    pub bytes: Vec<u8>,
+    pub resolved_path: PathBuf,
+    pub duration_ms: f64,
+    pub from_cache: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedAsset {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    pub size: u64,
+    pub resolved_from: PathBuf,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetRange {
+    pub bytes: Vec<u8>,
+    pub offset: u64,
+    /// Size of the whole file, so callers can tell when they've reached EOF.
+    pub total_size: u64,
+}
+
+/// In-flight loads keyed by the frontend-chosen request id.
+#[derive(Default)]
+pub struct AssetLoads {
+    in_flight: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl AssetLoads {
+    /// Registers a load and returns a guard that cancels it when dropped.
+    ///
+    /// JS promises can't be aborted, but if Tauri drops the command future
+    /// (e.g. the webview reloads) the guard still stops the blocking read.
+    pub fn begin(&self, request_id: Option<String>) -> LoadGuard<'_> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Some(id) = &request_id {
+            self.in_flight
+                .lock()
+                .unwrap()
+                .insert(id.clone(), flag.clone());
+        }
+        LoadGuard {
+            loads: self,
+            request_id,
+            flag,
+        }
+    }
+
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.in_flight.lock().unwrap().get(request_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct LoadGuard<'a> {
+    loads: &'a AssetLoads,
+    request_id: Option<String>,
+    flag: Arc<AtomicBool>,
+}
+
+impl LoadGuard<'_> {
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+}
+
+impl Drop for LoadGuard<'_> {
+    fn drop(&mut self) {
+        self.flag.store(true, Ordering::Relaxed);
+        if let Some(id) = &self.request_id {
+            let mut in_flight = self.loads.in_flight.lock().unwrap();
+            // A newer load may have reused the id; only remove our own entry.
+            if in_flight
+                .get(id)
+                .is_some_and(|f| Arc::ptr_eq(f, &self.flag))
+            {
+                in_flight.remove(id);
+            }
+        }
+    }
+}
+
+/// Reads the whole asset, bailing out early once `cancelled` is set.
+/// Compressed assets are decompressed, up to their `max_bytes` cap.
+pub fn read_cancellable(
+    source: &AssetSource,
+    cancelled: &AtomicBool,
+) -> Result<Vec<u8>, AssetError> {
+    let path = source.path();
+    let (file, size) = source.open_at(0)?;
+    let stored = file.take(size);
+
+    match source {
+        AssetSource::Zstd { max_bytes, .. } => {
+            let decompression = |e: std::io::Error| AssetError::decompression(&path, e.to_string());
+            let decoder = zstd::stream::read::Decoder::new(stored).map_err(decompression)?;
+            let bytes = read_chunks(
+                decoder.take(max_bytes.saturating_add(1)),
+                0,
+                cancelled,
+                &path,
+                decompression,
+            )?;
+            if bytes.len() as u64 > *max_bytes {
+                return Err(AssetError::decompression(
+                    &path,
+                    format!("decompressed size exceeds the {} byte limit", max_bytes),
+                ));
+            }
+            Ok(bytes)
+        }
+        _ => read_chunks(stored, size as usize, cancelled, &path, |e| {
+            AssetError::io(&path, e)
+        }),
+    }
+}
+
+fn read_chunks(
+    mut reader: impl Read,
+    capacity: usize,
+    cancelled: &AtomicBool,
+    path: &Path,
+    map_err: impl Fn(std::io::Error) -> AssetError,
+) -> Result<Vec<u8>, AssetError> {
+    let mut bytes = Vec::with_capacity(capacity);
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(AssetError::Cancelled(path.display().to_string()));
+        }
+        let n = reader.read(&mut chunk).map_err(&map_err)?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+    }
+
+    Ok(bytes)
+}
+
+/// Reads at most `length` bytes starting at `offset`. Windows that run past
+/// the end of the asset come back short (or empty) rather than failing.
+///
+/// Compressed assets can't be seeked into, so they are decompressed in full
+/// and sliced.
+pub fn read_range(
+    source: &AssetSource,
+    offset: u64,
+    length: u64,
+) -> Result<AssetRange, AssetError> {
+    if source.is_compressed() {
+        let bytes = read_cancellable(source, &AtomicBool::new(false))?;
+        return Ok(slice_range(&bytes, offset, length));
+    }
+
+    let (mut file, total_size) = source.open_at(offset)?;
+
+    let start = offset.min(total_size);
+    let end = offset.saturating_add(length).min(total_size);
+    let mut bytes = vec![0u8; (end - start) as usize];
+    file.read_exact(&mut bytes)
+        .map_err(|e| AssetError::io(source.path(), e))?;
+
+    Ok(AssetRange {
+        bytes,
+        offset: start,
+        total_size,
+    })
+}
+
+pub fn slice_range(bytes: &[u8], offset: u64, length: u64) -> AssetRange {
+    let total_size = bytes.len() as u64;
+    let start = offset.min(total_size);
+    let end = offset.saturating_add(length).min(total_size);
+    AssetRange {
+        bytes: bytes[start as usize..end as usize].to_vec(),
+        offset: start,
+        total_size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out full chunks forever, setting `cancel` once `cancel_after`
+    /// of them have been read.
+    struct Endless<'a> {
+        reads: usize,
+        cancel_after: usize,
+        cancel: &'a AtomicBool,
+    }
+
+    impl Read for Endless<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            if self.reads == self.cancel_after {
+                self.cancel.store(true, Ordering::Relaxed);
+            }
+            buf.fill(7);
+            Ok(buf.len())
+        }
+    }
+
+    #[test]
+    fn cancelled_reads_stop_within_a_chunk() {
+        let cancelled = AtomicBool::new(false);
+        let mut reader = Endless {
+            reads: 0,
+            cancel_after: 3,
+            cancel: &cancelled,
+        };
+        let result = read_chunks(&mut reader, 0, &cancelled, Path::new("loop.ogg"), |e| {
+            AssetError::io("loop.ogg", e)
+        });
+        assert!(matches!(result, Err(AssetError::Cancelled(_))));
+        assert_eq!(reader.reads, 3);
+    }
+
+    #[test]
+    fn reads_nothing_once_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.wav");
+        std::fs::write(&path, b"RIFF").unwrap();
+        let source = AssetSource::File(path);
+
+        assert!(matches!(
+            read_cancellable(&source, &AtomicBool::new(true)),
+            Err(AssetError::Cancelled(_))
+        ));
+        assert_eq!(
+            read_cancellable(&source, &AtomicBool::new(false)).unwrap(),
+            b"RIFF"
+        );
+    }
+
+    #[test]
+    fn cancels_by_request_id() {
+        let loads = AssetLoads::default();
+        let guard = loads.begin(Some("music".into()));
+        let flag = guard.flag();
+        assert!(!loads.cancel("other"));
+        assert!(loads.cancel("music"));
+        assert!(flag.load(Ordering::Relaxed));
+
+        drop(guard);
+        assert!(!loads.cancel("music"));
+    }
+
+    #[test]
+    fn dropping_a_guard_cancels_its_load() {
+        let loads = AssetLoads::default();
+        let flag = loads.begin(None).flag();
+        assert!(flag.load(Ordering::Relaxed));
+
+        // A newer load reusing the id isn't unregistered by the old guard.
+        let old = loads.begin(Some("music".into()));
+        let new = loads.begin(Some("music".into()));
+        let new_flag = new.flag();
+        drop(old);
+        assert!(loads.cancel("music"));
+        assert!(new_flag.load(Ordering::Relaxed));
+    }
+
+    fn numbered_file(dir: &Path, len: usize) -> (AssetSource, Vec<u8>) {
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let path = dir.join("music.ogg");
+        std::fs::write(&path, &data).unwrap();
+        (AssetSource::File(path), data)
+    }
+
+    #[test]
+    fn last_partial_chunk_comes_back_short() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, data) = numbered_file(dir.path(), 10_000);
+
+        let range = read_range(&source, 8_192, 4_096).unwrap();
+        assert_eq!(range.bytes, &data[8_192..]);
+        assert_eq!((range.offset, range.total_size), (8_192, 10_000));
+
+        let past_end = read_range(&source, 20_000, 4_096).unwrap();
+        assert!(past_end.bytes.is_empty());
+        assert_eq!((past_end.offset, past_end.total_size), (10_000, 10_000));
+
+        let overflow = read_range(&source, 9_999, u64::MAX).unwrap();
+        assert_eq!(overflow.bytes, &data[9_999..]);
+    }
+
+    #[test]
+    fn zero_length_range_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, _) = numbered_file(dir.path(), 10_000);
+
+        let range = read_range(&source, 4_000, 0).unwrap();
+        assert!(range.bytes.is_empty());
+        assert_eq!((range.offset, range.total_size), (4_000, 10_000));
+    }
+
+    #[test]
+    fn compressed_ranges_slice_the_inflated_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 13) as u8).collect();
+        let path = dir.path().join("music.ogg.zst");
+        std::fs::write(&path, zstd::encode_all(&data[..], 3).unwrap()).unwrap();
+        let source = AssetSource::Zstd {
+            inner: Box::new(AssetSource::File(path)),
+            max_bytes: 1 << 20,
+        };
+
+        let range = read_range(&source, 9_000, 4_096).unwrap();
+        assert_eq!(range.bytes, &data[9_000..]);
+        assert_eq!(range.total_size, 10_000);
+        assert!(read_range(&source, 100, 0).unwrap().bytes.is_empty());
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+const AUDIO_EXTENSIONS: &[&str] = &["ogg", "oga", "mp3", "wav", "flac", "m4a", "opus"];
+
+pub fn is_audio_extension(ext: &str) -> bool {
+    AUDIO_EXTENSIONS.iter().any(|a| a.eq_ignore_ascii_case(ext))
+}
+
+/// Best-effort MIME type from a file extension.
+pub fn mime_for_path(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "ogg" | "oga" => "audio/ogg",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "m4a" => "audio/mp4",
+        "opus" => "audio/opus",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "ktx2" => "image/ktx2",
+        "hdr" => "image/vnd.radiance",
+        "gltf" => "model/gltf+json",
+        "glb" => "model/gltf-binary",
+        "json" => "application/json",
+        "toml" => "application/toml",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,471 @@
+mod cache;
+mod compression;
+mod error;
+mod kind;
+mod listing;
+mod loader;
+mod mime;
+mod pak;
+mod path;
+mod preload;
+pub mod protocol;
+mod source;
+mod verify;
+mod watcher;
+
+pub use cache::{AssetCache, CacheStats};
+pub use compression::AssetLimits;
+pub use error::AssetError;
+pub use kind::AssetKind;
+pub use listing::AssetEntry;
+pub use loader::{AssetLoads, AssetRange, LoadedAsset, TypedAsset};
+pub use mime::mime_for_path;
+pub use pak::{PakInfo, PakRegistry};
+pub use path::{asset_roots, sanitize_relative};
+pub use preload::{PreloadGate, PreloadSummary};
+pub use source::{resolve_source, AssetSource};
+pub use verify::{hash_file, AssetCheck, VerifyReport, VerifyState};
+pub use watcher::AssetWatcher;
+
+use futures::stream::{self, StreamExt};
+use preload::{PreloadFailure, PreloadProgress};
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{Emitter, Manager, State};
+
+/// Loads an audio file off the main thread, serving repeat requests from the
+/// in-memory [`AssetCache`].
+///
+/// Pass a `request_id` to make the load cancellable via [`cancel_asset_load`].
+#[tauri::command]
+pub async fn load_audio_asset(
+    app: tauri::AppHandle,
+    loads: State<'_, AssetLoads>,
+    cache: State<'_, AssetCache>,
+    filename: String,
+    request_id: Option<String>,
+) -> Result<LoadedAsset, AssetError> {
+    let started = Instant::now();
+    let source = resolve_source(&app, AssetKind::Audio, &filename)?;
+    let (bytes, from_cache) = read_through_cache(&loads, &cache, &source, request_id).await?;
+
+    Ok(LoadedAsset {
+        bytes,
+        resolved_path: source.path(),
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        from_cache,
+    })
+}
+
+/// Loads any bundled asset by kind, reporting a MIME type inferred from the
+/// extension so the frontend can build blob URLs directly.
+#[tauri::command]
+pub async fn load_asset(
+    app: tauri::AppHandle,
+    loads: State<'_, AssetLoads>,
+    cache: State<'_, AssetCache>,
+    kind: AssetKind,
+    filename: String,
+    request_id: Option<String>,
+) -> Result<TypedAsset, AssetError> {
+    let source = resolve_source(&app, kind, &filename)?;
+    let (bytes, _) = read_through_cache(&loads, &cache, &source, request_id).await?;
+
+    Ok(TypedAsset {
+        mime_type: source.mime_type(),
+        size: bytes.len() as u64,
+        bytes,
+        resolved_from: source.path(),
+    })
+}
+
+/// Reads only the `[offset, offset + length)` window of a file, e.g. the first
+/// few seconds of a long music track. Defaults to audio when `kind` is omitted.
+#[tauri::command]
+pub async fn load_asset_range(
+    app: tauri::AppHandle,
+    filename: String,
+    offset: u64,
+    length: u64,
+    kind: Option<AssetKind>,
+) -> Result<AssetRange, AssetError> {
+    let source = resolve_source(&app, kind.unwrap_or(AssetKind::Audio), &filename)?;
+    let path = source.path();
+    tauri::async_runtime::spawn_blocking(move || loader::read_range(&source, offset, length))
+        .await
+        .map_err(|e| AssetError::task(path, e))?
+}
+
+/// Lists every audio file under `resources/audio` (dev folder first, then the
+/// bundled copy), optionally narrowed by a glob such as `weapons/*.ogg`.
+#[tauri::command]
+pub async fn list_audio_assets(
+    app: tauri::AppHandle,
+    filter: Option<String>,
+) -> Result<Vec<AssetEntry>, AssetError> {
+    let roots = asset_roots(&app, AssetKind::Audio);
+    tauri::async_runtime::spawn_blocking(move || {
+        listing::list_audio_files(&roots, filter.as_deref())
+    })
+    .await
+    .map_err(|e| AssetError::task("resources/audio", e))?
+}
+
+/// Warms the [`AssetCache`] with a batch of files, reading up to
+/// [`preload::MAX_PARALLEL_READS`] at once and emitting
+/// `asset-preload-progress` as each one finishes.
+///
+/// Missing or unreadable files are reported in the summary instead of failing
+/// the batch. Calling this while another preload is running returns a `busy`
+/// error rather than queueing.
+#[tauri::command]
+pub async fn preload_assets(
+    app: tauri::AppHandle,
+    gate: State<'_, PreloadGate>,
+    loads: State<'_, AssetLoads>,
+    cache: State<'_, AssetCache>,
+    filenames: Vec<String>,
+    kind: Option<AssetKind>,
+) -> Result<PreloadSummary, AssetError> {
+    let _permit = gate.try_begin()?;
+    let kind = kind.unwrap_or(AssetKind::Audio);
+    let total = filenames.len();
+
+    let mut results = stream::iter(filenames)
+        .map(|filename| {
+            let app = &app;
+            let loads = &*loads;
+            let cache = &*cache;
+            async move {
+                let result = match resolve_source(app, kind, &filename) {
+                    Ok(source) => read_through_cache(loads, cache, &source, None).await,
+                    Err(e) => Err(e),
+                };
+                (filename, result.map(|(bytes, _)| bytes.len() as u64))
+            }
+        })
+        .buffer_unordered(preload::MAX_PARALLEL_READS);
+
+    let mut summary = PreloadSummary {
+        total,
+        ..Default::default()
+    };
+    let mut loaded = 0;
+    while let Some((filename, result)) = results.next().await {
+        loaded += 1;
+        match result {
+            Ok(size) => {
+                summary.succeeded += 1;
+                summary.bytes_loaded += size;
+            }
+            Err(error) => summary.failed.push(PreloadFailure {
+                filename: filename.clone(),
+                error,
+            }),
+        }
+
+        let _ = app.emit(
+            preload::PROGRESS_EVENT,
+            PreloadProgress {
+                loaded,
+                total,
+                current_file: filename,
+                bytes_loaded: summary.bytes_loaded,
+            },
+        );
+    }
+
+    log::info!(
+        "Preloaded {}/{} assets ({} bytes)",
+        summary.succeeded,
+        total,
+        summary.bytes_loaded
+    );
+    for failure in &summary.failed {
+        log::warn!(
+            "Preload of {:?} failed: {}",
+            failure.filename,
+            failure.error
+        );
+    }
+    Ok(summary)
+}
+
+/// Resolves and reads an asset through the shared cache, for backend modules
+/// that need the bytes themselves rather than a command response.
+pub async fn read_asset(
+    app: &tauri::AppHandle,
+    kind: AssetKind,
+    filename: &str,
+) -> Result<(AssetSource, Vec<u8>), AssetError> {
+    let source = resolve_source(app, kind, filename)?;
+    let loads = app.state::<AssetLoads>();
+    let cache = app.state::<AssetCache>();
+    let (bytes, _) = read_through_cache(&loads, &cache, &source, None).await?;
+    Ok((source, bytes))
+}
+
+/// Returns the file contents and whether they came from the cache.
+async fn read_through_cache(
+    loads: &AssetLoads,
+    cache: &AssetCache,
+    source: &AssetSource,
+    request_id: Option<String>,
+) -> Result<(Vec<u8>, bool), AssetError> {
+    let path = source.path();
+    if let Some(bytes) = cache.get(&path) {
+        return Ok((bytes.to_vec(), true));
+    }
+
+    let guard = loads.begin(request_id);
+    let cancelled = guard.flag();
+    let read_source = source.clone();
+    let bytes = tauri::async_runtime::spawn_blocking(move || {
+        loader::read_cancellable(&read_source, &cancelled)
+    })
+    .await
+    .map_err(|e| AssetError::task(&path, e))??;
+
+    cache.insert(path, Arc::from(bytes.as_slice()));
+    Ok((bytes, false))
+}
+
+/// Abandons an in-flight [`load_audio_asset`] / [`load_asset`]. Returns `false` if no load
+/// with that id is running.
+#[tauri::command]
+pub fn cancel_asset_load(loads: State<'_, AssetLoads>, request_id: String) -> bool {
+    loads.cancel(&request_id)
+}
+
+#[tauri::command]
+pub fn clear_asset_cache(cache: State<'_, AssetCache>) {
+    cache.clear();
+}
+
+#[tauri::command]
+pub fn get_cache_stats(cache: State<'_, AssetCache>) -> CacheStats {
+    cache.stats()
+}
+
+/// Mounts a `.pak` archive from `resources/paks/` on top of any already
+/// mounted ones. Its entries take priority over loose files.
+#[tauri::command]
+pub fn mount_pak(
+    app: tauri::AppHandle,
+    paks: State<'_, PakRegistry>,
+    path: String,
+) -> Result<PakInfo, AssetError> {
+    let resolved = path::resolve_pak_path(&app, &path)?;
+    paks.mount(resolved)
+}
+
+#[tauri::command]
+pub fn unmount_pak(paks: State<'_, PakRegistry>, name: String) -> bool {
+    paks.unmount(&name)
+}
+
+/// Mounted paks in mount order (last one wins on conflicts).
+#[tauri::command]
+pub fn list_mounted_paks(paks: State<'_, PakRegistry>) -> Vec<PakInfo> {
+    paks.list()
+}
+
+/// Caps how large a single `.zst` asset may decompress to.
+#[tauri::command]
+pub fn set_asset_decompression_limit(limits: State<'_, AssetLimits>, max_bytes: u64) {
+    limits.set_max_decompressed_bytes(max_bytes);
+}
+
+/// Hashes every file listed in `resources/manifest.json` and reports missing,
+/// corrupted and unexpected extra files. Emits `verify-progress` after each
+/// file; stop early with [`cancel_verify`].
+#[tauri::command]
+pub async fn verify_assets(
+    app: tauri::AppHandle,
+    state: State<'_, VerifyState>,
+) -> Result<VerifyReport, AssetError> {
+    let _permit = state.try_begin()?;
+    let root = path::resources_root(&app)
+        .ok_or_else(|| AssetError::NotFound(verify::MANIFEST_NAME.to_string()))?;
+
+    let manifest_root = root.clone();
+    let manifest = Arc::new(
+        tauri::async_runtime::spawn_blocking(move || verify::load_manifest(&manifest_root))
+            .await
+            .map_err(|e| AssetError::task(&root, e))??,
+    );
+
+    let mut report = VerifyReport {
+        root: root.clone(),
+        total: manifest.files.len(),
+        ..Default::default()
+    };
+
+    let names: Vec<String> = manifest.files.keys().cloned().collect();
+    let mut checks = stream::iter(names)
+        .map(|name| {
+            let root = root.clone();
+            let manifest = manifest.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let expected = &manifest.files[&name];
+                verify::check_entry(&root, &name, expected)
+            })
+        })
+        .buffer_unordered(verify::MAX_PARALLEL_HASHES);
+
+    while let Some(check) = checks.next().await {
+        if state.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let check = check.map_err(|e| AssetError::task(&root, e))?;
+        report.checked += 1;
+
+        let current_file = match check {
+            AssetCheck::Ok { path } => {
+                report.ok += 1;
+                path
+            }
+            AssetCheck::Missing { path } => {
+                report.missing.push(path.clone());
+                path
+            }
+            AssetCheck::Corrupted(corrupted) => {
+                let path = corrupted.path.clone();
+                report.corrupted.push(corrupted);
+                path
+            }
+            AssetCheck::NotInManifest { path } => path,
+        };
+
+        let _ = app.emit(
+            verify::PROGRESS_EVENT,
+            verify::VerifyProgress {
+                checked: report.checked,
+                total: report.total,
+                current_file,
+            },
+        );
+    }
+
+    drop(checks);
+
+    if !report.cancelled {
+        let extra_root = root.clone();
+        report.extra = tauri::async_runtime::spawn_blocking(move || {
+            verify::find_extra(&extra_root, &manifest)
+        })
+        .await
+        .map_err(|e| AssetError::task(&root, e))?;
+    }
+
+    report.missing.sort();
+    report.corrupted.sort_by(|a, b| a.path.cmp(&b.path));
+    state.record(&report);
+    Ok(report)
+}
+
+/// Stops a running [`verify_assets`]; returns `false` if none is running.
+#[tauri::command]
+pub fn cancel_verify(state: State<'_, VerifyState>) -> bool {
+    state.cancel()
+}
+
+/// Spot-checks one file (path relative to `resources/`, e.g.
+/// `audio/combat.mp3`) against the manifest, e.g. after a failed load.
+#[tauri::command]
+pub async fn verify_asset(
+    app: tauri::AppHandle,
+    filename: String,
+) -> Result<AssetCheck, AssetError> {
+    let root = path::resources_root(&app)
+        .ok_or_else(|| AssetError::NotFound(verify::MANIFEST_NAME.to_string()))?;
+    let task_root = root.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let relative = path::sanitize_relative(&filename)?;
+        let name = path::slash_path(&relative)
+            .ok_or_else(|| AssetError::invalid(&filename, "path is not valid UTF-8"))?;
+        let manifest = verify::load_manifest(&task_root)?;
+        Ok(match manifest.files.get(&name) {
+            Some(expected) => verify::check_entry(&task_root, &name, expected),
+            None => AssetCheck::NotInManifest { path: name },
+        })
+    })
+    .await
+    .map_err(|e| AssetError::task(root, e))?
+}
+
+/// Toggles hot reload of `resources/audio` and `resources/data/weapons`.
+/// Only has an effect in debug builds; returns whether watching is now
+/// enabled.
+#[tauri::command]
+pub fn set_asset_watch_enabled(
+    app: tauri::AppHandle,
+    watcher: State<'_, AssetWatcher>,
+    enabled: bool,
+) -> bool {
+    watcher.set_enabled(&app, enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(i: usize) -> Vec<u8> {
+        (0..64 * 1024).map(|b| (b * (i + 1)) as u8).collect()
+    }
+
+    #[test]
+    fn concurrent_loads_do_not_wait_for_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        // Sparse, so it costs nothing on disk but takes a while to read.
+        let big = dir.path().join("ambience.ogg");
+        std::fs::File::create(&big)
+            .unwrap()
+            .set_len(1 << 30)
+            .unwrap();
+        let big = AssetSource::File(big);
+        let small: Vec<AssetSource> = (0..4)
+            .map(|i| {
+                let path = dir.path().join(format!("shot-{}.wav", i));
+                std::fs::write(&path, contents(i)).unwrap();
+                AssetSource::File(path)
+            })
+            .collect();
+        let loads = AssetLoads::default();
+        let cache = AssetCache::default();
+
+        tauri::async_runtime::block_on(async {
+            let mut big_load =
+                std::pin::pin!(read_through_cache(&loads, &cache, &big, Some("big".into())));
+            assert!(futures::poll!(big_load.as_mut()).is_pending());
+
+            let results = futures::future::join_all(
+                small
+                    .iter()
+                    .map(|source| read_through_cache(&loads, &cache, source, None)),
+            )
+            .await;
+            for (i, result) in results.into_iter().enumerate() {
+                let (bytes, from_cache) = result.unwrap();
+                assert_eq!(bytes, contents(i));
+                assert!(!from_cache);
+            }
+
+            // The small files finished while the big read was still going.
+            assert!(loads.cancel("big"));
+            assert!(matches!(big_load.await, Err(AssetError::Cancelled(_))));
+        });
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.misses), (4, 5));
+        tauri::async_runtime::block_on(async {
+            let (bytes, from_cache) = read_through_cache(&loads, &cache, &small[2], None)
+                .await
+                .unwrap();
+            assert_eq!(bytes, contents(2));
+            assert!(from_cache);
+        });
+    }
+}
@@ -0,0 +1,313 @@
+//! `.pak` archives: many assets packed into one file, read by seeking to each
+//! blob instead of loading the whole archive.
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! magic    b"FPAK"
+//! u32      format version (1)
+//! u32      entry count
+//! entries  u16 name length, UTF-8 name ("audio/weapons/ak.ogg"),
+//!          u64 absolute blob offset, u64 blob size
+//! blobs    concatenated file contents
+//! ```
+
+use super::path::{sanitize_relative, slash_path};
+use super::source::PakBlob;
+use super::AssetError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const PAK_MAGIC: &[u8; 4] = b"FPAK";
+pub const PAK_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy)]
+struct PakEntry {
+    offset: u64,
+    size: u64,
+}
+
+struct MountedPak {
+    name: String,
+    path: PathBuf,
+    entries: HashMap<String, PakEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PakInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub entry_count: usize,
+    pub content_bytes: u64,
+}
+
+/// Mounted archives in mount order; later mounts override earlier ones.
+#[derive(Default)]
+pub struct PakRegistry {
+    mounts: RwLock<Vec<MountedPak>>,
+}
+
+impl PakRegistry {
+    /// Reads the archive index and mounts it on top of the others. Mounting a
+    /// pak with the same name again replaces the old mount.
+    pub fn mount(&self, path: PathBuf) -> Result<PakInfo, AssetError> {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| AssetError::invalid_pak(&path, "file has no name"))?
+            .to_string();
+        let entries = read_index(&path)?;
+        let pak = MountedPak {
+            name,
+            path,
+            entries,
+        };
+        let info = pak.info();
+
+        let mut mounts = self.mounts.write().unwrap();
+        mounts.retain(|m| m.name != pak.name);
+        mounts.push(pak);
+        Ok(info)
+    }
+
+    pub fn unmount(&self, name: &str) -> bool {
+        let mut mounts = self.mounts.write().unwrap();
+        let before = mounts.len();
+        mounts.retain(|m| m.name != name);
+        mounts.len() != before
+    }
+
+    pub fn list(&self) -> Vec<PakInfo> {
+        self.mounts
+            .read()
+            .unwrap()
+            .iter()
+            .map(MountedPak::info)
+            .collect()
+    }
+
+    /// Finds `logical` (e.g. `audio/combat.mp3`) in the most recently mounted
+    /// pak that contains it.
+    pub fn lookup(&self, logical: &str) -> Option<PakBlob> {
+        let mounts = self.mounts.read().unwrap();
+        mounts.iter().rev().find_map(|pak| {
+            pak.entries.get(logical).map(|entry| PakBlob {
+                archive: pak.path.clone(),
+                entry: logical.to_string(),
+                offset: entry.offset,
+                size: entry.size,
+            })
+        })
+    }
+}
+
+impl MountedPak {
+    fn info(&self) -> PakInfo {
+        PakInfo {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            entry_count: self.entries.len(),
+            content_bytes: self.entries.values().map(|e| e.size).sum(),
+        }
+    }
+}
+
+fn read_index(path: &Path) -> Result<HashMap<String, PakEntry>, AssetError> {
+    let file = File::open(path).map_err(|e| AssetError::io(path, e))?;
+    let file_len = file.metadata().map_err(|e| AssetError::io(path, e))?.len();
+    let mut reader = BufReader::new(file);
+    let truncated = |_| AssetError::invalid_pak(path, "index is truncated");
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).map_err(truncated)?;
+    if &magic != PAK_MAGIC {
+        return Err(AssetError::invalid_pak(path, "not a pak file"));
+    }
+    let version = read_u32(&mut reader).map_err(truncated)?;
+    if version != PAK_VERSION {
+        return Err(AssetError::invalid_pak(
+            path,
+            format!("unsupported version {}", version),
+        ));
+    }
+
+    let count = read_u32(&mut reader).map_err(truncated)?;
+    let mut entries = HashMap::with_capacity(count.min(4096) as usize);
+    for _ in 0..count {
+        let name_len = read_u16(&mut reader).map_err(truncated)?;
+        let mut name = vec![0u8; name_len as usize];
+        reader.read_exact(&mut name).map_err(truncated)?;
+        let offset = read_u64(&mut reader).map_err(truncated)?;
+        let size = read_u64(&mut reader).map_err(truncated)?;
+
+        let name = String::from_utf8(name)
+            .map_err(|_| AssetError::invalid_pak(path, "entry name is not UTF-8"))?;
+        let logical = normalize_entry_name(&name).ok_or_else(|| {
+            AssetError::invalid_pak(
+                path,
+                format!("entry {:?} is not a safe relative path", name),
+            )
+        })?;
+        if offset.checked_add(size).is_none_or(|end| end > file_len) {
+            return Err(AssetError::invalid_pak(
+                path,
+                format!("entry {:?} points past the end of the file", name),
+            ));
+        }
+
+        entries.insert(logical, PakEntry { offset, size });
+    }
+
+    Ok(entries)
+}
+
+/// Entry names go through the same sanitization as loose asset filenames.
+fn normalize_entry_name(name: &str) -> Option<String> {
+    slash_path(&sanitize_relative(name).ok()?)
+}
+
+fn read_u16(reader: &mut impl Read) -> std::io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Packs `entries` (logical name, contents) into an archive in the layout
+/// above, for tests.
+#[cfg(test)]
+pub(crate) fn build_pak(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let index_len: usize = entries.iter().map(|(name, _)| 2 + name.len() + 16).sum();
+    let mut offset = (PAK_MAGIC.len() + 8 + index_len) as u64;
+    let mut out = Vec::new();
+    out.extend_from_slice(PAK_MAGIC);
+    out.extend_from_slice(&PAK_VERSION.to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for (name, data) in entries {
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        offset += data.len() as u64;
+    }
+    for (_, data) in entries {
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::loader::read_cancellable;
+    use crate::assets::AssetSource;
+    use std::sync::atomic::AtomicBool;
+
+    fn write_pak(dir: &Path, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn read(blob: PakBlob) -> Vec<u8> {
+        read_cancellable(&AssetSource::Pak(blob), &AtomicBool::new(false)).unwrap()
+    }
+
+    fn mount_error(bytes: &[u8]) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_pak(dir.path(), "bad.pak", bytes);
+        match PakRegistry::default().mount(path) {
+            Err(AssetError::InvalidPak { reason, .. }) => reason,
+            other => panic!("expected an invalid pak, got {:?}", other.map(|i| i.name)),
+        }
+    }
+
+    #[test]
+    fn packs_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let ak: Vec<u8> = (0..5_000u32).map(|i| i as u8).collect();
+        let bytes = build_pak(&[
+            ("audio/weapons/ak.ogg", &ak),
+            (r"audio\ui\click.wav", b"click"),
+            ("audio/empty.ogg", b""),
+        ]);
+        let path = write_pak(dir.path(), "base.pak", &bytes);
+
+        let paks = PakRegistry::default();
+        let info = paks.mount(path).unwrap();
+        assert_eq!(info.name, "base");
+        assert_eq!(info.entry_count, 3);
+        assert_eq!(info.content_bytes, 5_005);
+
+        assert_eq!(read(paks.lookup("audio/weapons/ak.ogg").unwrap()), ak);
+        assert_eq!(read(paks.lookup("audio/ui/click.wav").unwrap()), b"click");
+        assert!(read(paks.lookup("audio/empty.ogg").unwrap()).is_empty());
+        assert!(paks.lookup("audio/missing.ogg").is_none());
+    }
+
+    #[test]
+    fn later_mounts_override_earlier_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write_pak(
+            dir.path(),
+            "base.pak",
+            &build_pak(&[("audio/a.ogg", b"base"), ("audio/b.ogg", b"base")]),
+        );
+        let patch = write_pak(
+            dir.path(),
+            "patch.pak",
+            &build_pak(&[("audio/a.ogg", b"patch")]),
+        );
+        let paks = PakRegistry::default();
+        paks.mount(base).unwrap();
+        paks.mount(patch).unwrap();
+
+        assert_eq!(read(paks.lookup("audio/a.ogg").unwrap()), b"patch");
+        assert_eq!(read(paks.lookup("audio/b.ogg").unwrap()), b"base");
+        assert!(paks.unmount("patch"));
+        assert_eq!(read(paks.lookup("audio/a.ogg").unwrap()), b"base");
+        assert!(!paks.unmount("patch"));
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_versions() {
+        let mut bytes = build_pak(&[("audio/a.ogg", b"a")]);
+        bytes[..4].copy_from_slice(b"PKZ\x03");
+        assert_eq!(mount_error(&bytes), "not a pak file");
+
+        let mut bytes = build_pak(&[("audio/a.ogg", b"a")]);
+        bytes[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(mount_error(&bytes), "unsupported version 2");
+    }
+
+    #[test]
+    fn rejects_truncated_archives() {
+        let bytes = build_pak(&[("audio/a.ogg", b"hello"), ("audio/b.ogg", b"world")]);
+        assert_eq!(mount_error(&bytes[..2]), "index is truncated");
+        assert_eq!(mount_error(&bytes[..20]), "index is truncated");
+        // The index is whole but the last blob is cut short.
+        assert!(mount_error(&bytes[..bytes.len() - 1]).contains("past the end"));
+    }
+
+    #[test]
+    fn rejects_unsafe_entry_names() {
+        let bytes = build_pak(&[("../../secret.txt", b"x")]);
+        assert!(mount_error(&bytes).contains("not a safe relative path"));
+    }
+}
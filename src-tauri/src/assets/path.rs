@@ -0,0 +1,323 @@
+use super::{AssetError, AssetKind};
+use std::path::{Component, Path, PathBuf};
+use tauri::path::BaseDirectory;
+use tauri::{Manager, Runtime};
+
+/// Turns a frontend-supplied filename into a relative path that is safe to
+/// join onto a resource root.
+///
+/// Backslashes are treated as separators on every platform so that
+/// `..\..\secret` is rejected the same way on Linux as on Windows.
+pub fn sanitize_relative(filename: &str) -> Result<PathBuf, AssetError> {
+    if filename.trim().is_empty() {
+        return Err(AssetError::invalid(filename, "empty filename"));
+    }
+    if filename.contains('\0') {
+        return Err(AssetError::invalid(filename, "contains NUL byte"));
+    }
+
+    let normalized = filename.replace('\\', "/");
+    if normalized.starts_with('/') {
+        return Err(AssetError::invalid(
+            filename,
+            "absolute paths are not allowed",
+        ));
+    }
+    // Drive prefixes like `C:` are only parsed as such on Windows, so catch them by hand.
+    if normalized.as_bytes().get(1) == Some(&b':') {
+        return Err(AssetError::invalid(
+            filename,
+            "absolute paths are not allowed",
+        ));
+    }
+
+    let mut relative = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                return Err(AssetError::invalid(
+                    filename,
+                    "parent directory components are not allowed",
+                ));
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(AssetError::invalid(
+                    filename,
+                    "absolute paths are not allowed",
+                ));
+            }
+        }
+    }
+
+    if relative.as_os_str().is_empty() {
+        return Err(AssetError::invalid(filename, "empty filename"));
+    }
+    Ok(relative)
+}
+
+/// Renders a relative path with `/` separators on every platform.
+pub fn slash_path(relative: &Path) -> Option<String> {
+    let parts: Option<Vec<&str>> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect();
+    Some(parts?.join("/"))
+}
+
+/// Platform-independent name of an asset, e.g. `audio/weapons/ak.ogg`. This is
+/// what pak archive entries are keyed by.
+pub fn logical_name(kind: AssetKind, filename: &str) -> Result<String, AssetError> {
+    let relative = sanitize_relative(filename)?;
+    let relative = slash_path(&relative)
+        .ok_or_else(|| AssetError::invalid(filename, "path is not valid UTF-8"))?;
+    Ok(format!("{}/{}", kind.dir(), relative))
+}
+
+/// Joins `relative` onto `root` and makes sure the result, after resolving
+/// symlinks, still lives under `root`.
+///
+/// Returns `Ok(None)` when the file simply isn't there so callers can move on
+/// to the next root.
+pub fn resolve_in_root(
+    root: &Path,
+    relative: &Path,
+    filename: &str,
+) -> Result<Option<PathBuf>, AssetError> {
+    let candidate = root.join(relative);
+    if !candidate.exists() {
+        return Ok(None);
+    }
+
+    let canonical_root = root.canonicalize().map_err(|e| AssetError::io(root, e))?;
+    let canonical = candidate
+        .canonicalize()
+        .map_err(|e| AssetError::io(&candidate, e))?;
+    if !canonical.starts_with(&canonical_root) {
+        return Err(AssetError::invalid(
+            filename,
+            "resolves outside of the resources directory",
+        ));
+    }
+    if !canonical.is_file() {
+        return Ok(None);
+    }
+    Ok(Some(canonical))
+}
+
+/// Directories holding assets of `kind`, in priority order.
+pub fn asset_roots<R: Runtime>(app: &tauri::AppHandle<R>, kind: AssetKind) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    let dir = kind.dir();
+
+    // 1. Dev Mode Fallback: Check directly in the project folder
+    #[cfg(debug_assertions)]
+    {
+        roots.push(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("resources")
+                .join(dir),
+        );
+    }
+
+    // 2. Production / Standard Resource Mode
+    for dir in [format!("resources/{}", dir), dir.to_string()] {
+        if let Ok(path) = app.path().resolve(&dir, BaseDirectory::Resource) {
+            roots.push(path);
+        }
+    }
+
+    roots
+}
+
+/// Like [`asset_roots`], plus the legacy fallbacks used when resolving a
+/// single file (older builds also looked in the resource root itself).
+fn lookup_roots<R: Runtime>(app: &tauri::AppHandle<R>, kind: AssetKind) -> Vec<PathBuf> {
+    let mut roots = asset_roots(app, kind);
+    if kind == AssetKind::Audio {
+        for dir in ["resources", ""] {
+            if let Ok(path) = app.path().resolve(dir, BaseDirectory::Resource) {
+                roots.push(path);
+            }
+        }
+    }
+    roots
+}
+
+/// Resolves `filename` against each root in order, returning the canonical path
+/// of the first match.
+pub fn resolve_in_roots(roots: &[PathBuf], filename: &str) -> Result<PathBuf, AssetError> {
+    let relative = sanitize_relative(filename)?;
+
+    for root in roots {
+        if !root.is_dir() {
+            continue;
+        }
+        if let Some(path) = resolve_in_root(root, &relative, filename)? {
+            return Ok(path);
+        }
+    }
+
+    Err(AssetError::NotFound(filename.to_string()))
+}
+
+pub fn resolve_asset_path<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    kind: AssetKind,
+    filename: &str,
+) -> Result<PathBuf, AssetError> {
+    resolve_in_roots(&lookup_roots(app, kind), filename)
+}
+
+/// Directories that `mount_pak` looks in for archives.
+fn pak_roots<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    #[cfg(debug_assertions)]
+    {
+        roots.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/paks"));
+    }
+
+    for dir in ["resources/paks", "paks"] {
+        if let Ok(path) = app.path().resolve(dir, BaseDirectory::Resource) {
+            roots.push(path);
+        }
+    }
+
+    roots
+}
+
+pub fn resolve_pak_path<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    filename: &str,
+) -> Result<PathBuf, AssetError> {
+    resolve_in_roots(&pak_roots(app), filename)
+}
+
+/// The `resources/` directory holding `manifest.json`: the project folder in
+/// dev builds, the bundled copy otherwise.
+pub fn resources_root<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+
+    #[cfg(debug_assertions)]
+    {
+        candidates.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources"));
+    }
+
+    if let Ok(path) = app.path().resolve("resources", BaseDirectory::Resource) {
+        candidates.push(path);
+    }
+
+    candidates
+        .into_iter()
+        .find(|root| root.join(super::verify::MANIFEST_NAME).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(filename: &str) -> bool {
+        matches!(
+            sanitize_relative(filename),
+            Err(AssetError::InvalidPath { .. })
+        )
+    }
+
+    #[test]
+    fn normalizes_plain_and_backslash_paths() {
+        assert_eq!(
+            sanitize_relative("weapons/ak.ogg").unwrap(),
+            PathBuf::from("weapons").join("ak.ogg")
+        );
+        assert_eq!(
+            sanitize_relative(r"weapons\ak.ogg").unwrap(),
+            PathBuf::from("weapons").join("ak.ogg")
+        );
+        assert_eq!(
+            sanitize_relative("./weapons//./ak.ogg").unwrap(),
+            PathBuf::from("weapons").join("ak.ogg")
+        );
+    }
+
+    #[test]
+    fn rejects_parent_components() {
+        assert!(rejected("../secret.txt"));
+        assert!(rejected("weapons/../../secret.txt"));
+        assert!(rejected(r"..\..\secret.txt"));
+        assert!(rejected(r"weapons\..\ak.ogg"));
+    }
+
+    #[test]
+    fn rejects_absolute_and_empty_paths() {
+        assert!(rejected("/etc/passwd"));
+        assert!(rejected(r"\Windows\win.ini"));
+        assert!(rejected(r"C:\Windows\win.ini"));
+        assert!(rejected("C:/Windows/win.ini"));
+        assert!(rejected(""));
+        assert!(rejected("   "));
+        assert!(rejected("./."));
+        assert!(rejected("ak\0.ogg"));
+    }
+
+    #[test]
+    fn logical_names_use_forward_slashes() {
+        assert_eq!(
+            logical_name(AssetKind::Audio, r"weapons\ak.ogg").unwrap(),
+            "audio/weapons/ak.ogg"
+        );
+    }
+
+    #[test]
+    fn resolves_only_inside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(root.join("weapons")).unwrap();
+        std::fs::write(root.join("weapons/ak.ogg"), b"ogg").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), b"secret").unwrap();
+
+        let found = resolve_in_roots(std::slice::from_ref(&root), r"weapons\ak.ogg").unwrap();
+        assert_eq!(found, root.join("weapons/ak.ogg").canonicalize().unwrap());
+
+        assert!(matches!(
+            resolve_in_roots(std::slice::from_ref(&root), "missing.ogg"),
+            Err(AssetError::NotFound(_))
+        ));
+        // A directory isn't an asset.
+        assert!(matches!(
+            resolve_in_roots(std::slice::from_ref(&root), "weapons"),
+            Err(AssetError::NotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_out_of_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.path().join("secret.txt"), b"secret").unwrap();
+        std::fs::write(root.join("inside.ogg"), b"ogg").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret.txt"), root.join("escape.ogg")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), root.join("up")).unwrap();
+        std::os::unix::fs::symlink(root.join("inside.ogg"), root.join("alias.ogg")).unwrap();
+
+        let roots = std::slice::from_ref(&root);
+        for filename in ["escape.ogg", "up/secret.txt", r"up\secret.txt"] {
+            assert!(
+                matches!(
+                    resolve_in_roots(roots, filename),
+                    Err(AssetError::InvalidPath { .. })
+                ),
+                "{} should be rejected",
+                filename
+            );
+        }
+        // Links that stay inside the root are fine.
+        assert_eq!(
+            resolve_in_roots(roots, "alias.ogg").unwrap(),
+            root.join("inside.ogg").canonicalize().unwrap()
+        );
+    }
+}
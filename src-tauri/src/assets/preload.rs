@@ -0,0 +1,61 @@
+use super::AssetError;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const PROGRESS_EVENT: &str = "asset-preload-progress";
+
+/// Number of files read at the same time during a preload.
+pub const MAX_PARALLEL_READS: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreloadProgress {
+    pub loaded: usize,
+    pub total: usize,
+    pub current_file: String,
+    pub bytes_loaded: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreloadFailure {
+    pub filename: String,
+    pub error: AssetError,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreloadSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub bytes_loaded: u64,
+    pub failed: Vec<PreloadFailure>,
+}
+
+/// Only one preload batch runs at a time; a second call gets
+/// [`AssetError::Busy`] instead of queueing behind the first.
+#[derive(Default)]
+pub struct PreloadGate {
+    running: AtomicBool,
+}
+
+impl PreloadGate {
+    pub fn try_begin(&self) -> Result<PreloadPermit<'_>, AssetError> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(AssetError::Busy(
+                "a preload is already in progress".to_string(),
+            ));
+        }
+        Ok(PreloadPermit { gate: self })
+    }
+}
+
+pub struct PreloadPermit<'a> {
+    gate: &'a PreloadGate,
+}
+
+impl Drop for PreloadPermit<'_> {
+    fn drop(&mut self) {
+        self.gate.running.store(false, Ordering::Release);
+    }
+}
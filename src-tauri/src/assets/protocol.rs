@@ -0,0 +1,384 @@
+//! `gameasset://` URI scheme so the webview can stream assets directly
+//! (`<audio src="gameasset://audio/combat.mp3">`, `fetch()`), instead of
+//! pulling whole files through the invoke bridge as JSON number arrays.
+//!
+//! On Windows and Android the same handler is reachable as
+//! `http://gameasset.localhost/audio/<filename>`. Every [`AssetKind`] is
+//! served, e.g. `gameasset://textures/sky.ktx2`.
+
+use super::loader::{read_cancellable, read_range, slice_range};
+use super::{resolve_source, AssetCache, AssetError, AssetKind, AssetSource};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tauri::http::{header, HeaderValue, Request, Response, StatusCode};
+use tauri::{Manager, Runtime, UriSchemeContext, UriSchemeResponder};
+
+pub const SCHEME: &str = "gameasset";
+
+/// Upper bound for a single response to a `Range` request, however wide.
+/// Clients take the shorter 206 and ask for the rest; media elements keep
+/// asking for the next window as they play anyway.
+const MAX_RANGE_BYTES: u64 = 4 * 1024 * 1024;
+/// Largest asset sent whole to a request without a `Range` header. Bigger
+/// ones have to be fetched in ranges, the way media elements already do.
+const MAX_UNRANGED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Answers on the blocking pool, so resolving, reading and decompressing
+/// never hold up the webview thread.
+pub fn handle<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || responder.respond(respond(&app, &request)));
+}
+
+fn respond<R: Runtime>(app: &tauri::AppHandle<R>, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let mut response = match split_request_path(request) {
+        Some((kind, filename)) => serve_request(app, request, &kind, &filename),
+        None => status_response(StatusCode::NOT_FOUND, "Unknown asset URL"),
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+    if let Some(origin) = allowed_origin(request) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+    response
+}
+
+fn serve_request<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: &Request<Vec<u8>>,
+    kind: &str,
+    filename: &str,
+) -> Response<Vec<u8>> {
+    let resolved = match AssetKind::from_dir(kind) {
+        Some(kind) => resolve_source(app, kind, filename),
+        None => Err(AssetError::NotFound(format!("{}/{}", kind, filename))),
+    };
+    let source = match resolved {
+        Ok(source) => source,
+        Err(e) => return error_response(&e),
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+    serve(&app.state::<AssetCache>(), &source, range).unwrap_or_else(|e| error_response(&e))
+}
+
+/// Only pages of this app may read responses with `fetch()`. Plain media
+/// elements don't need CORS at all. The page origin is `tauri://localhost`
+/// or `http(s)://tauri.localhost` depending on the platform, plus the dev
+/// server in debug builds.
+fn allowed_origin(request: &Request<Vec<u8>>) -> Option<HeaderValue> {
+    let origin = request.headers().get(header::ORIGIN)?;
+    let value = origin.to_str().ok()?;
+    let app_origin = matches!(
+        value,
+        "tauri://localhost" | "http://tauri.localhost" | "https://tauri.localhost"
+    );
+    let dev_origin = cfg!(debug_assertions)
+        && value
+            .strip_prefix("http://localhost:")
+            .is_some_and(|port| port.parse::<u16>().is_ok());
+    (app_origin || dev_origin).then(|| origin.clone())
+}
+
+/// Extracts `(kind, filename)` from either `gameasset://audio/a/b.ogg` or
+/// `http://gameasset.localhost/audio/a/b.ogg`.
+fn split_request_path(request: &Request<Vec<u8>>) -> Option<(String, String)> {
+    let uri = request.uri();
+    let mut full = String::new();
+    if let Some(host) = uri.host() {
+        if host != "localhost" && !host.ends_with(".localhost") {
+            full.push_str(host);
+        }
+    }
+    full.push_str(uri.path());
+
+    let decoded = percent_encoding::percent_decode_str(full.trim_start_matches('/'))
+        .decode_utf8()
+        .ok()?;
+    let (kind, filename) = decoded.split_once('/')?;
+    if filename.is_empty() {
+        return None;
+    }
+    Some((kind.to_string(), filename.to_string()))
+}
+
+fn serve(
+    cache: &AssetCache,
+    source: &AssetSource,
+    range: Option<&str>,
+) -> Result<Response<Vec<u8>>, AssetError> {
+    let mime = source.mime_type();
+
+    // Compressed assets can't be seeked into, so they are inflated once and
+    // every request after that is served from the cached bytes.
+    let inflated = if source.is_compressed() {
+        Some(cached_bytes(cache, source)?)
+    } else {
+        None
+    };
+    let total = match &inflated {
+        Some(bytes) => bytes.len() as u64,
+        None => source.open_at(0)?.1,
+    };
+
+    let Some(range) = range else {
+        if total > MAX_UNRANGED_BYTES {
+            return Ok(status_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                &format!(
+                    "{:?} is {} bytes; request it with a Range header",
+                    source.path(),
+                    total
+                ),
+            ));
+        }
+        let body = match inflated {
+            Some(bytes) => bytes.to_vec(),
+            None => cached_bytes(cache, source)?.to_vec(),
+        };
+        return Ok(base_response(StatusCode::OK, mime)
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(body)
+            .unwrap());
+    };
+
+    let Some((start, end)) = parse_range(range, total) else {
+        return Ok(base_response(StatusCode::RANGE_NOT_SATISFIABLE, mime)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", total))
+            .body(Vec::new())
+            .unwrap());
+    };
+
+    let window = match &inflated {
+        Some(bytes) => slice_range(bytes, start, end - start + 1),
+        None => read_range(source, start, end - start + 1)?,
+    };
+    let end = (start + window.bytes.len() as u64).saturating_sub(1);
+
+    Ok(base_response(StatusCode::PARTIAL_CONTENT, mime)
+        .header(header::CONTENT_LENGTH, window.bytes.len())
+        .header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, window.total_size),
+        )
+        .body(window.bytes)
+        .unwrap())
+}
+
+/// The whole asset, from the shared [`AssetCache`] when it's there.
+fn cached_bytes(cache: &AssetCache, source: &AssetSource) -> Result<Arc<[u8]>, AssetError> {
+    let path = source.path();
+    if let Some(bytes) = cache.get(&path) {
+        return Ok(bytes);
+    }
+    let bytes: Arc<[u8]> = read_cancellable(source, &AtomicBool::new(false))?.into();
+    cache.insert(path, bytes.clone());
+    Ok(bytes)
+}
+
+/// Parses a single-range `bytes=` header into an inclusive `(start, end)` pair.
+/// Only the first range of a multi-range request is honoured, and none is
+/// longer than `MAX_RANGE_BYTES`.
+fn parse_range(header: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    let first = spec.split(',').next()?.trim();
+    let (start, end) = first.split_once('-')?;
+
+    if total == 0 {
+        return None;
+    }
+    let last = total - 1;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return None;
+            }
+            (total.saturating_sub(suffix.min(MAX_RANGE_BYTES)), last)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            (start, start.saturating_add(MAX_RANGE_BYTES - 1).min(last))
+        }
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end = end.parse::<u64>().ok()?;
+            (
+                start,
+                end.min(start.saturating_add(MAX_RANGE_BYTES - 1)).min(last),
+            )
+        }
+    };
+
+    if start > end || start > last {
+        return None;
+    }
+    Some((start, end))
+}
+
+fn base_response(status: StatusCode, mime: &str) -> tauri::http::response::Builder {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::ACCEPT_RANGES, "bytes")
+}
+
+fn error_response(error: &AssetError) -> Response<Vec<u8>> {
+    let status = match error {
+        AssetError::InvalidPath { .. } => StatusCode::FORBIDDEN,
+        AssetError::NotFound(_) => StatusCode::NOT_FOUND,
+        AssetError::Busy(_) => StatusCode::SERVICE_UNAVAILABLE,
+        AssetError::Io { .. }
+        | AssetError::InvalidPak { .. }
+        | AssetError::Decompression { .. }
+        | AssetError::Cancelled(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    status_response(status, &error.to_string())
+}
+
+fn status_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    base_response(status, "text/plain")
+        .body(message.as_bytes().to_vec())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn zstd_source(dir: &Path, bytes: &[u8]) -> AssetSource {
+        let path = dir.join("music.ogg.zst");
+        std::fs::write(&path, zstd::encode_all(bytes, 3).unwrap()).unwrap();
+        AssetSource::Zstd {
+            inner: Box::new(AssetSource::File(path)),
+            max_bytes: 1 << 20,
+        }
+    }
+
+    fn content_range(response: &Response<Vec<u8>>) -> &str {
+        response.headers()[header::CONTENT_RANGE].to_str().unwrap()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=900-2000", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=10-", 1000), Some((10, 999)));
+        assert_eq!(
+            parse_range("bytes=0-", 64 * 1024 * 1024),
+            Some((0, MAX_RANGE_BYTES - 1))
+        );
+        // Explicit ranges and suffixes are capped like open ones.
+        let big = 64 * 1024 * 1024;
+        assert_eq!(
+            parse_range("bytes=0-999999999", big),
+            Some((0, MAX_RANGE_BYTES - 1))
+        );
+        assert_eq!(
+            parse_range("bytes=100-", big),
+            Some((100, 100 + MAX_RANGE_BYTES - 1))
+        );
+        assert_eq!(
+            parse_range(&format!("bytes=-{}", big), big),
+            Some((big - MAX_RANGE_BYTES, big - 1))
+        );
+        assert_eq!(parse_range("bytes=-100", big), Some((big - 100, big - 1)));
+        assert_eq!(parse_range("bytes=0-9, 20-29", 1000), Some((0, 9)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=50-10", 1000), None);
+        assert_eq!(parse_range("bytes=-0", 1000), None);
+        assert_eq!(parse_range("bytes=0-", 0), None);
+        assert_eq!(parse_range("items=0-9", 1000), None);
+    }
+
+    #[test]
+    fn serves_ranges_of_plain_files_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.wav");
+        let data: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+        std::fs::write(&path, &data).unwrap();
+        let cache = AssetCache::default();
+        let source = AssetSource::File(path);
+
+        let response = serve(&cache, &source, Some("bytes=9990-")).unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range(&response), "bytes 9990-9999/10000");
+        assert_eq!(response.body(), &data[9990..]);
+        assert_eq!(cache.stats().entries, 0);
+
+        let response = serve(&cache, &source, None).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), &data);
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    #[test]
+    fn inflates_compressed_assets_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i % 251) as u8).collect();
+        let source = zstd_source(dir.path(), &data);
+        let cache = AssetCache::default();
+
+        for (range, start, end) in [("bytes=0-99", 0, 99), ("bytes=-10", 49_990, 49_999)] {
+            let response = serve(&cache, &source, Some(range)).unwrap();
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(
+                content_range(&response),
+                format!("bytes {}-{}/50000", start, end)
+            );
+            assert_eq!(response.body(), &data[start..=end]);
+        }
+        let stats = cache.stats();
+        assert_eq!((stats.misses, stats.hits, stats.entries), (1, 1, 1));
+
+        // Unsatisfiable ranges report the inflated size.
+        let response = serve(&cache, &source, Some("bytes=60000-")).unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(content_range(&response), "bytes */50000");
+    }
+
+    #[test]
+    fn refuses_large_unranged_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ambience.ogg");
+        let file = std::fs::File::create(&path).unwrap();
+        file.set_len(MAX_UNRANGED_BYTES + 1).unwrap();
+        let cache = AssetCache::default();
+        let source = AssetSource::File(path);
+
+        let response = serve(&cache, &source, None).unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(cache.stats().entries, 0);
+        let response = serve(&cache, &source, Some("bytes=0-")).unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body().len() as u64, MAX_RANGE_BYTES);
+    }
+
+    #[test]
+    fn allows_only_app_origins() {
+        let request = |origin: &str| {
+            Request::builder()
+                .uri("gameasset://audio/a.ogg")
+                .header(header::ORIGIN, origin)
+                .body(Vec::new())
+                .unwrap()
+        };
+        for origin in ["tauri://localhost", "http://tauri.localhost"] {
+            assert_eq!(allowed_origin(&request(origin)).unwrap(), origin);
+        }
+        for origin in ["https://example.com", "http://tauri.localhost.example.com"] {
+            assert!(allowed_origin(&request(origin)).is_none());
+        }
+    }
+}
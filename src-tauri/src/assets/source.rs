@@ -0,0 +1,130 @@
+use super::compression::{AssetLimits, DEFAULT_MAX_DECOMPRESSED_BYTES, ZSTD_SUFFIX};
+use super::path::{logical_name, resolve_asset_path, resolve_in_root, sanitize_relative};
+use super::{mime_for_path, AssetError, AssetKind, PakRegistry};
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::{Manager, Runtime};
+
+/// Where an asset's bytes actually live.
+#[derive(Debug, Clone)]
+pub enum AssetSource {
+    File(PathBuf),
+    Pak(PakBlob),
+    /// A `<name>.zst` file (loose or in a pak), decompressed on read.
+    Zstd {
+        inner: Box<AssetSource>,
+        max_bytes: u64,
+    },
+}
+
+/// A single entry inside a mounted `.pak` archive.
+#[derive(Debug, Clone)]
+pub struct PakBlob {
+    pub archive: PathBuf,
+    pub entry: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl AssetSource {
+    /// Path reported to the frontend and used as the cache key. For pak
+    /// entries this is `<archive>/<entry>`, which never exists on disk; for
+    /// compressed assets the `.zst` suffix is dropped so the key is the
+    /// logical filename.
+    pub fn path(&self) -> PathBuf {
+        match self {
+            AssetSource::File(path) => path.clone(),
+            AssetSource::Pak(blob) => blob.archive.join(&blob.entry),
+            AssetSource::Zstd { inner, .. } => {
+                let path = inner.path();
+                let logical = path.to_string_lossy();
+                PathBuf::from(logical.strip_suffix(ZSTD_SUFFIX).unwrap_or(&logical))
+            }
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AssetSource::File(path) => mime_for_path(path),
+            AssetSource::Pak(blob) => mime_for_path(Path::new(&blob.entry)),
+            AssetSource::Zstd { .. } => mime_for_path(&self.path()),
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, AssetSource::Zstd { .. })
+    }
+
+    /// Opens the backing file positioned `start` bytes into the stored data
+    /// and returns it with the stored size. For compressed assets this is the
+    /// raw compressed stream.
+    pub fn open_at(&self, start: u64) -> Result<(File, u64), AssetError> {
+        let (path, base, size) = match self {
+            AssetSource::Zstd { inner, .. } => return inner.open_at(start),
+            AssetSource::File(path) => {
+                let size = std::fs::metadata(path)
+                    .map_err(|e| AssetError::io(path, e))?
+                    .len();
+                (path, 0, size)
+            }
+            AssetSource::Pak(blob) => (&blob.archive, blob.offset, blob.size),
+        };
+
+        let mut file = File::open(path).map_err(|e| AssetError::io(path, e))?;
+        let start = start.min(size);
+        if base + start > 0 {
+            file.seek(SeekFrom::Start(base + start))
+                .map_err(|e| AssetError::io(path, e))?;
+        }
+        Ok((file, size))
+    }
+}
+
+/// Resolves `filename` to enabled mods first (last in load order wins),
+/// then mounted paks (latest mount wins), then loose files under the
+/// resource roots. If neither has it, the same lookup
+/// is repeated for `<filename>.zst`.
+pub fn resolve_source<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    kind: AssetKind,
+    filename: &str,
+) -> Result<AssetSource, AssetError> {
+    match resolve_stored(app, kind, filename) {
+        Err(AssetError::NotFound(_)) => {}
+        other => return other,
+    }
+
+    let compressed = format!("{}{}", filename, ZSTD_SUFFIX);
+    match resolve_stored(app, kind, &compressed) {
+        Ok(inner) => Ok(AssetSource::Zstd {
+            inner: Box::new(inner),
+            max_bytes: app
+                .try_state::<AssetLimits>()
+                .map(|limits| limits.max_decompressed_bytes())
+                .unwrap_or(DEFAULT_MAX_DECOMPRESSED_BYTES),
+        }),
+        Err(AssetError::NotFound(_)) => Err(AssetError::NotFound(filename.to_string())),
+        Err(e) => Err(e),
+    }
+}
+
+fn resolve_stored<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    kind: AssetKind,
+    filename: &str,
+) -> Result<AssetSource, AssetError> {
+    let logical = logical_name(kind, filename)?;
+    let relative = Path::new(kind.dir()).join(sanitize_relative(filename)?);
+    for root in crate::mods::asset_roots(app) {
+        if let Some(path) = resolve_in_root(&root, &relative, filename)? {
+            return Ok(AssetSource::File(path));
+        }
+    }
+    if let Some(paks) = app.try_state::<PakRegistry>() {
+        if let Some(blob) = paks.lookup(&logical) {
+            return Ok(AssetSource::Pak(blob));
+        }
+    }
+    resolve_asset_path(app, kind, filename).map(AssetSource::File)
+}
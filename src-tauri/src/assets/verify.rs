@@ -0,0 +1,202 @@
+//! Install integrity checks against `resources/manifest.json`, which
+//! `build.rs` generates at build time.
+
+use super::path::{resolve_in_root, sanitize_relative, slash_path};
+use super::AssetError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+pub const PROGRESS_EVENT: &str = "verify-progress";
+
+/// Number of files hashed at the same time.
+pub const MAX_PARALLEL_HASHES: usize = 4;
+
+const HASH_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorruptedAsset {
+    pub path: String,
+    pub expected_sha256: String,
+    pub actual_sha256: String,
+    pub expected_size: u64,
+    pub actual_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum AssetCheck {
+    Ok { path: String },
+    Missing { path: String },
+    Corrupted(CorruptedAsset),
+    NotInManifest { path: String },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyReport {
+    pub root: PathBuf,
+    pub total: usize,
+    pub checked: usize,
+    pub ok: usize,
+    pub missing: Vec<String>,
+    pub corrupted: Vec<CorruptedAsset>,
+    /// Files on disk that the manifest doesn't know about.
+    pub extra: Vec<String>,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyProgress {
+    pub checked: usize,
+    pub total: usize,
+    pub current_file: String,
+}
+
+/// Tracks the single verification run allowed at a time.
+#[derive(Default)]
+pub struct VerifyState {
+    running: AtomicBool,
+    cancel: AtomicBool,
+    /// The report of the last run to finish, kept for support bundles.
+    last: Mutex<Option<VerifyReport>>,
+}
+
+impl VerifyState {
+    pub fn try_begin(&self) -> Result<VerifyPermit<'_>, AssetError> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(AssetError::Busy(
+                "asset verification is already running".to_string(),
+            ));
+        }
+        self.cancel.store(false, Ordering::Relaxed);
+        Ok(VerifyPermit { state: self })
+    }
+
+    pub fn cancel(&self) -> bool {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.running.load(Ordering::Acquire)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, report: &VerifyReport) {
+        *self.last.lock().unwrap() = Some(report.clone());
+    }
+
+    pub fn last(&self) -> Option<VerifyReport> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+pub struct VerifyPermit<'a> {
+    state: &'a VerifyState,
+}
+
+impl Drop for VerifyPermit<'_> {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::Release);
+    }
+}
+
+pub fn load_manifest(root: &Path) -> Result<Manifest, AssetError> {
+    let path = root.join(MANIFEST_NAME);
+    let contents = std::fs::read_to_string(&path).map_err(|e| AssetError::io(&path, e))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        AssetError::io(
+            &path,
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+/// Hashes one manifest entry, streaming the file rather than reading it whole.
+pub fn check_entry(root: &Path, name: &str, expected: &ManifestEntry) -> AssetCheck {
+    let missing = || AssetCheck::Missing {
+        path: name.to_string(),
+    };
+
+    let Ok(relative) = sanitize_relative(name) else {
+        return missing();
+    };
+    let Ok(Some(path)) = resolve_in_root(root, &relative, name) else {
+        return missing();
+    };
+    let Ok((actual_sha256, actual_size)) = hash_file(&path) else {
+        return missing();
+    };
+
+    if actual_size == expected.size && actual_sha256.eq_ignore_ascii_case(&expected.sha256) {
+        AssetCheck::Ok {
+            path: name.to_string(),
+        }
+    } else {
+        AssetCheck::Corrupted(CorruptedAsset {
+            path: name.to_string(),
+            expected_sha256: expected.sha256.clone(),
+            actual_sha256,
+            expected_size: expected.size,
+            actual_size,
+        })
+    }
+}
+
+pub fn hash_file(path: &Path) -> std::io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; HASH_CHUNK_BYTES];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&chunk[..n]);
+        size += n as u64;
+    }
+    let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok((hex, size))
+}
+
+/// Files under `root` that aren't listed in the manifest, sorted.
+pub fn find_extra(root: &Path, manifest: &Manifest) -> Vec<String> {
+    let mut extra = BTreeSet::new();
+    for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Some(relative) = entry.path().strip_prefix(root).ok().and_then(slash_path) else {
+            continue;
+        };
+        if relative != MANIFEST_NAME && !manifest.files.contains_key(&relative) {
+            extra.insert(relative);
+        }
+    }
+    extra.into_iter().collect()
+}
@@ -0,0 +1,182 @@
+//! Dev-only hot reload of `resources/audio` and
+//! `resources/data/weapons`. Release builds compile the watcher out
+//! entirely; [`AssetWatcher::set_enabled`] is then a no-op.
+
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+
+#[derive(Default)]
+pub struct AssetWatcher {
+    #[cfg(debug_assertions)]
+    state: Mutex<dev::WatchState>,
+    #[cfg(not(debug_assertions))]
+    state: Mutex<()>,
+}
+
+impl AssetWatcher {
+    /// Starts or stops watching. Returns whether the watcher is now enabled,
+    /// which is always `false` in release builds.
+    pub fn set_enabled<R: Runtime>(&self, app: &AppHandle<R>, enabled: bool) -> bool {
+        #[cfg(debug_assertions)]
+        {
+            dev::set_enabled(&self.state, app, enabled)
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            let _ = (&self.state, app, enabled);
+            false
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+mod dev {
+    use crate::assets::compression::ZSTD_SUFFIX;
+    use crate::assets::path::slash_path;
+    use crate::assets::{AssetCache, AssetWatcher};
+    use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+    use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+    use serde::Serialize;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+    pub const CHANGED_EVENT: &str = "asset-changed";
+
+    #[derive(Debug, Clone, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum AssetChange {
+        /// Created or modified; editors often replace files, so the two can't be
+        /// told apart reliably.
+        Modified,
+        Removed,
+    }
+
+    #[derive(Debug, Clone, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AssetChanged {
+        /// Path relative to `resources/audio`, with the `.zst` suffix dropped.
+        pub filename: String,
+        pub change: AssetChange,
+    }
+
+    const DEBOUNCE: Duration = Duration::from_millis(250);
+    /// How often to look for the resources directory if it doesn't exist yet.
+    const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+    #[derive(Default)]
+    pub struct WatchState {
+        enabled: bool,
+        /// Bumped on every toggle so a stale retry thread knows to give up.
+        generation: u64,
+        debouncer: Option<Debouncer<RecommendedWatcher>>,
+    }
+
+    const AUDIO_DIR: &str = "audio";
+    const WEAPONS_DIR: &str = "data/weapons";
+
+    fn resources_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources")
+    }
+
+    pub fn set_enabled<R: Runtime>(
+        state: &Mutex<WatchState>,
+        app: &AppHandle<R>,
+        enabled: bool,
+    ) -> bool {
+        let mut guard = state.lock().unwrap();
+        if guard.enabled == enabled {
+            return enabled;
+        }
+        guard.enabled = enabled;
+        guard.generation += 1;
+        // Dropping the debouncer stops its watcher thread.
+        guard.debouncer = None;
+
+        if enabled {
+            let generation = guard.generation;
+            let app = app.clone();
+            std::thread::spawn(move || wait_and_watch(app, generation));
+        }
+        enabled
+    }
+
+    /// Polls until the resources directory exists, then installs the
+    /// watcher. The directory may legitimately be missing on a fresh
+    /// checkout.
+    fn wait_and_watch<R: Runtime>(app: AppHandle<R>, generation: u64) {
+        loop {
+            {
+                let watcher = app.state::<AssetWatcher>();
+                let mut state = watcher.state.lock().unwrap();
+                if !state.enabled || state.generation != generation {
+                    return;
+                }
+                if let Ok(root) = resources_dir().canonicalize() {
+                    match watch(app.clone(), root) {
+                        Ok(debouncer) => state.debouncer = Some(debouncer),
+                        Err(e) => log::warn!("asset watcher failed to start: {}", e),
+                    }
+                    return;
+                }
+            }
+            std::thread::sleep(RETRY_INTERVAL);
+        }
+    }
+
+    fn watch<R: Runtime>(
+        app: AppHandle<R>,
+        root: PathBuf,
+    ) -> notify_debouncer_mini::notify::Result<Debouncer<RecommendedWatcher>> {
+        let watched = root.clone();
+        let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+            let Ok(events) = result else {
+                return;
+            };
+            let audio = watched.join(AUDIO_DIR);
+            let mut weapons_changed = false;
+            for event in events {
+                if event.path.starts_with(&audio) {
+                    handle_change(&app, &audio, &event.path);
+                } else if event.path.starts_with(watched.join(WEAPONS_DIR)) {
+                    weapons_changed = true;
+                }
+            }
+            // One reload however many files an edit touched.
+            if weapons_changed {
+                crate::weapons::reload(&app);
+            }
+        })?;
+        debouncer.watcher().watch(&root, RecursiveMode::Recursive)?;
+        Ok(debouncer)
+    }
+
+    fn handle_change<R: Runtime>(app: &AppHandle<R>, root: &Path, path: &Path) {
+        if path.is_dir() {
+            return;
+        }
+        let Some(relative) = path.strip_prefix(root).ok().and_then(slash_path) else {
+            return;
+        };
+
+        // Compressed assets are cached under their logical (suffix-less) path.
+        let logical = relative.strip_suffix(ZSTD_SUFFIX).unwrap_or(&relative);
+        let cache = app.state::<AssetCache>();
+        cache.remove(path);
+        cache.remove(&root.join(logical));
+
+        let change = if path.exists() {
+            AssetChange::Modified
+        } else {
+            AssetChange::Removed
+        };
+        let _ = app.emit(
+            CHANGED_EVENT,
+            AssetChanged {
+                filename: logical.to_string(),
+                change,
+            },
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use super::AudioError;
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::Path;
+use symphonia::core::audio::{Channels, SampleBuffer};
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::{Hint, ProbeResult};
+
+/// Interleaved PCM ready to copy into a WebAudio `AudioBuffer`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    /// 1 or 2; anything wider is downmixed to stereo.
+    pub channels: u16,
+    pub frames: u64,
+}
+
+/// Detects the container from the magic bytes, using the file extension
+/// only as a tie-breaker.
+pub(super) fn probe(
+    bytes: Vec<u8>,
+    logical_path: &Path,
+    filename: &str,
+) -> Result<ProbeResult, AudioError> {
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = logical_path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| AudioError::unsupported(filename, e))
+}
+
+pub fn decode(
+    bytes: Vec<u8>,
+    logical_path: &Path,
+    filename: &str,
+) -> Result<DecodedAudio, AudioError> {
+    let mut format: Box<dyn FormatReader> = probe(bytes, logical_path, filename)?.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| AudioError::unsupported(filename, "no audio track"))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| AudioError::unsupported(filename, e))?;
+
+    let mut samples = Vec::new();
+    let mut out_channels = 0u16;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(AudioError::decode(filename, e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A single corrupt packet shouldn't sink the whole file.
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(AudioError::decode(filename, e)),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        let needs_new_buffer = buffer
+            .as_ref()
+            .is_none_or(|b| b.capacity() < decoded.capacity() * spec.channels.count());
+        if needs_new_buffer {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        let buffer = buffer.as_mut().unwrap();
+        buffer.copy_interleaved_ref(decoded);
+
+        out_channels = append_downmixed(&mut samples, buffer.samples(), spec.channels);
+    }
+
+    if out_channels == 0 || sample_rate == 0 {
+        return Err(AudioError::decode(filename, "stream contained no audio"));
+    }
+
+    Ok(DecodedAudio {
+        frames: (samples.len() / out_channels as usize) as u64,
+        samples,
+        sample_rate,
+        channels: out_channels,
+    })
+}
+
+/// Appends interleaved `input` to `out`, folding layouts wider than stereo
+/// down to two channels. Returns the output channel count.
+fn append_downmixed(out: &mut Vec<f32>, input: &[f32], layout: Channels) -> u16 {
+    let count = layout.count();
+    if count <= 2 {
+        out.extend_from_slice(input);
+        return count as u16;
+    }
+
+    let weights: Vec<(f32, f32)> = layout.iter().map(stereo_weights).collect();
+    let left_norm: f32 = weights.iter().map(|w| w.0).sum::<f32>().max(1.0);
+    let right_norm: f32 = weights.iter().map(|w| w.1).sum::<f32>().max(1.0);
+
+    out.reserve(input.len() / count * 2);
+    for frame in input.chunks_exact(count) {
+        let (mut left, mut right) = (0.0, 0.0);
+        for (sample, (wl, wr)) in frame.iter().zip(&weights) {
+            left += sample * wl;
+            right += sample * wr;
+        }
+        out.push(left / left_norm);
+        out.push(right / right_norm);
+    }
+    2
+}
+
+/// ITU-style downmix coefficients; LFE is dropped.
+fn stereo_weights(channel: Channels) -> (f32, f32) {
+    const HALF_POWER: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+    if channel
+        .intersects(Channels::FRONT_LEFT | Channels::FRONT_LEFT_CENTRE | Channels::FRONT_LEFT_WIDE)
+    {
+        (1.0, 0.0)
+    } else if channel.intersects(
+        Channels::FRONT_RIGHT | Channels::FRONT_RIGHT_CENTRE | Channels::FRONT_RIGHT_WIDE,
+    ) {
+        (0.0, 1.0)
+    } else if channel
+        .intersects(Channels::FRONT_CENTRE | Channels::REAR_CENTRE | Channels::TOP_CENTRE)
+    {
+        (HALF_POWER, HALF_POWER)
+    } else if channel.intersects(
+        Channels::SIDE_LEFT
+            | Channels::REAR_LEFT
+            | Channels::TOP_FRONT_LEFT
+            | Channels::TOP_REAR_LEFT,
+    ) {
+        (HALF_POWER, 0.0)
+    } else if channel.intersects(
+        Channels::SIDE_RIGHT
+            | Channels::REAR_RIGHT
+            | Channels::TOP_FRONT_RIGHT
+            | Channels::TOP_REAR_RIGHT,
+    ) {
+        (0.0, HALF_POWER)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+/// Linear-interpolation resampler. Cheap and good enough for game audio
+/// played back through WebAudio; not meant for mastering.
+pub fn resample(audio: DecodedAudio, target_rate: u32) -> DecodedAudio {
+    if target_rate == 0 || target_rate == audio.sample_rate || audio.frames == 0 {
+        return audio;
+    }
+
+    let channels = audio.channels as usize;
+    let in_frames = audio.frames as usize;
+    let ratio = audio.sample_rate as f64 / target_rate as f64;
+    let out_frames = ((in_frames as f64) / ratio).round().max(1.0) as usize;
+
+    let mut samples = Vec::with_capacity(out_frames * channels);
+    for i in 0..out_frames {
+        let position = i as f64 * ratio;
+        let index = (position.floor() as usize).min(in_frames - 1);
+        let next = (index + 1).min(in_frames - 1);
+        let t = (position - index as f64) as f32;
+        for c in 0..channels {
+            let a = audio.samples[index * channels + c];
+            let b = audio.samples[next * channels + c];
+            samples.push(a + (b - a) * t);
+        }
+    }
+
+    DecodedAudio {
+        samples,
+        sample_rate: target_rate,
+        channels: audio.channels,
+        frames: out_frames as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::fixture;
+
+    fn decode_fixture(name: &str) -> DecodedAudio {
+        decode(fixture(name), Path::new(name), name).unwrap()
+    }
+
+    #[test]
+    fn decodes_wav_samples() {
+        let audio = decode_fixture("tone.wav");
+        assert_eq!(
+            (audio.sample_rate, audio.channels, audio.frames),
+            (8000, 1, 4000)
+        );
+        for (i, sample) in audio.samples.iter().enumerate().take(100) {
+            let expected =
+                (12000.0 * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / 8000.0).sin()) as i16;
+            assert!((sample - expected as f32 / 32768.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn decodes_mp3_frames() {
+        let audio = decode_fixture("tagged.mp3");
+        assert_eq!(
+            (audio.sample_rate, audio.channels, audio.frames),
+            (44100, 2, 23040)
+        );
+        assert!(audio.samples.iter().any(|s| s.abs() > 0.1));
+    }
+
+    #[test]
+    fn decodes_ogg_vorbis_packets() {
+        let audio = decode_fixture("silence.ogg");
+        assert_eq!(
+            (audio.sample_rate, audio.channels, audio.frames),
+            (8000, 1, 7936)
+        );
+        assert!(audio.samples.iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn resamples_to_the_target_rate() {
+        let audio = resample(decode_fixture("tone.wav"), 16000);
+        assert_eq!((audio.sample_rate, audio.frames), (16000, 8000));
+        assert_eq!(audio.samples.len(), 8000);
+    }
+
+    #[test]
+    fn downmixes_surround_to_stereo() {
+        let layout = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE;
+        let mut out = Vec::new();
+        let channels = append_downmixed(&mut out, &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0], layout);
+        assert_eq!(channels, 2);
+        let norm = 1.0 + std::f32::consts::FRAC_1_SQRT_2;
+        let expected = [1.0 / norm, 0.0, std::f32::consts::FRAC_1_SQRT_2 / norm];
+        assert!((out[0] - expected[0]).abs() < 1e-6 && out[1] == 0.0);
+        assert!((out[2] - expected[2]).abs() < 1e-6 && (out[3] - expected[2]).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::assets::AssetError;
+use serde::{Serialize, Serializer};
+
+/// Errors from the audio decode / probe commands.
+///
+/// Serialized as `{ kind, message }`, same as [`AssetError`].
+#[derive(Debug, thiserror::Error)]
+pub enum AudioError {
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+
+    #[error("Unsupported audio format in {filename}: {reason}")]
+    Unsupported { filename: String, reason: String },
+
+    #[error("Failed to decode {filename}: {reason}")]
+    Decode { filename: String, reason: String },
+}
+
+impl AudioError {
+    pub(crate) fn unsupported(filename: &str, reason: impl ToString) -> Self {
+        AudioError::Unsupported {
+            filename: filename.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn decode(filename: &str, reason: impl ToString) -> Self {
+        AudioError::Decode {
+            filename: filename.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AudioError::Asset(e) => e.kind(),
+            AudioError::Unsupported { .. } => "unsupported",
+            AudioError::Decode { .. } => "decode",
+        }
+    }
+}
+
+impl Serialize for AudioError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AudioError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,170 @@
+use super::decode::probe;
+use super::AudioError;
+use serde::Serialize;
+use std::path::Path;
+use symphonia::core::codecs::CODEC_TYPE_NULL;
+use symphonia::core::meta::{MetadataRevision, StandardTagKey};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioMetadata {
+    pub duration_secs: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub codec: String,
+    pub file_size: u64,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+/// Reads container headers and tags (ID3, vorbis comments) without decoding
+/// any audio. When the header carries no frame count (e.g. VBR mp3 without a
+/// Xing header) the packets are walked to sum their durations, which is still
+/// far cheaper than decoding.
+pub fn read_metadata(
+    bytes: Vec<u8>,
+    logical_path: &Path,
+    filename: &str,
+) -> Result<AudioMetadata, AudioError> {
+    let file_size = bytes.len() as u64;
+    let mut probed = probe(bytes, logical_path, filename)?;
+
+    let mut title = None;
+    let mut artist = None;
+    // Tags found while probing (e.g. a leading ID3 block) come first, then the
+    // container's own metadata overrides them.
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            collect_tags(revision, &mut title, &mut artist);
+        }
+    }
+    let mut format = probed.format;
+    if let Some(revision) = format.metadata().current() {
+        collect_tags(revision, &mut title, &mut artist);
+    }
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| AudioError::decode(filename, "no audio track in header"))?;
+    let params = track.codec_params.clone();
+    let track_id = track.id;
+
+    let codec = symphonia::default::get_codecs()
+        .get_codec(params.codec)
+        .map(|d| d.short_name.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let n_frames = match params.n_frames {
+        Some(frames) => Some(frames),
+        None => {
+            let mut total = 0u64;
+            while let Ok(packet) = format.next_packet() {
+                if packet.track_id() == track_id {
+                    total += packet.dur;
+                }
+            }
+            (total > 0).then_some(total)
+        }
+    };
+
+    let duration_secs = match (n_frames, params.time_base, params.sample_rate) {
+        (Some(frames), Some(time_base), _) => {
+            let time = time_base.calc_time(frames);
+            Some(time.seconds as f64 + time.frac)
+        }
+        (Some(frames), None, Some(rate)) if rate > 0 => Some(frames as f64 / rate as f64),
+        _ => None,
+    };
+
+    Ok(AudioMetadata {
+        duration_secs,
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|c| c.count() as u16),
+        codec,
+        file_size,
+        title,
+        artist,
+    })
+}
+
+fn collect_tags(
+    revision: &MetadataRevision,
+    title: &mut Option<String>,
+    artist: &mut Option<String>,
+) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string();
+        if value.trim().is_empty() {
+            continue;
+        }
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => *title = Some(value),
+            Some(StandardTagKey::Artist) => *artist = Some(value),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::fixture;
+
+    fn metadata(name: &str) -> Result<AudioMetadata, AudioError> {
+        read_metadata(fixture(name), Path::new(name), name)
+    }
+
+    #[test]
+    fn reads_ogg_vorbis_headers_and_comments() {
+        let meta = metadata("silence.ogg").unwrap();
+        assert_eq!(meta.codec, "vorbis");
+        assert_eq!(meta.sample_rate, Some(8000));
+        assert_eq!(meta.channels, Some(1));
+        assert_eq!(meta.duration_secs, Some(7936.0 / 8000.0));
+        assert_eq!(meta.file_size, 394);
+        assert_eq!(meta.title.as_deref(), Some("Silent Fixture"));
+        assert_eq!(meta.artist.as_deref(), Some("fps-game"));
+    }
+
+    #[test]
+    fn reads_mp3_frames_and_id3_tags() {
+        let meta = metadata("tagged.mp3").unwrap();
+        assert_eq!(meta.codec, "mp3");
+        assert_eq!(meta.sample_rate, Some(44100));
+        assert_eq!(meta.channels, Some(2));
+        // No Xing header, so this is the sum of 20 frames of 1152 samples.
+        let duration = meta.duration_secs.unwrap();
+        assert!(
+            (duration - 20.0 * 1152.0 / 44100.0).abs() < 1e-9,
+            "{}",
+            duration
+        );
+        assert_eq!(meta.title.as_deref(), Some("Fixture Tone"));
+        assert_eq!(meta.artist.as_deref(), Some("fps-game"));
+    }
+
+    #[test]
+    fn untagged_wav_has_no_tag_fields() {
+        let meta = metadata("tone.wav").unwrap();
+        assert_eq!(meta.codec, "pcm_s16le");
+        assert_eq!(meta.sample_rate, Some(8000));
+        assert_eq!(meta.channels, Some(1));
+        assert_eq!(meta.duration_secs, Some(0.5));
+        assert_eq!(meta.file_size, 8044);
+        assert_eq!(meta.title, None);
+        assert_eq!(meta.artist, None);
+    }
+
+    #[test]
+    fn corrupt_headers_name_the_file() {
+        let mut truncated = fixture("tone.wav");
+        truncated.truncate(20);
+        let garbage = b"this is not audio at all".to_vec();
+        for (name, bytes) in [("broken.wav", truncated), ("garbage.ogg", garbage)] {
+            let error = read_metadata(bytes, Path::new(name), name).unwrap_err();
+            assert!(error.to_string().contains(name), "{}", error);
+        }
+    }
+}
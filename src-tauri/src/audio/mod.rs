@@ -0,0 +1,75 @@
+mod decode;
+mod error;
+mod metadata;
+
+pub use decode::DecodedAudio;
+pub use error::AudioError;
+pub use metadata::AudioMetadata;
+
+use crate::assets::{read_asset, AssetError, AssetKind};
+
+/// Decodes an audio asset (ogg/vorbis, wav, mp3) to interleaved f32 PCM on a
+/// blocking thread, so the webview can skip `decodeAudioData`.
+#[tauri::command]
+pub async fn decode_audio(
+    app: tauri::AppHandle,
+    filename: String,
+) -> Result<DecodedAudio, AudioError> {
+    decode_asset(&app, filename, None).await
+}
+
+/// Same as [`decode_audio`], then resampled to `target_rate` (typically the
+/// `AudioContext.sampleRate`).
+#[tauri::command]
+pub async fn decode_audio_resampled(
+    app: tauri::AppHandle,
+    filename: String,
+    target_rate: u32,
+) -> Result<DecodedAudio, AudioError> {
+    decode_asset(&app, filename, Some(target_rate)).await
+}
+
+/// Duration, format and tag info for the playlist UI, read from headers only.
+#[tauri::command]
+pub async fn get_audio_metadata(
+    app: tauri::AppHandle,
+    filename: String,
+) -> Result<AudioMetadata, AudioError> {
+    let (source, bytes) = read_asset(&app, AssetKind::Audio, &filename).await?;
+    let logical_path = source.path();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        metadata::read_metadata(bytes, &logical_path, &filename)
+    })
+    .await
+    .map_err(|e| AudioError::Asset(AssetError::task(source.path(), e)))?
+}
+
+/// Decodes an audio asset for backend modules, optionally resampled.
+pub async fn decode_asset(
+    app: &tauri::AppHandle,
+    filename: String,
+    target_rate: Option<u32>,
+) -> Result<DecodedAudio, AudioError> {
+    let (source, bytes) = read_asset(app, AssetKind::Audio, &filename).await?;
+    let logical_path = source.path();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let audio = decode::decode(bytes, &logical_path, &filename)?;
+        Ok(match target_rate {
+            Some(rate) => decode::resample(audio, rate),
+            None => audio,
+        })
+    })
+    .await
+    .map_err(|e| AudioError::Asset(AssetError::task(source.path(), e)))?
+}
+
+/// The bytes of `tests/fixtures/audio/<name>`.
+#[cfg(test)]
+fn fixture(name: &str) -> Vec<u8> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/audio")
+        .join(name);
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{:?}: {}", path, e))
+}
@@ -0,0 +1,67 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the benchmark commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum BenchmarkError {
+    #[error("A benchmark is already running")]
+    Busy,
+
+    #[error("Invalid benchmark spec: {0}")]
+    InvalidSpec(String),
+
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No benchmark report with id {0:?}")]
+    NotFound(String),
+
+    #[error("Benchmark report {id:?} is damaged: {reason}")]
+    Corrupted { id: String, reason: String },
+
+    #[error("The benchmark scene failed to build: {0}")]
+    Scene(String),
+}
+
+impl BenchmarkError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        BenchmarkError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        BenchmarkError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BenchmarkError::Busy => "busy",
+            BenchmarkError::InvalidSpec(_) => "invalidSpec",
+            BenchmarkError::NoDataDir(_) => "noDataDir",
+            BenchmarkError::Io { .. } => "io",
+            BenchmarkError::NotFound(_) => "notFound",
+            BenchmarkError::Corrupted { .. } => "corrupted",
+            BenchmarkError::Scene(_) => "scene",
+        }
+    }
+}
+
+impl Serialize for BenchmarkError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("BenchmarkError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
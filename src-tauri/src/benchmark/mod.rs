@@ -0,0 +1,113 @@
+mod error;
+mod model;
+mod run;
+mod scene;
+mod store;
+
+pub use error::BenchmarkError;
+pub use model::{BenchmarkReport, BenchmarkSpec, BenchmarkSummary};
+
+use crate::time::now_ms;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tauri::{Runtime, State};
+
+#[derive(Default)]
+pub struct BenchmarkState {
+    running: AtomicBool,
+}
+
+/// Clears the running flag however the run ends.
+struct RunGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Runs the stages `spec` enables in order, emitting `benchmark-progress`
+/// as each starts, and saves the report under `AppData/benchmarks`.
+///
+/// The frame capture stage emits `benchmark-frame-capture-start` with its
+/// length in seconds; the frontend should play its scripted sequence and
+/// stream frame times to `record_frame_times` until
+/// `benchmark-frame-capture-end`. A second run while one is going fails
+/// with `busy`.
+#[tauri::command]
+pub async fn run_benchmark(
+    app: tauri::AppHandle,
+    benchmark: State<'_, BenchmarkState>,
+    spec: Option<BenchmarkSpec>,
+) -> Result<BenchmarkReport, BenchmarkError> {
+    let spec = spec.unwrap_or_default().validated()?;
+    if benchmark.running.swap(true, Ordering::AcqRel) {
+        return Err(BenchmarkError::Busy);
+    }
+    let _guard = RunGuard(&benchmark.running);
+
+    let dir = store::benchmarks_dir(&app)?;
+    let started_at_ms = now_ms();
+    let start = Instant::now();
+    let mut stages = Vec::new();
+    let mut frames = None;
+    if spec.preload {
+        stages.extend(run::preload(&app).await);
+    }
+    if spec.decode {
+        stages.push(run::decode(&app).await);
+    }
+    if spec.raycast_iterations > 0 {
+        stages.push(run::raycast(&app, spec.raycast_iterations).await?);
+    }
+    if spec.frame_capture_secs > 0 {
+        let (stage, stats) = run::frame_capture(&app, spec.frame_capture_secs).await;
+        stages.push(stage);
+        frames = stats;
+    }
+
+    let report = BenchmarkReport {
+        id: store::new_id(&dir, started_at_ms),
+        label: spec.label.clone(),
+        started_at_ms,
+        total_ms: start.elapsed().as_secs_f64() * 1000.0,
+        app_version: app.package_info().version.to_string(),
+        spec,
+        system: crate::system::info(&app),
+        stages,
+        frames,
+    };
+    store::write(&dir, &report)?;
+    Ok(report)
+}
+
+/// Every saved run, newest first.
+#[tauri::command]
+pub async fn list_benchmark_reports(
+    app: tauri::AppHandle,
+) -> Result<Vec<BenchmarkSummary>, BenchmarkError> {
+    let dir = store::benchmarks_dir(&app)?;
+    let task_dir = dir.clone();
+    tauri::async_runtime::spawn_blocking(move || store::list(&dir))
+        .await
+        .map_err(|e| BenchmarkError::task(task_dir, e))?
+}
+
+#[tauri::command]
+pub async fn load_benchmark_report(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<BenchmarkReport, BenchmarkError> {
+    store::read(&store::benchmarks_dir(&app)?, &id)
+}
+
+/// The newest report, for backend modules; `None` before any run.
+pub fn latest_report<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+) -> Result<Option<BenchmarkReport>, BenchmarkError> {
+    let dir = store::benchmarks_dir(app)?;
+    match store::list(&dir)?.first() {
+        Some(newest) => store::read(&dir, &newest.id).map(Some),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,167 @@
+use super::BenchmarkError;
+use crate::perf::FrameStats;
+use crate::system::SystemInfo;
+use serde::{Deserialize, Serialize};
+
+const MAX_LABEL_LEN: usize = 64;
+const MAX_RAYCAST_ITERATIONS: u32 = 10_000;
+const MAX_FRAME_CAPTURE_SECS: u32 = 300;
+
+/// Which stages to run. Their inputs are fixed by the build, so two runs
+/// of the same spec on the same build do the same work.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BenchmarkSpec {
+    /// Shown next to the run in the history, e.g. a branch name.
+    pub label: Option<String>,
+    pub preload: bool,
+    pub decode: bool,
+    /// Batches of rays to cast at the canned scene; 0 skips the stage.
+    pub raycast_iterations: u32,
+    /// How long the frontend records frame times for; 0 skips the stage.
+    pub frame_capture_secs: u32,
+}
+
+impl Default for BenchmarkSpec {
+    fn default() -> Self {
+        Self {
+            label: None,
+            preload: true,
+            decode: true,
+            raycast_iterations: 200,
+            frame_capture_secs: 0,
+        }
+    }
+}
+
+impl BenchmarkSpec {
+    pub fn validated(mut self) -> Result<Self, BenchmarkError> {
+        self.label = self
+            .label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        if self
+            .label
+            .as_ref()
+            .is_some_and(|l| l.chars().count() > MAX_LABEL_LEN)
+        {
+            return Err(BenchmarkError::InvalidSpec(format!(
+                "label is longer than {} characters",
+                MAX_LABEL_LEN
+            )));
+        }
+        if self.raycast_iterations > MAX_RAYCAST_ITERATIONS {
+            return Err(BenchmarkError::InvalidSpec(format!(
+                "raycastIterations must be at most {}",
+                MAX_RAYCAST_ITERATIONS
+            )));
+        }
+        if self.frame_capture_secs > MAX_FRAME_CAPTURE_SECS {
+            return Err(BenchmarkError::InvalidSpec(format!(
+                "frameCaptureSecs must be at most {}",
+                MAX_FRAME_CAPTURE_SECS
+            )));
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageResult {
+    /// `preloadCold`, `preloadWarm`, `decode`, `raycast` or `frameCapture`.
+    pub name: String,
+    pub duration_ms: f64,
+    /// Files, rays or frames processed.
+    pub items: u64,
+    pub items_per_sec: f64,
+    /// Bytes read or samples decoded, where that's the better measure.
+    pub bytes: Option<u64>,
+    pub bytes_per_sec: Option<f64>,
+    /// A digest of the stage's output, e.g. the raycast hit count. Runs with
+    /// different checksums didn't do the same work.
+    pub checksum: Option<u64>,
+    /// What failed along the way. A stage with failures did less work, so
+    /// its numbers aren't comparable with a clean run.
+    pub failures: Vec<String>,
+}
+
+impl StageResult {
+    pub fn new(name: &str, duration_ms: f64, items: u64, bytes: Option<u64>) -> Self {
+        let per_sec = |n: u64| {
+            if duration_ms > 0.0 {
+                n as f64 * 1000.0 / duration_ms
+            } else {
+                0.0
+            }
+        };
+        Self {
+            name: name.to_string(),
+            duration_ms,
+            items,
+            items_per_sec: per_sec(items),
+            bytes,
+            bytes_per_sec: bytes.map(per_sec),
+            checksum: None,
+            failures: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub id: String,
+    pub label: Option<String>,
+    pub started_at_ms: u64,
+    pub total_ms: f64,
+    pub app_version: String,
+    pub spec: BenchmarkSpec,
+    pub system: SystemInfo,
+    pub stages: Vec<StageResult>,
+    /// Stats over the frame capture, when it ran and frames arrived.
+    pub frames: Option<FrameStats>,
+}
+
+/// One line of the history: enough to graph runs against each other.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkSummary {
+    pub id: String,
+    pub label: Option<String>,
+    pub started_at_ms: u64,
+    pub total_ms: f64,
+    pub app_version: String,
+    pub stages: Vec<StageSummary>,
+    pub avg_fps: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageSummary {
+    pub name: String,
+    pub duration_ms: f64,
+    pub items_per_sec: f64,
+}
+
+impl From<&BenchmarkReport> for BenchmarkSummary {
+    fn from(report: &BenchmarkReport) -> Self {
+        Self {
+            id: report.id.clone(),
+            label: report.label.clone(),
+            started_at_ms: report.started_at_ms,
+            total_ms: report.total_ms,
+            app_version: report.app_version.clone(),
+            stages: report
+                .stages
+                .iter()
+                .map(|s| StageSummary {
+                    name: s.name.clone(),
+                    duration_ms: s.duration_ms,
+                    items_per_sec: s.items_per_sec,
+                })
+                .collect(),
+            avg_fps: report.frames.as_ref().map(|f| f.avg_fps),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! The stages of a run, each timed on its own.
+
+use super::model::StageResult;
+use super::scene::CannedScene;
+use super::BenchmarkError;
+use crate::assets::{AssetCache, AssetKind};
+use crate::simulation::{HitResult, Scene, MAX_RAYS_PER_BATCH};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+pub const PROGRESS_EVENT: &str = "benchmark-progress";
+pub const CAPTURE_START_EVENT: &str = "benchmark-frame-capture-start";
+pub const CAPTURE_END_EVENT: &str = "benchmark-frame-capture-end";
+
+/// Bundled assets every run loads and decodes, so runs on one build are
+/// comparable; changing the list makes older reports incomparable.
+const ASSETS: &[(AssetKind, &str)] = &[
+    (AssetKind::Audio, "combat.mp3"),
+    (AssetKind::Audio, "rainy.mp3"),
+    (AssetKind::Audio, "sunny.mp3"),
+];
+/// How long after the capture to wait for the frontend's last batch of
+/// frame times.
+const CAPTURE_GRACE: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress<'a> {
+    stage: &'a str,
+}
+
+fn progress(app: &tauri::AppHandle, stage: &str) {
+    let _ = app.emit(PROGRESS_EVENT, Progress { stage });
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Reads every asset with its cache entry dropped first, then again from
+/// the cache, returning both stages.
+pub async fn preload(app: &tauri::AppHandle) -> [StageResult; 2] {
+    progress(app, "preloadCold");
+    let cache = app.state::<AssetCache>();
+    for (kind, filename) in ASSETS {
+        if let Ok(source) = crate::assets::resolve_source(app, *kind, filename) {
+            cache.remove(&source.path());
+        }
+    }
+    let cold = read_all(app, "preloadCold").await;
+    progress(app, "preloadWarm");
+    let warm = read_all(app, "preloadWarm").await;
+    [cold, warm]
+}
+
+async fn read_all(app: &tauri::AppHandle, name: &str) -> StageResult {
+    let start = Instant::now();
+    let mut bytes = 0u64;
+    let mut loaded = 0u64;
+    let mut failures = Vec::new();
+    for (kind, filename) in ASSETS {
+        match crate::assets::read_asset(app, *kind, filename).await {
+            Ok((_, data)) => {
+                loaded += 1;
+                bytes += data.len() as u64;
+            }
+            Err(e) => failures.push(format!("{}: {}", filename, e)),
+        }
+    }
+    let mut stage = StageResult::new(name, elapsed_ms(start), loaded, Some(bytes));
+    stage.failures = failures;
+    stage
+}
+
+/// Decodes each file at its own rate; `bytes` counts decoded samples.
+pub async fn decode(app: &tauri::AppHandle) -> StageResult {
+    progress(app, "decode");
+    let start = Instant::now();
+    let mut decoded = 0u64;
+    let mut samples = 0u64;
+    let mut failures = Vec::new();
+    for (_, filename) in ASSETS.iter().filter(|(kind, _)| *kind == AssetKind::Audio) {
+        match crate::audio::decode_asset(app, filename.to_string(), None).await {
+            Ok(audio) => {
+                decoded += 1;
+                samples += audio.samples.len() as u64;
+            }
+            Err(e) => failures.push(format!("{}: {}", filename, e)),
+        }
+    }
+    let mut stage = StageResult::new("decode", elapsed_ms(start), decoded, Some(samples));
+    stage.failures = failures;
+    stage
+}
+
+/// Casts `iterations` full batches at the canned scene. Building the scene
+/// isn't timed; ray generation is kept out of the timings too.
+pub async fn raycast(
+    app: &tauri::AppHandle,
+    iterations: u32,
+) -> Result<StageResult, BenchmarkError> {
+    progress(app, "raycast");
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut canned = CannedScene::generate();
+        let batches: Vec<_> = (0..iterations)
+            .map(|_| canned.next_rays(MAX_RAYS_PER_BATCH))
+            .collect();
+        let scene = Scene::build(canned.geometry, canned.hitboxes)
+            .map_err(|e| BenchmarkError::Scene(e.to_string()))?;
+
+        let start = Instant::now();
+        let mut hits = 0u64;
+        for rays in &batches {
+            let results = scene
+                .cast(rays)
+                .map_err(|e| BenchmarkError::Scene(e.to_string()))?;
+            hits += results
+                .iter()
+                .filter(|r| matches!(r, HitResult::Hit(_)))
+                .count() as u64;
+        }
+        let rays = (batches.len() * MAX_RAYS_PER_BATCH) as u64;
+        let mut stage = StageResult::new("raycast", elapsed_ms(start), rays, None);
+        stage.checksum = Some(hits);
+        Ok(stage)
+    })
+    .await
+    .map_err(|e| BenchmarkError::Scene(e.to_string()))?
+}
+
+/// Asks the frontend to render its scripted sequence for `secs` while
+/// streaming frame times to `record_frame_times`, then returns the stage
+/// and the stats over what arrived.
+pub async fn frame_capture(
+    app: &tauri::AppHandle,
+    secs: u32,
+) -> (StageResult, Option<crate::perf::FrameStats>) {
+    progress(app, "frameCapture");
+    crate::perf::reset(app);
+    let duration = Duration::from_secs(secs as u64);
+    let _ = app.emit(CAPTURE_START_EVENT, secs);
+    let start = Instant::now();
+    let _ = tauri::async_runtime::spawn_blocking(move || std::thread::sleep(duration)).await;
+    let duration_ms = elapsed_ms(start);
+    let _ = app.emit(CAPTURE_END_EVENT, ());
+    let _ = tauri::async_runtime::spawn_blocking(|| std::thread::sleep(CAPTURE_GRACE)).await;
+
+    let stats = crate::perf::current_stats(app);
+    let frames = stats.as_ref().map_or(0, |s| s.frame_count as u64);
+    let mut stage = StageResult::new("frameCapture", duration_ms, frames, None);
+    if stats.is_none() {
+        stage
+            .failures
+            .push("no frame times arrived during the capture".to_string());
+    }
+    (stage, stats)
+}
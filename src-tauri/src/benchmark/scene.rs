@@ -0,0 +1,119 @@
+//! The canned raycast scene: an arena of crates, ramps and players,
+//! generated from a fixed seed so every run casts the same rays at the
+//! same geometry.
+
+use crate::rng::Xoshiro256StarStar;
+use crate::simulation::{Aabb, EntityHitbox, Ray, Triangle, Vec3, WorldGeometry};
+
+const SEED: u64 = 0x6265_6e63_685f_3031;
+const ARENA_HALF_SIZE: f32 = 100.0;
+const CRATES: usize = 2_000;
+const RAMP_TRIANGLES: usize = 2_000;
+const PLAYERS: u32 = 64;
+const RAY_RANGE: f32 = 150.0;
+
+pub struct CannedScene {
+    pub geometry: WorldGeometry,
+    pub hitboxes: Vec<EntityHitbox>,
+    rng: Xoshiro256StarStar,
+}
+
+impl CannedScene {
+    pub fn generate() -> Self {
+        let mut rng = Xoshiro256StarStar::from_seed(SEED);
+        let mut aabbs = Vec::with_capacity(CRATES + 1);
+        // The floor.
+        aabbs.push(Aabb {
+            min: Vec3::new(-ARENA_HALF_SIZE, -1.0, -ARENA_HALF_SIZE),
+            max: Vec3::new(ARENA_HALF_SIZE, 0.0, ARENA_HALF_SIZE),
+        });
+        for _ in 0..CRATES {
+            let base = floor_point(&mut rng);
+            let size = Vec3::new(
+                uniform(&mut rng, 0.5, 4.0),
+                uniform(&mut rng, 0.5, 6.0),
+                uniform(&mut rng, 0.5, 4.0),
+            );
+            aabbs.push(Aabb {
+                min: base,
+                max: base + size,
+            });
+        }
+
+        let triangles = (0..RAMP_TRIANGLES)
+            .map(|_| {
+                let a = floor_point(&mut rng);
+                Triangle {
+                    a,
+                    b: a + Vec3::new(uniform(&mut rng, 1.0, 5.0), 0.0, 0.0),
+                    c: a + Vec3::new(
+                        0.0,
+                        uniform(&mut rng, 1.0, 3.0),
+                        uniform(&mut rng, 1.0, 5.0),
+                    ),
+                }
+            })
+            .collect();
+
+        let hitboxes = (0..PLAYERS)
+            .flat_map(|entity_id| {
+                let feet = floor_point(&mut rng);
+                [
+                    EntityHitbox {
+                        entity_id,
+                        min: feet + Vec3::new(-0.4, 0.0, -0.4),
+                        max: feet + Vec3::new(0.4, 1.5, 0.4),
+                        head: false,
+                    },
+                    EntityHitbox {
+                        entity_id,
+                        min: feet + Vec3::new(-0.2, 1.5, -0.2),
+                        max: feet + Vec3::new(0.2, 1.9, 0.2),
+                        head: true,
+                    },
+                ]
+            })
+            .collect();
+
+        Self {
+            geometry: WorldGeometry { aabbs, triangles },
+            hitboxes,
+            rng,
+        }
+    }
+
+    /// The next batch of shots: from eye height somewhere in the arena,
+    /// roughly level, in any direction.
+    pub fn next_rays(&mut self, count: usize) -> Vec<Ray> {
+        (0..count)
+            .map(|_| {
+                let origin = floor_point(&mut self.rng) + Vec3::new(0.0, 1.7, 0.0);
+                let yaw = uniform(&mut self.rng, 0.0, std::f32::consts::TAU);
+                let pitch = uniform(&mut self.rng, -0.3, 0.3);
+                Ray {
+                    origin,
+                    direction: Vec3::new(
+                        yaw.cos() * pitch.cos(),
+                        pitch.sin(),
+                        yaw.sin() * pitch.cos(),
+                    ),
+                    max_distance: Some(RAY_RANGE),
+                }
+            })
+            .collect()
+    }
+}
+
+fn uniform(rng: &mut Xoshiro256StarStar, min: f32, max: f32) -> f32 {
+    // 24 bits is all an f32 mantissa holds.
+    let unit = (rng.next_u32() >> 8) as f32 / (1u32 << 24) as f32;
+    min + (max - min) * unit
+}
+
+fn floor_point(rng: &mut Xoshiro256StarStar) -> Vec3 {
+    Vec3::new(
+        uniform(rng, -ARENA_HALF_SIZE, ARENA_HALF_SIZE),
+        0.0,
+        uniform(rng, -ARENA_HALF_SIZE, ARENA_HALF_SIZE),
+    )
+}
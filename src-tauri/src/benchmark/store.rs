@@ -0,0 +1,85 @@
+use super::model::{BenchmarkReport, BenchmarkSummary};
+use super::BenchmarkError;
+use crate::fs_atomic::write_atomic;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, Runtime};
+
+pub const DIR_NAME: &str = "benchmarks";
+pub const EXTENSION: &str = "json";
+
+pub fn benchmarks_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, BenchmarkError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| BenchmarkError::NoDataDir(e.to_string()))
+}
+
+/// Ids are `<startedAtMs>[-N]`, which also makes the filenames sort by time.
+pub fn report_path(dir: &Path, id: &str) -> Result<PathBuf, BenchmarkError> {
+    let valid =
+        !id.is_empty() && id.len() <= 32 && id.chars().all(|c| c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(BenchmarkError::NotFound(id.to_string()));
+    }
+    Ok(dir.join(format!("{}.{}", id, EXTENSION)))
+}
+
+/// A fresh id for a run started at `started_at_ms`, suffixed when another
+/// run already has that millisecond.
+pub fn new_id(dir: &Path, started_at_ms: u64) -> String {
+    let mut id = started_at_ms.to_string();
+    let mut n = 1;
+    while dir.join(format!("{}.{}", id, EXTENSION)).exists() {
+        id = format!("{}-{}", started_at_ms, n);
+        n += 1;
+    }
+    id
+}
+
+pub fn write(dir: &Path, report: &BenchmarkReport) -> Result<(), BenchmarkError> {
+    let path = report_path(dir, &report.id)?;
+    let json = serde_json::to_vec_pretty(report).expect("benchmark report serialize");
+    write_atomic(&path, &json).map_err(|e| BenchmarkError::io(&path, e))
+}
+
+pub fn read(dir: &Path, id: &str) -> Result<BenchmarkReport, BenchmarkError> {
+    let path = report_path(dir, id)?;
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(BenchmarkError::NotFound(id.to_string()))
+        }
+        Err(e) => return Err(BenchmarkError::io(&path, e)),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| BenchmarkError::Corrupted {
+        id: id.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Every readable report, newest first. Damaged ones are logged and left
+/// out; `load_benchmark_report` still says what's wrong with them.
+pub fn list(dir: &Path) -> Result<Vec<BenchmarkSummary>, BenchmarkError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(BenchmarkError::io(dir, e)),
+    };
+
+    let mut summaries = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        match read(dir, id) {
+            Ok(report) => summaries.push(BenchmarkSummary::from(&report)),
+            Err(e) => log::warn!("Skipping benchmark report {:?}: {}", id, e),
+        }
+    }
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.started_at_ms));
+    Ok(summaries)
+}
@@ -0,0 +1,64 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the crash report commands, serialized as
+/// `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum CrashError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No crash report with id {0:?}")]
+    NotFound(String),
+
+    #[error("Crash report {id:?} is damaged: {reason}")]
+    Corrupted { id: String, reason: String },
+
+    #[error("Invalid crash report endpoint {0:?}")]
+    InvalidEndpoint(String),
+
+    #[error("Submitting the crash report failed: {reason}")]
+    Submit { status: Option<u16>, reason: String },
+}
+
+impl CrashError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        CrashError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        CrashError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CrashError::NoDataDir(_) => "noDataDir",
+            CrashError::Io { .. } => "io",
+            CrashError::NotFound(_) => "notFound",
+            CrashError::Corrupted { .. } => "corrupted",
+            CrashError::InvalidEndpoint(_) => "invalidEndpoint",
+            CrashError::Submit { .. } => "submit",
+        }
+    }
+}
+
+impl Serialize for CrashError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("CrashError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,91 @@
+//! The panic hook. It runs on the panicking thread with the process in an
+//! unknown state, so it only uses what was prepared at startup, never waits
+//! on a lock and shrugs off its own failures.
+
+use super::model::CrashReport;
+use super::store;
+use crate::logging::LogTail;
+use crate::time::now_ms;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Log lines copied into each report.
+const RECENT_LOG_LINES: usize = 200;
+/// How long a second panicking thread holds off the abort while the first
+/// writes its report.
+const CONCURRENT_PANIC_WAIT: Duration = Duration::from_secs(2);
+
+struct Context {
+    dir: PathBuf,
+    logs: LogTail,
+}
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static CONTEXT: OnceLock<Context> = OnceLock::new();
+static OS_VERSION: OnceLock<Option<String>> = OnceLock::new();
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// Replaces the panic hook with one that writes a report, then runs the
+/// default hook and aborts. Panics before [`attach`] only get the default
+/// hook's output.
+pub fn install() {
+    let _ = STARTED.set(Instant::now());
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if REPORTING.swap(true, Ordering::SeqCst) {
+            std::thread::sleep(CONCURRENT_PANIC_WAIT);
+        } else if let Some(path) = write_report(info) {
+            eprintln!("Crash report written to {:?}", path);
+        }
+        previous(info);
+        std::process::abort();
+    }));
+}
+
+/// Gives the hook its directory, created now so the hook doesn't have to,
+/// and the log tail to copy from.
+pub fn attach(dir: PathBuf, logs: LogTail) {
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::warn!("Failed to create {:?}: {}", dir, e);
+    }
+    let _ = CONTEXT.set(Context { dir, logs });
+}
+
+pub fn set_os_version(version: Option<String>) {
+    let _ = OS_VERSION.set(version);
+}
+
+fn write_report(info: &PanicHookInfo) -> Option<PathBuf> {
+    let context = CONTEXT.get()?;
+    let timestamp_ms = now_ms();
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+    let report = CrashReport {
+        id: store::new_id(&context.dir, timestamp_ms),
+        timestamp_ms,
+        message,
+        location: info.location().map(|l| l.to_string()),
+        thread: std::thread::current().name().map(str::to_string),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        os_version: OS_VERSION.get().cloned().flatten(),
+        uptime_ms: STARTED
+            .get()
+            .map(|t| t.elapsed().as_millis() as u64)
+            .unwrap_or(0),
+        recent_logs: context.logs.lines(RECENT_LOG_LINES),
+        handled: false,
+        submission: None,
+    };
+    store::write(&context.dir, &report).ok()?;
+    store::report_path(&context.dir, &report.id).ok()
+}
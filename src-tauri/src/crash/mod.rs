@@ -0,0 +1,143 @@
+mod error;
+mod hook;
+mod model;
+mod store;
+
+pub use error::CrashError;
+pub use hook::install;
+pub use model::{CrashReport, CrashSummary, Submission};
+
+use crate::time::now_ms;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Runtime, State};
+
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Serializes updates to report files, which are read, changed and
+/// written back.
+#[derive(Default)]
+pub struct CrashState {
+    lock: Mutex<()>,
+}
+
+/// Points the panic hook at `AppData/crashes/` and the log tail. Called in
+/// setup, right after logging starts.
+pub fn init<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let dir = match store::crashes_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::error!("Crash reports are disabled: {}", e);
+            return;
+        }
+    };
+    hook::attach(dir, crate::logging::tail(app));
+    // The probe can take a moment; a crash before it finishes just goes
+    // without the OS version.
+    let app = app.clone();
+    let _ = std::thread::Builder::new()
+        .name("crash-os-probe".into())
+        .spawn(move || hook::set_os_version(crate::system::info(&app).os_version));
+}
+
+/// Every report, newest first, for backend modules.
+pub fn reports<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<Vec<CrashReport>, CrashError> {
+    store::list(&store::crashes_dir(app)?)
+}
+
+/// The newest report the player hasn't dealt with, meaning the previous
+/// session (or one before it) crashed, or `None`.
+#[tauri::command]
+pub async fn get_last_crash_report(
+    app: tauri::AppHandle,
+) -> Result<Option<CrashReport>, CrashError> {
+    let dir = store::crashes_dir(&app)?;
+    Ok(store::list(&dir)?.into_iter().find(|r| !r.handled))
+}
+
+/// Every report, newest first, without backtraces or logs.
+#[tauri::command]
+pub async fn list_crash_reports(app: tauri::AppHandle) -> Result<Vec<CrashSummary>, CrashError> {
+    let dir = store::crashes_dir(&app)?;
+    store::summaries(&dir)
+}
+
+/// Stops `get_last_crash_report` offering the report. It stays on disk and
+/// in the list.
+#[tauri::command]
+pub async fn mark_crash_report_handled(
+    app: tauri::AppHandle,
+    crashes: State<'_, CrashState>,
+    id: String,
+) -> Result<(), CrashError> {
+    let dir = store::crashes_dir(&app)?;
+    let _guard = crashes.lock.lock().unwrap();
+    let mut report = store::read(&dir, &id)?;
+    if !report.handled {
+        report.handled = true;
+        store::write(&dir, &report)?;
+    }
+    Ok(())
+}
+
+/// POSTs the report as JSON to `endpoint`, which the player opted into,
+/// and records the outcome in the report whether or not it got through.
+#[tauri::command]
+pub async fn submit_crash_report(
+    app: tauri::AppHandle,
+    crashes: State<'_, CrashState>,
+    id: String,
+    endpoint: String,
+) -> Result<Submission, CrashError> {
+    let url = reqwest::Url::parse(&endpoint)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or_else(|| CrashError::InvalidEndpoint(endpoint.clone()))?;
+    let dir = store::crashes_dir(&app)?;
+    let report = {
+        let _guard = crashes.lock.lock().unwrap();
+        store::read(&dir, &id)?
+    };
+
+    let result = post(url, &report).await;
+    let submission = Submission {
+        endpoint,
+        submitted_at_ms: now_ms(),
+        succeeded: result.is_ok(),
+        status: match &result {
+            Ok(status) => Some(*status),
+            Err(CrashError::Submit { status, .. }) => *status,
+            Err(_) => None,
+        },
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+
+    {
+        // Re-read so a handled mark made meanwhile isn't lost.
+        let _guard = crashes.lock.lock().unwrap();
+        let mut report = store::read(&dir, &id)?;
+        report.submission = Some(submission.clone());
+        store::write(&dir, &report)?;
+    }
+    result.map(|_| submission)
+}
+
+/// The response status on success.
+async fn post(url: reqwest::Url, report: &CrashReport) -> Result<u16, CrashError> {
+    let failed = |status: Option<u16>, reason: String| CrashError::Submit { status, reason };
+    let http = reqwest::Client::builder()
+        .timeout(SUBMIT_TIMEOUT)
+        .build()
+        .map_err(|e| failed(None, e.to_string()))?;
+    let response = http
+        .post(url)
+        .json(report)
+        .send()
+        .await
+        .map_err(|e| failed(None, e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(failed(Some(status.as_u16()), format!("HTTP {}", status)));
+    }
+    Ok(status.as_u16())
+}
@@ -0,0 +1,63 @@
+use crate::logging::LogLine;
+use serde::{Deserialize, Serialize};
+
+/// Everything known about a panic, written by the hook as it happens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub message: String,
+    /// `file:line:column` of the panic, when the payload had one.
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub app_version: String,
+    /// `linux x86_64`, `windows x86_64`...
+    pub os: String,
+    /// The distribution or Windows build, if the system probe finished
+    /// before the panic.
+    pub os_version: Option<String>,
+    pub uptime_ms: u64,
+    pub recent_logs: Vec<LogLine>,
+    /// Set once the player has seen it, so it isn't offered again.
+    #[serde(default)]
+    pub handled: bool,
+    #[serde(default)]
+    pub submission: Option<Submission>,
+}
+
+/// The outcome of the last [`super::submit_crash_report`] for a report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub endpoint: String,
+    pub submitted_at_ms: u64,
+    pub succeeded: bool,
+    /// The HTTP status, when the endpoint answered at all.
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// A report without its backtrace and logs, for listing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashSummary {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub message: String,
+    pub handled: bool,
+    pub submitted: bool,
+}
+
+impl From<&CrashReport> for CrashSummary {
+    fn from(report: &CrashReport) -> Self {
+        Self {
+            id: report.id.clone(),
+            timestamp_ms: report.timestamp_ms,
+            message: report.message.clone(),
+            handled: report.handled,
+            submitted: report.submission.as_ref().is_some_and(|s| s.succeeded),
+        }
+    }
+}
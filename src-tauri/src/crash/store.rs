@@ -0,0 +1,89 @@
+use super::model::{CrashReport, CrashSummary};
+use super::CrashError;
+use crate::fs_atomic::write_atomic;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, Runtime};
+
+pub const DIR_NAME: &str = "crashes";
+pub const EXTENSION: &str = "json";
+
+pub fn crashes_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, CrashError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| CrashError::NoDataDir(e.to_string()))
+}
+
+/// Ids are `<timestampMs>[-N]`, which also makes the filenames sort by time.
+pub fn report_path(dir: &Path, id: &str) -> Result<PathBuf, CrashError> {
+    let valid =
+        !id.is_empty() && id.len() <= 32 && id.chars().all(|c| c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(CrashError::NotFound(id.to_string()));
+    }
+    Ok(dir.join(format!("{}.{}", id, EXTENSION)))
+}
+
+/// A fresh id for a crash at `timestamp_ms`, suffixed when another
+/// report already has that millisecond.
+pub fn new_id(dir: &Path, timestamp_ms: u64) -> String {
+    let mut id = timestamp_ms.to_string();
+    let mut n = 1;
+    while dir.join(format!("{}.{}", id, EXTENSION)).exists() {
+        id = format!("{}-{}", timestamp_ms, n);
+        n += 1;
+    }
+    id
+}
+
+pub fn write(dir: &Path, report: &CrashReport) -> Result<(), CrashError> {
+    let path = report_path(dir, &report.id)?;
+    let json = serde_json::to_vec_pretty(report).map_err(|e| CrashError::task(&path, e))?;
+    write_atomic(&path, &json).map_err(|e| CrashError::io(&path, e))
+}
+
+pub fn read(dir: &Path, id: &str) -> Result<CrashReport, CrashError> {
+    let path = report_path(dir, id)?;
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CrashError::NotFound(id.to_string()))
+        }
+        Err(e) => return Err(CrashError::io(&path, e)),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| CrashError::Corrupted {
+        id: id.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Every readable report, newest first. Damaged ones are logged and left
+/// out.
+pub fn list(dir: &Path) -> Result<Vec<CrashReport>, CrashError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CrashError::io(dir, e)),
+    };
+
+    let mut reports = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        match read(dir, id) {
+            Ok(report) => reports.push(report),
+            Err(e) => log::warn!("Skipping crash report {:?}: {}", id, e),
+        }
+    }
+    reports.sort_by_key(|r| std::cmp::Reverse(r.timestamp_ms));
+    Ok(reports)
+}
+
+pub fn summaries(dir: &Path) -> Result<Vec<CrashSummary>, CrashError> {
+    Ok(list(dir)?.iter().map(CrashSummary::from).collect())
+}
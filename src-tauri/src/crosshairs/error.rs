@@ -0,0 +1,80 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the crosshair commands, serialized as `{ kind, message }`.
+/// Messages name the problem plainly, since they're shown to players as is.
+#[derive(Debug, thiserror::Error)]
+pub enum CrosshairError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid crosshair id {0:?}")]
+    InvalidId(String),
+
+    #[error("No crosshair named {0:?}")]
+    NotFound(String),
+
+    #[error("The file is {size} bytes; crosshair images can be at most {max} bytes")]
+    FileTooLarge { size: u64, max: u64 },
+
+    #[error("The file is not a PNG image; only PNG crosshairs are supported")]
+    NotPng,
+
+    #[error("The PNG file is damaged and could not be read: {0}")]
+    Corrupt(String),
+
+    #[error("The image has no transparency; crosshairs need a transparent background")]
+    NoAlpha,
+
+    #[error("The image is {width} x {height}; crosshairs can be at most {max} x {max}")]
+    TooBig { width: u32, height: u32, max: u32 },
+
+    #[error("Could not save the crosshair choice: {0}")]
+    Settings(String),
+}
+
+impl CrosshairError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        CrosshairError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        CrosshairError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CrosshairError::NoDataDir(_) => "noDataDir",
+            CrosshairError::Io { .. } => "io",
+            CrosshairError::InvalidId(_) => "invalidId",
+            CrosshairError::NotFound(_) => "notFound",
+            CrosshairError::FileTooLarge { .. } => "fileTooLarge",
+            CrosshairError::NotPng => "notPng",
+            CrosshairError::Corrupt(_) => "corrupt",
+            CrosshairError::NoAlpha => "noAlpha",
+            CrosshairError::TooBig { .. } => "tooBig",
+            CrosshairError::Settings(_) => "settings",
+        }
+    }
+}
+
+impl Serialize for CrosshairError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("CrosshairError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,128 @@
+//! Crosshair PNG checking and normalizing, with the `png` crate only.
+
+use super::CrosshairError;
+use std::io::Cursor;
+
+pub const MAX_SIZE: u32 = 256;
+/// Largest source image accepted for downscaling.
+const MAX_SOURCE_SIZE: u32 = 4096;
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// 8-bit RGBA, straight alpha.
+pub struct Rgba {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Decodes a PNG that has transparency, whatever its color type: grey
+/// and palette images are expanded and 16-bit ones narrowed.
+pub fn decode(bytes: &[u8]) -> Result<Rgba, CrosshairError> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(CrosshairError::NotPng);
+    }
+    let corrupt = |e: png::DecodingError| CrosshairError::Corrupt(e.to_string());
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(corrupt)?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    if width > MAX_SOURCE_SIZE || height > MAX_SOURCE_SIZE {
+        return Err(CrosshairError::TooBig {
+            width,
+            height,
+            max: MAX_SIZE,
+        });
+    }
+    // After expansion a `tRNS` chunk shows up as an alpha channel too.
+    let (color, _) = reader.output_color_type();
+    if !matches!(color, png::ColorType::Rgba | png::ColorType::GrayscaleAlpha) {
+        return Err(CrosshairError::NoAlpha);
+    }
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).map_err(corrupt)?;
+    reader.finish().map_err(corrupt)?;
+
+    let channels = frame.color_type.samples();
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in buf.chunks_exact(frame.line_size).take(height as usize) {
+        for px in row[..width as usize * channels].chunks_exact(channels) {
+            match channels {
+                2 => pixels.extend_from_slice(&[px[0], px[0], px[0], px[1]]),
+                _ => pixels.extend_from_slice(&px[..4]),
+            }
+        }
+    }
+    // An alpha channel that's opaque everywhere would draw a solid block.
+    if pixels.chunks_exact(4).all(|px| px[3] == u8::MAX) {
+        return Err(CrosshairError::NoAlpha);
+    }
+    Ok(Rgba {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Averages `image` down to fit `MAX_SIZE` square, keeping the aspect
+/// ratio. Colors are weighted by alpha so transparent pixels don't bleed
+/// dark fringes into the edges.
+pub fn downscale(image: &Rgba) -> Rgba {
+    let scale = (MAX_SIZE as f64 / image.width.max(image.height) as f64).min(1.0);
+    let width = ((image.width as f64 * scale).round() as u32).max(1);
+    let height = ((image.height as f64 * scale).round() as u32).max(1);
+
+    let span = |i: u32, out: u32, src: u32| {
+        let start = (i as u64 * src as u64 / out as u64) as usize;
+        let end = ((i as u64 + 1) * src as u64 / out as u64) as usize;
+        start..end.max(start + 1)
+    };
+    let stride = image.width as usize * 4;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        let rows = span(y, height, image.height);
+        for x in 0..width {
+            let cols = span(x, width, image.width);
+            let mut sum = [0u64; 4];
+            for row in rows.clone() {
+                let line = &image.pixels[row * stride..(row + 1) * stride];
+                for px in line[cols.start * 4..cols.end * 4].chunks_exact(4) {
+                    let a = px[3] as u64;
+                    for (s, v) in sum.iter_mut().zip(&px[..3]) {
+                        *s += *v as u64 * a;
+                    }
+                    sum[3] += a;
+                }
+            }
+            let count = (rows.len() * cols.len()) as u64;
+            let alpha = sum[3];
+            for s in &sum[..3] {
+                pixels.push((s + alpha / 2).checked_div(alpha).unwrap_or(0) as u8);
+            }
+            pixels.push(((alpha + count / 2) / count) as u8);
+        }
+    }
+    Rgba {
+        width,
+        height,
+        pixels,
+    }
+}
+
+pub fn encode(image: &Rgba) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&image.pixels)
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Width and height from the header, for listing without decoding.
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let reader = png::Decoder::new(Cursor::new(bytes)).read_info().ok()?;
+    Some((reader.info().width, reader.info().height))
+}
@@ -0,0 +1,274 @@
+mod error;
+mod image;
+
+pub use error::CrosshairError;
+
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+const DIR_NAME: &str = "crosshairs";
+const EXTENSION: &str = "png";
+/// A 4096 x 4096 RGBA PNG that compresses at all fits comfortably.
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+const MAX_ID_LEN: usize = 48;
+const FALLBACK_ID: &str = "crosshair";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedCrosshair {
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    /// The image was larger than 256 x 256 and was scaled to fit.
+    pub downscaled: bool,
+    /// The same image was already imported; `id` is the existing copy.
+    pub already_imported: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrosshairInfo {
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    pub size_bytes: u64,
+    /// A `data:image/png;base64,` URL, ready for an `<img>`.
+    pub preview: String,
+    pub selected: bool,
+}
+
+pub fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+fn crosshairs_dir(app: &tauri::AppHandle) -> Result<PathBuf, CrosshairError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| CrosshairError::NoDataDir(e.to_string()))
+}
+
+fn crosshair_path(dir: &Path, id: &str) -> Result<PathBuf, CrosshairError> {
+    if !valid_id(id) {
+        return Err(CrosshairError::InvalidId(id.to_string()));
+    }
+    Ok(dir.join(format!("{}.{}", id, EXTENSION)))
+}
+
+/// An id from the picked file's name: lowercased, runs of anything but
+/// letters, digits, `-` and `_` turned into one `-`.
+fn sanitize_id(source: &Path) -> String {
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut id = String::new();
+    for c in stem.chars() {
+        if c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-' {
+            id.push(c);
+        } else if !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    let mut id = id.trim_matches('-').to_string();
+    id.truncate(MAX_ID_LEN - 4);
+    let id = id.trim_end_matches('-');
+    if id.is_empty() {
+        FALLBACK_ID.to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Every stored crosshair as `(id, path)`, sorted by id.
+fn stored(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(String, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_str()?.to_string();
+            valid_id(&id).then_some((id, path))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+fn selected(app: &tauri::AppHandle) -> Option<String> {
+    crate::settings::current(app).ok()?.crosshair
+}
+
+fn import(dir: &Path, source: &Path, downscale: bool) -> Result<ImportedCrosshair, CrosshairError> {
+    let size = std::fs::metadata(source)
+        .map_err(|e| CrosshairError::io(source, e))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err(CrosshairError::FileTooLarge {
+            size,
+            max: MAX_FILE_BYTES,
+        });
+    }
+    let bytes = std::fs::read(source).map_err(|e| CrosshairError::io(source, e))?;
+    let mut decoded = image::decode(&bytes)?;
+    let oversized = decoded.width > image::MAX_SIZE || decoded.height > image::MAX_SIZE;
+    if oversized && !downscale {
+        return Err(CrosshairError::TooBig {
+            width: decoded.width,
+            height: decoded.height,
+            max: image::MAX_SIZE,
+        });
+    }
+    if oversized {
+        decoded = image::downscale(&decoded);
+    }
+    // Re-encoding drops whatever else was in the file, and makes the
+    // same picture give the same bytes for the duplicate check.
+    let normalized = image::encode(&decoded).map_err(|e| CrosshairError::task(source, e))?;
+
+    let hash = sha256_hex(&normalized);
+    let existing = stored(dir);
+    let imported = |id: String, already_imported| ImportedCrosshair {
+        id,
+        width: decoded.width,
+        height: decoded.height,
+        downscaled: oversized,
+        already_imported,
+    };
+    for (id, path) in &existing {
+        if crate::assets::hash_file(path).is_ok_and(|(h, _)| h == hash) {
+            return Ok(imported(id.clone(), true));
+        }
+    }
+
+    let base = sanitize_id(source);
+    let taken = |id: &str| existing.iter().any(|(other, _)| other == id);
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken(&id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    let path = crosshair_path(dir, &id)?;
+    crate::fs_atomic::write_atomic(&path, &normalized).map_err(|e| CrosshairError::io(&path, e))?;
+    log::info!(
+        "Imported crosshair {} ({} x {}) from {:?}",
+        id,
+        decoded.width,
+        decoded.height,
+        source
+    );
+    Ok(imported(id, false))
+}
+
+/// Imports a crosshair PNG the player picked. It must have transparency
+/// and be at most 256 x 256; pass `downscale` to shrink larger images to
+/// fit instead of refusing them. The image is re-encoded into
+/// `AppData/crosshairs/` under an id made from the file name. Importing a
+/// picture that's already there returns the existing id.
+#[tauri::command]
+pub async fn import_crosshair(
+    app: tauri::AppHandle,
+    source_path: String,
+    downscale: Option<bool>,
+) -> Result<ImportedCrosshair, CrosshairError> {
+    let dir = crosshairs_dir(&app)?;
+    let source = PathBuf::from(source_path);
+    let task_source = source.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        import(&dir, &task_source, downscale.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| CrosshairError::task(&source, e))?
+}
+
+/// Every imported crosshair, by id, with a preview.
+#[tauri::command]
+pub async fn list_crosshairs(app: tauri::AppHandle) -> Result<Vec<CrosshairInfo>, CrosshairError> {
+    let dir = crosshairs_dir(&app)?;
+    let selected = selected(&app);
+    let mut out = Vec::new();
+    for (id, path) in stored(&dir) {
+        let bytes = std::fs::read(&path).map_err(|e| CrosshairError::io(&path, e))?;
+        let Some((width, height)) = image::dimensions(&bytes) else {
+            log::warn!("Skipping unreadable crosshair {:?}", path);
+            continue;
+        };
+        out.push(CrosshairInfo {
+            selected: selected.as_deref() == Some(id.as_str()),
+            id,
+            width,
+            height,
+            size_bytes: bytes.len() as u64,
+            preview: format!(
+                "data:image/png;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(&bytes)
+            ),
+        });
+    }
+    Ok(out)
+}
+
+/// The PNG bytes of crosshair `id`, for the renderer.
+#[tauri::command]
+pub async fn get_crosshair(app: tauri::AppHandle, id: String) -> Result<Vec<u8>, CrosshairError> {
+    let path = crosshair_path(&crosshairs_dir(&app)?, &id)?;
+    match std::fs::read(&path) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(CrosshairError::NotFound(id)),
+        Err(e) => Err(CrosshairError::io(&path, e)),
+    }
+}
+
+/// Deletes crosshair `id`, going back to the built-in one if it was
+/// selected.
+#[tauri::command]
+pub async fn delete_crosshair(app: tauri::AppHandle, id: String) -> Result<(), CrosshairError> {
+    let path = crosshair_path(&crosshairs_dir(&app)?, &id)?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CrosshairError::NotFound(id))
+        }
+        Err(e) => return Err(CrosshairError::io(&path, e)),
+    }
+    if selected(&app).as_deref() == Some(id.as_str()) {
+        crate::settings::update(&app, |settings| settings.crosshair = None)
+            .map_err(|e| CrosshairError::Settings(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Selects crosshair `id`, or the built-in one for `None`, saving the
+/// choice in settings.
+#[tauri::command]
+pub async fn set_crosshair(
+    app: tauri::AppHandle,
+    id: Option<String>,
+) -> Result<(), CrosshairError> {
+    if let Some(id) = &id {
+        if !crosshair_path(&crosshairs_dir(&app)?, id)?.is_file() {
+            return Err(CrosshairError::NotFound(id.clone()));
+        }
+    }
+    crate::settings::update(&app, |settings| settings.crosshair = id)
+        .map_err(|e| CrosshairError::Settings(e.to_string()))?;
+    Ok(())
+}
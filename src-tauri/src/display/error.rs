@@ -0,0 +1,48 @@
+use crate::settings::SettingsError;
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the display commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum DisplayError {
+    #[error("No monitor is connected")]
+    NoMonitor,
+
+    #[error("Confirm timeout {secs} s is outside {min}..={max}")]
+    InvalidTimeout { secs: u64, min: u64, max: u64 },
+
+    #[error("No display mode is waiting to be confirmed")]
+    NothingToConfirm,
+
+    #[error("Failed to change the window: {0}")]
+    Window(#[source] tauri::Error),
+
+    #[error("Failed to save the display mode: {0}")]
+    Settings(#[source] SettingsError),
+
+    #[error("Failed to start the revert timer: {0}")]
+    Spawn(#[source] std::io::Error),
+}
+
+impl DisplayError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DisplayError::NoMonitor => "noMonitor",
+            DisplayError::InvalidTimeout { .. } => "invalidTimeout",
+            DisplayError::NothingToConfirm => "nothingToConfirm",
+            DisplayError::Window(_) => "window",
+            DisplayError::Settings(_) => "settings",
+            DisplayError::Spawn(_) => "spawn",
+        }
+    }
+}
+
+impl Serialize for DisplayError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("DisplayError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
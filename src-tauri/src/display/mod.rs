@@ -0,0 +1,346 @@
+mod error;
+mod mode;
+mod monitors;
+#[cfg(windows)]
+mod windows;
+
+pub use error::DisplayError;
+pub use mode::DisplayMode;
+pub use monitors::MonitorInfo;
+
+use serde::Serialize;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, Runtime, State};
+
+pub const CHANGED_EVENT: &str = "display-changed";
+pub const REVERTED_EVENT: &str = "display-mode-reverted";
+
+/// The window that restoring a saved mode and monitor fallback act on.
+const MAIN_WINDOW: &str = "main";
+const MIN_CONFIRM_SECS: u64 = 1;
+const MAX_CONFIRM_SECS: u64 = 120;
+/// Tauri has no monitor hotplug event, so the monitor list is polled.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+/// How long shutdown waits for the watcher. A fallback in progress waits on
+/// the event loop, which is busy running the exit handler.
+const WATCHER_STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct DisplayState {
+    inner: Mutex<Inner>,
+    watcher: Mutex<Option<Watcher>>,
+}
+
+/// The monitor polling thread.
+struct Watcher {
+    /// Dropped to stop the thread.
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Watcher {
+    fn stop(self) {
+        drop(self.stop);
+        let deadline = Instant::now() + WATCHER_STOP_TIMEOUT;
+        while !self.thread.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        if self.thread.is_finished() {
+            let _ = self.thread.join();
+        } else {
+            log::warn!("The monitor watcher didn't stop in time; leaving it");
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    /// The last mode the game put a window into, by window label.
+    applied: Option<(String, DisplayMode)>,
+    pending: Option<Pending>,
+    next_pending_id: u64,
+}
+
+/// A mode on trial, reverted unless confirmed.
+struct Pending {
+    id: u64,
+    label: String,
+    mode: DisplayMode,
+    /// What to go back to. Stays the original mode when a second trial
+    /// replaces the first.
+    previous: DisplayMode,
+    /// Dropped to stop the revert timer.
+    _cancel: mpsc::Sender<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayChanged {
+    pub monitors: Vec<MonitorInfo>,
+    pub mode: DisplayMode,
+    /// Whether the window's monitor went away and it was moved to the
+    /// primary one.
+    pub fell_back: bool,
+}
+
+/// Re-applies the display mode saved in settings to the main window and
+/// starts watching for monitors coming and going. Failure leaves the
+/// window as configured and is only logged.
+pub fn restore(app: &tauri::AppHandle) {
+    start_watcher(app);
+    let mode = match crate::settings::current(app) {
+        Ok(settings) => settings.video.display,
+        Err(e) => {
+            log::warn!("Failed to read the saved display mode: {}", e);
+            return;
+        }
+    };
+    let (Some(mode), Some(window)) = (mode, app.get_webview_window(MAIN_WINDOW)) else {
+        return;
+    };
+    match mode::apply(&window, &mode) {
+        Ok(_) => {
+            app.state::<DisplayState>().inner.lock().unwrap().applied =
+                Some((window.label().to_string(), mode))
+        }
+        Err(e) => log::warn!("Failed to restore the display mode: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn list_monitors(app: tauri::AppHandle) -> Result<Vec<MonitorInfo>, DisplayError> {
+    monitors::list(&app)
+}
+
+#[tauri::command]
+pub async fn get_current_display_mode(
+    window: tauri::WebviewWindow,
+) -> Result<DisplayMode, DisplayError> {
+    mode::current(&window)
+}
+
+/// Applies `mode` to the calling window and saves it to settings. Replaces
+/// any mode awaiting confirmation. Returns the mode the window ended up in.
+#[tauri::command]
+pub async fn apply_display_mode(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    display: State<'_, DisplayState>,
+    mode: DisplayMode,
+) -> Result<DisplayMode, DisplayError> {
+    let mode = mode.sanitized();
+    display.inner.lock().unwrap().pending = None;
+    mode::apply(&window, &mode)?;
+    display.inner.lock().unwrap().applied = Some((window.label().to_string(), mode.clone()));
+    save(&app, mode)?;
+    mode::current(&window)
+}
+
+/// Applies `mode` to the calling window on trial: unless
+/// `confirm_display_mode` is called within `timeout_secs`, the window
+/// goes back to its previous mode and `display-mode-reverted` is emitted.
+/// Nothing is saved until confirmed.
+#[tauri::command]
+pub async fn apply_display_mode_with_confirm(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    display: State<'_, DisplayState>,
+    mode: DisplayMode,
+    timeout_secs: u64,
+) -> Result<DisplayMode, DisplayError> {
+    if !(MIN_CONFIRM_SECS..=MAX_CONFIRM_SECS).contains(&timeout_secs) {
+        return Err(DisplayError::InvalidTimeout {
+            secs: timeout_secs,
+            min: MIN_CONFIRM_SECS,
+            max: MAX_CONFIRM_SECS,
+        });
+    }
+    let mode = mode.sanitized();
+    let label = window.label().to_string();
+    let earlier = display.inner.lock().unwrap().pending.take();
+    let previous = match earlier {
+        Some(pending) if pending.label == label => pending.previous,
+        _ => mode::current(&window)?,
+    };
+
+    if let Err(e) = mode::apply(&window, &mode) {
+        let _ = mode::apply(&window, &previous);
+        return Err(e);
+    }
+
+    let (cancel, cancelled) = mpsc::channel();
+    let mut inner = display.inner.lock().unwrap();
+    inner.next_pending_id += 1;
+    let id = inner.next_pending_id;
+    let timeout = Duration::from_secs(timeout_secs);
+    let timer = std::thread::Builder::new()
+        .name("display-revert".into())
+        .spawn(move || revert_after(&app, id, timeout, cancelled));
+    if let Err(e) = timer {
+        drop(inner);
+        // Without a timer the trial could never end on its own.
+        let _ = mode::apply(&window, &previous);
+        return Err(DisplayError::Spawn(e));
+    }
+    inner.applied = Some((label.clone(), mode.clone()));
+    inner.pending = Some(Pending {
+        id,
+        label,
+        mode,
+        previous,
+        _cancel: cancel,
+    });
+    drop(inner);
+    mode::current(&window)
+}
+
+/// Keeps the mode on trial and saves it to settings.
+#[tauri::command]
+pub async fn confirm_display_mode(
+    app: tauri::AppHandle,
+    display: State<'_, DisplayState>,
+) -> Result<DisplayMode, DisplayError> {
+    let pending = display.inner.lock().unwrap().pending.take();
+    let pending = pending.ok_or(DisplayError::NothingToConfirm)?;
+    save(&app, pending.mode.clone())?;
+    Ok(pending.mode)
+}
+
+fn save(app: &tauri::AppHandle, mode: DisplayMode) -> Result<(), DisplayError> {
+    crate::settings::update(app, |settings| settings.video.display = Some(mode))
+        .map(|_| ())
+        .map_err(DisplayError::Settings)
+}
+
+fn revert_after<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    id: u64,
+    timeout: Duration,
+    cancelled: mpsc::Receiver<()>,
+) {
+    // Confirming or replacing the trial drops the sender.
+    if cancelled.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+        return;
+    }
+    let display = app.state::<DisplayState>();
+    let pending = display
+        .inner
+        .lock()
+        .unwrap()
+        .pending
+        .take_if(|p| p.id == id);
+    let Some(pending) = pending else {
+        return;
+    };
+    let Some(window) = app.get_webview_window(&pending.label) else {
+        return;
+    };
+    if let Err(e) = mode::apply(&window, &pending.previous) {
+        log::error!("Failed to revert the display mode: {}", e);
+        return;
+    }
+    display.inner.lock().unwrap().applied = Some((pending.label, pending.previous.clone()));
+    let _ = app.emit(REVERTED_EVENT, pending.previous);
+}
+
+fn start_watcher<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let handle = app.clone();
+    let mut known = monitors::list(app).unwrap_or_default();
+    let (stop, stopped) = mpsc::channel::<()>();
+    let spawned = std::thread::Builder::new()
+        .name("monitor-watcher".into())
+        .spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(WATCH_INTERVAL) {
+                let Ok(now) = monitors::list(&handle) else {
+                    continue;
+                };
+                if now != known {
+                    on_monitors_changed(&handle, &known, &now);
+                    known = now;
+                }
+            }
+        });
+    match spawned {
+        Ok(thread) => {
+            let watcher = Watcher { stop, thread };
+            let previous = app
+                .state::<DisplayState>()
+                .watcher
+                .lock()
+                .unwrap()
+                .replace(watcher);
+            if let Some(previous) = previous {
+                previous.stop();
+            }
+        }
+        Err(e) => log::warn!("Failed to start the monitor watcher: {}", e),
+    }
+}
+
+/// Stops the monitor watcher.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let watcher = app.state::<DisplayState>().watcher.lock().unwrap().take();
+    if let Some(watcher) = watcher {
+        watcher.stop();
+    }
+}
+
+/// Re-applies the current mode when the monitor it's on just went away,
+/// which lands it on the primary, then reports the new layout.
+fn on_monitors_changed<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    before: &[MonitorInfo],
+    after: &[MonitorInfo],
+) {
+    let applied = app
+        .state::<DisplayState>()
+        .inner
+        .lock()
+        .unwrap()
+        .applied
+        .clone();
+    let label = match &applied {
+        Some((label, _)) => label.as_str(),
+        None => MAIN_WINDOW,
+    };
+    let Some(window) = app.get_webview_window(label) else {
+        return;
+    };
+
+    let fell_back = match &applied {
+        Some((_, mode)) if lost(mode, before, after) => {
+            if let Err(e) = mode::apply(&window, mode) {
+                log::warn!("Failed to move the window off a lost monitor: {}", e);
+            }
+            true
+        }
+        _ => false,
+    };
+
+    let Ok(mode) = mode::current(&window) else {
+        return;
+    };
+    let _ = app.emit(
+        CHANGED_EVENT,
+        DisplayChanged {
+            monitors: after.to_vec(),
+            mode,
+            fell_back,
+        },
+    );
+}
+
+/// Whether the monitor `mode` targets was connected before and isn't now.
+/// For the primary, whether a different monitor became primary.
+fn lost(mode: &DisplayMode, before: &[MonitorInfo], after: &[MonitorInfo]) -> bool {
+    let has =
+        |list: &[MonitorInfo], name: &String| list.iter().any(|m| m.name.as_ref() == Some(name));
+    let primary = |list: &[MonitorInfo]| list.iter().find(|m| m.primary).map(|m| m.name.clone());
+    match &mode.monitor {
+        Some(name) => has(before, name) && !has(after, name),
+        None => primary(before) != primary(after),
+    }
+}
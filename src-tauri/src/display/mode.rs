@@ -0,0 +1,155 @@
+//! Display modes and putting a window into one.
+
+use super::DisplayError;
+use serde::{Deserialize, Serialize};
+use tauri::{Monitor, PhysicalPosition, PhysicalSize, Runtime, WebviewWindow};
+
+const MIN_WIDTH: u32 = 640;
+const MIN_HEIGHT: u32 = 480;
+const MAX_WIDTH: u32 = 7680;
+const MAX_HEIGHT: u32 = 4320;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowMode {
+    Windowed,
+    /// A borderless window covering the whole monitor.
+    Borderless,
+    /// The platform's own fullscreen. The webview can't take exclusive
+    /// control of the display, so this is as close as it gets.
+    Fullscreen,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DisplayMode {
+    pub window_mode: WindowMode,
+    /// Monitor name from `list_monitors`. `None`, or a monitor that isn't
+    /// connected, means the primary one.
+    pub monitor: Option<String>,
+    /// Inner window size in physical pixels. Only used when windowed; the
+    /// other modes fill the monitor.
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for DisplayMode {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowMode::Windowed,
+            monitor: None,
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+impl DisplayMode {
+    pub fn sanitized(mut self) -> Self {
+        self.width = self.width.clamp(MIN_WIDTH, MAX_WIDTH);
+        self.height = self.height.clamp(MIN_HEIGHT, MAX_HEIGHT);
+        self.monitor = self
+            .monitor
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        self
+    }
+}
+
+/// Reads back the mode `window` is in now.
+pub fn current<R: Runtime>(window: &WebviewWindow<R>) -> Result<DisplayMode, DisplayError> {
+    let window_mode = if window.is_fullscreen().map_err(DisplayError::Window)? {
+        WindowMode::Fullscreen
+    } else if !window.is_decorated().map_err(DisplayError::Window)? {
+        WindowMode::Borderless
+    } else {
+        WindowMode::Windowed
+    };
+    let size = window.inner_size().map_err(DisplayError::Window)?;
+    let monitor = window
+        .current_monitor()
+        .map_err(DisplayError::Window)?
+        .and_then(|m| m.name().cloned());
+    Ok(DisplayMode {
+        window_mode,
+        monitor,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+/// Puts `window` into `mode` and returns the monitor it went to, which is
+/// the primary when the requested one isn't connected.
+pub fn apply<R: Runtime>(
+    window: &WebviewWindow<R>,
+    mode: &DisplayMode,
+) -> Result<Monitor, DisplayError> {
+    let monitor = target_monitor(window, mode.monitor.as_deref())?;
+    let area = monitor.work_area();
+
+    // Leave the old mode first; resizing a fullscreen or maximized window
+    // is ignored on some platforms.
+    if window.is_fullscreen().map_err(DisplayError::Window)? {
+        window.set_fullscreen(false).map_err(DisplayError::Window)?;
+    }
+    if window.is_maximized().map_err(DisplayError::Window)? {
+        window.unmaximize().map_err(DisplayError::Window)?;
+    }
+
+    match mode.window_mode {
+        WindowMode::Windowed => {
+            let size = PhysicalSize::new(
+                mode.width.min(area.size.width),
+                mode.height.min(area.size.height),
+            );
+            let position = PhysicalPosition::new(
+                area.position.x + (area.size.width - size.width) as i32 / 2,
+                area.position.y + (area.size.height - size.height) as i32 / 2,
+            );
+            window.set_decorations(true).map_err(DisplayError::Window)?;
+            window.set_size(size).map_err(DisplayError::Window)?;
+            window
+                .set_position(position)
+                .map_err(DisplayError::Window)?;
+        }
+        WindowMode::Borderless => {
+            window
+                .set_decorations(false)
+                .map_err(DisplayError::Window)?;
+            window
+                .set_position(*monitor.position())
+                .map_err(DisplayError::Window)?;
+            window
+                .set_size(*monitor.size())
+                .map_err(DisplayError::Window)?;
+        }
+        WindowMode::Fullscreen => {
+            // Fullscreen takes over whichever monitor the window is on.
+            window.set_decorations(true).map_err(DisplayError::Window)?;
+            window
+                .set_position(area.position)
+                .map_err(DisplayError::Window)?;
+            window.set_fullscreen(true).map_err(DisplayError::Window)?;
+        }
+    }
+    Ok(monitor)
+}
+
+/// The monitor named `name`, falling back to the primary, then any.
+fn target_monitor<R: Runtime>(
+    window: &WebviewWindow<R>,
+    name: Option<&str>,
+) -> Result<Monitor, DisplayError> {
+    let monitors = window.available_monitors().map_err(DisplayError::Window)?;
+    if let Some(found) = name.and_then(|name| {
+        monitors
+            .iter()
+            .find(|m| m.name().map(String::as_str) == Some(name))
+    }) {
+        return Ok(found.clone());
+    }
+    if let Some(primary) = window.primary_monitor().map_err(DisplayError::Window)? {
+        return Ok(primary);
+    }
+    monitors.into_iter().next().ok_or(DisplayError::NoMonitor)
+}
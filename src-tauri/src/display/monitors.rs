@@ -0,0 +1,55 @@
+use super::DisplayError;
+use serde::Serialize;
+use tauri::{Monitor, PhysicalPosition, PhysicalSize, Runtime};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    /// What `DisplayMode::monitor` refers to it by. Not always friendly: on
+    /// Windows it's the device name, like `\\.\DISPLAY1`.
+    pub name: Option<String>,
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+    /// Refresh rates in Hz the monitor offers at its current resolution,
+    /// ascending. Empty where the platform doesn't say.
+    pub refresh_rates: Vec<u32>,
+    pub primary: bool,
+}
+
+/// Every connected monitor, primary first.
+pub fn list<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<Vec<MonitorInfo>, DisplayError> {
+    let primary = app.primary_monitor().map_err(DisplayError::Window)?;
+    let mut monitors: Vec<MonitorInfo> = app
+        .available_monitors()
+        .map_err(DisplayError::Window)?
+        .iter()
+        .map(|m| MonitorInfo {
+            name: m.name().cloned(),
+            position: *m.position(),
+            size: *m.size(),
+            scale_factor: m.scale_factor(),
+            refresh_rates: m
+                .name()
+                .map(|name| refresh_rates(name, m.size()))
+                .unwrap_or_default(),
+            primary: primary.as_ref().is_some_and(|p| same(p, m)),
+        })
+        .collect();
+    monitors.sort_by_key(|m| !m.primary);
+    Ok(monitors)
+}
+
+fn same(a: &Monitor, b: &Monitor) -> bool {
+    a.name() == b.name() && a.position() == b.position()
+}
+
+#[cfg(windows)]
+fn refresh_rates(name: &str, size: &PhysicalSize<u32>) -> Vec<u32> {
+    super::windows::refresh_rates(name, size.width, size.height)
+}
+
+#[cfg(not(windows))]
+fn refresh_rates(_name: &str, _size: &PhysicalSize<u32>) -> Vec<u32> {
+    Vec::new()
+}
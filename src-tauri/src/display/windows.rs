@@ -0,0 +1,64 @@
+//! Refresh rates from the display driver's mode list, which Tauri doesn't
+//! expose.
+
+#[repr(C)]
+struct DevModeW {
+    device_name: [u16; 32],
+    spec_version: u16,
+    driver_version: u16,
+    size: u16,
+    driver_extra: u16,
+    fields: u32,
+    /// The display half of a union with printer settings.
+    position: [i32; 2],
+    display_orientation: u32,
+    display_fixed_output: u32,
+    color: i16,
+    duplex: i16,
+    y_resolution: i16,
+    tt_option: i16,
+    collate: i16,
+    form_name: [u16; 32],
+    log_pixels: u16,
+    bits_per_pel: u32,
+    pels_width: u32,
+    pels_height: u32,
+    display_flags: u32,
+    display_frequency: u32,
+    icm_method: u32,
+    icm_intent: u32,
+    media_type: u32,
+    dither_type: u32,
+    reserved1: u32,
+    reserved2: u32,
+    panning_width: u32,
+    panning_height: u32,
+}
+
+#[link(name = "user32")]
+extern "system" {
+    fn EnumDisplaySettingsW(device_name: *const u16, mode_num: u32, mode: *mut DevModeW) -> i32;
+}
+
+/// Distinct rates among the adapter's modes at `width`x`height`. Rates of
+/// 0 and 1 mean "hardware default" and are left out.
+pub fn refresh_rates(device: &str, width: u32, height: u32) -> Vec<u32> {
+    let device: Vec<u16> = device.encode_utf16().chain(Some(0)).collect();
+    let mut rates = Vec::new();
+    for i in 0.. {
+        // SAFETY: all-zero is a valid DEVMODEW, and `size` tells the call
+        // which version of the struct it's writing.
+        let mut mode: DevModeW = unsafe { std::mem::zeroed() };
+        mode.size = std::mem::size_of::<DevModeW>() as u16;
+        // SAFETY: `device` is NUL-terminated and `mode` is writable.
+        if unsafe { EnumDisplaySettingsW(device.as_ptr(), i, &mut mode) } == 0 {
+            break;
+        }
+        if mode.pels_width == width && mode.pels_height == height && mode.display_frequency > 1 {
+            rates.push(mode.display_frequency);
+        }
+    }
+    rates.sort_unstable();
+    rates.dedup();
+    rates
+}
@@ -0,0 +1,39 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the game loop commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum GameLoopError {
+    #[error("The game loop is already running")]
+    AlreadyRunning,
+
+    #[error("The game loop is not running")]
+    NotRunning,
+
+    #[error("Tick rate {rate} Hz is outside {min}..={max}")]
+    InvalidTickRate { rate: u32, min: u32, max: u32 },
+
+    #[error("Failed to start the game loop thread: {0}")]
+    Spawn(#[source] std::io::Error),
+}
+
+impl GameLoopError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameLoopError::AlreadyRunning => "alreadyRunning",
+            GameLoopError::NotRunning => "notRunning",
+            GameLoopError::InvalidTickRate { .. } => "invalidTickRate",
+            GameLoopError::Spawn(_) => "spawn",
+        }
+    }
+}
+
+impl Serialize for GameLoopError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("GameLoopError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,283 @@
+mod error;
+mod runner;
+mod stats;
+
+pub use error::GameLoopError;
+pub use stats::LoopStats;
+
+use runner::{Control, Shared};
+use stats::TickWindow;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tauri::{Emitter, Manager, Runtime, State};
+
+pub const DEFAULT_TICK_RATE: u32 = 64;
+const MIN_TICK_RATE: u32 = 1;
+const MAX_TICK_RATE: u32 = 1000;
+
+/// The fixed-rate simulation clock. Ticks run on a dedicated thread and are
+/// delivered to the frontend as `game-tick` events.
+pub struct GameLoop {
+    shared: Arc<Shared>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Default for GameLoop {
+    fn default() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                control: Mutex::new(Control {
+                    running: false,
+                    tick_rate: DEFAULT_TICK_RATE,
+                    rate_changed: false,
+                    pause_on_blur: false,
+                    focused: true,
+                    tick_number: 0,
+                }),
+                wake: Condvar::new(),
+                window: Mutex::new(TickWindow::default()),
+            }),
+            thread: Mutex::new(None),
+        }
+    }
+}
+
+impl GameLoop {
+    fn update(&self, change: impl FnOnce(&mut Control)) {
+        change(&mut self.shared.control.lock().unwrap());
+        self.shared.wake.notify_all();
+    }
+
+    /// Stops the thread and waits for it to exit. Returns whether it was
+    /// running.
+    fn stop(&self) -> bool {
+        let Some(thread) = self.thread.lock().unwrap().take() else {
+            return false;
+        };
+        self.update(|ctl| ctl.running = false);
+        let _ = thread.join();
+        true
+    }
+}
+
+/// Starts ticking at `tick_rate` Hz (default 64). Tick numbers restart at 1.
+#[tauri::command]
+pub async fn start_game_loop(
+    app: tauri::AppHandle,
+    game_loop: State<'_, GameLoop>,
+    tick_rate: Option<u32>,
+) -> Result<(), GameLoopError> {
+    let rate = validate_rate(tick_rate.unwrap_or(DEFAULT_TICK_RATE))?;
+    let mut thread = game_loop.thread.lock().unwrap();
+    if thread.is_some() {
+        return Err(GameLoopError::AlreadyRunning);
+    }
+
+    game_loop.update(|ctl| {
+        ctl.running = true;
+        ctl.tick_rate = rate;
+        ctl.rate_changed = true;
+        ctl.tick_number = 0;
+    });
+    let shared = game_loop.shared.clone();
+    let handle = std::thread::Builder::new()
+        .name("game-loop".into())
+        .spawn(move || {
+            runner::run(&shared, |tick| {
+                let _ = app.emit(runner::TICK_EVENT, tick);
+            })
+        })
+        .map_err(|e| {
+            game_loop.update(|ctl| ctl.running = false);
+            GameLoopError::Spawn(e)
+        })?;
+    *thread = Some(handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_game_loop(app: tauri::AppHandle) -> Result<(), GameLoopError> {
+    // Joining blocks until the thread notices, which is at most one spin.
+    let stopped = tauri::async_runtime::spawn_blocking(move || app.state::<GameLoop>().stop())
+        .await
+        .unwrap_or(false);
+    if stopped {
+        Ok(())
+    } else {
+        Err(GameLoopError::NotRunning)
+    }
+}
+
+/// Changes the rate of a running (or the next started) loop. The schedule
+/// restarts from the next tick, so there is no burst or gap.
+#[tauri::command]
+pub fn set_tick_rate(game_loop: State<'_, GameLoop>, rate: u32) -> Result<(), GameLoopError> {
+    let rate = validate_rate(rate)?;
+    game_loop.update(|ctl| {
+        ctl.tick_rate = rate;
+        ctl.rate_changed = true;
+    });
+    Ok(())
+}
+
+/// When enabled, ticks stop while the window is unfocused and resume on a
+/// fresh schedule when it regains focus.
+#[tauri::command]
+pub fn set_pause_on_blur(game_loop: State<'_, GameLoop>, enabled: bool) {
+    game_loop.update(|ctl| ctl.pause_on_blur = enabled);
+}
+
+#[tauri::command]
+pub fn get_loop_stats(game_loop: State<'_, GameLoop>) -> LoopStats {
+    let (running, paused, tick_rate, tick_number) = {
+        let ctl = game_loop.shared.control.lock().unwrap();
+        (ctl.running, ctl.paused(), ctl.tick_rate, ctl.tick_number)
+    };
+    let (achieved_rate, worst_jitter_us) = if running && !paused {
+        game_loop
+            .shared
+            .window
+            .lock()
+            .unwrap()
+            .summary(Instant::now())
+    } else {
+        (0.0, 0)
+    };
+    LoopStats {
+        running,
+        paused,
+        tick_rate,
+        tick_number,
+        achieved_rate,
+        worst_jitter_us,
+    }
+}
+
+/// Feeds window focus changes to the pause-on-blur logic.
+pub fn set_focused<R: Runtime>(app: &tauri::AppHandle<R>, focused: bool) {
+    app.state::<GameLoop>().update(|ctl| ctl.focused = focused);
+}
+
+/// Stops the loop and joins its thread. Called on exit.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    app.state::<GameLoop>().stop();
+}
+
+fn validate_rate(rate: u32) -> Result<u32, GameLoopError> {
+    if (MIN_TICK_RATE..=MAX_TICK_RATE).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(GameLoopError::InvalidTickRate {
+            rate,
+            min: MIN_TICK_RATE,
+            max: MAX_TICK_RATE,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::runner::GameTick;
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// Runs the loop thread outside Tauri, sending each tick and when it
+    /// arrived down a channel.
+    fn start(game_loop: &GameLoop, rate: u32) -> mpsc::Receiver<(Instant, GameTick)> {
+        game_loop.update(|ctl| {
+            ctl.running = true;
+            ctl.tick_rate = rate;
+            ctl.rate_changed = true;
+        });
+        let (tx, rx) = mpsc::channel();
+        let shared = game_loop.shared.clone();
+        let handle = std::thread::spawn(move || {
+            runner::run(&shared, |tick| {
+                let _ = tx.send((Instant::now(), tick));
+            })
+        });
+        *game_loop.thread.lock().unwrap() = Some(handle);
+        rx
+    }
+
+    #[test]
+    fn ticks_keep_to_the_schedule() {
+        const RATE: u32 = 250;
+        let period = Duration::from_secs(1) / RATE;
+        let game_loop = GameLoop::default();
+        let rx = start(&game_loop, RATE);
+
+        let ticks: Vec<(Instant, GameTick)> = rx.iter().take(RATE as usize / 2).collect();
+        let (achieved_rate, _) = game_loop
+            .shared
+            .window
+            .lock()
+            .unwrap()
+            .summary(Instant::now());
+        assert!(game_loop.stop());
+
+        for (i, (_, tick)) in ticks.iter().enumerate() {
+            assert_eq!(tick.tick_number, i as u64 + 1);
+            assert_eq!(tick.tick_dt, 1.0 / RATE as f64);
+        }
+        // Deadlines are anchored to the schedule start, so the last tick lands
+        // where the schedule says however much each sleep overshot.
+        let first = ticks[0].0;
+        let (last, _) = ticks[ticks.len() - 1];
+        let expected = period * (ticks.len() as u32 - 1);
+        let error = last.duration_since(first).abs_diff(expected);
+        assert!(
+            error < Duration::from_millis(5),
+            "{} ticks took {:?}, expected {:?}",
+            ticks.len(),
+            last.duration_since(first),
+            expected
+        );
+        assert!(
+            (achieved_rate - RATE as f64).abs() < RATE as f64 * 0.05,
+            "achieved {} Hz",
+            achieved_rate
+        );
+
+        // Most ticks fire well within a millisecond of their deadline.
+        let mut drifts: Vec<u64> = ticks.iter().map(|(_, t)| t.drift_us).collect();
+        drifts.sort_unstable();
+        assert!(
+            drifts[drifts.len() / 2] < 1_000,
+            "median drift {:?}",
+            drifts
+        );
+    }
+
+    #[test]
+    fn rate_changes_and_pauses_take_effect() {
+        let game_loop = GameLoop::default();
+        let rx = start(&game_loop, 500);
+        assert_eq!(rx.recv().unwrap().1.tick_dt, 1.0 / 500.0);
+
+        game_loop.update(|ctl| {
+            ctl.tick_rate = 200;
+            ctl.rate_changed = true;
+        });
+        let tick = rx.iter().map(|(_, t)| t).find(|t| t.tick_dt != 1.0 / 500.0);
+        assert_eq!(tick.unwrap().tick_dt, 1.0 / 200.0);
+
+        game_loop.update(|ctl| {
+            ctl.pause_on_blur = true;
+            ctl.focused = false;
+        });
+        // At most one tick was already past its wait when the pause landed.
+        while rx.recv_timeout(Duration::from_millis(50)).is_ok() {}
+        let paused_at = game_loop.shared.control.lock().unwrap().tick_number;
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        game_loop.update(|ctl| ctl.focused = true);
+        let (_, resumed) = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(resumed.tick_number, paused_at + 1);
+
+        assert!(game_loop.stop());
+        assert!(!game_loop.stop());
+    }
+}
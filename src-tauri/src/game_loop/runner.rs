@@ -0,0 +1,145 @@
+use super::stats::TickWindow;
+use serde::Serialize;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+pub const TICK_EVENT: &str = "game-tick";
+
+/// Below this much time to the deadline the thread yields instead of
+/// sleeping, since OS sleeps can overshoot by a millisecond or more.
+const SPIN_THRESHOLD: Duration = Duration::from_micros(1500);
+/// Falling further behind than this (e.g. after the machine slept) restarts
+/// the schedule instead of firing a burst of catch-up ticks.
+const MAX_BEHIND_TICKS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameTick {
+    pub tick_number: u64,
+    /// Fixed simulation step in seconds, `1 / tickRate`.
+    pub tick_dt: f64,
+    /// How late this tick fired relative to its scheduled time.
+    pub drift_us: u64,
+}
+
+pub struct Control {
+    pub running: bool,
+    pub tick_rate: u32,
+    /// Set by `set_tick_rate`; the thread restarts its schedule on seeing it.
+    pub rate_changed: bool,
+    pub pause_on_blur: bool,
+    pub focused: bool,
+    pub tick_number: u64,
+}
+
+impl Control {
+    pub fn paused(&self) -> bool {
+        self.pause_on_blur && !self.focused
+    }
+}
+
+/// State shared with the loop thread. Every change to `control` is followed
+/// by a notify on `wake`, so the thread reacts without waiting out a sleep.
+pub struct Shared {
+    pub control: Mutex<Control>,
+    pub wake: Condvar,
+    pub window: Mutex<TickWindow>,
+}
+
+/// The loop thread. Deadlines are computed from the start of the schedule
+/// (`anchor + n * period`) rather than from the previous tick, so sleep
+/// overshoot on one tick is taken out of the next wait instead of
+/// accumulating. Each tick is handed to `emit`.
+pub fn run(shared: &Shared, mut emit: impl FnMut(GameTick)) {
+    let mut anchor = Instant::now();
+    let mut n: u64 = 0;
+    let mut rate = 0;
+    let mut last_fired: Option<Instant> = None;
+
+    loop {
+        let mut ctl = shared.control.lock().unwrap();
+        let mut resumed = false;
+        loop {
+            if !ctl.running {
+                return;
+            }
+            if !ctl.paused() {
+                break;
+            }
+            ctl = shared.wake.wait(ctl).unwrap();
+            resumed = true;
+        }
+        if resumed || ctl.rate_changed || rate == 0 {
+            rate = ctl.tick_rate;
+            ctl.rate_changed = false;
+            anchor = Instant::now();
+            n = 0;
+            last_fired = None;
+            shared.window.lock().unwrap().clear();
+        }
+
+        let deadline = anchor + nth_tick(n + 1, rate);
+        let now = Instant::now();
+        if deadline > now + SPIN_THRESHOLD {
+            // Re-evaluate from the top: the wait may end early on a control
+            // change, or spuriously.
+            let _ = shared
+                .wake
+                .wait_timeout(ctl, deadline - now - SPIN_THRESHOLD)
+                .unwrap();
+            continue;
+        }
+        ctl.tick_number += 1;
+        let tick_number = ctl.tick_number;
+        drop(ctl);
+
+        while Instant::now() < deadline {
+            std::thread::yield_now();
+        }
+        let fired = Instant::now();
+        let drift = fired.duration_since(deadline);
+        n += 1;
+
+        let period = nth_tick(1, rate);
+        if drift > period * MAX_BEHIND_TICKS {
+            anchor = fired;
+            n = 0;
+        }
+
+        emit(GameTick {
+            tick_number,
+            tick_dt: 1.0 / rate as f64,
+            drift_us: drift.as_micros() as u64,
+        });
+
+        if let Some(last) = last_fired {
+            let interval = fired.duration_since(last);
+            let jitter = interval.abs_diff(period);
+            shared
+                .window
+                .lock()
+                .unwrap()
+                .record(fired, jitter.as_micros() as u64);
+        }
+        last_fired = Some(fired);
+    }
+}
+
+/// Offset of tick `n` from the schedule start, computed in whole nanoseconds
+/// so rates that don't divide a second evenly don't drift through rounding.
+fn nth_tick(n: u64, rate: u32) -> Duration {
+    Duration::from_nanos((n as u128 * 1_000_000_000 / rate as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_offsets_are_exact_for_uneven_rates() {
+        assert_eq!(nth_tick(1, 3), Duration::from_nanos(333_333_333));
+        assert_eq!(nth_tick(3, 3), Duration::from_secs(1));
+        assert_eq!(nth_tick(64, 64), Duration::from_secs(1));
+        assert_eq!(nth_tick(6_400, 64), Duration::from_secs(100));
+    }
+}
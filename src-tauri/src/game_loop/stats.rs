@@ -0,0 +1,61 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoopStats {
+    pub running: bool,
+    pub paused: bool,
+    pub tick_rate: u32,
+    pub tick_number: u64,
+    /// Ticks per second actually delivered over the last second.
+    pub achieved_rate: f64,
+    /// Largest deviation of a tick interval from the ideal period over the
+    /// last second, in microseconds.
+    pub worst_jitter_us: u64,
+}
+
+/// When each tick of the last second fired and how far its interval strayed
+/// from the period.
+#[derive(Default)]
+pub struct TickWindow {
+    ticks: VecDeque<(Instant, u64)>,
+}
+
+impl TickWindow {
+    pub fn record(&mut self, at: Instant, jitter_us: u64) {
+        self.ticks.push_back((at, jitter_us));
+        self.prune(at);
+    }
+
+    pub fn clear(&mut self) {
+        self.ticks.clear();
+    }
+
+    /// `(achieved rate, worst jitter)` as of `now`.
+    pub fn summary(&mut self, now: Instant) -> (f64, u64) {
+        self.prune(now);
+        let worst = self.ticks.iter().map(|&(_, j)| j).max().unwrap_or(0);
+        let rate = match (self.ticks.front(), self.ticks.back()) {
+            (Some(first), Some(last)) if self.ticks.len() > 1 => {
+                let span = last.0.duration_since(first.0).as_secs_f64();
+                (self.ticks.len() - 1) as f64 / span
+            }
+            _ => 0.0,
+        };
+        (rate, worst)
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .ticks
+            .front()
+            .is_some_and(|&(at, _)| now.duration_since(at) > WINDOW)
+        {
+            self.ticks.pop_front();
+        }
+    }
+}
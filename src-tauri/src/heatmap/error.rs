@@ -0,0 +1,71 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the heatmap commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum HeatmapError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Could not locate the documents directory: {0}")]
+    NoDocumentDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Level {id:?} could not be loaded: {reason}")]
+    Level { id: String, reason: String },
+
+    #[error("Invalid heatmap grid: {0}")]
+    InvalidGrid(String),
+
+    #[error("Invalid point at index {index}: {reason}")]
+    InvalidPoint { index: usize, reason: String },
+
+    #[error("{count} points in one batch is more than the limit of {max}")]
+    TooMany { count: usize, max: usize },
+
+    #[error("Export path {0:?} must be absolute")]
+    InvalidExportPath(PathBuf),
+}
+
+impl HeatmapError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        HeatmapError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        HeatmapError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HeatmapError::NoDataDir(_) => "noDataDir",
+            HeatmapError::NoDocumentDir(_) => "noDocumentDir",
+            HeatmapError::Io { .. } => "io",
+            HeatmapError::Level { .. } => "level",
+            HeatmapError::InvalidGrid(_) => "invalidGrid",
+            HeatmapError::InvalidPoint { .. } => "invalidPoint",
+            HeatmapError::TooMany { .. } => "tooMany",
+            HeatmapError::InvalidExportPath(_) => "invalidExportPath",
+        }
+    }
+}
+
+impl Serialize for HeatmapError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("HeatmapError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,290 @@
+use super::HeatmapError;
+use crate::levels::grid_size;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_CELL_SIZE: f32 = 1.0;
+const MAX_CELLS: u64 = 1 << 20;
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HeatmapKind {
+    Death,
+    Presence,
+    Kill,
+}
+
+impl HeatmapKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            HeatmapKind::Death => "death",
+            HeatmapKind::Presence => "presence",
+            HeatmapKind::Kill => "kill",
+        }
+    }
+}
+
+/// A top-down grid over a level's X/Z bounds, row-major with row 0 at
+/// the lowest Z.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Geometry {
+    pub origin_x: f32,
+    pub origin_z: f32,
+    pub cell_size: f32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Geometry {
+    pub fn covering(
+        (min_x, min_z): (f32, f32),
+        (max_x, max_z): (f32, f32),
+        cell_size: f32,
+    ) -> Result<Self, HeatmapError> {
+        if !(cell_size.is_finite() && cell_size > 0.0) {
+            return Err(HeatmapError::InvalidGrid(format!(
+                "cell size must be positive, not {}",
+                cell_size
+            )));
+        }
+        let valid = [min_x, min_z, max_x, max_z].iter().all(|v| v.is_finite())
+            && min_x <= max_x
+            && min_z <= max_z;
+        if !valid {
+            return Err(HeatmapError::InvalidGrid(
+                "the level bounds are invalid".into(),
+            ));
+        }
+        let (width, height) = grid_size(max_x - min_x, max_z - min_z, cell_size, MAX_CELLS)
+            .map_err(|e| HeatmapError::InvalidGrid(format!("{}; use larger cells", e)))?;
+        Ok(Self {
+            origin_x: min_x,
+            origin_z: min_z,
+            cell_size,
+            width,
+            height,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn max(&self) -> (f32, f32) {
+        (
+            self.origin_x + self.width as f32 * self.cell_size,
+            self.origin_z + self.height as f32 * self.cell_size,
+        )
+    }
+
+    /// The cell holding `(x, z)`, clamped onto the grid, and whether it
+    /// had to be. Points on the far edges count as inside.
+    pub fn bin(&self, x: f32, z: f32) -> (usize, bool) {
+        let (max_x, max_z) = self.max();
+        let outside = x < self.origin_x || z < self.origin_z || x > max_x || z > max_z;
+        let axis = |v: f32, origin: f32, n: u32| {
+            (((v - origin) / self.cell_size).floor().max(0.0) as u32).min(n - 1)
+        };
+        let cx = axis(x, self.origin_x, self.width);
+        let cz = axis(z, self.origin_z, self.height);
+        (cz as usize * self.width as usize + cx as usize, outside)
+    }
+
+    fn center(&self, i: usize) -> (f32, f32) {
+        let (x, z) = (i % self.width as usize, i / self.width as usize);
+        (
+            self.origin_x + (x as f32 + 0.5) * self.cell_size,
+            self.origin_z + (z as f32 + 0.5) * self.cell_size,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Layer {
+    pub counts: Vec<u32>,
+    /// Points that fell outside the grid. They're clamped into the edge
+    /// cells as well, so this says how much of the edge is really beyond
+    /// it.
+    pub out_of_bounds: u64,
+}
+
+impl Layer {
+    fn new(len: usize) -> Self {
+        Self {
+            counts: vec![0; len],
+            out_of_bounds: 0,
+        }
+    }
+}
+
+/// Every kind's counts for one level.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    pub geometry: Geometry,
+    pub layers: BTreeMap<HeatmapKind, Layer>,
+}
+
+impl Grid {
+    pub fn new(geometry: Geometry) -> Self {
+        Self {
+            geometry,
+            layers: BTreeMap::new(),
+        }
+    }
+
+    pub fn layer_mut(&mut self, kind: HeatmapKind) -> &mut Layer {
+        let len = self.geometry.len();
+        self.layers.entry(kind).or_insert_with(|| Layer::new(len))
+    }
+
+    /// Bins `points`, `(x, z)` in world units.
+    pub fn record(&mut self, kind: HeatmapKind, points: &[(f32, f32)]) {
+        let geometry = self.geometry;
+        let layer = self.layer_mut(kind);
+        for &(x, z) in points {
+            let (i, outside) = geometry.bin(x, z);
+            layer.counts[i] = layer.counts[i].saturating_add(1);
+            layer.out_of_bounds += outside as u64;
+        }
+    }
+
+    /// The same counts on `geometry`, each old cell moved whole to the new
+    /// cell under its center. Exact when only the bounds grew by whole
+    /// cells; otherwise as close as cell-level data allows.
+    pub fn rebinned(&self, geometry: Geometry) -> Self {
+        if geometry == self.geometry {
+            return self.clone();
+        }
+        let mut out = Self::new(geometry);
+        for (&kind, layer) in &self.layers {
+            let target = out.layer_mut(kind);
+            target.out_of_bounds = layer.out_of_bounds;
+            for (i, &count) in layer.counts.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                let (x, z) = self.geometry.center(i);
+                let (j, outside) = geometry.bin(x, z);
+                target.counts[j] = target.counts[j].saturating_add(count);
+                if outside {
+                    target.out_of_bounds += count as u64;
+                }
+            }
+        }
+        out
+    }
+
+    /// Adds `other`'s counts, rebinned onto this grid first if needed.
+    pub fn merge(&mut self, other: &Grid) {
+        let other = other.rebinned(self.geometry);
+        for (kind, layer) in other.layers {
+            let target = self.layer_mut(kind);
+            for (t, c) in target.counts.iter_mut().zip(&layer.counts) {
+                *t = t.saturating_add(*c);
+            }
+            target.out_of_bounds += layer.out_of_bounds;
+        }
+    }
+}
+
+/// `heatmaps/<level>.json`. Cells are stored sparsely as
+/// `[index, count]`, since most of a level sees nobody.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapFile {
+    pub version: u32,
+    pub geometry: Geometry,
+    pub layers: BTreeMap<HeatmapKind, StoredLayer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredLayer {
+    pub out_of_bounds: u64,
+    pub cells: Vec<[u64; 2]>,
+}
+
+impl HeatmapFile {
+    pub fn from_grid(grid: &Grid) -> Self {
+        let layers = grid
+            .layers
+            .iter()
+            .map(|(&kind, layer)| {
+                let cells = layer
+                    .counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c > 0)
+                    .map(|(i, c)| [i as u64, *c as u64])
+                    .collect();
+                let stored = StoredLayer {
+                    out_of_bounds: layer.out_of_bounds,
+                    cells,
+                };
+                (kind, stored)
+            })
+            .collect();
+        Self {
+            version: FORMAT_VERSION,
+            geometry: grid.geometry,
+            layers,
+        }
+    }
+
+    pub fn into_grid(self) -> Result<Grid, String> {
+        let g = self.geometry;
+        let valid = g.origin_x.is_finite()
+            && g.origin_z.is_finite()
+            && g.cell_size.is_finite()
+            && g.cell_size > 0.0
+            && g.width > 0
+            && g.height > 0
+            && g.len() as u64 <= MAX_CELLS;
+        if !valid {
+            return Err(format!("invalid grid {:?}", g));
+        }
+        let mut grid = Grid::new(g);
+        for (kind, stored) in self.layers {
+            let layer = grid.layer_mut(kind);
+            layer.out_of_bounds = stored.out_of_bounds;
+            for [i, count] in stored.cells {
+                let cell = layer
+                    .counts
+                    .get_mut(i as usize)
+                    .ok_or_else(|| format!("cell {} is outside the grid", i))?;
+                *cell = cell.saturating_add(count.min(u32::MAX as u64) as u32);
+            }
+        }
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiny_cells_are_refused_before_anything_is_allocated() {
+        let (min, max) = ((-100_000.0, -100_000.0), (100_000.0, 100_000.0));
+        for cell_size in [1e-5, 1e-30, f32::MIN_POSITIVE] {
+            match Geometry::covering(min, max, cell_size) {
+                Err(HeatmapError::InvalidGrid(reason)) => {
+                    assert!(reason.contains("use larger cells"))
+                }
+                other => panic!("expected invalidGrid, got {:?}", other),
+            }
+        }
+        let geometry = Geometry::covering(min, max, 200.0).unwrap();
+        assert_eq!((geometry.width, geometry.height), (1000, 1000));
+        assert_eq!(geometry.len(), 1_000_000);
+    }
+
+    #[test]
+    fn a_flat_level_still_gets_one_row() {
+        let geometry = Geometry::covering((0.0, 5.0), (10.0, 5.0), 1.0).unwrap();
+        assert_eq!((geometry.width, geometry.height), (10, 1));
+        assert_eq!(geometry.bin(9.99, 5.0), (9, false));
+    }
+}
@@ -0,0 +1,360 @@
+mod error;
+mod grid;
+mod render;
+
+pub use error::HeatmapError;
+pub use grid::HeatmapKind;
+
+use crate::time::now_ms;
+use grid::{Geometry, Grid, HeatmapFile, DEFAULT_CELL_SIZE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::path::BaseDirectory;
+use tauri::{Manager, Runtime, State};
+
+const DIR_NAME: &str = "heatmaps";
+const EXPORT_DIR: &str = "fps-game";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// About a minute of presence samples from a full server.
+pub const MAX_POINTS_PER_BATCH: usize = 65_536;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Png,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Png => "png",
+        }
+    }
+}
+
+/// One kind of count over a level, for the editor overlay.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapView {
+    pub level_id: String,
+    pub kind: HeatmapKind,
+    #[serde(flatten)]
+    pub geometry: Geometry,
+    /// The far corner of the grid; it can pass the level bounds by up to
+    /// a cell.
+    pub max_x: f32,
+    pub max_z: f32,
+    /// Row-major, `width` per row, starting at the lowest Z.
+    pub counts: Vec<u32>,
+    pub total: u64,
+    pub max_count: u32,
+    /// Points recorded outside the grid, which were clamped into the edge
+    /// cells.
+    pub out_of_bounds: u64,
+}
+
+struct Entry {
+    path: PathBuf,
+    grid: Grid,
+    dirty: bool,
+}
+
+impl Entry {
+    fn flush(&mut self) -> Result<(), HeatmapError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let json =
+            serde_json::to_vec(&HeatmapFile::from_grid(&self.grid)).expect("heatmap serialize");
+        crate::fs_atomic::write_atomic(&self.path, &json)
+            .map_err(|e| HeatmapError::io(&self.path, e))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn view(&self, level_id: &str, kind: HeatmapKind) -> HeatmapView {
+        let geometry = self.grid.geometry;
+        let (counts, out_of_bounds) = match self.grid.layers.get(&kind) {
+            Some(layer) => (layer.counts.clone(), layer.out_of_bounds),
+            None => (vec![0; geometry.len()], 0),
+        };
+        let (max_x, max_z) = geometry.max();
+        HeatmapView {
+            level_id: level_id.to_string(),
+            kind,
+            geometry,
+            max_x,
+            max_z,
+            total: counts.iter().map(|c| *c as u64).sum(),
+            max_count: counts.iter().copied().max().unwrap_or(0),
+            counts,
+            out_of_bounds,
+        }
+    }
+}
+
+/// Per-level grids, loaded on first use and written back by
+/// [`HeatmapStore::flush`] rather than on every batch.
+#[derive(Default)]
+pub struct HeatmapStore {
+    grids: Mutex<HashMap<String, Entry>>,
+}
+
+impl HeatmapStore {
+    /// Runs `f` against `level_id`'s grid, loading it first if needed.
+    fn with_grid<T>(
+        &self,
+        app: &tauri::AppHandle,
+        level_id: &str,
+        f: impl FnOnce(&mut Entry) -> T,
+    ) -> Result<T, HeatmapError> {
+        let mut grids = self.grids.lock().unwrap();
+        if !grids.contains_key(level_id) {
+            let entry = load(app, level_id)?;
+            grids.insert(level_id.to_string(), entry);
+        }
+        Ok(f(grids.get_mut(level_id).unwrap()))
+    }
+
+    /// Writes every grid changed since the last flush.
+    pub fn flush(&self) -> Result<(), HeatmapError> {
+        let mut first_error = None;
+        for entry in self.grids.lock().unwrap().values_mut() {
+            if let Err(e) = entry.flush() {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+fn heatmaps_dir(app: &tauri::AppHandle) -> Result<PathBuf, HeatmapError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| HeatmapError::NoDataDir(e.to_string()))
+}
+
+fn file_path(app: &tauri::AppHandle, level_id: &str) -> Result<PathBuf, HeatmapError> {
+    crate::levels::valid_id(level_id).map_err(|e| HeatmapError::Level {
+        id: level_id.to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(heatmaps_dir(app)?.join(format!("{}.json", level_id)))
+}
+
+/// The grid covering `level_id`'s X/Z bounds at `cell_size`.
+fn level_geometry(
+    app: &tauri::AppHandle,
+    level_id: &str,
+    cell_size: f32,
+) -> Result<Geometry, HeatmapError> {
+    let level = crate::levels::load(app, level_id).map_err(|e| HeatmapError::Level {
+        id: level_id.to_string(),
+        reason: e.to_string(),
+    })?;
+    let b = level.bounds;
+    Geometry::covering((b.min.x, b.min.z), (b.max.x, b.max.z), cell_size)
+}
+
+/// What earlier sessions saved, or `None`. A file that doesn't parse is
+/// moved to `<level>.json.bak` so recording can carry on.
+fn read_saved(path: &std::path::Path) -> Result<Option<Grid>, HeatmapError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(HeatmapError::io(path, e)),
+    };
+    let parsed = serde_json::from_slice::<HeatmapFile>(&bytes)
+        .map_err(|e| e.to_string())
+        .and_then(HeatmapFile::into_grid);
+    match parsed {
+        Ok(grid) => Ok(Some(grid)),
+        Err(e) => {
+            let backup = path.with_extension("json.bak");
+            log::warn!("Invalid {:?} ({}); moved to {:?}", path, e, backup);
+            std::fs::rename(path, &backup).map_err(|e| HeatmapError::io(path, e))?;
+            Ok(None)
+        }
+    }
+}
+
+/// The saved counts for `level_id` laid onto a grid over the level as it
+/// is now. When the level's bounds changed since they were saved, the old
+/// cells are rebinned onto the new grid rather than thrown away. If the
+/// level can no longer be loaded, the saved grid is used as is.
+fn load(app: &tauri::AppHandle, level_id: &str) -> Result<Entry, HeatmapError> {
+    let path = file_path(app, level_id)?;
+    let saved = read_saved(&path)?;
+    let cell_size = saved
+        .as_ref()
+        .map_or(DEFAULT_CELL_SIZE, |g| g.geometry.cell_size);
+    let geometry = match (level_geometry(app, level_id, cell_size), &saved) {
+        (Ok(geometry), _) => geometry,
+        (Err(e), Some(saved)) => {
+            log::warn!("Heatmap {} keeps its saved grid: {}", level_id, e);
+            saved.geometry
+        }
+        (Err(e), None) => return Err(e),
+    };
+    let mut grid = Grid::new(geometry);
+    let mut dirty = false;
+    if let Some(saved) = saved {
+        dirty = saved.geometry != geometry;
+        if dirty {
+            log::info!(
+                "Heatmap {} rebinned onto the level's current bounds",
+                level_id
+            );
+        }
+        grid.merge(&saved);
+    }
+    Ok(Entry { path, grid, dirty })
+}
+
+/// Bins `points`, world `(x, z)` pairs, into `level_id`'s `kind` grid.
+/// Points beyond the level are clamped into its edge cells and counted in
+/// `outOfBounds`. Applies all of the batch or, when any point isn't
+/// finite, none of it. Only touches memory; grids are written out every
+/// 30 seconds and on exit.
+#[tauri::command]
+pub async fn record_positions(
+    app: tauri::AppHandle,
+    store: State<'_, HeatmapStore>,
+    level_id: String,
+    kind: HeatmapKind,
+    points: Vec<(f32, f32)>,
+) -> Result<(), HeatmapError> {
+    if points.len() > MAX_POINTS_PER_BATCH {
+        return Err(HeatmapError::TooMany {
+            count: points.len(),
+            max: MAX_POINTS_PER_BATCH,
+        });
+    }
+    if let Some(index) = points
+        .iter()
+        .position(|(x, z)| !(x.is_finite() && z.is_finite()))
+    {
+        return Err(HeatmapError::InvalidPoint {
+            index,
+            reason: "coordinates must be finite".into(),
+        });
+    }
+    store.with_grid(&app, &level_id, |entry| {
+        entry.grid.record(kind, &points);
+        entry.dirty |= !points.is_empty();
+    })
+}
+
+/// `level_id`'s `kind` counts with the grid they're on, merged across
+/// every session so far.
+#[tauri::command]
+pub async fn get_heatmap(
+    app: tauri::AppHandle,
+    store: State<'_, HeatmapStore>,
+    level_id: String,
+    kind: HeatmapKind,
+) -> Result<HeatmapView, HeatmapError> {
+    store.with_grid(&app, &level_id, |entry| entry.view(&level_id, kind))
+}
+
+/// Changes the size of `level_id`'s cells in world units, moving the
+/// counts gathered so far onto the new grid. Going finer can't split old
+/// cells, so their counts land where their centers fall.
+#[tauri::command]
+pub async fn set_heatmap_cell_size(
+    app: tauri::AppHandle,
+    store: State<'_, HeatmapStore>,
+    level_id: String,
+    cell_size: f32,
+) -> Result<(), HeatmapError> {
+    let geometry = level_geometry(&app, &level_id, cell_size)?;
+    store.with_grid(&app, &level_id, |entry| {
+        if entry.grid.geometry != geometry {
+            entry.grid = entry.grid.rebinned(geometry);
+            entry.dirty = true;
+        }
+    })
+}
+
+/// Writes `level_id`'s `kind` counts as JSON (what `get_heatmap` returns)
+/// or as a color-mapped PNG overlay, and returns the path written. `path`
+/// may be a file or an existing directory; without one the export goes
+/// to `Documents/fps-game/`.
+#[tauri::command]
+pub async fn export_heatmap(
+    app: tauri::AppHandle,
+    store: State<'_, HeatmapStore>,
+    level_id: String,
+    kind: HeatmapKind,
+    format: ExportFormat,
+    path: Option<String>,
+) -> Result<String, HeatmapError> {
+    let view = store.with_grid(&app, &level_id, |entry| entry.view(&level_id, kind))?;
+
+    let default_name = format!(
+        "heatmap-{}-{}-{}.{}",
+        level_id,
+        kind.name(),
+        now_ms(),
+        format.extension()
+    );
+    let target = match path.map(PathBuf::from) {
+        Some(path) if !path.is_absolute() => return Err(HeatmapError::InvalidExportPath(path)),
+        Some(path) if path.is_dir() => path.join(default_name),
+        Some(path) => path,
+        None => app
+            .path()
+            .resolve(EXPORT_DIR, BaseDirectory::Document)
+            .map_err(|e| HeatmapError::NoDocumentDir(e.to_string()))?
+            .join(default_name),
+    };
+
+    let bytes = tauri::async_runtime::spawn_blocking(move || match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(&view).map_err(|e| e.to_string()),
+        ExportFormat::Png => render::png(&view.geometry, &view.counts),
+    })
+    .await
+    .map_err(|e| HeatmapError::task(&target, e))?
+    .map_err(|e| HeatmapError::task(&target, e))?;
+    crate::fs_atomic::write_atomic(&target, &bytes).map_err(|e| HeatmapError::io(&target, e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// Clears every kind of count for `level_id`, in memory and on disk.
+#[tauri::command]
+pub async fn reset_heatmap(
+    app: tauri::AppHandle,
+    store: State<'_, HeatmapStore>,
+    level_id: String,
+) -> Result<(), HeatmapError> {
+    let path = file_path(&app, &level_id)?;
+    store.grids.lock().unwrap().remove(&level_id);
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(HeatmapError::io(&path, e)),
+    }
+}
+
+/// Flushes pending heatmaps every [`FLUSH_INTERVAL`] for the life of the
+/// app.
+pub fn start_flush_timer<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        flush(&app);
+    });
+}
+
+/// Writes pending heatmaps now, logging on failure. Called on exit.
+pub fn flush<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Err(e) = app.state::<HeatmapStore>().flush() {
+        log::error!("Failed to flush heatmaps: {}", e);
+    }
+}
@@ -0,0 +1,64 @@
+//! Heatmap layers as PNG overlays, with the `png` crate only.
+
+use super::grid::Geometry;
+
+/// Each cell becomes a square of pixels, sized so the long side of the
+/// image comes out near this, never past `MAX_PIXELS`.
+const TARGET_PIXELS: u32 = 1024;
+const MAX_PIXELS: u32 = 4096;
+/// Black through blue, red and yellow to white.
+const STOPS: [[f32; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [0.1, 0.1, 0.8],
+    [0.9, 0.1, 0.1],
+    [1.0, 0.9, 0.1],
+    [1.0, 1.0, 1.0],
+];
+
+fn color(t: f32) -> [u8; 3] {
+    let scaled = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (scaled as usize).min(STOPS.len() - 2);
+    let f = scaled - i as f32;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+    [0, 1, 2].map(|c| ((a[c] + (b[c] - a[c]) * f) * 255.0).round() as u8)
+}
+
+/// Renders `counts` top-down with row 0 (the lowest Z) at the top. Counts
+/// are normalized to the busiest cell on a square-root scale, so cells
+/// visited a handful of times still show next to hot spots. Empty cells
+/// are transparent, ready to lay over a map.
+pub fn png(geometry: &Geometry, counts: &[u32]) -> Result<Vec<u8>, String> {
+    let long_side = geometry.width.max(geometry.height);
+    let scale = (TARGET_PIXELS / long_side).clamp(1, (MAX_PIXELS / long_side).max(1));
+    let (width, height) = (geometry.width * scale, geometry.height * scale);
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    let palette: Vec<[u8; 4]> = counts
+        .iter()
+        .map(|&count| match count {
+            0 => [0; 4],
+            count => {
+                let [r, g, b] = color((count as f32 / max).sqrt());
+                [r, g, b, u8::MAX]
+            }
+        })
+        .collect();
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        let row = (y / scale) as usize * geometry.width as usize;
+        for x in 0..width {
+            pixels.extend_from_slice(&palette[row + (x / scale) as usize]);
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&pixels)
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(out)
+}
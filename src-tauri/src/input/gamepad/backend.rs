@@ -0,0 +1,69 @@
+//! The platform side: XInput on Windows, evdev on Linux, and nothing
+//! elsewhere. Backends are polled, and report hot-plugging from their polls
+//! like any other event.
+
+use super::event::{GamepadInfo, RawEvent};
+use std::time::Duration;
+
+/// How often a backend looks for newly plugged pads.
+pub const RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+/// How far a trigger travels before it counts as pressed, on pads that
+/// only report it as an axis. XInput's own threshold.
+pub const TRIGGER_THRESHOLD: f32 = 30.0 / 255.0;
+
+pub trait Backend: Send {
+    /// Appends everything that happened since the last poll, by gamepad id.
+    fn poll(&mut self, events: &mut Vec<(u32, RawEvent)>);
+
+    /// The connected pads. May rescan, with any change reported by the next
+    /// poll.
+    fn gamepads(&mut self) -> Vec<GamepadInfo>;
+
+    /// Starts both motors, `strong` being the low-frequency one, for
+    /// `duration`. Returns `None` for an unknown pad and `Some(false)` when
+    /// the pad can't rumble.
+    fn rumble(&mut self, id: u32, strong: f32, weak: f32, duration: Duration) -> Option<bool>;
+}
+
+pub fn open() -> Box<dyn Backend> {
+    #[cfg(windows)]
+    return Box::new(super::xinput::XInput::default());
+    #[cfg(target_os = "linux")]
+    return Box::new(super::evdev::Evdev::default());
+    #[cfg(not(any(windows, target_os = "linux")))]
+    return Box::new(Unsupported);
+}
+
+/// No gamepad support on this platform: no pads, ever.
+#[cfg(not(any(windows, target_os = "linux")))]
+struct Unsupported;
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl Backend for Unsupported {
+    fn poll(&mut self, _events: &mut Vec<(u32, RawEvent)>) {}
+
+    fn gamepads(&mut self) -> Vec<GamepadInfo> {
+        Vec::new()
+    }
+
+    fn rumble(&mut self, _id: u32, _strong: f32, _weak: f32, _duration: Duration) -> Option<bool> {
+        None
+    }
+}
+
+/// Moves a tracked button to `now`, returning the edge when it changed.
+pub fn button_edge(
+    pressed: &mut bool,
+    now: bool,
+    button: super::event::Button,
+    value: f32,
+) -> Option<RawEvent> {
+    (*pressed != now).then(|| {
+        *pressed = now;
+        RawEvent::Button {
+            button,
+            pressed: now,
+            value,
+        }
+    })
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the gamepad commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum GamepadError {
+    #[error("The gamepad listener is already running")]
+    AlreadyRunning,
+
+    #[error("The gamepad listener is not running")]
+    NotRunning,
+
+    #[error("Deadzone {value} is outside 0..={max}")]
+    InvalidDeadzone { value: f32, max: f32 },
+
+    #[error("Invalid rumble: {0}")]
+    InvalidRumble(String),
+
+    #[error("No connected gamepad with id {0}")]
+    UnknownGamepad(u32),
+
+    #[error("Failed to start the gamepad thread: {0}")]
+    Spawn(#[source] std::io::Error),
+}
+
+impl GamepadError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GamepadError::AlreadyRunning => "alreadyRunning",
+            GamepadError::NotRunning => "notRunning",
+            GamepadError::InvalidDeadzone { .. } => "invalidDeadzone",
+            GamepadError::InvalidRumble(_) => "invalidRumble",
+            GamepadError::UnknownGamepad(_) => "unknownGamepad",
+            GamepadError::Spawn(_) => "spawn",
+        }
+    }
+}
+
+impl Serialize for GamepadError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("GamepadError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,435 @@
+//! evdev: every `/dev/input/event*` node that has gamepad buttons, read
+//! without blocking. Nodes the user can't open yet are retried on each
+//! rescan, since udev grants access a moment after a pad is plugged in.
+
+use super::backend::{button_edge, Backend, RESCAN_INTERVAL, TRIGGER_THRESHOLD};
+use super::event::{Axis, BatteryLevel, Button, GamepadInfo, PowerInfo, RawEvent};
+use libc::{ff_effect, ff_rumble_effect, input_absinfo, input_event};
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const INPUT_DIR: &str = "/dev/input";
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const EV_FF: u16 = 0x15;
+const BTN_SOUTH: u16 = 0x130;
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+const FF_RUMBLE: u16 = 0x50;
+
+const BUTTONS: [(u16, Button); 17] = [
+    (0x130, Button::South),
+    (0x131, Button::East),
+    (0x133, Button::North),
+    (0x134, Button::West),
+    (0x136, Button::LeftBumper),
+    (0x137, Button::RightBumper),
+    (BTN_TL2, Button::LeftTrigger),
+    (BTN_TR2, Button::RightTrigger),
+    (0x13a, Button::Select),
+    (0x13b, Button::Start),
+    (0x13c, Button::Mode),
+    (0x13d, Button::LeftThumb),
+    (0x13e, Button::RightThumb),
+    (0x220, Button::DpadUp),
+    (0x221, Button::DpadDown),
+    (0x222, Button::DpadLeft),
+    (0x223, Button::DpadRight),
+];
+
+/// The layout current kernel drivers (xpad, hid-playstation, hid-nintendo)
+/// agree on. Some old generic pads put the right stick on Z/RZ instead.
+const AXES: [(u16, Axis); 8] = [
+    (0x00, Axis::LeftStickX),
+    (0x01, Axis::LeftStickY),
+    (0x03, Axis::RightStickX),
+    (0x04, Axis::RightStickY),
+    (0x02, Axis::LeftTrigger),
+    (0x05, Axis::RightTrigger),
+    (0x0a, Axis::LeftTrigger),
+    (0x09, Axis::RightTrigger),
+];
+
+const EVIOCGNAME: libc::Ioctl = libc::_IOR::<[u8; 256]>(b'E' as u32, 0x06);
+const EVIOCGBIT_KEY: libc::Ioctl = libc::_IOR::<[u8; 96]>(b'E' as u32, 0x20 + EV_KEY as u32);
+const EVIOCGBIT_ABS: libc::Ioctl = libc::_IOR::<[u8; 8]>(b'E' as u32, 0x20 + EV_ABS as u32);
+const EVIOCGBIT_FF: libc::Ioctl = libc::_IOR::<[u8; 16]>(b'E' as u32, 0x20 + EV_FF as u32);
+const EVIOCSFF: libc::Ioctl = libc::_IOW::<ff_effect>(b'E' as u32, 0x80);
+
+const fn eviocgabs(code: u16) -> libc::Ioctl {
+    libc::_IOR::<input_absinfo>(b'E' as u32, 0x40 + code as u32)
+}
+
+/// Fills `out` through a read ioctl. `request` must be for `T`.
+fn ioctl_read<T>(file: &File, request: libc::Ioctl, out: &mut T) -> bool {
+    // SAFETY: the request's size matches `T`, so the kernel writes only
+    // within `out`.
+    unsafe { libc::ioctl(file.as_raw_fd(), request, out as *mut T) >= 0 }
+}
+
+fn bit(bits: &[u8], n: u16) -> bool {
+    bits.get(n as usize / 8)
+        .is_some_and(|b| b & (1 << (n % 8)) != 0)
+}
+
+#[derive(Clone, Copy)]
+struct Range {
+    min: i32,
+    max: i32,
+}
+
+impl Range {
+    fn normalize(self, axis: Axis, raw: i32) -> f32 {
+        let span = (self.max - self.min).max(1) as f32;
+        let unit = ((raw - self.min) as f32 / span).clamp(0.0, 1.0);
+        match axis {
+            Axis::LeftTrigger | Axis::RightTrigger => unit,
+            // evdev's Y grows downward.
+            Axis::LeftStickY | Axis::RightStickY => 1.0 - unit * 2.0,
+            _ => unit * 2.0 - 1.0,
+        }
+    }
+}
+
+struct Device {
+    id: u32,
+    path: PathBuf,
+    file: File,
+    name: String,
+    axes: HashMap<u16, (Axis, Range)>,
+    /// The pad reports its triggers only as axes, so presses are derived.
+    analog_triggers_only: bool,
+    triggers: [bool; 2],
+    hat: (i32, i32),
+    rumble: bool,
+    /// The uploaded rumble effect, reused for every rumble.
+    effect: Option<i16>,
+}
+
+impl Device {
+    /// `None` for nodes that aren't gamepads.
+    fn open(path: &Path, id: u32) -> std::io::Result<Option<Self>> {
+        let open = |write| {
+            OpenOptions::new()
+                .read(true)
+                .write(write)
+                .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+                .open(path)
+        };
+        // Rumble needs write access, which not every setup grants.
+        let (file, writable) = match open(true) {
+            Ok(file) => (file, true),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => (open(false)?, false),
+            Err(e) => return Err(e),
+        };
+
+        let mut keys = [0u8; 96];
+        if !ioctl_read(&file, EVIOCGBIT_KEY, &mut keys) || !bit(&keys, BTN_SOUTH) {
+            return Ok(None);
+        }
+        let mut name = [0u8; 256];
+        ioctl_read(&file, EVIOCGNAME, &mut name);
+        let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = String::from_utf8_lossy(&name[..len]).into_owned();
+
+        let mut abs = [0u8; 8];
+        ioctl_read(&file, EVIOCGBIT_ABS, &mut abs);
+        let mut axes = HashMap::new();
+        for (code, axis) in AXES {
+            if !bit(&abs, code) || axes.values().any(|(a, _)| *a == axis) {
+                continue;
+            }
+            // SAFETY: input_absinfo is plain integers.
+            let mut info: input_absinfo = unsafe { std::mem::zeroed() };
+            if ioctl_read(&file, eviocgabs(code), &mut info) {
+                let range = Range {
+                    min: info.minimum,
+                    max: info.maximum,
+                };
+                axes.insert(code, (axis, range));
+            }
+        }
+
+        let mut ff = [0u8; 16];
+        let rumble = writable && ioctl_read(&file, EVIOCGBIT_FF, &mut ff) && bit(&ff, FF_RUMBLE);
+        Ok(Some(Self {
+            id,
+            path: path.to_path_buf(),
+            file,
+            name,
+            axes,
+            analog_triggers_only: !bit(&keys, BTN_TL2) && !bit(&keys, BTN_TR2),
+            triggers: [false; 2],
+            hat: (0, 0),
+            rumble,
+            effect: None,
+        }))
+    }
+
+    /// Reads what's waiting. `Err` means the pad is gone.
+    fn read(&mut self, events: &mut Vec<(u32, RawEvent)>) -> std::io::Result<()> {
+        const SIZE: usize = std::mem::size_of::<input_event>();
+        let mut buf = [0u8; SIZE * 64];
+        loop {
+            let n = match self.file.read(&mut buf) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for chunk in buf[..n].chunks_exact(SIZE) {
+                // SAFETY: the kernel writes whole input_events, and any bit
+                // pattern is a valid one.
+                let event: input_event = unsafe { std::ptr::read_unaligned(chunk.as_ptr().cast()) };
+                self.translate(&event, events);
+            }
+        }
+    }
+
+    fn translate(&mut self, event: &input_event, events: &mut Vec<(u32, RawEvent)>) {
+        let id = self.id;
+        let mut push = |e| events.push((id, e));
+        match event.type_ {
+            EV_KEY => {
+                if let Some(&(_, button)) = BUTTONS.iter().find(|(c, _)| *c == event.code) {
+                    // 2 is autorepeat, which gamepads shouldn't send anyway.
+                    if event.value != 2 {
+                        let pressed = event.value != 0;
+                        push(RawEvent::Button {
+                            button,
+                            pressed,
+                            value: pressed as u8 as f32,
+                        });
+                    }
+                }
+            }
+            EV_ABS if event.code == ABS_HAT0X || event.code == ABS_HAT0Y => {
+                let value = event.value.signum();
+                let (old, negative, positive) = if event.code == ABS_HAT0X {
+                    (
+                        std::mem::replace(&mut self.hat.0, value),
+                        Button::DpadLeft,
+                        Button::DpadRight,
+                    )
+                } else {
+                    (
+                        std::mem::replace(&mut self.hat.1, value),
+                        Button::DpadUp,
+                        Button::DpadDown,
+                    )
+                };
+                for (state, button) in [(-1, negative), (1, positive)] {
+                    let (was, is) = (old == state, value == state);
+                    if was != is {
+                        push(RawEvent::Button {
+                            button,
+                            pressed: is,
+                            value: is as u8 as f32,
+                        });
+                    }
+                }
+            }
+            EV_ABS => {
+                let Some(&(axis, range)) = self.axes.get(&event.code) else {
+                    return;
+                };
+                let value = range.normalize(axis, event.value);
+                push(RawEvent::Axis { axis, value });
+                let trigger = match axis {
+                    Axis::LeftTrigger => Some((0, Button::LeftTrigger)),
+                    Axis::RightTrigger => Some((1, Button::RightTrigger)),
+                    _ => None,
+                };
+                if let Some((i, button)) = trigger.filter(|_| self.analog_triggers_only) {
+                    let pressed = value >= TRIGGER_THRESHOLD;
+                    if let Some(edge) = button_edge(&mut self.triggers[i], pressed, button, value) {
+                        push(edge);
+                    }
+                }
+            }
+            // Includes SYN_DROPPED; the next events carry current values.
+            EV_SYN => {}
+            _ => {}
+        }
+    }
+
+    fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> bool {
+        if !self.rumble {
+            return false;
+        }
+        // SAFETY: ff_effect is plain integers.
+        let mut effect: ff_effect = unsafe { std::mem::zeroed() };
+        effect.type_ = FF_RUMBLE;
+        effect.id = self.effect.unwrap_or(-1);
+        effect.replay.length = duration.as_millis().min(u16::MAX as u128) as u16;
+        let magnitudes = ff_rumble_effect {
+            strong_magnitude: (strong * 65535.0) as u16,
+            weak_magnitude: (weak * 65535.0) as u16,
+        };
+        // SAFETY: `u` is the effect's parameter union, which rumble effects
+        // start with their two magnitudes.
+        unsafe { std::ptr::write(effect.u.as_mut_ptr().cast(), magnitudes) };
+        // SAFETY: EVIOCSFF reads and updates one ff_effect.
+        let uploaded = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                EVIOCSFF,
+                &mut effect as *mut ff_effect,
+            )
+        };
+        if uploaded < 0 {
+            return false;
+        }
+        self.effect = Some(effect.id);
+
+        // SAFETY: input_event is plain integers.
+        let mut play: input_event = unsafe { std::mem::zeroed() };
+        play.type_ = EV_FF;
+        play.code = effect.id as u16;
+        play.value = 1;
+        // SAFETY: reading the bytes of a plain struct.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (&play as *const input_event).cast::<u8>(),
+                std::mem::size_of::<input_event>(),
+            )
+        };
+        (&self.file).write_all(bytes).is_ok()
+    }
+
+    /// Best effort: a battery the driver exposes under the input device.
+    fn power(&self) -> PowerInfo {
+        let Some(node) = self.path.file_name() else {
+            return PowerInfo::Unknown;
+        };
+        let supplies = Path::new("/sys/class/input")
+            .join(node)
+            .join("device/device/power_supply");
+        let Some(supply) = std::fs::read_dir(supplies)
+            .ok()
+            .and_then(|mut dir| dir.next())
+            .and_then(Result::ok)
+        else {
+            return PowerInfo::Unknown;
+        };
+        let read = |file| std::fs::read_to_string(supply.path().join(file)).unwrap_or_default();
+        match read("status").trim() {
+            "Charging" | "Full" => PowerInfo::Wired,
+            "Discharging" | "Not charging" => {
+                let level = match read("capacity").trim().parse::<u8>() {
+                    Ok(0..=5) => BatteryLevel::Empty,
+                    Ok(6..=40) => BatteryLevel::Low,
+                    Ok(41..=75) => BatteryLevel::Medium,
+                    Ok(_) => BatteryLevel::Full,
+                    Err(_) => return PowerInfo::Unknown,
+                };
+                PowerInfo::Battery { level }
+            }
+            _ => PowerInfo::Unknown,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Evdev {
+    devices: Vec<Device>,
+    /// Nodes opened once and found not to be gamepads.
+    ignored: HashSet<PathBuf>,
+    next_id: u32,
+    last_rescan: Option<Instant>,
+    /// Hot-plug changes found outside a poll, reported by the next one.
+    changes: Vec<(u32, RawEvent)>,
+}
+
+impl Evdev {
+    fn rescan(&mut self) {
+        self.last_rescan = Some(Instant::now());
+        let Ok(dir) = std::fs::read_dir(INPUT_DIR) else {
+            return;
+        };
+        let present: HashSet<PathBuf> = dir
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("event"))
+            .map(|e| e.path())
+            .collect();
+        self.ignored.retain(|p| present.contains(p));
+
+        let changes = &mut self.changes;
+        self.devices.retain(|d| {
+            let alive = present.contains(&d.path);
+            if !alive {
+                changes.push((d.id, RawEvent::Disconnected));
+            }
+            alive
+        });
+        for path in present {
+            if self.ignored.contains(&path) || self.devices.iter().any(|d| d.path == path) {
+                continue;
+            }
+            match Device::open(&path, self.next_id) {
+                Ok(Some(device)) => {
+                    self.next_id += 1;
+                    self.changes.push((
+                        device.id,
+                        RawEvent::Connected {
+                            name: device.name.clone(),
+                        },
+                    ));
+                    self.devices.push(device);
+                }
+                Ok(None) => {
+                    self.ignored.insert(path);
+                }
+                // Most likely no access yet; try again next rescan.
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+impl Backend for Evdev {
+    fn poll(&mut self, events: &mut Vec<(u32, RawEvent)>) {
+        if self
+            .last_rescan
+            .is_none_or(|at| at.elapsed() >= RESCAN_INTERVAL)
+        {
+            self.rescan();
+        }
+        events.append(&mut self.changes);
+        self.devices.retain_mut(|d| {
+            let alive = d.read(events).is_ok();
+            if !alive {
+                events.push((d.id, RawEvent::Disconnected));
+            }
+            alive
+        });
+    }
+
+    fn gamepads(&mut self) -> Vec<GamepadInfo> {
+        self.rescan();
+        self.devices
+            .iter()
+            .map(|d| GamepadInfo {
+                id: d.id,
+                name: d.name.clone(),
+                power: d.power(),
+                rumble: d.rumble,
+            })
+            .collect()
+    }
+
+    fn rumble(&mut self, id: u32, strong: f32, weak: f32, duration: Duration) -> Option<bool> {
+        let device = self.devices.iter_mut().find(|d| d.id == id)?;
+        Some(device.rumble(strong, weak, duration))
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// Buttons by position, Xbox layout: `south` is A, `east` is B.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Button {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    /// Triggers are buttons as well as axes, pressed past a small threshold.
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    /// The guide button; XInput doesn't report it.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+}
+
+/// Sticks run -1 to 1 with Y positive up; triggers run 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Axis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 6] = [
+        Axis::LeftStickX,
+        Axis::LeftStickY,
+        Axis::RightStickX,
+        Axis::RightStickY,
+        Axis::LeftTrigger,
+        Axis::RightTrigger,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// What a backend reports, before deadzones and coalescing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawEvent {
+    Connected {
+        name: String,
+    },
+    Disconnected,
+    Button {
+        button: Button,
+        pressed: bool,
+        value: f32,
+    },
+    Axis {
+        axis: Axis,
+        value: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GamepadEventKind {
+    Connected { name: String },
+    Disconnected,
+    ButtonDown { button: Button, value: f32 },
+    ButtonUp { button: Button, value: f32 },
+    AxisMoved { axis: Axis, value: f32 },
+}
+
+/// The `gamepad-event` payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GamepadEvent {
+    pub gamepad_id: u32,
+    #[serde(flatten)]
+    pub event: GamepadEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatteryLevel {
+    Empty,
+    Low,
+    Medium,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum PowerInfo {
+    Unknown,
+    Wired,
+    Battery { level: BatteryLevel },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GamepadInfo {
+    pub id: u32,
+    pub name: String,
+    pub power: PowerInfo,
+    /// Whether `rumble` does anything on this pad.
+    pub rumble: bool,
+}
@@ -0,0 +1,342 @@
+//! The polling thread. Buttons and hot-plug go out as they happen; axes are
+//! coalesced to the latest value per pad and axis and flushed at a fixed
+//! rate, so a stick flick costs a handful of events rather than one per
+//! poll.
+
+use super::backend::Backend;
+use super::event::{Axis, GamepadEvent, GamepadEventKind, RawEvent};
+use super::GamepadError;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const GAMEPAD_EVENT: &str = "gamepad-event";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(4);
+/// At most one `axisMoved` per pad and axis this often.
+const AXIS_FLUSH_INTERVAL: Duration = Duration::from_millis(8);
+/// Smaller moves than this since the last `axisMoved` aren't reported,
+/// except onto rest or full deflection.
+const AXIS_EPSILON: f32 = 0.004;
+
+pub const DEFAULT_STICK_DEADZONE: f32 = 0.15;
+pub const DEFAULT_TRIGGER_DEADZONE: f32 = 0.05;
+
+pub struct Shared {
+    pub backend: Mutex<Box<dyn Backend>>,
+    /// By `Axis::index`.
+    pub deadzones: Mutex<[f32; 6]>,
+}
+
+impl Shared {
+    fn default_deadzones() -> [f32; 6] {
+        Axis::ALL.map(|axis| match axis {
+            Axis::LeftTrigger | Axis::RightTrigger => DEFAULT_TRIGGER_DEADZONE,
+            _ => DEFAULT_STICK_DEADZONE,
+        })
+    }
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            backend: Mutex::new(super::backend::open()),
+            deadzones: Mutex::new(Self::default_deadzones()),
+        }
+    }
+}
+
+/// Zero inside the deadzone, rescaled to the full range outside it so
+/// there's no jump at its edge.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+#[derive(Default)]
+struct Coalescer {
+    pending: HashMap<(u32, Axis), f32>,
+    sent: HashMap<(u32, Axis), f32>,
+}
+
+impl Coalescer {
+    fn push(
+        &mut self,
+        id: u32,
+        event: RawEvent,
+        deadzones: &[f32; 6],
+        emit: &mut impl FnMut(u32, GamepadEventKind),
+    ) {
+        let kind = match event {
+            RawEvent::Axis { axis, value } => {
+                let value = apply_deadzone(value, deadzones[axis.index()]);
+                self.pending.insert((id, axis), value);
+                return;
+            }
+            RawEvent::Button {
+                button,
+                pressed: true,
+                value,
+            } => GamepadEventKind::ButtonDown { button, value },
+            RawEvent::Button {
+                button,
+                pressed: false,
+                value,
+            } => GamepadEventKind::ButtonUp { button, value },
+            RawEvent::Connected { name } => {
+                self.forget(id);
+                GamepadEventKind::Connected { name }
+            }
+            RawEvent::Disconnected => {
+                self.forget(id);
+                GamepadEventKind::Disconnected
+            }
+        };
+        emit(id, kind);
+    }
+
+    fn forget(&mut self, id: u32) {
+        self.pending.retain(|(pad, _), _| *pad != id);
+        self.sent.retain(|(pad, _), _| *pad != id);
+    }
+
+    fn flush(&mut self, emit: &mut impl FnMut(u32, GamepadEventKind)) {
+        for ((id, axis), value) in self.pending.drain() {
+            let last = self.sent.get(&(id, axis)).copied().unwrap_or(0.0);
+            let settled = (value == 0.0 || value.abs() == 1.0) && value != last;
+            if settled || (value - last).abs() >= AXIS_EPSILON {
+                self.sent.insert((id, axis), value);
+                emit(id, GamepadEventKind::AxisMoved { axis, value });
+            }
+        }
+    }
+}
+
+pub struct Listener {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Listener {
+    pub fn spawn<R: Runtime>(app: AppHandle<R>, shared: Arc<Shared>) -> Result<Self, GamepadError> {
+        let (stop, stopped) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("gamepad".into())
+            .spawn(move || run(&app, &shared, stopped))
+            .map_err(GamepadError::Spawn)?;
+        Ok(Self { stop, thread })
+    }
+
+    pub fn stop(self) {
+        drop(self.stop);
+        let _ = self.thread.join();
+    }
+}
+
+fn run<R: Runtime>(app: &AppHandle<R>, shared: &Shared, stopped: mpsc::Receiver<()>) {
+    let mut emit = |gamepad_id, event| {
+        let _ = app.emit(GAMEPAD_EVENT, GamepadEvent { gamepad_id, event });
+    };
+    let mut raw = Vec::new();
+
+    // Pads already plugged in are announced up front. Anything they did
+    // before the listener started is dropped.
+    let pads = {
+        let mut backend = shared.backend.lock().unwrap();
+        let pads = backend.gamepads();
+        backend.poll(&mut raw);
+        raw.clear();
+        pads
+    };
+    for pad in pads {
+        emit(pad.id, GamepadEventKind::Connected { name: pad.name });
+    }
+
+    let mut coalescer = Coalescer::default();
+    let mut next_flush = Instant::now() + AXIS_FLUSH_INTERVAL;
+    loop {
+        shared.backend.lock().unwrap().poll(&mut raw);
+        let deadzones = *shared.deadzones.lock().unwrap();
+        for (id, event) in raw.drain(..) {
+            coalescer.push(id, event, &deadzones, &mut emit);
+        }
+        if Instant::now() >= next_flush {
+            coalescer.flush(&mut emit);
+            next_flush = Instant::now() + AXIS_FLUSH_INTERVAL;
+        }
+        match stopped.recv_timeout(POLL_INTERVAL) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::event::{Button, GamepadInfo};
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Hands out one scripted batch of events per poll.
+    #[derive(Default)]
+    struct Fake {
+        polls: VecDeque<Vec<(u32, RawEvent)>>,
+    }
+
+    impl Backend for Fake {
+        fn poll(&mut self, events: &mut Vec<(u32, RawEvent)>) {
+            events.extend(self.polls.pop_front().unwrap_or_default());
+        }
+
+        fn gamepads(&mut self) -> Vec<GamepadInfo> {
+            Vec::new()
+        }
+
+        fn rumble(
+            &mut self,
+            _id: u32,
+            _strong: f32,
+            _weak: f32,
+            _duration: Duration,
+        ) -> Option<bool> {
+            None
+        }
+    }
+
+    /// Polls `fake` `polls` times through `coalescer` as the listener
+    /// does, then flushes, returning everything emitted in order.
+    fn drive(
+        fake: &mut Fake,
+        coalescer: &mut Coalescer,
+        polls: usize,
+    ) -> Vec<(u32, GamepadEventKind)> {
+        let deadzones = Shared::default_deadzones();
+        let mut emitted = Vec::new();
+        let mut emit = |id, kind| emitted.push((id, kind));
+        let mut raw = Vec::new();
+        for _ in 0..polls {
+            fake.poll(&mut raw);
+            for (id, event) in raw.drain(..) {
+                coalescer.push(id, event, &deadzones, &mut emit);
+            }
+        }
+        coalescer.flush(&mut emit);
+        emitted
+    }
+
+    fn axis(value: f32) -> RawEvent {
+        RawEvent::Axis {
+            axis: Axis::LeftStickX,
+            value,
+        }
+    }
+
+    fn moved(value: f32) -> GamepadEventKind {
+        GamepadEventKind::AxisMoved {
+            axis: Axis::LeftStickX,
+            value,
+        }
+    }
+
+    #[test]
+    fn deadzones_zero_small_values_and_rescale_the_rest() {
+        assert_eq!(apply_deadzone(0.1, 0.15), 0.0);
+        assert_eq!(apply_deadzone(-0.15, 0.15), 0.0);
+        assert!((apply_deadzone(-0.575, 0.15) + 0.5).abs() < 1e-6);
+        assert!(apply_deadzone(0.151, 0.15) < 0.002);
+        assert_eq!(apply_deadzone(1.0, 0.15), 1.0);
+        assert_eq!(apply_deadzone(-1.3, 0.15), -1.0);
+        assert_eq!(apply_deadzone(0.4, 0.0), 0.4);
+    }
+
+    #[test]
+    fn buttons_go_out_at_once_and_axes_only_as_their_latest_value() {
+        let press = RawEvent::Button {
+            button: Button::South,
+            pressed: true,
+            value: 1.0,
+        };
+        let mut fake = Fake {
+            polls: VecDeque::from([
+                vec![(1, axis(0.3))],
+                vec![(1, axis(0.5)), (1, press), (2, axis(-1.0))],
+                vec![(1, axis(0.915))],
+            ]),
+        };
+        let mut emitted = drive(&mut fake, &mut Coalescer::default(), 3);
+        assert_eq!(
+            emitted.remove(0),
+            (
+                1,
+                GamepadEventKind::ButtonDown {
+                    button: Button::South,
+                    value: 1.0
+                }
+            )
+        );
+        emitted.sort_by_key(|(id, _)| *id);
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[1], (2, moved(-1.0)));
+        let GamepadEventKind::AxisMoved { value, .. } = emitted[0].1 else {
+            panic!("{:?}", emitted[0]);
+        };
+        assert!((value - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tiny_moves_are_dropped_but_coming_to_rest_is_not() {
+        let mut coalescer = Coalescer::default();
+        let mut fake = Fake {
+            polls: VecDeque::from([
+                vec![(1, axis(0.575))],
+                vec![(1, axis(0.577))],
+                vec![(1, axis(0.152))],
+                vec![(1, axis(0.149))],
+            ]),
+        };
+        let first = drive(&mut fake, &mut coalescer, 1);
+        assert_eq!(first.len(), 1);
+        assert!(drive(&mut fake, &mut coalescer, 1).is_empty());
+        assert_eq!(drive(&mut fake, &mut coalescer, 1).len(), 1);
+        // Into the deadzone is a move of under epsilon, but rest is always
+        // sent.
+        assert_eq!(drive(&mut fake, &mut coalescer, 1), [(1, moved(0.0))]);
+        assert!(drive(&mut fake, &mut coalescer, 1).is_empty());
+    }
+
+    #[test]
+    fn unplugging_drops_pending_axes_and_what_was_sent() {
+        let mut coalescer = Coalescer::default();
+        let mut fake = Fake {
+            polls: VecDeque::from([
+                vec![(1, axis(1.0))],
+                vec![(1, axis(-1.0)), (1, RawEvent::Disconnected)],
+                vec![
+                    (
+                        1,
+                        RawEvent::Connected {
+                            name: "Pad".to_string(),
+                        },
+                    ),
+                    (1, axis(1.0)),
+                ],
+            ]),
+        };
+        assert_eq!(drive(&mut fake, &mut coalescer, 1), [(1, moved(1.0))]);
+        assert_eq!(
+            drive(&mut fake, &mut coalescer, 1),
+            [(1, GamepadEventKind::Disconnected)]
+        );
+        // Full deflection again is news to whoever saw the pad reconnect.
+        let emitted = drive(&mut fake, &mut coalescer, 1);
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[1], (1, moved(1.0)));
+    }
+}
@@ -0,0 +1,119 @@
+mod backend;
+mod error;
+#[cfg(target_os = "linux")]
+mod evdev;
+mod event;
+mod listener;
+#[cfg(windows)]
+mod xinput;
+
+pub use error::GamepadError;
+pub use event::{Axis, GamepadInfo};
+
+use listener::{Listener, Shared};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Manager, Runtime, State};
+
+const MAX_DEADZONE: f32 = 0.9;
+const MAX_RUMBLE_MS: u32 = 10_000;
+
+/// Gamepads through the platform's native API rather than the webview's
+/// Gamepad API. The listener thread streams `gamepad-event`s while running.
+#[derive(Default)]
+pub struct GamepadState {
+    shared: Arc<Shared>,
+    listener: Mutex<Option<Listener>>,
+}
+
+/// Starts streaming `gamepad-event`s: `connected` (also for pads already
+/// plugged in), `disconnected`, `buttonDown` / `buttonUp` and `axisMoved`
+/// with deadzones applied, each tagged with the pad's id.
+#[tauri::command]
+pub async fn start_gamepad_listener(
+    app: tauri::AppHandle,
+    gamepads: State<'_, GamepadState>,
+) -> Result<(), GamepadError> {
+    let mut listener = gamepads.listener.lock().unwrap();
+    if listener.is_some() {
+        return Err(GamepadError::AlreadyRunning);
+    }
+    *listener = Some(Listener::spawn(app, gamepads.shared.clone())?);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_gamepad_listener(app: tauri::AppHandle) -> Result<(), GamepadError> {
+    let listener = app.state::<GamepadState>().listener.lock().unwrap().take();
+    let listener = listener.ok_or(GamepadError::NotRunning)?;
+    // Joining waits out at most one poll.
+    let _ = tauri::async_runtime::spawn_blocking(move || listener.stop()).await;
+    Ok(())
+}
+
+/// Sets one axis's deadzone, as a fraction of its range. Values inside it
+/// read as 0; values outside are rescaled to start from 0.
+#[tauri::command]
+pub fn set_gamepad_deadzone(
+    gamepads: State<'_, GamepadState>,
+    axis: Axis,
+    value: f32,
+) -> Result<(), GamepadError> {
+    if !(0.0..=MAX_DEADZONE).contains(&value) {
+        return Err(GamepadError::InvalidDeadzone {
+            value,
+            max: MAX_DEADZONE,
+        });
+    }
+    gamepads.shared.deadzones.lock().unwrap()[axis.index()] = value;
+    Ok(())
+}
+
+/// The connected pads, with battery state where the platform reports it.
+#[tauri::command]
+pub async fn list_gamepads(
+    gamepads: State<'_, GamepadState>,
+) -> Result<Vec<GamepadInfo>, GamepadError> {
+    Ok(gamepads.shared.backend.lock().unwrap().gamepads())
+}
+
+/// Runs the pad's motors at `strong` (low frequency) and `weak` (high
+/// frequency), each 0 to 1, for `duration_ms`. Does nothing on pads without
+/// force feedback.
+#[tauri::command]
+pub async fn rumble(
+    gamepads: State<'_, GamepadState>,
+    gamepad_id: u32,
+    strong: f32,
+    weak: f32,
+    duration_ms: u32,
+) -> Result<(), GamepadError> {
+    if ![strong, weak].iter().all(|m| (0.0..=1.0).contains(m)) {
+        return Err(GamepadError::InvalidRumble(
+            "magnitudes must be 0 to 1".to_string(),
+        ));
+    }
+    if duration_ms > MAX_RUMBLE_MS {
+        return Err(GamepadError::InvalidRumble(format!(
+            "duration must be at most {} ms",
+            MAX_RUMBLE_MS
+        )));
+    }
+    let duration = Duration::from_millis(duration_ms as u64);
+    gamepads
+        .shared
+        .backend
+        .lock()
+        .unwrap()
+        .rumble(gamepad_id, strong, weak, duration)
+        .map(|_| ())
+        .ok_or(GamepadError::UnknownGamepad(gamepad_id))
+}
+
+/// Stops the listener. Called on exit.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let listener = app.state::<GamepadState>().listener.lock().unwrap().take();
+    if let Some(listener) = listener {
+        listener.stop();
+    }
+}
@@ -0,0 +1,273 @@
+//! XInput: up to four Xbox-style pads, by user index. Polled state is
+//! diffed into events. Checking an empty slot is slow, so those are only
+//! checked once per rescan interval.
+
+use super::backend::{button_edge, Backend, RESCAN_INTERVAL, TRIGGER_THRESHOLD};
+use super::event::{Axis, BatteryLevel, Button, GamepadInfo, PowerInfo, RawEvent};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const SLOTS: u32 = 4;
+const ERROR_SUCCESS: u32 = 0;
+const BATTERY_DEVTYPE_GAMEPAD: u8 = 0;
+const NAME: &str = "Xbox Controller";
+
+const BUTTONS: [(u16, Button); 14] = [
+    (0x0001, Button::DpadUp),
+    (0x0002, Button::DpadDown),
+    (0x0004, Button::DpadLeft),
+    (0x0008, Button::DpadRight),
+    (0x0010, Button::Start),
+    (0x0020, Button::Select),
+    (0x0040, Button::LeftThumb),
+    (0x0080, Button::RightThumb),
+    (0x0100, Button::LeftBumper),
+    (0x0200, Button::RightBumper),
+    (0x1000, Button::South),
+    (0x2000, Button::East),
+    (0x4000, Button::West),
+    (0x8000, Button::North),
+];
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Gamepad {
+    buttons: u16,
+    left_trigger: u8,
+    right_trigger: u8,
+    thumb_lx: i16,
+    thumb_ly: i16,
+    thumb_rx: i16,
+    thumb_ry: i16,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct State {
+    packet_number: u32,
+    gamepad: Gamepad,
+}
+
+#[repr(C)]
+struct Vibration {
+    left_motor: u16,
+    right_motor: u16,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct BatteryInformation {
+    battery_type: u8,
+    battery_level: u8,
+}
+
+#[link(name = "xinput")]
+extern "system" {
+    fn XInputGetState(user_index: u32, state: *mut State) -> u32;
+    fn XInputSetState(user_index: u32, vibration: *mut Vibration) -> u32;
+    fn XInputGetBatteryInformation(
+        user_index: u32,
+        dev_type: u8,
+        info: *mut BatteryInformation,
+    ) -> u32;
+}
+
+fn get_state(slot: u32) -> Option<Gamepad> {
+    let mut state = State::default();
+    // SAFETY: `state` is a valid, writable XINPUT_STATE.
+    let result = unsafe { XInputGetState(slot, &mut state) };
+    (result == ERROR_SUCCESS).then_some(state.gamepad)
+}
+
+fn set_motors(slot: u32, strong: f32, weak: f32) {
+    let mut vibration = Vibration {
+        left_motor: (strong * 65535.0) as u16,
+        right_motor: (weak * 65535.0) as u16,
+    };
+    // SAFETY: `vibration` is a valid XINPUT_VIBRATION.
+    unsafe { XInputSetState(slot, &mut vibration) };
+}
+
+fn power(slot: u32) -> PowerInfo {
+    let mut info = BatteryInformation::default();
+    // SAFETY: `info` is a valid, writable XINPUT_BATTERY_INFORMATION.
+    let result = unsafe { XInputGetBatteryInformation(slot, BATTERY_DEVTYPE_GAMEPAD, &mut info) };
+    if result != ERROR_SUCCESS {
+        return PowerInfo::Unknown;
+    }
+    let level = match info.battery_level {
+        0 => BatteryLevel::Empty,
+        1 => BatteryLevel::Low,
+        2 => BatteryLevel::Medium,
+        _ => BatteryLevel::Full,
+    };
+    match info.battery_type {
+        1 => PowerInfo::Wired,
+        2 | 3 => PowerInfo::Battery { level },
+        _ => PowerInfo::Unknown,
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    last: Option<Gamepad>,
+    left_trigger: bool,
+    right_trigger: bool,
+}
+
+#[derive(Default)]
+pub struct XInput {
+    slots: [Slot; SLOTS as usize],
+    last_rescan: Option<Instant>,
+    /// When each slot's motors stop. `Some` while that slot's stop timer
+    /// runs, so there is at most one per slot however often rumble is
+    /// called.
+    rumble_until: Arc<Mutex<[Option<Instant>; SLOTS as usize]>>,
+}
+
+impl XInput {
+    fn check(&mut self, slot: u32, events: &mut Vec<(u32, RawEvent)>) {
+        let s = &mut self.slots[slot as usize];
+        let Some(now) = get_state(slot) else {
+            if s.last.take().is_some() {
+                *s = Slot::default();
+                events.push((slot, RawEvent::Disconnected));
+            }
+            return;
+        };
+        let before = match s.last.replace(now) {
+            Some(before) => before,
+            None => {
+                events.push((
+                    slot,
+                    RawEvent::Connected {
+                        name: NAME.to_string(),
+                    },
+                ));
+                Gamepad::default()
+            }
+        };
+        if before == now {
+            return;
+        }
+        let mut push = |event| events.push((slot, event));
+
+        for (mask, button) in BUTTONS {
+            let pressed = now.buttons & mask != 0;
+            if pressed != (before.buttons & mask != 0) {
+                push(RawEvent::Button {
+                    button,
+                    pressed,
+                    value: pressed as u8 as f32,
+                });
+            }
+        }
+        let triggers = [
+            (
+                before.left_trigger,
+                now.left_trigger,
+                Axis::LeftTrigger,
+                Button::LeftTrigger,
+                &mut s.left_trigger,
+            ),
+            (
+                before.right_trigger,
+                now.right_trigger,
+                Axis::RightTrigger,
+                Button::RightTrigger,
+                &mut s.right_trigger,
+            ),
+        ];
+        for (was, is, axis, button, pressed) in triggers {
+            if was != is {
+                let value = is as f32 / 255.0;
+                push(RawEvent::Axis { axis, value });
+                if let Some(edge) = button_edge(pressed, value >= TRIGGER_THRESHOLD, button, value)
+                {
+                    push(edge);
+                }
+            }
+        }
+        let sticks = [
+            (before.thumb_lx, now.thumb_lx, Axis::LeftStickX),
+            (before.thumb_ly, now.thumb_ly, Axis::LeftStickY),
+            (before.thumb_rx, now.thumb_rx, Axis::RightStickX),
+            (before.thumb_ry, now.thumb_ry, Axis::RightStickY),
+        ];
+        for (was, is, axis) in sticks {
+            if was != is {
+                let value = (is as f32 / 32767.0).max(-1.0);
+                push(RawEvent::Axis { axis, value });
+            }
+        }
+    }
+
+    fn rescan_due(&mut self) -> bool {
+        let due = self
+            .last_rescan
+            .is_none_or(|at| at.elapsed() >= RESCAN_INTERVAL);
+        if due {
+            self.last_rescan = Some(Instant::now());
+        }
+        due
+    }
+}
+
+impl Backend for XInput {
+    fn poll(&mut self, events: &mut Vec<(u32, RawEvent)>) {
+        let rescan = self.rescan_due();
+        for slot in 0..SLOTS {
+            if rescan || self.slots[slot as usize].last.is_some() {
+                self.check(slot, events);
+            }
+        }
+    }
+
+    fn gamepads(&mut self) -> Vec<GamepadInfo> {
+        (0..SLOTS)
+            .filter(|&slot| self.slots[slot as usize].last.is_some() || get_state(slot).is_some())
+            .map(|slot| GamepadInfo {
+                id: slot,
+                name: NAME.to_string(),
+                power: power(slot),
+                rumble: true,
+            })
+            .collect()
+    }
+
+    fn rumble(&mut self, id: u32, strong: f32, weak: f32, duration: Duration) -> Option<bool> {
+        if id >= SLOTS || get_state(id).is_none() {
+            return None;
+        }
+        // XInput keeps the motors running until told otherwise.
+        let mut until = self.rumble_until.lock().unwrap();
+        set_motors(id, strong, weak);
+        let timer_running = until[id as usize].is_some();
+        until[id as usize] = Some(Instant::now() + duration);
+        if !timer_running {
+            let rumble_until = self.rumble_until.clone();
+            let spawned = std::thread::Builder::new()
+                .name("gamepad-rumble".into())
+                .spawn(move || stop_rumble_at_deadline(&rumble_until, id));
+            if spawned.is_err() {
+                set_motors(id, 0.0, 0.0);
+                until[id as usize] = None;
+            }
+        }
+        Some(true)
+    }
+}
+
+/// Polls rather than sleeping the whole way, since a later, shorter rumble
+/// can move the deadline closer.
+fn stop_rumble_at_deadline(rumble_until: &Mutex<[Option<Instant>; SLOTS as usize]>, slot: u32) {
+    loop {
+        std::thread::sleep(Duration::from_millis(10));
+        let mut until = rumble_until.lock().unwrap();
+        if until[slot as usize].is_none_or(|at| Instant::now() >= at) {
+            set_motors(slot, 0.0, 0.0);
+            until[slot as usize] = None;
+            return;
+        }
+    }
+}
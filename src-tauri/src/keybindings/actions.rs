@@ -0,0 +1,41 @@
+use super::model::Modifiers;
+
+/// A bindable action and its default key. Keys use `KeyboardEvent.code`
+/// names; mouse buttons are `Mouse0` (left) .. `Mouse4`.
+pub struct ActionDef {
+    pub id: &'static str,
+    pub default_key: &'static str,
+    pub default_modifiers: Modifiers,
+}
+
+const fn action(id: &'static str, default_key: &'static str) -> ActionDef {
+    ActionDef {
+        id,
+        default_key,
+        default_modifiers: Modifiers::NONE,
+    }
+}
+
+/// Canonical action list, in menu order. Profiles saved by older builds pick
+/// up actions added here with their default key.
+pub const ACTIONS: &[ActionDef] = &[
+    action("moveForward", "KeyW"),
+    action("moveBackward", "KeyS"),
+    action("moveLeft", "KeyA"),
+    action("moveRight", "KeyD"),
+    action("jump", "Space"),
+    action("sprint", "ShiftLeft"),
+    action("crouch", "KeyC"),
+    action("prone", "KeyZ"),
+    action("fire", "Mouse0"),
+    action("aim", "Mouse2"),
+    action("pickup", "KeyF"),
+    action("weaponRifle", "Digit1"),
+    action("weaponGrenade", "Digit2"),
+    action("quickGrenade", "KeyG"),
+    action("cycleWeather", "KeyT"),
+];
+
+pub fn find_action(id: &str) -> Option<&'static ActionDef> {
+    ACTIONS.iter().find(|a| a.id == id)
+}
@@ -0,0 +1,63 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the keybinding commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum KeybindingError {
+    #[error("Could not locate the config directory: {0}")]
+    NoConfigDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Unknown action {0:?}")]
+    UnknownAction(String),
+
+    #[error("Invalid key {0:?}")]
+    InvalidKey(String),
+
+    #[error("Invalid profile name {0:?}: use 1-32 letters, digits, spaces, '-' or '_'")]
+    InvalidProfileName(String),
+
+    #[error("No keybinding profile named {0:?}")]
+    ProfileNotFound(String),
+
+    #[error("Invalid keybinding file {path:?}: {reason}")]
+    Invalid { path: PathBuf, reason: String },
+}
+
+impl KeybindingError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        KeybindingError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KeybindingError::NoConfigDir(_) => "noConfigDir",
+            KeybindingError::Io { .. } => "io",
+            KeybindingError::UnknownAction(_) => "unknownAction",
+            KeybindingError::InvalidKey(_) => "invalidKey",
+            KeybindingError::InvalidProfileName(_) => "invalidProfileName",
+            KeybindingError::ProfileNotFound(_) => "profileNotFound",
+            KeybindingError::Invalid { .. } => "invalid",
+        }
+    }
+}
+
+impl Serialize for KeybindingError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("KeybindingError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,99 @@
+mod actions;
+mod error;
+mod model;
+mod store;
+
+pub use error::KeybindingError;
+pub use model::{Binding, Modifiers, SetBindingOutcome};
+pub use store::KeybindingStore;
+
+use model::BindingSet;
+use store::KeybindingPaths;
+use tauri::State;
+
+/// Current bindings, one per canonical action in menu order.
+#[tauri::command]
+pub async fn get_keybindings(
+    app: tauri::AppHandle,
+    store: State<'_, KeybindingStore>,
+) -> Result<Vec<Binding>, KeybindingError> {
+    let paths = KeybindingPaths::resolve(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    Ok(store::load_active(&paths.active).bindings().to_vec())
+}
+
+/// Binds `action` to `key`. When the chord is already taken the bindings are
+/// left untouched and the owning action is returned as a `conflict`; pass
+/// `swap: true` to give that action this one's old key instead.
+#[tauri::command]
+pub async fn set_keybinding(
+    app: tauri::AppHandle,
+    store: State<'_, KeybindingStore>,
+    action: String,
+    key: String,
+    modifiers: Option<Modifiers>,
+    swap: Option<bool>,
+) -> Result<SetBindingOutcome, KeybindingError> {
+    let paths = KeybindingPaths::resolve(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    let mut set = store::load_active(&paths.active);
+    let outcome = set.set(
+        &action,
+        &key,
+        modifiers.unwrap_or_default(),
+        swap.unwrap_or(false),
+    )?;
+    if matches!(outcome, SetBindingOutcome::Bound { .. }) {
+        store::write(&paths.active, &set)?;
+    }
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn reset_keybindings(
+    app: tauri::AppHandle,
+    store: State<'_, KeybindingStore>,
+) -> Result<Vec<Binding>, KeybindingError> {
+    let paths = KeybindingPaths::resolve(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    let set = BindingSet::default();
+    store::write(&paths.active, &set)?;
+    Ok(set.bindings().to_vec())
+}
+
+/// Saves the current bindings as profile `name`, overwriting any existing one.
+#[tauri::command]
+pub async fn save_keybinding_profile(
+    app: tauri::AppHandle,
+    store: State<'_, KeybindingStore>,
+    name: String,
+) -> Result<(), KeybindingError> {
+    let paths = KeybindingPaths::resolve(&app)?;
+    let profile = paths.profile(&name)?;
+    let _guard = store.lock.lock().unwrap();
+    let set = store::load_active(&paths.active);
+    store::write(&profile, &set)
+}
+
+/// Makes profile `name` the active bindings and returns them.
+#[tauri::command]
+pub async fn load_keybinding_profile(
+    app: tauri::AppHandle,
+    store: State<'_, KeybindingStore>,
+    name: String,
+) -> Result<Vec<Binding>, KeybindingError> {
+    let paths = KeybindingPaths::resolve(&app)?;
+    let profile = paths.profile(&name)?;
+    let _guard = store.lock.lock().unwrap();
+    let set = store::load_profile(&profile, &name)?;
+    store::write(&paths.active, &set)?;
+    Ok(set.bindings().to_vec())
+}
+
+#[tauri::command]
+pub async fn list_keybinding_profiles(
+    app: tauri::AppHandle,
+) -> Result<Vec<String>, KeybindingError> {
+    let paths = KeybindingPaths::resolve(&app)?;
+    store::list_profiles(&paths.profiles)
+}
@@ -0,0 +1,137 @@
+use super::actions::{find_action, ACTIONS};
+use super::KeybindingError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Binding {
+    pub action: String,
+    pub key: String,
+    #[serde(default)]
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    fn same_chord(&self, key: &str, modifiers: Modifiers) -> bool {
+        self.key == key && self.modifiers == modifiers
+    }
+}
+
+/// Result of [`BindingSet::set`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SetBindingOutcome {
+    Bound {
+        bindings: Vec<Binding>,
+    },
+    /// The chord already belongs to `owner`; nothing was changed. Retry with
+    /// `swap` to exchange the two bindings.
+    Conflict {
+        owner: String,
+        key: String,
+    },
+}
+
+/// One binding per canonical action, in [`ACTIONS`] order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingSet {
+    bindings: Vec<Binding>,
+}
+
+impl Default for BindingSet {
+    fn default() -> Self {
+        Self {
+            bindings: ACTIONS
+                .iter()
+                .map(|a| Binding {
+                    action: a.id.to_string(),
+                    key: a.default_key.to_string(),
+                    modifiers: a.default_modifiers,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl BindingSet {
+    /// Builds a set from stored bindings: entries for unknown actions are
+    /// dropped and actions without an entry get their default key.
+    pub fn from_stored(stored: Vec<Binding>) -> Self {
+        let mut set = BindingSet::default();
+        for binding in stored {
+            if let Some(slot) = set.bindings.iter_mut().find(|b| b.action == binding.action) {
+                if !binding.key.trim().is_empty() {
+                    *slot = binding;
+                }
+            }
+        }
+        set
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Binds `action` to `key` + `modifiers`. If another action already owns
+    /// the chord, either reports the conflict or, with `swap`, hands that
+    /// action the key `action` had before.
+    pub fn set(
+        &mut self,
+        action: &str,
+        key: &str,
+        modifiers: Modifiers,
+        swap: bool,
+    ) -> Result<SetBindingOutcome, KeybindingError> {
+        if find_action(action).is_none() {
+            return Err(KeybindingError::UnknownAction(action.to_string()));
+        }
+        let key = key.trim();
+        if key.is_empty() || key.chars().any(char::is_whitespace) {
+            return Err(KeybindingError::InvalidKey(key.to_string()));
+        }
+
+        let target = self
+            .bindings
+            .iter()
+            .position(|b| b.action == action)
+            .expect("binding sets cover every canonical action");
+        let owner = self
+            .bindings
+            .iter()
+            .position(|b| b.action != action && b.same_chord(key, modifiers));
+
+        if let Some(owner) = owner {
+            if !swap {
+                return Ok(SetBindingOutcome::Conflict {
+                    owner: self.bindings[owner].action.clone(),
+                    key: key.to_string(),
+                });
+            }
+            let previous = self.bindings[target].clone();
+            self.bindings[owner].key = previous.key;
+            self.bindings[owner].modifiers = previous.modifiers;
+        }
+
+        self.bindings[target].key = key.to_string();
+        self.bindings[target].modifiers = modifiers;
+        Ok(SetBindingOutcome::Bound {
+            bindings: self.bindings.clone(),
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use super::model::{Binding, BindingSet};
+use super::KeybindingError;
+use crate::fs_atomic::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Runtime;
+
+pub const ACTIVE_FILE: &str = "keybindings.json";
+pub const PROFILE_DIR: &str = "keybinding-profiles";
+
+const FORMAT_VERSION: u32 = 1;
+const MAX_PROFILE_NAME: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct BindingFile {
+    version: u32,
+    bindings: Vec<Binding>,
+}
+
+/// Serializes read-modify-write cycles on the keybinding files.
+#[derive(Default)]
+pub struct KeybindingStore {
+    pub(super) lock: Mutex<()>,
+}
+
+/// Where the active bindings and named binding profiles live inside the
+/// active player profile.
+pub struct KeybindingPaths {
+    pub active: PathBuf,
+    pub profiles: PathBuf,
+}
+
+impl KeybindingPaths {
+    pub fn resolve<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<Self, KeybindingError> {
+        let dir = crate::profiles::active_dir(app)
+            .map_err(|e| KeybindingError::NoConfigDir(e.to_string()))?;
+        Ok(Self {
+            active: dir.join(ACTIVE_FILE),
+            profiles: dir.join(PROFILE_DIR),
+        })
+    }
+
+    pub fn profile(&self, name: &str) -> Result<PathBuf, KeybindingError> {
+        Ok(self
+            .profiles
+            .join(format!("{}.json", validate_profile_name(name)?)))
+    }
+}
+
+/// Profile names double as file names, so keep them to a portable subset.
+fn validate_profile_name(name: &str) -> Result<&str, KeybindingError> {
+    let trimmed = name.trim();
+    let valid = !trimmed.is_empty()
+        && trimmed.chars().count() <= MAX_PROFILE_NAME
+        && trimmed
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+    if valid {
+        Ok(trimmed)
+    } else {
+        Err(KeybindingError::InvalidProfileName(name.to_string()))
+    }
+}
+
+/// Reads the active bindings; a missing or unreadable file gives the defaults.
+pub fn load_active(path: &Path) -> BindingSet {
+    match read(path) {
+        Ok(Some(set)) => set,
+        Ok(None) => BindingSet::default(),
+        Err(e) => {
+            log::warn!("{}; using default keybindings", e);
+            BindingSet::default()
+        }
+    }
+}
+
+pub fn load_profile(path: &Path, name: &str) -> Result<BindingSet, KeybindingError> {
+    read(path)?.ok_or_else(|| KeybindingError::ProfileNotFound(name.to_string()))
+}
+
+fn read(path: &Path) -> Result<Option<BindingSet>, KeybindingError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(KeybindingError::io(path, e)),
+    };
+    let file: BindingFile =
+        serde_json::from_slice(&bytes).map_err(|e| KeybindingError::Invalid {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+    Ok(Some(BindingSet::from_stored(file.bindings)))
+}
+
+pub fn write(path: &Path, set: &BindingSet) -> Result<(), KeybindingError> {
+    let file = BindingFile {
+        version: FORMAT_VERSION,
+        bindings: set.bindings().to_vec(),
+    };
+    let json = serde_json::to_vec_pretty(&file).map_err(|e| KeybindingError::Invalid {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    write_atomic(path, &json).map_err(|e| KeybindingError::io(path, e))
+}
+
+/// Names of saved profiles, sorted. A missing directory means none yet.
+pub fn list_profiles(dir: &Path) -> Result<Vec<String>, KeybindingError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(KeybindingError::io(dir, e)),
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let stem = path.file_stem()?.to_str()?;
+            validate_profile_name(stem).ok().map(str::to_string)
+        })
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    Ok(names)
+}
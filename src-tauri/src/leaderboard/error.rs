@@ -0,0 +1,55 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the leaderboard commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum LeaderboardError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid level id {0:?}")]
+    InvalidLevel(String),
+
+    #[error("Rejected score: {0}")]
+    Rejected(String),
+}
+
+impl LeaderboardError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        LeaderboardError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn rejected(reason: impl Into<String>) -> Self {
+        LeaderboardError::Rejected(reason.into())
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LeaderboardError::NoDataDir(_) => "noDataDir",
+            LeaderboardError::Io { .. } => "io",
+            LeaderboardError::InvalidLevel(_) => "invalidLevel",
+            LeaderboardError::Rejected(_) => "rejected",
+        }
+    }
+}
+
+impl Serialize for LeaderboardError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("LeaderboardError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,80 @@
+mod error;
+mod model;
+mod store;
+
+pub use error::LeaderboardError;
+pub use model::{RankedScore, ScoreSubmission};
+pub use store::LeaderboardStore;
+
+use crate::time::now_ms;
+use store::level_path;
+use tauri::State;
+
+const DEFAULT_TOP_LIMIT: usize = 10;
+const MAX_TOP_LIMIT: usize = 100;
+
+/// Validates and records a finished run, returning its rank on the level's
+/// table.
+#[tauri::command]
+pub async fn submit_score(
+    app: tauri::AppHandle,
+    store: State<'_, LeaderboardStore>,
+    entry: ScoreSubmission,
+) -> Result<RankedScore, LeaderboardError> {
+    let path = level_path(&app, &entry.level_id)?;
+    let entry = entry.validate(store.max_score(), now_ms())?;
+    let _guard = store.lock.lock().unwrap();
+    let rank = store::append(&path, &entry)?;
+    Ok(RankedScore { rank, entry })
+}
+
+/// The best `limit` scores on a level (default 10, at most 100). Equal
+/// scores are ordered by who got there first.
+#[tauri::command]
+pub async fn get_top_scores(
+    app: tauri::AppHandle,
+    store: State<'_, LeaderboardStore>,
+    level_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<RankedScore>, LeaderboardError> {
+    let path = level_path(&app, &level_id)?;
+    let limit = limit.unwrap_or(DEFAULT_TOP_LIMIT).min(MAX_TOP_LIMIT);
+    let _guard = store.lock.lock().unwrap();
+    Ok(store::top(&path, limit)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| RankedScore { rank: i + 1, entry })
+        .collect())
+}
+
+/// A player's best run on a level, or `None` if they haven't finished it.
+#[tauri::command]
+pub async fn get_player_best(
+    app: tauri::AppHandle,
+    store: State<'_, LeaderboardStore>,
+    level_id: String,
+    player_name: String,
+) -> Result<Option<RankedScore>, LeaderboardError> {
+    let path = level_path(&app, &level_id)?;
+    let _guard = store.lock.lock().unwrap();
+    Ok(store::player_best(&path, &player_name)?.map(|(rank, entry)| RankedScore { rank, entry }))
+}
+
+/// Wipes a level's table. Returns whether it had any entries.
+#[tauri::command]
+pub async fn clear_leaderboard(
+    app: tauri::AppHandle,
+    store: State<'_, LeaderboardStore>,
+    level_id: String,
+) -> Result<bool, LeaderboardError> {
+    let path = level_path(&app, &level_id)?;
+    let _guard = store.lock.lock().unwrap();
+    store::clear(&path)
+}
+
+/// Caps the score [`submit_score`] will accept; anything higher is rejected
+/// as tampered or bugged.
+#[tauri::command]
+pub fn set_leaderboard_score_cap(store: State<'_, LeaderboardStore>, max_score: u64) {
+    store.set_max_score(max_score);
+}
@@ -0,0 +1,133 @@
+use super::LeaderboardError;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+
+pub const DEFAULT_MAX_SCORE: u64 = 10_000_000;
+
+const MAX_NAME_CHARS: usize = 32;
+/// Runs longer than a day are a stuck timer, not a real score.
+const MAX_TIME_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// What the frontend submits. Numbers are signed so out-of-range values get
+/// a clear rejection instead of a deserialization error.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreSubmission {
+    pub player_name: String,
+    pub score: i64,
+    pub level_id: String,
+    /// Hits over shots fired, `0.0..=1.0`.
+    pub accuracy: f64,
+    pub time_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreEntry {
+    pub player_name: String,
+    pub score: u64,
+    pub level_id: String,
+    pub accuracy: f64,
+    pub time_ms: u64,
+    /// Stamped by the backend when the score is accepted.
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedScore {
+    /// 1-based.
+    pub rank: usize,
+    #[serde(flatten)]
+    pub entry: ScoreEntry,
+}
+
+impl ScoreSubmission {
+    pub fn validate(self, max_score: u64, created_at: u64) -> Result<ScoreEntry, LeaderboardError> {
+        let player_name = self.player_name.trim().to_string();
+        if player_name.is_empty() || player_name.chars().count() > MAX_NAME_CHARS {
+            return Err(LeaderboardError::rejected(format!(
+                "player name must be 1-{} characters",
+                MAX_NAME_CHARS
+            )));
+        }
+        if player_name.chars().any(char::is_control) {
+            return Err(LeaderboardError::rejected(
+                "player name contains control characters",
+            ));
+        }
+        if self.score < 0 || self.score as u64 > max_score {
+            return Err(LeaderboardError::rejected(format!(
+                "score {} is outside 0..={}",
+                self.score, max_score
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.accuracy) {
+            return Err(LeaderboardError::rejected(format!(
+                "accuracy {} is outside 0..=1",
+                self.accuracy
+            )));
+        }
+        if !(0..=MAX_TIME_MS).contains(&self.time_ms) {
+            return Err(LeaderboardError::rejected(format!(
+                "time {} ms is outside 0..={}",
+                self.time_ms, MAX_TIME_MS
+            )));
+        }
+
+        Ok(ScoreEntry {
+            player_name,
+            score: self.score as u64,
+            level_id: self.level_id,
+            accuracy: self.accuracy,
+            time_ms: self.time_ms as u64,
+            created_at,
+        })
+    }
+}
+
+/// Sort key where smaller is better: higher score first, then the earlier
+/// submission, then file order for identical timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RankKey {
+    score: Reverse<u64>,
+    created_at: u64,
+    line: usize,
+}
+
+impl RankKey {
+    pub fn new(entry: &ScoreEntry, line: usize) -> Self {
+        Self {
+            score: Reverse(entry.score),
+            created_at: entry.created_at,
+            line,
+        }
+    }
+}
+
+/// Heap item ordered by [`RankKey`], so a max-heap keeps the worst entry on
+/// top and can be trimmed to the best `n`.
+pub struct Ranked {
+    pub key: RankKey,
+    pub entry: ScoreEntry,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
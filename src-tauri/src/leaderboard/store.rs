@@ -0,0 +1,288 @@
+use super::model::{RankKey, Ranked, ScoreEntry, DEFAULT_MAX_SCORE};
+use super::LeaderboardError;
+use std::collections::BinaryHeap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{Manager, Runtime};
+
+pub const DIR_NAME: &str = "leaderboard";
+
+const MAX_LEVEL_ID: usize = 64;
+
+/// Serializes appends and clears on the per-level score files, and holds the
+/// score cap submissions are checked against.
+pub struct LeaderboardStore {
+    pub(super) lock: Mutex<()>,
+    max_score: AtomicU64,
+}
+
+impl Default for LeaderboardStore {
+    fn default() -> Self {
+        Self {
+            lock: Mutex::new(()),
+            max_score: AtomicU64::new(DEFAULT_MAX_SCORE),
+        }
+    }
+}
+
+impl LeaderboardStore {
+    pub fn max_score(&self) -> u64 {
+        self.max_score.load(Ordering::Relaxed)
+    }
+
+    pub fn set_max_score(&self, score: u64) {
+        self.max_score.store(score, Ordering::Relaxed);
+    }
+}
+
+/// `AppData/leaderboard/<level_id>.jsonl`. The table is shared by everyone
+/// on the machine, so unlike saves it isn't scoped to the active profile.
+pub fn level_path<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    level_id: &str,
+) -> Result<PathBuf, LeaderboardError> {
+    let level_id = validate_level_id(level_id)?;
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| LeaderboardError::NoDataDir(e.to_string()))?;
+    Ok(dir.join(DIR_NAME).join(format!("{}.jsonl", level_id)))
+}
+
+/// Level ids double as file names, so keep them to a portable subset.
+fn validate_level_id(level_id: &str) -> Result<&str, LeaderboardError> {
+    let valid = !level_id.is_empty()
+        && level_id.len() <= MAX_LEVEL_ID
+        && level_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if valid {
+        Ok(level_id)
+    } else {
+        Err(LeaderboardError::InvalidLevel(level_id.to_string()))
+    }
+}
+
+/// Calls `visit` with each entry and its line number, one line at a time.
+/// Lines that don't parse, such as a record torn by a crash mid-append, are
+/// skipped. A missing file is an empty table.
+fn scan(path: &Path, mut visit: impl FnMut(usize, ScoreEntry)) -> Result<(), LeaderboardError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(LeaderboardError::io(path, e)),
+    };
+    for (line_no, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| LeaderboardError::io(path, e))?;
+        if let Ok(entry) = serde_json::from_str::<ScoreEntry>(&line) {
+            visit(line_no, entry);
+        }
+    }
+    Ok(())
+}
+
+/// Appends `entry` as a single line and returns the rank it achieved.
+/// Existing entries with the same score rank ahead, being older or at worst
+/// earlier in the file.
+pub fn append(path: &Path, entry: &ScoreEntry) -> Result<usize, LeaderboardError> {
+    let mut ahead = 0;
+    scan(path, |_, existing| {
+        if existing.score > entry.score
+            || (existing.score == entry.score && existing.created_at <= entry.created_at)
+        {
+            ahead += 1;
+        }
+    })?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| LeaderboardError::io(parent, e))?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| LeaderboardError::io(path, e))?;
+
+    // Terminate a torn last line so it can't swallow the new record.
+    let mut line = if ends_mid_line(&mut file).map_err(|e| LeaderboardError::io(path, e))? {
+        b"\n".to_vec()
+    } else {
+        Vec::new()
+    };
+    serde_json::to_writer(&mut line, entry).expect("score entry serialize");
+    line.push(b'\n');
+    file.write_all(&line)
+        .and_then(|_| file.sync_data())
+        .map_err(|e| LeaderboardError::io(path, e))?;
+
+    Ok(ahead + 1)
+}
+
+fn ends_mid_line(file: &mut File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+/// The best `limit` entries, best first. Only `limit` entries are held in
+/// memory however large the file is.
+pub fn top(path: &Path, limit: usize) -> Result<Vec<ScoreEntry>, LeaderboardError> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    let mut heap = BinaryHeap::with_capacity(limit);
+    scan(path, |line, entry| {
+        let key = RankKey::new(&entry, line);
+        if heap.len() < limit {
+            heap.push(Ranked { key, entry });
+        } else if heap.peek().is_some_and(|worst: &Ranked| key < worst.key) {
+            heap.pop();
+            heap.push(Ranked { key, entry });
+        }
+    })?;
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|r| r.entry)
+        .collect())
+}
+
+/// The best entry by `player_name` (case-insensitive) and its rank.
+pub fn player_best(
+    path: &Path,
+    player_name: &str,
+) -> Result<Option<(usize, ScoreEntry)>, LeaderboardError> {
+    let wanted = player_name.trim().to_lowercase();
+    let mut best: Option<Ranked> = None;
+    scan(path, |line, entry| {
+        if entry.player_name.to_lowercase() != wanted {
+            return;
+        }
+        let key = RankKey::new(&entry, line);
+        if best.as_ref().is_none_or(|b| key < b.key) {
+            best = Some(Ranked { key, entry });
+        }
+    })?;
+
+    let Some(best) = best else {
+        return Ok(None);
+    };
+    let mut ahead = 0;
+    scan(path, |line, entry| {
+        if RankKey::new(&entry, line) < best.key {
+            ahead += 1;
+        }
+    })?;
+    Ok(Some((ahead + 1, best.entry)))
+}
+
+/// Deletes the level's table. Returns whether there was one.
+pub fn clear(path: &Path) -> Result<bool, LeaderboardError> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(LeaderboardError::io(path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(player_name: &str, score: u64, created_at: u64) -> ScoreEntry {
+        ScoreEntry {
+            player_name: player_name.to_string(),
+            score,
+            level_id: "dust".to_string(),
+            accuracy: 0.5,
+            time_ms: 60_000,
+            created_at,
+        }
+    }
+
+    fn names(entries: &[ScoreEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.player_name.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_by_score_then_submission_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dust.jsonl");
+        assert_eq!(append(&path, &entry("ann", 500, 10)).unwrap(), 1);
+        assert_eq!(append(&path, &entry("bob", 900, 20)).unwrap(), 1);
+        assert_eq!(append(&path, &entry("cat", 700, 30)).unwrap(), 2);
+        // Ties go to whoever got there first.
+        assert_eq!(append(&path, &entry("dan", 700, 40)).unwrap(), 3);
+        assert_eq!(append(&path, &entry("eve", 700, 5)).unwrap(), 2);
+        // Same score and timestamp: file order decides.
+        assert_eq!(append(&path, &entry("fay", 700, 40)).unwrap(), 5);
+
+        let all = top(&path, 10).unwrap();
+        assert_eq!(names(&all), ["bob", "eve", "cat", "dan", "fay", "ann"]);
+        assert_eq!(names(&top(&path, 3).unwrap()), ["bob", "eve", "cat"]);
+        assert!(top(&path, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn finds_a_players_best_rank() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dust.jsonl");
+        for e in [
+            entry("Ann", 300, 1),
+            entry("bob", 900, 2),
+            entry("ann", 800, 3),
+            entry("cat", 800, 4),
+        ] {
+            append(&path, &e).unwrap();
+        }
+
+        let (rank, best) = player_best(&path, " ANN ").unwrap().unwrap();
+        assert_eq!((rank, best.score), (2, 800));
+        assert!(player_best(&path, "zed").unwrap().is_none());
+    }
+
+    #[test]
+    fn scores_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dust.jsonl");
+        append(&path, &entry("ann", 500, 1)).unwrap();
+        append(&path, &entry("bob", 600, 2)).unwrap();
+
+        // A crash mid-append leaves a torn last line behind.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"playerName":"cat","sco"#).unwrap();
+        drop(file);
+
+        // Nothing is cached between calls, so this reads the file as a fresh
+        // process would.
+        assert_eq!(names(&top(&path, 10).unwrap()), ["bob", "ann"]);
+        assert_eq!(append(&path, &entry("dan", 550, 3)).unwrap(), 2);
+        assert_eq!(names(&top(&path, 10).unwrap()), ["bob", "dan", "ann"]);
+
+        assert!(clear(&path).unwrap());
+        assert!(!clear(&path).unwrap());
+        assert!(top(&path, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn level_ids_must_be_portable_file_names() {
+        assert!(validate_level_id("dust_2-night").is_ok());
+        for id in [
+            "",
+            "../dust",
+            "dust/2",
+            "dust 2",
+            &"x".repeat(MAX_LEVEL_ID + 1),
+        ] {
+            assert!(validate_level_id(id).is_err(), "{:?}", id);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the level commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum LevelError {
+    #[error("Invalid level id {0:?}")]
+    InvalidId(String),
+
+    #[error("No level named {0:?}")]
+    NotFound(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{path:?} is larger than the {max_bytes} byte level limit")]
+    TooLarge { path: PathBuf, max_bytes: u64 },
+
+    #[error("Failed to parse {path:?}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+
+    #[error("Level {id:?} has {count} validation error(s) and was not compiled")]
+    Invalid { id: String, count: usize },
+}
+
+impl LevelError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        LevelError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LevelError::InvalidId(_) => "invalidId",
+            LevelError::NotFound(_) => "notFound",
+            LevelError::Io { .. } => "io",
+            LevelError::TooLarge { .. } => "tooLarge",
+            LevelError::Parse { .. } => "parse",
+            LevelError::Invalid { .. } => "invalid",
+        }
+    }
+}
+
+impl Serialize for LevelError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("LevelError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,103 @@
+//! The two forms a level ships in.
+//!
+//! `<id>.json` is the `LevelFile` as pretty JSON, for hand editing.
+//! `<id>.level` is what `compile_level` writes for shipping (all integers
+//! little-endian):
+//!
+//! ```text
+//! magic    b"FLVL"
+//! u32      format version (1)
+//! u32      payload length
+//! u32      payload CRC-32
+//! payload  zstd-compressed compact JSON `LevelFile`
+//! ```
+
+use super::model::{LevelFile, LEVEL_SCHEMA_VERSION};
+use std::io::Read;
+
+pub const LEVEL_MAGIC: &[u8; 4] = b"FLVL";
+pub const LEVEL_VERSION: u32 = 1;
+
+pub const JSON_EXTENSION: &str = "json";
+pub const BINARY_EXTENSION: &str = "level";
+
+const HEADER_LEN: usize = 16;
+const ZSTD_LEVEL: i32 = 19;
+/// Well past any hand-built level; bigger is a damaged or hostile file.
+pub const MAX_LEVEL_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LevelFormat {
+    Json,
+    Binary,
+}
+
+impl LevelFormat {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            JSON_EXTENSION => Some(LevelFormat::Json),
+            BINARY_EXTENSION => Some(LevelFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+pub fn encode_binary(level: &LevelFile) -> std::io::Result<Vec<u8>> {
+    let json = serde_json::to_vec(level).expect("level serialize");
+    let payload = zstd::bulk::compress(&json, ZSTD_LEVEL)?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    out.extend_from_slice(LEVEL_MAGIC);
+    out.extend_from_slice(&LEVEL_VERSION.to_le_bytes());
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Parses either form. The error is a reason to show next to the file name.
+pub fn decode(bytes: &[u8], format: LevelFormat) -> Result<LevelFile, String> {
+    let level: LevelFile = match format {
+        LevelFormat::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string())?,
+        LevelFormat::Binary => {
+            let json = unpack(bytes)?;
+            serde_json::from_slice(&json).map_err(|e| e.to_string())?
+        }
+    };
+    if level.version > LEVEL_SCHEMA_VERSION {
+        return Err(format!(
+            "schema version {} is newer than this build supports ({})",
+            level.version, LEVEL_SCHEMA_VERSION
+        ));
+    }
+    Ok(level)
+}
+
+fn unpack(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != LEVEL_MAGIC {
+        return Err("not a compiled level".to_string());
+    }
+    let word =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    let version = word(4);
+    if version != LEVEL_VERSION {
+        return Err(format!("unsupported compiled level version {}", version));
+    }
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() != word(8) as usize {
+        return Err("truncated payload".to_string());
+    }
+    if crc32fast::hash(payload) != word(12) {
+        return Err("payload checksum mismatch".to_string());
+    }
+
+    let mut json = Vec::new();
+    zstd::stream::read::Decoder::new(payload)
+        .and_then(|decoder| decoder.take(MAX_LEVEL_BYTES + 1).read_to_end(&mut json))
+        .map_err(|e| format!("payload does not decompress: {}", e))?;
+    if json.len() as u64 > MAX_LEVEL_BYTES {
+        return Err("payload decompresses past the level size limit".to_string());
+    }
+    Ok(json)
+}
@@ -0,0 +1,259 @@
+mod error;
+mod format;
+mod model;
+mod validate;
+
+pub use error::LevelError;
+pub use format::LevelFormat;
+pub use model::{grid_size, LevelFile, NavData, NavHintKind, MAX_NAV_CELLS};
+pub use validate::{LevelIssue, Validation};
+
+use model::LevelMeta;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::Runtime;
+
+const BUNDLED: &str = "bundled";
+const MAX_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelInfo {
+    pub id: String,
+    /// `bundled` or the id of the mod providing the level.
+    pub source: String,
+    pub format: LevelFormat,
+    /// `None` when the file doesn't parse; `error` says why.
+    pub meta: Option<LevelMeta>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedLevel {
+    pub id: String,
+    pub source: String,
+    pub format: LevelFormat,
+    pub level: LevelFile,
+    #[serde(flatten)]
+    pub validation: Validation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompiledLevel {
+    pub id: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub source_bytes: u64,
+    pub warnings: Vec<LevelIssue>,
+}
+
+/// Where a level was found.
+struct Located {
+    source: String,
+    path: PathBuf,
+    format: LevelFormat,
+}
+
+pub fn valid_id(id: &str) -> Result<(), LevelError> {
+    let ok = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+    if ok {
+        Ok(())
+    } else {
+        Err(LevelError::InvalidId(id.to_string()))
+    }
+}
+
+/// Directories that may hold levels, lowest priority first: the bundled
+/// ones, then each enabled mod's `levels/` in load order.
+fn level_dirs<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<(String, Vec<PathBuf>)> {
+    let kind = crate::assets::AssetKind::Level;
+    let mut dirs = vec![(BUNDLED.to_string(), crate::assets::asset_roots(app, kind))];
+    for (id, root) in crate::mods::enabled_roots(app) {
+        dirs.push((id, vec![root.join(kind.dir())]));
+    }
+    dirs
+}
+
+/// Levels in `dir` by id. When both forms are there the JSON wins, since
+/// the compiled file is built from it and may be stale.
+fn files_in(dir: &Path) -> BTreeMap<String, (PathBuf, LevelFormat)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    let mut found = BTreeMap::new();
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let Some(format) = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(LevelFormat::from_extension)
+        else {
+            continue;
+        };
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || valid_id(id).is_err() {
+            continue;
+        }
+        match found.get(id) {
+            Some((_, LevelFormat::Json)) => {}
+            _ => {
+                found.insert(id.to_string(), (path, format));
+            }
+        }
+    }
+    found
+}
+
+/// Every level by id, from the highest priority source that has it.
+/// Bundled roots are the same files in different install layouts, so only
+/// the first with a given id counts.
+fn discover<R: Runtime>(app: &tauri::AppHandle<R>) -> BTreeMap<String, Located> {
+    let mut levels = BTreeMap::new();
+    for (source, roots) in level_dirs(app) {
+        let mut from_source = BTreeMap::new();
+        for root in roots.iter().rev() {
+            from_source.extend(files_in(root));
+        }
+        for (id, (path, format)) in from_source {
+            let source = source.clone();
+            levels.insert(
+                id,
+                Located {
+                    source,
+                    path,
+                    format,
+                },
+            );
+        }
+    }
+    levels
+}
+
+fn locate<R: Runtime>(app: &tauri::AppHandle<R>, id: &str) -> Result<Located, LevelError> {
+    valid_id(id)?;
+    discover(app)
+        .remove(id)
+        .ok_or_else(|| LevelError::NotFound(id.to_string()))
+}
+
+fn read(path: &Path, format: LevelFormat) -> Result<(LevelFile, u64), LevelError> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| LevelError::io(path, e))?
+        .len();
+    if size > format::MAX_LEVEL_BYTES {
+        return Err(LevelError::TooLarge {
+            path: path.to_path_buf(),
+            max_bytes: format::MAX_LEVEL_BYTES,
+        });
+    }
+    let bytes = std::fs::read(path).map_err(|e| LevelError::io(path, e))?;
+    let level = format::decode(&bytes, format).map_err(|reason| LevelError::Parse {
+        path: path.to_path_buf(),
+        reason,
+    })?;
+    Ok((level, size))
+}
+
+fn check<R: Runtime>(app: &tauri::AppHandle<R>, level: &LevelFile) -> Validation {
+    validate::validate(level, |kind, name| {
+        crate::assets::resolve_source(app, kind, name).is_ok()
+    })
+}
+
+/// Level `id` as parsed, without validating it.
+pub fn load<R: Runtime>(app: &tauri::AppHandle<R>, id: &str) -> Result<LevelFile, LevelError> {
+    let found = locate(app, id)?;
+    read(&found.path, found.format).map(|(level, _)| level)
+}
+
+/// Every level in `resources/levels/` or an enabled mod's `levels/`, with
+/// its metadata. A mod's level replaces a bundled one with the same id.
+/// Files that don't parse are still listed, with `error` set.
+#[tauri::command]
+pub async fn list_levels(app: tauri::AppHandle) -> Vec<LevelInfo> {
+    discover(&app)
+        .into_iter()
+        .map(|(id, found)| {
+            let (meta, error) = match read(&found.path, found.format) {
+                Ok((level, _)) => (Some(level.meta), None),
+                Err(e) => {
+                    log::warn!("Level {} from {}: {}", id, found.source, e);
+                    (None, Some(e.to_string()))
+                }
+            };
+            LevelInfo {
+                id,
+                source: found.source,
+                format: found.format,
+                meta,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// Parses level `id` and validates it. The level comes back even when it
+/// has validation errors, so editors can show all of them at once; only a
+/// file that can't be read or parsed fails.
+#[tauri::command]
+pub async fn load_level(app: tauri::AppHandle, id: String) -> Result<LoadedLevel, LevelError> {
+    let found = locate(&app, &id)?;
+    let (level, _) = read(&found.path, found.format)?;
+    let validation = check(&app, &level);
+    if !validation.errors.is_empty() {
+        log::warn!(
+            "Level {} loaded with {} validation error(s)",
+            id,
+            validation.errors.len()
+        );
+    }
+    Ok(LoadedLevel {
+        id,
+        source: found.source,
+        format: found.format,
+        level,
+        validation,
+    })
+}
+
+/// Writes the compact binary form of level `id` as `<id>.level` next to
+/// its source, for shipping. Refuses a level with validation errors;
+/// warnings are returned.
+#[tauri::command]
+pub async fn compile_level(app: tauri::AppHandle, id: String) -> Result<CompiledLevel, LevelError> {
+    let found = locate(&app, &id)?;
+    let (level, source_bytes) = read(&found.path, found.format)?;
+    let validation = check(&app, &level);
+    if !validation.errors.is_empty() {
+        return Err(LevelError::Invalid {
+            id,
+            count: validation.errors.len(),
+        });
+    }
+
+    let path = found.path.with_extension(format::BINARY_EXTENSION);
+    let bytes = format::encode_binary(&level).map_err(|e| LevelError::io(&path, e))?;
+    crate::fs_atomic::write_atomic(&path, &bytes).map_err(|e| LevelError::io(&path, e))?;
+    log::info!(
+        "Compiled level {} to {:?} ({} bytes from {})",
+        id,
+        path,
+        bytes.len(),
+        source_bytes
+    );
+    Ok(CompiledLevel {
+        id,
+        path,
+        size_bytes: bytes.len() as u64,
+        source_bytes,
+        warnings: validation.warnings,
+    })
+}
@@ -0,0 +1,230 @@
+use crate::simulation::{Aabb, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Newest level schema this build understands.
+pub const LEVEL_SCHEMA_VERSION: u32 = 1;
+
+fn default_schema_version() -> u32 {
+    LEVEL_SCHEMA_VERSION
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn unit_scale() -> Vec3 {
+    Vec3::new(1.0, 1.0, 1.0)
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+/// A level as stored in `resources/levels/<id>.json` or a mod's `levels/`.
+/// Every entity carries an `id` unique across the whole file, so tools and
+/// validation messages can point at it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelFile {
+    #[serde(default = "default_schema_version")]
+    pub version: u32,
+    pub meta: LevelMeta,
+    /// Nothing in the level may sit outside this box.
+    pub bounds: Aabb,
+    #[serde(default)]
+    pub spawns: Vec<SpawnPoint>,
+    #[serde(default)]
+    pub brushes: Vec<Brush>,
+    #[serde(default)]
+    pub props: Vec<StaticProp>,
+    #[serde(default)]
+    pub lights: Vec<Light>,
+    #[serde(default)]
+    pub nav_hints: Vec<NavHint>,
+    /// A texture asset, e.g. `skies/dusk.ktx2`.
+    #[serde(default)]
+    pub skybox: Option<String>,
+    /// An audio asset played on loop while the level is up.
+    #[serde(default)]
+    pub music: Option<String>,
+    /// Precomputed bot walkability; without it the grid is worked out from
+    /// the brushes when bots need it.
+    #[serde(default)]
+    pub nav: Option<NavData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelMeta {
+    pub name: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Game modes the level supports, e.g. `deathmatch`.
+    #[serde(default)]
+    pub modes: Vec<String>,
+    #[serde(default)]
+    pub max_players: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpawnPoint {
+    pub id: String,
+    pub position: Vec3,
+    /// Facing, in degrees clockwise from -Z.
+    #[serde(default)]
+    pub yaw: f32,
+    /// `None` spawns anyone.
+    #[serde(default)]
+    pub team: Option<String>,
+}
+
+/// An axis-aligned block of static geometry, which is also what the
+/// simulation collides against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Brush {
+    pub id: String,
+    pub bounds: Aabb,
+    /// A texture asset; `None` uses the level's default material.
+    #[serde(default)]
+    pub texture: Option<String>,
+    #[serde(default = "default_true")]
+    pub collidable: bool,
+}
+
+/// A placed model, drawn but never collided with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticProp {
+    pub id: String,
+    /// A model asset.
+    pub model: String,
+    pub position: Vec3,
+    /// Euler angles in degrees, applied Y, X, then Z.
+    #[serde(default)]
+    pub rotation: Vec3,
+    #[serde(default = "unit_scale")]
+    pub scale: Vec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LightKind {
+    Ambient,
+    Directional,
+    Point,
+    Spot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Light {
+    pub id: String,
+    pub kind: LightKind,
+    /// Ignored for ambient and directional lights.
+    #[serde(default)]
+    pub position: Option<Vec3>,
+    /// Where directional and spot lights point.
+    #[serde(default)]
+    pub direction: Option<Vec3>,
+    /// Linear RGB, 0 to 1.
+    #[serde(default = "white")]
+    pub color: [f32; 3],
+    #[serde(default = "default_intensity")]
+    pub intensity: f32,
+    /// Falloff distance for point and spot lights.
+    #[serde(default)]
+    pub range: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NavHintKind {
+    Waypoint,
+    Cover,
+    Jump,
+    Avoid,
+}
+
+/// A hint for bots about a spot in the level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavHint {
+    pub id: String,
+    pub kind: NavHintKind,
+    pub position: Vec3,
+    #[serde(default)]
+    pub radius: Option<f32>,
+}
+
+/// A walkability grid over the XZ plane. Cell `(x, z)` spans from
+/// `origin + (x, z) * cellSize` one cell size along each axis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavData {
+    pub origin_x: f32,
+    pub origin_z: f32,
+    pub cell_size: f32,
+    pub width: u32,
+    pub height: u32,
+    /// Height waypoints are placed at.
+    #[serde(default)]
+    pub floor_y: f32,
+    /// Row-major, `width * height` long: 0 is blocked, anything else is the
+    /// cost of crossing the cell, 1 being open ground.
+    pub costs: Vec<u8>,
+}
+
+/// Cap on the cells in one grid, which also bounds what a search allocates.
+pub const MAX_NAV_CELLS: u64 = 1 << 20;
+
+/// Columns and rows of `cell_size` cells needed to cover `width` x `depth`,
+/// at least one each way. Counted in `f64`, so a tiny cell size can't
+/// overflow the count on its way to the `max_cells` check.
+pub fn grid_size(
+    width: f32,
+    depth: f32,
+    cell_size: f32,
+    max_cells: u64,
+) -> Result<(u32, u32), String> {
+    let columns = (width as f64 / cell_size as f64).ceil().max(1.0);
+    let rows = (depth as f64 / cell_size as f64).ceil().max(1.0);
+    if columns * rows > max_cells as f64 {
+        return Err(format!(
+            "{} x {} cells at cell size {} is more than {}",
+            columns, rows, cell_size, max_cells
+        ));
+    }
+    Ok((columns as u32, rows as u32))
+}
+
+impl NavData {
+    /// Why the grid can't be used, if it can't.
+    pub fn problem(&self) -> Option<String> {
+        let cells = self.width as u64 * self.height as u64;
+        if !(self.cell_size.is_finite() && self.cell_size > 0.0) {
+            Some("cellSize must be positive".to_string())
+        } else if !(self.origin_x.is_finite()
+            && self.origin_z.is_finite()
+            && self.floor_y.is_finite())
+        {
+            Some("origin and floorY must be finite".to_string())
+        } else if cells == 0 || cells > MAX_NAV_CELLS {
+            Some(format!(
+                "{} x {} cells is outside 1 to {}",
+                self.width, self.height, MAX_NAV_CELLS
+            ))
+        } else if self.costs.len() as u64 != cells {
+            Some(format!("{} costs for {} cells", self.costs.len(), cells))
+        } else {
+            None
+        }
+    }
+}
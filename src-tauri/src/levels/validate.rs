@@ -0,0 +1,325 @@
+use super::model::{LevelFile, LightKind};
+use crate::assets::AssetKind;
+use crate::simulation::{Aabb, Vec3};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Float precision gets visibly bad past this far from the origin.
+const MAX_COORDINATE: f32 = 100_000.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelIssue {
+    /// Where in the file, e.g. `spawns[2].position`.
+    pub path: String,
+    pub message: String,
+}
+
+/// Everything wrong with a level. Errors break it in game; warnings are
+/// probably mistakes but play.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Validation {
+    pub errors: Vec<LevelIssue>,
+    pub warnings: Vec<LevelIssue>,
+}
+
+impl Validation {
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(LevelIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn warn(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.warnings.push(LevelIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+}
+
+struct Checker<'a, F> {
+    out: Validation,
+    /// `None` when the level's own bounds are unusable, which skips the
+    /// containment checks rather than failing every one of them.
+    bounds: Option<Aabb>,
+    resolves: F,
+    resolved: HashMap<(&'a str, &'a str), bool>,
+}
+
+impl<'a, F: FnMut(AssetKind, &str) -> bool> Checker<'a, F> {
+    fn point(&mut self, path: String, p: Vec3) {
+        if !p.is_finite() {
+            self.out.error(path, "is not a finite position");
+        } else if let Some(b) = self.bounds {
+            if !contains(b, p) {
+                self.out.error(path, "is outside the level bounds");
+            }
+        }
+    }
+
+    fn asset(&mut self, path: String, kind: AssetKind, name: &'a str) {
+        let found = match self.resolved.get(&(kind.dir(), name)) {
+            Some(found) => *found,
+            None => {
+                let found = (self.resolves)(kind, name);
+                self.resolved.insert((kind.dir(), name), found);
+                found
+            }
+        };
+        if !found {
+            self.out.error(
+                path,
+                format!("{} asset {:?} does not resolve", kind.dir(), name),
+            );
+        }
+    }
+}
+
+fn contains(b: Aabb, p: Vec3) -> bool {
+    (0..3).all(|i| p.axis(i) >= b.min.axis(i) && p.axis(i) <= b.max.axis(i))
+}
+
+fn encloses(outer: Aabb, inner: Aabb) -> bool {
+    contains(outer, inner.min) && contains(outer, inner.max)
+}
+
+/// Checks `level` for everything that would otherwise break silently in
+/// game, reporting every problem rather than stopping at the first.
+/// `resolves` says whether an asset reference can be loaded.
+pub fn validate(level: &LevelFile, resolves: impl FnMut(AssetKind, &str) -> bool) -> Validation {
+    let bounds_ok = level.bounds.is_valid();
+    let mut c = Checker {
+        out: Validation::default(),
+        bounds: bounds_ok.then_some(level.bounds),
+        resolves,
+        resolved: HashMap::new(),
+    };
+
+    if !bounds_ok {
+        c.out
+            .error("bounds", "must be finite with min no greater than max");
+    } else if (0..3).any(|i| {
+        level.bounds.min.axis(i).abs() > MAX_COORDINATE
+            || level.bounds.max.axis(i).abs() > MAX_COORDINATE
+    }) {
+        c.out.error(
+            "bounds",
+            format!("reaches past ±{} on some axis", MAX_COORDINATE),
+        );
+    }
+
+    if level.meta.name.trim().is_empty() {
+        c.out.error("meta.name", "is empty");
+    }
+    if level.meta.max_players == Some(0) {
+        c.out.error("meta.maxPlayers", "must be at least 1");
+    }
+
+    check_ids(level, &mut c.out);
+
+    if level.spawns.is_empty() {
+        c.out.error("spawns", "the level has no spawn points");
+    } else if let Some(max) = level.meta.max_players {
+        if (level.spawns.len() as u64) < max as u64 {
+            c.out.warn(
+                "spawns",
+                format!(
+                    "{} spawn points for up to {} players",
+                    level.spawns.len(),
+                    max
+                ),
+            );
+        }
+    }
+    for (i, spawn) in level.spawns.iter().enumerate() {
+        c.point(format!("spawns[{}].position", i), spawn.position);
+        if !spawn.yaw.is_finite() {
+            c.out.error(format!("spawns[{}].yaw", i), "is not finite");
+        }
+        let inside = level
+            .brushes
+            .iter()
+            .find(|b| b.collidable && b.bounds.is_valid() && contains(b.bounds, spawn.position));
+        if let Some(brush) = inside {
+            c.out.warn(
+                format!("spawns[{}].position", i),
+                format!("is inside brush {:?}", brush.id),
+            );
+        }
+    }
+
+    for (i, brush) in level.brushes.iter().enumerate() {
+        let path = format!("brushes[{}].bounds", i);
+        if !brush.bounds.is_valid() {
+            c.out
+                .error(path, "must be finite with min no greater than max");
+        } else {
+            if c.bounds.is_some_and(|b| !encloses(b, brush.bounds)) {
+                c.out.error(path.clone(), "is outside the level bounds");
+            }
+            if (0..3).any(|a| brush.bounds.min.axis(a) == brush.bounds.max.axis(a)) {
+                c.out.warn(path, "has no volume");
+            }
+        }
+        if let Some(texture) = &brush.texture {
+            c.asset(
+                format!("brushes[{}].texture", i),
+                AssetKind::Texture,
+                texture,
+            );
+        }
+    }
+
+    for (i, prop) in level.props.iter().enumerate() {
+        c.point(format!("props[{}].position", i), prop.position);
+        if !prop.rotation.is_finite() {
+            c.out
+                .error(format!("props[{}].rotation", i), "is not finite");
+        }
+        if !prop.scale.is_finite() || (0..3).any(|a| prop.scale.axis(a) == 0.0) {
+            c.out.error(
+                format!("props[{}].scale", i),
+                "must be finite and non-zero on every axis",
+            );
+        }
+        c.asset(format!("props[{}].model", i), AssetKind::Model, &prop.model);
+    }
+
+    if level.lights.is_empty() {
+        c.out.warn("lights", "the level has no lights");
+    }
+    for (i, light) in level.lights.iter().enumerate() {
+        let positioned = matches!(light.kind, LightKind::Point | LightKind::Spot);
+        let aimed = matches!(light.kind, LightKind::Directional | LightKind::Spot);
+        match light.position {
+            Some(p) if positioned => c.point(format!("lights[{}].position", i), p),
+            None if positioned => c.out.error(
+                format!("lights[{}].position", i),
+                "is required for this kind",
+            ),
+            _ => {}
+        }
+        match light.direction {
+            Some(d) if aimed && (!d.is_finite() || d.normalized().is_none()) => c.out.error(
+                format!("lights[{}].direction", i),
+                "must be a finite, non-zero vector",
+            ),
+            None if aimed => c.out.error(
+                format!("lights[{}].direction", i),
+                "is required for this kind",
+            ),
+            _ => {}
+        }
+        if !light.intensity.is_finite() || light.intensity < 0.0 {
+            c.out.error(
+                format!("lights[{}].intensity", i),
+                "must be finite and not negative",
+            );
+        }
+        if !light.color.iter().all(|v| (0.0..=1.0).contains(v)) {
+            c.out.warn(
+                format!("lights[{}].color", i),
+                "has components outside 0 to 1",
+            );
+        }
+        if let Some(range) = light.range {
+            if !range.is_finite() || range <= 0.0 {
+                c.out
+                    .error(format!("lights[{}].range", i), "must be positive");
+            }
+        }
+    }
+
+    for (i, hint) in level.nav_hints.iter().enumerate() {
+        c.point(format!("navHints[{}].position", i), hint.position);
+        if let Some(radius) = hint.radius {
+            if !radius.is_finite() || radius <= 0.0 {
+                c.out
+                    .error(format!("navHints[{}].radius", i), "must be positive");
+            }
+        }
+    }
+
+    if let Some(nav) = &level.nav {
+        if let Some(problem) = nav.problem() {
+            c.out.error("nav", problem);
+        } else if let Some(b) = c.bounds {
+            let far_x = nav.origin_x + nav.width as f32 * nav.cell_size;
+            let far_z = nav.origin_z + nav.height as f32 * nav.cell_size;
+            let inside = nav.origin_x >= b.min.x
+                && nav.origin_z >= b.min.z
+                && far_x <= b.max.x
+                && far_z <= b.max.z;
+            if !inside {
+                c.out
+                    .warn("nav", "the grid reaches outside the level bounds");
+            }
+        }
+    }
+
+    if let Some(skybox) = &level.skybox {
+        c.asset("skybox".to_string(), AssetKind::Texture, skybox);
+    }
+    if let Some(music) = &level.music {
+        c.asset("music".to_string(), AssetKind::Audio, music);
+    }
+    c.out
+}
+
+/// Ids must be non-empty and unique across every kind of entity, since
+/// scripts and tools look entities up by id alone.
+fn check_ids(level: &LevelFile, out: &mut Validation) {
+    let ids = level
+        .spawns
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (format!("spawns[{}].id", i), &e.id))
+        .chain(
+            level
+                .brushes
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (format!("brushes[{}].id", i), &e.id)),
+        )
+        .chain(
+            level
+                .props
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (format!("props[{}].id", i), &e.id)),
+        )
+        .chain(
+            level
+                .lights
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (format!("lights[{}].id", i), &e.id)),
+        )
+        .chain(
+            level
+                .nav_hints
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (format!("navHints[{}].id", i), &e.id)),
+        );
+
+    let mut seen: HashMap<&str, String> = HashMap::new();
+    for (path, id) in ids {
+        if id.trim().is_empty() {
+            out.error(path, "is empty");
+            continue;
+        }
+        match seen.get(id.as_str()) {
+            Some(first) => {
+                let message = format!("duplicate id {:?}, first used at {}", id, first);
+                out.error(path, message);
+            }
+            None => {
+                seen.insert(id, path);
+            }
+        }
+    }
+}
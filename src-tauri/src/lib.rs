@@ -1,6 +1,45 @@
+mod achievements;
+mod assets;
+mod audio;
+mod benchmark;
+mod crash;
+mod crosshairs;
+mod display;
+mod fs_atomic;
+mod game_loop;
+mod heatmap;
+mod input;
+mod keybindings;
+mod leaderboard;
+mod levels;
+mod locale;
+mod logging;
+mod match_history;
+mod mods;
+mod music;
+mod nav;
+mod net;
+mod perf;
+mod presence;
+mod profiles;
+mod raw_input;
+mod replay;
+mod rng;
+mod saves;
+mod screenshots;
+mod server;
+mod settings;
+mod simulation;
+mod snapshot;
+mod stats;
+mod support;
+mod system;
+mod time;
+mod updates;
+mod voice;
+mod weapons;
+
 use tauri::Manager;
-use tauri::path::BaseDirectory;
-use std::fs;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -8,51 +47,273 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-fn load_audio_asset(app: tauri::AppHandle, filename: String) -> Result<Vec<u8>, String> {
-    // 1. Dev Mode Fallback: Check directly in the project folder
-    #[cfg(debug_assertions)]
-    {
-        use std::path::PathBuf;
-
-        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?;
-        let mut dev_path = PathBuf::from(manifest_dir);
-        // Adjusted for new structure: src-tauri/resources/audio
-        dev_path.push("resources/audio");
-        dev_path.push(&filename);
-        
-        if dev_path.exists() {
-             return fs::read(&dev_path).map_err(|e| format!("Failed to read file from dev path {:?}: {}", dev_path, e));
-        }
-    }
-
-    // 2. Production / Standard Resource Mode
-    let maybe_paths = vec![
-        format!("resources/audio/{}", filename), 
-        format!("resources/{}", filename),       
-        format!("audio/{}", filename),           
-        filename.clone(), 
-    ];
-
-    for path_str in maybe_paths {
-         match app.path().resolve(&path_str, BaseDirectory::Resource) {
-            Ok(path) => {
-                if path.exists() {
-                     return fs::read(&path).map_err(|e| format!("Failed to read file at {:?}: {}", path, e));
-                }
-            },
-            Err(_) => {},
-         }
-    }
-    
-    Err(format!("Could not find audio file {} in resources", filename))
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    crash::install();
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet, load_audio_asset])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .register_asynchronous_uri_scheme_protocol(
+            assets::protocol::SCHEME,
+            assets::protocol::handle,
+        )
+        .manage(logging::LogState::default())
+        .manage(assets::AssetLoads::default())
+        .manage(assets::AssetCache::default())
+        .manage(assets::PreloadGate::default())
+        .manage(assets::PakRegistry::default())
+        .manage(assets::AssetLimits::default())
+        .manage(assets::VerifyState::default())
+        .manage(assets::AssetWatcher::default())
+        .manage(profiles::ActiveProfile::default())
+        .manage(settings::SettingsStore::default())
+        .manage(settings::SettingsWatcher::default())
+        .manage(keybindings::KeybindingStore::default())
+        .manage(saves::SaveStore::default())
+        .manage(saves::SyncState::default())
+        .manage(achievements::AchievementStore::default())
+        .manage(leaderboard::LeaderboardStore::default())
+        .manage(match_history::MatchHistoryStore::default())
+        .manage(stats::StatsStore::default())
+        .manage(heatmap::HeatmapStore::default())
+        .manage(replay::ReplayRecorder::default())
+        .manage(rng::RngStreams::default())
+        .manage(game_loop::GameLoop::default())
+        .manage(simulation::SimulationWorld::default())
+        .manage(snapshot::SnapshotCodec::default())
+        .manage(net::NetSockets::default())
+        .manage(net::LanState::default())
+        .manage(net::LatencyMonitors::default())
+        .manage(server::HostState::default())
+        .manage(presence::PresenceState::default())
+        .manage(input::gamepad::GamepadState::default())
+        .manage(raw_input::RawInputState::default())
+        .manage(display::DisplayState::default())
+        .manage(system::SystemState::default())
+        .manage(perf::PerfState::default())
+        .manage(benchmark::BenchmarkState::default())
+        .manage(crash::CrashState::default())
+        .manage(updates::UpdateState::default())
+        .manage(mods::ModRegistry::default())
+        .manage(nav::NavState::default())
+        .manage(voice::VoiceState::default())
+        .manage(music::MusicState::default())
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::Focused(focused) => {
+                game_loop::set_focused(window.app_handle(), *focused);
+                raw_input::set_focused(window.app_handle(), *focused);
+            }
+            tauri::WindowEvent::Destroyed => {
+                server::shutdown(window.app_handle());
+                net::close_all(window.app_handle());
+            }
+            _ => {}
+        })
+        .setup(|app| {
+            let handle = app.handle();
+            logging::init(handle);
+            crash::init(handle);
+            // Dev builds pick up edited audio and weapons without a restart.
+            handle
+                .state::<assets::AssetWatcher>()
+                .set_enabled(handle, true);
+            settings::start_watcher(handle);
+            display::restore(handle);
+            stats::start_flush_timer(handle);
+            heatmap::start_flush_timer(handle);
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            assets::load_audio_asset,
+            assets::load_asset,
+            assets::load_asset_range,
+            assets::list_audio_assets,
+            assets::preload_assets,
+            assets::cancel_asset_load,
+            assets::clear_asset_cache,
+            assets::get_cache_stats,
+            assets::mount_pak,
+            assets::unmount_pak,
+            assets::list_mounted_paks,
+            assets::set_asset_decompression_limit,
+            assets::verify_assets,
+            assets::cancel_verify,
+            assets::verify_asset,
+            assets::set_asset_watch_enabled,
+            audio::decode_audio,
+            audio::decode_audio_resampled,
+            audio::get_audio_metadata,
+            settings::load_settings,
+            settings::save_settings,
+            settings::reset_settings,
+            keybindings::get_keybindings,
+            keybindings::set_keybinding,
+            keybindings::reset_keybindings,
+            keybindings::save_keybinding_profile,
+            keybindings::load_keybinding_profile,
+            keybindings::list_keybinding_profiles,
+            saves::write_save,
+            saves::read_save,
+            saves::list_saves,
+            saves::delete_save,
+            saves::sync_saves,
+            saves::resolve_save_conflict,
+            profiles::create_profile,
+            profiles::list_profiles,
+            profiles::switch_profile,
+            profiles::rename_profile,
+            profiles::delete_profile,
+            achievements::get_achievements,
+            achievements::unlock_achievement,
+            achievements::increment_achievement_progress,
+            achievements::reset_achievements,
+            leaderboard::submit_score,
+            leaderboard::get_top_scores,
+            leaderboard::get_player_best,
+            leaderboard::clear_leaderboard,
+            leaderboard::set_leaderboard_score_cap,
+            match_history::record_match,
+            match_history::get_match_history,
+            match_history::export_match_history,
+            match_history::set_match_history_cap,
+            stats::record_stat_delta,
+            stats::get_stats,
+            stats::get_stats_for_period,
+            replay::start_replay_recording,
+            replay::append_replay_frames,
+            replay::stop_replay_recording,
+            replay::list_replays,
+            replay::load_replay,
+            rng::create_rng_stream,
+            rng::rng_next_u32,
+            rng::rng_next_range,
+            rng::get_rng_state,
+            rng::set_rng_state,
+            game_loop::start_game_loop,
+            game_loop::stop_game_loop,
+            game_loop::set_tick_rate,
+            game_loop::set_pause_on_blur,
+            game_loop::get_loop_stats,
+            simulation::register_world_geometry,
+            simulation::register_entities,
+            simulation::raycast_batch,
+            simulation::record_entity_snapshot,
+            simulation::raycast_at_time,
+            snapshot::encode_snapshot,
+            snapshot::encode_delta,
+            snapshot::decode_snapshot,
+            snapshot::apply_delta,
+            net::open_udp_socket,
+            net::udp_send,
+            net::close_udp_socket,
+            net::get_socket_stats,
+            net::start_lan_advertise,
+            net::stop_lan_advertise,
+            net::start_lan_discovery,
+            net::stop_lan_discovery,
+            net::fetch_server_list,
+            net::refresh_server_ping,
+            net::measure_latency,
+            net::start_latency_monitor,
+            net::stop_latency_monitor,
+            server::start_host,
+            server::stop_host,
+            server::kick_player,
+            server::get_host_status,
+            presence::init_presence,
+            presence::update_presence,
+            presence::clear_presence,
+            input::gamepad::start_gamepad_listener,
+            input::gamepad::stop_gamepad_listener,
+            input::gamepad::set_gamepad_deadzone,
+            input::gamepad::list_gamepads,
+            input::gamepad::rumble,
+            raw_input::start_raw_mouse_capture,
+            raw_input::stop_raw_mouse_capture,
+            raw_input::set_raw_mouse_flush_interval,
+            display::list_monitors,
+            display::get_current_display_mode,
+            display::apply_display_mode,
+            display::apply_display_mode_with_confirm,
+            display::confirm_display_mode,
+            system::get_system_info,
+            system::suggest_graphics_preset,
+            perf::record_frame_times,
+            perf::get_frame_stats,
+            perf::reset_frame_stats,
+            perf::export_frame_trace,
+            benchmark::run_benchmark,
+            benchmark::list_benchmark_reports,
+            benchmark::load_benchmark_report,
+            logging::log_message,
+            logging::get_recent_logs,
+            logging::set_log_level,
+            crash::get_last_crash_report,
+            crash::list_crash_reports,
+            crash::mark_crash_report_handled,
+            crash::submit_crash_report,
+            support::create_support_bundle,
+            updates::check_for_updates,
+            updates::download_update,
+            updates::get_downloaded_update,
+            mods::list_mods,
+            mods::set_mod_enabled,
+            mods::get_mod_load_order,
+            mods::set_mod_load_order,
+            locale::list_locales,
+            locale::load_locale,
+            locale::set_locale,
+            levels::list_levels,
+            levels::load_level,
+            levels::compile_level,
+            nav::build_nav_grid,
+            nav::find_path,
+            nav::find_paths_batch,
+            nav::set_cells_blocked,
+            weapons::get_weapon_defs,
+            weapons::get_weapon_def,
+            screenshots::save_screenshot,
+            screenshots::list_screenshots,
+            screenshots::delete_screenshot,
+            screenshots::open_screenshot_folder,
+            crosshairs::import_crosshair,
+            crosshairs::list_crosshairs,
+            crosshairs::get_crosshair,
+            crosshairs::delete_crosshair,
+            crosshairs::set_crosshair,
+            heatmap::record_positions,
+            heatmap::get_heatmap,
+            heatmap::set_heatmap_cell_size,
+            heatmap::export_heatmap,
+            heatmap::reset_heatmap,
+            voice::start_voice_capture,
+            voice::stop_voice_capture,
+            voice::list_input_devices,
+            voice::set_voice_transmitting,
+            music::music_play,
+            music::music_stop,
+            music::music_crossfade_to,
+            music::music_set_volume,
+            music::music_pause,
+            music::music_resume
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                game_loop::shutdown(app);
+                stats::flush(app);
+                heatmap::flush(app);
+                replay::flush(app);
+                server::shutdown(app);
+                voice::shutdown(app);
+                music::shutdown(app);
+                net::close_all(app);
+                presence::shutdown(app);
+                input::gamepad::shutdown(app);
+                raw_input::shutdown(app);
+                display::shutdown(app);
+                logging::flush(app);
+            }
+        });
 }
@@ -0,0 +1,35 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the locale commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum LocaleError {
+    #[error("Invalid language tag {0:?}")]
+    InvalidTag(String),
+
+    #[error("No strings for {0:?} or any language it falls back to")]
+    NotFound(String),
+
+    #[error("Failed to save the locale: {0}")]
+    Settings(String),
+}
+
+impl LocaleError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LocaleError::InvalidTag(_) => "invalidTag",
+            LocaleError::NotFound(_) => "notFound",
+            LocaleError::Settings(_) => "settings",
+        }
+    }
+}
+
+impl Serialize for LocaleError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("LocaleError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,220 @@
+mod error;
+mod table;
+mod tag;
+
+pub use error::LocaleError;
+pub use tag::normalize as normalize_tag;
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Runtime};
+
+pub const CHANGED_EVENT: &str = "locale-changed";
+const BUNDLED: &str = "bundled";
+const EXTENSION: &str = "json";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    pub tag: String,
+    /// `bundled` and/or the ids of the mods providing it, in load order.
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleTable {
+    pub locale: String,
+    /// The tags that had strings, most specific first.
+    pub loaded: Vec<String>,
+    pub strings: BTreeMap<String, String>,
+    /// English keys no file in the chain above English translates, sorted.
+    pub missing_keys: Vec<String>,
+    /// Files that failed to parse or had bad entries, as
+    /// `<source> <file>: <problem>`.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LocaleChanged {
+    locale: String,
+}
+
+/// Directories that may hold `<tag>.json`, lowest priority first: the
+/// bundled ones, then each enabled mod's `locales/` in load order.
+fn locale_dirs<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<(String, Vec<PathBuf>)> {
+    let kind = crate::assets::AssetKind::Locale;
+    let mut dirs = vec![(BUNDLED.to_string(), crate::assets::asset_roots(app, kind))];
+    for (id, root) in crate::mods::enabled_roots(app) {
+        dirs.push((id, vec![root.join(kind.dir())]));
+    }
+    dirs
+}
+
+/// Files in `dir` named after a valid tag, keyed by the normalized tag.
+fn files_in(dir: &Path) -> BTreeMap<String, PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let tag = tag::normalize(path.file_stem()?.to_str()?)?;
+            Some((tag, path))
+        })
+        .collect()
+}
+
+/// The first of `roots` with a file for `tag`. Bundled roots are the same
+/// files in different install layouts, so only one counts.
+fn find(roots: &[PathBuf], tag: &str) -> Option<PathBuf> {
+    roots.iter().find_map(|root| files_in(root).remove(tag))
+}
+
+/// Every source's strings for `tag` layered in priority order, or `None`
+/// if no source has a usable file for it.
+fn load_tag(
+    dirs: &[(String, Vec<PathBuf>)],
+    tag: &str,
+    warnings: &mut Vec<String>,
+) -> Option<BTreeMap<String, String>> {
+    let mut merged: Option<BTreeMap<String, String>> = None;
+    for (source, roots) in dirs {
+        let Some(path) = find(roots, tag) else {
+            continue;
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let parsed = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| table::parse(&bytes));
+        match parsed {
+            Ok(parsed) => {
+                for warning in parsed.warnings {
+                    log::warn!("Locale {} {}: {}", source, name, warning);
+                    warnings.push(format!("{} {}: {}", source, name, warning));
+                }
+                merged
+                    .get_or_insert_with(BTreeMap::new)
+                    .extend(parsed.strings);
+            }
+            // A broken file, most likely from a mod, only loses its own
+            // strings.
+            Err(e) => {
+                log::warn!("Skipping locale {} {}: {}", source, name, e);
+                warnings.push(format!("{} {}: {}", source, name, e));
+            }
+        }
+    }
+    merged
+}
+
+fn requested(lang: &str) -> Result<String, LocaleError> {
+    tag::normalize(lang).ok_or_else(|| LocaleError::InvalidTag(lang.to_string()))
+}
+
+/// Every language with a file in `resources/locales/` or an enabled mod's
+/// `locales/`, by tag.
+#[tauri::command]
+pub async fn list_locales(app: tauri::AppHandle) -> Vec<LocaleInfo> {
+    let mut found: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (source, roots) in locale_dirs(&app) {
+        let tags: BTreeSet<String> = roots.iter().flat_map(|r| files_in(r).into_keys()).collect();
+        for tag in tags {
+            found.entry(tag).or_default().push(source.clone());
+        }
+    }
+    found
+        .into_iter()
+        .map(|(tag, sources)| LocaleInfo { tag, sources })
+        .collect()
+}
+
+/// The strings for `lang`, or the saved locale without one, merged over
+/// its fallbacks: `zh-CN` over `zh` over `en`, with mods over bundled
+/// files at each step. English keys nothing above English translates are
+/// listed in `missingKeys`. Broken files are skipped with a warning rather
+/// than failing the load.
+#[tauri::command]
+pub async fn load_locale(
+    app: tauri::AppHandle,
+    lang: Option<String>,
+) -> Result<LocaleTable, LocaleError> {
+    let lang = match lang {
+        Some(lang) => lang,
+        None => crate::settings::current(&app)
+            .ok()
+            .and_then(|s| s.locale)
+            .unwrap_or_else(|| tag::FALLBACK.to_string()),
+    };
+    let locale = requested(&lang)?;
+    let dirs = locale_dirs(&app);
+    let mut warnings = Vec::new();
+    let mut loaded = Vec::new();
+
+    let english = load_tag(&dirs, tag::FALLBACK, &mut warnings);
+    let mut translated = BTreeMap::new();
+    // Least specific first, so regional strings win.
+    for tag in tag::chain(&locale).iter().rev() {
+        if tag == tag::FALLBACK {
+            continue;
+        }
+        if let Some(strings) = load_tag(&dirs, tag, &mut warnings) {
+            loaded.insert(0, tag.clone());
+            translated.extend(strings);
+        }
+    }
+    if english.is_some() {
+        loaded.push(tag::FALLBACK.to_string());
+    }
+    if loaded.is_empty() {
+        return Err(LocaleError::NotFound(locale));
+    }
+
+    let mut strings = english.unwrap_or_default();
+    let missing_keys = if locale == tag::FALLBACK {
+        Vec::new()
+    } else {
+        strings
+            .keys()
+            .filter(|key| !translated.contains_key(*key))
+            .cloned()
+            .collect()
+    };
+    strings.extend(translated);
+    Ok(LocaleTable {
+        locale,
+        loaded,
+        strings,
+        missing_keys,
+        warnings,
+    })
+}
+
+/// Saves `lang` as the player's locale and emits `locale-changed` with
+/// the normalized tag. Fails for a language nothing provides strings for.
+#[tauri::command]
+pub async fn set_locale(app: tauri::AppHandle, lang: String) -> Result<String, LocaleError> {
+    let locale = requested(&lang)?;
+    let dirs = locale_dirs(&app);
+    let available = tag::chain(&locale)
+        .iter()
+        .filter(|t| *t != tag::FALLBACK || locale == tag::FALLBACK)
+        .any(|t| dirs.iter().any(|(_, roots)| find(roots, t).is_some()));
+    if !available {
+        return Err(LocaleError::NotFound(locale));
+    }
+
+    crate::settings::update(&app, |settings| settings.locale = Some(locale.clone()))
+        .map_err(|e| LocaleError::Settings(e.to_string()))?;
+    let _ = app.emit(
+        CHANGED_EVENT,
+        LocaleChanged {
+            locale: locale.clone(),
+        },
+    );
+    Ok(locale)
+}
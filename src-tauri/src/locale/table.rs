@@ -0,0 +1,117 @@
+//! Parsing one locale file into flat `key → string` pairs. Nested objects
+//! flatten to dotted keys, so `{"menu": {"play": "Play"}}` and
+//! `{"menu.play": "Play"}` are the same string.
+
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Anything JSON can hold, with objects kept as ordered pairs so repeated
+/// keys are seen instead of silently collapsed.
+enum Node {
+    Text(String),
+    Table(Vec<(String, Node)>),
+    /// Numbers, booleans, arrays and nulls, named for the warning.
+    Other(&'static str),
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string or an object of strings")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Node, E> {
+                Ok(Node::Text(v.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Node, E> {
+                Ok(Node::Text(v))
+            }
+
+            fn visit_bool<E: de::Error>(self, _: bool) -> Result<Node, E> {
+                Ok(Node::Other("a boolean"))
+            }
+
+            fn visit_i64<E: de::Error>(self, _: i64) -> Result<Node, E> {
+                Ok(Node::Other("a number"))
+            }
+
+            fn visit_u64<E: de::Error>(self, _: u64) -> Result<Node, E> {
+                Ok(Node::Other("a number"))
+            }
+
+            fn visit_f64<E: de::Error>(self, _: f64) -> Result<Node, E> {
+                Ok(Node::Other("a number"))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Node, E> {
+                Ok(Node::Other("null"))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(Node::Other("an array"))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+                let mut entries = Vec::new();
+                while let Some((key, value)) = map.next_entry::<String, Node>()? {
+                    entries.push((key, value));
+                }
+                Ok(Node::Table(entries))
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+pub struct ParsedTable {
+    pub strings: BTreeMap<String, String>,
+    /// Repeated keys and values that aren't strings. The file still loads.
+    pub warnings: Vec<String>,
+}
+
+/// Flattens a locale file. Fails only for malformed JSON or a file that
+/// isn't an object; problems within it are warnings.
+pub fn parse(bytes: &[u8]) -> Result<ParsedTable, String> {
+    let entries = match serde_json::from_slice::<Node>(bytes).map_err(|e| e.to_string())? {
+        Node::Table(entries) => entries,
+        _ => return Err("expected an object at the top level".to_string()),
+    };
+    let mut table = ParsedTable {
+        strings: BTreeMap::new(),
+        warnings: Vec::new(),
+    };
+    flatten("", entries, &mut table);
+    Ok(table)
+}
+
+fn flatten(prefix: &str, entries: Vec<(String, Node)>, table: &mut ParsedTable) {
+    for (key, node) in entries {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match node {
+            Node::Text(text) => {
+                if table.strings.insert(key.clone(), text).is_some() {
+                    table
+                        .warnings
+                        .push(format!("duplicate key {:?}; the last one wins", key));
+                }
+            }
+            Node::Table(entries) => flatten(&key, entries, table),
+            Node::Other(what) => table
+                .warnings
+                .push(format!("{:?} is {}, not a string; ignored", key, what)),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Language tags as the locale files are named: `en`, `zh-CN`,
+//! `zh-Hant-TW`.
+
+pub const FALLBACK: &str = "en";
+
+/// The canonical form of `tag`: lowercase language, titlecase script,
+/// uppercase region, `-` separators (`zh_hant_tw` becomes `zh-Hant-TW`).
+/// `None` if it isn't a plausible tag.
+pub fn normalize(tag: &str) -> Option<String> {
+    let mut parts = tag.trim().split(['-', '_']);
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut normalized = language.to_ascii_lowercase();
+    for part in parts {
+        if !(2..=8).contains(&part.len()) || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        normalized.push('-');
+        match part.len() {
+            2 => normalized.push_str(&part.to_ascii_uppercase()),
+            4 => {
+                normalized.push_str(&part[..1].to_ascii_uppercase());
+                normalized.push_str(&part[1..].to_ascii_lowercase());
+            }
+            _ => normalized.push_str(&part.to_ascii_lowercase()),
+        }
+    }
+    Some(normalized)
+}
+
+/// `tag` and each shorter prefix of it, then English: `zh-Hant-TW`,
+/// `zh-Hant`, `zh`, `en`. Most specific first.
+pub fn chain(tag: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut current = tag;
+    loop {
+        chain.push(current.to_string());
+        match current.rfind('-') {
+            Some(i) => current = &current[..i],
+            None => break,
+        }
+    }
+    if !chain.iter().any(|t| t == FALLBACK) {
+        chain.push(FALLBACK.to_string());
+    }
+    chain
+}
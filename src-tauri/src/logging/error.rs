@@ -0,0 +1,27 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the logging commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum LoggingError {
+    #[error("Invalid log target {0:?}")]
+    InvalidTarget(String),
+}
+
+impl LoggingError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LoggingError::InvalidTarget(_) => "invalidTarget",
+        }
+    }
+}
+
+impl Serialize for LoggingError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("LoggingError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,299 @@
+//! The process-wide `log` implementation. Records are formatted on the
+//! caller's thread, kept in a ring for the console overlay and queued to a
+//! writer thread that owns the file, so logging never waits on disk.
+
+use super::rotate::RotatingFile;
+use crate::time::{now_ms, UtcDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::Duration;
+
+/// Lines kept in memory for [`super::get_recent_logs`].
+pub const RECENT_CAPACITY: usize = 2000;
+/// Lines waiting for the writer; past this, new lines skip the file.
+const QUEUE_CAPACITY: usize = 8192;
+/// Targets that follow the runtime level. Everything else, which is our
+/// dependencies, only logs warnings and errors.
+const OWN_TARGETS: [&str; 2] = ["fps_game_lib", "frontend"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn to_level(self) -> log::Level {
+        match self {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Trace => log::Level::Trace,
+        }
+    }
+
+    fn from_level(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warn,
+            log::Level::Info => LogLevel::Info,
+            log::Level::Debug => LogLevel::Debug,
+            log::Level::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    pub timestamp_ms: u64,
+    pub level: LogLevel,
+    pub target: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub fields: Option<Map<String, Value>>,
+}
+
+enum Queued {
+    Line(String),
+    Flush(mpsc::Sender<()>),
+}
+
+pub struct Shared {
+    level: AtomicUsize,
+    recent: Mutex<VecDeque<LogLine>>,
+    queue: Mutex<Option<SyncSender<Queued>>>,
+    dropped: AtomicU64,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            level: AtomicUsize::new(default_level().to_level() as usize),
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+            queue: Mutex::new(None),
+            dropped: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Debug builds log everything of ours; release builds start at info.
+pub fn default_level() -> LogLevel {
+    if cfg!(debug_assertions) {
+        LogLevel::Debug
+    } else {
+        LogLevel::Info
+    }
+}
+
+impl Shared {
+    pub fn level(&self) -> LogLevel {
+        match self.level.load(Ordering::Relaxed) {
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+
+    /// Sets the level for our own targets, and the `log` crate's global
+    /// cap so filtered-out macros cost nothing.
+    pub fn set_level(&self, level: LogLevel) {
+        self.level
+            .store(level.to_level() as usize, Ordering::Relaxed);
+        log::set_max_level(level.max(LogLevel::Warn).to_level().to_level_filter());
+    }
+
+    pub fn enabled(&self, level: log::Level, target: &str) -> bool {
+        let own = OWN_TARGETS
+            .iter()
+            .any(|t| target == *t || target.strip_prefix(t).is_some_and(|r| r.starts_with("::")));
+        let max = if own { self.level() } else { LogLevel::Warn };
+        LogLevel::from_level(level) <= max
+    }
+
+    /// Records `line`. Never blocks on the file: a full queue drops the
+    /// line from the file and counts it, though the overlay still has it.
+    pub fn push(&self, line: LogLine) {
+        let text = format_line(&line);
+        if cfg!(debug_assertions) {
+            eprintln!("{}", text);
+        }
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(line);
+        }
+        if let Some(queue) = self.queue.lock().unwrap().as_ref() {
+            match queue.try_send(Queued::Line(text)) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+                Err(TrySendError::Full(_)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// The newest `count` lines, oldest first.
+    pub fn recent(&self, count: usize) -> Vec<LogLine> {
+        let recent = self.recent.lock().unwrap();
+        let skip = recent.len().saturating_sub(count);
+        recent.iter().skip(skip).cloned().collect()
+    }
+
+    /// As [`Shared::recent`], but empty rather than waiting when the ring
+    /// is locked, which it can be by the very thread that's panicking.
+    pub fn try_recent(&self, count: usize) -> Vec<LogLine> {
+        let recent = match self.recent.try_lock() {
+            Ok(recent) => recent,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return Vec::new(),
+        };
+        let skip = recent.len().saturating_sub(count);
+        recent.iter().skip(skip).cloned().collect()
+    }
+
+    /// Starts the writer thread on `file`. Lines logged before this are
+    /// only in the ring.
+    pub fn attach(self: &Arc<Self>, file: RotatingFile) -> std::io::Result<()> {
+        let (queue, received) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let shared = self.clone();
+        std::thread::Builder::new()
+            .name("log-writer".into())
+            .spawn(move || write_loop(file, &received, &shared))?;
+        *self.queue.lock().unwrap() = Some(queue);
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for everything queued so far to reach disk.
+    pub fn flush(&self, timeout: Duration) {
+        let Some(queue) = self.queue.lock().unwrap().clone() else {
+            return;
+        };
+        let (ack, acked) = mpsc::channel();
+        // Blocking here is fine: only exit and `Log::flush` get here.
+        if queue.send(Queued::Flush(ack)).is_ok() {
+            let _ = acked.recv_timeout(timeout);
+        }
+    }
+}
+
+fn write_loop(mut file: RotatingFile, received: &mpsc::Receiver<Queued>, shared: &Shared) {
+    let mut failed = false;
+    let mut reported_drops = 0;
+    loop {
+        // Flushes to disk whenever the queue runs dry, so a crash loses at
+        // most what arrived in the last moment.
+        let next = match received.try_recv() {
+            Ok(next) => next,
+            Err(_) => {
+                let _ = file.flush();
+                match received.recv_timeout(Duration::from_secs(1)) {
+                    Ok(next) => next,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        };
+        match next {
+            Queued::Line(text) => {
+                let dropped = shared.dropped.load(Ordering::Relaxed);
+                if dropped != reported_drops {
+                    let note = format!(
+                        "{} WARN  fps_game_lib::logging: {} lines were dropped while the log queue was full",
+                        rfc3339(now_ms()),
+                        dropped - reported_drops
+                    );
+                    let _ = file.write_line(&note);
+                    reported_drops = dropped;
+                }
+                match file.write_line(&text) {
+                    Ok(()) => failed = false,
+                    Err(e) if !failed => {
+                        // Once per outage; the logger can't log its own failure.
+                        eprintln!("Failed to write {:?}: {}", file.path(), e);
+                        failed = true;
+                    }
+                    Err(_) => {}
+                }
+            }
+            Queued::Flush(ack) => {
+                let _ = file.flush();
+                let _ = ack.send(());
+            }
+        }
+    }
+}
+
+/// The `log` facade's entry point, shared with the commands.
+pub struct Logger(pub Arc<Shared>);
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.0.enabled(metadata.level(), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.0.push(LogLine {
+            timestamp_ms: now_ms(),
+            level: LogLevel::from_level(record.level()),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            fields: None,
+        });
+    }
+
+    fn flush(&self) {
+        self.0.flush(Duration::from_millis(500));
+    }
+}
+
+/// `2026-01-02T03:04:05.678Z ERROR target: message {"field":1}`
+fn format_line(line: &LogLine) -> String {
+    let level = match line.level {
+        LogLevel::Error => "ERROR",
+        LogLevel::Warn => "WARN ",
+        LogLevel::Info => "INFO ",
+        LogLevel::Debug => "DEBUG",
+        LogLevel::Trace => "TRACE",
+    };
+    // One line per record, whatever the message holds.
+    let message = line.message.replace('\n', "\\n");
+    let mut text = format!(
+        "{} {} {}: {}",
+        rfc3339(line.timestamp_ms),
+        level,
+        line.target,
+        message
+    );
+    if let Some(fields) = line.fields.as_ref().filter(|f| !f.is_empty()) {
+        text.push(' ');
+        text.push_str(&Value::Object(fields.clone()).to_string());
+    }
+    text
+}
+
+/// UTC, to the millisecond.
+fn rfc3339(ms: u64) -> String {
+    let t = UtcDateTime::from_ms(ms);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second, t.millis
+    )
+}
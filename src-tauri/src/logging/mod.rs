@@ -0,0 +1,151 @@
+mod error;
+mod logger;
+mod rotate;
+
+pub use error::LoggingError;
+pub use logger::{LogLevel, LogLine};
+
+use crate::time::now_ms;
+use logger::{Logger, Shared};
+use rotate::RotatingFile;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Manager, Runtime, State};
+
+const FILE_NAME: &str = "fps-game.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept besides the live one.
+const KEEP_FILES: usize = 4;
+const DEFAULT_RECENT_LINES: usize = 200;
+const MAX_TARGET_LEN: usize = 64;
+/// How long exit waits for queued lines to reach disk.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The log stream shared by backend modules, through the `log` macros, and
+/// the frontend, through [`log_message`].
+#[derive(Default)]
+pub struct LogState {
+    shared: Arc<Shared>,
+}
+
+/// The in-memory tail, for code that can't reach app state when it needs
+/// it, like the panic hook.
+#[derive(Clone)]
+pub struct LogTail(Arc<Shared>);
+
+impl LogTail {
+    /// The newest `count` records, or none if taking them would block.
+    pub fn lines(&self, count: usize) -> Vec<LogLine> {
+        self.0.try_recent(count)
+    }
+}
+
+pub fn tail<R: Runtime>(app: &tauri::AppHandle<R>) -> LogTail {
+    LogTail(app.state::<LogState>().shared.clone())
+}
+
+/// Installs the logger and opens the file in the app's log directory.
+/// Called first thing in setup; without a log directory, logging still
+/// reaches the console overlay.
+pub fn init<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let shared = app.state::<LogState>().shared.clone();
+    if log::set_boxed_logger(Box::new(Logger(shared.clone()))).is_err() {
+        return;
+    }
+    shared.set_level(logger::default_level());
+
+    let dir = match app.path().app_log_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::error!("No log directory, logging to memory only: {}", e);
+            return;
+        }
+    };
+    match RotatingFile::open(&dir, FILE_NAME, MAX_FILE_BYTES, KEEP_FILES) {
+        Ok(file) => {
+            let path = file.path();
+            match shared.attach(file) {
+                Ok(()) => log::info!(
+                    "fps-game {} logging to {:?}",
+                    app.package_info().version,
+                    path
+                ),
+                Err(e) => log::error!("Failed to start the log writer: {}", e),
+            }
+        }
+        Err(e) => log::error!("Failed to open {:?}: {}", dir.join(FILE_NAME), e),
+    }
+}
+
+/// The log files on disk, live one first, then older rotations.
+pub fn log_files<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<PathBuf> {
+    let Ok(dir) = app.path().app_log_dir() else {
+        return Vec::new();
+    };
+    std::iter::once(dir.join(FILE_NAME))
+        .chain((1..=KEEP_FILES).map(|n| dir.join(format!("{}.{}", FILE_NAME, n))))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Writes everything logged so far to disk, on exit and before the files
+/// are read.
+pub fn flush<R: Runtime>(app: &tauri::AppHandle<R>) {
+    app.state::<LogState>().shared.flush(FLUSH_TIMEOUT);
+}
+
+/// Writes one record from the frontend. Its target goes under `frontend::`
+/// (`frontend` alone without one) so it can be told apart from the
+/// backend's, and filtered by the same level.
+#[tauri::command]
+pub fn log_message(
+    logs: State<'_, LogState>,
+    level: LogLevel,
+    target: Option<String>,
+    message: String,
+    fields: Option<Map<String, Value>>,
+) -> Result<(), LoggingError> {
+    let target = match target.filter(|t| !t.is_empty()) {
+        Some(t) if !valid_target(&t) => return Err(LoggingError::InvalidTarget(t)),
+        Some(t) => format!("frontend::{}", t),
+        None => "frontend".to_string(),
+    };
+    if logs.shared.enabled(level.to_level(), &target) {
+        logs.shared.push(LogLine {
+            timestamp_ms: now_ms(),
+            level,
+            target,
+            message,
+            fields,
+        });
+    }
+    Ok(())
+}
+
+/// Names like `hud`, `net::lobby` or `weapon-select`.
+fn valid_target(target: &str) -> bool {
+    target.len() <= MAX_TARGET_LEN
+        && target
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'))
+}
+
+/// The newest `lines` records, oldest first, for the console overlay.
+/// Defaults to 200; at most the last two thousand are kept.
+#[tauri::command]
+pub fn get_recent_logs(logs: State<'_, LogState>, lines: Option<usize>) -> Vec<LogLine> {
+    let lines = lines
+        .unwrap_or(DEFAULT_RECENT_LINES)
+        .min(logger::RECENT_CAPACITY);
+    logs.shared.recent(lines)
+}
+
+/// Changes the level for the game's own records, backend and frontend
+/// alike, from the next record on. Dependencies stay at warnings and above.
+#[tauri::command]
+pub fn set_log_level(logs: State<'_, LogState>, level: LogLevel) {
+    logs.shared.set_level(level);
+    log::info!("Log level set to {:?}", level);
+}
@@ -0,0 +1,164 @@
+//! A log file that rolls over by size: `fps-game.log` is the live file,
+//! and on rotation each `fps-game.log.N` moves to `N + 1`, dropping the
+//! oldest.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub struct RotatingFile {
+    dir: PathBuf,
+    name: String,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<BufWriter<File>>,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Opens `dir/name` for appending, creating the directory. A file
+    /// left over from the last run keeps filling up to the limit.
+    pub fn open(dir: &Path, name: &str, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            max_bytes,
+            keep,
+            file: Some(BufWriter::new(file)),
+            written,
+        })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(&self.name)
+    }
+
+    /// Appends `line`, rotating first if it would take the file past the
+    /// limit. A line longer than the limit still goes into a fresh file.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            // A failed rotation left no live file; try again.
+            None => self.reopen()?,
+        };
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+        self.written += len;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn numbered(&self, n: usize) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.name, n))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Closed before renaming, which Windows requires.
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        if self.keep == 0 {
+            let _ = std::fs::remove_file(self.path());
+        } else {
+            let _ = std::fs::remove_file(self.numbered(self.keep));
+            for n in (1..self.keep).rev() {
+                match std::fs::rename(self.numbered(n), self.numbered(n + 1)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            std::fs::rename(self.path(), self.numbered(1))?;
+        }
+        self.reopen()?;
+        Ok(())
+    }
+
+    fn reopen(&mut self) -> io::Result<&mut BufWriter<File>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path())?;
+        self.written = file.metadata()?.len();
+        Ok(self.file.insert(BufWriter::new(file)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn rolls_over_and_drops_the_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        // Each "line N" is 7 bytes with its newline, so two fit in 16.
+        let mut log = RotatingFile::open(dir.path(), "game.log", 16, 2).unwrap();
+        for n in 1..=7 {
+            log.write_line(&format!("line {}", n)).unwrap();
+        }
+        log.flush().unwrap();
+
+        let live = log.path();
+        assert_eq!(read(&live), "line 7\n");
+        assert_eq!(read(&dir.path().join("game.log.1")), "line 5\nline 6\n");
+        assert_eq!(read(&dir.path().join("game.log.2")), "line 3\nline 4\n");
+        assert!(!dir.path().join("game.log.3").exists());
+    }
+
+    #[test]
+    fn keeping_none_truncates_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = RotatingFile::open(dir.path(), "game.log", 10, 0).unwrap();
+        for n in 1..=3 {
+            log.write_line(&format!("line {}", n)).unwrap();
+        }
+        log.flush().unwrap();
+        assert_eq!(read(&log.path()), "line 3\n");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn long_lines_go_into_a_fresh_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = RotatingFile::open(dir.path(), "game.log", 8, 1).unwrap();
+        let long = "x".repeat(20);
+        log.write_line("short").unwrap();
+        log.write_line(&long).unwrap();
+        log.flush().unwrap();
+        assert_eq!(read(&log.path()), format!("{}\n", long));
+        assert_eq!(read(&dir.path().join("game.log.1")), "short\n");
+    }
+
+    #[test]
+    fn reopening_keeps_filling_the_last_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = RotatingFile::open(dir.path(), "game.log", 16, 1).unwrap();
+        log.write_line("line 1").unwrap();
+        log.flush().unwrap();
+        drop(log);
+
+        let mut log = RotatingFile::open(dir.path(), "game.log", 16, 1).unwrap();
+        log.write_line("line 2").unwrap();
+        log.write_line("line 3").unwrap();
+        log.flush().unwrap();
+        assert_eq!(read(&log.path()), "line 3\n");
+        assert_eq!(read(&dir.path().join("game.log.1")), "line 1\nline 2\n");
+    }
+}
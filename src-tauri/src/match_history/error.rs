@@ -0,0 +1,55 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the match history commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum MatchHistoryError {
+    #[error("Could not locate the profile directory: {0}")]
+    NoProfileDir(String),
+
+    #[error("Could not locate the documents directory: {0}")]
+    NoDocumentDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid match summary: {0}")]
+    InvalidSummary(String),
+
+    #[error("Export path {0:?} must be absolute")]
+    InvalidExportPath(PathBuf),
+}
+
+impl MatchHistoryError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        MatchHistoryError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MatchHistoryError::NoProfileDir(_) => "noProfileDir",
+            MatchHistoryError::NoDocumentDir(_) => "noDocumentDir",
+            MatchHistoryError::Io { .. } => "io",
+            MatchHistoryError::InvalidSummary(_) => "invalidSummary",
+            MatchHistoryError::InvalidExportPath(_) => "invalidExportPath",
+        }
+    }
+}
+
+impl Serialize for MatchHistoryError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("MatchHistoryError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,98 @@
+use super::model::{ExportFormat, MatchRecord};
+use serde::Serialize;
+use std::fmt::Write;
+
+const CSV_HEADER: &[&str] = &[
+    "id",
+    "recordedAtMs",
+    "playerName",
+    "mapName",
+    "kills",
+    "deaths",
+    "accuracy",
+    "durationMs",
+    "weapons",
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonExport<'a> {
+    version: u32,
+    exported_at_ms: u64,
+    matches: &'a [MatchRecord],
+}
+
+/// Renders `matches` (oldest first) in `format`.
+pub fn render(format: ExportFormat, matches: &[MatchRecord], exported_at_ms: u64) -> Vec<u8> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(&JsonExport {
+            version: 1,
+            exported_at_ms,
+            matches,
+        })
+        .expect("match export serialize"),
+        ExportFormat::Csv => to_csv(matches).into_bytes(),
+    }
+}
+
+/// One row per match. Weapon usage is flattened into a single
+/// `weapon:shots/hits/kills; ...` column so the row count stays one per match.
+fn to_csv(matches: &[MatchRecord]) -> String {
+    let mut out = String::new();
+    push_row(&mut out, CSV_HEADER.iter().map(|h| h.to_string()));
+    for record in matches {
+        let s = &record.summary;
+        let weapons = s
+            .weapon_usage
+            .iter()
+            .map(|w| format!("{}:{}/{}/{}", w.weapon, w.shots, w.hits, w.kills))
+            .collect::<Vec<_>>()
+            .join("; ");
+        push_row(
+            &mut out,
+            [
+                record.id.to_string(),
+                record.recorded_at_ms.to_string(),
+                text_cell(&s.player_name),
+                text_cell(&s.map_name),
+                s.kills.to_string(),
+                s.deaths.to_string(),
+                format!("{:.4}", s.accuracy),
+                s.duration_ms.to_string(),
+                text_cell(&weapons),
+            ]
+            .into_iter(),
+        );
+    }
+    out
+}
+
+fn push_row(out: &mut String, cells: impl Iterator<Item = String>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_cell(out, &cell);
+    }
+    out.push_str("\r\n");
+}
+
+/// RFC 4180 quoting: wrap in quotes when the cell holds a separator, quote
+/// or line break, doubling any embedded quotes.
+fn push_cell(out: &mut String, cell: &str) {
+    if cell.contains([',', '"', '\r', '\n']) {
+        let _ = write!(out, "\"{}\"", cell.replace('"', "\"\""));
+    } else {
+        out.push_str(cell);
+    }
+}
+
+/// Player-chosen text is prefixed with `'` when a spreadsheet would read it
+/// as a formula.
+fn text_cell(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
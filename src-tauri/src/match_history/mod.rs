@@ -0,0 +1,98 @@
+mod error;
+mod export;
+mod model;
+mod store;
+
+pub use error::MatchHistoryError;
+pub use model::{ExportFormat, MatchPage, MatchRecord, MatchSummary};
+pub use store::MatchHistoryStore;
+
+use crate::time::now_ms;
+use std::path::PathBuf;
+use store::file_path;
+use tauri::path::BaseDirectory;
+use tauri::{Manager, State};
+
+const DEFAULT_PAGE_LIMIT: usize = 20;
+const EXPORT_DIR: &str = "fps-game";
+
+/// Stores a finished round in the active profile's history, pruning the
+/// oldest matches beyond the cap.
+#[tauri::command]
+pub async fn record_match(
+    app: tauri::AppHandle,
+    store: State<'_, MatchHistoryStore>,
+    summary: MatchSummary,
+) -> Result<MatchRecord, MatchHistoryError> {
+    let summary = summary.validated()?;
+    let path = file_path(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    let mut file = store::read(&path)?;
+    let record = file.record(summary, now_ms(), store.max_matches());
+    store::write(&path, &mut file)?;
+    Ok(record)
+}
+
+/// Newest-first history. Leave `anchor` unset for the first page and pass
+/// back the returned one when fetching later pages.
+#[tauri::command]
+pub async fn get_match_history(
+    app: tauri::AppHandle,
+    store: State<'_, MatchHistoryStore>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    anchor: Option<u64>,
+) -> Result<MatchPage, MatchHistoryError> {
+    let path = file_path(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    let file = store::read(&path)?;
+    Ok(file.page(
+        limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+        offset.unwrap_or(0),
+        anchor,
+    ))
+}
+
+/// Writes the whole history as JSON or CSV and returns the path written.
+/// `path` may be a file or an existing directory; without one the export goes
+/// to `Documents/fps-game/`.
+#[tauri::command]
+pub async fn export_match_history(
+    app: tauri::AppHandle,
+    store: State<'_, MatchHistoryStore>,
+    format: ExportFormat,
+    path: Option<String>,
+) -> Result<String, MatchHistoryError> {
+    let matches = {
+        let path = file_path(&app)?;
+        let _guard = store.lock.lock().unwrap();
+        store::read(&path)?.matches
+    };
+
+    let exported_at_ms = now_ms();
+    let default_name = format!("match-history-{}.{}", exported_at_ms, format.extension());
+    let target = match path.map(PathBuf::from) {
+        Some(path) if !path.is_absolute() => {
+            return Err(MatchHistoryError::InvalidExportPath(path))
+        }
+        Some(path) if path.is_dir() => path.join(default_name),
+        Some(path) => path,
+        None => app
+            .path()
+            .resolve(EXPORT_DIR, BaseDirectory::Document)
+            .map_err(|e| MatchHistoryError::NoDocumentDir(e.to_string()))?
+            .join(default_name),
+    };
+
+    let bytes = export::render(format, &matches, exported_at_ms);
+    crate::fs_atomic::write_atomic(&target, &bytes)
+        .map_err(|e| MatchHistoryError::io(&target, e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// Sets how many matches are kept per profile. Takes effect on the next
+/// recorded match.
+#[tauri::command]
+pub fn set_match_history_cap(store: State<'_, MatchHistoryStore>, max_matches: usize) {
+    store.set_max_matches(max_matches);
+}
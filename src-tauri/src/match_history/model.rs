@@ -0,0 +1,100 @@
+use super::MatchHistoryError;
+use serde::{Deserialize, Serialize};
+
+const MAX_NAME_CHARS: usize = 64;
+
+/// End-of-round stats as computed by the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchSummary {
+    pub player_name: String,
+    pub map_name: String,
+    pub kills: u32,
+    pub deaths: u32,
+    /// Hits over shots fired across all weapons, `0.0..=1.0`.
+    pub accuracy: f64,
+    #[serde(default)]
+    pub weapon_usage: Vec<WeaponUsage>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponUsage {
+    pub weapon: String,
+    pub shots: u32,
+    pub hits: u32,
+    pub kills: u32,
+}
+
+/// A stored match. `id` increases with every recorded match and is never
+/// reused, even after pruning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRecord {
+    pub id: u64,
+    pub recorded_at_ms: u64,
+    #[serde(flatten)]
+    pub summary: MatchSummary,
+}
+
+/// One page of history, newest first. Pass `anchor` back with the next
+/// offset so matches recorded in between don't shift the pages.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchPage {
+    pub matches: Vec<MatchRecord>,
+    /// Matches at or below `anchor`.
+    pub total: usize,
+    pub anchor: u64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl MatchSummary {
+    pub fn validated(mut self) -> Result<Self, MatchHistoryError> {
+        self.player_name = clean_name("player name", &self.player_name)?;
+        self.map_name = clean_name("map name", &self.map_name)?;
+        if !(0.0..=1.0).contains(&self.accuracy) {
+            return Err(MatchHistoryError::InvalidSummary(format!(
+                "accuracy {} is outside 0..=1",
+                self.accuracy
+            )));
+        }
+        for usage in &mut self.weapon_usage {
+            usage.weapon = clean_name("weapon", &usage.weapon)?;
+            if usage.hits > usage.shots {
+                return Err(MatchHistoryError::InvalidSummary(format!(
+                    "{} has more hits than shots",
+                    usage.weapon
+                )));
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn clean_name(field: &str, value: &str) -> Result<String, MatchHistoryError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed.chars().count() > MAX_NAME_CHARS {
+        return Err(MatchHistoryError::InvalidSummary(format!(
+            "{} must be 1-{} characters",
+            field, MAX_NAME_CHARS
+        )));
+    }
+    Ok(trimmed.to_string())
+}
@@ -0,0 +1,124 @@
+use super::model::{MatchPage, MatchRecord, MatchSummary};
+use super::MatchHistoryError;
+use crate::fs_atomic::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::Runtime;
+
+pub const FILE_NAME: &str = "match-history.json";
+
+pub const DEFAULT_MAX_MATCHES: usize = 500;
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Serializes read-modify-write cycles on `match-history.json` and holds the
+/// number of matches kept before the oldest are pruned.
+pub struct MatchHistoryStore {
+    pub(super) lock: Mutex<()>,
+    max_matches: AtomicUsize,
+}
+
+impl Default for MatchHistoryStore {
+    fn default() -> Self {
+        Self {
+            lock: Mutex::new(()),
+            max_matches: AtomicUsize::new(DEFAULT_MAX_MATCHES),
+        }
+    }
+}
+
+impl MatchHistoryStore {
+    pub fn max_matches(&self) -> usize {
+        self.max_matches.load(Ordering::Relaxed)
+    }
+
+    pub fn set_max_matches(&self, count: usize) {
+        self.max_matches.store(count, Ordering::Relaxed);
+    }
+}
+
+/// Matches oldest first, so recording is a push and pruning drains the front.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryFile {
+    pub version: u32,
+    pub next_id: u64,
+    pub matches: Vec<MatchRecord>,
+}
+
+impl HistoryFile {
+    /// Appends `summary` and prunes down to `cap` matches.
+    pub fn record(
+        &mut self,
+        summary: MatchSummary,
+        recorded_at_ms: u64,
+        cap: usize,
+    ) -> MatchRecord {
+        // Ids start at 1 so an anchor of 0 can mean "nothing recorded yet".
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        let record = MatchRecord {
+            id,
+            recorded_at_ms,
+            summary,
+        };
+        self.matches.push(record.clone());
+        self.prune(cap);
+        record
+    }
+
+    pub fn prune(&mut self, cap: usize) {
+        let excess = self.matches.len().saturating_sub(cap);
+        self.matches.drain(..excess);
+    }
+
+    /// Newest-first page counted down from `anchor`, or from the newest match
+    /// when no anchor is given.
+    pub fn page(&self, limit: usize, offset: usize, anchor: Option<u64>) -> MatchPage {
+        let anchor = anchor.unwrap_or_else(|| self.matches.last().map_or(0, |m| m.id));
+        // Ids ascend with position, so everything up to the anchor is a prefix.
+        let visible = self.matches.partition_point(|m| m.id <= anchor);
+        let matches = self.matches[..visible]
+            .iter()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect();
+        MatchPage {
+            matches,
+            total: visible,
+            anchor,
+        }
+    }
+}
+
+pub fn file_path<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, MatchHistoryError> {
+    crate::profiles::active_dir(app)
+        .map(|dir| dir.join(FILE_NAME))
+        .map_err(|e| MatchHistoryError::NoProfileDir(e.to_string()))
+}
+
+/// A missing file is an empty history. An unreadable one is an error so the
+/// next recorded match can't overwrite it.
+pub fn read(path: &Path) -> Result<HistoryFile, MatchHistoryError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HistoryFile::default()),
+        Err(e) => return Err(MatchHistoryError::io(path, e)),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| {
+        MatchHistoryError::io(
+            path,
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+pub fn write(path: &Path, file: &mut HistoryFile) -> Result<(), MatchHistoryError> {
+    file.version = FORMAT_VERSION;
+    let json = serde_json::to_vec(file).expect("match history serialize");
+    write_atomic(path, &json).map_err(|e| MatchHistoryError::io(path, e))
+}
@@ -0,0 +1,55 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the mod commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum ModError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Could not locate the profile directory: {0}")]
+    NoProfileDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No mod named {0:?} is installed")]
+    UnknownMod(String),
+
+    #[error("Invalid load order: {0}")]
+    InvalidOrder(String),
+}
+
+impl ModError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        ModError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ModError::NoDataDir(_) => "noDataDir",
+            ModError::NoProfileDir(_) => "noProfileDir",
+            ModError::Io { .. } => "io",
+            ModError::UnknownMod(_) => "unknownMod",
+            ModError::InvalidOrder(_) => "invalidOrder",
+        }
+    }
+}
+
+impl Serialize for ModError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ModError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,170 @@
+mod error;
+mod scan;
+mod store;
+
+pub use error::ModError;
+pub use scan::ModInfo;
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Manager, Runtime, State};
+
+const DIR_NAME: &str = "mods";
+
+/// Serializes changes to `mods.json`, and caches the asset roots of the
+/// enabled mods for the profile they were worked out for.
+#[derive(Default)]
+pub struct ModRegistry {
+    lock: Mutex<()>,
+    roots: Mutex<Option<CachedRoots>>,
+}
+
+/// The enabled mods' ids and asset roots, and the profile they're for.
+struct CachedRoots {
+    profile: PathBuf,
+    roots: Vec<(String, PathBuf)>,
+}
+
+impl ModRegistry {
+    fn invalidate(&self) {
+        *self.roots.lock().unwrap() = None;
+    }
+}
+
+fn mods_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, ModError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| ModError::NoDataDir(e.to_string()))
+}
+
+/// Installed mods in load order, with the active profile's choices.
+fn installed<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<Vec<ModInfo>, ModError> {
+    let dir = mods_dir(app)?;
+    let mut mods = scan::scan(&dir).map_err(|e| ModError::io(&dir, e))?;
+    let config = store::read(&store::config_path(app)?)?;
+    let ids: Vec<String> = mods.iter().map(|m| m.id.clone()).collect();
+    let order = store::load_order(&config, &ids);
+    mods.sort_by_key(|m| order.iter().position(|id| *id == m.id));
+    for info in &mut mods {
+        info.enabled = config.enabled.contains(&info.id);
+    }
+    Ok(mods)
+}
+
+/// Asset roots of the usable, enabled mods, highest priority (last
+/// loaded) first, for asset resolution.
+pub fn asset_roots<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = enabled_roots(app).into_iter().map(|(_, r)| r).collect();
+    roots.reverse();
+    roots
+}
+
+/// Ids and asset roots of the usable, enabled mods in load order.
+/// Rescanned after any mod command or profile switch.
+pub fn enabled_roots<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<(String, PathBuf)> {
+    let Some(registry) = app.try_state::<ModRegistry>() else {
+        return Vec::new();
+    };
+    let Ok(profile) = crate::profiles::active_dir(app) else {
+        return Vec::new();
+    };
+    if let Some(cached) = registry.roots.lock().unwrap().as_ref() {
+        if cached.profile == profile {
+            return cached.roots.clone();
+        }
+    }
+    let roots: Vec<(String, PathBuf)> = match installed(app) {
+        Ok(mods) => mods
+            .into_iter()
+            .filter(|m| m.enabled)
+            .filter_map(|m| Some((m.id, m.asset_root?)))
+            .collect(),
+        Err(e) => {
+            log::warn!("Mods are ignored: {}", e);
+            Vec::new()
+        }
+    };
+    *registry.roots.lock().unwrap() = Some(CachedRoots {
+        profile,
+        roots: roots.clone(),
+    });
+    roots
+}
+
+/// Every directory under `AppData/mods/`, in load order. Mods whose
+/// `mod.json` is missing or broken are included with an `error`.
+#[tauri::command]
+pub async fn list_mods(
+    app: tauri::AppHandle,
+    registry: State<'_, ModRegistry>,
+) -> Result<Vec<ModInfo>, ModError> {
+    // Picks up mods added or removed since the last scan.
+    registry.invalidate();
+    installed(&app)
+}
+
+#[tauri::command]
+pub async fn set_mod_enabled(
+    app: tauri::AppHandle,
+    registry: State<'_, ModRegistry>,
+    id: String,
+    enabled: bool,
+) -> Result<(), ModError> {
+    let dir = mods_dir(&app)?;
+    let path = store::config_path(&app)?;
+    let _guard = registry.lock.lock().unwrap();
+    if !scan::scan(&dir)
+        .map_err(|e| ModError::io(&dir, e))?
+        .iter()
+        .any(|m| m.id == id)
+    {
+        return Err(ModError::UnknownMod(id));
+    }
+    let mut config = store::read(&path)?;
+    config.enabled.retain(|e| *e != id);
+    if enabled {
+        config.enabled.push(id);
+    }
+    store::write(&path, &config)?;
+    registry.invalidate();
+    Ok(())
+}
+
+/// Installed mod ids, first loaded first. Later mods override earlier
+/// ones where they provide the same asset.
+#[tauri::command]
+pub async fn get_mod_load_order(app: tauri::AppHandle) -> Result<Vec<String>, ModError> {
+    Ok(installed(&app)?.into_iter().map(|m| m.id).collect())
+}
+
+/// Saves `order` for the active profile and returns the resulting full
+/// order. Installed mods left out of it keep loading last, by id.
+#[tauri::command]
+pub async fn set_mod_load_order(
+    app: tauri::AppHandle,
+    registry: State<'_, ModRegistry>,
+    order: Vec<String>,
+) -> Result<Vec<String>, ModError> {
+    let dir = mods_dir(&app)?;
+    let path = store::config_path(&app)?;
+    let _guard = registry.lock.lock().unwrap();
+    let ids: Vec<String> = scan::scan(&dir)
+        .map_err(|e| ModError::io(&dir, e))?
+        .into_iter()
+        .map(|m| m.id)
+        .collect();
+    for (i, id) in order.iter().enumerate() {
+        if !ids.contains(id) {
+            return Err(ModError::UnknownMod(id.clone()));
+        }
+        if order[..i].contains(id) {
+            return Err(ModError::InvalidOrder(format!("{:?} is listed twice", id)));
+        }
+    }
+    let mut config = store::read(&path)?;
+    config.order = order;
+    store::write(&path, &config)?;
+    registry.invalidate();
+    Ok(store::load_order(&config, &ids))
+}
@@ -0,0 +1,122 @@
+//! Reading `AppData/mods/<dir>/mod.json`. A mod that can't be used is
+//! still listed, with the reason in `error`.
+
+use crate::assets::sanitize_relative;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_NAME: &str = "mod.json";
+const MAX_MANIFEST_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModManifest {
+    name: String,
+    version: String,
+    #[serde(default)]
+    author: Option<String>,
+    /// Directory inside the mod holding `audio/`, `textures/`... Defaults
+    /// to the mod directory itself.
+    #[serde(default)]
+    asset_root: Option<String>,
+}
+
+/// An installed mod. `id` is its directory name, which is what the
+/// commands and the load order refer to; `name` is what it calls itself.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModInfo {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub enabled: bool,
+    /// Why the mod can't be used, when it can't. Such mods are skipped
+    /// when resolving assets even if enabled.
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub asset_root: Option<PathBuf>,
+}
+
+/// Every subdirectory of `dir`, sorted by id. A missing `dir` has none.
+pub fn scan(dir: &Path) -> std::io::Result<Vec<ModInfo>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut mods: Vec<ModInfo> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.to_string();
+            Some(read(&entry.path(), id))
+        })
+        .collect();
+    mods.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(mods)
+}
+
+fn read(mod_dir: &Path, id: String) -> ModInfo {
+    let mut info = ModInfo {
+        name: id.clone(),
+        id,
+        version: None,
+        author: None,
+        enabled: false,
+        error: None,
+        asset_root: None,
+    };
+    match load(mod_dir) {
+        Ok((manifest, asset_root)) => {
+            info.name = manifest.name;
+            info.version = Some(manifest.version);
+            info.author = manifest.author;
+            info.asset_root = Some(asset_root);
+        }
+        Err(reason) => info.error = Some(reason),
+    }
+    info
+}
+
+fn load(mod_dir: &Path) -> Result<(ModManifest, PathBuf), String> {
+    let path = mod_dir.join(MANIFEST_NAME);
+    let size = match std::fs::metadata(&path) {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!("no {}", MANIFEST_NAME))
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    if size > MAX_MANIFEST_BYTES {
+        return Err(format!(
+            "{} is over {} bytes",
+            MANIFEST_NAME, MAX_MANIFEST_BYTES
+        ));
+    }
+    let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let manifest: ModManifest =
+        serde_json::from_slice(&bytes).map_err(|e| format!("invalid {}: {}", MANIFEST_NAME, e))?;
+    if manifest.name.trim().is_empty() {
+        return Err(format!("{} has an empty name", MANIFEST_NAME));
+    }
+
+    let root = match manifest.asset_root.as_deref() {
+        None | Some("") | Some(".") => mod_dir.to_path_buf(),
+        Some(root) => {
+            mod_dir.join(sanitize_relative(root).map_err(|e| format!("invalid assetRoot: {}", e))?)
+        }
+    };
+    if !root.is_dir() {
+        return Err(format!("asset root {:?} doesn't exist", root));
+    }
+    // The root must not be a symlink out of the mod.
+    let inside = match (root.canonicalize(), mod_dir.canonicalize()) {
+        (Ok(root), Ok(dir)) => root.starts_with(dir),
+        _ => false,
+    };
+    if !inside {
+        return Err("assetRoot resolves outside of the mod".to_string());
+    }
+    Ok((manifest, root))
+}
@@ -0,0 +1,68 @@
+//! The active profile's mod choices, in `<profile>/mods.json`.
+
+use super::ModError;
+use crate::fs_atomic::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::Runtime;
+
+pub const CONFIG_FILE: &str = "mods.json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModConfig {
+    version: u32,
+    /// Mod ids, first loaded first; later mods override earlier ones.
+    /// Installed mods missing from it load last, by id.
+    pub order: Vec<String>,
+    pub enabled: Vec<String>,
+}
+
+pub fn config_path<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, ModError> {
+    crate::profiles::active_dir(app)
+        .map(|dir| dir.join(CONFIG_FILE))
+        .map_err(|e| ModError::NoProfileDir(e.to_string()))
+}
+
+/// The saved config, or the default (nothing enabled) when there is none
+/// or it can't be parsed.
+pub fn read(path: &Path) -> Result<ModConfig, ModError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ModConfig::default()),
+        Err(e) => return Err(ModError::io(path, e)),
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(config) => Ok(config),
+        Err(e) => {
+            log::warn!("Invalid {:?} ({}); no mods enabled", path, e);
+            Ok(ModConfig::default())
+        }
+    }
+}
+
+pub fn write(path: &Path, config: &ModConfig) -> Result<(), ModError> {
+    let config = ModConfig {
+        version: FORMAT_VERSION,
+        ..config.clone()
+    };
+    let json = serde_json::to_vec_pretty(&config).expect("mod config serialize");
+    write_atomic(path, &json).map_err(|e| ModError::io(path, e))
+}
+
+/// `installed` ids in load order.
+pub fn load_order(config: &ModConfig, installed: &[String]) -> Vec<String> {
+    let mut order: Vec<String> = config
+        .order
+        .iter()
+        .filter(|id| installed.contains(id))
+        .cloned()
+        .collect();
+    for id in installed {
+        if !order.contains(id) {
+            order.push(id.clone());
+        }
+    }
+    order
+}
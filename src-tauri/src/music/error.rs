@@ -0,0 +1,60 @@
+use crate::assets::AssetError;
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the music commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum MusicError {
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+
+    #[error("Failed to decode {filename}: {reason}")]
+    Decode { filename: String, reason: String },
+
+    #[error("No audio output: {0}")]
+    NoOutput(String),
+
+    #[error("Failed to play {filename}: {reason}")]
+    Playback { filename: String, reason: String },
+
+    #[error("Volume {0} is outside 0..=1")]
+    InvalidVolume(f32),
+
+    #[error("Fade of {ms} ms is longer than {max} ms")]
+    InvalidFade { ms: u32, max: u32 },
+
+    #[error("The music player has stopped")]
+    PlayerStopped,
+
+    #[error("Failed to start the music thread: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    #[error("Background music task failed: {0}")]
+    Task(String),
+}
+
+impl MusicError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MusicError::Asset(e) => e.kind(),
+            MusicError::Decode { .. } => "decode",
+            MusicError::NoOutput(_) => "noOutput",
+            MusicError::Playback { .. } => "playback",
+            MusicError::InvalidVolume(_) => "invalidVolume",
+            MusicError::InvalidFade { .. } => "invalidFade",
+            MusicError::PlayerStopped => "playerStopped",
+            MusicError::Spawn(_) => "spawn",
+            MusicError::Task(_) => "task",
+        }
+    }
+}
+
+impl Serialize for MusicError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("MusicError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,206 @@
+//! Gains that ramp sample by sample, so fades and volume changes never
+//! jump. A [`Gain`] is set from any thread; each [`Ramped`] source playing
+//! through it notices within a few samples and slides to the new value.
+
+use rodio::Source;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How many samples pass between looks at the gain's setting.
+const CHECK_EVERY: u32 = 64;
+
+/// A target and how long to take getting there, packed into one atomic so
+/// the two are always read together.
+pub struct Gain(AtomicU64);
+
+impl Gain {
+    pub fn new(value: f32) -> Arc<Self> {
+        Arc::new(Self(AtomicU64::new(pack(value, Duration::ZERO))))
+    }
+
+    pub fn ramp_to(&self, target: f32, over: Duration) {
+        self.0.store(pack(target, over), Ordering::Relaxed);
+    }
+
+    fn load(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+fn pack(target: f32, over: Duration) -> u64 {
+    let ms = over.as_millis().min(u32::MAX as u128) as u64;
+    ((target.to_bits() as u64) << 32) | ms
+}
+
+fn unpack(setting: u64) -> (f32, u64) {
+    (
+        f32::from_bits((setting >> 32) as u32),
+        setting & 0xffff_ffff,
+    )
+}
+
+/// `inner` scaled by `gain`, moving linearly to each new setting.
+pub struct Ramped<S> {
+    inner: S,
+    gain: Arc<Gain>,
+    setting: Option<u64>,
+    current: f32,
+    target: f32,
+    step: f32,
+    until_check: u32,
+}
+
+impl<S: Source<Item = f32>> Ramped<S> {
+    /// Starts at the gain's current target rather than ramping to it.
+    pub fn new(inner: S, gain: Arc<Gain>) -> Self {
+        let (start, _) = unpack(gain.load());
+        Self {
+            inner,
+            gain,
+            setting: None,
+            current: start,
+            target: start,
+            step: 0.0,
+            until_check: 0,
+        }
+    }
+
+    fn check(&mut self) {
+        let setting = self.gain.load();
+        if self.setting == Some(setting) {
+            return;
+        }
+        self.setting = Some(setting);
+        let (target, ms) = unpack(setting);
+        let rate = self.inner.sample_rate() as u64 * self.inner.channels() as u64;
+        let samples = (ms * rate / 1000).max(1);
+        self.target = target;
+        self.step = (target - self.current).abs() / samples as f32;
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Ramped<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.until_check == 0 {
+            self.check();
+            self.until_check = CHECK_EVERY;
+        }
+        self.until_check -= 1;
+        let sample = self.inner.next()?;
+        if self.current < self.target {
+            self.current = (self.current + self.step).min(self.target);
+        } else if self.current > self.target {
+            self.current = (self.current - self.step).max(self.target);
+        }
+        Some(sample * self.current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Ramped<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Full scale at 1 kHz mono, so a millisecond is one sample.
+    struct Ones;
+
+    impl Iterator for Ones {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            Some(1.0)
+        }
+    }
+
+    impl Source for Ones {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            1
+        }
+
+        fn sample_rate(&self) -> u32 {
+            1000
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    fn take(source: &mut Ramped<Ones>, n: usize) -> Vec<f32> {
+        source.by_ref().take(n).collect()
+    }
+
+    #[test]
+    fn ramps_take_their_duration_in_even_steps() {
+        let gain = Gain::new(0.0);
+        let mut source = Ramped::new(Ones, gain.clone());
+        assert!(take(&mut source, 10).iter().all(|s| *s == 0.0));
+
+        // Noticed at the next check, then 1000 steps of 0.001.
+        gain.ramp_to(1.0, Duration::from_secs(1));
+        let out = take(&mut source, 2000);
+        let start = out.iter().position(|s| *s > 0.0).unwrap();
+        assert!(start < CHECK_EVERY as usize);
+        let ramp = &out[start..];
+        for pair in ramp.windows(2) {
+            assert!(pair[1] >= pair[0] && pair[1] - pair[0] <= 0.001 + 1e-6);
+        }
+        let done = ramp.iter().position(|s| *s == 1.0).unwrap();
+        assert!((998..=1001).contains(&done), "{}", done);
+    }
+
+    #[test]
+    fn a_new_setting_mid_ramp_continues_from_where_it_got_to() {
+        let gain = Gain::new(1.0);
+        let mut source = Ramped::new(Ones, gain.clone());
+        gain.ramp_to(0.0, Duration::from_millis(640));
+        let out = take(&mut source, 320);
+        let reached = *out.last().unwrap();
+        assert!((reached - 0.5).abs() < 0.1, "{}", reached);
+
+        gain.ramp_to(1.0, Duration::from_millis(64));
+        let out = take(&mut source, 200);
+        for pair in out.windows(2) {
+            assert!((pair[1] - pair[0]).abs() < 0.02);
+        }
+        assert_eq!(*out.last().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn a_zero_duration_setting_lands_at_the_next_check() {
+        let gain = Gain::new(0.25);
+        let mut source = Ramped::new(Ones, gain.clone());
+        assert_eq!(take(&mut source, 1), [0.25]);
+        gain.ramp_to(0.75, Duration::ZERO);
+        let out = take(&mut source, CHECK_EVERY as usize + 1);
+        assert_eq!(*out.last().unwrap(), 0.75);
+    }
+}
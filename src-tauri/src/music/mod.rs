@@ -0,0 +1,171 @@
+mod error;
+mod gain;
+mod player;
+
+pub use error::MusicError;
+
+use crate::assets::{read_asset, AssetKind};
+use gain::Gain;
+use player::{Command, Player, Track, CLICK_FREE_RAMP};
+use rodio::Source;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Manager, Runtime, State};
+
+const MAX_FADE_MS: u32 = 60_000;
+
+/// Background music through rodio rather than WebAudio, so long tracks
+/// stream from a decoder instead of sitting decoded in JS memory. The
+/// player, and the audio device with it, starts on first use: opening the
+/// device at startup fails on machines without one, CI included.
+pub struct MusicState {
+    volume: Arc<Gain>,
+    player: Mutex<Option<Player>>,
+}
+
+impl Default for MusicState {
+    fn default() -> Self {
+        Self {
+            volume: Gain::new(1.0),
+            player: Mutex::new(None),
+        }
+    }
+}
+
+impl MusicState {
+    /// Hands `command` to the player. With `start` the player is started
+    /// if it isn't running; without, there's nothing to do when it isn't.
+    fn send(
+        &self,
+        app: &tauri::AppHandle,
+        command: Command,
+        start: bool,
+    ) -> Result<(), MusicError> {
+        let mut player = self.player.lock().unwrap();
+        if player.as_ref().is_some_and(|p| p.is_finished()) {
+            player.take();
+        }
+        let player = match player.as_mut() {
+            Some(player) => player,
+            None if start => player.insert(Player::spawn(app.clone(), self.volume.clone())?),
+            None => return Ok(()),
+        };
+        player.send(command)
+    }
+}
+
+/// Sends `command` from a blocking thread, the player possibly having to
+/// open the device first.
+async fn dispatch(app: tauri::AppHandle, command: Command, start: bool) -> Result<(), MusicError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<MusicState>().send(&app, command, start)
+    })
+    .await
+    .map_err(|e| MusicError::Task(e.to_string()))?
+}
+
+/// Reads `filename` the way `load_audio_asset` resolves it and starts
+/// decoding, so a missing or broken file fails before anything that's
+/// playing is touched.
+async fn open_track(app: &tauri::AppHandle, filename: &str) -> Result<Track, MusicError> {
+    let (_, bytes) = read_asset(app, AssetKind::Audio, filename).await?;
+    let filename = filename.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let decoder = rodio::Decoder::new(Cursor::new(bytes)).map_err(|e| MusicError::Decode {
+            filename,
+            reason: e.to_string(),
+        })?;
+        Ok(Box::new(decoder.convert_samples::<f32>()) as Track)
+    })
+    .await
+    .map_err(|e| MusicError::Task(e.to_string()))?
+}
+
+fn fade(ms: Option<u32>) -> Result<Duration, MusicError> {
+    match ms.unwrap_or(0) {
+        ms if ms > MAX_FADE_MS => Err(MusicError::InvalidFade {
+            ms,
+            max: MAX_FADE_MS,
+        }),
+        ms => Ok(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Plays an audio asset, fading it in over `fade_in_ms`. Whatever was
+/// playing stops, with just enough of a fade not to click. Emits
+/// `music-track-ended` when the track plays to its end, for the playlist
+/// to queue the next.
+#[tauri::command]
+pub async fn music_play(
+    app: tauri::AppHandle,
+    filename: String,
+    fade_in_ms: Option<u32>,
+) -> Result<(), MusicError> {
+    let fade_in = fade(fade_in_ms)?;
+    let track = open_track(&app, &filename).await?;
+    let command = Command::Play {
+        filename,
+        track,
+        fade_in,
+        fade_out: Duration::ZERO,
+    };
+    dispatch(app, command, true).await
+}
+
+/// Fades the current track out over `fade_out_ms`, without a
+/// `music-track-ended`.
+#[tauri::command]
+pub async fn music_stop(app: tauri::AppHandle, fade_out_ms: Option<u32>) -> Result<(), MusicError> {
+    let fade_out = fade(fade_out_ms)?;
+    dispatch(app, Command::Stop { fade_out }, false).await
+}
+
+/// Fades the current track out and `filename` in, both over
+/// `duration_ms`. If `filename` can't be loaded the current track plays on
+/// and the error is returned.
+#[tauri::command]
+pub async fn music_crossfade_to(
+    app: tauri::AppHandle,
+    filename: String,
+    duration_ms: u32,
+) -> Result<(), MusicError> {
+    let duration = fade(Some(duration_ms))?;
+    let track = open_track(&app, &filename).await?;
+    let command = Command::Play {
+        filename,
+        track,
+        fade_in: duration,
+        fade_out: duration,
+    };
+    dispatch(app, command, true).await
+}
+
+/// Sets the music volume, 0 to 1, with a short ramp rather than a jump.
+/// Kept across tracks, and applies to the first one if nothing's playing.
+#[tauri::command]
+pub fn music_set_volume(music: State<'_, MusicState>, volume: f32) -> Result<(), MusicError> {
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(MusicError::InvalidVolume(volume));
+    }
+    music.volume.ramp_to(volume, CLICK_FREE_RAMP);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn music_pause(app: tauri::AppHandle) -> Result<(), MusicError> {
+    dispatch(app, Command::Pause, false).await
+}
+
+#[tauri::command]
+pub async fn music_resume(app: tauri::AppHandle) -> Result<(), MusicError> {
+    dispatch(app, Command::Resume, false).await
+}
+
+/// Stops the music and closes the device. Called on exit.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let player = app.state::<MusicState>().player.lock().unwrap().take();
+    if let Some(player) = player {
+        player.stop();
+    }
+}
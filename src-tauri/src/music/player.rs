@@ -0,0 +1,251 @@
+//! The player thread. rodio's output stream isn't `Send`, so it's opened
+//! on, and only ever touched from, the thread that runs the tracks; the
+//! commands talk to it over a channel.
+
+use super::gain::{Gain, Ramped};
+use super::MusicError;
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use serde::Serialize;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const TRACK_ENDED_EVENT: &str = "music-track-ended";
+
+/// Short enough to feel instant, long enough not to click.
+pub const CLICK_FREE_RAMP: Duration = Duration::from_millis(30);
+/// How often the thread looks for finished fades and tracks.
+const TICK: Duration = Duration::from_millis(50);
+
+pub type Track = Box<dyn Source<Item = f32> + Send>;
+type Reply = mpsc::Sender<Result<(), MusicError>>;
+
+/// The `music-track-ended` payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackEnded {
+    pub filename: String,
+}
+
+pub enum Command {
+    /// Starts `track`, fading it in over `fade_in` while whatever was
+    /// playing fades out over `fade_out`.
+    Play {
+        filename: String,
+        track: Track,
+        fade_in: Duration,
+        fade_out: Duration,
+    },
+    Stop {
+        fade_out: Duration,
+    },
+    Pause,
+    Resume,
+}
+
+pub struct Player {
+    commands: mpsc::Sender<(Command, Reply)>,
+    thread: JoinHandle<()>,
+}
+
+impl Player {
+    /// Opens the default output device and starts the thread. Fails, with
+    /// nothing left running, when there's no device to open.
+    pub fn spawn<R: Runtime>(app: AppHandle<R>, volume: Arc<Gain>) -> Result<Self, MusicError> {
+        let (commands, received) = mpsc::channel::<(Command, Reply)>();
+        let (ready, opened) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("music".into())
+            .spawn(move || {
+                let (stream, handle) = match OutputStream::try_default() {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        let _ = ready.send(Err(MusicError::NoOutput(e.to_string())));
+                        return;
+                    }
+                };
+                let _ = ready.send(Ok(()));
+                let mut tracks = Tracks {
+                    handle,
+                    volume,
+                    pause_gain: Gain::new(1.0),
+                    paused: false,
+                    current: None,
+                    fading: Vec::new(),
+                    pause_at: None,
+                };
+                loop {
+                    match received.recv_timeout(TICK) {
+                        Ok((command, reply)) => {
+                            let _ = reply.send(tracks.apply(command));
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    if let Some(ended) = tracks.tick(Instant::now()) {
+                        let _ = app.emit(TRACK_ENDED_EVENT, TrackEnded { filename: ended });
+                    }
+                }
+                drop(tracks);
+                drop(stream);
+            })
+            .map_err(MusicError::Spawn)?;
+
+        match opened.recv() {
+            Ok(Ok(())) => Ok(Self { commands, thread }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err(MusicError::NoOutput("the music thread exited".to_string()))
+            }
+        }
+    }
+
+    pub fn send(&self, command: Command) -> Result<(), MusicError> {
+        let (reply, replied) = mpsc::channel();
+        self.commands
+            .send((command, reply))
+            .map_err(|_| MusicError::PlayerStopped)?;
+        replied.recv().map_err(|_| MusicError::PlayerStopped)?
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Stops playback and closes the device, which takes at most one tick.
+    pub fn stop(self) {
+        drop(self.commands);
+        let _ = self.thread.join();
+    }
+}
+
+struct Playing {
+    filename: String,
+    sink: Sink,
+    fade: Arc<Gain>,
+}
+
+struct Tracks {
+    handle: OutputStreamHandle,
+    /// Set by `music_set_volume`, shared by every track.
+    volume: Arc<Gain>,
+    /// Ramped to 0 before pausing and back to 1 on resuming, so neither
+    /// clicks.
+    pause_gain: Arc<Gain>,
+    paused: bool,
+    current: Option<Playing>,
+    /// Tracks fading out, with when they'll be silent.
+    fading: Vec<(Playing, Instant)>,
+    /// When the pause ramp ends and the sinks actually pause.
+    pause_at: Option<Instant>,
+}
+
+impl Tracks {
+    fn apply(&mut self, command: Command) -> Result<(), MusicError> {
+        match command {
+            Command::Play {
+                filename,
+                track,
+                fade_in,
+                fade_out,
+            } => {
+                let sink = Sink::try_new(&self.handle).map_err(|e| MusicError::Playback {
+                    filename: filename.clone(),
+                    reason: e.to_string(),
+                })?;
+                if self.paused && self.pause_at.is_none() {
+                    // Fully paused, so already silent: no fade needed.
+                    for sink in self.sinks() {
+                        sink.stop();
+                    }
+                    self.current = None;
+                    self.fading.clear();
+                }
+                self.resume();
+                self.retire(fade_out);
+                let fade = Gain::new(0.0);
+                fade.ramp_to(1.0, fade_in.max(CLICK_FREE_RAMP));
+                let track = Ramped::new(track, fade.clone());
+                let track = Ramped::new(track, self.pause_gain.clone());
+                sink.append(Ramped::new(track, self.volume.clone()));
+                log::info!("Playing {}", filename);
+                self.current = Some(Playing {
+                    filename,
+                    sink,
+                    fade,
+                });
+            }
+            Command::Stop { fade_out } => self.retire(fade_out),
+            Command::Pause if !self.paused => {
+                self.paused = true;
+                self.pause_gain.ramp_to(0.0, CLICK_FREE_RAMP);
+                self.pause_at = Some(Instant::now() + CLICK_FREE_RAMP);
+            }
+            Command::Pause => {}
+            Command::Resume => self.resume(),
+        }
+        Ok(())
+    }
+
+    fn sinks(&self) -> impl Iterator<Item = &Sink> {
+        self.current
+            .iter()
+            .chain(self.fading.iter().map(|(p, _)| p))
+            .map(|p| &p.sink)
+    }
+
+    fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+        self.paused = false;
+        self.pause_at = None;
+        for sink in self.sinks() {
+            sink.play();
+        }
+        self.pause_gain.ramp_to(1.0, CLICK_FREE_RAMP);
+    }
+
+    /// Fades the current track out, to be dropped once it's silent.
+    fn retire(&mut self, fade_out: Duration) {
+        if let Some(playing) = self.current.take() {
+            let fade_out = fade_out.max(CLICK_FREE_RAMP);
+            playing.fade.ramp_to(0.0, fade_out);
+            // A paused track won't get any quieter, so it goes as soon as
+            // the pause has faded it out.
+            let silent_at = match self.paused {
+                true => self.pause_at.unwrap_or_else(Instant::now),
+                false => Instant::now() + fade_out + TICK,
+            };
+            self.fading.push((playing, silent_at));
+        }
+    }
+
+    /// Finishes pauses and fades that are due, returning the current
+    /// track's filename if it has played to its end.
+    fn tick(&mut self, now: Instant) -> Option<String> {
+        if self.pause_at.is_some_and(|at| at <= now) {
+            self.pause_at = None;
+            for sink in self.sinks() {
+                sink.pause();
+            }
+        }
+        self.fading.retain(|(playing, silent_at)| {
+            let done = *silent_at <= now || playing.sink.empty();
+            if done {
+                playing.sink.stop();
+            }
+            !done
+        });
+        match &self.current {
+            Some(playing) if playing.sink.empty() => self.current.take().map(|p| p.filename),
+            _ => None,
+        }
+    }
+}
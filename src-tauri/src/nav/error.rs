@@ -0,0 +1,47 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the nav commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum NavError {
+    #[error("No nav grid has been built")]
+    NoGrid,
+
+    #[error("Invalid nav grid: {0}")]
+    InvalidGrid(String),
+
+    #[error("Failed to load the level: {0}")]
+    Level(String),
+
+    #[error("{0} is off the nav grid")]
+    OffGrid(String),
+
+    #[error("Invalid path request {index}: {reason}")]
+    InvalidRequest { index: usize, reason: String },
+
+    #[error("Too many path requests: {count} (limit {max})")]
+    TooMany { count: usize, max: usize },
+}
+
+impl NavError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NavError::NoGrid => "noGrid",
+            NavError::InvalidGrid(_) => "invalidGrid",
+            NavError::Level(_) => "level",
+            NavError::OffGrid(_) => "offGrid",
+            NavError::InvalidRequest { .. } => "invalidRequest",
+            NavError::TooMany { .. } => "tooMany",
+        }
+    }
+}
+
+impl Serialize for NavError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("NavError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,307 @@
+use super::NavError;
+use crate::levels::{grid_size, LevelFile, NavData, NavHintKind, MAX_NAV_CELLS};
+use crate::simulation::Vec3;
+use serde::Serialize;
+
+pub const BLOCKED: u8 = 0;
+pub const OPEN: u8 = 1;
+/// Cost of cells around an `avoid` nav hint.
+const AVOID_COST: u8 = 8;
+/// Brushes lower than this off the floor are stepped over.
+const STEP_HEIGHT: f32 = 0.5;
+/// Brushes starting higher than this off the floor are walked under.
+const AGENT_HEIGHT: f32 = 1.8;
+pub const DEFAULT_CELL_SIZE: f32 = 0.5;
+
+/// A cell by column and row.
+pub type Cell = [u32; 2];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavGridInfo {
+    pub origin_x: f32,
+    pub origin_z: f32,
+    pub cell_size: f32,
+    pub width: u32,
+    pub height: u32,
+    pub floor_y: f32,
+    pub walkable_cells: usize,
+    pub blocked_cells: usize,
+}
+
+/// Walkability for bots, with doors and other dynamic blockers laid over
+/// the costs it was built with so unblocking restores them.
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    pub origin_x: f32,
+    pub origin_z: f32,
+    pub cell_size: f32,
+    pub width: u32,
+    pub height: u32,
+    pub floor_y: f32,
+    base: Vec<u8>,
+    blocked: Vec<bool>,
+    /// `base` with `blocked` applied, which is what searches read.
+    costs: Vec<u8>,
+    /// Which connected region each cell is in, 0 for blocked cells, so an
+    /// unreachable goal is known before searching.
+    regions: Vec<u32>,
+}
+
+impl NavGrid {
+    pub fn from_data(data: NavData) -> Result<Self, NavError> {
+        if let Some(problem) = data.problem() {
+            return Err(NavError::InvalidGrid(problem));
+        }
+        let len = data.costs.len();
+        let mut grid = Self {
+            origin_x: data.origin_x,
+            origin_z: data.origin_z,
+            cell_size: data.cell_size,
+            width: data.width,
+            height: data.height,
+            floor_y: data.floor_y,
+            base: data.costs.clone(),
+            blocked: vec![false; len],
+            costs: data.costs,
+            regions: Vec::new(),
+        };
+        grid.label_regions();
+        Ok(grid)
+    }
+
+    /// Works walkability out from the level's brushes: a collidable brush
+    /// blocks the cells under it unless bots can step onto it or walk
+    /// beneath it. Cells near `avoid` nav hints cost more.
+    pub fn from_level(level: &LevelFile, cell_size: f32, floor_y: f32) -> Result<Self, NavError> {
+        let b = level.bounds;
+        if !b.is_valid() {
+            return Err(NavError::InvalidGrid("the level bounds are invalid".into()));
+        }
+        if !(cell_size.is_finite() && cell_size > 0.0 && floor_y.is_finite()) {
+            return Err(NavError::InvalidGrid(
+                "cellSize must be positive and floorY finite".into(),
+            ));
+        }
+        let (width, height) = grid_size(
+            b.max.x - b.min.x,
+            b.max.z - b.min.z,
+            cell_size,
+            MAX_NAV_CELLS,
+        )
+        .map_err(NavError::InvalidGrid)?;
+        let mut grid = Self::from_data(NavData {
+            origin_x: b.min.x,
+            origin_z: b.min.z,
+            cell_size,
+            width,
+            height,
+            floor_y,
+            costs: vec![OPEN; width as usize * height as usize],
+        })?;
+
+        for hint in &level.nav_hints {
+            if hint.kind != NavHintKind::Avoid {
+                continue;
+            }
+            let radius = hint.radius.unwrap_or(cell_size).max(0.0);
+            let (x0, z0) = (hint.position.x - radius, hint.position.z - radius);
+            let (x1, z1) = (hint.position.x + radius, hint.position.z + radius);
+            for i in grid.cells_overlapping(x0, z0, x1, z1) {
+                let (cx, cz) = grid.center(i);
+                if (cx - hint.position.x).hypot(cz - hint.position.z) <= radius {
+                    grid.base[i] = grid.base[i].max(AVOID_COST);
+                }
+            }
+        }
+        for brush in &level.brushes {
+            let bb = brush.bounds;
+            let obstructs = bb.max.y > floor_y + STEP_HEIGHT && bb.min.y < floor_y + AGENT_HEIGHT;
+            if !brush.collidable || !bb.is_valid() || !obstructs {
+                continue;
+            }
+            for i in grid.cells_overlapping(bb.min.x, bb.min.z, bb.max.x, bb.max.z) {
+                grid.base[i] = BLOCKED;
+            }
+        }
+        grid.costs.copy_from_slice(&grid.base);
+        grid.label_regions();
+        Ok(grid)
+    }
+
+    pub fn info(&self) -> NavGridInfo {
+        let walkable = self.costs.iter().filter(|c| **c != BLOCKED).count();
+        NavGridInfo {
+            origin_x: self.origin_x,
+            origin_z: self.origin_z,
+            cell_size: self.cell_size,
+            width: self.width,
+            height: self.height,
+            floor_y: self.floor_y,
+            walkable_cells: walkable,
+            blocked_cells: self.costs.len() - walkable,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn costs(&self) -> &[u8] {
+        &self.costs
+    }
+
+    pub fn index(&self, x: u32, z: u32) -> usize {
+        z as usize * self.width as usize + x as usize
+    }
+
+    pub fn coords(&self, i: usize) -> (u32, u32) {
+        (
+            (i % self.width as usize) as u32,
+            (i / self.width as usize) as u32,
+        )
+    }
+
+    pub fn cost_at(&self, x: i64, z: i64) -> u8 {
+        if x < 0 || z < 0 || x >= self.width as i64 || z >= self.height as i64 {
+            return BLOCKED;
+        }
+        self.costs[self.index(x as u32, z as u32)]
+    }
+
+    /// The cell holding world point `p`, if it's on the grid.
+    pub fn cell_of(&self, p: Vec3) -> Option<usize> {
+        let x = ((p.x - self.origin_x) / self.cell_size).floor();
+        let z = ((p.z - self.origin_z) / self.cell_size).floor();
+        let inside = x >= 0.0 && z >= 0.0 && x < self.width as f32 && z < self.height as f32;
+        inside.then(|| self.index(x as u32, z as u32))
+    }
+
+    pub fn center(&self, i: usize) -> (f32, f32) {
+        let (x, z) = self.coords(i);
+        (
+            self.origin_x + (x as f32 + 0.5) * self.cell_size,
+            self.origin_z + (z as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    /// Cells whose area overlaps the world rectangle, excluding cells it
+    /// only touches along an edge.
+    fn cells_overlapping(&self, x0: f32, z0: f32, x1: f32, z1: f32) -> Vec<usize> {
+        let span = |lo: f32, hi: f32, origin: f32, n: u32| {
+            let first = ((lo - origin) / self.cell_size).floor().max(0.0);
+            let last = ((hi - origin) / self.cell_size).ceil().min(n as f32);
+            (first as u32, last.max(first) as u32)
+        };
+        let (xa, xb) = span(x0, x1, self.origin_x, self.width);
+        let (za, zb) = span(z0, z1, self.origin_z, self.height);
+        (za..zb)
+            .flat_map(|z| (xa..xb).map(move |x| (x, z)))
+            .map(|(x, z)| self.index(x, z))
+            .collect()
+    }
+
+    /// Blocks or unblocks `cells`, returning how many changed. Fails
+    /// without changing anything when any cell is off the grid.
+    pub fn set_blocked(&mut self, cells: &[Cell], blocked: bool) -> Result<usize, NavError> {
+        if let Some(bad) = cells
+            .iter()
+            .find(|[x, z]| *x >= self.width || *z >= self.height)
+        {
+            return Err(NavError::OffGrid(format!("cell {:?}", bad)));
+        }
+        let mut changed = 0;
+        for &[x, z] in cells {
+            let i = self.index(x, z);
+            if self.blocked[i] != blocked {
+                self.blocked[i] = blocked;
+                self.costs[i] = if blocked { BLOCKED } else { self.base[i] };
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.label_regions();
+        }
+        Ok(changed)
+    }
+
+    /// Flood-fills the walkable cells. Four-way connectivity is enough:
+    /// a diagonal step needs both cells beside it open anyway.
+    fn label_regions(&mut self) {
+        self.regions = vec![0; self.costs.len()];
+        let mut next = 0;
+        let mut stack = Vec::new();
+        for seed in 0..self.costs.len() {
+            if self.costs[seed] == BLOCKED || self.regions[seed] != 0 {
+                continue;
+            }
+            next += 1;
+            self.regions[seed] = next;
+            stack.push(seed);
+            while let Some(i) = stack.pop() {
+                let (x, z) = self.coords(i);
+                let (x, z) = (x as i64, z as i64);
+                for (nx, nz) in [(x + 1, z), (x - 1, z), (x, z + 1), (x, z - 1)] {
+                    if self.cost_at(nx, nz) == BLOCKED {
+                        continue;
+                    }
+                    let n = self.index(nx as u32, nz as u32);
+                    if self.regions[n] == 0 {
+                        self.regions[n] = next;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn region(&self, i: usize) -> u32 {
+        self.regions[i]
+    }
+
+    /// The cell in `from`'s region nearest `target` in a straight line,
+    /// which is where the best partial path to an unreachable goal ends.
+    pub fn nearest_in_region(&self, from: usize, target: usize) -> usize {
+        let region = self.regions[from];
+        let (tx, tz) = self.coords(target);
+        let (tx, tz) = (tx as i64, tz as i64);
+        let mut best = (i64::MAX, from);
+        for (i, r) in self.regions.iter().enumerate() {
+            if *r != region {
+                continue;
+            }
+            let (x, z) = self.coords(i);
+            let (dx, dz) = (x as i64 - tx, z as i64 - tz);
+            let d = dx * dx + dz * dz;
+            if d < best.0 {
+                best = (d, i);
+            }
+        }
+        best.1
+    }
+}
+
+/// A grid with one-unit cells drawn as rows of text, for tests: `#` is
+/// blocked, a digit is that cost and anything else is open.
+#[cfg(test)]
+pub(crate) fn from_rows(rows: &[&str]) -> NavGrid {
+    let costs = rows
+        .iter()
+        .flat_map(|row| row.bytes())
+        .map(|c| match c {
+            b'#' => BLOCKED,
+            b'1'..=b'9' => c - b'0',
+            _ => OPEN,
+        })
+        .collect();
+    NavGrid::from_data(NavData {
+        origin_x: 0.0,
+        origin_z: 0.0,
+        cell_size: 1.0,
+        width: rows[0].len() as u32,
+        height: rows.len() as u32,
+        floor_y: 0.0,
+        costs,
+    })
+    .unwrap()
+}
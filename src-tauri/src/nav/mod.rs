@@ -0,0 +1,504 @@
+mod error;
+mod grid;
+mod search;
+mod smooth;
+
+pub use error::NavError;
+pub use grid::NavGridInfo;
+
+use crate::levels::NavData;
+use crate::simulation::Vec3;
+use grid::{Cell, NavGrid, BLOCKED};
+use search::Scratch;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::State;
+
+pub const MAX_PATHS_PER_BATCH: usize = 256;
+/// Below this many requests per thread a batch isn't worth splitting.
+const PATHS_PER_WORKER: usize = 4;
+/// How far a start or goal in a blocked cell is moved to find open ground.
+const SNAP_RADIUS: i64 = 2;
+/// Bots don't need the strictly cheapest path, and smoothing straightens
+/// most of the difference out, so trade a little length for speed.
+const DEFAULT_HEURISTIC_WEIGHT: f32 = 1.2;
+const MAX_HEURISTIC_WEIGHT: f32 = 4.0;
+
+static THREADS: OnceLock<usize> = OnceLock::new();
+
+/// The bot walkability grid. Searches clone the `Arc` and let go of the
+/// lock, so blocking cells mid-search copies the grid instead of waiting.
+#[derive(Default)]
+pub struct NavState {
+    grid: Mutex<Option<Arc<NavGrid>>>,
+    /// Search buffers, returned after each search so the next one skips
+    /// allocating a grid's worth of them.
+    scratch: Mutex<Vec<Scratch>>,
+}
+
+impl NavState {
+    fn grid(&self) -> Result<Arc<NavGrid>, NavError> {
+        self.grid.lock().unwrap().clone().ok_or(NavError::NoGrid)
+    }
+
+    fn take_scratch(&self) -> Scratch {
+        self.scratch.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    fn return_scratch(&self, scratch: Scratch) {
+        self.scratch.lock().unwrap().push(scratch);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NavSource {
+    /// The level's precomputed grid if its file has one, otherwise a grid
+    /// worked out from its brushes with `cellSize` (0.5 by default) cells
+    /// and the floor at `floorY` (0 by default).
+    Level {
+        id: String,
+        #[serde(default)]
+        cell_size: Option<f32>,
+        #[serde(default)]
+        floor_y: Option<f32>,
+    },
+    Cells(NavData),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PathOptions {
+    /// Drop waypoints a bot can walk straight past.
+    pub smooth: bool,
+    /// Settle for the best partial path after this many cells; by default
+    /// the whole grid may be searched.
+    pub max_expansions: Option<u32>,
+    /// 1 finds the cheapest path. Higher searches far fewer cells for a
+    /// path at most this many times the cheapest's cost.
+    pub heuristic_weight: f32,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            smooth: true,
+            max_expansions: None,
+            heuristic_weight: DEFAULT_HEURISTIC_WEIGHT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathRequest {
+    pub start: Vec3,
+    pub goal: Vec3,
+    #[serde(default)]
+    pub options: PathOptions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathResult {
+    /// From the start to the goal, or to the reachable point nearest it,
+    /// at the grid's floor height.
+    pub waypoints: Vec<Vec3>,
+    /// Length of the grid path, before smoothing, weighted by cell costs.
+    pub cost: f32,
+    pub reached: bool,
+    /// Cells the search expanded, for tuning `maxExpansions`.
+    pub expanded: u32,
+}
+
+/// A request with its endpoints resolved to cells.
+struct Job {
+    start: Vec3,
+    goal: Vec3,
+    start_cell: usize,
+    goal_cell: usize,
+    /// The goal was off the grid, blocked or unreachable, so the path
+    /// can't end exactly on it.
+    goal_moved: bool,
+    start_moved: bool,
+    stuck: bool,
+    options: PathOptions,
+}
+
+/// The nearest walkable cell to `(x, z)` within `SNAP_RADIUS`, or `None`.
+fn snap(grid: &NavGrid, x: i64, z: i64) -> Option<usize> {
+    let mut best: Option<(i64, usize)> = None;
+    for dz in -SNAP_RADIUS..=SNAP_RADIUS {
+        for dx in -SNAP_RADIUS..=SNAP_RADIUS {
+            let (cx, cz) = (x + dx, z + dz);
+            if grid.cost_at(cx, cz) == BLOCKED {
+                continue;
+            }
+            let d = dx * dx + dz * dz;
+            if best.is_none_or(|(bd, _)| d < bd) {
+                best = Some((d, grid.index(cx as u32, cz as u32)));
+            }
+        }
+    }
+    best.map(|(_, cell)| cell)
+}
+
+fn prepare(grid: &NavGrid, index: usize, request: PathRequest) -> Result<Job, NavError> {
+    let invalid = |reason: &str| NavError::InvalidRequest {
+        index,
+        reason: reason.to_string(),
+    };
+    if !request.start.is_finite() || !request.goal.is_finite() {
+        return Err(invalid("positions must be finite"));
+    }
+    let weight = request.options.heuristic_weight;
+    if !(1.0..=MAX_HEURISTIC_WEIGHT).contains(&weight) {
+        return Err(invalid(&format!(
+            "heuristicWeight must be between 1 and {}",
+            MAX_HEURISTIC_WEIGHT
+        )));
+    }
+    let start = grid
+        .cell_of(request.start)
+        .ok_or_else(|| invalid("the start is off the nav grid"))?;
+    let (sx, sz) = grid.coords(start);
+    let snapped_start = snap(grid, sx as i64, sz as i64);
+
+    // An off-grid goal is searched toward the nearest edge cell.
+    let gx = ((request.goal.x - grid.origin_x) / grid.cell_size).floor() as i64;
+    let gz = ((request.goal.z - grid.origin_z) / grid.cell_size).floor() as i64;
+    let (cx, cz) = (
+        gx.clamp(0, grid.width as i64 - 1),
+        gz.clamp(0, grid.height as i64 - 1),
+    );
+    let goal = grid.index(cx as u32, cz as u32);
+    let start_cell = snapped_start.unwrap_or(start);
+    // A goal in another region is swapped for the nearest cell that can be
+    // reached, rather than searching every reachable cell to find out.
+    let goal_cell = match snap(grid, cx, cz) {
+        Some(cell) if grid.region(cell) == grid.region(start_cell) => cell,
+        _ if snapped_start.is_some() => grid.nearest_in_region(start_cell, goal),
+        _ => goal,
+    };
+
+    Ok(Job {
+        start: request.start,
+        goal: request.goal,
+        start_cell,
+        goal_cell,
+        start_moved: snapped_start != Some(start),
+        goal_moved: (cx, cz) != (gx, gz) || goal_cell != goal,
+        stuck: snapped_start.is_none(),
+        options: request.options,
+    })
+}
+
+fn run(grid: &NavGrid, job: &Job, scratch: &mut Scratch) -> PathResult {
+    let at = |(x, z): (f32, f32)| Vec3::new(x, grid.floor_y, z);
+    let start_point = Vec3::new(job.start.x, grid.floor_y, job.start.z);
+    if job.stuck {
+        return PathResult {
+            waypoints: vec![start_point],
+            cost: 0.0,
+            reached: false,
+            expanded: 0,
+        };
+    }
+
+    let max_expansions = job
+        .options
+        .max_expansions
+        .map_or(grid.len(), |n| n as usize);
+    let found = search::search(
+        grid,
+        job.start_cell,
+        job.goal_cell,
+        max_expansions,
+        job.options.heuristic_weight,
+        scratch,
+    );
+    let cells = if job.options.smooth {
+        smooth::smooth(grid, &found.cells)
+    } else {
+        found.cells
+    };
+
+    let mut waypoints: Vec<Vec3> = cells.iter().map(|&c| at(grid.center(c))).collect();
+    if !job.start_moved {
+        waypoints[0] = start_point;
+    }
+    let reached = found.reached && !job.goal_moved;
+    if reached {
+        let goal = Vec3::new(job.goal.x, grid.floor_y, job.goal.z);
+        if waypoints.len() == 1 {
+            waypoints.push(goal);
+        } else {
+            *waypoints.last_mut().unwrap() = goal;
+        }
+    }
+    PathResult {
+        waypoints,
+        cost: found.cost * grid.cell_size,
+        reached,
+        expanded: found.expanded as u32,
+    }
+}
+
+/// Runs `jobs` in order, split across threads when there are enough of
+/// them, each thread with its own search buffers.
+fn run_all(nav: &NavState, grid: &NavGrid, jobs: &[Job]) -> Vec<PathResult> {
+    let threads =
+        *THREADS.get_or_init(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let workers = jobs.len().div_ceil(PATHS_PER_WORKER).clamp(1, threads);
+    if workers == 1 {
+        let mut scratch = nav.take_scratch();
+        let results = jobs
+            .iter()
+            .map(|job| run(grid, job, &mut scratch))
+            .collect();
+        nav.return_scratch(scratch);
+        return results;
+    }
+    let per_worker = jobs.len().div_ceil(workers);
+    std::thread::scope(|s| {
+        let handles: Vec<_> = jobs
+            .chunks(per_worker)
+            .map(|chunk| {
+                let mut scratch = nav.take_scratch();
+                s.spawn(move || {
+                    let results: Vec<PathResult> = chunk
+                        .iter()
+                        .map(|job| run(grid, job, &mut scratch))
+                        .collect();
+                    (results, scratch)
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(jobs.len());
+        for handle in handles {
+            let (chunk, scratch) = handle.join().expect("nav worker panicked");
+            results.extend(chunk);
+            nav.return_scratch(scratch);
+        }
+        results
+    })
+}
+
+/// Replaces the nav grid, from a level or from cells sent as-is.
+#[tauri::command]
+pub async fn build_nav_grid(
+    app: tauri::AppHandle,
+    nav: State<'_, NavState>,
+    source: NavSource,
+) -> Result<NavGridInfo, NavError> {
+    let built = match source {
+        NavSource::Cells(data) => NavGrid::from_data(data)?,
+        NavSource::Level {
+            id,
+            cell_size,
+            floor_y,
+        } => {
+            let level =
+                crate::levels::load(&app, &id).map_err(|e| NavError::Level(e.to_string()))?;
+            match level.nav {
+                Some(ref data) => NavGrid::from_data(data.clone())?,
+                None => NavGrid::from_level(
+                    &level,
+                    cell_size.unwrap_or(grid::DEFAULT_CELL_SIZE),
+                    floor_y.unwrap_or(0.0),
+                )?,
+            }
+        }
+    };
+    let info = built.info();
+    *nav.grid.lock().unwrap() = Some(Arc::new(built));
+    log::info!(
+        "Built a {} x {} nav grid, {} cells walkable",
+        info.width,
+        info.height,
+        info.walkable_cells
+    );
+    Ok(info)
+}
+
+/// A* from `start` to `goal`. An unreachable goal isn't an error: the path
+/// to the closest reachable point comes back with `reached: false`.
+#[tauri::command]
+pub async fn find_path(
+    nav: State<'_, NavState>,
+    start: Vec3,
+    goal: Vec3,
+    options: Option<PathOptions>,
+) -> Result<PathResult, NavError> {
+    let grid = nav.grid()?;
+    let request = PathRequest {
+        start,
+        goal,
+        options: options.unwrap_or_default(),
+    };
+    let job = prepare(&grid, 0, request)?;
+    Ok(run_all(&nav, &grid, &[job]).remove(0))
+}
+
+/// Like [`find_path`] for many bots at once, spread over the available
+/// cores. Results are in request order.
+#[tauri::command]
+pub async fn find_paths_batch(
+    nav: State<'_, NavState>,
+    requests: Vec<PathRequest>,
+) -> Result<Vec<PathResult>, NavError> {
+    if requests.len() > MAX_PATHS_PER_BATCH {
+        return Err(NavError::TooMany {
+            count: requests.len(),
+            max: MAX_PATHS_PER_BATCH,
+        });
+    }
+    let grid = nav.grid()?;
+    let jobs = requests
+        .into_iter()
+        .enumerate()
+        .map(|(i, request)| prepare(&grid, i, request))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(run_all(&nav, &grid, &jobs))
+}
+
+/// Blocks or unblocks cells, given as `[column, row]`, for doors and other
+/// dynamic blockers. Unblocking restores the cell's built cost. Returns
+/// how many cells changed.
+#[tauri::command]
+pub async fn set_cells_blocked(
+    nav: State<'_, NavState>,
+    cells: Vec<Cell>,
+    blocked: bool,
+) -> Result<usize, NavError> {
+    let mut guard = nav.grid.lock().unwrap();
+    let grid = guard.as_mut().ok_or(NavError::NoGrid)?;
+    Arc::make_mut(grid).set_blocked(&cells, blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// 256 x 256 cells of 32-cell rooms, each wall with a doorway, plus
+    /// scattered pillars and a few costly patches.
+    fn arena() -> NavGrid {
+        let rows: Vec<String> = (0..256)
+            .map(|z| {
+                (0..256)
+                    .map(|x| {
+                        let wall_x = x % 32 == 31 && !(12..16).contains(&(z % 32));
+                        let wall_z = z % 32 == 31 && !(20..24).contains(&(x % 32));
+                        if wall_x || wall_z || (x % 7 == 3 && z % 9 == 4) {
+                            '#'
+                        } else if (x / 16 + z / 16) % 5 == 0 && x % 16 < 4 {
+                            '6'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        grid::from_rows(&rows)
+    }
+
+    fn request(start: (f32, f32), goal: (f32, f32)) -> PathRequest {
+        PathRequest {
+            start: Vec3::new(start.0, 0.0, start.1),
+            goal: Vec3::new(goal.0, 0.0, goal.1),
+            options: PathOptions::default(),
+        }
+    }
+
+    fn jobs(grid: &NavGrid) -> Vec<Job> {
+        // Corner to corner, across the middle and room to neighbouring room.
+        (0..16)
+            .map(|i| {
+                let t = i as f32 * 13.0;
+                let req = match i % 4 {
+                    0 => request((1.5 + t, 1.5), (254.5 - t, 254.5)),
+                    1 => request((1.5, 254.5 - t), (254.5, 1.5 + t)),
+                    2 => request((128.5, 1.5 + t), (1.5 + t, 128.5)),
+                    _ => request((10.5 + t, 10.5 + t), (40.5 + t, 70.5)),
+                };
+                prepare(grid, i, req).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sixteen_paths_on_a_256_grid_finish_in_a_couple_of_milliseconds() {
+        let grid = arena();
+        let nav = NavState::default();
+        let jobs = jobs(&grid);
+
+        let results = run_all(&nav, &grid, &jobs);
+        assert!(results.iter().all(|r| r.reached));
+
+        // The target is 2 ms with the batch spread over four cores. Fewer
+        // cores get proportionally longer, and unoptimized builds, which run
+        // about ten times slower, ten times that.
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let mut budget = Duration::from_millis(2) * 4 / threads.min(4) as u32;
+        if cfg!(debug_assertions) {
+            budget *= 10;
+        }
+        let best = (0..10)
+            .map(|_| {
+                let start = Instant::now();
+                std::hint::black_box(run_all(&nav, &grid, &jobs));
+                start.elapsed()
+            })
+            .min()
+            .unwrap();
+        assert!(
+            best < budget,
+            "16 paths took {:?} ({:?} allowed)",
+            best,
+            budget
+        );
+    }
+
+    #[test]
+    fn closing_the_only_door_returns_a_partial_path() {
+        let mut grid = grid::from_rows(&["....#....", "....#....", ".........", "....#...."]);
+        let nav = NavState::default();
+        let open = run_all(
+            &nav,
+            &grid,
+            &[prepare(&grid, 0, request((0.5, 0.5), (8.5, 0.5))).unwrap()],
+        );
+        assert!(open[0].reached);
+        assert_eq!(open[0].waypoints.last(), Some(&Vec3::new(8.5, 0.0, 0.5)));
+
+        assert_eq!(grid.set_blocked(&[[4, 2]], true).unwrap(), 1);
+        let job = prepare(&grid, 0, request((0.5, 0.5), (8.5, 0.5))).unwrap();
+        let closed = run_all(&nav, &grid, &[job]);
+        assert!(!closed[0].reached);
+        // As close as the wall allows, at the centre of that cell.
+        assert_eq!(closed[0].waypoints.last(), Some(&Vec3::new(3.5, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn tiny_cells_on_a_huge_level_are_refused_not_allocated() {
+        let level: crate::levels::LevelFile = serde_json::from_value(serde_json::json!({
+            "meta": { "name": "huge" },
+            "bounds": {
+                "min": { "x": -100000.0, "y": 0.0, "z": -100000.0 },
+                "max": { "x": 100000.0, "y": 10.0, "z": 100000.0 }
+            }
+        }))
+        .unwrap();
+        for cell_size in [1e-5, 1e-30, f32::MIN_POSITIVE] {
+            match NavGrid::from_level(&level, cell_size, 0.0) {
+                Err(NavError::InvalidGrid(reason)) => assert!(reason.contains("more than")),
+                other => panic!("expected invalidGrid, got {:?}", other.map(|g| g.width)),
+            }
+        }
+        let grid = NavGrid::from_level(&level, 400.0, 0.0).unwrap();
+        assert_eq!((grid.width, grid.height), (500, 500));
+    }
+}
@@ -0,0 +1,399 @@
+//! A* over a [`NavGrid`], eight-connected with no corner cutting, in cell
+//! units. The octile heuristic is admissible because no cell costs less
+//! than 1; weighting it above 1 trades that for far fewer expansions, with
+//! paths at most that factor longer than the best.
+
+use super::grid::{NavGrid, BLOCKED};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+const SQRT_2: f32 = std::f32::consts::SQRT_2;
+const ORTHOGONAL: [(i64, i64); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+/// Each diagonal with the two `ORTHOGONAL` steps beside it.
+const DIAGONAL: [(i64, i64, usize, usize); 4] =
+    [(1, 1, 0, 2), (1, -1, 0, 3), (-1, 1, 1, 2), (-1, -1, 1, 3)];
+
+/// An open node, keyed on f then h so ties go toward the goal. Both are
+/// non-negative, so their bits order like the floats.
+#[derive(PartialEq, Eq)]
+struct Open {
+    key: u64,
+    node: u32,
+}
+
+impl Open {
+    fn new(f: f32, h: f32, node: usize) -> Self {
+        Self {
+            key: (f.to_bits() as u64) << 32 | h.to_bits() as u64,
+            node: node as u32,
+        }
+    }
+
+    fn h(&self) -> f32 {
+        f32::from_bits(self.key as u32)
+    }
+}
+
+impl Ord for Open {
+    // Reversed, for a min-heap.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.cmp(&self.key)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// One cell's bookkeeping, together so a visit touches one cache line.
+#[derive(Clone, Copy, Default)]
+struct Node {
+    g: f32,
+    parent: u32,
+    seen: u32,
+    closed: u32,
+}
+
+/// Per-search bookkeeping, kept between searches so each one only pays
+/// for the cells it touches. A cell's entries count for the current
+/// search only when its stamp matches.
+#[derive(Default)]
+pub struct Scratch {
+    nodes: Vec<Node>,
+    stamp: u32,
+    heap: BinaryHeap<Open>,
+}
+
+impl Scratch {
+    fn reset(&mut self, len: usize) {
+        if self.nodes.len() != len || self.stamp == u32::MAX {
+            self.nodes = vec![Node::default(); len];
+            self.stamp = 0;
+        }
+        self.stamp += 1;
+        self.heap.clear();
+    }
+}
+
+pub struct Search {
+    /// From the start cell to the goal, or to the closest cell to it that
+    /// could be reached.
+    pub cells: Vec<usize>,
+    /// In cell units.
+    pub cost: f32,
+    pub reached: bool,
+    pub expanded: usize,
+}
+
+fn octile((ax, az): (i64, i64), (bx, bz): (i64, i64)) -> f32 {
+    let dx = ax.abs_diff(bx) as f32;
+    let dz = az.abs_diff(bz) as f32;
+    dx.max(dz) + (SQRT_2 - 1.0) * dx.min(dz)
+}
+
+fn coords(grid: &NavGrid, i: usize) -> (i64, i64) {
+    let (x, z) = grid.coords(i);
+    (x as i64, z as i64)
+}
+
+/// Searches from `start` to `goal`, both walkable cells, expanding at most
+/// `max_expansions` cells before settling for the best partial path.
+pub fn search(
+    grid: &NavGrid,
+    start: usize,
+    goal: usize,
+    max_expansions: usize,
+    weight: f32,
+    scratch: &mut Scratch,
+) -> Search {
+    scratch.reset(grid.len());
+    let stamp = scratch.stamp;
+    let nodes = &mut scratch.nodes;
+    let heap = &mut scratch.heap;
+    let costs = grid.costs();
+    let (width, height) = (grid.width as i64, grid.height as i64);
+
+    let target = coords(grid, goal);
+    let h0 = octile(coords(grid, start), target);
+    nodes[start] = Node {
+        g: 0.0,
+        parent: start as u32,
+        seen: stamp,
+        closed: 0,
+    };
+    heap.push(Open::new(h0 * weight, h0, start));
+    let (mut best, mut best_h) = (start, h0);
+    let mut expanded = 0;
+
+    while let Some(open) = heap.pop() {
+        let node = open.node as usize;
+        if nodes[node].closed == stamp {
+            continue;
+        }
+        nodes[node].closed = stamp;
+        let h = open.h();
+        if h < best_h || (h == best_h && nodes[node].g < nodes[best].g) {
+            best = node;
+            best_h = h;
+        }
+        if node == goal || expanded >= max_expansions {
+            break;
+        }
+        expanded += 1;
+
+        let (x, z) = coords(grid, node);
+        let g = nodes[node].g;
+        // Away from the edges every neighbor exists, so skip the checks.
+        let interior = x > 0 && z > 0 && x < width - 1 && z < height - 1;
+        let offset = |dx: i64, dz: i64| (node as i64 + dz * width + dx) as usize;
+        let cost_of = |dx: i64, dz: i64| {
+            if interior {
+                costs[offset(dx, dz)]
+            } else {
+                grid.cost_at(x + dx, z + dz)
+            }
+        };
+        let mut visit = |dx: i64, dz: i64, step: f32, cost: u8| {
+            let next = offset(dx, dz);
+            let n = &mut nodes[next];
+            if n.closed == stamp {
+                return;
+            }
+            let tentative = g + step * cost as f32;
+            if n.seen == stamp && tentative >= n.g {
+                return;
+            }
+            n.seen = stamp;
+            n.g = tentative;
+            n.parent = node as u32;
+            let h = octile((x + dx, z + dz), target);
+            heap.push(Open::new(tentative + h * weight, h, next));
+        };
+
+        let mut open_sides = [false; 4];
+        for (side, (dx, dz)) in ORTHOGONAL.into_iter().enumerate() {
+            let cost = cost_of(dx, dz);
+            if cost != BLOCKED {
+                open_sides[side] = true;
+                visit(dx, dz, 1.0, cost);
+            }
+        }
+        for (dx, dz, a, b) in DIAGONAL {
+            if !(open_sides[a] && open_sides[b]) {
+                continue;
+            }
+            let cost = cost_of(dx, dz);
+            if cost != BLOCKED {
+                visit(dx, dz, SQRT_2, cost);
+            }
+        }
+    }
+
+    let mut cells = vec![best];
+    let mut at = best;
+    while at != start {
+        at = nodes[at].parent as usize;
+        cells.push(at);
+    }
+    cells.reverse();
+    Search {
+        cells,
+        cost: nodes[best].g,
+        reached: best == goal,
+        expanded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav::grid::from_rows;
+    use crate::rng::Xoshiro256StarStar;
+
+    fn find(grid: &NavGrid, start: (u32, u32), goal: (u32, u32), max: usize) -> Search {
+        let (start, goal) = (grid.index(start.0, start.1), grid.index(goal.0, goal.1));
+        search(grid, start, goal, max, 1.0, &mut Scratch::default())
+    }
+
+    /// Checks every step moves to a walkable neighbour and that diagonal
+    /// steps have both cells beside them open, and returns the path's cost.
+    fn walk(grid: &NavGrid, cells: &[usize]) -> f32 {
+        let mut cost = 0.0;
+        for pair in cells.windows(2) {
+            let (ax, az) = coords(grid, pair[0]);
+            let (bx, bz) = coords(grid, pair[1]);
+            let (dx, dz) = (bx - ax, bz - az);
+            assert!(dx.abs() <= 1 && dz.abs() <= 1 && (dx, dz) != (0, 0));
+            let step = grid.cost_at(bx, bz);
+            assert_ne!(step, BLOCKED, "stepped into ({}, {})", bx, bz);
+            if dx != 0 && dz != 0 {
+                assert!(
+                    grid.cost_at(ax + dx, az) != BLOCKED && grid.cost_at(ax, az + dz) != BLOCKED,
+                    "cut the corner from ({}, {}) to ({}, {})",
+                    ax,
+                    az,
+                    bx,
+                    bz
+                );
+                cost += SQRT_2 * step as f32;
+            } else {
+                cost += step as f32;
+            }
+        }
+        cost
+    }
+
+    /// Cheapest cost from `start` to every cell by Dijkstra, stepping the
+    /// same way the search does.
+    fn dijkstra(grid: &NavGrid, start: usize) -> Vec<f32> {
+        let mut best = vec![f32::INFINITY; grid.len()];
+        let mut heap = BinaryHeap::new();
+        best[start] = 0.0;
+        heap.push(Open::new(0.0, 0.0, start));
+        while let Some(open) = heap.pop() {
+            let node = open.node as usize;
+            let g = f32::from_bits((open.key >> 32) as u32);
+            if g > best[node] {
+                continue;
+            }
+            let (x, z) = coords(grid, node);
+            let open_at = |dx: i64, dz: i64| grid.cost_at(x + dx, z + dz) != BLOCKED;
+            for dx in -1..=1 {
+                for dz in -1..=1 {
+                    let diagonal = dx != 0 && dz != 0;
+                    if (dx, dz) == (0, 0) || !open_at(dx, dz) {
+                        continue;
+                    }
+                    if diagonal && !(open_at(dx, 0) && open_at(0, dz)) {
+                        continue;
+                    }
+                    let step = if diagonal { SQRT_2 } else { 1.0 };
+                    let next = grid.index((x + dx) as u32, (z + dz) as u32);
+                    let cost = g + step * grid.cost_at(x + dx, z + dz) as f32;
+                    if cost < best[next] {
+                        best[next] = cost;
+                        heap.push(Open::new(cost, 0.0, next));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn finds_straight_and_diagonal_paths() {
+        let grid = from_rows(&["........", "........", "........", "........"]);
+        let found = find(&grid, (0, 0), (7, 0), usize::MAX);
+        assert!(found.reached);
+        assert_eq!(found.cost, 7.0);
+        assert_eq!(found.cells.len(), 8);
+
+        let found = find(&grid, (0, 0), (3, 3), usize::MAX);
+        assert_eq!(found.cost, 3.0 * SQRT_2);
+        assert_eq!(found.cells.len(), 4);
+
+        let found = find(&grid, (2, 1), (2, 1), usize::MAX);
+        assert!(found.reached);
+        assert_eq!((found.cells, found.cost), (vec![grid.index(2, 1)], 0.0));
+    }
+
+    #[test]
+    fn never_cuts_corners() {
+        // The diagonal from (0, 0) to (1, 1) clips the wall at (1, 0).
+        let grid = from_rows(&[".#.", "...", "..."]);
+        let found = find(&grid, (0, 0), (1, 1), usize::MAX);
+        assert!(found.reached);
+        assert_eq!(found.cost, 2.0);
+        walk(&grid, &found.cells);
+
+        // Two walls meeting at a corner can't be squeezed between.
+        let grid = from_rows(&[".#..", "#...", "...."]);
+        let found = find(&grid, (0, 0), (1, 1), usize::MAX);
+        assert!(!found.reached);
+        assert_eq!(found.cells, [0]);
+
+        let grid = from_rows(&[
+            "..........",
+            "......#...",
+            ".#......#.",
+            "......#...",
+            "......#...",
+            "......#...",
+        ]);
+        let found = find(&grid, (4, 4), (0, 0), usize::MAX);
+        assert!(found.reached);
+        assert_eq!(walk(&grid, &found.cells), found.cost);
+    }
+
+    #[test]
+    fn unreachable_goals_return_the_best_partial_path() {
+        let grid = from_rows(&[".....#....", ".....#....", ".....#....", ".....#...."]);
+        let found = find(&grid, (0, 3), (9, 0), usize::MAX);
+        assert!(!found.reached);
+        // The closest the wall lets it get, by the same distance ordering
+        // the search uses.
+        assert_eq!(*found.cells.last().unwrap(), grid.index(4, 0));
+        assert_eq!(found.cells[0], grid.index(0, 3));
+        assert_eq!(walk(&grid, &found.cells), found.cost);
+        // It checked every cell on its side before giving up.
+        assert_eq!(found.expanded, 20);
+    }
+
+    #[test]
+    fn stops_after_max_expansions() {
+        let row = ".".repeat(64);
+        let grid = from_rows(&[row.as_str(); 64]);
+        let found = find(&grid, (0, 0), (63, 63), 10);
+        assert!(!found.reached);
+        assert_eq!(found.expanded, 10);
+        let (x, z) = grid.coords(*found.cells.last().unwrap());
+        assert!(x > 0 && z > 0, "made no progress");
+        assert_eq!(walk(&grid, &found.cells), found.cost);
+
+        let found = find(&grid, (0, 0), (63, 63), 0);
+        assert_eq!((found.expanded, found.cells.len()), (0, 1));
+        assert!(find(&grid, (0, 0), (63, 63), usize::MAX).reached);
+    }
+
+    #[test]
+    fn unweighted_paths_are_the_cheapest() {
+        let mut rng = Xoshiro256StarStar::from_seed(51);
+        let mut scratch = Scratch::default();
+        for _ in 0..20 {
+            let rows: Vec<String> = (0..24)
+                .map(|_| {
+                    (0..24)
+                        .map(|_| match rng.next_below(10) {
+                            0..=2 => '#',
+                            3 => '5',
+                            _ => '.',
+                        })
+                        .collect()
+                })
+                .collect();
+            let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+            let grid = from_rows(&rows);
+            let open: Vec<usize> = (0..grid.len())
+                .filter(|&i| grid.costs()[i] != BLOCKED)
+                .collect();
+            let start = open[rng.next_below(open.len() as u32) as usize];
+            let best = dijkstra(&grid, start);
+
+            for _ in 0..10 {
+                let goal = open[rng.next_below(open.len() as u32) as usize];
+                let found = search(&grid, start, goal, usize::MAX, 1.0, &mut scratch);
+                assert_eq!(found.reached, best[goal].is_finite());
+                if found.reached {
+                    assert!((found.cost - best[goal]).abs() < 1e-3);
+                    assert!((walk(&grid, &found.cells) - found.cost).abs() < 1e-3);
+
+                    // A weighted search stays within its bound.
+                    let weighted = search(&grid, start, goal, usize::MAX, 2.0, &mut scratch);
+                    assert!(weighted.cost <= best[goal] * 2.0 + 1e-3);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! String pulling: drops grid path cells a bot can walk straight past.
+
+use super::grid::{NavGrid, BLOCKED};
+
+/// Whether the straight line between the centers of cells `a` and `b`
+/// crosses only walkable cells costing at most `max_cost`. A line through
+/// a cell corner needs both cells beside the corner clear, matching the
+/// search's no-corner-cutting rule.
+fn line_of_sight(grid: &NavGrid, a: usize, b: usize, max_cost: u8) -> bool {
+    let clear = |x: i64, z: i64| {
+        let cost = grid.cost_at(x, z);
+        cost != BLOCKED && cost <= max_cost
+    };
+    let (x0, z0) = grid.coords(a);
+    let (x1, z1) = grid.coords(b);
+    let (mut x, mut z) = (x0 as i64, z0 as i64);
+    let (dx, dz) = (x1 as i64 - x, z1 as i64 - z);
+    let (step_x, step_z) = (dx.signum(), dz.signum());
+    let (nx, nz) = (dx.abs(), dz.abs());
+    let (mut ix, mut iz) = (0, 0);
+    while ix < nx || iz < nz {
+        // Which cell boundary the line crosses next, compared exactly:
+        // (ix + 0.5) / nx against (iz + 0.5) / nz.
+        let next = (1 + 2 * ix) * nz - (1 + 2 * iz) * nx;
+        if next == 0 {
+            if !clear(x + step_x, z) || !clear(x, z + step_z) {
+                return false;
+            }
+            x += step_x;
+            z += step_z;
+            ix += 1;
+            iz += 1;
+        } else if next < 0 {
+            x += step_x;
+            ix += 1;
+        } else {
+            z += step_z;
+            iz += 1;
+        }
+        if !clear(x, z) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Where the path changes direction, plus both ends; a straight run
+/// between two of these never needs a waypoint of its own.
+fn turns(grid: &NavGrid, cells: &[usize]) -> Vec<usize> {
+    let step = |a: usize, b: usize| {
+        let (ax, az) = grid.coords(a);
+        let (bx, bz) = grid.coords(b);
+        (bx as i64 - ax as i64, bz as i64 - az as i64)
+    };
+    let mut out = vec![0];
+    for i in 1..cells.len() - 1 {
+        if step(cells[i - 1], cells[i]) != step(cells[i], cells[i + 1]) {
+            out.push(i);
+        }
+    }
+    if cells.len() > 1 {
+        out.push(cells.len() - 1);
+    }
+    out
+}
+
+/// Keeps the first and last cells and only the turns in between that a
+/// straight line can't skip. A shortcut may not cross cells costlier than
+/// the stretch of path it replaces, so paths still skirt expensive ground
+/// the search avoided.
+pub fn smooth(grid: &NavGrid, cells: &[usize]) -> Vec<usize> {
+    if cells.is_empty() {
+        return Vec::new();
+    }
+    let costs = grid.costs();
+    let turns = turns(grid, cells);
+    let worst = |from: usize, to: usize| {
+        cells[from..=to]
+            .iter()
+            .map(|&c| costs[c])
+            .max()
+            .unwrap_or(BLOCKED)
+    };
+    let mut out = vec![cells[0]];
+    let mut anchor = 0;
+    while anchor < turns.len() - 1 {
+        let mut reach = anchor + 1;
+        while reach + 1 < turns.len() {
+            let max_cost = worst(turns[anchor], turns[reach + 1]);
+            if !line_of_sight(
+                grid,
+                cells[turns[anchor]],
+                cells[turns[reach + 1]],
+                max_cost,
+            ) {
+                break;
+            }
+            reach += 1;
+        }
+        out.push(cells[turns[reach]]);
+        anchor = reach;
+    }
+    out
+}
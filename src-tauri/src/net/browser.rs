@@ -0,0 +1,274 @@
+//! Internet server browser: the community server list from the master
+//! server, and latency to each server's game port.
+//!
+//! Pings go out from one socket per address family, at most
+//! [`MAX_IN_FLIGHT`] at a time. Each carries a random nonce that the
+//! server's game socket echoes back, so a reply is matched to its request
+//! even when several servers share an address.
+
+use super::reliable::{self, Datagram};
+use super::NetError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, RandomState};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+pub const PING_RESULT_EVENT: &str = "server-ping-result";
+
+const MAX_IN_FLIGHT: usize = 32;
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+/// Replies are polled for, so ping times are accurate to about this much.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+const MASTER_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_SERVERS: usize = 5000;
+const MAX_LIST_BYTES: usize = 4 * 1024 * 1024;
+const MAX_TEXT_LEN: usize = 64;
+
+/// One server as listed by the master server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListedServer {
+    /// `ip:port` of the game socket.
+    pub address: String,
+    pub name: String,
+    #[serde(default)]
+    pub map: String,
+    #[serde(default)]
+    pub player_count: u32,
+    #[serde(default)]
+    pub max_players: u32,
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+/// The master server may answer with a bare array or `{ "servers": [...] }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MasterResponse {
+    Bare(Vec<ListedServer>),
+    Wrapped { servers: Vec<ListedServer> },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingResult {
+    pub reachable: bool,
+    /// Round trip to the game port; `None` when unreachable.
+    pub ping_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingEvent {
+    pub address: String,
+    #[serde(flatten)]
+    pub result: PingResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowsedServer {
+    #[serde(flatten)]
+    pub server: ListedServer,
+    #[serde(flatten)]
+    pub ping: PingResult,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerList {
+    pub servers: Vec<BrowsedServer>,
+    /// Entries dropped for an unparsable address, bad fields or a repeat.
+    pub skipped: usize,
+}
+
+/// Downloads the list and keeps the entries that can be pinged, in the
+/// master server's order. Returns them with the number skipped.
+pub async fn fetch_list(
+    master_url: &str,
+) -> Result<(Vec<(ListedServer, SocketAddr)>, usize), NetError> {
+    let master = |status: Option<u16>, reason: String| NetError::MasterServer { status, reason };
+    let http = reqwest::Client::builder()
+        .timeout(MASTER_TIMEOUT)
+        .build()
+        .map_err(|e| master(None, e.to_string()))?;
+    let response = http
+        .get(master_url)
+        .send()
+        .await
+        .map_err(|e| master(None, e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(master(Some(status.as_u16()), format!("HTTP {}", status)));
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_LIST_BYTES as u64)
+    {
+        return Err(master(
+            Some(status.as_u16()),
+            "server list is too large".to_string(),
+        ));
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| master(Some(status.as_u16()), e.to_string()))?;
+    if body.len() > MAX_LIST_BYTES {
+        return Err(master(
+            Some(status.as_u16()),
+            "server list is too large".to_string(),
+        ));
+    }
+    let listed = match serde_json::from_slice(&body) {
+        Ok(MasterResponse::Bare(servers) | MasterResponse::Wrapped { servers }) => servers,
+        Err(e) => {
+            return Err(master(
+                Some(status.as_u16()),
+                format!("malformed server list: {}", e),
+            ))
+        }
+    };
+    if listed.len() > MAX_SERVERS {
+        return Err(master(
+            Some(status.as_u16()),
+            format!("{} servers listed (limit {})", listed.len(), MAX_SERVERS),
+        ));
+    }
+
+    let total = listed.len();
+    let mut seen = HashSet::new();
+    let valid: Vec<_> = listed
+        .into_iter()
+        .filter_map(|s| {
+            let addr: SocketAddr = s.address.parse().ok()?;
+            let fields_ok = addr.port() != 0
+                && !s.name.trim().is_empty()
+                && [&s.name, &s.map]
+                    .iter()
+                    .all(|t| t.chars().count() <= MAX_TEXT_LEN);
+            (fields_ok && seen.insert(addr)).then_some((s, addr))
+        })
+        .collect();
+    let skipped = total - valid.len();
+    Ok((valid, skipped))
+}
+
+/// Pings every address, at most [`MAX_IN_FLIGHT`] at once, calling
+/// `on_result` the moment each resolves. Results come back in input order.
+/// Blocking; run it off the async runtime.
+pub fn ping_all(
+    addrs: &[SocketAddr],
+    mut on_result: impl FnMut(usize, PingResult),
+) -> Vec<PingResult> {
+    let unreachable = PingResult {
+        reachable: false,
+        ping_ms: None,
+    };
+    let mut results = vec![unreachable; addrs.len()];
+    let mut sockets = Sockets::default();
+    let nonces = RandomState::new();
+    let mut queue: VecDeque<usize> = (0..addrs.len()).collect();
+    // nonce -> (index, sent at)
+    let mut in_flight: HashMap<u64, (usize, Instant)> = HashMap::new();
+    let mut counter = 0u64;
+    let mut buf = [0u8; 64];
+
+    while !queue.is_empty() || !in_flight.is_empty() {
+        while in_flight.len() < MAX_IN_FLIGHT {
+            let Some(i) = queue.pop_front() else { break };
+            counter += 1;
+            let nonce = nonces.hash_one(counter);
+            match sockets.for_addr(addrs[i]) {
+                Some(socket) if socket.send_to(&reliable::ping(nonce), addrs[i]).is_ok() => {
+                    in_flight.insert(nonce, (i, Instant::now()));
+                }
+                // No route or no socket for the family: unreachable now.
+                _ => on_result(i, unreachable),
+            }
+        }
+
+        let now = Instant::now();
+        in_flight.retain(|_, &mut (i, sent)| {
+            let pending = now.duration_since(sent) < PING_TIMEOUT;
+            if !pending {
+                on_result(i, unreachable);
+            }
+            pending
+        });
+        let Some(deadline) = in_flight
+            .values()
+            .map(|&(_, sent)| sent + PING_TIMEOUT)
+            .min()
+        else {
+            continue;
+        };
+
+        let mut received = false;
+        sockets.drain(&mut buf, |from, bytes| {
+            received = true;
+            let Some(Datagram::Pong(nonce)) = reliable::parse(bytes) else {
+                return;
+            };
+            let Some(&(i, sent)) = in_flight.get(&nonce) else {
+                return;
+            };
+            if !same_host(from, addrs[i]) {
+                return;
+            }
+            in_flight.remove(&nonce);
+            let result = PingResult {
+                reachable: true,
+                ping_ms: Some(sent.elapsed().as_secs_f64() * 1000.0),
+            };
+            results[i] = result;
+            on_result(i, result);
+        });
+        if !received {
+            std::thread::sleep(deadline.saturating_duration_since(now).min(POLL_INTERVAL));
+        }
+    }
+    results
+}
+
+/// Replies may come from another address of a dual-stack server, such as
+/// the IPv4-mapped form, so only the port and the unmapped IP are compared.
+fn same_host(from: SocketAddr, to: SocketAddr) -> bool {
+    let canon = |a: SocketAddr| a.ip().to_canonical();
+    from.port() == to.port() && canon(from) == canon(to)
+}
+
+/// One ping socket per address family, opened on first use.
+#[derive(Default)]
+struct Sockets {
+    v4: Option<Option<UdpSocket>>,
+    v6: Option<Option<UdpSocket>>,
+}
+
+impl Sockets {
+    fn for_addr(&mut self, addr: SocketAddr) -> Option<&UdpSocket> {
+        let (slot, bind): (_, SocketAddr) = match addr {
+            SocketAddr::V4(_) => (&mut self.v4, (Ipv4Addr::UNSPECIFIED, 0).into()),
+            SocketAddr::V6(_) => (&mut self.v6, (Ipv6Addr::UNSPECIFIED, 0).into()),
+        };
+        slot.get_or_insert_with(|| {
+            let socket = UdpSocket::bind(bind).ok()?;
+            socket.set_nonblocking(true).ok()?;
+            Some(socket)
+        })
+        .as_ref()
+    }
+
+    /// Hands every datagram already waiting on the open sockets to
+    /// `handle`, without blocking.
+    fn drain(&self, buf: &mut [u8], mut handle: impl FnMut(SocketAddr, &[u8])) {
+        for socket in [&self.v4, &self.v6].into_iter().flatten().flatten() {
+            // Also stops on errors such as an ICMP reset; the next pass
+            // tries again.
+            while let Ok((len, from)) = socket.recv_from(buf) {
+                handle(from, &buf[..len]);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the networking commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum NetError {
+    #[error("Invalid address {addr:?}: {reason}")]
+    InvalidAddress { addr: String, reason: String },
+
+    #[error("Failed to bind {addr}: {source}")]
+    Bind {
+        addr: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to send to {peer}: {source}")]
+    Send {
+        peer: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No open socket with handle {0}")]
+    UnknownSocket(u32),
+
+    #[error("Too many open sockets (limit {max})")]
+    TooManySockets { max: usize },
+
+    #[error("Payload of {len} bytes exceeds the {max} byte limit")]
+    PayloadTooLarge { len: usize, max: usize },
+
+    #[error("{pending} reliable packets are awaiting acks; wait before sending more")]
+    Backlogged { pending: usize },
+
+    #[error("Invalid server info: {0}")]
+    InvalidServerInfo(String),
+
+    #[error("Invalid latency probe: {0}")]
+    InvalidProbe(String),
+
+    #[error("Master server request failed: {reason}")]
+    MasterServer { status: Option<u16>, reason: String },
+
+    #[error("{0} is not running")]
+    NotRunning(&'static str),
+
+    #[error("Failed to start a network thread: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    #[error("Background network task failed: {0}")]
+    Task(String),
+}
+
+impl NetError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetError::InvalidAddress { .. } => "invalidAddress",
+            NetError::Bind { .. } => "bind",
+            NetError::Send { .. } => "send",
+            NetError::UnknownSocket(_) => "unknownSocket",
+            NetError::TooManySockets { .. } => "tooManySockets",
+            NetError::PayloadTooLarge { .. } => "payloadTooLarge",
+            NetError::Backlogged { .. } => "backlogged",
+            NetError::InvalidServerInfo(_) => "invalidServerInfo",
+            NetError::InvalidProbe(_) => "invalidProbe",
+            NetError::MasterServer { .. } => "masterServer",
+            NetError::NotRunning(_) => "notRunning",
+            NetError::Spawn(_) => "spawn",
+            NetError::Task(_) => "task",
+        }
+    }
+}
+
+impl Serialize for NetError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        // Master server failures also carry the HTTP status, when there was a
+        // response at all.
+        let status = match self {
+            NetError::MasterServer { status, .. } => *status,
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("NetError", 2 + status.is_some() as usize)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(status) = status {
+            state.serialize_field("status", &status)?;
+        }
+        state.end()
+    }
+}
+
+pub(crate) fn parse_addr(addr: &str) -> Result<std::net::SocketAddr, NetError> {
+    addr.parse()
+        .map_err(|e: std::net::AddrParseError| NetError::InvalidAddress {
+            addr: addr.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+pub(crate) fn task(e: tauri::Error) -> NetError {
+    NetError::Task(e.to_string())
+}
@@ -0,0 +1,344 @@
+//! LAN server discovery. A host broadcasts a beacon every couple of seconds;
+//! listeners collect beacons into a server list keyed by the id in the
+//! beacon, and report servers that stop beaconing as lost.
+//!
+//! A beacon is `b"FGLB"` followed by a JSON [`Beacon`].
+
+use super::worker::Worker;
+use super::NetError;
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Runtime};
+
+pub const FOUND_EVENT: &str = "lan-server-found";
+pub const LOST_EVENT: &str = "lan-server-lost";
+
+pub const DEFAULT_PORT: u16 = 47_777;
+const BEACON_MAGIC: &[u8; 4] = b"FGLB";
+const GAME_NAME: &str = "fps-game";
+const BEACON_INTERVAL: Duration = Duration::from_secs(2);
+/// A server is lost after this many intervals without a beacon.
+const MISSED_BEACONS: u32 = 3;
+const MAX_BEACON_LEN: usize = 1024;
+const MAX_TEXT_LEN: usize = 64;
+const MAX_SERVERS: usize = 256;
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the host fills in about its server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub name: String,
+    pub map: String,
+    pub player_count: u32,
+    pub max_players: u32,
+    /// The game port clients should connect to.
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Beacon {
+    server_id: String,
+    game: String,
+    version: String,
+    name: String,
+    map: String,
+    player_count: u32,
+    max_players: u32,
+    port: u16,
+    interval_ms: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanServer {
+    pub server_id: String,
+    /// Where to connect: the address the first beacon came from, with the
+    /// advertised game port.
+    pub address: String,
+    pub name: String,
+    pub map: String,
+    pub player_count: u32,
+    pub max_players: u32,
+    pub version: String,
+    /// The server runs a different game version than this client.
+    pub version_mismatch: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanServerLost {
+    pub server_id: String,
+}
+
+#[derive(Default)]
+pub struct LanState {
+    advertiser: Mutex<Option<Worker>>,
+    discovery: Mutex<Option<Worker>>,
+}
+
+impl LanState {
+    pub fn start_advertise<R: Runtime>(
+        &self,
+        app: &tauri::AppHandle<R>,
+        info: ServerInfo,
+        target: SocketAddr,
+    ) -> Result<(), NetError> {
+        validate(&info)?;
+        let beacon = Beacon {
+            server_id: server_id().to_string(),
+            game: GAME_NAME.to_string(),
+            version: app.package_info().version.to_string(),
+            name: info.name,
+            map: info.map,
+            player_count: info.player_count,
+            max_players: info.max_players,
+            port: info.port,
+            interval_ms: BEACON_INTERVAL.as_millis() as u32,
+        };
+        let mut bytes = BEACON_MAGIC.to_vec();
+        serde_json::to_writer(&mut bytes, &beacon).expect("beacon serialize");
+        if bytes.len() > MAX_BEACON_LEN {
+            return Err(NetError::InvalidServerInfo(
+                "server details are too long to advertise".to_string(),
+            ));
+        }
+
+        let bind = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0);
+        let socket = UdpSocket::bind(bind).map_err(|source| NetError::Bind {
+            addr: bind.to_string(),
+            source,
+        })?;
+        socket
+            .set_broadcast(true)
+            .map_err(|source| NetError::Bind {
+                addr: bind.to_string(),
+                source,
+            })?;
+
+        // Replacing a running advertiser keeps the same server id, so
+        // listeners see an update rather than a new server.
+        let mut advertiser = self.advertiser.lock().unwrap();
+        if let Some(old) = advertiser.take() {
+            old.stop();
+        }
+        *advertiser = Some(Worker::spawn("lan-advertise", move |stopped| loop {
+            if let Err(e) = socket.send_to(&bytes, target) {
+                log::warn!("LAN beacon to {} failed: {}", target, e);
+            }
+            match stopped.recv_timeout(BEACON_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+        })?);
+        Ok(())
+    }
+
+    pub fn stop_advertise(&self) -> bool {
+        let worker = self.advertiser.lock().unwrap().take();
+        worker.map(Worker::stop).is_some()
+    }
+
+    pub fn start_discovery<R: Runtime>(
+        &self,
+        app: tauri::AppHandle<R>,
+        port: u16,
+    ) -> Result<(), NetError> {
+        let socket = bind_shared(port)?;
+        let mut discovery = self.discovery.lock().unwrap();
+        if let Some(old) = discovery.take() {
+            old.stop();
+        }
+        let version = app.package_info().version.to_string();
+        *discovery = Some(Worker::spawn("lan-discovery", move |stopped| {
+            listen(&app, &socket, &version, stopped)
+        })?);
+        Ok(())
+    }
+
+    pub fn stop_discovery(&self) -> bool {
+        let worker = self.discovery.lock().unwrap().take();
+        worker.map(Worker::stop).is_some()
+    }
+
+    pub fn stop_all(&self) {
+        self.stop_advertise();
+        self.stop_discovery();
+    }
+}
+
+/// Random per run, so a host reached over several interfaces (or through
+/// several of its addresses) is still one server.
+fn server_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| format!("{:016x}", RandomState::new().hash_one(std::process::id())))
+}
+
+fn validate(info: &ServerInfo) -> Result<(), NetError> {
+    for (field, value) in [("name", &info.name), ("map", &info.map)] {
+        if value.trim().is_empty() || value.chars().count() > MAX_TEXT_LEN {
+            return Err(NetError::InvalidServerInfo(format!(
+                "{} must be 1 to {} characters",
+                field, MAX_TEXT_LEN
+            )));
+        }
+    }
+    if info.port == 0 {
+        return Err(NetError::InvalidServerInfo(
+            "port must not be 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Binds the discovery port with address reuse, so a host and a client on
+/// the same machine (or two clients) can listen at once.
+fn bind_shared(port: u16) -> Result<UdpSocket, NetError> {
+    let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
+    let bind_err = |source| NetError::Bind {
+        addr: addr.to_string(),
+        source,
+    };
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)).map_err(bind_err)?;
+    socket.set_reuse_address(true).map_err(bind_err)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true).map_err(bind_err)?;
+    socket.bind(&SockAddr::from(addr)).map_err(bind_err)?;
+    socket
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(bind_err)?;
+    Ok(socket.into())
+}
+
+struct Known {
+    server: LanServer,
+    last_seen: Instant,
+    /// How long after `last_seen` the server counts as lost.
+    timeout: Duration,
+}
+
+fn listen<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    socket: &UdpSocket,
+    version: &str,
+    stopped: mpsc::Receiver<()>,
+) {
+    let mut known: HashMap<String, Known> = HashMap::new();
+    // One byte over the limit, so an oversized beacon is seen as such.
+    let mut buf = [0u8; MAX_BEACON_LEN + 1];
+    loop {
+        if !matches!(stopped.try_recv(), Err(TryRecvError::Empty)) {
+            return;
+        }
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                if let Some(beacon) = parse_beacon(&buf[..len]) {
+                    seen(app, &mut known, beacon, from, version);
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) if e.kind() == ErrorKind::ConnectionReset => {}
+            Err(e) => {
+                log::warn!("LAN discovery receive failed: {}", e);
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        let now = Instant::now();
+        known.retain(|id, k| {
+            let alive = now.duration_since(k.last_seen) < k.timeout;
+            if !alive {
+                let _ = app.emit(
+                    LOST_EVENT,
+                    LanServerLost {
+                        server_id: id.clone(),
+                    },
+                );
+            }
+            alive
+        });
+    }
+}
+
+/// Beacons from other games are ignored; those from other versions of this
+/// one are kept and flagged.
+fn parse_beacon(bytes: &[u8]) -> Option<Beacon> {
+    if bytes.len() > MAX_BEACON_LEN {
+        return None;
+    }
+    let json = bytes.strip_prefix(BEACON_MAGIC)?;
+    let beacon: Beacon = serde_json::from_slice(json).ok()?;
+    let valid = beacon.game == GAME_NAME
+        && !beacon.server_id.is_empty()
+        && beacon.server_id.len() <= MAX_TEXT_LEN
+        && beacon.name.chars().count() <= MAX_TEXT_LEN
+        && beacon.map.chars().count() <= MAX_TEXT_LEN
+        && beacon.version.len() <= MAX_TEXT_LEN
+        && beacon.port != 0;
+    valid.then_some(beacon)
+}
+
+/// Records a beacon, emitting `lan-server-found` for a new server and again
+/// whenever a known one's details change.
+fn seen<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    known: &mut HashMap<String, Known>,
+    beacon: Beacon,
+    from: SocketAddr,
+    version: &str,
+) {
+    // Trust the advertised interval within reason, so a host beaconing
+    // slowly isn't dropped and a bogus one can't linger for hours.
+    let interval = Duration::from_millis(beacon.interval_ms.clamp(250, 10_000) as u64);
+    let timeout = interval * MISSED_BEACONS;
+    let now = Instant::now();
+
+    if let Some(k) = known.get_mut(&beacon.server_id) {
+        k.last_seen = now;
+        k.timeout = timeout;
+        let s = &mut k.server;
+        let changed = s.name != beacon.name
+            || s.map != beacon.map
+            || s.player_count != beacon.player_count
+            || s.max_players != beacon.max_players;
+        if changed {
+            s.name = beacon.name;
+            s.map = beacon.map;
+            s.player_count = beacon.player_count;
+            s.max_players = beacon.max_players;
+            let _ = app.emit(FOUND_EVENT, s.clone());
+        }
+        return;
+    }
+    if known.len() >= MAX_SERVERS {
+        return;
+    }
+
+    let server = LanServer {
+        address: SocketAddr::new(from.ip(), beacon.port).to_string(),
+        version_mismatch: beacon.version != version,
+        server_id: beacon.server_id.clone(),
+        name: beacon.name,
+        map: beacon.map,
+        player_count: beacon.player_count,
+        max_players: beacon.max_players,
+        version: beacon.version,
+    };
+    let _ = app.emit(FOUND_EVENT, server.clone());
+    known.insert(
+        beacon.server_id,
+        Known {
+            server,
+            last_seen: now,
+            timeout,
+        },
+    );
+}
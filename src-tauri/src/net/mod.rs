@@ -0,0 +1,325 @@
+mod browser;
+mod error;
+mod lan;
+mod probe;
+mod reliable;
+mod socket;
+mod worker;
+
+pub use error::NetError;
+pub use lan::{LanState, ServerInfo};
+pub use probe::{LatencyMonitors, LatencyStats};
+pub use socket::SocketStats;
+
+use browser::{BrowsedServer, PingEvent, PingResult, ServerList};
+use error::{parse_addr, task};
+use serde::Serialize;
+use socket::Endpoint;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager, Runtime, State};
+
+const MAX_SOCKETS: usize = 64;
+
+/// Open UDP sockets by handle. Each has a receive thread that emits
+/// `udp-packet` events until the socket is closed.
+pub struct NetSockets {
+    next_handle: AtomicU32,
+    sockets: Mutex<HashMap<u32, Arc<Endpoint>>>,
+}
+
+impl Default for NetSockets {
+    fn default() -> Self {
+        Self {
+            next_handle: AtomicU32::new(1),
+            sockets: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl NetSockets {
+    fn get(&self, handle: u32) -> Result<Arc<Endpoint>, NetError> {
+        self.sockets
+            .lock()
+            .unwrap()
+            .get(&handle)
+            .cloned()
+            .ok_or(NetError::UnknownSocket(handle))
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedSocket {
+    pub handle: u32,
+    /// The bound address, with the actual port when `0` was asked for.
+    pub local_addr: String,
+}
+
+/// Binds a UDP socket to `bind_addr` (`ip:port`, port `0` for any) and
+/// starts delivering what it receives as `udp-packet` events. The socket
+/// also answers server browser pings.
+#[tauri::command]
+pub async fn open_udp_socket(
+    app: tauri::AppHandle,
+    net: State<'_, NetSockets>,
+    bind_addr: String,
+) -> Result<OpenedSocket, NetError> {
+    let addr = parse_addr(&bind_addr)?;
+    if net.sockets.lock().unwrap().len() >= MAX_SOCKETS {
+        return Err(NetError::TooManySockets { max: MAX_SOCKETS });
+    }
+    let handle = net.next_handle.fetch_add(1, Ordering::Relaxed);
+    let endpoint = Arc::new(Endpoint::bind(handle, addr)?);
+
+    let thread = {
+        let endpoint = endpoint.clone();
+        std::thread::Builder::new()
+            .name(format!("udp-{}", handle))
+            .spawn(move || socket::run(app, &endpoint))
+            .map_err(NetError::Spawn)?
+    };
+    endpoint.set_thread(thread);
+    let opened = OpenedSocket {
+        handle,
+        local_addr: endpoint.local_addr.to_string(),
+    };
+    net.sockets.lock().unwrap().insert(handle, endpoint);
+    log::info!("udp socket {} bound to {}", handle, opened.local_addr);
+    Ok(opened)
+}
+
+/// Sends one datagram. With `reliable`, it is resent until `peer` acks it
+/// and the returned sequence number identifies it in `udp-send-failed`
+/// should it never arrive.
+#[tauri::command]
+pub async fn udp_send(
+    net: State<'_, NetSockets>,
+    handle: u32,
+    peer_addr: String,
+    payload: Vec<u8>,
+    reliable: Option<bool>,
+) -> Result<Option<u32>, NetError> {
+    let peer = parse_addr(&peer_addr)?;
+    net.get(handle)?
+        .send(peer, &payload, reliable.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn close_udp_socket(app: tauri::AppHandle, handle: u32) -> Result<(), NetError> {
+    let endpoint = app
+        .state::<NetSockets>()
+        .sockets
+        .lock()
+        .unwrap()
+        .remove(&handle)
+        .ok_or(NetError::UnknownSocket(handle))?;
+    // Joining waits out the thread's current poll.
+    let _ = tauri::async_runtime::spawn_blocking(move || endpoint.close()).await;
+    log::info!("udp socket {} closed", handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_socket_stats(net: State<'_, NetSockets>, handle: u32) -> Result<SocketStats, NetError> {
+    Ok(net.get(handle)?.stats())
+}
+
+/// Broadcasts a beacon for this server every two seconds until stopped.
+/// Calling it again updates the advertised details. `broadcast_addr`
+/// (`ip:port`) defaults to `255.255.255.255` on the discovery port; a
+/// subnet broadcast address helps where the limited broadcast is filtered.
+#[tauri::command]
+pub async fn start_lan_advertise(
+    app: tauri::AppHandle,
+    lan: State<'_, LanState>,
+    server_info: ServerInfo,
+    broadcast_addr: Option<String>,
+) -> Result<(), NetError> {
+    let target = match broadcast_addr {
+        Some(addr) => parse_addr(&addr)?,
+        None => (Ipv4Addr::BROADCAST, lan::DEFAULT_PORT).into(),
+    };
+    lan.start_advertise(&app, server_info, target)
+}
+
+#[tauri::command]
+pub async fn stop_lan_advertise(lan: State<'_, LanState>) -> Result<(), NetError> {
+    if lan.stop_advertise() {
+        Ok(())
+    } else {
+        Err(NetError::NotRunning("LAN advertising"))
+    }
+}
+
+/// Listens for beacons on `port` (default 47777), emitting
+/// `lan-server-found` for each server (and again when its details change)
+/// and `lan-server-lost` once it misses three beacons.
+#[tauri::command]
+pub async fn start_lan_discovery(
+    app: tauri::AppHandle,
+    lan: State<'_, LanState>,
+    port: Option<u16>,
+) -> Result<(), NetError> {
+    lan.start_discovery(app, port.unwrap_or(lan::DEFAULT_PORT))
+}
+
+#[tauri::command]
+pub async fn stop_lan_discovery(lan: State<'_, LanState>) -> Result<(), NetError> {
+    if lan.stop_discovery() {
+        Ok(())
+    } else {
+        Err(NetError::NotRunning("LAN discovery"))
+    }
+}
+
+/// Downloads the community server list from `master_url` and pings every
+/// server's game port, emitting `server-ping-result` as each ping resolves.
+/// Servers that never answer are returned as unreachable.
+#[tauri::command]
+pub async fn fetch_server_list(
+    app: tauri::AppHandle,
+    master_url: String,
+) -> Result<ServerList, NetError> {
+    let (listed, skipped) = browser::fetch_list(&master_url).await?;
+    let addrs: Vec<SocketAddr> = listed.iter().map(|(_, addr)| *addr).collect();
+    let pings = tauri::async_runtime::spawn_blocking(move || {
+        browser::ping_all(&addrs, |i, result| {
+            let _ = app.emit(
+                browser::PING_RESULT_EVENT,
+                PingEvent {
+                    address: addrs[i].to_string(),
+                    result,
+                },
+            );
+        })
+    })
+    .await
+    .map_err(task)?;
+
+    let servers = listed
+        .into_iter()
+        .zip(pings)
+        .map(|((server, _), ping)| BrowsedServer { server, ping })
+        .collect();
+    Ok(ServerList { servers, skipped })
+}
+
+/// Pings one server again, for refreshing a single row of the browser.
+#[tauri::command]
+pub async fn refresh_server_ping(addr: String) -> Result<PingResult, NetError> {
+    let addr = parse_addr(&addr)?;
+    let pings = tauri::async_runtime::spawn_blocking(move || browser::ping_all(&[addr], |_, _| {}))
+        .await
+        .map_err(task)?;
+    Ok(pings[0])
+}
+
+/// Sends `samples` pings to `addr`'s game port `interval_ms` apart and
+/// summarizes the round trips. A probe unanswered after a second counts as
+/// lost.
+#[tauri::command]
+pub async fn measure_latency(
+    addr: String,
+    samples: u8,
+    interval_ms: u16,
+) -> Result<LatencyStats, NetError> {
+    let addr = parse_addr(&addr)?;
+    if samples == 0 {
+        return Err(NetError::InvalidProbe(
+            "at least one sample is needed".to_string(),
+        ));
+    }
+    let interval = Duration::from_millis(interval_ms as u64);
+    tauri::async_runtime::spawn_blocking(move || probe::measure(addr, samples, interval))
+        .await
+        .map_err(task)?
+}
+
+/// Pings `addr` every `interval_ms` until stopped, emitting
+/// `latency-update` with each result and stats over the last 20. Returns
+/// the monitor id.
+#[tauri::command]
+pub async fn start_latency_monitor(
+    app: tauri::AppHandle,
+    monitors: State<'_, LatencyMonitors>,
+    addr: String,
+    interval_ms: u32,
+) -> Result<u32, NetError> {
+    let addr = parse_addr(&addr)?;
+    let range = probe::MIN_MONITOR_INTERVAL_MS..=probe::MAX_MONITOR_INTERVAL_MS;
+    if !range.contains(&interval_ms) {
+        return Err(NetError::InvalidProbe(format!(
+            "interval {} ms is outside {}..={}",
+            interval_ms,
+            range.start(),
+            range.end()
+        )));
+    }
+    monitors.start(app, addr, Duration::from_millis(interval_ms as u64))
+}
+
+#[tauri::command]
+pub async fn stop_latency_monitor(app: tauri::AppHandle, monitor_id: u32) -> Result<(), NetError> {
+    // Joining waits out the monitor's current probe.
+    let stopped = tauri::async_runtime::spawn_blocking(move || {
+        app.state::<LatencyMonitors>().stop(monitor_id)
+    })
+    .await
+    .map_err(task)?;
+    if stopped {
+        Ok(())
+    } else {
+        Err(NetError::NotRunning("That latency monitor"))
+    }
+}
+
+/// Sends unreliable datagrams from an open socket to one peer, for backend
+/// modules streaming their own traffic, such as voice chat.
+pub struct UdpSender {
+    endpoint: Arc<Endpoint>,
+    peer: SocketAddr,
+}
+
+impl UdpSender {
+    /// Fails with `unknownSocket` once the socket has been closed.
+    pub fn send(&self, payload: &[u8]) -> Result<(), NetError> {
+        if self.endpoint.is_closed() {
+            return Err(NetError::UnknownSocket(self.endpoint.handle));
+        }
+        self.endpoint.send(self.peer, payload, false).map(|_| ())
+    }
+}
+
+pub fn udp_sender<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    handle: u32,
+    peer_addr: &str,
+) -> Result<UdpSender, NetError> {
+    Ok(UdpSender {
+        endpoint: app.state::<NetSockets>().get(handle)?,
+        peer: parse_addr(peer_addr)?,
+    })
+}
+
+/// Closes every socket and stops LAN discovery and latency monitors. Called
+/// when the window closes and on exit, so dev reloads don't leave ports
+/// bound.
+pub fn close_all<R: Runtime>(app: &tauri::AppHandle<R>) {
+    app.state::<LanState>().stop_all();
+    app.state::<LatencyMonitors>().stop_all();
+    let endpoints: Vec<_> = app
+        .state::<NetSockets>()
+        .sockets
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, e)| e)
+        .collect();
+    for endpoint in endpoints {
+        endpoint.close();
+    }
+}
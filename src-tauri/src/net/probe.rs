@@ -0,0 +1,278 @@
+//! Latency probes: repeated pings to one game port, summarized as min, mean,
+//! max and jitter. Each measurement and each monitor uses its own socket
+//! connected to the target, so replies from other servers, or late replies
+//! to an earlier measurement, never reach it.
+
+use super::reliable::{self, Datagram};
+use super::worker::Worker;
+use super::NetError;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, RandomState};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Runtime};
+
+pub const LATENCY_EVENT: &str = "latency-update";
+
+/// A probe with no reply after this long counts as lost.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_MONITORS: usize = 8;
+/// Probes summarized in each `latency-update`.
+const MONITOR_WINDOW: usize = 20;
+pub const MIN_MONITOR_INTERVAL_MS: u32 = 100;
+pub const MAX_MONITOR_INTERVAL_MS: u32 = 60_000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    /// `None` for all four when no probe was answered.
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    /// Mean difference between consecutive round trips.
+    pub jitter_ms: Option<f64>,
+    pub loss_pct: f64,
+    pub samples_sent: u32,
+    pub samples_received: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyUpdate {
+    pub monitor_id: u32,
+    pub addr: String,
+    /// The latest probe's round trip; `None` when it was lost.
+    pub rtt_ms: Option<f64>,
+    /// Over the last few probes.
+    #[serde(flatten)]
+    pub stats: LatencyStats,
+}
+
+/// Round trips in probe order, `None` for lost probes.
+pub fn summarize(rtts: &[Option<f64>]) -> LatencyStats {
+    let received: Vec<f64> = rtts.iter().flatten().copied().collect();
+    let sent = rtts.len() as u32;
+    let count = received.len() as u32;
+    let jitter = (received.len() > 1).then(|| {
+        let total: f64 = received.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+        total / (received.len() - 1) as f64
+    });
+    LatencyStats {
+        min_ms: received.iter().copied().reduce(f64::min),
+        avg_ms: (count > 0).then(|| received.iter().sum::<f64>() / count as f64),
+        max_ms: received.iter().copied().reduce(f64::max),
+        jitter_ms: jitter,
+        loss_pct: if sent == 0 {
+            0.0
+        } else {
+            (sent - count) as f64 * 100.0 / sent as f64
+        },
+        samples_sent: sent,
+        samples_received: count,
+    }
+}
+
+/// A socket connected to one target, sending pings and matching pongs by
+/// nonce.
+struct Prober {
+    socket: UdpSocket,
+    nonces: RandomState,
+    counter: u64,
+}
+
+impl Prober {
+    fn connect(addr: SocketAddr) -> Result<Self, NetError> {
+        let bind: SocketAddr = match addr {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let bind_err = |source| NetError::Bind {
+            addr: bind.to_string(),
+            source,
+        };
+        let socket = UdpSocket::bind(bind).map_err(bind_err)?;
+        socket.connect(addr).map_err(bind_err)?;
+        Ok(Self {
+            socket,
+            nonces: RandomState::new(),
+            counter: 0,
+        })
+    }
+
+    /// Sends a ping and returns its nonce. A failed send is left to time
+    /// out like a lost probe.
+    fn send(&mut self) -> u64 {
+        self.counter += 1;
+        let nonce = self.nonces.hash_one(self.counter);
+        let _ = self.socket.send(&reliable::ping(nonce));
+        nonce
+    }
+
+    /// Waits until `deadline` for a pong to one of `pending`, returning its
+    /// nonce.
+    fn recv(&self, pending: &HashMap<u64, Instant>, deadline: Instant) -> Option<u64> {
+        let mut buf = [0u8; 64];
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            if wait.is_zero() {
+                return None;
+            }
+            self.socket.set_read_timeout(Some(wait)).ok()?;
+            match self.socket.recv(&mut buf) {
+                Ok(len) => {
+                    if let Some(Datagram::Pong(nonce)) = reliable::parse(&buf[..len]) {
+                        if pending.contains_key(&nonce) {
+                            return Some(nonce);
+                        }
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return None
+                }
+                // Such as a refused port reported by ICMP, which only means
+                // that probe is lost; keep waiting for the rest.
+                Err(_) => std::thread::sleep(wait.min(Duration::from_millis(1))),
+            }
+        }
+    }
+}
+
+/// Sends `samples` probes `interval` apart and waits for each up to the
+/// probe timeout. Blocking; run it off the async runtime.
+pub fn measure(
+    addr: SocketAddr,
+    samples: u8,
+    interval: Duration,
+) -> Result<LatencyStats, NetError> {
+    let mut prober = Prober::connect(addr)?;
+    let mut rtts: Vec<Option<f64>> = vec![None; samples as usize];
+    // nonce -> probe index, and nonce -> sent at for those still awaited
+    let mut index: HashMap<u64, usize> = HashMap::new();
+    let mut pending: HashMap<u64, Instant> = HashMap::new();
+    let start = Instant::now();
+    let mut next = 0usize;
+
+    loop {
+        let now = Instant::now();
+        if next < samples as usize && now >= start + interval * next as u32 {
+            let nonce = prober.send();
+            index.insert(nonce, next);
+            pending.insert(nonce, Instant::now());
+            next += 1;
+            continue;
+        }
+        pending.retain(|_, sent| now.duration_since(*sent) < PROBE_TIMEOUT);
+        let next_send = (next < samples as usize).then(|| start + interval * next as u32);
+        let next_expiry = pending.values().map(|&sent| sent + PROBE_TIMEOUT).min();
+        let Some(deadline) = next_send.into_iter().chain(next_expiry).min() else {
+            break;
+        };
+        if let Some(nonce) = prober.recv(&pending, deadline) {
+            let sent = pending.remove(&nonce).unwrap();
+            rtts[index[&nonce]] = Some(sent.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    Ok(summarize(&rtts))
+}
+
+/// Background probes for the in-game ping display, by monitor id.
+pub struct LatencyMonitors {
+    next_id: AtomicU32,
+    running: Mutex<HashMap<u32, Worker>>,
+}
+
+impl Default for LatencyMonitors {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU32::new(1),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl LatencyMonitors {
+    pub fn start<R: Runtime>(
+        &self,
+        app: tauri::AppHandle<R>,
+        addr: SocketAddr,
+        interval: Duration,
+    ) -> Result<u32, NetError> {
+        let mut running = self.running.lock().unwrap();
+        if running.len() >= MAX_MONITORS {
+            return Err(NetError::InvalidProbe(format!(
+                "at most {} latency monitors can run at once",
+                MAX_MONITORS
+            )));
+        }
+        let prober = Prober::connect(addr)?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let worker = Worker::spawn(&format!("latency-{}", id), move |stopped| {
+            monitor(&app, id, addr, prober, interval, stopped)
+        })?;
+        running.insert(id, worker);
+        Ok(id)
+    }
+
+    pub fn stop(&self, id: u32) -> bool {
+        let worker = self.running.lock().unwrap().remove(&id);
+        worker.map(Worker::stop).is_some()
+    }
+
+    pub fn stop_all(&self) {
+        let workers: Vec<_> = self.running.lock().unwrap().drain().collect();
+        for (_, worker) in workers {
+            worker.stop();
+        }
+    }
+}
+
+/// One probe per interval, each given up on by the next one (or the probe
+/// timeout, if sooner), so a monitor never has more than one outstanding.
+fn monitor<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    monitor_id: u32,
+    addr: SocketAddr,
+    mut prober: Prober,
+    interval: Duration,
+    stopped: mpsc::Receiver<()>,
+) {
+    let mut window: VecDeque<Option<f64>> = VecDeque::with_capacity(MONITOR_WINDOW);
+    let mut next = Instant::now();
+    loop {
+        let nonce = prober.send();
+        let sent = Instant::now();
+        let pending = HashMap::from([(nonce, sent)]);
+        let rtt = prober
+            .recv(&pending, sent + PROBE_TIMEOUT.min(interval))
+            .map(|_| sent.elapsed().as_secs_f64() * 1000.0);
+
+        if window.len() == MONITOR_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(rtt);
+        let _ = app.emit(
+            LATENCY_EVENT,
+            LatencyUpdate {
+                monitor_id,
+                addr: addr.to_string(),
+                rtt_ms: rtt,
+                stats: summarize(window.make_contiguous()),
+            },
+        );
+
+        next += interval;
+        let now = Instant::now();
+        if next < now {
+            next = now;
+        }
+        match stopped.recv_timeout(next - now) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => return,
+        }
+    }
+}
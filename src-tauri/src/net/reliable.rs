@@ -0,0 +1,483 @@
+//! Datagram framing and the optional reliability layer.
+//!
+//! Every datagram starts with a protocol byte and a kind byte. Reliable
+//! datagrams and acks follow them with the sender's session and a sequence
+//! number, little-endian:
+//!
+//! ```text
+//! u8   PROTOCOL_ID
+//! u8   kind: 0 unreliable, 1 reliable, 2 ack, 3 ping, 4 pong
+//! u32  session                       (reliable and ack only)
+//! u32  sequence                      (reliable and ack only)
+//! u64  nonce                         (ping and pong only)
+//! ...  payload                       (data only)
+//! ```
+//!
+//! Every socket answers a ping with a pong carrying the same nonce, which
+//! is how the server browser measures latency to a game port.
+//!
+//! A reliable datagram is acked as soon as it arrives, and resent until
+//! the ack comes back or it runs out of attempts. The receiver remembers
+//! recent sequence numbers per peer so a resend whose ack was lost is acked
+//! again but not delivered twice.
+//!
+//! Sequence numbers restart at 0 with every socket, so each socket also
+//! picks a random session. A peer that restarts on the same address comes
+//! back with a new session, and what was remembered about it is dropped
+//! instead of swallowing its first packets as duplicates.
+
+use lru::LruCache;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, RandomState};
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+const PROTOCOL_ID: u8 = 0x7a;
+const KIND_UNRELIABLE: u8 = 0;
+const KIND_RELIABLE: u8 = 1;
+const KIND_ACK: u8 = 2;
+const KIND_PING: u8 = 3;
+const KIND_PONG: u8 = 4;
+
+pub const HEADER_LEN: usize = 10;
+pub const MAX_PENDING: usize = 4096;
+
+const MAX_ATTEMPTS: u32 = 10;
+const INITIAL_RTO: Duration = Duration::from_millis(250);
+const MIN_RTO: Duration = Duration::from_millis(50);
+const MAX_RTO: Duration = Duration::from_secs(2);
+/// Sequence numbers remembered per peer for duplicate detection.
+const SEEN_PER_PEER: usize = 1024;
+const SEEN_PEERS: usize = 256;
+
+/// Identifies a reliable datagram: its sender's session and its sequence
+/// number within it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reliable {
+    pub session: u32,
+    pub seq: u32,
+}
+
+pub enum Datagram<'a> {
+    Data {
+        reliable: Option<Reliable>,
+        payload: &'a [u8],
+    },
+    Ack(Reliable),
+    Ping(u64),
+    Pong(u64),
+}
+
+pub fn parse(bytes: &[u8]) -> Option<Datagram<'_>> {
+    let [PROTOCOL_ID, kind, rest @ ..] = bytes else {
+        return None;
+    };
+    match *kind {
+        KIND_UNRELIABLE => {
+            return Some(Datagram::Data {
+                reliable: None,
+                payload: rest,
+            })
+        }
+        KIND_PING | KIND_PONG => {
+            let nonce = u64::from_le_bytes(rest.try_into().ok()?);
+            return Some(if *kind == KIND_PING {
+                Datagram::Ping(nonce)
+            } else {
+                Datagram::Pong(nonce)
+            });
+        }
+        _ => {}
+    }
+    let (session, rest) = rest.split_first_chunk::<4>()?;
+    let (seq, payload) = rest.split_first_chunk::<4>()?;
+    let id = Reliable {
+        session: u32::from_le_bytes(*session),
+        seq: u32::from_le_bytes(*seq),
+    };
+    match *kind {
+        KIND_RELIABLE => Some(Datagram::Data {
+            reliable: Some(id),
+            payload,
+        }),
+        KIND_ACK if payload.is_empty() => Some(Datagram::Ack(id)),
+        _ => None,
+    }
+}
+
+pub fn frame(reliable: Option<Reliable>, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    match reliable {
+        Some(id) => {
+            out.extend_from_slice(&[PROTOCOL_ID, KIND_RELIABLE]);
+            out.extend_from_slice(&id.session.to_le_bytes());
+            out.extend_from_slice(&id.seq.to_le_bytes());
+        }
+        None => out.extend_from_slice(&[PROTOCOL_ID, KIND_UNRELIABLE]),
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Acks carry the session of the datagram they answer, so the sender can
+/// ignore acks meant for an earlier socket on the same port.
+pub fn ack(id: Reliable) -> [u8; HEADER_LEN] {
+    let mut out = [0; HEADER_LEN];
+    out[0] = PROTOCOL_ID;
+    out[1] = KIND_ACK;
+    out[2..6].copy_from_slice(&id.session.to_le_bytes());
+    out[6..].copy_from_slice(&id.seq.to_le_bytes());
+    out
+}
+
+pub const PING_LEN: usize = 10;
+
+pub fn ping(nonce: u64) -> [u8; PING_LEN] {
+    probe(KIND_PING, nonce)
+}
+
+pub fn pong(nonce: u64) -> [u8; PING_LEN] {
+    probe(KIND_PONG, nonce)
+}
+
+fn probe(kind: u8, nonce: u64) -> [u8; PING_LEN] {
+    let mut out = [0; PING_LEN];
+    out[0] = PROTOCOL_ID;
+    out[1] = kind;
+    out[2..].copy_from_slice(&nonce.to_le_bytes());
+    out
+}
+
+struct Pending {
+    peer: SocketAddr,
+    datagram: Vec<u8>,
+    first_sent: Instant,
+    last_sent: Instant,
+    attempts: u32,
+}
+
+/// Recent sequence numbers from one peer, all from its latest session.
+struct Seen {
+    session: u32,
+    set: HashSet<u32>,
+    order: VecDeque<u32>,
+}
+
+impl Seen {
+    fn new(session: u32) -> Self {
+        Self {
+            session,
+            set: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+}
+
+pub struct Due {
+    pub resend: Vec<(SocketAddr, Vec<u8>)>,
+    pub expired: Vec<(SocketAddr, u32)>,
+}
+
+/// Per-socket reliability state, driven by the receive thread.
+pub struct Reliability {
+    session: u32,
+    next_seq: u32,
+    pending: HashMap<u32, Pending>,
+    seen: LruCache<SocketAddr, Seen>,
+    srtt: Option<Duration>,
+    rttvar: Duration,
+    /// Reliable transmissions, first sends and resends together.
+    pub transmissions: u64,
+    pub resends: u64,
+    pub failed: u64,
+}
+
+impl Default for Reliability {
+    fn default() -> Self {
+        Self {
+            session: RandomState::new().hash_one(std::process::id()) as u32,
+            next_seq: 0,
+            pending: HashMap::new(),
+            seen: LruCache::new(NonZeroUsize::new(SEEN_PEERS).unwrap()),
+            srtt: None,
+            rttvar: Duration::ZERO,
+            transmissions: 0,
+            resends: 0,
+            failed: 0,
+        }
+    }
+}
+
+impl Reliability {
+    /// Frames `payload` under a new sequence number and keeps it for resends.
+    /// The caller sends the returned datagram.
+    pub fn track(&mut self, peer: SocketAddr, payload: &[u8], now: Instant) -> (u32, Vec<u8>) {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        let id = Reliable {
+            session: self.session,
+            seq,
+        };
+        let datagram = frame(Some(id), payload);
+        self.pending.insert(
+            seq,
+            Pending {
+                peer,
+                datagram: datagram.clone(),
+                first_sent: now,
+                last_sent: now,
+                attempts: 1,
+            },
+        );
+        self.transmissions += 1;
+        (seq, datagram)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Clears an acked packet. Only packets that were never resent feed the
+    /// RTT estimate, since an ack for a resend can't be told apart from a
+    /// late ack for the original.
+    pub fn acked(&mut self, peer: SocketAddr, id: Reliable, now: Instant) {
+        if id.session != self.session {
+            return;
+        }
+        let seq = id.seq;
+        let Some(p) = self.pending.get(&seq).filter(|p| p.peer == peer) else {
+            return;
+        };
+        if p.attempts == 1 {
+            let sample = now.duration_since(p.first_sent);
+            match self.srtt {
+                None => {
+                    self.srtt = Some(sample);
+                    self.rttvar = sample / 2;
+                }
+                Some(srtt) => {
+                    self.rttvar = (self.rttvar * 3 + srtt.abs_diff(sample)) / 4;
+                    self.srtt = Some((srtt * 7 + sample) / 8);
+                }
+            }
+        }
+        self.pending.remove(&seq);
+    }
+
+    /// Whether a reliable packet from `peer` is new. Either way it should
+    /// be acked. A new session from the peer starts its history afresh.
+    pub fn first_delivery(&mut self, peer: SocketAddr, id: Reliable) -> bool {
+        let seen = self.seen.get_or_insert_mut(peer, || Seen::new(id.session));
+        if seen.session != id.session {
+            *seen = Seen::new(id.session);
+        }
+        let seq = id.seq;
+        if !seen.set.insert(seq) {
+            return false;
+        }
+        seen.order.push_back(seq);
+        if seen.order.len() > SEEN_PER_PEER {
+            let old = seen.order.pop_front().unwrap();
+            seen.set.remove(&old);
+        }
+        true
+    }
+
+    /// Datagrams whose ack is overdue, to be sent again, and packets that
+    /// are out of attempts and have been given up on.
+    pub fn due(&mut self, now: Instant) -> Due {
+        let rto = self.rto();
+        let mut resend = Vec::new();
+        let mut expired = Vec::new();
+        for (&seq, p) in &mut self.pending {
+            // Back off exponentially per attempt.
+            let wait = (rto * (1 << (p.attempts - 1).min(5))).min(MAX_RTO);
+            if now.duration_since(p.last_sent) < wait {
+                continue;
+            }
+            if p.attempts >= MAX_ATTEMPTS {
+                expired.push((p.peer, seq));
+                continue;
+            }
+            p.attempts += 1;
+            p.last_sent = now;
+            resend.push((p.peer, p.datagram.clone()));
+        }
+        for (_, seq) in &expired {
+            self.pending.remove(seq);
+        }
+        self.transmissions += resend.len() as u64;
+        self.resends += resend.len() as u64;
+        self.failed += expired.len() as u64;
+        Due { resend, expired }
+    }
+
+    pub fn rtt(&self) -> Option<Duration> {
+        self.srtt
+    }
+
+    /// Fraction of reliable transmissions that had to be repeated.
+    pub fn estimated_loss(&self) -> f64 {
+        if self.transmissions == 0 {
+            0.0
+        } else {
+            self.resends as f64 / self.transmissions as f64
+        }
+    }
+
+    fn rto(&self) -> Duration {
+        match self.srtt {
+            Some(srtt) => (srtt + self.rttvar * 4).clamp(MIN_RTO, MAX_RTO),
+            None => INITIAL_RTO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn id(session: u32, seq: u32) -> Reliable {
+        Reliable { session, seq }
+    }
+
+    #[test]
+    fn datagrams_parse_back() {
+        let framed = frame(Some(id(7, 42)), b"hello");
+        assert_eq!(framed.len(), HEADER_LEN + 5);
+        match parse(&framed) {
+            Some(Datagram::Data {
+                reliable: Some(got),
+                payload,
+            }) => assert_eq!((got, payload), (id(7, 42), &b"hello"[..])),
+            _ => panic!("expected reliable data"),
+        }
+        assert!(matches!(
+            parse(&frame(None, b"x")),
+            Some(Datagram::Data {
+                reliable: None,
+                payload: b"x"
+            })
+        ));
+        assert!(matches!(parse(&ack(id(7, 42))), Some(Datagram::Ack(got)) if got == id(7, 42)));
+        assert!(matches!(parse(&ping(9)), Some(Datagram::Ping(9))));
+        assert!(matches!(parse(&pong(9)), Some(Datagram::Pong(9))));
+        // Acks with a payload, short headers and foreign traffic.
+        assert!(parse(&[&ack(id(7, 42))[..], b"x"].concat()).is_none());
+        assert!(parse(&framed[..HEADER_LEN - 1]).is_none());
+        assert!(parse(b"GET / HTTP/1.1").is_none());
+    }
+
+    #[test]
+    fn duplicates_are_acked_but_delivered_once() {
+        let mut rel = Reliability::default();
+        assert!(rel.first_delivery(peer(1), id(5, 0)));
+        assert!(!rel.first_delivery(peer(1), id(5, 0)));
+        // Another peer's numbers are its own.
+        assert!(rel.first_delivery(peer(2), id(5, 0)));
+
+        for seq in 1..=SEEN_PER_PEER as u32 {
+            assert!(rel.first_delivery(peer(1), id(5, seq)));
+        }
+        assert!(!rel.first_delivery(peer(1), id(5, SEEN_PER_PEER as u32)));
+        // The oldest has been forgotten.
+        assert!(rel.first_delivery(peer(1), id(5, 0)));
+    }
+
+    #[test]
+    fn a_restarted_peer_is_not_taken_for_a_duplicate() {
+        let mut rel = Reliability::default();
+        for seq in 0..10 {
+            assert!(rel.first_delivery(peer(1), id(5, seq)));
+        }
+        // Same address, new socket: sequence numbers start over.
+        for seq in 0..10 {
+            assert!(rel.first_delivery(peer(1), id(6, seq)));
+        }
+        assert!(!rel.first_delivery(peer(1), id(6, 3)));
+    }
+
+    #[test]
+    fn acks_clear_only_matching_packets() {
+        let t0 = Instant::now();
+        let mut rel = Reliability::default();
+        let session = rel.session;
+        let (seq, datagram) = rel.track(peer(1), b"hi", t0);
+        assert!(
+            matches!(parse(&datagram), Some(Datagram::Data { reliable: Some(got), .. }) if got == id(session, seq))
+        );
+
+        rel.acked(peer(1), id(session.wrapping_add(1), seq), t0);
+        rel.acked(peer(2), id(session, seq), t0);
+        assert_eq!(rel.pending(), 1);
+        rel.acked(peer(1), id(session, seq), t0);
+        assert_eq!(rel.pending(), 0);
+    }
+
+    #[test]
+    fn resends_back_off_and_then_give_up() {
+        let t0 = Instant::now();
+        let mut rel = Reliability::default();
+        let (seq, datagram) = rel.track(peer(1), b"hi", t0);
+
+        let mut resent_at = Vec::new();
+        let mut expired_at = None;
+        for ms in (0..=20_000).step_by(10) {
+            let due = rel.due(t0 + Duration::from_millis(ms));
+            for (to, resent) in &due.resend {
+                assert_eq!((to, resent), (&peer(1), &datagram));
+                resent_at.push(ms);
+            }
+            if !due.expired.is_empty() {
+                assert_eq!(due.expired, [(peer(1), seq)]);
+                expired_at = Some(ms);
+                break;
+            }
+        }
+        // 250 ms doubling per attempt, capped at 2 s.
+        assert_eq!(
+            resent_at,
+            [250, 750, 1750, 3750, 5750, 7750, 9750, 11750, 13750]
+        );
+        assert_eq!(expired_at, Some(15750));
+        assert_eq!(rel.pending(), 0);
+        assert_eq!(
+            (rel.transmissions, rel.resends, rel.failed),
+            (MAX_ATTEMPTS as u64, MAX_ATTEMPTS as u64 - 1, 1)
+        );
+        assert!((rel.estimated_loss() - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rtt_is_smoothed_from_first_attempts_only() {
+        let t0 = Instant::now();
+        let ms = |n| t0 + Duration::from_millis(n);
+        let mut rel = Reliability::default();
+        let session = rel.session;
+        assert_eq!(rel.rtt(), None);
+        assert_eq!(rel.rto(), INITIAL_RTO);
+
+        let (a, _) = rel.track(peer(1), b"a", t0);
+        rel.acked(peer(1), id(session, a), ms(100));
+        assert_eq!(rel.rtt(), Some(Duration::from_millis(100)));
+        // srtt + 4 * rttvar, with rttvar starting at half the sample.
+        assert_eq!(rel.rto(), Duration::from_millis(300));
+
+        let (b, _) = rel.track(peer(1), b"b", ms(1000));
+        rel.acked(peer(1), id(session, b), ms(1200));
+        assert_eq!(rel.rtt(), Some(Duration::from_micros(112_500)));
+        assert_eq!(rel.rto(), Duration::from_micros(362_500));
+
+        // Resent once, so its ack can't be timed.
+        let (c, _) = rel.track(peer(1), b"c", ms(2000));
+        assert_eq!(rel.due(ms(2400)).resend.len(), 1);
+        rel.acked(peer(1), id(session, c), ms(2450));
+        assert_eq!(rel.rtt(), Some(Duration::from_micros(112_500)));
+        assert_eq!(rel.pending(), 0);
+    }
+}
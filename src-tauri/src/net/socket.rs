@@ -0,0 +1,277 @@
+use super::reliable::{self, Datagram, Due, Reliability, HEADER_LEN, MAX_PENDING};
+use super::NetError;
+use serde::Serialize;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Runtime};
+
+pub const PACKET_EVENT: &str = "udp-packet";
+pub const SEND_FAILED_EVENT: &str = "udp-send-failed";
+
+/// Keeps datagrams under a typical path MTU so they are never fragmented.
+pub const MAX_PAYLOAD_LEN: usize = 1200;
+/// How often the receive thread wakes to resend and to notice shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UdpPacket {
+    pub handle: u32,
+    pub from: String,
+    pub payload: Vec<u8>,
+    pub reliable: bool,
+}
+
+/// A reliable packet that was never acked.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendFailed {
+    pub handle: u32,
+    pub peer: String,
+    pub sequence: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketStats {
+    pub handle: u32,
+    pub local_addr: String,
+    pub packets_in: u64,
+    pub packets_out: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Datagrams dropped on arrival: not ours, oversized, or duplicates.
+    pub dropped_in: u64,
+    pub reliable_pending: usize,
+    pub resends: u64,
+    pub reliable_failed: u64,
+    /// Share of reliable transmissions that had to be resent. Lost acks
+    /// count too, so this is round-trip loss.
+    pub estimated_loss: f64,
+    /// Smoothed round trip from acks; `None` until the first ack.
+    pub rtt_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct Counters {
+    packets_in: AtomicU64,
+    packets_out: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    dropped_in: AtomicU64,
+}
+
+pub struct Endpoint {
+    pub handle: u32,
+    pub local_addr: SocketAddr,
+    socket: UdpSocket,
+    stop: AtomicBool,
+    reliability: Mutex<Reliability>,
+    counters: Counters,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Endpoint {
+    pub fn bind(handle: u32, addr: SocketAddr) -> Result<Self, NetError> {
+        let bind_err = |source| NetError::Bind {
+            addr: addr.to_string(),
+            source,
+        };
+        let socket = UdpSocket::bind(addr).map_err(bind_err)?;
+        socket
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(bind_err)?;
+        Ok(Self {
+            handle,
+            local_addr: socket.local_addr().map_err(bind_err)?,
+            socket,
+            stop: AtomicBool::new(false),
+            reliability: Mutex::new(Reliability::default()),
+            counters: Counters::default(),
+            thread: Mutex::new(None),
+        })
+    }
+
+    pub fn set_thread(&self, thread: JoinHandle<()>) {
+        *self.thread.lock().unwrap() = Some(thread);
+    }
+
+    /// Sends `payload` to `peer`, returning its sequence number when sent
+    /// reliably.
+    pub fn send(
+        &self,
+        peer: SocketAddr,
+        payload: &[u8],
+        reliable: bool,
+    ) -> Result<Option<u32>, NetError> {
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(NetError::PayloadTooLarge {
+                len: payload.len(),
+                max: MAX_PAYLOAD_LEN,
+            });
+        }
+        if !reliable {
+            let datagram = reliable::frame(None, payload);
+            self.send_raw(peer, &datagram)
+                .map_err(|source| NetError::Send {
+                    peer: peer.to_string(),
+                    source,
+                })?;
+            return Ok(None);
+        }
+
+        let (seq, datagram) = {
+            let mut rel = self.reliability.lock().unwrap();
+            if rel.pending() >= MAX_PENDING {
+                return Err(NetError::Backlogged {
+                    pending: rel.pending(),
+                });
+            }
+            rel.track(peer, payload, Instant::now())
+        };
+        // A failed first send is retried like a lost one, and reported
+        // through `udp-send-failed` if it never gets through.
+        let _ = self.send_raw(peer, &datagram);
+        Ok(Some(seq))
+    }
+
+    fn send_raw(&self, peer: SocketAddr, datagram: &[u8]) -> std::io::Result<()> {
+        self.socket.send_to(datagram, peer)?;
+        self.counters.packets_out.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .bytes_out
+            .fetch_add(datagram.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn stats(&self) -> SocketStats {
+        let c = &self.counters;
+        let rel = self.reliability.lock().unwrap();
+        SocketStats {
+            handle: self.handle,
+            local_addr: self.local_addr.to_string(),
+            packets_in: c.packets_in.load(Ordering::Relaxed),
+            packets_out: c.packets_out.load(Ordering::Relaxed),
+            bytes_in: c.bytes_in.load(Ordering::Relaxed),
+            bytes_out: c.bytes_out.load(Ordering::Relaxed),
+            dropped_in: c.dropped_in.load(Ordering::Relaxed),
+            reliable_pending: rel.pending(),
+            resends: rel.resends,
+            reliable_failed: rel.failed,
+            estimated_loss: rel.estimated_loss(),
+            rtt_ms: rel.rtt().map(|d| d.as_secs_f64() * 1000.0),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Stops the receive thread and waits for it, which takes at most one
+    /// poll interval. The socket closes when the last reference drops.
+    pub fn close(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The receive thread: delivers packets as events, answers and consumes
+/// acks, and resends overdue reliable packets between reads.
+pub fn run<R: Runtime>(app: tauri::AppHandle<R>, endpoint: &Endpoint) {
+    // Room for any datagram, so an oversized one is seen and dropped rather
+    // than silently truncated to the buffer.
+    let mut buf = vec![0u8; 64 * 1024];
+    while !endpoint.stop.load(Ordering::Relaxed) {
+        match endpoint.socket.recv_from(&mut buf) {
+            Ok((len, from)) => receive(&app, endpoint, &buf[..len], from),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            // Windows reports an ICMP port-unreachable from an earlier send
+            // as a reset on the next read; the socket itself is fine.
+            Err(e) if e.kind() == ErrorKind::ConnectionReset => {}
+            Err(e) => {
+                log::warn!("udp socket {} receive failed: {}", endpoint.handle, e);
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+        resend_due(&app, endpoint);
+    }
+}
+
+fn receive<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    endpoint: &Endpoint,
+    bytes: &[u8],
+    from: SocketAddr,
+) {
+    let c = &endpoint.counters;
+    c.packets_in.fetch_add(1, Ordering::Relaxed);
+    c.bytes_in.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+    let drop_it = || {
+        c.dropped_in.fetch_add(1, Ordering::Relaxed);
+    };
+    if bytes.len() > HEADER_LEN + MAX_PAYLOAD_LEN {
+        return drop_it();
+    }
+
+    let (reliable, payload) = match reliable::parse(bytes) {
+        Some(Datagram::Ack(id)) => {
+            endpoint
+                .reliability
+                .lock()
+                .unwrap()
+                .acked(from, id, Instant::now());
+            return;
+        }
+        Some(Datagram::Ping(nonce)) => {
+            let _ = endpoint.send_raw(from, &reliable::pong(nonce));
+            return;
+        }
+        // Pongs are for the server browser's own socket, not game traffic.
+        Some(Datagram::Pong(_)) => return drop_it(),
+        Some(Datagram::Data { reliable, payload }) => (reliable, payload),
+        None => return drop_it(),
+    };
+    if let Some(id) = reliable {
+        let _ = endpoint.send_raw(from, &reliable::ack(id));
+        if !endpoint
+            .reliability
+            .lock()
+            .unwrap()
+            .first_delivery(from, id)
+        {
+            return drop_it();
+        }
+    }
+    let _ = app.emit(
+        PACKET_EVENT,
+        UdpPacket {
+            handle: endpoint.handle,
+            from: from.to_string(),
+            payload: payload.to_vec(),
+            reliable: reliable.is_some(),
+        },
+    );
+}
+
+fn resend_due<R: Runtime>(app: &tauri::AppHandle<R>, endpoint: &Endpoint) {
+    let Due { resend, expired } = endpoint.reliability.lock().unwrap().due(Instant::now());
+    for (peer, datagram) in resend {
+        let _ = endpoint.send_raw(peer, &datagram);
+    }
+    for (peer, sequence) in expired {
+        let _ = app.emit(
+            SEND_FAILED_EVENT,
+            SendFailed {
+                handle: endpoint.handle,
+                peer: peer.to_string(),
+                sequence,
+            },
+        );
+    }
+}
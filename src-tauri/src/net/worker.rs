@@ -0,0 +1,28 @@
+use super::NetError;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+/// A background thread that exits once its stop sender is dropped.
+pub struct Worker {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Worker {
+    pub fn spawn(
+        name: &str,
+        run: impl FnOnce(mpsc::Receiver<()>) + Send + 'static,
+    ) -> Result<Self, NetError> {
+        let (stop, stopped) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name(name.into())
+            .spawn(move || run(stopped))
+            .map_err(NetError::Spawn)?;
+        Ok(Self { stop, thread })
+    }
+
+    pub fn stop(self) {
+        drop(self.stop);
+        let _ = self.thread.join();
+    }
+}
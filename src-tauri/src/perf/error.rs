@@ -0,0 +1,47 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the perf commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum PerfError {
+    #[error("Could not locate the documents directory: {0}")]
+    NoDocumentDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Export path {0:?} must be absolute")]
+    InvalidExportPath(PathBuf),
+}
+
+impl PerfError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        PerfError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PerfError::NoDocumentDir(_) => "noDocumentDir",
+            PerfError::Io { .. } => "io",
+            PerfError::InvalidExportPath(_) => "invalidExportPath",
+        }
+    }
+}
+
+impl Serialize for PerfError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("PerfError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
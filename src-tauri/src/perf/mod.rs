@@ -0,0 +1,91 @@
+mod error;
+mod stats;
+mod window;
+
+pub use error::PerfError;
+pub use stats::FrameStats;
+
+use crate::time::now_ms;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::path::BaseDirectory;
+use tauri::{Manager, Runtime, State};
+use window::FrameWindow;
+
+const EXPORT_DIR: &str = "fps-game";
+
+/// Frame times streamed from the renderer, kept for the last five minutes
+/// of play.
+#[derive(Default)]
+pub struct PerfState {
+    window: Mutex<FrameWindow>,
+}
+
+/// Adds a batch of frame times in milliseconds, oldest first. Meant to be
+/// called about once a second with everything rendered since.
+#[tauri::command]
+pub fn record_frame_times(perf: State<'_, PerfState>, times_ms: Vec<f32>) {
+    perf.window.lock().unwrap().extend(&times_ms);
+}
+
+/// Averages, lows, percentiles and stutters over the window, or `None`
+/// before any frames are recorded.
+#[tauri::command]
+pub async fn get_frame_stats(perf: State<'_, PerfState>) -> Result<Option<FrameStats>, PerfError> {
+    // Sorted outside the lock so recording isn't held up.
+    let times = perf.window.lock().unwrap().to_vec();
+    Ok(stats::compute(times))
+}
+
+/// Empties the window, e.g. as a benchmark run starts.
+#[tauri::command]
+pub fn reset_frame_stats(perf: State<'_, PerfState>) {
+    perf.window.lock().unwrap().clear();
+}
+
+/// Stats over the window for backend modules, as [`get_frame_stats`]
+/// returns them.
+pub fn current_stats<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<FrameStats> {
+    let times = app.state::<PerfState>().window.lock().unwrap().to_vec();
+    stats::compute(times)
+}
+
+/// Empties the window for backend modules starting a measurement.
+pub fn reset<R: Runtime>(app: &tauri::AppHandle<R>) {
+    app.state::<PerfState>().window.lock().unwrap().clear();
+}
+
+/// Writes the window as CSV, one row per frame with its time and the
+/// running total, and returns the path written. `path` may be a file or an
+/// existing directory; without one the export goes to `Documents/fps-game/`.
+#[tauri::command]
+pub async fn export_frame_trace(
+    app: tauri::AppHandle,
+    perf: State<'_, PerfState>,
+    path: Option<String>,
+) -> Result<String, PerfError> {
+    let times = perf.window.lock().unwrap().to_vec();
+
+    let default_name = format!("frame-trace-{}.csv", now_ms());
+    let target = match path.map(PathBuf::from) {
+        Some(path) if !path.is_absolute() => return Err(PerfError::InvalidExportPath(path)),
+        Some(path) if path.is_dir() => path.join(default_name),
+        Some(path) => path,
+        None => app
+            .path()
+            .resolve(EXPORT_DIR, BaseDirectory::Document)
+            .map_err(|e| PerfError::NoDocumentDir(e.to_string()))?
+            .join(default_name),
+    };
+
+    let mut csv = String::from("frame,frame_time_ms,elapsed_ms\n");
+    let mut elapsed = 0.0f64;
+    for (i, t) in times.iter().enumerate() {
+        elapsed += *t as f64;
+        let _ = writeln!(csv, "{},{:.3},{:.3}", i, t, elapsed);
+    }
+    crate::fs_atomic::write_atomic(&target, csv.as_bytes())
+        .map_err(|e| PerfError::io(&target, e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
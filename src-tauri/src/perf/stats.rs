@@ -0,0 +1,150 @@
+//! Summary statistics over a window of frame times.
+
+use serde::{Deserialize, Serialize};
+
+/// A frame this many times the median counts as a stutter.
+const STUTTER_FACTOR: f64 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameStats {
+    pub frame_count: usize,
+    /// Play time the window covers, in milliseconds.
+    pub duration_ms: f64,
+    pub avg_ms: f64,
+    pub avg_fps: f64,
+    /// FPS over the slowest 1% of frames: the average of their times,
+    /// inverted. At least one frame always counts.
+    pub one_percent_low_fps: f64,
+    pub point_one_percent_low_fps: f64,
+    /// Frame time percentiles, interpolated between neighbouring frames.
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    /// Frames longer than twice the median.
+    pub stutter_count: usize,
+}
+
+/// `None` for an empty window.
+pub fn compute(mut times: Vec<f32>) -> Option<FrameStats> {
+    if times.is_empty() {
+        return None;
+    }
+    times.sort_unstable_by(f32::total_cmp);
+    let sorted: Vec<f64> = times.into_iter().map(f64::from).collect();
+
+    let duration_ms: f64 = sorted.iter().sum();
+    let avg_ms = duration_ms / sorted.len() as f64;
+    let p50_ms = percentile(&sorted, 50.0);
+    let stutter_limit = p50_ms * STUTTER_FACTOR;
+    Some(FrameStats {
+        frame_count: sorted.len(),
+        duration_ms,
+        avg_ms,
+        avg_fps: fps(avg_ms),
+        one_percent_low_fps: fps(slowest_avg(&sorted, 0.01)),
+        point_one_percent_low_fps: fps(slowest_avg(&sorted, 0.001)),
+        p50_ms,
+        p95_ms: percentile(&sorted, 95.0),
+        p99_ms: percentile(&sorted, 99.0),
+        stutter_count: sorted.iter().filter(|&&t| t > stutter_limit).count(),
+    })
+}
+
+/// The `p`th percentile of ascending, non-empty `sorted`, interpolating
+/// linearly between the two nearest ranks.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Average of the slowest `fraction` of ascending, non-empty `sorted`.
+fn slowest_avg(sorted: &[f64], fraction: f64) -> f64 {
+    let n = ((sorted.len() as f64 * fraction).ceil() as usize).clamp(1, sorted.len());
+    sorted[sorted.len() - n..].iter().sum::<f64>() / n as f64
+}
+
+fn fps(frame_ms: f64) -> f64 {
+    1000.0 / frame_ms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn empty_windows_have_no_stats() {
+        assert_eq!(compute(Vec::new()), None);
+    }
+
+    #[test]
+    fn a_single_frame_is_every_percentile() {
+        let stats = compute(vec![16.0]).unwrap();
+        assert_eq!(stats.frame_count, 1);
+        assert_eq!(stats.duration_ms, 16.0);
+        assert_eq!(stats.avg_fps, 62.5);
+        assert_eq!(stats.one_percent_low_fps, 62.5);
+        assert_eq!(stats.point_one_percent_low_fps, 62.5);
+        assert_eq!(
+            (stats.p50_ms, stats.p95_ms, stats.p99_ms),
+            (16.0, 16.0, 16.0)
+        );
+        assert_eq!(stats.stutter_count, 0);
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_ranks() {
+        // 1..=100 ms, out of order.
+        let times: Vec<f32> = (0..100).map(|i| ((i * 37) % 100 + 1) as f32).collect();
+        let stats = compute(times).unwrap();
+        assert_eq!(stats.duration_ms, 5050.0);
+        assert_eq!(stats.avg_ms, 50.5);
+        assert_close(stats.p50_ms, 50.5);
+        assert_close(stats.p95_ms, 95.05);
+        assert_close(stats.p99_ms, 99.01);
+        // One frame is the slowest 1% of a hundred.
+        assert_eq!(stats.one_percent_low_fps, 10.0);
+        assert_eq!(stats.point_one_percent_low_fps, 10.0);
+        assert_eq!(stats.stutter_count, 0);
+    }
+
+    #[test]
+    fn lows_average_the_slowest_frames() {
+        let mut times = vec![10.0; 990];
+        times.extend([50.0; 10]);
+        let stats = compute(times).unwrap();
+        assert_eq!(stats.p50_ms, 10.0);
+        assert_close(stats.p99_ms, 10.4);
+        assert_eq!(stats.one_percent_low_fps, 20.0);
+        assert_eq!(stats.point_one_percent_low_fps, 20.0);
+        assert_eq!(stats.stutter_count, 10);
+
+        // The slowest 1% of 250 frames rounds up to three of them.
+        let mut times = vec![10.0; 247];
+        times.extend([20.0, 40.0, 60.0]);
+        assert_eq!(compute(times).unwrap().one_percent_low_fps, 25.0);
+    }
+
+    #[test]
+    fn ties_are_handled_evenly() {
+        let stats = compute(vec![8.0; 64]).unwrap();
+        assert_eq!((stats.p50_ms, stats.p95_ms, stats.p99_ms), (8.0, 8.0, 8.0));
+        assert_eq!(stats.one_percent_low_fps, 125.0);
+        assert_eq!(stats.stutter_count, 0);
+
+        // Exactly twice the median is not yet a stutter.
+        let stats = compute(vec![5.0, 10.0, 5.0, 20.0, 5.0]).unwrap();
+        assert_eq!(stats.p50_ms, 5.0);
+        assert_eq!(stats.stutter_count, 1);
+    }
+}
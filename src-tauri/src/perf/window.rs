@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+
+/// How much play the window covers, in summed frame time.
+const WINDOW_MS: f64 = 5.0 * 60.0 * 1000.0;
+/// Hard cap on stored frames: five minutes at 1000 fps, about 1.2 MB. Above
+/// that rate the window covers less than five minutes.
+const MAX_FRAMES: usize = 300_000;
+/// Longer "frames" are loading hitches or a suspended tab, not rendering.
+const MAX_FRAME_MS: f32 = 10_000.0;
+
+/// The most recent frame times, oldest first, bounded by both duration and
+/// count however long the session runs.
+#[derive(Default)]
+pub struct FrameWindow {
+    times: VecDeque<f32>,
+    total_ms: f64,
+}
+
+impl FrameWindow {
+    /// Appends a batch, dropping non-positive, non-finite and implausibly
+    /// long times, then ages out frames beyond the window.
+    pub fn extend(&mut self, times: &[f32]) {
+        for &t in times {
+            if t.is_finite() && t > 0.0 && t <= MAX_FRAME_MS {
+                self.times.push_back(t);
+                self.total_ms += t as f64;
+            }
+        }
+        while self.total_ms > WINDOW_MS || self.times.len() > MAX_FRAMES {
+            let Some(oldest) = self.times.pop_front() else {
+                break;
+            };
+            self.total_ms -= oldest as f64;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.times.clear();
+        self.times.shrink_to_fit();
+        self.total_ms = 0.0;
+    }
+
+    pub fn to_vec(&self) -> Vec<f32> {
+        self.times.iter().copied().collect()
+    }
+}
@@ -0,0 +1,145 @@
+//! The thread that owns the Discord connection. It applies activity
+//! changes as they arrive, pings Discord to notice it closing, and while
+//! disconnected retries with backoff, re-sending the latest activity once
+//! it gets through.
+
+use super::ipc::Connection;
+use super::PresenceError;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a command waits for the thread to apply it.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long exit waits for the activity to be cleared.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum Command {
+    /// Replies with whether Discord is connected afterwards.
+    Set(Option<Value>, mpsc::Sender<bool>),
+}
+
+pub struct Client {
+    pub app_id: String,
+    commands: mpsc::Sender<Command>,
+    connected: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Client {
+    /// Starts the thread, with `first` as the result of an initial
+    /// connection attempt made by the caller.
+    pub fn spawn(app_id: String, first: Option<Connection>) -> Result<Self, PresenceError> {
+        let (commands, received) = mpsc::channel();
+        let connected = Arc::new(AtomicBool::new(first.is_some()));
+        let thread = {
+            let app_id = app_id.clone();
+            let connected = connected.clone();
+            std::thread::Builder::new()
+                .name("discord-presence".into())
+                .spawn(move || run(&app_id, first, &connected, received))
+                .map_err(PresenceError::Spawn)?
+        };
+        Ok(Self {
+            app_id,
+            commands,
+            connected,
+            thread,
+        })
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Sets or clears the activity. Blocking; returns whether it reached
+    /// Discord. When it didn't, it's sent on reconnect.
+    pub fn set(&self, activity: Option<Value>) -> bool {
+        let (reply, replied) = mpsc::channel();
+        if self.commands.send(Command::Set(activity, reply)).is_err() {
+            return false;
+        }
+        replied.recv_timeout(REPLY_TIMEOUT).unwrap_or(false)
+    }
+
+    /// Ends the thread, which clears the activity first. Waits briefly: a
+    /// hung Discord must not hold up exit.
+    pub fn stop(self) {
+        drop(self.commands);
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while !self.thread.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        if self.thread.is_finished() {
+            let _ = self.thread.join();
+        }
+    }
+}
+
+fn run(
+    app_id: &str,
+    mut conn: Option<Connection>,
+    connected: &AtomicBool,
+    received: mpsc::Receiver<Command>,
+) {
+    let mut activity: Option<Value> = None;
+    let mut backoff = MIN_BACKOFF;
+    let mut next_attempt = Instant::now() + backoff;
+
+    loop {
+        let wait = match conn {
+            Some(_) => PING_INTERVAL,
+            None => next_attempt.saturating_duration_since(Instant::now()),
+        };
+        match received.recv_timeout(wait) {
+            Ok(Command::Set(next, reply)) => {
+                activity = next;
+                if let Some(c) = conn.as_mut() {
+                    if c.set_activity(activity.as_ref()).is_err() {
+                        conn = None;
+                        backoff = MIN_BACKOFF;
+                        next_attempt = Instant::now() + backoff;
+                    }
+                }
+                let _ = reply.send(conn.is_some());
+            }
+            Err(RecvTimeoutError::Timeout) => match conn.as_mut() {
+                Some(c) => {
+                    if c.ping().is_err() {
+                        conn = None;
+                        backoff = MIN_BACKOFF;
+                        next_attempt = Instant::now() + backoff;
+                    }
+                }
+                None => {
+                    conn = Connection::open(app_id)
+                        .and_then(|mut c| {
+                            if activity.is_some() {
+                                c.set_activity(activity.as_ref())?;
+                            }
+                            Ok(c)
+                        })
+                        .ok();
+                    if conn.is_none() {
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                        next_attempt = Instant::now() + backoff;
+                    }
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Some(c) = conn.as_mut() {
+                    let _ = c.set_activity(None);
+                }
+                connected.store(false, Ordering::Relaxed);
+                return;
+            }
+        }
+        connected.store(conn.is_some(), Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the presence commands, serialized as `{ kind, message }`.
+/// Discord being unavailable is never one of them; see `PresenceStatus`.
+#[derive(Debug, thiserror::Error)]
+pub enum PresenceError {
+    #[error("Invalid Discord application id {0:?}")]
+    InvalidAppId(String),
+
+    #[error("Invalid presence: {0}")]
+    InvalidActivity(String),
+
+    #[error("Presence has not been initialized")]
+    NotInitialized,
+
+    #[error("Failed to start the presence thread: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    #[error("Background presence task failed: {0}")]
+    Task(String),
+}
+
+impl PresenceError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PresenceError::InvalidAppId(_) => "invalidAppId",
+            PresenceError::InvalidActivity(_) => "invalidActivity",
+            PresenceError::NotInitialized => "notInitialized",
+            PresenceError::Spawn(_) => "spawn",
+            PresenceError::Task(_) => "task",
+        }
+    }
+}
+
+impl Serialize for PresenceError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("PresenceError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+pub(crate) fn task(e: tauri::Error) -> PresenceError {
+    PresenceError::Task(e.to_string())
+}
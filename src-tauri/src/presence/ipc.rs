@@ -0,0 +1,182 @@
+//! Discord's local RPC transport: a Unix socket or named pipe called
+//! `discord-ipc-N`, carrying frames of a little-endian `u32` opcode, a
+//! `u32` length and that much JSON.
+
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::time::Duration;
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+const OP_CLOSE: u32 = 2;
+const OP_PING: u32 = 3;
+const OP_PONG: u32 = 4;
+
+/// Discord tries `discord-ipc-0` first and moves up when it's taken.
+const MAX_PIPE_INDEX: u32 = 10;
+const MAX_FRAME_LEN: usize = 64 * 1024;
+/// How long to wait for Discord to answer before treating it as gone.
+#[cfg(unix)]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+trait Pipe: Read + Write + Send {}
+impl<T: Read + Write + Send> Pipe for T {}
+
+pub struct Connection {
+    pipe: Box<dyn Pipe>,
+    nonce: u64,
+}
+
+impl Connection {
+    /// Finds a running Discord client and completes the handshake for
+    /// `app_id`. Fails when Discord isn't running or rejects the app.
+    pub fn open(app_id: &str) -> io::Result<Self> {
+        let mut last = io::Error::new(io::ErrorKind::NotFound, "Discord is not running");
+        for pipe in candidates() {
+            let mut conn = Self { pipe, nonce: 0 };
+            match conn.handshake(app_id) {
+                Ok(()) => return Ok(conn),
+                Err(e) => last = e,
+            }
+        }
+        Err(last)
+    }
+
+    fn handshake(&mut self, app_id: &str) -> io::Result<()> {
+        self.send(OP_HANDSHAKE, &json!({ "v": 1, "client_id": app_id }))?;
+        let (op, reply) = self.recv()?;
+        match op {
+            OP_FRAME if reply["evt"] == "READY" => Ok(()),
+            _ => Err(rejected(&reply)),
+        }
+    }
+
+    /// Sets this process's activity, or clears it with `None`. Discord
+    /// rejecting the activity itself is logged, not an error: the
+    /// connection is still good.
+    pub fn set_activity(&mut self, activity: Option<&Value>) -> io::Result<()> {
+        self.nonce += 1;
+        let command = json!({
+            "cmd": "SET_ACTIVITY",
+            "args": { "pid": std::process::id(), "activity": activity },
+            "nonce": self.nonce.to_string(),
+        });
+        self.send(OP_FRAME, &command)?;
+        let (op, reply) = self.recv()?;
+        match op {
+            OP_FRAME if reply["evt"] == "ERROR" => {
+                log::warn!(
+                    "Discord rejected the activity: {}",
+                    reply["data"]["message"]
+                );
+                Ok(())
+            }
+            OP_FRAME => Ok(()),
+            _ => Err(rejected(&reply)),
+        }
+    }
+
+    /// Checks the connection is still alive.
+    pub fn ping(&mut self) -> io::Result<()> {
+        self.send(OP_PING, &json!({}))?;
+        loop {
+            match self.recv()? {
+                (OP_PONG, _) => return Ok(()),
+                (OP_CLOSE, reply) => return Err(rejected(&reply)),
+                // Stray replies or events; keep waiting for the pong.
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, op: u32, body: &Value) -> io::Result<()> {
+        let body = serde_json::to_vec(body).expect("presence frame serialize");
+        let mut frame = Vec::with_capacity(body.len() + 8);
+        frame.extend_from_slice(&op.to_le_bytes());
+        frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&body);
+        self.pipe.write_all(&frame)?;
+        self.pipe.flush()
+    }
+
+    fn recv(&mut self) -> io::Result<(u32, Value)> {
+        let mut head = [0u8; 8];
+        self.pipe.read_exact(&mut head)?;
+        let op = u32::from_le_bytes([head[0], head[1], head[2], head[3]]);
+        let len = u32::from_le_bytes([head[4], head[5], head[6], head[7]]) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(invalid("oversized frame from Discord"));
+        }
+        let mut body = vec![0u8; len];
+        self.pipe.read_exact(&mut body)?;
+        let body =
+            serde_json::from_slice(&body).map_err(|_| invalid("malformed frame from Discord"))?;
+        Ok((op, body))
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// A close frame or unexpected reply, with Discord's message if it gave one.
+fn rejected(reply: &Value) -> io::Error {
+    let message = reply["message"]
+        .as_str()
+        .or(reply["data"]["message"].as_str())
+        .unwrap_or("unexpected reply");
+    io::Error::new(
+        io::ErrorKind::ConnectionRefused,
+        format!("Discord: {}", message),
+    )
+}
+
+/// Every pipe that could be Discord's, opened, in the order Discord claims
+/// them.
+#[cfg(windows)]
+fn candidates() -> impl Iterator<Item = Box<dyn Pipe>> {
+    (0..MAX_PIPE_INDEX).filter_map(|i| {
+        let path = format!(r"\\.\pipe\discord-ipc-{}", i);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .ok()?;
+        Some(Box::new(file) as Box<dyn Pipe>)
+    })
+}
+
+/// Every socket that could be Discord's, opened. Besides the runtime and
+/// temp directories, Flatpak and Snap installs put theirs one level down.
+#[cfg(unix)]
+fn candidates() -> impl Iterator<Item = Box<dyn Pipe>> {
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+
+    let mut dirs: Vec<PathBuf> = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from)
+        .collect();
+    dirs.push(PathBuf::from("/tmp"));
+    let dirs: Vec<PathBuf> = dirs
+        .into_iter()
+        .flat_map(|d| {
+            [
+                d.clone(),
+                d.join("app/com.discordapp.Discord"),
+                d.join("snap.discord"),
+            ]
+        })
+        .collect();
+
+    dirs.into_iter().flat_map(|dir| {
+        (0..MAX_PIPE_INDEX).filter_map(move |i| {
+            let stream = UnixStream::connect(dir.join(format!("discord-ipc-{}", i))).ok()?;
+            stream.set_read_timeout(Some(REPLY_TIMEOUT)).ok()?;
+            stream.set_write_timeout(Some(REPLY_TIMEOUT)).ok()?;
+            Some(Box::new(stream) as Box<dyn Pipe>)
+        })
+    })
+}
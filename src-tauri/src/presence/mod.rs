@@ -0,0 +1,166 @@
+mod client;
+mod error;
+mod ipc;
+
+pub use error::PresenceError;
+
+use client::Client;
+use error::task;
+use ipc::Connection;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::sync::Mutex;
+use tauri::{Manager, Runtime};
+
+/// Discord's limits on the activity text fields.
+const MIN_TEXT_LEN: usize = 2;
+const MAX_TEXT_LEN: usize = 128;
+const MAX_IMAGE_KEY_LEN: usize = 256;
+
+/// The Discord Rich Presence connection, once initialized. Discord may come
+/// and go; the client thread reconnects on its own.
+#[derive(Default)]
+pub struct PresenceState {
+    client: Mutex<Option<Client>>,
+}
+
+/// What every presence command returns. `connected: false` means Discord
+/// isn't running (or went away); the latest activity is sent once it's back.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenceStatus {
+    pub connected: bool,
+}
+
+/// Connects to the local Discord client as application `app_id`. Calling it
+/// again with another id reconnects as that application.
+#[tauri::command]
+pub async fn init_presence(
+    app: tauri::AppHandle,
+    app_id: String,
+) -> Result<PresenceStatus, PresenceError> {
+    let app_id = app_id.trim().to_string();
+    if app_id.is_empty() || !app_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(PresenceError::InvalidAppId(app_id));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<PresenceState>();
+        let mut current = state.client.lock().unwrap();
+        if let Some(client) = current.as_ref().filter(|c| c.app_id == app_id) {
+            return Ok(PresenceStatus {
+                connected: client.connected(),
+            });
+        }
+        if let Some(old) = current.take() {
+            old.stop();
+        }
+        let first = Connection::open(&app_id).ok();
+        let connected = first.is_some();
+        *current = Some(Client::spawn(app_id, first)?);
+        Ok(PresenceStatus { connected })
+    })
+    .await
+    .map_err(task)?
+}
+
+/// Shows `details` over `state` (such as "Deathmatch on Warehouse" over
+/// "In match"), with the map's art asset, a party size and an elapsed timer
+/// from `start_timestamp` (Unix milliseconds, as from `Date.now()`). Every
+/// field is optional.
+#[tauri::command]
+pub async fn update_presence(
+    app: tauri::AppHandle,
+    state: Option<String>,
+    details: Option<String>,
+    map_image_key: Option<String>,
+    party_size: Option<u32>,
+    party_max: Option<u32>,
+    start_timestamp: Option<i64>,
+) -> Result<PresenceStatus, PresenceError> {
+    let party = match (party_size, party_max) {
+        (Some(size), Some(max)) => Some((size, max)),
+        (None, None) => None,
+        _ => {
+            return Err(PresenceError::InvalidActivity(
+                "party size and party max must be set together".to_string(),
+            ))
+        }
+    };
+    let activity = activity(state, details, map_image_key, party, start_timestamp)?;
+    set(app, Some(activity)).await
+}
+
+#[tauri::command]
+pub async fn clear_presence(app: tauri::AppHandle) -> Result<PresenceStatus, PresenceError> {
+    set(app, None).await
+}
+
+async fn set(
+    app: tauri::AppHandle,
+    activity: Option<Value>,
+) -> Result<PresenceStatus, PresenceError> {
+    // Waits for the client thread, which may be mid-ping.
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<PresenceState>();
+        let client = state.client.lock().unwrap();
+        let client = client.as_ref().ok_or(PresenceError::NotInitialized)?;
+        Ok(PresenceStatus {
+            connected: client.set(activity),
+        })
+    })
+    .await
+    .map_err(task)?
+}
+
+fn activity(
+    state: Option<String>,
+    details: Option<String>,
+    map_image_key: Option<String>,
+    party: Option<(u32, u32)>,
+    start_timestamp: Option<i64>,
+) -> Result<Value, PresenceError> {
+    let invalid = |reason: String| Err(PresenceError::InvalidActivity(reason));
+    let mut activity = Map::new();
+    for (field, value) in [("state", state), ("details", details)] {
+        let Some(value) = value else { continue };
+        let len = value.trim().chars().count();
+        if !(MIN_TEXT_LEN..=MAX_TEXT_LEN).contains(&len) {
+            return invalid(format!(
+                "{} must be {} to {} characters",
+                field, MIN_TEXT_LEN, MAX_TEXT_LEN
+            ));
+        }
+        activity.insert(field.to_string(), Value::String(value.trim().to_string()));
+    }
+    if let Some(key) = map_image_key {
+        if key.is_empty() || key.len() > MAX_IMAGE_KEY_LEN {
+            return invalid(format!(
+                "map image key must be 1 to {} bytes",
+                MAX_IMAGE_KEY_LEN
+            ));
+        }
+        activity.insert("assets".to_string(), json!({ "large_image": key }));
+    }
+    if let Some((size, max)) = party {
+        if size == 0 || size > max {
+            return invalid(format!("party of {} out of {} is not valid", size, max));
+        }
+        activity.insert("party".to_string(), json!({ "size": [size, max] }));
+    }
+    if let Some(start) = start_timestamp {
+        if start < 0 {
+            return invalid("start timestamp must not be negative".to_string());
+        }
+        activity.insert("timestamps".to_string(), json!({ "start": start }));
+    }
+    Ok(Value::Object(activity))
+}
+
+/// Clears the activity and disconnects, so the status doesn't outlive the
+/// game. Called on exit.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let client = app.state::<PresenceState>().client.lock().unwrap().take();
+    if let Some(client) = client {
+        client.stop();
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the profile commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No profile with id {0:?}")]
+    NotFound(String),
+
+    #[error("Invalid profile name {name:?}: {reason}")]
+    InvalidName { name: String, reason: String },
+}
+
+impl ProfileError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        ProfileError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn invalid_name(name: &str, reason: impl Into<String>) -> Self {
+        ProfileError::InvalidName {
+            name: name.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProfileError::NoDataDir(_) => "noDataDir",
+            ProfileError::Io { .. } => "io",
+            ProfileError::NotFound(_) => "notFound",
+            ProfileError::InvalidName { .. } => "invalidName",
+        }
+    }
+}
+
+impl Serialize for ProfileError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ProfileError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,213 @@
+mod error;
+mod model;
+mod store;
+
+pub use error::ProfileError;
+pub use model::{ProfileEntry, ProfileInfo};
+
+use model::{is_valid_id, validate_name, DEFAULT_PROFILE_ID, DEFAULT_PROFILE_NAME};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use store::profile_dir;
+use tauri::{Emitter, Manager, Runtime, State};
+
+pub const CHANGED_EVENT: &str = "profile-changed";
+
+/// The profile whose directory settings, keybindings and saves resolve to.
+/// Loaded lazily from `profiles/active-profile.json` on first use.
+#[derive(Default)]
+pub struct ActiveProfile {
+    current: Mutex<Option<ProfileInfo>>,
+}
+
+/// Directory of the active profile, e.g. `AppData/profiles/default`.
+pub fn active_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, ProfileError> {
+    let root = profiles_root(app)?;
+    let active = app.state::<ActiveProfile>();
+    let mut current = active.current.lock().unwrap();
+    let info = resolve_active(app, &root, &mut current)?;
+    Ok(profile_dir(&root, &info.id))
+}
+
+fn profiles_root<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, ProfileError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(store::PROFILES_DIR))
+        .map_err(|e| ProfileError::NoDataDir(e.to_string()))
+}
+
+fn resolve_active<'a, R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    root: &Path,
+    current: &'a mut Option<ProfileInfo>,
+) -> Result<&'a ProfileInfo, ProfileError> {
+    if current.is_none() {
+        let persisted = store::read_active_id(root)
+            .filter(|id| is_valid_id(id))
+            .and_then(|id| store::read_info(root, &id).ok());
+        let info = match persisted {
+            Some(info) => info,
+            None => activate_default(app, root)?,
+        };
+        *current = Some(info);
+    }
+    Ok(current.as_ref().expect("just resolved"))
+}
+
+/// Makes the default profile active, creating it if needed. The first time
+/// it is created, data from before profiles existed is moved into it.
+fn activate_default<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    root: &Path,
+) -> Result<ProfileInfo, ProfileError> {
+    let info = match store::read_info(root, DEFAULT_PROFILE_ID) {
+        Ok(info) => info,
+        Err(ProfileError::NotFound(_)) => {
+            let info = store::create_with_id(
+                root,
+                DEFAULT_PROFILE_ID.to_string(),
+                DEFAULT_PROFILE_NAME.to_string(),
+            )?;
+            store::adopt_legacy(&profile_dir(root, &info.id), &legacy_items(app));
+            info
+        }
+        Err(e) => return Err(e),
+    };
+    store::write_active_id(root, &info.id)?;
+    Ok(info)
+}
+
+/// Where settings, keybindings and saves lived before profiles.
+fn legacy_items<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<(PathBuf, &'static str)> {
+    let mut items = Vec::new();
+    if let Ok(config) = app.path().app_config_dir() {
+        for name in ["settings.json", "keybindings.json", "keybinding-profiles"] {
+            items.push((config.join(name), name));
+        }
+    }
+    if let Ok(data) = app.path().app_data_dir() {
+        items.push((data.join("saves"), "saves"));
+    }
+    items
+}
+
+fn checked_id(id: &str) -> Result<&str, ProfileError> {
+    if is_valid_id(id) {
+        Ok(id)
+    } else {
+        Err(ProfileError::NotFound(id.to_string()))
+    }
+}
+
+/// Tells the frontend to reload everything profile-scoped, and points the
+/// settings watcher at the new profile's file.
+fn announce(app: &tauri::AppHandle, info: &ProfileInfo) {
+    crate::settings::start_watcher(app);
+    let _ = app.emit(CHANGED_EVENT, info.clone());
+}
+
+/// Creates a profile without switching to it. The display name is kept as
+/// typed; the directory id is a filesystem-safe slug of it.
+#[tauri::command]
+pub async fn create_profile(
+    app: tauri::AppHandle,
+    active: State<'_, ActiveProfile>,
+    name: String,
+) -> Result<ProfileInfo, ProfileError> {
+    let name = validate_name(&name)?;
+    let root = profiles_root(&app)?;
+    let _current = active.current.lock().unwrap();
+    store::create(&root, name)
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: tauri::AppHandle) -> Result<Vec<ProfileEntry>, ProfileError> {
+    let root = profiles_root(&app)?;
+    let active_id = {
+        let active = app.state::<ActiveProfile>();
+        let mut current = active.current.lock().unwrap();
+        resolve_active(&app, &root, &mut current)?.id.clone()
+    };
+    Ok(store::list(&root)?
+        .into_iter()
+        .map(|info| ProfileEntry {
+            active: info.id == active_id,
+            info,
+        })
+        .collect())
+}
+
+/// Makes `id` the active profile (persisted across launches) and emits
+/// `profile-changed`.
+#[tauri::command]
+pub async fn switch_profile(
+    app: tauri::AppHandle,
+    active: State<'_, ActiveProfile>,
+    id: String,
+) -> Result<ProfileInfo, ProfileError> {
+    let root = profiles_root(&app)?;
+    let info = {
+        let mut current = active.current.lock().unwrap();
+        let info = store::read_info(&root, checked_id(&id)?)?;
+        store::write_active_id(&root, &info.id)?;
+        *current = Some(info.clone());
+        info
+    };
+    announce(&app, &info);
+    Ok(info)
+}
+
+/// Changes the display name only; the directory id stays the same.
+#[tauri::command]
+pub async fn rename_profile(
+    app: tauri::AppHandle,
+    active: State<'_, ActiveProfile>,
+    id: String,
+    name: String,
+) -> Result<ProfileInfo, ProfileError> {
+    let name = validate_name(&name)?;
+    let root = profiles_root(&app)?;
+    let (info, is_active) = {
+        let mut current = active.current.lock().unwrap();
+        let mut info = store::read_info(&root, checked_id(&id)?)?;
+        info.name = name;
+        store::write_info(&root, &info)?;
+        let is_active = current.as_ref().is_some_and(|c| c.id == info.id);
+        if is_active {
+            *current = Some(info.clone());
+        }
+        (info, is_active)
+    };
+    if is_active {
+        let _ = app.emit(CHANGED_EVENT, info.clone());
+    }
+    Ok(info)
+}
+
+/// Deletes a profile and everything in it. Deleting the active profile
+/// switches to the default one, recreating it if it was the one deleted.
+/// Returns the profile that is active afterwards.
+#[tauri::command]
+pub async fn delete_profile(
+    app: tauri::AppHandle,
+    active: State<'_, ActiveProfile>,
+    id: String,
+) -> Result<ProfileInfo, ProfileError> {
+    let root = profiles_root(&app)?;
+    let (info, switched) = {
+        let mut current = active.current.lock().unwrap();
+        let active_id = resolve_active(&app, &root, &mut current)?.id.clone();
+        store::delete(&root, checked_id(&id)?)?;
+        if id == active_id {
+            let info = activate_default(&app, &root)?;
+            *current = Some(info.clone());
+            (info, true)
+        } else {
+            (current.clone().expect("resolved above"), false)
+        }
+    };
+    if switched {
+        announce(&app, &info);
+    }
+    Ok(info)
+}
@@ -0,0 +1,92 @@
+use super::ProfileError;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+pub const DEFAULT_PROFILE_NAME: &str = "Player";
+
+const MAX_NAME_CHARS: usize = 40;
+const MAX_ID_CHARS: usize = 32;
+
+/// Device names Windows refuses as file names, in any case and with any
+/// extension.
+const RESERVED_IDS: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Stored as `profile.json` in each profile directory. `id` doubles as the
+/// directory name and never changes; `name` is what the player typed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileInfo {
+    pub id: String,
+    pub name: String,
+    pub created_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileEntry {
+    #[serde(flatten)]
+    pub info: ProfileInfo,
+    pub active: bool,
+}
+
+/// Trims the display name and rejects empty, overlong or control-character
+/// names. Anything else is allowed; the id is derived separately.
+pub fn validate_name(name: &str) -> Result<String, ProfileError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(ProfileError::invalid_name(name, "name is empty"));
+    }
+    if trimmed.chars().count() > MAX_NAME_CHARS {
+        return Err(ProfileError::invalid_name(
+            name,
+            format!("name is longer than {} characters", MAX_NAME_CHARS),
+        ));
+    }
+    if trimmed.chars().any(char::is_control) {
+        return Err(ProfileError::invalid_name(
+            name,
+            "name contains control characters",
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Filesystem-safe directory name for a display name: lowercase ASCII
+/// letters and digits joined by `-`. The caller makes it unique.
+pub fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= MAX_ID_CHARS {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        "profile".to_string()
+    } else if RESERVED_IDS.contains(&slug) {
+        format!("{}-profile", slug)
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Whether `id` looks like something [`slug`] (plus a uniqueness suffix)
+/// produced; guards commands that take an id from the frontend against path
+/// tricks.
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_CHARS * 2
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !id.starts_with('-')
+}
@@ -0,0 +1,133 @@
+use super::model::{slug, ProfileInfo};
+use super::ProfileError;
+use crate::fs_atomic::write_atomic;
+use crate::time::now_ms;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const PROFILES_DIR: &str = "profiles";
+pub const INFO_FILE: &str = "profile.json";
+const ACTIVE_FILE: &str = "active-profile.json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActiveFile {
+    active_id: String,
+}
+
+pub fn profile_dir(root: &Path, id: &str) -> PathBuf {
+    root.join(id)
+}
+
+pub fn read_info(root: &Path, id: &str) -> Result<ProfileInfo, ProfileError> {
+    let path = profile_dir(root, id).join(INFO_FILE);
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ProfileError::NotFound(id.to_string()))
+        }
+        Err(e) => return Err(ProfileError::io(&path, e)),
+    };
+    let mut info: ProfileInfo = serde_json::from_slice(&bytes).map_err(|e| {
+        ProfileError::io(
+            &path,
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })?;
+    // The directory name is authoritative if the file was copied around.
+    info.id = id.to_string();
+    Ok(info)
+}
+
+pub fn write_info(root: &Path, info: &ProfileInfo) -> Result<(), ProfileError> {
+    let path = profile_dir(root, &info.id).join(INFO_FILE);
+    let json = serde_json::to_vec_pretty(info).expect("profile info serializes");
+    write_atomic(&path, &json).map_err(|e| ProfileError::io(&path, e))
+}
+
+/// Every profile directory with a readable `profile.json`, sorted by name.
+pub fn list(root: &Path) -> Result<Vec<ProfileInfo>, ProfileError> {
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ProfileError::io(root, e)),
+    };
+
+    let mut profiles: Vec<ProfileInfo> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.to_string();
+            read_info(root, &id).ok()
+        })
+        .collect();
+    profiles.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok(profiles)
+}
+
+/// Creates a profile directory for `name`, picking `slug(name)` or the first
+/// free `slug(name)-N`.
+pub fn create(root: &Path, name: String) -> Result<ProfileInfo, ProfileError> {
+    let base = slug(&name);
+    let id = std::iter::once(base.clone())
+        .chain((2..).map(|n| format!("{}-{}", base, n)))
+        .find(|id| !profile_dir(root, id).exists())
+        .expect("unbounded id candidates");
+    create_with_id(root, id, name)
+}
+
+pub fn create_with_id(root: &Path, id: String, name: String) -> Result<ProfileInfo, ProfileError> {
+    let dir = profile_dir(root, &id);
+    std::fs::create_dir_all(&dir).map_err(|e| ProfileError::io(&dir, e))?;
+    let info = ProfileInfo {
+        id,
+        name,
+        created_ms: now_ms(),
+    };
+    write_info(root, &info)?;
+    Ok(info)
+}
+
+pub fn delete(root: &Path, id: &str) -> Result<(), ProfileError> {
+    let dir = profile_dir(root, id);
+    if !dir.join(INFO_FILE).exists() {
+        return Err(ProfileError::NotFound(id.to_string()));
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| ProfileError::io(&dir, e))
+}
+
+pub fn read_active_id(root: &Path) -> Option<String> {
+    let bytes = std::fs::read(root.join(ACTIVE_FILE)).ok()?;
+    serde_json::from_slice::<ActiveFile>(&bytes)
+        .ok()
+        .map(|f| f.active_id)
+}
+
+pub fn write_active_id(root: &Path, id: &str) -> Result<(), ProfileError> {
+    let path = root.join(ACTIVE_FILE);
+    let json = serde_json::to_vec_pretty(&ActiveFile {
+        active_id: id.to_string(),
+    })
+    .expect("active profile serializes");
+    write_atomic(&path, &json).map_err(|e| ProfileError::io(&path, e))
+}
+
+/// Moves files from before profiles existed into `profile_dir`. Each item is
+/// `(old location, name inside the profile)`; missing sources and targets
+/// that already exist are skipped, and failures only cost the old data.
+pub fn adopt_legacy(profile_dir: &Path, items: &[(PathBuf, &str)]) {
+    for (source, name) in items {
+        let target = profile_dir.join(name);
+        if !source.exists() || target.exists() {
+            continue;
+        }
+        if let Err(e) = std::fs::rename(source, &target) {
+            log::warn!("could not move {:?} into profile: {}", source, e);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Deltas from the capture thread, summed until the flush thread takes
+//! them. Taking swaps each sum with zero, so nothing added in between is
+//! lost or counted twice.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+pub struct Accumulator {
+    dx: AtomicI64,
+    dy: AtomicI64,
+    paused: AtomicBool,
+    flush_interval_us: AtomicU64,
+}
+
+impl Accumulator {
+    pub fn new(flush_interval: Duration, paused: bool) -> Self {
+        Self {
+            dx: AtomicI64::new(0),
+            dy: AtomicI64::new(0),
+            paused: AtomicBool::new(paused),
+            flush_interval_us: AtomicU64::new(flush_interval.as_micros() as u64),
+        }
+    }
+
+    /// Dropped while paused: movement while the game is in the background
+    /// mustn't turn the view once it's back.
+    pub fn add(&self, dx: i64, dy: i64) {
+        if !self.paused.load(Ordering::Relaxed) {
+            self.dx.fetch_add(dx, Ordering::Relaxed);
+            self.dy.fetch_add(dy, Ordering::Relaxed);
+        }
+    }
+
+    pub fn take(&self) -> (i64, i64) {
+        (
+            self.dx.swap(0, Ordering::Relaxed),
+            self.dy.swap(0, Ordering::Relaxed),
+        )
+    }
+
+    /// Returns whether this changed anything.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        let changed = self.paused.swap(paused, Ordering::Relaxed) != paused;
+        if changed && paused {
+            self.take();
+        }
+        changed
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_micros(self.flush_interval_us.load(Ordering::Relaxed))
+    }
+
+    pub fn set_flush_interval(&self, interval: Duration) {
+        self.flush_interval_us
+            .store(interval.as_micros() as u64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the raw input commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum RawInputError {
+    /// Windows always has raw input.
+    #[cfg_attr(windows, allow(dead_code))]
+    #[error("Raw mouse input is unsupported on this platform: {0}")]
+    Unsupported(String),
+
+    #[error("Raw mouse capture is already running")]
+    AlreadyRunning,
+
+    #[error("Raw mouse capture is not running")]
+    NotRunning,
+
+    #[error("Flush interval {ms} ms is outside {min}..={max}")]
+    InvalidInterval { ms: u32, min: u32, max: u32 },
+
+    /// Only the Windows backend has startup steps of its own that can fail.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    #[error("Failed to start raw mouse capture: {0}")]
+    Capture(String),
+
+    #[error("Failed to start a raw input thread: {0}")]
+    Spawn(#[source] std::io::Error),
+}
+
+impl RawInputError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RawInputError::Unsupported(_) => "unsupported",
+            RawInputError::AlreadyRunning => "alreadyRunning",
+            RawInputError::NotRunning => "notRunning",
+            RawInputError::InvalidInterval { .. } => "invalidInterval",
+            RawInputError::Capture(_) => "capture",
+            RawInputError::Spawn(_) => "spawn",
+        }
+    }
+}
+
+impl Serialize for RawInputError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("RawInputError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
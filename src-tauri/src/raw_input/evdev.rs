@@ -0,0 +1,175 @@
+//! Linux: relative motion straight from every mouse's evdev node, below any
+//! acceleration the desktop applies. Needs read access to `/dev/input`,
+//! which many setups only give to the `input` group.
+
+use super::accumulator::Accumulator;
+use super::RawInputError;
+use libc::input_event;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const INPUT_DIR: &str = "/dev/input";
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const BTN_LEFT: u16 = 0x110;
+/// How long one wait for input lasts, which bounds how late a stop is seen.
+const POLL_TIMEOUT_MS: i32 = 50;
+const RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+const EVIOCGBIT_KEY: libc::Ioctl = libc::_IOR::<[u8; 96]>(b'E' as u32, 0x20 + EV_KEY as u32);
+const EVIOCGBIT_REL: libc::Ioctl = libc::_IOR::<[u8; 2]>(b'E' as u32, 0x20 + EV_REL as u32);
+
+fn bit(bits: &[u8], n: u16) -> bool {
+    bits.get(n as usize / 8)
+        .is_some_and(|b| b & (1 << (n % 8)) != 0)
+}
+
+struct Mouse {
+    path: PathBuf,
+    file: File,
+}
+
+/// Every readable node with relative X/Y and a left button. Nodes that
+/// can't be opened are skipped; `denied` reports whether any were.
+fn scan(open: &[Mouse], denied: &mut bool) -> Vec<Mouse> {
+    let Ok(dir) = std::fs::read_dir(INPUT_DIR) else {
+        return Vec::new();
+    };
+    let known: HashSet<&PathBuf> = open.iter().map(|m| &m.path).collect();
+    let mut found = Vec::new();
+    for entry in dir.flatten() {
+        let path = entry.path();
+        if !entry.file_name().to_string_lossy().starts_with("event") || known.contains(&path) {
+            continue;
+        }
+        let file = match OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                *denied |= e.kind() == ErrorKind::PermissionDenied;
+                continue;
+            }
+        };
+        let mut rel = [0u8; 2];
+        let mut keys = [0u8; 96];
+        // SAFETY: each buffer is the size its request names.
+        let ok = unsafe {
+            libc::ioctl(file.as_raw_fd(), EVIOCGBIT_REL, rel.as_mut_ptr()) >= 0
+                && libc::ioctl(file.as_raw_fd(), EVIOCGBIT_KEY, keys.as_mut_ptr()) >= 0
+        };
+        if ok && bit(&rel, REL_X) && bit(&rel, REL_Y) && bit(&keys, BTN_LEFT) {
+            found.push(Mouse { path, file });
+        }
+    }
+    found
+}
+
+pub struct Capture {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Capture {
+    pub fn start(accumulator: Arc<Accumulator>) -> Result<Self, RawInputError> {
+        let mut denied = false;
+        let mice = scan(&[], &mut denied);
+        if mice.is_empty() {
+            return Err(RawInputError::Unsupported(if denied {
+                "no readable mouse; raw input needs read access to /dev/input".to_string()
+            } else {
+                "no mouse found".to_string()
+            }));
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("raw-mouse".into())
+                .spawn(move || run(mice, &accumulator, &stop))
+                .map_err(RawInputError::Spawn)?
+        };
+        Ok(Self { stop, thread })
+    }
+
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.thread.join();
+    }
+}
+
+fn run(mut mice: Vec<Mouse>, accumulator: &Accumulator, stop: &AtomicBool) {
+    const SIZE: usize = std::mem::size_of::<input_event>();
+    let mut buf = [0u8; SIZE * 64];
+    let mut last_scan = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        if last_scan.elapsed() >= RESCAN_INTERVAL {
+            let added = scan(&mice, &mut false);
+            mice.extend(added);
+            last_scan = Instant::now();
+        }
+        let mut fds: Vec<libc::pollfd> = mice
+            .iter()
+            .map(|m| libc::pollfd {
+                fd: m.file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        // SAFETY: `fds` is a valid array of `fds.len()` pollfds.
+        let ready =
+            unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, POLL_TIMEOUT_MS) };
+        if ready <= 0 {
+            continue;
+        }
+
+        let mut gone = Vec::new();
+        for (i, mouse) in mice.iter_mut().enumerate() {
+            if fds[i].revents == 0 {
+                continue;
+            }
+            loop {
+                let n = match mouse.file.read(&mut buf) {
+                    Ok(n) if n > 0 => n,
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    // Unplugged.
+                    _ => {
+                        gone.push(i);
+                        break;
+                    }
+                };
+                let (mut dx, mut dy) = (0i64, 0i64);
+                for chunk in buf[..n].chunks_exact(SIZE) {
+                    // SAFETY: the kernel writes whole input_events, and any
+                    // bit pattern is a valid one.
+                    let event: input_event =
+                        unsafe { std::ptr::read_unaligned(chunk.as_ptr().cast()) };
+                    match (event.type_, event.code) {
+                        (EV_REL, REL_X) => dx += event.value as i64,
+                        (EV_REL, REL_Y) => dy += event.value as i64,
+                        _ => {}
+                    }
+                }
+                if dx != 0 || dy != 0 {
+                    accumulator.add(dx, dy);
+                }
+            }
+        }
+        for i in gone.into_iter().rev() {
+            mice.remove(i);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+mod accumulator;
+mod error;
+#[cfg(target_os = "linux")]
+mod evdev;
+#[cfg(windows)]
+mod windows;
+
+pub use error::RawInputError;
+
+use accumulator::Accumulator;
+#[cfg(target_os = "linux")]
+use evdev::Capture;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{Emitter, Manager, Runtime, State};
+#[cfg(windows)]
+use windows::Capture;
+
+pub const DELTA_EVENT: &str = "raw-mouse-delta";
+pub const PAUSED_EVENT: &str = "raw-mouse-paused";
+pub const RESUMED_EVENT: &str = "raw-mouse-resumed";
+
+pub const DEFAULT_FLUSH_INTERVAL_MS: u32 = 4;
+const MIN_FLUSH_INTERVAL_MS: u32 = 1;
+const MAX_FLUSH_INTERVAL_MS: u32 = 100;
+
+/// Raw mouse motion, bypassing the webview's pointer events. Captured while
+/// running, delivered in batches, and paused while the window is in the
+/// background.
+pub struct RawInputState {
+    focused: AtomicBool,
+    flush_interval_ms: AtomicU32,
+    running: Mutex<Option<Running>>,
+}
+
+impl Default for RawInputState {
+    fn default() -> Self {
+        Self {
+            focused: AtomicBool::new(true),
+            flush_interval_ms: AtomicU32::new(DEFAULT_FLUSH_INTERVAL_MS),
+            running: Mutex::new(None),
+        }
+    }
+}
+
+struct Running {
+    accumulator: Arc<Accumulator>,
+    capture: Capture,
+    stop_flush: mpsc::Sender<()>,
+    flusher: JoinHandle<()>,
+}
+
+impl Running {
+    fn stop(self) {
+        self.capture.stop();
+        drop(self.stop_flush);
+        let _ = self.flusher.join();
+    }
+}
+
+/// Counts as reported by the device, summed since the previous event.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawMouseDelta {
+    pub dx: i64,
+    pub dy: i64,
+}
+
+/// Starts capturing, emitting `raw-mouse-delta` once per flush interval
+/// with any motion since the last one. Fails with `unsupported` where
+/// there's no raw input to read.
+#[tauri::command]
+pub async fn start_raw_mouse_capture(
+    app: tauri::AppHandle,
+    raw_input: State<'_, RawInputState>,
+) -> Result<(), RawInputError> {
+    let mut running = raw_input.running.lock().unwrap();
+    if running.is_some() {
+        return Err(RawInputError::AlreadyRunning);
+    }
+    let interval = raw_input.flush_interval_ms.load(Ordering::Relaxed);
+    let accumulator = Arc::new(Accumulator::new(
+        Duration::from_millis(interval as u64),
+        !raw_input.focused.load(Ordering::Relaxed),
+    ));
+    let capture = start_capture(accumulator.clone())?;
+
+    let (stop_flush, stopped) = mpsc::channel();
+    let flushing = accumulator.clone();
+    let flusher = std::thread::Builder::new()
+        .name("raw-mouse-flush".into())
+        .spawn(move || flush(&app, &flushing, stopped));
+    let flusher = match flusher {
+        Ok(flusher) => flusher,
+        Err(e) => {
+            capture.stop();
+            return Err(RawInputError::Spawn(e));
+        }
+    };
+    *running = Some(Running {
+        accumulator,
+        capture,
+        stop_flush,
+        flusher,
+    });
+    Ok(())
+}
+
+#[cfg(any(windows, target_os = "linux"))]
+fn start_capture(accumulator: Arc<Accumulator>) -> Result<Capture, RawInputError> {
+    Capture::start(accumulator)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn start_capture(_accumulator: Arc<Accumulator>) -> Result<Capture, RawInputError> {
+    Err(RawInputError::Unsupported(
+        "only Windows and Linux provide raw mouse input".to_string(),
+    ))
+}
+
+/// Never constructed; keeps `Running` well-formed where capture can't start.
+#[cfg(not(any(windows, target_os = "linux")))]
+struct Capture;
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl Capture {
+    fn stop(self) {}
+}
+
+fn flush<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    accumulator: &Accumulator,
+    stopped: mpsc::Receiver<()>,
+) {
+    loop {
+        match stopped.recv_timeout(accumulator.flush_interval()) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => return,
+        }
+        let (dx, dy) = accumulator.take();
+        if dx != 0 || dy != 0 {
+            let _ = app.emit(DELTA_EVENT, RawMouseDelta { dx, dy });
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn stop_raw_mouse_capture(app: tauri::AppHandle) -> Result<(), RawInputError> {
+    let running = app.state::<RawInputState>().running.lock().unwrap().take();
+    let running = running.ok_or(RawInputError::NotRunning)?;
+    // Joining waits out the capture thread's current wait for input.
+    let _ = tauri::async_runtime::spawn_blocking(move || running.stop()).await;
+    Ok(())
+}
+
+/// Sets how often deltas are delivered, also while capturing. Motion in
+/// between is summed, never dropped.
+#[tauri::command]
+pub fn set_raw_mouse_flush_interval(
+    raw_input: State<'_, RawInputState>,
+    ms: u32,
+) -> Result<(), RawInputError> {
+    if !(MIN_FLUSH_INTERVAL_MS..=MAX_FLUSH_INTERVAL_MS).contains(&ms) {
+        return Err(RawInputError::InvalidInterval {
+            ms,
+            min: MIN_FLUSH_INTERVAL_MS,
+            max: MAX_FLUSH_INTERVAL_MS,
+        });
+    }
+    raw_input.flush_interval_ms.store(ms, Ordering::Relaxed);
+    if let Some(running) = raw_input.running.lock().unwrap().as_ref() {
+        running
+            .accumulator
+            .set_flush_interval(Duration::from_millis(ms as u64));
+    }
+    Ok(())
+}
+
+/// Pauses capture while the window is in the background, emitting
+/// `raw-mouse-paused` and `raw-mouse-resumed` as it changes.
+pub fn set_focused<R: Runtime>(app: &tauri::AppHandle<R>, focused: bool) {
+    let raw_input = app.state::<RawInputState>();
+    raw_input.focused.store(focused, Ordering::Relaxed);
+    let running = raw_input.running.lock().unwrap();
+    let Some(running) = running.as_ref() else {
+        return;
+    };
+    if running.accumulator.set_paused(!focused) {
+        let event = if focused { RESUMED_EVENT } else { PAUSED_EVENT };
+        let _ = app.emit(event, ());
+    }
+}
+
+/// Stops capturing. Called on exit.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let running = app.state::<RawInputState>().running.lock().unwrap().take();
+    if let Some(running) = running {
+        running.stop();
+    }
+}
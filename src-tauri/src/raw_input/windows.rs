@@ -0,0 +1,279 @@
+//! Windows Raw Input: a message-only window registered for mouse input
+//! with `RIDEV_INPUTSINK`, so it receives motion whichever window has
+//! focus. Its thread does nothing but pump messages.
+
+use super::accumulator::Accumulator;
+use super::RawInputError;
+use std::ffi::c_void;
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+type Hwnd = *mut c_void;
+
+const HWND_MESSAGE: isize = -3;
+const WM_QUIT: u32 = 0x0012;
+const WM_INPUT: u32 = 0x00ff;
+const RID_INPUT: u32 = 0x1000_0003;
+const RIM_TYPEMOUSE: u32 = 0;
+const RIDEV_REMOVE: u32 = 0x0001;
+const RIDEV_INPUTSINK: u32 = 0x0100;
+const MOUSE_MOVE_ABSOLUTE: u16 = 0x0001;
+const HID_USAGE_PAGE_GENERIC: u16 = 0x01;
+const HID_USAGE_GENERIC_MOUSE: u16 = 0x02;
+
+type WndProc = unsafe extern "system" fn(Hwnd, u32, usize, isize) -> isize;
+
+#[repr(C)]
+struct WndClassExW {
+    size: u32,
+    style: u32,
+    wnd_proc: WndProc,
+    cls_extra: i32,
+    wnd_extra: i32,
+    instance: *mut c_void,
+    icon: *mut c_void,
+    cursor: *mut c_void,
+    background: *mut c_void,
+    menu_name: *const u16,
+    class_name: *const u16,
+    icon_small: *mut c_void,
+}
+
+#[repr(C)]
+struct Msg {
+    hwnd: Hwnd,
+    message: u32,
+    w_param: usize,
+    l_param: isize,
+    time: u32,
+    pt: [i32; 2],
+}
+
+#[repr(C)]
+struct RawInputDevice {
+    usage_page: u16,
+    usage: u16,
+    flags: u32,
+    target: Hwnd,
+}
+
+#[repr(C)]
+struct RawInputHeader {
+    kind: u32,
+    size: u32,
+    device: *mut c_void,
+    w_param: usize,
+}
+
+#[repr(C)]
+struct RawMouse {
+    flags: u16,
+    buttons: u32,
+    raw_buttons: u32,
+    last_x: i32,
+    last_y: i32,
+    extra_information: u32,
+}
+
+#[repr(C)]
+struct RawInputMouse {
+    header: RawInputHeader,
+    mouse: RawMouse,
+}
+
+#[link(name = "user32")]
+extern "system" {
+    fn RegisterClassExW(class: *const WndClassExW) -> u16;
+    fn CreateWindowExW(
+        ex_style: u32,
+        class_name: *const u16,
+        window_name: *const u16,
+        style: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        parent: Hwnd,
+        menu: *mut c_void,
+        instance: *mut c_void,
+        param: *mut c_void,
+    ) -> Hwnd;
+    fn DestroyWindow(hwnd: Hwnd) -> i32;
+    fn DefWindowProcW(hwnd: Hwnd, msg: u32, w_param: usize, l_param: isize) -> isize;
+    fn GetMessageW(msg: *mut Msg, hwnd: Hwnd, min: u32, max: u32) -> i32;
+    fn DispatchMessageW(msg: *const Msg) -> isize;
+    fn PostThreadMessageW(thread_id: u32, msg: u32, w_param: usize, l_param: isize) -> i32;
+    fn RegisterRawInputDevices(devices: *const RawInputDevice, count: u32, size: u32) -> i32;
+    fn GetRawInputData(
+        raw_input: *mut c_void,
+        command: u32,
+        data: *mut c_void,
+        size: *mut u32,
+        header_size: u32,
+    ) -> u32;
+}
+
+// The flush thread sleeps a few milliseconds at a time, which needs a
+// finer system timer than the default 15.6 ms.
+#[link(name = "winmm")]
+extern "system" {
+    fn timeBeginPeriod(period: u32) -> u32;
+    fn timeEndPeriod(period: u32) -> u32;
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+    fn GetCurrentThreadId() -> u32;
+    fn GetLastError() -> u32;
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+pub struct Capture {
+    thread_id: u32,
+    thread: JoinHandle<()>,
+}
+
+impl Capture {
+    pub fn start(accumulator: Arc<Accumulator>) -> Result<Self, RawInputError> {
+        let (ready, started) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("raw-mouse".into())
+            .spawn(move || {
+                // SAFETY: the window and its registration live and die on
+                // this thread.
+                let hwnd = match unsafe { create_window() } {
+                    Ok(hwnd) => hwnd,
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                        return;
+                    }
+                };
+                // SAFETY: no preconditions.
+                let _ = ready.send(Ok(unsafe { GetCurrentThreadId() }));
+                // SAFETY: `hwnd` is this thread's window.
+                unsafe { pump(hwnd, &accumulator) };
+            })
+            .map_err(RawInputError::Spawn)?;
+        match started.recv() {
+            Ok(Ok(thread_id)) => {
+                // SAFETY: paired with timeEndPeriod in `stop`.
+                unsafe { timeBeginPeriod(1) };
+                Ok(Self { thread_id, thread })
+            }
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err(RawInputError::Capture(
+                "the capture thread exited during startup".to_string(),
+            )),
+        }
+    }
+
+    pub fn stop(self) {
+        // SAFETY: posting to a thread id is always safe; a gone thread just
+        // makes it fail.
+        unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+        let _ = self.thread.join();
+        // SAFETY: paired with the timeBeginPeriod in `start`.
+        unsafe { timeEndPeriod(1) };
+    }
+}
+
+unsafe fn create_window() -> Result<Hwnd, RawInputError> {
+    let class_name = wide("FpsGameRawMouse");
+    let instance = GetModuleHandleW(std::ptr::null());
+    let class = WndClassExW {
+        size: std::mem::size_of::<WndClassExW>() as u32,
+        style: 0,
+        wnd_proc: DefWindowProcW,
+        cls_extra: 0,
+        wnd_extra: 0,
+        instance,
+        icon: std::ptr::null_mut(),
+        cursor: std::ptr::null_mut(),
+        background: std::ptr::null_mut(),
+        menu_name: std::ptr::null(),
+        class_name: class_name.as_ptr(),
+        icon_small: std::ptr::null_mut(),
+    };
+    // Fails harmlessly when an earlier capture already registered it.
+    RegisterClassExW(&class);
+    let hwnd = CreateWindowExW(
+        0,
+        class_name.as_ptr(),
+        class_name.as_ptr(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE as Hwnd,
+        std::ptr::null_mut(),
+        instance,
+        std::ptr::null_mut(),
+    );
+    if hwnd.is_null() {
+        return Err(RawInputError::Capture(format!(
+            "CreateWindowExW failed with error {}",
+            GetLastError()
+        )));
+    }
+    if !register(hwnd, RIDEV_INPUTSINK) {
+        let error = GetLastError();
+        DestroyWindow(hwnd);
+        return Err(RawInputError::Capture(format!(
+            "RegisterRawInputDevices failed with error {}",
+            error
+        )));
+    }
+    Ok(hwnd)
+}
+
+unsafe fn register(hwnd: Hwnd, flags: u32) -> bool {
+    let device = RawInputDevice {
+        usage_page: HID_USAGE_PAGE_GENERIC,
+        usage: HID_USAGE_GENERIC_MOUSE,
+        flags,
+        target: hwnd,
+    };
+    RegisterRawInputDevices(&device, 1, std::mem::size_of::<RawInputDevice>() as u32) != 0
+}
+
+/// Runs until `WM_QUIT`, then unregisters and destroys the window.
+unsafe fn pump(hwnd: Hwnd, accumulator: &Accumulator) {
+    let mut msg: Msg = std::mem::zeroed();
+    while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+        if msg.message == WM_INPUT {
+            read_input(msg.l_param, accumulator);
+        }
+        // Lets DefWindowProc release the input.
+        DispatchMessageW(&msg);
+    }
+    register(std::ptr::null_mut(), RIDEV_REMOVE);
+    DestroyWindow(hwnd);
+}
+
+unsafe fn read_input(handle: isize, accumulator: &Accumulator) {
+    let mut data: RawInputMouse = std::mem::zeroed();
+    let mut size = std::mem::size_of::<RawInputMouse>() as u32;
+    let read = GetRawInputData(
+        handle as *mut c_void,
+        RID_INPUT,
+        (&mut data as *mut RawInputMouse).cast(),
+        &mut size,
+        std::mem::size_of::<RawInputHeader>() as u32,
+    );
+    if read == u32::MAX || data.header.kind != RIM_TYPEMOUSE {
+        return;
+    }
+    // Absolute positions come from tablets and remote desktop sessions,
+    // and aren't motion.
+    if data.mouse.flags & MOUSE_MOVE_ABSOLUTE == 0 {
+        accumulator.add(data.mouse.last_x as i64, data.mouse.last_y as i64);
+    }
+}
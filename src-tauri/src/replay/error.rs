@@ -0,0 +1,90 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the replay commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No replay with id {0:?}")]
+    NotFound(String),
+
+    #[error("A replay is already being recorded")]
+    AlreadyRecording,
+
+    #[error("No replay is being recorded")]
+    NotRecording,
+
+    #[error("Frame for tick {tick} arrived after tick {last}")]
+    OutOfOrder { tick: u32, last: u32 },
+
+    #[error("Invalid frame at tick {tick}: {reason}")]
+    InvalidFrame { tick: u32, reason: String },
+
+    #[error("Invalid replay metadata: {0}")]
+    InvalidMetadata(String),
+
+    #[error("Replay {id:?} is damaged: {reason}")]
+    Corrupted { id: String, reason: String },
+
+    #[error("Replay {id:?} uses format version {found}; this build reads up to {supported}")]
+    NewerVersion {
+        id: String,
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl ReplayError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        ReplayError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        ReplayError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub(crate) fn corrupted(id: &str, reason: impl ToString) -> Self {
+        ReplayError::Corrupted {
+            id: id.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReplayError::NoDataDir(_) => "noDataDir",
+            ReplayError::Io { .. } => "io",
+            ReplayError::NotFound(_) => "notFound",
+            ReplayError::AlreadyRecording => "alreadyRecording",
+            ReplayError::NotRecording => "notRecording",
+            ReplayError::OutOfOrder { .. } => "outOfOrder",
+            ReplayError::InvalidFrame { .. } => "invalidFrame",
+            ReplayError::InvalidMetadata(_) => "invalidMetadata",
+            ReplayError::Corrupted { .. } => "corrupted",
+            ReplayError::NewerVersion { .. } => "newerVersion",
+        }
+    }
+}
+
+impl Serialize for ReplayError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ReplayError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
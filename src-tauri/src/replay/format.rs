@@ -0,0 +1,348 @@
+//! On-disk replay container.
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! magic    b"FRPL"
+//! u32      format version (1)
+//! u32      header length
+//! u32      header CRC-32
+//! header   JSON `ReplayHeader`
+//! chunk*   u32 frame count, u32 payload length, u32 payload CRC-32, payload
+//! end      a chunk with all three fields zero
+//! ```
+//!
+//! Each frame in a payload is `u32 tick, u32 buttons, f32 yaw delta,
+//! f32 pitch delta, u8 flags` followed by three `f32` position components
+//! when bit 0 of the flags is set.
+//!
+//! Chunks are appended as recording goes, so a file cut short by a crash
+//! reads back up to its last whole chunk and simply lacks the end marker.
+
+use super::model::{InputFrame, ReplayHeader};
+use super::ReplayError;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+pub const REPLAY_MAGIC: &[u8; 4] = b"FRPL";
+pub const REPLAY_VERSION: u32 = 1;
+
+pub const END_MARKER: [u8; CHUNK_PREFIX_LEN] = [0; CHUNK_PREFIX_LEN];
+
+const CHUNK_PREFIX_LEN: usize = 12;
+const MAX_HEADER_LEN: u32 = 64 * 1024;
+/// Far above what the recorder writes per chunk; bigger means a damaged prefix.
+const MAX_PAYLOAD_LEN: u32 = 16 * 1024 * 1024;
+const MIN_FRAME_LEN: u32 = 17;
+const HAS_POSITION: u8 = 1;
+
+pub fn encode_header(header: &ReplayHeader) -> Vec<u8> {
+    let json = serde_json::to_vec(header).expect("replay header serialize");
+    let mut out = Vec::with_capacity(16 + json.len());
+    out.extend_from_slice(REPLAY_MAGIC);
+    out.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+    out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32fast::hash(&json).to_le_bytes());
+    out.extend_from_slice(&json);
+    out
+}
+
+pub fn encode_frame(out: &mut Vec<u8>, frame: &InputFrame) {
+    out.extend_from_slice(&frame.tick.to_le_bytes());
+    out.extend_from_slice(&frame.buttons.to_le_bytes());
+    out.extend_from_slice(&frame.yaw_delta.to_le_bytes());
+    out.extend_from_slice(&frame.pitch_delta.to_le_bytes());
+    match frame.position {
+        Some(position) => {
+            out.push(HAS_POSITION);
+            for v in position {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        None => out.push(0),
+    }
+}
+
+/// Prefixes an encoded payload of `frame_count` frames, ready to append.
+pub fn encode_chunk(frame_count: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(CHUNK_PREFIX_LEN + payload.len());
+    out.extend_from_slice(&frame_count.to_le_bytes());
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Reads a replay chunk by chunk, so even long demos are never fully in
+/// memory.
+pub struct ReplayReader {
+    id: String,
+    reader: BufReader<File>,
+    pub header: ReplayHeader,
+    pub version: u32,
+    pub file_size: u64,
+    pos: u64,
+    /// Set once the end marker has been read.
+    pub complete: bool,
+    finished: bool,
+}
+
+struct ChunkPrefix {
+    frame_count: u32,
+    payload_len: u32,
+    payload_crc: u32,
+}
+
+impl ReplayReader {
+    pub fn open(path: &Path, id: &str) -> Result<Self, ReplayError> {
+        let file = File::open(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                ReplayError::NotFound(id.to_string())
+            } else {
+                ReplayError::io(path, e)
+            }
+        })?;
+        let file_size = file.metadata().map_err(|e| ReplayError::io(path, e))?.len();
+        let mut reader = BufReader::new(file);
+        let truncated = |_| ReplayError::corrupted(id, "file is truncated");
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).map_err(truncated)?;
+        if &magic != REPLAY_MAGIC {
+            return Err(ReplayError::corrupted(id, "not a replay file"));
+        }
+        // Checked before the rest of the header, which a newer format may change.
+        let version = read_u32(&mut reader).map_err(truncated)?;
+        if version > REPLAY_VERSION {
+            return Err(ReplayError::NewerVersion {
+                id: id.to_string(),
+                found: version,
+                supported: REPLAY_VERSION,
+            });
+        }
+        if version == 0 {
+            return Err(ReplayError::corrupted(id, "invalid format version 0"));
+        }
+
+        let header_len = read_u32(&mut reader).map_err(truncated)?;
+        let header_crc = read_u32(&mut reader).map_err(truncated)?;
+        if header_len > MAX_HEADER_LEN {
+            return Err(ReplayError::corrupted(id, "header length out of range"));
+        }
+        let mut json = vec![0u8; header_len as usize];
+        reader.read_exact(&mut json).map_err(truncated)?;
+        if crc32fast::hash(&json) != header_crc {
+            return Err(ReplayError::corrupted(id, "header checksum mismatch"));
+        }
+        let header = serde_json::from_slice(&json).map_err(|e| ReplayError::corrupted(id, e))?;
+
+        Ok(Self {
+            id: id.to_string(),
+            reader,
+            header,
+            version,
+            file_size,
+            pos: 16 + header_len as u64,
+            complete: false,
+            finished: false,
+        })
+    }
+
+    /// The next chunk's frames, or `None` at the end marker or at a torn tail.
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<InputFrame>>, ReplayError> {
+        let Some(prefix) = self.read_prefix()? else {
+            return Ok(None);
+        };
+        let mut payload = vec![0u8; prefix.payload_len as usize];
+        if self.reader.read_exact(&mut payload).is_err()
+            || crc32fast::hash(&payload) != prefix.payload_crc
+        {
+            // Most likely the chunk being written when the game died.
+            self.finished = true;
+            return Ok(None);
+        }
+        self.pos += prefix.payload_len as u64;
+        decode_frames(&payload, prefix.frame_count)
+            .map(Some)
+            .ok_or_else(|| ReplayError::corrupted(&self.id, "chunk does not match its frame count"))
+    }
+
+    /// Like [`next_chunk`](Self::next_chunk), but only returns the frame
+    /// count, seeking past the payload without reading or verifying it.
+    pub fn skip_chunk(&mut self) -> Result<Option<u32>, ReplayError> {
+        let Some(prefix) = self.read_prefix()? else {
+            return Ok(None);
+        };
+        self.reader
+            .seek_relative(prefix.payload_len as i64)
+            .map_err(|e| ReplayError::corrupted(&self.id, e))?;
+        self.pos += prefix.payload_len as u64;
+        Ok(Some(prefix.frame_count))
+    }
+
+    fn read_prefix(&mut self) -> Result<Option<ChunkPrefix>, ReplayError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let mut raw = [0u8; CHUNK_PREFIX_LEN];
+        if self.reader.read_exact(&mut raw).is_err() {
+            self.finished = true;
+            return Ok(None);
+        }
+        if raw == END_MARKER {
+            self.complete = true;
+            self.finished = true;
+            return Ok(None);
+        }
+        let field = |i: usize| u32::from_le_bytes(raw[i..i + 4].try_into().unwrap());
+        let prefix = ChunkPrefix {
+            frame_count: field(0),
+            payload_len: field(4),
+            payload_crc: field(8),
+        };
+        self.pos += CHUNK_PREFIX_LEN as u64;
+
+        let remaining = self.file_size.saturating_sub(self.pos);
+        if prefix.payload_len > MAX_PAYLOAD_LEN || prefix.payload_len as u64 > remaining {
+            self.finished = true;
+            return Ok(None);
+        }
+        if (prefix.frame_count as u64) * (MIN_FRAME_LEN as u64) > prefix.payload_len as u64 {
+            return Err(ReplayError::corrupted(
+                &self.id,
+                "chunk frame count out of range",
+            ));
+        }
+        Ok(Some(prefix))
+    }
+}
+
+fn decode_frames(mut payload: &[u8], frame_count: u32) -> Option<Vec<InputFrame>> {
+    let mut frames = Vec::with_capacity(frame_count as usize);
+    for _ in 0..frame_count {
+        let tick = take_u32(&mut payload)?;
+        let buttons = take_u32(&mut payload)?;
+        let yaw_delta = f32::from_bits(take_u32(&mut payload)?);
+        let pitch_delta = f32::from_bits(take_u32(&mut payload)?);
+        let (&flags, rest) = payload.split_first()?;
+        payload = rest;
+        let position = if flags & HAS_POSITION != 0 {
+            Some([
+                f32::from_bits(take_u32(&mut payload)?),
+                f32::from_bits(take_u32(&mut payload)?),
+                f32::from_bits(take_u32(&mut payload)?),
+            ])
+        } else {
+            None
+        };
+        frames.push(InputFrame {
+            tick,
+            buttons,
+            yaw_delta,
+            pitch_delta,
+            position,
+        });
+    }
+    payload.is_empty().then_some(frames)
+}
+
+fn take_u32(buf: &mut &[u8]) -> Option<u32> {
+    let (head, rest) = buf.split_first_chunk::<4>()?;
+    *buf = rest;
+    Some(u32::from_le_bytes(*head))
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{sample_frames as frames, sample_header as header};
+    use std::io::Write;
+
+    fn write_replay(dir: &Path, chunks: &[&[InputFrame]], end: bool) -> std::path::PathBuf {
+        let path = dir.join("1.replay");
+        let mut file = File::create(&path).unwrap();
+        file.write_all(&encode_header(&header())).unwrap();
+        for chunk in chunks {
+            let mut payload = Vec::new();
+            for frame in *chunk {
+                encode_frame(&mut payload, frame);
+            }
+            file.write_all(&encode_chunk(chunk.len() as u32, &payload))
+                .unwrap();
+        }
+        if end {
+            file.write_all(&END_MARKER).unwrap();
+        }
+        path
+    }
+
+    fn open_error(bytes: &[u8]) -> ReplayError {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("1.replay");
+        std::fs::write(&path, bytes).unwrap();
+        ReplayReader::open(&path, "1")
+            .err()
+            .expect("should not open")
+    }
+
+    #[test]
+    fn stops_at_a_torn_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let all = frames(30);
+        let path = write_replay(dir.path(), &[&all[..10], &all[10..]], false);
+        // Cut the last chunk short.
+        let len = std::fs::metadata(&path).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 5)
+            .unwrap();
+
+        let mut reader = ReplayReader::open(&path, "1").unwrap();
+        assert_eq!(reader.next_chunk().unwrap().unwrap(), &all[..10]);
+        assert!(reader.next_chunk().unwrap().is_none());
+        assert!(!reader.complete);
+    }
+
+    #[test]
+    fn skipping_counts_without_decoding() {
+        let dir = tempfile::tempdir().unwrap();
+        let all = frames(30);
+        let path = write_replay(dir.path(), &[&all[..10], &all[10..]], true);
+        let mut reader = ReplayReader::open(&path, "1").unwrap();
+        assert_eq!(reader.skip_chunk().unwrap(), Some(10));
+        assert_eq!(reader.skip_chunk().unwrap(), Some(20));
+        assert_eq!(reader.skip_chunk().unwrap(), None);
+        assert!(reader.complete);
+    }
+
+    #[test]
+    fn newer_versions_fail_cleanly() {
+        let mut bytes = encode_header(&header());
+        bytes[4..8].copy_from_slice(&(REPLAY_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            open_error(&bytes),
+            ReplayError::NewerVersion { found, .. } if found == REPLAY_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_damaged_headers() {
+        let good = encode_header(&header());
+        let mut bad_magic = good.clone();
+        bad_magic[..4].copy_from_slice(b"RIFF");
+        let mut bad_crc = good.clone();
+        *bad_crc.last_mut().unwrap() ^= 0xff;
+        for bytes in [&bad_magic[..], &bad_crc[..], &good[..10]] {
+            assert!(matches!(open_error(bytes), ReplayError::Corrupted { .. }));
+        }
+    }
+}
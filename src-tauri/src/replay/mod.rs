@@ -0,0 +1,166 @@
+mod error;
+mod format;
+mod model;
+mod recorder;
+mod store;
+
+pub use error::ReplayError;
+pub use model::{InputFrame, ReplayChunk, ReplayHeader, ReplayInfo, ReplayListing, ReplayMetadata};
+pub use recorder::ReplayRecorder;
+
+use crate::time::now_ms;
+use format::ReplayReader;
+use recorder::Recording;
+use tauri::ipc::Channel;
+use tauri::{Manager, Runtime, State};
+
+/// Starts writing a new replay under `AppData/replays`. Returns its header,
+/// including the generated id.
+#[tauri::command]
+pub async fn start_replay_recording(
+    app: tauri::AppHandle,
+    recorder: State<'_, ReplayRecorder>,
+    metadata: ReplayMetadata,
+) -> Result<ReplayHeader, ReplayError> {
+    let header = ReplayHeader {
+        id: String::new(),
+        started_at_ms: now_ms(),
+        metadata: metadata.validated()?,
+    };
+    let dir = store::replays_dir(&app)?;
+
+    let mut active = recorder.active.lock().unwrap();
+    if active.is_some() {
+        return Err(ReplayError::AlreadyRecording);
+    }
+    let recording = Recording::create(&dir, header.clone())?;
+    let header = ReplayHeader {
+        id: recording.id.clone(),
+        ..header
+    };
+    *active = Some(recording);
+    Ok(header)
+}
+
+/// Adds frames to the current recording. Ticks must keep increasing across
+/// calls; a batch with an out-of-order frame is rejected whole.
+#[tauri::command]
+pub async fn append_replay_frames(
+    recorder: State<'_, ReplayRecorder>,
+    frames: Vec<InputFrame>,
+) -> Result<(), ReplayError> {
+    recorder
+        .active
+        .lock()
+        .unwrap()
+        .as_mut()
+        .ok_or(ReplayError::NotRecording)?
+        .append(&frames)
+}
+
+/// Finishes the current recording and returns its final info.
+#[tauri::command]
+pub async fn stop_replay_recording(
+    recorder: State<'_, ReplayRecorder>,
+) -> Result<ReplayInfo, ReplayError> {
+    let recording = recorder
+        .active
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or(ReplayError::NotRecording)?;
+    let id = recording.id.clone();
+    let path = recording.finish()?;
+    let task_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || store::info(&path, &id))
+        .await
+        .map_err(|e| ReplayError::task(task_path, e))?
+}
+
+/// Every replay on disk, newest first. Damaged files are listed last so the
+/// UI can offer to delete them.
+#[tauri::command]
+pub async fn list_replays(app: tauri::AppHandle) -> Result<Vec<ReplayListing>, ReplayError> {
+    let dir = store::replays_dir(&app)?;
+    let task_dir = dir.clone();
+    tauri::async_runtime::spawn_blocking(move || store::list(&dir))
+        .await
+        .map_err(|e| ReplayError::task(task_dir, e))?
+}
+
+/// Streams a replay's frames over `on_chunk` one stored chunk at a time, then
+/// returns its info. A replay cut short by a crash plays up to its last
+/// whole chunk.
+#[tauri::command]
+pub async fn load_replay(
+    app: tauri::AppHandle,
+    id: String,
+    on_chunk: Channel<ReplayChunk>,
+) -> Result<ReplayInfo, ReplayError> {
+    let path = store::replay_path(&store::replays_dir(&app)?, &id)?;
+    let task_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reader = ReplayReader::open(&path, &id)?;
+        let mut index = 0;
+        let mut frame_count = 0u64;
+        while let Some(frames) = reader.next_chunk()? {
+            frame_count += frames.len() as u64;
+            if on_chunk.send(ReplayChunk { index, frames }).is_err() {
+                // The page that asked for it is gone.
+                break;
+            }
+            index += 1;
+        }
+        Ok(ReplayInfo {
+            header: reader.header,
+            format_version: reader.version,
+            frame_count,
+            file_size: reader.file_size,
+            complete: reader.complete,
+        })
+    })
+    .await
+    .map_err(|e| ReplayError::task(task_path, e))?
+}
+
+/// Writes out any buffered frames of the current recording. Called on exit
+/// so quitting mid-match keeps the replay up to that point.
+pub fn flush<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let recorder = app.state::<ReplayRecorder>();
+    let mut active = recorder.active.lock().unwrap();
+    if let Some(recording) = active.as_mut() {
+        if let Err(e) = recording.flush() {
+            log::error!("Failed to flush replay: {}", e);
+        }
+    }
+}
+
+/// The header of a replay recorded for tests.
+#[cfg(test)]
+fn sample_header() -> ReplayHeader {
+    ReplayHeader {
+        id: String::new(),
+        started_at_ms: 1_700_000_000_000,
+        metadata: ReplayMetadata {
+            level_id: "dust".into(),
+            player_name: "ann".into(),
+            tick_rate: 64,
+            snapshot_interval: 32,
+            game_version: Some("0.1.0".into()),
+        },
+    }
+}
+
+/// Synthetic input: every other tick, a snapshot every 16 frames.
+#[cfg(test)]
+fn sample_frames(count: u32) -> Vec<InputFrame> {
+    (0..count)
+        .map(|i| InputFrame {
+            tick: i * 2 + 1,
+            buttons: i.wrapping_mul(2_654_435_761) >> 16,
+            yaw_delta: (i as f32 * 0.37).sin(),
+            pitch_delta: -(i as f32 * 0.11).cos() * 0.5,
+            position: (i % 16 == 0).then(|| [i as f32, 1.75, -(i as f32) * 0.25]),
+        })
+        .collect()
+}
@@ -0,0 +1,122 @@
+use super::ReplayError;
+use serde::{Deserialize, Serialize};
+
+const MAX_NAME_CHARS: usize = 64;
+
+/// Supplied by the frontend when recording starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayMetadata {
+    pub level_id: String,
+    pub player_name: String,
+    /// Simulation ticks per second.
+    pub tick_rate: u32,
+    /// Frames carry a position snapshot every this many ticks so playback
+    /// can correct drift.
+    pub snapshot_interval: u32,
+    #[serde(default)]
+    pub game_version: Option<String>,
+}
+
+/// The JSON block at the head of every replay file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayHeader {
+    pub id: String,
+    pub started_at_ms: u64,
+    #[serde(flatten)]
+    pub metadata: ReplayMetadata,
+}
+
+/// One tick of player input.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputFrame {
+    pub tick: u32,
+    /// Bitfield of held buttons, as defined by the frontend.
+    pub buttons: u32,
+    pub yaw_delta: f32,
+    pub pitch_delta: f32,
+    #[serde(default)]
+    pub position: Option<[f32; 3]>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayInfo {
+    #[serde(flatten)]
+    pub header: ReplayHeader,
+    pub format_version: u32,
+    pub frame_count: u64,
+    pub file_size: u64,
+    /// False when recording never reached `stop_replay_recording`, e.g. the
+    /// game crashed. The frames up to that point are still playable.
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum ReplayListing {
+    Ok(ReplayInfo),
+    Damaged {
+        id: String,
+        kind: &'static str,
+        message: String,
+    },
+}
+
+/// A batch of frames sent over the `load_replay` channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayChunk {
+    pub index: u32,
+    pub frames: Vec<InputFrame>,
+}
+
+impl ReplayMetadata {
+    pub fn validated(mut self) -> Result<Self, ReplayError> {
+        self.level_id = clean("level id", &self.level_id)?;
+        self.player_name = clean("player name", &self.player_name)?;
+        if !(1..=1000).contains(&self.tick_rate) {
+            return Err(ReplayError::InvalidMetadata(format!(
+                "tick rate {} is outside 1..=1000",
+                self.tick_rate
+            )));
+        }
+        if self.snapshot_interval == 0 {
+            return Err(ReplayError::InvalidMetadata(
+                "snapshot interval must be at least 1".to_string(),
+            ));
+        }
+        Ok(self)
+    }
+}
+
+impl InputFrame {
+    pub fn validate(&self) -> Result<(), ReplayError> {
+        let finite = self.yaw_delta.is_finite()
+            && self.pitch_delta.is_finite()
+            && self
+                .position
+                .is_none_or(|p| p.iter().all(|v| v.is_finite()));
+        if finite {
+            Ok(())
+        } else {
+            Err(ReplayError::InvalidFrame {
+                tick: self.tick,
+                reason: "values must be finite".to_string(),
+            })
+        }
+    }
+}
+
+fn clean(field: &str, value: &str) -> Result<String, ReplayError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed.chars().count() > MAX_NAME_CHARS {
+        return Err(ReplayError::InvalidMetadata(format!(
+            "{} must be 1-{} characters",
+            field, MAX_NAME_CHARS
+        )));
+    }
+    Ok(trimmed.to_string())
+}
@@ -0,0 +1,222 @@
+use super::format::{self, END_MARKER};
+use super::model::{InputFrame, ReplayHeader};
+use super::ReplayError;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Pending frames are written as a chunk once there are this many...
+const CHUNK_FRAMES: u32 = 512;
+/// ...or once this long has passed, so a crash loses at most a couple of
+/// seconds of input.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The replay being recorded, if any. Only one recording runs at a time.
+#[derive(Default)]
+pub struct ReplayRecorder {
+    pub(super) active: Mutex<Option<Recording>>,
+}
+
+pub struct Recording {
+    pub id: String,
+    pub path: PathBuf,
+    file: File,
+    last_tick: Option<u32>,
+    pending: Vec<u8>,
+    pending_frames: u32,
+    last_flush: Instant,
+}
+
+impl Recording {
+    /// Creates `<dir>/<startedAtMs>.replay`, adding a `-N` suffix if a replay
+    /// started in the same millisecond, and writes the header.
+    pub fn create(dir: &Path, mut header: ReplayHeader) -> Result<Self, ReplayError> {
+        std::fs::create_dir_all(dir).map_err(|e| ReplayError::io(dir, e))?;
+
+        let base = header.started_at_ms.to_string();
+        let mut n = 1;
+        let (id, path, mut file) = loop {
+            let id = if n == 1 {
+                base.clone()
+            } else {
+                format!("{}-{}", base, n)
+            };
+            let path = super::store::replay_path(dir, &id)?;
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (id, path, file),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+                Err(e) => return Err(ReplayError::io(&path, e)),
+            }
+        };
+
+        header.id = id.clone();
+        file.write_all(&format::encode_header(&header))
+            .map_err(|e| ReplayError::io(&path, e))?;
+        Ok(Self {
+            id,
+            path,
+            file,
+            last_tick: None,
+            pending: Vec::new(),
+            pending_frames: 0,
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Buffers `frames`, writing a chunk when enough has built up. The whole
+    /// batch is rejected if any frame is invalid or not after the last tick.
+    pub fn append(&mut self, frames: &[InputFrame]) -> Result<(), ReplayError> {
+        let mut last = self.last_tick;
+        for frame in frames {
+            frame.validate()?;
+            if let Some(last) = last.filter(|&last| frame.tick <= last) {
+                return Err(ReplayError::OutOfOrder {
+                    tick: frame.tick,
+                    last,
+                });
+            }
+            last = Some(frame.tick);
+        }
+
+        self.last_tick = last;
+        for frame in frames {
+            format::encode_frame(&mut self.pending, frame);
+            self.pending_frames += 1;
+            if self.pending_frames >= CHUNK_FRAMES {
+                self.flush()?;
+            }
+        }
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes buffered frames out as a chunk.
+    pub fn flush(&mut self) -> Result<(), ReplayError> {
+        self.last_flush = Instant::now();
+        if self.pending_frames == 0 {
+            return Ok(());
+        }
+        let chunk = format::encode_chunk(self.pending_frames, &self.pending);
+        self.file
+            .write_all(&chunk)
+            .map_err(|e| ReplayError::io(&self.path, e))?;
+        self.pending.clear();
+        self.pending_frames = 0;
+        Ok(())
+    }
+
+    /// Flushes, writes the end marker and syncs the file to disk.
+    pub fn finish(mut self) -> Result<PathBuf, ReplayError> {
+        self.flush()?;
+        self.file
+            .write_all(&END_MARKER)
+            .and_then(|_| self.file.sync_all())
+            .map_err(|e| ReplayError::io(&self.path, e))?;
+        Ok(self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::format::ReplayReader;
+    use crate::replay::{sample_frames as frames, sample_header as header};
+
+    fn read_all(path: &Path, id: &str) -> (Vec<InputFrame>, Vec<usize>, bool) {
+        let mut reader = ReplayReader::open(path, id).unwrap();
+        let mut all = Vec::new();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.next_chunk().unwrap() {
+            chunks.push(chunk.len());
+            all.extend(chunk);
+        }
+        (all, chunks, reader.complete)
+    }
+
+    #[test]
+    fn round_trips_thousands_of_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recording = Recording::create(dir.path(), header()).unwrap();
+        let id = recording.id.clone();
+        let expected = frames(5_000);
+        // Batches of uneven size, as the frontend sends them.
+        let mut rest = &expected[..];
+        let mut batch = 1;
+        while !rest.is_empty() {
+            let (now, later) = rest.split_at(batch.min(rest.len()));
+            recording.append(now).unwrap();
+            rest = later;
+            batch = batch * 3 % 700 + 1;
+        }
+        let path = recording.finish().unwrap();
+
+        let (frames, chunks, complete) = read_all(&path, &id);
+        assert_eq!(frames, expected);
+        assert!(complete);
+        assert!(chunks.iter().all(|&n| n <= CHUNK_FRAMES as usize));
+
+        let info = crate::replay::store::info(&path, &id).unwrap();
+        assert_eq!(info.frame_count, 5_000);
+        assert!(info.complete);
+        assert_eq!(info.header.id, id);
+        assert_eq!(info.header.metadata.level_id, "dust");
+        assert_eq!(info.file_size, std::fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn rejects_out_of_order_batches_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recording = Recording::create(dir.path(), header()).unwrap();
+        let id = recording.id.clone();
+        let all = frames(4);
+        recording.append(&all[..2]).unwrap();
+
+        let mut replayed = all[2];
+        replayed.tick = all[1].tick;
+        assert!(matches!(
+            recording.append(&[all[2], all[3], replayed]),
+            Err(ReplayError::OutOfOrder { .. })
+        ));
+        let mut bad = all[2];
+        bad.yaw_delta = f32::NAN;
+        assert!(matches!(
+            recording.append(&[bad]),
+            Err(ReplayError::InvalidFrame { .. })
+        ));
+
+        recording.append(&all[2..]).unwrap();
+        let path = recording.finish().unwrap();
+        assert_eq!(read_all(&path, &id).0, all);
+    }
+
+    #[test]
+    fn a_crash_keeps_flushed_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recording = Recording::create(dir.path(), header()).unwrap();
+        let id = recording.id.clone();
+        let path = recording.path.clone();
+        let all = frames(1_000);
+        recording.append(&all[..700]).unwrap();
+        recording.flush().unwrap();
+        recording.append(&all[700..]).unwrap();
+        // Dropped without `finish`, as if the game died here.
+        drop(recording);
+
+        let (frames, _, complete) = read_all(&path, &id);
+        assert_eq!(frames, &all[..700]);
+        assert!(!complete);
+    }
+
+    #[test]
+    fn ids_are_unique_within_a_millisecond() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = Recording::create(dir.path(), header()).unwrap();
+        let second = Recording::create(dir.path(), header()).unwrap();
+        assert_eq!(first.id, "1700000000000");
+        assert_eq!(second.id, "1700000000000-2");
+    }
+}
@@ -0,0 +1,79 @@
+use super::format::ReplayReader;
+use super::model::{ReplayInfo, ReplayListing};
+use super::ReplayError;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, Runtime};
+
+pub const DIR_NAME: &str = "replays";
+pub const EXTENSION: &str = "replay";
+
+pub fn replays_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, ReplayError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| ReplayError::NoDataDir(e.to_string()))
+}
+
+/// Ids are generated by the recorder as `<startedAtMs>[-N]`; anything else
+/// can't name a replay and mustn't reach the filesystem.
+pub fn replay_path(dir: &Path, id: &str) -> Result<PathBuf, ReplayError> {
+    let valid =
+        !id.is_empty() && id.len() <= 32 && id.chars().all(|c| c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(ReplayError::NotFound(id.to_string()));
+    }
+    Ok(dir.join(format!("{}.{}", id, EXTENSION)))
+}
+
+/// Reads the header and walks the chunk prefixes to count frames, without
+/// decoding any of them.
+pub fn info(path: &Path, id: &str) -> Result<ReplayInfo, ReplayError> {
+    let mut reader = ReplayReader::open(path, id)?;
+    let mut frame_count = 0u64;
+    while let Some(frames) = reader.skip_chunk()? {
+        frame_count += frames as u64;
+    }
+    Ok(ReplayInfo {
+        header: reader.header,
+        format_version: reader.version,
+        frame_count,
+        file_size: reader.file_size,
+        complete: reader.complete,
+    })
+}
+
+/// Every replay, newest first, with unreadable ones listed last.
+pub fn list(dir: &Path) -> Result<Vec<ReplayListing>, ReplayError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ReplayError::io(dir, e)),
+    };
+
+    let mut replays = Vec::new();
+    let mut damaged = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        match info(&path, id) {
+            Ok(info) => replays.push(info),
+            Err(e) => damaged.push(ReplayListing::Damaged {
+                id: id.to_string(),
+                kind: e.kind(),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    replays.sort_by_key(|r| std::cmp::Reverse(r.header.started_at_ms));
+    Ok(replays
+        .into_iter()
+        .map(ReplayListing::Ok)
+        .chain(damaged)
+        .collect())
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the RNG commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum RngError {
+    #[error("No RNG stream named {0:?}")]
+    UnknownStream(String),
+
+    #[error("RNG stream {name:?} already exists with seed {seed}")]
+    StreamExists { name: String, seed: u64 },
+
+    #[error("Invalid RNG stream name {0:?}")]
+    InvalidName(String),
+
+    #[error("Invalid range {min}..={max}")]
+    InvalidRange { min: i32, max: i32 },
+
+    #[error("Requested {requested} values; at most {max} per call")]
+    TooMany { requested: u32, max: u32 },
+
+    #[error("Invalid RNG state: {0}")]
+    InvalidState(String),
+}
+
+impl RngError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RngError::UnknownStream(_) => "unknownStream",
+            RngError::StreamExists { .. } => "streamExists",
+            RngError::InvalidName(_) => "invalidName",
+            RngError::InvalidRange { .. } => "invalidRange",
+            RngError::TooMany { .. } => "tooMany",
+            RngError::InvalidState(_) => "invalidState",
+        }
+    }
+}
+
+impl Serialize for RngError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("RngError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,180 @@
+mod error;
+mod xoshiro;
+
+pub use error::RngError;
+pub use xoshiro::Xoshiro256StarStar;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::State;
+
+/// Caps a single call so a bad `count` can't allocate without bound.
+const MAX_COUNT: u32 = 65_536;
+const MAX_NAME_LEN: usize = 64;
+
+/// Named deterministic streams, all xoshiro256** (see `xoshiro.rs`).
+#[derive(Default)]
+pub struct RngStreams {
+    streams: Mutex<HashMap<String, Stream>>,
+}
+
+struct Stream {
+    seed: u64,
+    rng: Xoshiro256StarStar,
+}
+
+/// A stream snapshot. Words are 16-digit hex strings because JavaScript
+/// numbers can't hold a `u64` exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RngState {
+    pub seed: String,
+    pub state: [String; 4],
+}
+
+impl RngState {
+    fn new(stream: &Stream) -> Self {
+        Self {
+            seed: hex(stream.seed),
+            state: stream.rng.state().map(hex),
+        }
+    }
+
+    fn parse(&self) -> Result<Stream, RngError> {
+        let word = |s: &str| {
+            u64::from_str_radix(s, 16)
+                .map_err(|e| RngError::InvalidState(format!("{:?}: {}", s, e)))
+        };
+        let mut words = [0u64; 4];
+        for (w, s) in words.iter_mut().zip(&self.state) {
+            *w = word(s)?;
+        }
+        Ok(Stream {
+            seed: word(&self.seed)?,
+            rng: Xoshiro256StarStar::from_state(words)
+                .ok_or_else(|| RngError::InvalidState("state is all zero".to_string()))?,
+        })
+    }
+}
+
+fn hex(v: u64) -> String {
+    format!("{:016x}", v)
+}
+
+/// Creates stream `name` from `seed`. Creating it again with the same seed is
+/// a no-op that leaves its position alone; a different seed is an error.
+#[tauri::command]
+pub fn create_rng_stream(
+    streams: State<'_, RngStreams>,
+    name: String,
+    seed: u64,
+) -> Result<RngState, RngError> {
+    validate_name(&name)?;
+    let mut streams = streams.streams.lock().unwrap();
+    if let Some(existing) = streams.get(&name) {
+        if existing.seed != seed {
+            return Err(RngError::StreamExists {
+                name,
+                seed: existing.seed,
+            });
+        }
+        return Ok(RngState::new(existing));
+    }
+    let stream = Stream {
+        seed,
+        rng: Xoshiro256StarStar::from_seed(seed),
+    };
+    let state = RngState::new(&stream);
+    streams.insert(name, stream);
+    Ok(state)
+}
+
+/// The next `count` raw values from stream `name`.
+#[tauri::command]
+pub fn rng_next_u32(
+    streams: State<'_, RngStreams>,
+    name: String,
+    count: u32,
+) -> Result<Vec<u32>, RngError> {
+    check_count(count)?;
+    with_stream(&streams, &name, |rng| {
+        (0..count).map(|_| rng.next_u32()).collect()
+    })
+}
+
+/// The next `count` values uniform over `min..=max`, without modulo bias.
+#[tauri::command]
+pub fn rng_next_range(
+    streams: State<'_, RngStreams>,
+    name: String,
+    min: i32,
+    max: i32,
+    count: u32,
+) -> Result<Vec<i32>, RngError> {
+    if min > max {
+        return Err(RngError::InvalidRange { min, max });
+    }
+    check_count(count)?;
+    // The full i32 range wraps to a span of 0, which `next_below` treats as 2^32.
+    let span = (max as i64 - min as i64 + 1) as u32;
+    with_stream(&streams, &name, |rng| {
+        (0..count)
+            .map(|_| (min as i64 + rng.next_below(span) as i64) as i32)
+            .collect()
+    })
+}
+
+#[tauri::command]
+pub fn get_rng_state(streams: State<'_, RngStreams>, name: String) -> Result<RngState, RngError> {
+    streams
+        .streams
+        .lock()
+        .unwrap()
+        .get(&name)
+        .map(RngState::new)
+        .ok_or(RngError::UnknownStream(name))
+}
+
+/// Overwrites (or creates) stream `name` from a snapshot taken with
+/// [`get_rng_state`], e.g. to resync a client that drifted.
+#[tauri::command]
+pub fn set_rng_state(
+    streams: State<'_, RngStreams>,
+    name: String,
+    state: RngState,
+) -> Result<(), RngError> {
+    validate_name(&name)?;
+    let stream = state.parse()?;
+    streams.streams.lock().unwrap().insert(name, stream);
+    Ok(())
+}
+
+fn with_stream<T>(
+    streams: &RngStreams,
+    name: &str,
+    f: impl FnOnce(&mut Xoshiro256StarStar) -> T,
+) -> Result<T, RngError> {
+    let mut streams = streams.streams.lock().unwrap();
+    let stream = streams
+        .get_mut(name)
+        .ok_or_else(|| RngError::UnknownStream(name.to_string()))?;
+    Ok(f(&mut stream.rng))
+}
+
+fn validate_name(name: &str) -> Result<(), RngError> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN || name.chars().any(char::is_control) {
+        return Err(RngError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+fn check_count(count: u32) -> Result<(), RngError> {
+    if count > MAX_COUNT {
+        return Err(RngError::TooMany {
+            requested: count,
+            max: MAX_COUNT,
+        });
+    }
+    Ok(())
+}
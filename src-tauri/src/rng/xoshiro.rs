@@ -0,0 +1,146 @@
+//! xoshiro256** 1.0 (Blackman & Vigna, <https://prng.di.unimi.it/>), seeded
+//! by expanding a `u64` through SplitMix64 as the authors recommend.
+//!
+//! Implemented here rather than pulled from a crate so the output sequence
+//! is fixed by this file alone: peers computing spread and loot rolls must
+//! agree bit for bit, on every platform and across dependency updates. Only
+//! wrapping integer arithmetic is used, never floats.
+//!
+//! For seed 0 the first three outputs of [`Xoshiro256StarStar::next_u64`]
+//! are `0x99ec5f36cb75f2b4`, `0xbf6e1f784956452a` and `0x1a5f849d4933e6e0`.
+
+/// Advances a SplitMix64 state and returns its next output.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Xoshiro256StarStar {
+    s: [u64; 4],
+}
+
+impl Xoshiro256StarStar {
+    pub fn from_seed(seed: u64) -> Self {
+        let mut sm = seed;
+        Self {
+            s: [
+                splitmix64(&mut sm),
+                splitmix64(&mut sm),
+                splitmix64(&mut sm),
+                splitmix64(&mut sm),
+            ],
+        }
+    }
+
+    /// Restores a snapshot. The all-zero state is a fixed point and can never
+    /// come out of [`from_seed`](Self::from_seed), so it is rejected.
+    pub fn from_state(s: [u64; 4]) -> Option<Self> {
+        (s != [0; 4]).then_some(Self { s })
+    }
+
+    pub fn state(&self) -> [u64; 4] {
+        self.s
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /// The upper half of [`next_u64`](Self::next_u64), whose bits are the
+    /// strongest.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform in `0..span` (or any `u32` when `span` is 0, meaning 2^32),
+    /// using Lemire's multiply-and-reject so no value is favored.
+    pub fn next_below(&mut self, span: u32) -> u32 {
+        if span == 0 {
+            return self.next_u32();
+        }
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let m = self.next_u32() as u64 * span as u64;
+            if (m as u32) >= threshold {
+                return (m >> 32) as u32;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_zero_matches_the_reference_sequence() {
+        let mut rng = Xoshiro256StarStar::from_seed(0);
+        assert_eq!(
+            rng.state(),
+            [
+                0xe220_a839_7b1d_cdaf,
+                0x6e78_9e6a_a1b9_65f4,
+                0x06c4_5d18_8009_454f,
+                0xf88b_b8a8_724c_81ec,
+            ]
+        );
+        assert_eq!(rng.next_u64(), 0x99ec_5f36_cb75_f2b4);
+        assert_eq!(rng.next_u64(), 0xbf6e_1f78_4956_452a);
+        assert_eq!(rng.next_u64(), 0x1a5f_849d_4933_e6e0);
+    }
+
+    #[test]
+    fn next_u32_is_pinned() {
+        let mut rng = Xoshiro256StarStar::from_seed(42);
+        let values: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+        assert_eq!(values, [0x1578_0b2e, 0x6104_d986, 0xae17_5332, 0xecb8_ad47]);
+
+        let mut rng = Xoshiro256StarStar::from_seed(0);
+        assert_eq!(rng.next_u32(), 0x99ec_5f36);
+    }
+
+    #[test]
+    fn next_below_is_pinned() {
+        let mut rng = Xoshiro256StarStar::from_seed(7);
+        let rolls: Vec<u32> = (0..12).map(|_| rng.next_below(6)).collect();
+        assert_eq!(rolls, [4, 1, 5, 5, 5, 5, 0, 0, 2, 0, 3, 4]);
+
+        let mut rng = Xoshiro256StarStar::from_seed(7);
+        let wide: Vec<u32> = (0..4).map(|_| rng.next_below(1_000_000_007)).collect();
+        assert_eq!(wide, [278_751_231, 839_627_467, 981_097_731, 990_860_285]);
+    }
+
+    #[test]
+    fn next_below_edge_spans() {
+        let mut rng = Xoshiro256StarStar::from_seed(3);
+        let mut same = rng;
+        assert_eq!(rng.next_below(0), same.next_u32());
+        assert!((0..100).all(|_| rng.next_below(1) == 0));
+    }
+
+    #[test]
+    fn state_round_trip_continues_the_sequence() {
+        let mut rng = Xoshiro256StarStar::from_seed(99);
+        rng.next_u64();
+        let mut restored = Xoshiro256StarStar::from_state(rng.state()).unwrap();
+        for _ in 0..16 {
+            assert_eq!(restored.next_u64(), rng.next_u64());
+        }
+        assert!(Xoshiro256StarStar::from_state([0; 4]).is_none());
+    }
+}
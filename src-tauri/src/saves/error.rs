@@ -0,0 +1,81 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the save commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+    #[error("Could not locate the app data directory: {0}")]
+    NoDataDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Save slot {0} is empty")]
+    NotFound(u8),
+
+    #[error("Save in slot {slot} is corrupted: {reason}")]
+    Corrupted { slot: u8, reason: String },
+
+    #[error(
+        "Save in slot {slot} is from a newer version of the game \
+         (format {found}, this build reads up to {supported})"
+    )]
+    NewerVersion {
+        slot: u8,
+        found: u32,
+        supported: u32,
+    },
+
+    #[error("Cloud sync is not configured: {0}")]
+    SyncNotConfigured(String),
+
+    #[error("Cloud sync failed: {0}")]
+    Network(String),
+
+    #[error("Save sync busy: {0}")]
+    Busy(String),
+}
+
+impl SaveError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        SaveError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn corrupted(slot: u8, reason: impl std::fmt::Display) -> Self {
+        SaveError::Corrupted {
+            slot,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SaveError::NoDataDir(_) => "noDataDir",
+            SaveError::Io { .. } => "io",
+            SaveError::NotFound(_) => "notFound",
+            SaveError::Corrupted { .. } => "corrupted",
+            SaveError::NewerVersion { .. } => "newerVersion",
+            SaveError::SyncNotConfigured(_) => "syncNotConfigured",
+            SaveError::Network(_) => "network",
+            SaveError::Busy(_) => "busy",
+        }
+    }
+}
+
+impl Serialize for SaveError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SaveError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
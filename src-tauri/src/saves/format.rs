@@ -0,0 +1,263 @@
+//! On-disk save container.
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! magic    b"FSAV"
+//! u32      format version (1)
+//! u32      summary length
+//! u32      summary CRC-32
+//! u64      payload length
+//! u32      payload CRC-32
+//! summary  JSON `SaveSummary`
+//! payload  JSON `SaveGame`
+//! ```
+//!
+//! The summary has its own checksum so `list_saves` can trust it without
+//! reading the payload.
+
+use super::model::{SaveGame, SaveSummary};
+use super::SaveError;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+pub const SAVE_MAGIC: &[u8; 4] = b"FSAV";
+pub const SAVE_VERSION: u32 = 1;
+
+const HEADER_LEN: u64 = 4 + 4 + 4 + 4 + 8 + 4;
+/// Summaries are a few hundred bytes; anything bigger is a damaged header.
+const MAX_SUMMARY_LEN: u32 = 64 * 1024;
+
+struct Header {
+    version: u32,
+    summary_len: u32,
+    summary_crc: u32,
+    payload_len: u64,
+    payload_crc: u32,
+}
+
+impl Header {
+    fn total_len(&self) -> u64 {
+        HEADER_LEN
+            .saturating_add(self.summary_len as u64)
+            .saturating_add(self.payload_len)
+    }
+}
+
+pub fn encode(save: &SaveGame) -> Result<Vec<u8>, serde_json::Error> {
+    let summary = serde_json::to_vec(&SaveSummary::from(save))?;
+    let payload = serde_json::to_vec(save)?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN as usize + summary.len() + payload.len());
+    out.extend_from_slice(SAVE_MAGIC);
+    out.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+    out.extend_from_slice(&(summary.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32fast::hash(&summary).to_le_bytes());
+    out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    out.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    out.extend_from_slice(&summary);
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Reads only the header and summary of the save at `path`. Returns the
+/// summary, format version and file size.
+pub fn read_summary(path: &Path, slot: u8) -> Result<(SaveSummary, u32, u64), SaveError> {
+    let file = File::open(path).map_err(|e| open_error(path, slot, e))?;
+    let file_size = file.metadata().map_err(|e| SaveError::io(path, e))?.len();
+    let mut reader = BufReader::new(file);
+
+    let header = read_header(&mut reader, slot)?;
+    check_length(&header, file_size, slot)?;
+
+    let mut summary = vec![0u8; header.summary_len as usize];
+    reader
+        .read_exact(&mut summary)
+        .map_err(|e| SaveError::corrupted(slot, e))?;
+    if crc32fast::hash(&summary) != header.summary_crc {
+        return Err(SaveError::corrupted(slot, "summary checksum mismatch"));
+    }
+    let summary = serde_json::from_slice(&summary).map_err(|e| SaveError::corrupted(slot, e))?;
+    Ok((summary, header.version, file_size))
+}
+
+/// Verifies both checksums and parses the full save.
+pub fn decode(bytes: &[u8], slot: u8) -> Result<SaveGame, SaveError> {
+    let mut reader = bytes;
+    let header = read_header(&mut reader, slot)?;
+    check_length(&header, bytes.len() as u64, slot)?;
+
+    let (summary, payload) = reader.split_at(header.summary_len as usize);
+    if crc32fast::hash(summary) != header.summary_crc {
+        return Err(SaveError::corrupted(slot, "summary checksum mismatch"));
+    }
+    if crc32fast::hash(payload) != header.payload_crc {
+        return Err(SaveError::corrupted(slot, "payload checksum mismatch"));
+    }
+    serde_json::from_slice(payload).map_err(|e| SaveError::corrupted(slot, e))
+}
+
+pub fn open_error(path: &Path, slot: u8, e: std::io::Error) -> SaveError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        SaveError::NotFound(slot)
+    } else {
+        SaveError::io(path, e)
+    }
+}
+
+/// Checks the magic and version before anything else, since a newer format
+/// is free to change the rest of the header.
+fn read_header(reader: &mut impl Read, slot: u8) -> Result<Header, SaveError> {
+    let truncated = |_| SaveError::corrupted(slot, "file is truncated");
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).map_err(truncated)?;
+    if &magic != SAVE_MAGIC {
+        return Err(SaveError::corrupted(slot, "not a save file"));
+    }
+
+    let version = read_u32(reader).map_err(truncated)?;
+    if version > SAVE_VERSION {
+        return Err(SaveError::NewerVersion {
+            slot,
+            found: version,
+            supported: SAVE_VERSION,
+        });
+    }
+    if version == 0 {
+        return Err(SaveError::corrupted(slot, "invalid format version 0"));
+    }
+
+    let header = Header {
+        version,
+        summary_len: read_u32(reader).map_err(truncated)?,
+        summary_crc: read_u32(reader).map_err(truncated)?,
+        payload_len: read_u64(reader).map_err(truncated)?,
+        payload_crc: read_u32(reader).map_err(truncated)?,
+    };
+    if header.summary_len > MAX_SUMMARY_LEN {
+        return Err(SaveError::corrupted(slot, "summary length out of range"));
+    }
+    Ok(header)
+}
+
+fn check_length(header: &Header, actual: u64, slot: u8) -> Result<(), SaveError> {
+    let expected = header.total_len();
+    if expected != actual {
+        return Err(SaveError::corrupted(
+            slot,
+            format!("expected {} bytes, file has {}", expected, actual),
+        ));
+    }
+    Ok(())
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::saves::model::{InventoryItem, PlayerStats};
+
+    fn save() -> SaveGame {
+        SaveGame {
+            level_id: "warehouse".to_string(),
+            player: PlayerStats {
+                health: 72.5,
+                ammo: 90,
+                grenades: 2,
+                score: 1_250,
+                current_weapon: "rifle".to_string(),
+                stance: "crouch".to_string(),
+            },
+            inventory: vec![InventoryItem {
+                item_id: "medkit".to_string(),
+                count: 3,
+            }],
+            playtime_secs: 812.25,
+            timestamp_ms: 1_772_388_273_512,
+        }
+    }
+
+    fn corrupted_reason(result: Result<SaveGame, SaveError>) -> String {
+        match result {
+            Err(SaveError::Corrupted { slot: 3, reason }) => reason,
+            other => panic!("expected a corrupted save, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let bytes = encode(&save()).unwrap();
+        let decoded = decode(&bytes, 3).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(save()).unwrap()
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("slot-3.sav");
+        std::fs::write(&path, &bytes).unwrap();
+        let (summary, version, size) = read_summary(&path, 3).unwrap();
+        assert_eq!(summary.level_id, "warehouse");
+        assert_eq!(summary.timestamp_ms, 1_772_388_273_512);
+        assert_eq!((version, size), (SAVE_VERSION, bytes.len() as u64));
+    }
+
+    #[test]
+    fn a_flipped_payload_byte_fails_the_checksum() {
+        let mut bytes = encode(&save()).unwrap();
+        let last = bytes.len() - 2;
+        bytes[last] ^= 0x01;
+        assert_eq!(
+            corrupted_reason(decode(&bytes, 3)),
+            "payload checksum mismatch"
+        );
+
+        let mut bytes = encode(&save()).unwrap();
+        bytes[HEADER_LEN as usize] ^= 0x20;
+        assert_eq!(
+            corrupted_reason(decode(&bytes, 3)),
+            "summary checksum mismatch"
+        );
+    }
+
+    #[test]
+    fn saves_from_a_newer_format_are_refused_as_such() {
+        let mut bytes = encode(&save()).unwrap();
+        bytes[4..8].copy_from_slice(&(SAVE_VERSION + 1).to_le_bytes());
+        match decode(&bytes, 3) {
+            Err(SaveError::NewerVersion {
+                slot: 3,
+                found,
+                supported: SAVE_VERSION,
+            }) => assert_eq!(found, SAVE_VERSION + 1),
+            other => panic!("expected newerVersion, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn truncated_and_foreign_files_are_corrupted() {
+        let bytes = encode(&save()).unwrap();
+        assert_eq!(
+            corrupted_reason(decode(&bytes[..10], 3)),
+            "file is truncated"
+        );
+        assert!(corrupted_reason(decode(&bytes[..bytes.len() - 1], 3)).starts_with("expected"));
+        assert_eq!(
+            corrupted_reason(decode(b"PK\x03\x04rest", 3)),
+            "not a save file"
+        );
+    }
+}
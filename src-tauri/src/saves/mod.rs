@@ -0,0 +1,226 @@
+mod error;
+mod format;
+mod model;
+mod store;
+mod sync;
+
+pub use error::SaveError;
+pub use model::{SaveGame, SaveSlotInfo};
+pub use store::SaveStore;
+pub use sync::{ConflictSide, SyncReport, SyncState};
+
+use crate::fs_atomic::write_atomic;
+use std::collections::BTreeMap;
+use std::path::Path;
+use store::{saves_dir, slot_path};
+use sync::{
+    plan, slot_union, Baseline, LocalState, Plan, RemoteSlot, SaveConflict, SyncClient, SyncPhase,
+    SyncProgress, PROGRESS_EVENT,
+};
+use tauri::{Emitter, State};
+
+/// Writes `data` to `slot` in the active profile. An existing save in the slot
+/// is only replaced once the new one is fully on disk.
+#[tauri::command]
+pub async fn write_save(
+    app: tauri::AppHandle,
+    store: State<'_, SaveStore>,
+    slot: u8,
+    data: SaveGame,
+) -> Result<(), SaveError> {
+    let dir = saves_dir(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    store::write(&dir, slot, &data)
+        .inspect(|()| log::info!("Saved slot {}", slot))
+        .inspect_err(|e| log::error!("Failed to save slot {}: {}", slot, e))
+}
+
+/// Reads and checksums the save in `slot`.
+#[tauri::command]
+pub async fn read_save(app: tauri::AppHandle, slot: u8) -> Result<SaveGame, SaveError> {
+    let dir = saves_dir(&app)?;
+    store::read(&dir, slot).inspect_err(|e| log::warn!("Failed to load slot {}: {}", slot, e))
+}
+
+/// Slot summaries (level, playtime, timestamp) without the save payloads,
+/// newest first.
+#[tauri::command]
+pub async fn list_saves(app: tauri::AppHandle) -> Result<Vec<SaveSlotInfo>, SaveError> {
+    let dir = saves_dir(&app)?;
+    store::list(&dir)
+}
+
+/// Returns `false` if the slot was already empty.
+#[tauri::command]
+pub async fn delete_save(
+    app: tauri::AppHandle,
+    store: State<'_, SaveStore>,
+    slot: u8,
+) -> Result<bool, SaveError> {
+    let dir = saves_dir(&app)?;
+    let _guard = store.lock.lock().unwrap();
+    store::delete(&dir, slot)
+}
+
+/// Two-way sync with the cloud endpoint from settings. Uploads happen first;
+/// downloads are held in memory and only written once every request has
+/// succeeded, so a dropped connection leaves local saves as they were.
+/// Slots changed on both sides since the last sync come back as `conflicts`.
+#[tauri::command]
+pub async fn sync_saves(
+    app: tauri::AppHandle,
+    store: State<'_, SaveStore>,
+    sync: State<'_, SyncState>,
+) -> Result<SyncReport, SaveError> {
+    let _permit = sync.try_begin()?;
+    let client = sync_client(&app)?;
+    let dir = saves_dir(&app)?;
+
+    emit_progress(&app, SyncPhase::Listing, None, 0, 0);
+    let remote: BTreeMap<u8, RemoteSlot> = client
+        .list()
+        .await?
+        .into_iter()
+        .map(|r| (r.slot, r))
+        .collect();
+    let remote_list: Vec<RemoteSlot> = remote.values().cloned().collect();
+    let mut baseline = Baseline::load(&dir);
+
+    let mut report = SyncReport::default();
+    let mut uploads = Vec::new();
+    let mut downloads = Vec::new();
+    for slot in slot_union(&dir, &remote_list) {
+        let local = LocalState::read(&dir, slot);
+        match plan(slot, &local, remote.get(&slot), &baseline) {
+            Plan::Upload => {
+                if let LocalState::Readable(local) = local {
+                    uploads.push((slot, local));
+                }
+            }
+            Plan::Download(remote) => downloads.push((slot, remote, local.crc())),
+            Plan::Conflict(conflict) => report.conflicts.push(conflict),
+            Plan::Unchanged => report.unchanged += 1,
+            Plan::Skip => report.skipped.push(slot),
+        }
+    }
+
+    let total = uploads.len();
+    for (done, (slot, local)) in uploads.iter().enumerate() {
+        emit_progress(&app, SyncPhase::Uploading, Some(*slot), done, total);
+        let uploaded = client.upload(*slot, local).await?;
+        baseline.record_saved(&dir, *slot, &uploaded, local.crc)?;
+        report.uploaded.push(*slot);
+    }
+
+    let total = downloads.len();
+    let mut fetched = Vec::with_capacity(total);
+    for (done, (slot, remote, seen_crc)) in downloads.into_iter().enumerate() {
+        emit_progress(&app, SyncPhase::Downloading, Some(slot), done, total);
+        let bytes = client.download(slot).await?;
+        fetched.push((slot, remote, seen_crc, bytes));
+    }
+
+    emit_progress(&app, SyncPhase::Writing, None, 0, fetched.len());
+    {
+        let _guard = store.lock.lock().unwrap();
+        for (slot, remote, seen_crc, bytes) in fetched {
+            // The game may have saved into this slot while we were downloading.
+            let now = LocalState::read(&dir, slot);
+            if now.crc() != seen_crc {
+                if let LocalState::Readable(local) = now {
+                    report.conflicts.push(SaveConflict {
+                        slot,
+                        local_timestamp_ms: local.timestamp_ms,
+                        remote_modified_ms: remote.modified_ms,
+                    });
+                } else {
+                    report.skipped.push(slot);
+                }
+                continue;
+            }
+            write_downloaded(&dir, slot, &bytes)?;
+            baseline.record(slot, &remote, crc32fast::hash(&bytes));
+            report.downloaded.push(slot);
+        }
+        baseline.save(&dir)?;
+    }
+
+    Ok(report)
+}
+
+/// Settles a conflict from [`sync_saves`] by pushing the local save or
+/// pulling the remote one over it.
+#[tauri::command]
+pub async fn resolve_save_conflict(
+    app: tauri::AppHandle,
+    store: State<'_, SaveStore>,
+    sync: State<'_, SyncState>,
+    slot: u8,
+    keep: ConflictSide,
+) -> Result<(), SaveError> {
+    let _permit = sync.try_begin()?;
+    let client = sync_client(&app)?;
+    let dir = saves_dir(&app)?;
+    let mut baseline = Baseline::load(&dir);
+
+    match keep {
+        ConflictSide::Local => {
+            let LocalState::Readable(local) = LocalState::read(&dir, slot) else {
+                return Err(SaveError::NotFound(slot));
+            };
+            emit_progress(&app, SyncPhase::Uploading, Some(slot), 0, 1);
+            let uploaded = client.upload(slot, &local).await?;
+            baseline.record(slot, &uploaded, local.crc);
+        }
+        ConflictSide::Remote => {
+            emit_progress(&app, SyncPhase::Listing, None, 0, 0);
+            let remote = client
+                .list()
+                .await?
+                .into_iter()
+                .find(|r| r.slot == slot)
+                .ok_or(SaveError::NotFound(slot))?;
+            emit_progress(&app, SyncPhase::Downloading, Some(slot), 0, 1);
+            let bytes = client.download(slot).await?;
+
+            let _guard = store.lock.lock().unwrap();
+            write_downloaded(&dir, slot, &bytes)?;
+            baseline.record(slot, &remote, crc32fast::hash(&bytes));
+        }
+    }
+    baseline.save(&dir)
+}
+
+fn sync_client(app: &tauri::AppHandle) -> Result<SyncClient, SaveError> {
+    let settings =
+        crate::settings::current(app).map_err(|e| SaveError::SyncNotConfigured(e.to_string()))?;
+    match (&settings.cloud.endpoint, &settings.cloud.auth_token) {
+        (Some(endpoint), Some(token)) => SyncClient::new(endpoint, token),
+        _ => Err(SaveError::SyncNotConfigured(
+            "set an endpoint and auth token in settings".to_string(),
+        )),
+    }
+}
+
+fn write_downloaded(dir: &Path, slot: u8, bytes: &[u8]) -> Result<(), SaveError> {
+    let path = slot_path(dir, slot);
+    write_atomic(&path, bytes).map_err(|e| SaveError::io(&path, e))
+}
+
+fn emit_progress(
+    app: &tauri::AppHandle,
+    phase: SyncPhase,
+    slot: Option<u8>,
+    done: usize,
+    total: usize,
+) {
+    let _ = app.emit(
+        PROGRESS_EVENT,
+        SyncProgress {
+            phase,
+            slot,
+            done,
+            total,
+        },
+    );
+}
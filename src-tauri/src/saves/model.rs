@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// A campaign checkpoint, filled in by the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveGame {
+    pub level_id: String,
+    pub player: PlayerStats,
+    #[serde(default)]
+    pub inventory: Vec<InventoryItem>,
+    pub playtime_secs: f64,
+    /// Unix time in milliseconds when the save was made.
+    pub timestamp_ms: u64,
+}
+
+/// Mirrors the persistent parts of `GameState` in `GameState.ts`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerStats {
+    pub health: f64,
+    pub ammo: u32,
+    pub grenades: u32,
+    pub score: u64,
+    pub current_weapon: String,
+    pub stance: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryItem {
+    pub item_id: String,
+    pub count: u32,
+}
+
+/// The part of a save stored ahead of the payload so the slot list can be
+/// built without reading whole files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveSummary {
+    pub level_id: String,
+    pub playtime_secs: f64,
+    pub timestamp_ms: u64,
+}
+
+impl From<&SaveGame> for SaveSummary {
+    fn from(save: &SaveGame) -> Self {
+        Self {
+            level_id: save.level_id.clone(),
+            playtime_secs: save.playtime_secs,
+            timestamp_ms: save.timestamp_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveMetadata {
+    pub slot: u8,
+    #[serde(flatten)]
+    pub summary: SaveSummary,
+    pub format_version: u32,
+    pub file_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SaveSlotInfo {
+    Ok(SaveMetadata),
+    /// The slot has a file that can't be read; the UI should still show it
+    /// so the player doesn't overwrite it by accident.
+    Damaged {
+        slot: u8,
+        kind: &'static str,
+        message: String,
+    },
+}
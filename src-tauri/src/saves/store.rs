@@ -0,0 +1,111 @@
+use super::format::{self, open_error};
+use super::model::{SaveGame, SaveMetadata, SaveSlotInfo};
+use super::SaveError;
+use crate::fs_atomic::write_atomic;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Runtime;
+
+pub const SAVE_DIR: &str = "saves";
+pub const SAVE_EXTENSION: &str = "sav";
+
+/// Serializes writes and deletes so two saves to one slot can't race on the
+/// temp file.
+#[derive(Default)]
+pub struct SaveStore {
+    pub(super) lock: Mutex<()>,
+}
+
+/// `saves/` inside the active profile's directory.
+pub fn saves_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, SaveError> {
+    crate::profiles::active_dir(app)
+        .map(|dir| dir.join(SAVE_DIR))
+        .map_err(|e| SaveError::NoDataDir(e.to_string()))
+}
+
+pub fn slot_path(dir: &Path, slot: u8) -> PathBuf {
+    dir.join(format!("slot-{:03}.{}", slot, SAVE_EXTENSION))
+}
+
+pub(super) fn slot_from_path(path: &Path) -> Option<u8> {
+    if path.extension()? != SAVE_EXTENSION {
+        return None;
+    }
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix("slot-")?
+        .parse()
+        .ok()
+}
+
+pub fn write(dir: &Path, slot: u8, save: &SaveGame) -> Result<(), SaveError> {
+    let path = slot_path(dir, slot);
+    let bytes = format::encode(save).map_err(|e| {
+        SaveError::io(
+            &path,
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })?;
+    write_atomic(&path, &bytes).map_err(|e| SaveError::io(&path, e))
+}
+
+pub fn read(dir: &Path, slot: u8) -> Result<SaveGame, SaveError> {
+    let path = slot_path(dir, slot);
+    let bytes = std::fs::read(&path).map_err(|e| open_error(&path, slot, e))?;
+    format::decode(&bytes, slot)
+}
+
+/// Returns whether a save was removed.
+pub fn delete(dir: &Path, slot: u8) -> Result<bool, SaveError> {
+    let path = slot_path(dir, slot);
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(SaveError::io(&path, e)),
+    }
+}
+
+/// Summaries of every slot on disk, newest first. Damaged slots are listed
+/// after the readable ones, by slot number.
+pub fn list(dir: &Path) -> Result<Vec<SaveSlotInfo>, SaveError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(SaveError::io(dir, e)),
+    };
+
+    let mut ok = Vec::new();
+    let mut damaged = Vec::new();
+    for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+        let Some(slot) = slot_from_path(&path) else {
+            continue;
+        };
+        match format::read_summary(&path, slot) {
+            Ok((summary, format_version, file_size)) => ok.push(SaveMetadata {
+                slot,
+                summary,
+                format_version,
+                file_size,
+            }),
+            Err(e) => damaged.push((slot, e)),
+        }
+    }
+
+    ok.sort_by(|a, b| {
+        b.summary
+            .timestamp_ms
+            .cmp(&a.summary.timestamp_ms)
+            .then(a.slot.cmp(&b.slot))
+    });
+    damaged.sort_by_key(|(slot, _)| *slot);
+
+    Ok(ok
+        .into_iter()
+        .map(SaveSlotInfo::Ok)
+        .chain(damaged.into_iter().map(|(slot, e)| SaveSlotInfo::Damaged {
+            slot,
+            kind: e.kind(),
+            message: e.to_string(),
+        }))
+        .collect())
+}
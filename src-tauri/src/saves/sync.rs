@@ -0,0 +1,428 @@
+//! Cloud save sync against a small REST service.
+//!
+//! Contract (all requests carry `Authorization: Bearer <token>`):
+//!
+//! ```text
+//! GET  {endpoint}/saves          -> [{ "slot": 0, "etag": "...", "modifiedMs": 0 }]
+//! GET  {endpoint}/saves/{slot}   -> raw save file bytes
+//! PUT  {endpoint}/saves/{slot}   <- raw save file bytes, `X-Modified-Ms` header
+//!                                -> { "etag": "...", "modifiedMs": 0 }
+//! ```
+//!
+//! A baseline of what each slot looked like after the last successful sync
+//! is kept in `saves/sync-state.json`. A side "changed" if its content (local
+//! CRC) or etag (remote) differs from the baseline; when both did, the slot
+//! is reported as a conflict and left alone. Deleting a save is not synced.
+
+use super::format;
+use super::store::slot_path;
+use super::SaveError;
+use crate::fs_atomic::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub const PROGRESS_EVENT: &str = "save-sync-progress";
+
+const BASELINE_FILE: &str = "sync-state.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSide {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveConflict {
+    pub slot: u8,
+    /// `timestampMs` from the local save.
+    pub local_timestamp_ms: u64,
+    pub remote_modified_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub uploaded: Vec<u8>,
+    pub downloaded: Vec<u8>,
+    pub unchanged: usize,
+    pub conflicts: Vec<SaveConflict>,
+    /// Local files that failed to read (damaged, or from a newer build).
+    /// They are neither uploaded nor overwritten.
+    pub skipped: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncPhase {
+    Listing,
+    Uploading,
+    Downloading,
+    Writing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProgress {
+    pub phase: SyncPhase,
+    pub slot: Option<u8>,
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Only one sync (or conflict resolution) runs at a time.
+#[derive(Default)]
+pub struct SyncState {
+    running: AtomicBool,
+}
+
+impl SyncState {
+    pub fn try_begin(&self) -> Result<SyncPermit<'_>, SaveError> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(SaveError::Busy(
+                "a save sync is already running".to_string(),
+            ));
+        }
+        Ok(SyncPermit { state: self })
+    }
+}
+
+pub struct SyncPermit<'a> {
+    state: &'a SyncState,
+}
+
+impl Drop for SyncPermit<'_> {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::Release);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSlot {
+    pub slot: u8,
+    pub etag: String,
+    pub modified_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PutResponse {
+    etag: String,
+    modified_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlotBaseline {
+    etag: String,
+    remote_modified_ms: u64,
+    local_crc: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    slots: BTreeMap<u8, SlotBaseline>,
+}
+
+impl Baseline {
+    fn path(dir: &Path) -> PathBuf {
+        dir.join(BASELINE_FILE)
+    }
+
+    /// A missing or unreadable baseline just means every slot present on
+    /// both sides is treated as a conflict once.
+    pub fn load(dir: &Path) -> Self {
+        std::fs::read(Self::path(dir))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), SaveError> {
+        let path = Self::path(dir);
+        let json = serde_json::to_vec_pretty(self).expect("baseline serializes");
+        write_atomic(&path, &json).map_err(|e| SaveError::io(&path, e))
+    }
+
+    fn get(&self, slot: u8) -> Option<&SlotBaseline> {
+        self.slots.get(&slot)
+    }
+
+    pub fn record(&mut self, slot: u8, remote: &RemoteSlot, local_crc: u32) {
+        self.slots.insert(
+            slot,
+            SlotBaseline {
+                etag: remote.etag.clone(),
+                remote_modified_ms: remote.modified_ms,
+                local_crc,
+            },
+        );
+    }
+
+    /// Records a slot and saves straight away. Uploads go through this one
+    /// at a time: if a later one fails, the slots already on the server must
+    /// not look changed on both sides next sync.
+    pub fn record_saved(
+        &mut self,
+        dir: &Path,
+        slot: u8,
+        remote: &RemoteSlot,
+        local_crc: u32,
+    ) -> Result<(), SaveError> {
+        self.record(slot, remote, local_crc);
+        self.save(dir)
+    }
+}
+
+/// A readable local save with its raw bytes.
+pub struct LocalSlot {
+    pub bytes: Vec<u8>,
+    pub crc: u32,
+    pub timestamp_ms: u64,
+}
+
+/// The local side of one slot.
+pub enum LocalState {
+    Empty,
+    Readable(LocalSlot),
+    /// A file is there but this build can't use it.
+    Unreadable,
+}
+
+impl LocalState {
+    pub fn read(dir: &Path, slot: u8) -> Self {
+        let bytes = match std::fs::read(slot_path(dir, slot)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return LocalState::Empty,
+            Err(_) => return LocalState::Unreadable,
+        };
+        match format::decode(&bytes, slot) {
+            Ok(save) => LocalState::Readable(LocalSlot {
+                crc: crc32fast::hash(&bytes),
+                timestamp_ms: save.timestamp_ms,
+                bytes,
+            }),
+            Err(_) => LocalState::Unreadable,
+        }
+    }
+
+    pub fn crc(&self) -> Option<u32> {
+        match self {
+            LocalState::Readable(local) => Some(local.crc),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with one slot, decided from the baseline.
+pub enum Plan {
+    Upload,
+    Download(RemoteSlot),
+    Conflict(SaveConflict),
+    Unchanged,
+    Skip,
+}
+
+pub fn plan(
+    slot: u8,
+    local: &LocalState,
+    remote: Option<&RemoteSlot>,
+    baseline: &Baseline,
+) -> Plan {
+    let local = match local {
+        LocalState::Unreadable => return Plan::Skip,
+        LocalState::Empty => None,
+        LocalState::Readable(local) => Some(local),
+    };
+    match (local, remote) {
+        (Some(_), None) => Plan::Upload,
+        (None, Some(remote)) => Plan::Download(remote.clone()),
+        (None, None) => Plan::Unchanged,
+        (Some(local), Some(remote)) => {
+            let (local_changed, remote_changed) = match baseline.get(slot) {
+                Some(base) => (local.crc != base.local_crc, remote.etag != base.etag),
+                None => (true, true),
+            };
+            match (local_changed, remote_changed) {
+                (false, false) => Plan::Unchanged,
+                (true, false) => Plan::Upload,
+                (false, true) => Plan::Download(remote.clone()),
+                (true, true) => Plan::Conflict(SaveConflict {
+                    slot,
+                    local_timestamp_ms: local.timestamp_ms,
+                    remote_modified_ms: remote.modified_ms,
+                }),
+            }
+        }
+    }
+}
+
+/// Every slot number present locally (readable or not) or remotely.
+pub fn slot_union(local_dir: &Path, remote: &[RemoteSlot]) -> BTreeSet<u8> {
+    let mut slots: BTreeSet<u8> = remote.iter().map(|r| r.slot).collect();
+    if let Ok(entries) = std::fs::read_dir(local_dir) {
+        for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+            if let Some(slot) = super::store::slot_from_path(&path) {
+                slots.insert(slot);
+            }
+        }
+    }
+    slots
+}
+
+pub struct SyncClient {
+    http: reqwest::Client,
+    endpoint: String,
+    token: String,
+}
+
+impl SyncClient {
+    pub fn new(endpoint: &str, token: &str) -> Result<Self, SaveError> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(network)?;
+        Ok(Self {
+            http,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        })
+    }
+
+    fn url(&self, slot: Option<u8>) -> String {
+        match slot {
+            Some(slot) => format!("{}/saves/{}", self.endpoint, slot),
+            None => format!("{}/saves", self.endpoint),
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<RemoteSlot>, SaveError> {
+        self.http
+            .get(self.url(None))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(network)?
+            .json()
+            .await
+            .map_err(network)
+    }
+
+    /// Downloads a slot and checks it is a save this build can read before
+    /// it is allowed anywhere near the local file.
+    pub async fn download(&self, slot: u8) -> Result<Vec<u8>, SaveError> {
+        let bytes = self
+            .http
+            .get(self.url(Some(slot)))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(network)?
+            .bytes()
+            .await
+            .map_err(network)?
+            .to_vec();
+        format::decode(&bytes, slot)?;
+        Ok(bytes)
+    }
+
+    pub async fn upload(&self, slot: u8, local: &LocalSlot) -> Result<RemoteSlot, SaveError> {
+        let response: PutResponse = self
+            .http
+            .put(self.url(Some(slot)))
+            .bearer_auth(&self.token)
+            .header("X-Modified-Ms", local.timestamp_ms.to_string())
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(local.bytes.clone())
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(network)?
+            .json()
+            .await
+            .map_err(network)?;
+        Ok(RemoteSlot {
+            slot,
+            etag: response.etag,
+            modified_ms: response.modified_ms,
+        })
+    }
+}
+
+fn network(e: reqwest::Error) -> SaveError {
+    SaveError::Network(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(crc: u32) -> LocalState {
+        LocalState::Readable(LocalSlot {
+            bytes: Vec::new(),
+            crc,
+            timestamp_ms: 1_000,
+        })
+    }
+
+    fn remote(slot: u8, etag: &str) -> RemoteSlot {
+        RemoteSlot {
+            slot,
+            etag: etag.to_string(),
+            modified_ms: 2_000,
+        }
+    }
+
+    fn planned(slot: u8, crc: u32, etag: &str, baseline: &Baseline) -> &'static str {
+        match plan(slot, &local(crc), Some(&remote(slot, etag)), baseline) {
+            Plan::Upload => "upload",
+            Plan::Download(_) => "download",
+            Plan::Conflict(_) => "conflict",
+            Plan::Unchanged => "unchanged",
+            Plan::Skip => "skip",
+        }
+    }
+
+    #[test]
+    fn each_side_changing_alone_moves_it_and_both_is_a_conflict() {
+        let mut baseline = Baseline::default();
+        assert_eq!(planned(0, 1, "a", &baseline), "conflict");
+        baseline.record(0, &remote(0, "a"), 1);
+        assert_eq!(planned(0, 1, "a", &baseline), "unchanged");
+        assert_eq!(planned(0, 2, "a", &baseline), "upload");
+        assert_eq!(planned(0, 1, "b", &baseline), "download");
+        assert_eq!(planned(0, 2, "b", &baseline), "conflict");
+        assert!(matches!(
+            plan(0, &LocalState::Unreadable, None, &baseline),
+            Plan::Skip
+        ));
+    }
+
+    #[test]
+    fn uploads_before_a_failed_one_stay_in_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut baseline = Baseline::default();
+        baseline.record(1, &remote(1, "old-1"), 10);
+        baseline.record(2, &remote(2, "old-2"), 20);
+        baseline.save(dir.path()).unwrap();
+
+        // Slots 1 and 2 changed locally. Slot 1 uploads and gets a new etag;
+        // slot 2's upload fails and the sync stops there.
+        baseline
+            .record_saved(dir.path(), 1, &remote(1, "new-1"), 11)
+            .unwrap();
+        drop(baseline);
+
+        let baseline = Baseline::load(dir.path());
+        assert_eq!(planned(1, 11, "new-1", &baseline), "unchanged");
+        assert_eq!(planned(2, 21, "old-2", &baseline), "upload");
+    }
+}
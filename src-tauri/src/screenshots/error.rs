@@ -0,0 +1,63 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the screenshot commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum ScreenshotError {
+    #[error("Could not locate the pictures directory: {0}")]
+    NoPictureDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Screenshot is {size} bytes, more than the {max} byte limit")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Screenshot is not a valid PNG: {0}")]
+    InvalidPng(String),
+
+    #[error("{0:?} is not a screenshot")]
+    NotScreenshot(PathBuf),
+
+    #[error("Could not open the screenshot folder: {0}")]
+    Open(String),
+}
+
+impl ScreenshotError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        ScreenshotError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        ScreenshotError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScreenshotError::NoPictureDir(_) => "noPictureDir",
+            ScreenshotError::Io { .. } => "io",
+            ScreenshotError::TooLarge { .. } => "tooLarge",
+            ScreenshotError::InvalidPng(_) => "invalidPng",
+            ScreenshotError::NotScreenshot(_) => "notScreenshot",
+            ScreenshotError::Open(_) => "open",
+        }
+    }
+}
+
+impl Serialize for ScreenshotError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ScreenshotError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
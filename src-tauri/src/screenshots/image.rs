@@ -0,0 +1,140 @@
+//! PNG checking with the `png` crate, and JPEG thumbnails with `image`.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{imageops, ImageBuffer};
+use std::io::Cursor;
+
+/// Wider than any display the game runs on.
+const MAX_DIMENSION: u32 = 16_384;
+/// Memory the decoder may use; an 8K capture at 16-bit RGBA fits.
+const MAX_DECODED_BYTES: usize = 512 * 1024 * 1024;
+/// Thumbnails fit in this box, keeping the aspect ratio.
+pub const THUMBNAIL_WIDTH: u32 = 320;
+pub const THUMBNAIL_HEIGHT: u32 = 180;
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// A decoded image as 8-bit RGB.
+pub struct Rgb {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Decodes all of `bytes`, checksums included, so a truncated or
+/// corrupted capture is caught before it's saved.
+pub fn decode(bytes: &[u8]) -> Result<Rgb, String> {
+    let limits = png::Limits {
+        bytes: MAX_DECODED_BYTES,
+    };
+    let mut decoder = png::Decoder::new_with_limits(Cursor::new(bytes), limits);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(format!("{} x {} is too large", width, height));
+    }
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    reader.finish().map_err(|e| e.to_string())?;
+
+    let channels = frame.color_type.samples();
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+    for row in buf.chunks_exact(frame.line_size).take(height as usize) {
+        for px in row[..width as usize * channels].chunks_exact(channels) {
+            // Grey and grey-alpha spread to all three channels; alpha is
+            // dropped, canvas captures being opaque.
+            match channels {
+                1 | 2 => pixels.extend_from_slice(&[px[0]; 3]),
+                _ => pixels.extend_from_slice(&px[..3]),
+            }
+        }
+    }
+    Ok(Rgb {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Scales `image` down to fit the thumbnail box and encodes it as JPEG.
+/// Images already small enough keep their size.
+pub fn thumbnail(image: &Rgb) -> Result<Vec<u8>, String> {
+    let scale = (THUMBNAIL_WIDTH as f64 / image.width as f64)
+        .min(THUMBNAIL_HEIGHT as f64 / image.height as f64)
+        .min(1.0);
+    let width = ((image.width as f64 * scale).round() as u32).max(1);
+    let height = ((image.height as f64 * scale).round() as u32).max(1);
+
+    let source = ImageBuffer::<image::Rgb<u8>, _>::from_raw(
+        image.width,
+        image.height,
+        image.pixels.as_slice(),
+    )
+    .ok_or("the pixel buffer doesn't match the image size")?;
+    // `thumbnail` averages the block of source pixels under each output
+    // pixel, which is what a picture this much smaller wants.
+    let small = imageops::thumbnail(&source, width, height);
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, THUMBNAIL_QUALITY)
+        .encode_image(&small)
+        .map_err(|e| e.to_string())?;
+    Ok(jpeg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> Rgb {
+        let pixels = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [
+                    (x * 255 / width) as u8,
+                    (y * 255 / height) as u8,
+                    ((x + y) % 64 * 4) as u8,
+                ]
+            })
+            .collect();
+        Rgb {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn decode_jpeg(jpeg: &[u8]) -> image::RgbImage {
+        image::load_from_memory_with_format(jpeg, image::ImageFormat::Jpeg)
+            .unwrap()
+            .to_rgb8()
+    }
+
+    #[test]
+    fn thumbnails_decode_close_to_the_scaled_picture() {
+        let source = gradient(1280, 720);
+        let jpeg = thumbnail(&source).unwrap();
+        assert_eq!(&jpeg[..3], [0xff, 0xd8, 0xff]);
+        let decoded = decode_jpeg(&jpeg);
+        assert_eq!(decoded.dimensions(), (THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT));
+
+        let view = ImageBuffer::<image::Rgb<u8>, _>::from_raw(1280, 720, &source.pixels[..]);
+        let expected = imageops::thumbnail(&view.unwrap(), THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+        let squared: f64 = expected
+            .as_raw()
+            .iter()
+            .zip(decoded.as_raw())
+            .map(|(a, b)| (*a as f64 - *b as f64).powi(2))
+            .sum();
+        let mse = squared / expected.as_raw().len() as f64;
+        let psnr = 10.0 * (255.0f64.powi(2) / mse).log10();
+        assert!(psnr > 30.0, "PSNR {:.1} dB", psnr);
+    }
+
+    #[test]
+    fn thumbnails_keep_the_aspect_ratio_and_small_sizes() {
+        let size = |w, h| decode_jpeg(&thumbnail(&gradient(w, h)).unwrap()).dimensions();
+        assert_eq!(size(1000, 1000), (180, 180));
+        assert_eq!(size(100, 50), (100, 50));
+        assert_eq!(size(5000, 3), (320, 1));
+    }
+}
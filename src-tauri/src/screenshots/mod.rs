@@ -0,0 +1,355 @@
+mod error;
+mod image;
+
+pub use error::ScreenshotError;
+
+use crate::time::{now_ms, to_ms, UtcDateTime};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::path::BaseDirectory;
+use tauri::Manager;
+use tauri_plugin_opener::OpenerExt;
+
+/// Under the user's pictures directory.
+const DIR_NAME: &str = "fps-game";
+/// Under the app cache directory.
+const THUMBNAIL_DIR: &str = "screenshot-thumbnails";
+const PREFIX: &str = "screenshot";
+const EXTENSION: &str = "png";
+const THUMBNAIL_EXTENSION: &str = "jpg";
+const SIDECAR_EXTENSION: &str = "json";
+/// An uncompressed 8K RGBA capture is about 130 MB; real canvas PNGs are
+/// a fraction of that.
+const MAX_PNG_BYTES: usize = 64 * 1024 * 1024;
+
+/// What was going on when the screenshot was taken.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotMetadata {
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub score: Option<i64>,
+}
+
+/// `<screenshot>.json`, written next to each screenshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sidecar {
+    captured_at_ms: u64,
+    width: u32,
+    height: u32,
+    #[serde(flatten)]
+    metadata: ScreenshotMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotInfo {
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    /// When the file was created, or last modified where the filesystem
+    /// doesn't record creation.
+    pub created_ms: u64,
+    /// From the sidecar; `None` for PNGs saved some other way.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub metadata: Option<ScreenshotMetadata>,
+    /// A JPEG at most 320 x 180, or `None` if the file doesn't decode.
+    /// The bytes are sent as a `data:image/jpeg;base64,` URL rather than
+    /// raw: serde would make a `Vec<u8>` a JSON array of numbers, several
+    /// times the size, and the gallery can put a data URL straight into an
+    /// `<img>`.
+    pub thumbnail: Option<String>,
+}
+
+fn screenshots_dir(app: &tauri::AppHandle) -> Result<PathBuf, ScreenshotError> {
+    app.path()
+        .resolve(DIR_NAME, BaseDirectory::Picture)
+        .map_err(|e| ScreenshotError::NoPictureDir(e.to_string()))
+}
+
+/// `None` only when the platform has no cache directory, in which case
+/// thumbnails are made fresh every time.
+fn thumbnails_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_cache_dir()
+        .ok()
+        .map(|dir| dir.join(THUMBNAIL_DIR))
+}
+
+/// `screenshot-2026-03-01_18-04-33-512` for a UTC timestamp, so names sort
+/// by time.
+fn file_stem(timestamp_ms: u64) -> String {
+    let t = UtcDateTime::from_ms(timestamp_ms);
+    format!(
+        "{}-{:04}-{:02}-{:02}_{:02}-{:02}-{:02}-{:03}",
+        PREFIX, t.year, t.month, t.day, t.hour, t.minute, t.second, t.millis
+    )
+}
+
+/// Creates an empty file under a name nothing else has, suffixing `-N`
+/// when two screenshots land in the same millisecond. Creating it claims
+/// the name, so concurrent saves can't pick the same one.
+fn reserve(dir: &Path, stem: &str) -> Result<PathBuf, ScreenshotError> {
+    let mut n = 0;
+    loop {
+        let name = match n {
+            0 => format!("{}.{}", stem, EXTENSION),
+            n => format!("{}-{}.{}", stem, n, EXTENSION),
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(ScreenshotError::io(&path, e)),
+        }
+    }
+}
+
+fn thumbnail_path(thumbnails: &Path, screenshot: &Path) -> PathBuf {
+    let stem = screenshot.file_stem().unwrap_or_default().to_string_lossy();
+    thumbnails.join(format!("{}.{}", stem, THUMBNAIL_EXTENSION))
+}
+
+fn write_thumbnail(thumbnails: Option<&Path>, screenshot: &Path, bytes: &[u8]) {
+    let Some(path) = thumbnails.map(|dir| thumbnail_path(dir, screenshot)) else {
+        return;
+    };
+    if let Err(e) = crate::fs_atomic::write_atomic(&path, bytes) {
+        log::warn!("Could not cache thumbnail {:?}: {}", path, e);
+    }
+}
+
+/// The cached thumbnail for `screenshot` if it's at least as new as the
+/// file, otherwise a fresh one, cached for next time.
+fn load_thumbnail(
+    thumbnails: Option<&Path>,
+    screenshot: &Path,
+    modified: SystemTime,
+) -> Option<Vec<u8>> {
+    if let Some(path) = thumbnails.map(|dir| thumbnail_path(dir, screenshot)) {
+        let fresh = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|cached| cached >= modified);
+        if fresh {
+            if let Ok(bytes) = std::fs::read(&path) {
+                return Some(bytes);
+            }
+        }
+    }
+    let made = std::fs::read(screenshot)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| image::decode(&bytes))
+        .and_then(|decoded| image::thumbnail(&decoded));
+    match made {
+        Ok(bytes) => {
+            write_thumbnail(thumbnails, screenshot, &bytes);
+            Some(bytes)
+        }
+        Err(e) => {
+            log::warn!("No thumbnail for {:?}: {}", screenshot, e);
+            None
+        }
+    }
+}
+
+fn read_sidecar(screenshot: &Path) -> Option<Sidecar> {
+    let bytes = std::fs::read(screenshot.with_extension(SIDECAR_EXTENSION)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn is_png(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+fn list(dir: &Path, thumbnails: Option<&Path>) -> Result<Vec<ScreenshotInfo>, ScreenshotError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ScreenshotError::io(dir, e)),
+    };
+    let mut shots = Vec::new();
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let Ok(meta) = std::fs::metadata(&path) else {
+            continue;
+        };
+        // Empty files are names still being written by `save_screenshot`.
+        if !meta.is_file() || !is_png(&path) || meta.len() == 0 {
+            continue;
+        }
+        let modified = meta.modified().unwrap_or(UNIX_EPOCH);
+        let sidecar = read_sidecar(&path);
+        shots.push(ScreenshotInfo {
+            path: path.to_string_lossy().into_owned(),
+            file_name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            size_bytes: meta.len(),
+            created_ms: to_ms(meta.created().unwrap_or(modified)),
+            width: sidecar.as_ref().map(|s| s.width),
+            height: sidecar.as_ref().map(|s| s.height),
+            metadata: sidecar.map(|s| s.metadata),
+            thumbnail: load_thumbnail(thumbnails, &path, modified).map(|jpeg| {
+                format!(
+                    "data:image/jpeg;base64,{}",
+                    base64::engine::general_purpose::STANDARD.encode(jpeg)
+                )
+            }),
+        });
+    }
+    shots.sort_by(|a, b| {
+        b.created_ms
+            .cmp(&a.created_ms)
+            .then_with(|| b.file_name.cmp(&a.file_name))
+    });
+
+    // Thumbnails of screenshots deleted outside the game, and PNG ones
+    // left from before thumbnails were JPEG.
+    if let Some(Ok(cached)) = thumbnails.map(std::fs::read_dir) {
+        for path in cached.filter_map(Result::ok).map(|entry| entry.path()) {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let orphaned = path
+                .extension()
+                .is_some_and(|ext| ext == THUMBNAIL_EXTENSION)
+                && !dir.join(format!("{}.{}", stem, EXTENSION)).exists();
+            if orphaned || is_png(&path) {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+    Ok(shots)
+}
+
+/// Checks that `path` is a PNG directly inside the screenshot folder, so
+/// the frontend can't delete anything else.
+fn screenshot_path(dir: &Path, path: &str) -> Result<PathBuf, ScreenshotError> {
+    let not_screenshot = || ScreenshotError::NotScreenshot(PathBuf::from(path));
+    let canonical = Path::new(path)
+        .canonicalize()
+        .map_err(|_| not_screenshot())?;
+    let dir = dir.canonicalize().map_err(|_| not_screenshot())?;
+    if canonical.parent() != Some(dir.as_path()) || !is_png(&canonical) || !canonical.is_file() {
+        return Err(not_screenshot());
+    }
+    Ok(canonical)
+}
+
+fn remove_if_present(path: &Path) -> Result<(), ScreenshotError> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(ScreenshotError::io(path, e)),
+    }
+}
+
+/// Saves a PNG captured from the canvas to `Pictures/fps-game/` under a
+/// timestamped name, with `metadata` in a JSON file beside it, and returns
+/// the path. The bytes are fully decoded first, so a corrupt capture fails
+/// with `invalidPng` instead of leaving a broken file. The thumbnail is
+/// made at the same time, so the gallery doesn't have to.
+#[tauri::command]
+pub async fn save_screenshot(
+    app: tauri::AppHandle,
+    png_bytes: Vec<u8>,
+    metadata: Option<ScreenshotMetadata>,
+) -> Result<String, ScreenshotError> {
+    if png_bytes.len() > MAX_PNG_BYTES {
+        return Err(ScreenshotError::TooLarge {
+            size: png_bytes.len(),
+            max: MAX_PNG_BYTES,
+        });
+    }
+    let dir = screenshots_dir(&app)?;
+    let thumbnails = thumbnails_dir(&app);
+    let task_dir = dir.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let decoded = image::decode(&png_bytes).map_err(ScreenshotError::InvalidPng)?;
+        let captured_at_ms = now_ms();
+        std::fs::create_dir_all(&task_dir).map_err(|e| ScreenshotError::io(&task_dir, e))?;
+        let path = reserve(&task_dir, &file_stem(captured_at_ms))?;
+        if let Err(e) = crate::fs_atomic::write_atomic(&path, &png_bytes) {
+            let _ = std::fs::remove_file(&path);
+            return Err(ScreenshotError::io(&path, e));
+        }
+
+        let sidecar = Sidecar {
+            captured_at_ms,
+            width: decoded.width,
+            height: decoded.height,
+            metadata: metadata.unwrap_or_default(),
+        };
+        let sidecar_path = path.with_extension(SIDECAR_EXTENSION);
+        let written = serde_json::to_vec_pretty(&sidecar)
+            .map_err(std::io::Error::other)
+            .and_then(|json| crate::fs_atomic::write_atomic(&sidecar_path, &json));
+        // The picture is what matters; losing its caption isn't worth
+        // failing the save over.
+        if let Err(e) = written {
+            log::warn!("Could not write {:?}: {}", sidecar_path, e);
+        }
+        match image::thumbnail(&decoded) {
+            Ok(jpeg) => write_thumbnail(thumbnails.as_deref(), &path, &jpeg),
+            Err(e) => log::warn!("No thumbnail for {:?}: {}", path, e),
+        }
+        log::info!(
+            "Saved {} x {} screenshot to {:?}",
+            decoded.width,
+            decoded.height,
+            path
+        );
+        Ok(path.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| ScreenshotError::task(&dir, e))?
+}
+
+/// Every PNG in the screenshot folder, newest first, with metadata and a
+/// thumbnail. Thumbnails are cached on disk and only remade when the
+/// screenshot changes.
+#[tauri::command]
+pub async fn list_screenshots(
+    app: tauri::AppHandle,
+) -> Result<Vec<ScreenshotInfo>, ScreenshotError> {
+    let dir = screenshots_dir(&app)?;
+    let thumbnails = thumbnails_dir(&app);
+    let task_dir = dir.clone();
+    tauri::async_runtime::spawn_blocking(move || list(&task_dir, thumbnails.as_deref()))
+        .await
+        .map_err(|e| ScreenshotError::task(&dir, e))?
+}
+
+/// Deletes a screenshot from the folder along with its metadata and
+/// thumbnail. Anything outside the folder is refused with
+/// `notScreenshot`.
+#[tauri::command]
+pub async fn delete_screenshot(app: tauri::AppHandle, path: String) -> Result<(), ScreenshotError> {
+    let dir = screenshots_dir(&app)?;
+    let path = screenshot_path(&dir, &path)?;
+    std::fs::remove_file(&path).map_err(|e| ScreenshotError::io(&path, e))?;
+    remove_if_present(&path.with_extension(SIDECAR_EXTENSION))?;
+    if let Some(thumbnails) = thumbnails_dir(&app) {
+        remove_if_present(&thumbnail_path(&thumbnails, &path))?;
+    }
+    Ok(())
+}
+
+/// Opens the screenshot folder in the system file manager, creating it
+/// first if nothing has been saved yet.
+#[tauri::command]
+pub async fn open_screenshot_folder(app: tauri::AppHandle) -> Result<(), ScreenshotError> {
+    let dir = screenshots_dir(&app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| ScreenshotError::io(&dir, e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| ScreenshotError::Open(e.to_string()))
+}
@@ -0,0 +1,58 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the hosting commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    #[error("Already hosting on port {port}")]
+    AlreadyHosting { port: u16 },
+
+    #[error("Not hosting a server")]
+    NotHosting,
+
+    #[error("Failed to listen on port {port}: {source}")]
+    Bind {
+        port: u16,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid host settings: {0}")]
+    InvalidConfig(String),
+
+    #[error("No player in slot {0}")]
+    UnknownPlayer(u8),
+
+    #[error("Failed to start a host thread: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    #[error("Background host task failed: {0}")]
+    Task(String),
+}
+
+impl ServerError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServerError::AlreadyHosting { .. } => "alreadyHosting",
+            ServerError::NotHosting => "notHosting",
+            ServerError::Bind { .. } => "bind",
+            ServerError::InvalidConfig(_) => "invalidConfig",
+            ServerError::UnknownPlayer(_) => "unknownPlayer",
+            ServerError::Spawn(_) => "spawn",
+            ServerError::Task(_) => "task",
+        }
+    }
+}
+
+impl Serialize for ServerError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("ServerError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+pub(crate) fn task(e: tauri::Error) -> ServerError {
+    ServerError::Task(e.to_string())
+}
@@ -0,0 +1,486 @@
+//! The hosted server: an accept thread, and one thread per connection that
+//! does the join handshake and then relays the client's messages. Writes to
+//! a client go through its own lock, so a slow or dead client only ever
+//! holds up itself.
+
+use super::protocol::{ClientHello, Control, LeaveReason, PlayerSummary};
+use super::ws::{self, Message};
+use super::ServerError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufReader, ErrorKind, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const PLAYER_JOINED_EVENT: &str = "player-joined";
+pub const PLAYER_LEFT_EVENT: &str = "player-left";
+pub const HOST_MESSAGE_EVENT: &str = "host-message";
+
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+/// Time a new connection gets to upgrade and join.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// A client that can't take a write this quickly is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+/// Connections still handshaking, on top of the joined players.
+const MAX_PENDING: usize = 16;
+const MAX_NAME_LEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerJoined {
+    pub slot: u8,
+    pub name: String,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerLeft {
+    pub slot: u8,
+    pub name: String,
+    pub reason: LeaveReason,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// A gameplay message from a client, as seen by the hosting frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMessage {
+    pub slot: u8,
+    /// A string for text frames, an array of bytes for binary ones.
+    pub payload: Payload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerInfo {
+    pub slot: u8,
+    pub name: String,
+    pub address: String,
+    pub connected_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostStatus {
+    pub port: u16,
+    pub max_players: u8,
+    pub uptime_ms: u64,
+    pub players: Vec<PlayerInfo>,
+    pub messages_relayed: u64,
+    /// Payload bytes written to clients, counted once per recipient.
+    pub bytes_relayed: u64,
+}
+
+struct Client {
+    slot: u8,
+    name: String,
+    addr: SocketAddr,
+    joined: Instant,
+    writer: Mutex<TcpStream>,
+    /// Set before the host closes the connection, so the reader reports why.
+    closing: Mutex<Option<LeaveReason>>,
+}
+
+impl Client {
+    /// Writes under the client's lock; on failure the connection is shut
+    /// down, which ends its reader and removes it.
+    fn send(&self, write: impl FnOnce(&mut TcpStream) -> std::io::Result<()>) -> bool {
+        let mut writer = self.writer.lock().unwrap();
+        let ok = write(&mut writer).and_then(|_| writer.flush()).is_ok();
+        if !ok {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+        ok
+    }
+
+    /// Says why, then closes. The reader thread sees the close and cleans up.
+    fn close(&self, reason: LeaveReason, message: Control<'_>, code: u16) {
+        *self.closing.lock().unwrap() = Some(reason);
+        let mut writer = self.writer.lock().unwrap();
+        let _ = ws::write_text(&mut *writer, &message.to_json());
+        let _ = ws::write_close(&mut *writer, code, "");
+        let _ = writer.shutdown(Shutdown::Both);
+    }
+}
+
+/// Lets the reader answer pings through the client's lock. `ws` writes
+/// each frame in one call, so one lock per `write` keeps frames whole.
+struct Locked<'a>(&'a Mutex<TcpStream>);
+
+impl Write for Locked<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+pub struct Host {
+    pub port: u16,
+    max_players: u8,
+    password: Option<String>,
+    version: String,
+    started: Instant,
+    stopping: AtomicBool,
+    clients: Mutex<BTreeMap<u8, Arc<Client>>>,
+    /// Every open connection, joined or not, so shutdown can close them all.
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    next_connection: AtomicU64,
+    acceptor: Mutex<Option<JoinHandle<()>>>,
+    threads: Mutex<Vec<JoinHandle<()>>>,
+    messages_relayed: AtomicU64,
+    bytes_relayed: AtomicU64,
+}
+
+impl Host {
+    pub fn start<R: Runtime>(
+        app: AppHandle<R>,
+        port: u16,
+        max_players: u8,
+        password: Option<String>,
+    ) -> Result<Arc<Self>, ServerError> {
+        let bind = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port);
+        let bind_err = |source| ServerError::Bind { port, source };
+        let listener = TcpListener::bind(bind).map_err(bind_err)?;
+        listener.set_nonblocking(true).map_err(bind_err)?;
+        let host = Arc::new(Self {
+            port: listener.local_addr().map_err(bind_err)?.port(),
+            max_players,
+            password,
+            version: app.package_info().version.to_string(),
+            started: Instant::now(),
+            stopping: AtomicBool::new(false),
+            clients: Mutex::new(BTreeMap::new()),
+            connections: Mutex::new(HashMap::new()),
+            next_connection: AtomicU64::new(0),
+            acceptor: Mutex::new(None),
+            threads: Mutex::new(Vec::new()),
+            messages_relayed: AtomicU64::new(0),
+            bytes_relayed: AtomicU64::new(0),
+        });
+
+        let accepting = host.clone();
+        let thread = std::thread::Builder::new()
+            .name("host-accept".into())
+            .spawn(move || accepting.accept_loop(&app, listener))
+            .map_err(ServerError::Spawn)?;
+        *host.acceptor.lock().unwrap() = Some(thread);
+        Ok(host)
+    }
+
+    pub fn status(&self) -> HostStatus {
+        let players = self
+            .clients
+            .lock()
+            .unwrap()
+            .values()
+            .map(|c| PlayerInfo {
+                slot: c.slot,
+                name: c.name.clone(),
+                address: c.addr.to_string(),
+                connected_ms: c.joined.elapsed().as_millis() as u64,
+            })
+            .collect();
+        HostStatus {
+            port: self.port,
+            max_players: self.max_players,
+            uptime_ms: self.started.elapsed().as_millis() as u64,
+            players,
+            messages_relayed: self.messages_relayed.load(Ordering::Relaxed),
+            bytes_relayed: self.bytes_relayed.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn kick(&self, slot: u8) -> Result<(), ServerError> {
+        let client = self
+            .clients
+            .lock()
+            .unwrap()
+            .get(&slot)
+            .cloned()
+            .ok_or(ServerError::UnknownPlayer(slot))?;
+        client.close(LeaveReason::Kicked, Control::Kicked, ws::CLOSE_POLICY);
+        Ok(())
+    }
+
+    /// Tells every client the host is going away, closes every connection
+    /// and waits for all threads to finish.
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        // Once the acceptor is gone no new connection can appear.
+        if let Some(acceptor) = self.acceptor.lock().unwrap().take() {
+            let _ = acceptor.join();
+        }
+        let clients: Vec<_> = self.clients.lock().unwrap().values().cloned().collect();
+        for client in clients {
+            client.close(
+                LeaveReason::Shutdown,
+                Control::Shutdown,
+                ws::CLOSE_GOING_AWAY,
+            );
+        }
+        for stream in self.connections.lock().unwrap().values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        let threads = std::mem::take(&mut *self.threads.lock().unwrap());
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+
+    fn accept_loop<R: Runtime>(self: &Arc<Self>, app: &AppHandle<R>, listener: TcpListener) {
+        while !self.stopping.load(Ordering::Relaxed) {
+            let (stream, addr) = match listener.accept() {
+                Ok(accepted) => accepted,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(ACCEPT_POLL);
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("host accept failed: {}", e);
+                    std::thread::sleep(ACCEPT_POLL);
+                    continue;
+                }
+            };
+
+            let mut threads = self.threads.lock().unwrap();
+            threads.retain(|t| !t.is_finished());
+            let open = self.connections.lock().unwrap().len();
+            if open >= self.max_players as usize + MAX_PENDING {
+                continue;
+            }
+            let Ok(registered) = stream.try_clone() else {
+                continue;
+            };
+            let id = self.next_connection.fetch_add(1, Ordering::Relaxed);
+            self.connections.lock().unwrap().insert(id, registered);
+
+            let host = self.clone();
+            let app = app.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("host-conn-{}", id))
+                .spawn(move || {
+                    host.serve(&app, stream, addr);
+                    host.connections.lock().unwrap().remove(&id);
+                });
+            match spawned {
+                Ok(thread) => threads.push(thread),
+                Err(_) => {
+                    self.connections.lock().unwrap().remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Handshake, then relay until the connection ends for any reason.
+    fn serve<R: Runtime>(&self, app: &AppHandle<R>, stream: TcpStream, addr: SocketAddr) {
+        let Some((client, mut reader)) = self.handshake(stream, addr) else {
+            return;
+        };
+        let _ = app.emit(
+            PLAYER_JOINED_EVENT,
+            PlayerJoined {
+                slot: client.slot,
+                name: client.name.clone(),
+                address: addr.to_string(),
+            },
+        );
+        let joined = Control::PlayerJoined {
+            slot: client.slot,
+            name: &client.name,
+        }
+        .to_json();
+        self.broadcast(client.slot, |w| ws::write_text(w, &joined));
+
+        let reason = loop {
+            match ws::read_message(&mut reader, &mut Locked(&client.writer), MAX_MESSAGE_LEN) {
+                Ok(Message::Text(text)) => {
+                    let relay = Control::Relay {
+                        from: client.slot,
+                        data: &text,
+                    }
+                    .to_json();
+                    let sent = self.broadcast(client.slot, |w| ws::write_text(w, &relay));
+                    self.count(sent, text.len());
+                    let _ = app.emit(
+                        HOST_MESSAGE_EVENT,
+                        HostMessage {
+                            slot: client.slot,
+                            payload: Payload::Text(text),
+                        },
+                    );
+                }
+                Ok(Message::Binary(data)) => {
+                    let mut framed = Vec::with_capacity(data.len() + 1);
+                    framed.push(client.slot);
+                    framed.extend_from_slice(&data);
+                    let sent = self.broadcast(client.slot, |w| ws::write_binary(w, &framed));
+                    self.count(sent, data.len());
+                    let _ = app.emit(
+                        HOST_MESSAGE_EVENT,
+                        HostMessage {
+                            slot: client.slot,
+                            payload: Payload::Binary(data),
+                        },
+                    );
+                }
+                Ok(Message::Close(_)) => {
+                    client.send(|w| ws::write_close(w, ws::CLOSE_NORMAL, ""));
+                    break LeaveReason::Left;
+                }
+                Err(e) => {
+                    if e.get_ref().is_some_and(|inner| inner.is::<ws::TooBig>()) {
+                        client.send(|w| ws::write_close(w, ws::CLOSE_TOO_BIG, "message too big"));
+                    }
+                    break LeaveReason::Disconnected;
+                }
+            }
+        };
+        // A kick or shutdown closed the socket under the reader; report that
+        // rather than the read error it caused.
+        let reason = client.closing.lock().unwrap().unwrap_or(reason);
+        self.leave(app, &client, reason);
+    }
+
+    fn handshake(
+        &self,
+        stream: TcpStream,
+        addr: SocketAddr,
+    ) -> Option<(Arc<Client>, BufReader<TcpStream>)> {
+        stream.set_nonblocking(false).ok()?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).ok()?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+        let _ = stream.set_nodelay(true);
+        let mut writer = stream.try_clone().ok()?;
+        let mut reader = BufReader::new(stream);
+        ws::accept(&mut reader).ok()?;
+
+        let reject = |writer: &mut TcpStream, reason: &str| {
+            let _ = ws::write_text(writer, &Control::Rejected { reason }.to_json());
+            let _ = ws::write_close(writer, ws::CLOSE_POLICY, reason);
+            let _ = writer.shutdown(Shutdown::Both);
+        };
+        let hello = match ws::read_message(&mut reader, &mut writer, MAX_MESSAGE_LEN).ok()? {
+            Message::Text(text) => serde_json::from_str::<ClientHello>(&text).ok(),
+            _ => None,
+        };
+        let Some(ClientHello::Join {
+            name,
+            version,
+            password,
+        }) = hello
+        else {
+            reject(&mut writer, "expected a join message");
+            return None;
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            reject(&mut writer, "invalid player name");
+            return None;
+        }
+        if version != self.version {
+            reject(
+                &mut writer,
+                &format!("version mismatch: host runs {}", self.version),
+            );
+            return None;
+        }
+        if let Some(expected) = &self.password {
+            if !password.is_some_and(|p| constant_time_eq(p.as_bytes(), expected.as_bytes())) {
+                reject(&mut writer, "wrong password");
+                return None;
+            }
+        }
+
+        // Slot assignment and the cap check happen under one lock, so two
+        // clients joining at once can't both take the last slot.
+        let mut clients = self.clients.lock().unwrap();
+        if self.stopping.load(Ordering::Relaxed) {
+            return None;
+        }
+        let Some(slot) = (1..=self.max_players).find(|s| !clients.contains_key(s)) else {
+            drop(clients);
+            reject(&mut writer, "server is full");
+            return None;
+        };
+        let players = clients
+            .values()
+            .map(|c| PlayerSummary {
+                slot: c.slot,
+                name: c.name.clone(),
+            })
+            .collect();
+        ws::write_text(&mut writer, &Control::Welcome { slot, players }.to_json()).ok()?;
+        reader.get_ref().set_read_timeout(None).ok()?;
+        let client = Arc::new(Client {
+            slot,
+            name,
+            addr,
+            joined: Instant::now(),
+            writer: Mutex::new(writer),
+            closing: Mutex::new(None),
+        });
+        clients.insert(slot, client.clone());
+        Some((client, reader))
+    }
+
+    fn leave<R: Runtime>(&self, app: &AppHandle<R>, client: &Client, reason: LeaveReason) {
+        self.clients.lock().unwrap().remove(&client.slot);
+        let _ = client.writer.lock().unwrap().shutdown(Shutdown::Both);
+        let _ = app.emit(
+            PLAYER_LEFT_EVENT,
+            PlayerLeft {
+                slot: client.slot,
+                name: client.name.clone(),
+                reason,
+            },
+        );
+        if reason != LeaveReason::Shutdown {
+            let left = Control::PlayerLeft {
+                slot: client.slot,
+                reason,
+            }
+            .to_json();
+            self.broadcast(client.slot, |w| ws::write_text(w, &left));
+        }
+    }
+
+    /// Writes to every joined client except `from`. Returns how many writes
+    /// succeeded.
+    fn broadcast(&self, from: u8, write: impl Fn(&mut TcpStream) -> std::io::Result<()>) -> u64 {
+        let targets: Vec<_> = self
+            .clients
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|c| c.slot != from)
+            .cloned()
+            .collect();
+        targets.iter().filter(|c| c.send(&write)).count() as u64
+    }
+
+    fn count(&self, recipients: u64, len: usize) {
+        self.messages_relayed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_relayed
+            .fetch_add(recipients * len as u64, Ordering::Relaxed);
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
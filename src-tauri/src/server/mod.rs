@@ -0,0 +1,98 @@
+mod error;
+mod host;
+mod protocol;
+mod ws;
+
+pub use error::ServerError;
+pub use host::HostStatus;
+
+use error::task;
+use host::Host;
+use std::sync::{Arc, Mutex};
+use tauri::{Manager, Runtime, State};
+
+/// Largest lobby a host accepts; slots are numbered from 1.
+const MAX_PLAYERS: u8 = 64;
+
+/// The server this client is hosting, if any.
+#[derive(Default)]
+pub struct HostState {
+    host: Mutex<Option<Arc<Host>>>,
+}
+
+impl HostState {
+    fn get(&self) -> Result<Arc<Host>, ServerError> {
+        self.host
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(ServerError::NotHosting)
+    }
+}
+
+/// Starts a WebSocket server on `port` (`0` for any) that clients join with
+/// a `join` message carrying their name, game version and, when `password`
+/// is set, the password. Joined clients' messages are relayed to each other
+/// and emitted as `host-message`; `player-joined` and `player-left` report
+/// the lobby. Returns the port listened on.
+#[tauri::command]
+pub async fn start_host(
+    app: tauri::AppHandle,
+    hosting: State<'_, HostState>,
+    port: u16,
+    max_players: u8,
+    password: Option<String>,
+) -> Result<u16, ServerError> {
+    if !(1..=MAX_PLAYERS).contains(&max_players) {
+        return Err(ServerError::InvalidConfig(format!(
+            "max players must be 1 to {}",
+            MAX_PLAYERS
+        )));
+    }
+    let password = password.filter(|p| !p.is_empty());
+    let mut current = hosting.host.lock().unwrap();
+    if let Some(host) = current.as_ref() {
+        return Err(ServerError::AlreadyHosting { port: host.port });
+    }
+    let host = Host::start(app, port, max_players, password)?;
+    let port = host.port;
+    *current = Some(host);
+    Ok(port)
+}
+
+/// Tells every client the server is shutting down, then closes it.
+#[tauri::command]
+pub async fn stop_host(app: tauri::AppHandle) -> Result<(), ServerError> {
+    let host = app
+        .state::<HostState>()
+        .host
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or(ServerError::NotHosting)?;
+    // Joining waits for every connection thread to wind down.
+    tauri::async_runtime::spawn_blocking(move || host.stop())
+        .await
+        .map_err(task)
+}
+
+/// Tells the player in `slot` they were kicked and disconnects them.
+#[tauri::command]
+pub async fn kick_player(hosting: State<'_, HostState>, slot: u8) -> Result<(), ServerError> {
+    hosting.get()?.kick(slot)
+}
+
+/// `None` when not hosting.
+#[tauri::command]
+pub fn get_host_status(hosting: State<'_, HostState>) -> Option<HostStatus> {
+    hosting.get().ok().map(|host| host.status())
+}
+
+/// Stops hosting, if hosting. Called when the window closes and on exit, so
+/// clients are told rather than just dropped.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let host = app.state::<HostState>().host.lock().unwrap().take();
+    if let Some(host) = host {
+        host.stop();
+    }
+}
@@ -0,0 +1,68 @@
+//! Control messages between the host and its clients, sent as JSON text
+//! frames with a `type` field. Anything a joined client sends is gameplay
+//! traffic and relayed to every other client: text as a `relay` message
+//! naming the sender, binary with the sender's slot as a leading byte.
+
+use serde::{Deserialize, Serialize};
+
+/// The first message a client must send.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ClientHello {
+    Join {
+        name: String,
+        version: String,
+        #[serde(default)]
+        password: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSummary {
+    pub slot: u8,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LeaveReason {
+    /// The client closed the connection cleanly.
+    Left,
+    /// The connection dropped or sent something invalid.
+    Disconnected,
+    Kicked,
+    Shutdown,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Control<'a> {
+    Welcome {
+        slot: u8,
+        players: Vec<PlayerSummary>,
+    },
+    Rejected {
+        reason: &'a str,
+    },
+    PlayerJoined {
+        slot: u8,
+        name: &'a str,
+    },
+    PlayerLeft {
+        slot: u8,
+        reason: LeaveReason,
+    },
+    Kicked,
+    Shutdown,
+    Relay {
+        from: u8,
+        data: &'a str,
+    },
+}
+
+impl Control<'_> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("control message serialize")
+    }
+}
@@ -0,0 +1,469 @@
+//! The server side of RFC 6455 over a blocking `TcpStream`: the opening
+//! handshake, and reading and writing frames. Only what browsers send is
+//! supported, which means no extensions and no subprotocols.
+
+use base64::Engine;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xa;
+
+pub const CLOSE_NORMAL: u16 = 1000;
+pub const CLOSE_GOING_AWAY: u16 = 1001;
+pub const CLOSE_POLICY: u16 = 1008;
+pub const CLOSE_TOO_BIG: u16 = 1009;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    /// The peer is closing, with its status code if it sent one.
+    Close(Option<u16>),
+}
+
+/// Reads the HTTP upgrade request and answers it. Anything that isn't a
+/// WebSocket upgrade gets a 400 and an error.
+pub fn accept<S: Read + Write>(stream: &mut BufReader<S>) -> io::Result<()> {
+    let mut key = None;
+    let mut upgrade = false;
+    let mut total = 0;
+    let mut line = String::new();
+    let mut first = true;
+    loop {
+        line.clear();
+        let n = stream
+            .by_ref()
+            .take(MAX_REQUEST_LEN as u64)
+            .read_line(&mut line)?;
+        total += n;
+        if n == 0 || total > MAX_REQUEST_LEN {
+            return Err(invalid("incomplete or oversized upgrade request"));
+        }
+        let text = line.trim_end();
+        if first {
+            first = false;
+            if !text.starts_with("GET ") {
+                return reject(stream.get_mut(), "expected a GET request");
+            }
+            continue;
+        }
+        if text.is_empty() {
+            break;
+        }
+        let Some((name, value)) = text.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "upgrade" => upgrade = value.eq_ignore_ascii_case("websocket"),
+            "sec-websocket-key" => key = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let Some(key) = key.filter(|_| upgrade) else {
+        return reject(stream.get_mut(), "not a WebSocket upgrade");
+    };
+
+    let accept = base64::engine::general_purpose::STANDARD
+        .encode(sha1(format!("{}{}", key, GUID).as_bytes()));
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    stream.get_mut().write_all(response.as_bytes())
+}
+
+fn reject(stream: &mut impl Write, reason: &str) -> io::Result<()> {
+    let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n");
+    Err(invalid(reason))
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// Reads the next data or close message, reassembling fragments and
+/// answering pings on the way. Messages over `max_len` are an error.
+pub fn read_message(
+    stream: &mut impl Read,
+    writer: &mut impl Write,
+    max_len: usize,
+) -> io::Result<Message> {
+    let mut message: Option<(u8, Vec<u8>)> = None;
+    loop {
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head)?;
+        let fin = head[0] & 0x80 != 0;
+        if head[0] & 0x70 != 0 {
+            return Err(invalid("reserved bits set"));
+        }
+        let opcode = head[0] & 0x0f;
+        if head[1] & 0x80 == 0 {
+            return Err(invalid("client frames must be masked"));
+        }
+        let len = match head[1] & 0x7f {
+            126 => {
+                let mut b = [0u8; 2];
+                stream.read_exact(&mut b)?;
+                u16::from_be_bytes(b) as u64
+            }
+            127 => {
+                let mut b = [0u8; 8];
+                stream.read_exact(&mut b)?;
+                u64::from_be_bytes(b)
+            }
+            n => n as u64,
+        };
+        let so_far = message.as_ref().map_or(0, |(_, m)| m.len());
+        if len > (max_len - so_far.min(max_len)) as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, TooBig));
+        }
+        let mut mask = [0u8; 4];
+        stream.read_exact(&mut mask)?;
+        let mut payload = vec![0u8; len as usize];
+        stream.read_exact(&mut payload)?;
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= mask[i % 4];
+        }
+
+        match opcode {
+            OP_PING => {
+                if !fin || payload.len() > 125 {
+                    return Err(invalid("bad ping frame"));
+                }
+                write_frame(writer, OP_PONG, &payload)?;
+            }
+            OP_PONG => {}
+            OP_CLOSE => {
+                let code = payload.get(..2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+                return Ok(Message::Close(code));
+            }
+            OP_TEXT | OP_BINARY if message.is_none() => {
+                if fin {
+                    return finish(opcode, payload);
+                }
+                message = Some((opcode, payload));
+            }
+            OP_CONTINUATION if message.is_some() => {
+                let (op, mut data) = message.take().unwrap();
+                data.extend_from_slice(&payload);
+                if fin {
+                    return finish(op, data);
+                }
+                message = Some((op, data));
+            }
+            _ => return Err(invalid("unexpected frame")),
+        }
+    }
+}
+
+/// The error payload [`read_message`] uses for an oversized message, so the
+/// caller can close with the right status.
+#[derive(Debug)]
+pub struct TooBig;
+
+impl std::fmt::Display for TooBig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("message too big")
+    }
+}
+
+impl std::error::Error for TooBig {}
+
+fn finish(opcode: u8, data: Vec<u8>) -> io::Result<Message> {
+    if opcode == OP_TEXT {
+        String::from_utf8(data)
+            .map(Message::Text)
+            .map_err(|_| invalid("text message is not UTF-8"))
+    } else {
+        Ok(Message::Binary(data))
+    }
+}
+
+pub fn write_text(writer: &mut impl Write, text: &str) -> io::Result<()> {
+    write_frame(writer, OP_TEXT, text.as_bytes())
+}
+
+pub fn write_binary(writer: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    write_frame(writer, OP_BINARY, data)
+}
+
+pub fn write_close(writer: &mut impl Write, code: u16, reason: &str) -> io::Result<()> {
+    let mut payload = code.to_be_bytes().to_vec();
+    // Control frames carry at most 125 bytes.
+    payload.extend(reason.bytes().take(123));
+    write_frame(writer, OP_CLOSE, &payload)
+}
+
+/// Server frames are never masked. Written in one call so concurrent
+/// writers holding the stream's lock never interleave.
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)
+}
+
+/// SHA-1, needed only for `Sec-WebSocket-Accept`.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &wi) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(wi);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    let mut out = [0u8; 20];
+    for (chunk, v) in out.chunks_exact_mut(4).zip(h) {
+        chunk.copy_from_slice(&v.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A connection whose peer has already sent `input`.
+    struct Duplex {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Duplex {
+        fn new(input: Vec<u8>) -> Self {
+            Self {
+                input: Cursor::new(input),
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A frame as a browser sends it: masked with `key`, unless `None`.
+    fn client_frame(fin: bool, opcode: u8, payload: &[u8], key: Option<[u8; 4]>) -> Vec<u8> {
+        let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+        let mask_bit = if key.is_some() { 0x80 } else { 0 };
+        match payload.len() {
+            n if n < 126 => frame.push(mask_bit | n as u8),
+            n => {
+                frame.push(mask_bit | 126);
+                frame.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+        }
+        match key {
+            Some(key) => {
+                frame.extend_from_slice(&key);
+                frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ key[i % 4]));
+            }
+            None => frame.extend_from_slice(payload),
+        }
+        frame
+    }
+
+    fn masked(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        client_frame(fin, opcode, payload, Some([0x12, 0x34, 0x56, 0x78]))
+    }
+
+    fn read(frames: &[Vec<u8>], max_len: usize) -> (io::Result<Message>, Vec<u8>) {
+        let mut stream = Cursor::new(frames.concat());
+        let mut written = Vec::new();
+        let result = read_message(&mut stream, &mut written, max_len);
+        (result, written)
+    }
+
+    fn error_text(result: io::Result<Message>) -> String {
+        result.expect_err("expected an error").to_string()
+    }
+
+    #[test]
+    fn answers_the_rfc_6455_sample_handshake() {
+        let request = "GET /chat HTTP/1.1\r\nHost: server.example.com\r\n\
+                       Upgrade: websocket\r\nConnection: Upgrade\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                       Sec-WebSocket-Version: 13\r\n\r\n";
+        let mut stream = BufReader::new(Duplex::new(request.as_bytes().to_vec()));
+        accept(&mut stream).unwrap();
+        let response = String::from_utf8(stream.get_ref().output.clone()).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(response.contains("\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn plain_http_requests_get_a_400() {
+        let request = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec();
+        let mut stream = BufReader::new(Duplex::new(request));
+        assert!(accept(&mut stream).is_err());
+        assert!(stream.get_ref().output.starts_with(b"HTTP/1.1 400 "));
+    }
+
+    #[test]
+    fn sha1_matches_the_fips_vectors() {
+        let hex = |d: [u8; 20]| d.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!(
+            hex(sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+
+    #[test]
+    fn unmasked_client_frames_are_rejected() {
+        let frame = client_frame(true, OP_TEXT, b"hi", None);
+        assert_eq!(
+            error_text(read(&[frame], 1024).0),
+            "client frames must be masked"
+        );
+    }
+
+    #[test]
+    fn reassembles_fragments_around_a_ping() {
+        let frames = [
+            masked(false, OP_TEXT, b"Hel"),
+            masked(true, OP_PING, b"are you there"),
+            masked(false, OP_CONTINUATION, b"lo, "),
+            masked(true, OP_CONTINUATION, b"world"),
+        ];
+        let (message, written) = read(&frames, 1024);
+        assert_eq!(message.unwrap(), Message::Text("Hello, world".into()));
+        let mut pong = vec![0x80 | OP_PONG, 13];
+        pong.extend_from_slice(b"are you there");
+        assert_eq!(written, pong);
+    }
+
+    #[test]
+    fn reads_binary_close_and_extended_lengths() {
+        let big = vec![7u8; 300];
+        let (message, _) = read(&[masked(true, OP_BINARY, &big)], 1024);
+        assert_eq!(message.unwrap(), Message::Binary(big));
+
+        let (message, _) = read(&[masked(true, OP_CLOSE, &1001u16.to_be_bytes())], 1024);
+        assert_eq!(message.unwrap(), Message::Close(Some(CLOSE_GOING_AWAY)));
+        let (message, _) = read(&[masked(true, OP_CLOSE, b"")], 1024);
+        assert_eq!(message.unwrap(), Message::Close(None));
+    }
+
+    #[test]
+    fn oversized_messages_are_too_big() {
+        let is_too_big = |result: io::Result<Message>| {
+            let error = result.expect_err("expected an error");
+            error.get_ref().is_some_and(|e| e.is::<TooBig>())
+        };
+        assert!(is_too_big(read(&[masked(true, OP_BINARY, &[0; 65])], 64).0));
+        // Each fragment fits; together they don't.
+        let frames = [
+            masked(false, OP_BINARY, &[0; 40]),
+            masked(true, OP_CONTINUATION, &[0; 40]),
+        ];
+        assert!(is_too_big(read(&frames, 64).0));
+        assert!(read(&[masked(true, OP_BINARY, &[0; 64])], 64).0.is_ok());
+    }
+
+    #[test]
+    fn rejects_protocol_errors() {
+        let stray = masked(true, OP_CONTINUATION, b"x");
+        assert_eq!(error_text(read(&[stray], 64).0), "unexpected frame");
+        let mut reserved = masked(true, OP_TEXT, b"x");
+        reserved[0] |= 0x40;
+        assert_eq!(error_text(read(&[reserved], 64).0), "reserved bits set");
+        let split_ping = masked(false, OP_PING, b"x");
+        assert_eq!(error_text(read(&[split_ping], 64).0), "bad ping frame");
+        let bad_utf8 = masked(true, OP_TEXT, &[0xff, 0xfe]);
+        assert_eq!(
+            error_text(read(&[bad_utf8], 64).0),
+            "text message is not UTF-8"
+        );
+    }
+
+    #[test]
+    fn server_frames_are_unmasked_with_the_right_length() {
+        let mut out = Vec::new();
+        write_text(&mut out, "hi").unwrap();
+        assert_eq!(out, [0x81, 2, b'h', b'i']);
+
+        let mut out = Vec::new();
+        write_binary(&mut out, &[1; 70_000]).unwrap();
+        assert_eq!(out[..2], [0x82, 127]);
+        assert_eq!(out[2..10], 70_000u64.to_be_bytes());
+        assert_eq!(out.len(), 10 + 70_000);
+
+        let mut out = Vec::new();
+        write_close(&mut out, CLOSE_TOO_BIG, &"x".repeat(200)).unwrap();
+        assert_eq!(out[..4], [0x88, 125, 0x03, 0xf1]);
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the settings commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("Could not locate the config directory: {0}")]
+    NoConfigDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid settings file: {0}")]
+    Invalid(String),
+
+    #[error("Settings file is version {found}, newer than the supported {supported}")]
+    NewerVersion { found: u32, supported: u32 },
+}
+
+impl SettingsError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        SettingsError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn invalid(reason: impl std::fmt::Display) -> Self {
+        SettingsError::Invalid(reason.to_string())
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SettingsError::NoConfigDir(_) => "noConfigDir",
+            SettingsError::Io { .. } => "io",
+            SettingsError::Invalid(_) => "invalid",
+            SettingsError::NewerVersion { .. } => "newerVersion",
+        }
+    }
+}
+
+impl Serialize for SettingsError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SettingsError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,145 @@
+//! Upgrades settings files written by older builds.
+//!
+//! Version history:
+//! - **v1**: a flat object using the `RuntimeSettings` key names the frontend
+//!   kept in localStorage (`cameraSensitivity`, `defaultFov`, ...), plus
+//!   optional `masterVolume`/`musicVolume`/`sfxVolume`/`graphicsQuality`.
+//!   Files without a `version` field are treated as v1.
+//! - **v2**: grouped into `controls`, `video` and `audio` sections.
+
+use super::model::{GameSettings, CURRENT_VERSION};
+use super::SettingsError;
+use serde_json::{Map, Value};
+
+/// Reads the schema version of a parsed settings file.
+pub fn schema_version(value: &Value) -> Result<u32, SettingsError> {
+    match value.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| SettingsError::invalid(format!("bad version field {}", version))),
+    }
+}
+
+/// Runs every upgrade step from the file's version to [`CURRENT_VERSION`]
+/// and parses the result.
+pub fn migrate(mut value: Value) -> Result<GameSettings, SettingsError> {
+    if !value.is_object() {
+        return Err(SettingsError::invalid("expected a JSON object"));
+    }
+
+    let found = schema_version(&value)?;
+    if found > CURRENT_VERSION {
+        return Err(SettingsError::NewerVersion {
+            found,
+            supported: CURRENT_VERSION,
+        });
+    }
+
+    for version in found..CURRENT_VERSION {
+        value = match version {
+            1 => v1_to_v2(value),
+            _ => unreachable!("no migration from settings v{}", version),
+        };
+    }
+
+    let settings: GameSettings = serde_json::from_value(value).map_err(SettingsError::invalid)?;
+    Ok(settings.sanitized())
+}
+
+fn v1_to_v2(value: Value) -> Value {
+    let Value::Object(old) = value else {
+        return value;
+    };
+    let section = |keys: &[(&str, &str)]| {
+        let mut map = Map::new();
+        for (from, to) in keys {
+            if let Some(v) = old.get(*from) {
+                map.insert(to.to_string(), v.clone());
+            }
+        }
+        Value::Object(map)
+    };
+
+    let controls = section(&[
+        ("cameraSensitivity", "mouseSensitivity"),
+        ("aimSensitivityMultiplier", "aimSensitivityMultiplier"),
+        ("invertY", "invertY"),
+    ]);
+    let video = section(&[
+        ("defaultFov", "fov"),
+        ("aimFov", "aimFov"),
+        ("graphicsQuality", "quality"),
+    ]);
+    let audio = section(&[
+        ("masterVolume", "masterVolume"),
+        ("musicVolume", "musicVolume"),
+        ("sfxVolume", "sfxVolume"),
+    ]);
+
+    serde_json::json!({
+        "version": 2,
+        "controls": controls,
+        "video": video,
+        "audio": audio,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::model::GraphicsQuality;
+    use crate::settings::v1_fixture;
+
+    #[test]
+    fn migrates_a_v1_file() {
+        let value: Value = serde_json::from_slice(&v1_fixture()).unwrap();
+        assert_eq!(schema_version(&value).unwrap(), 1);
+        let settings = migrate(value).unwrap();
+
+        assert_eq!(settings.version, CURRENT_VERSION);
+        assert_eq!(settings.controls.mouse_sensitivity, 0.004);
+        assert_eq!(settings.controls.aim_sensitivity_multiplier, 0.5);
+        assert!(settings.controls.invert_y);
+        assert_eq!(settings.video.fov, 90.0);
+        assert_eq!(settings.video.aim_fov, 30.0);
+        assert_eq!(settings.video.quality, GraphicsQuality::Medium);
+        assert_eq!(settings.audio.master_volume, 0.8);
+        assert_eq!(settings.audio.music_volume, 0.25);
+        // Out of range in the old file; clamped on the way in.
+        assert_eq!(settings.audio.sfx_volume, 1.0);
+        // v1 had no such settings, so they take their defaults.
+        let defaults = GameSettings::default();
+        assert_eq!(settings.video.vsync, defaults.video.vsync);
+        assert_eq!(settings.cloud, defaults.cloud);
+        assert_eq!(settings.locale, None);
+    }
+
+    #[test]
+    fn empty_v1_file_gives_defaults() {
+        assert_eq!(
+            migrate(serde_json::json!({})).unwrap(),
+            GameSettings::default()
+        );
+    }
+
+    #[test]
+    fn current_files_pass_through() {
+        let mut settings = GameSettings::default();
+        settings.video.fov = 100.0;
+        let value = serde_json::to_value(&settings).unwrap();
+        assert_eq!(schema_version(&value).unwrap(), CURRENT_VERSION);
+        assert_eq!(migrate(value).unwrap(), settings);
+    }
+
+    #[test]
+    fn rejects_newer_and_malformed_files() {
+        assert!(matches!(
+            migrate(serde_json::json!({ "version": CURRENT_VERSION + 1 })),
+            Err(SettingsError::NewerVersion { found, .. }) if found == CURRENT_VERSION + 1
+        ));
+        assert!(migrate(serde_json::json!({ "version": "two" })).is_err());
+        assert!(migrate(serde_json::json!([1, 2, 3])).is_err());
+    }
+}
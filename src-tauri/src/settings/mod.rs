@@ -0,0 +1,117 @@
+mod error;
+mod migrate;
+mod model;
+mod store;
+mod watcher;
+
+pub use error::SettingsError;
+pub use model::{GameSettings, GraphicsQuality};
+pub use store::SettingsStore;
+pub use watcher::SettingsWatcher;
+
+use store::settings_path;
+use tauri::{Emitter, Manager, State};
+use watcher::{SettingsChanged, CHANGED_EVENT};
+
+/// Starts watching `settings.json` for edits made outside the game. Failure
+/// only costs live reload, so it is logged rather than aborting startup.
+pub fn start_watcher(app: &tauri::AppHandle) {
+    let result =
+        settings_path(app).and_then(|path| app.state::<SettingsWatcher>().watch(app, path));
+    if let Err(e) = result {
+        log::warn!("settings watcher failed to start: {}", e);
+    }
+}
+
+/// Current settings for backend modules, read the same way `load_settings`
+/// does.
+pub fn current(app: &tauri::AppHandle) -> Result<GameSettings, SettingsError> {
+    let path = settings_path(app)?;
+    app.state::<SettingsStore>().load(&path)
+}
+
+/// Changes stored settings on behalf of a backend module and tells every
+/// window via `settings-changed`.
+pub fn update(
+    app: &tauri::AppHandle,
+    change: impl FnOnce(&mut GameSettings),
+) -> Result<GameSettings, SettingsError> {
+    let path = settings_path(app)?;
+    let store = app.state::<SettingsStore>();
+    let mut settings = store.load(&path)?;
+    change(&mut settings);
+    let settings = settings.sanitized();
+    let sequence = store.save(&path, &settings)?;
+    let _ = app.emit(
+        CHANGED_EVENT,
+        SettingsChanged {
+            settings: settings.clone(),
+            sequence,
+            origin: None,
+        },
+    );
+    Ok(settings)
+}
+
+/// Loads `settings.json` from the active profile, upgrading older
+/// files in place. Missing or corrupt files give the defaults.
+#[tauri::command]
+pub async fn load_settings(
+    app: tauri::AppHandle,
+    store: State<'_, SettingsStore>,
+) -> Result<GameSettings, SettingsError> {
+    let path = settings_path(&app)?;
+    store.load(&path)
+}
+
+/// Clamps `settings` into range, writes it atomically and returns what was
+/// actually stored. Other windows are told via `settings-changed`, tagged
+/// with the calling window's label.
+#[tauri::command]
+pub async fn save_settings(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    store: State<'_, SettingsStore>,
+    settings: GameSettings,
+) -> Result<GameSettings, SettingsError> {
+    store_and_broadcast(&app, &window, &store, settings.sanitized())
+}
+
+/// Overwrites the settings file with the defaults and returns them.
+#[tauri::command]
+pub async fn reset_settings(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    store: State<'_, SettingsStore>,
+) -> Result<GameSettings, SettingsError> {
+    store_and_broadcast(&app, &window, &store, GameSettings::default())
+}
+
+fn store_and_broadcast(
+    app: &tauri::AppHandle,
+    window: &tauri::Window,
+    store: &SettingsStore,
+    settings: GameSettings,
+) -> Result<GameSettings, SettingsError> {
+    let path = settings_path(app)?;
+    let sequence = store.save(&path, &settings)?;
+    let _ = app.emit(
+        CHANGED_EVENT,
+        SettingsChanged {
+            settings: settings.clone(),
+            sequence,
+            origin: Some(window.label().to_string()),
+        },
+    );
+    Ok(settings)
+}
+
+/// A settings file as written by v1 builds.
+#[cfg(test)]
+fn v1_fixture() -> Vec<u8> {
+    std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/settings/v1.json"
+    ))
+    .unwrap()
+}
@@ -0,0 +1,182 @@
+use crate::display::DisplayMode;
+use serde::{Deserialize, Serialize};
+
+/// Schema version written by this build. Bump it together with a new step in
+/// [`super::migrate`].
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Everything the player can change from the options menu.
+///
+/// Unknown fields are ignored and missing ones take their defaults, so
+/// adding a field doesn't need a version bump; renaming or restructuring does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GameSettings {
+    pub version: u32,
+    pub controls: ControlSettings,
+    pub video: VideoSettings,
+    pub audio: AudioSettings,
+    pub cloud: CloudSettings,
+    /// Language tag like `zh-CN`; `None` follows the frontend's default.
+    pub locale: Option<String>,
+    /// Id of an imported crosshair; `None` uses the built-in one.
+    pub crosshair: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ControlSettings {
+    /// Radians per pixel of mouse movement.
+    pub mouse_sensitivity: f64,
+    pub aim_sensitivity_multiplier: f64,
+    pub invert_y: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VideoSettings {
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    pub aim_fov: f64,
+    pub quality: GraphicsQuality,
+    pub vsync: bool,
+    /// `None` means uncapped.
+    pub fps_limit: Option<u32>,
+    /// Window mode restored at startup; `None` keeps the configured window.
+    pub display: Option<DisplayMode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AudioSettings {
+    /// All volumes are linear gain in `0.0..=1.0`.
+    pub master_volume: f64,
+    pub music_volume: f64,
+    pub sfx_volume: f64,
+}
+
+/// Save sync target; sync is off while either field is unset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CloudSettings {
+    /// Base URL of the save service, e.g. `https://saves.example.com/v1`.
+    pub endpoint: Option<String>,
+    pub auth_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphicsQuality {
+    Low,
+    Medium,
+    High,
+    Ultra,
+}
+
+/// Defaults mirror `PlayerConfig.camera` in `GameConfig.ts`.
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            controls: ControlSettings::default(),
+            video: VideoSettings::default(),
+            audio: AudioSettings::default(),
+            cloud: CloudSettings::default(),
+            locale: None,
+            crosshair: None,
+        }
+    }
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 0.002,
+            aim_sensitivity_multiplier: 0.35,
+            invert_y: false,
+        }
+    }
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            fov: 75.0,
+            aim_fov: 25.0,
+            quality: GraphicsQuality::High,
+            vsync: true,
+            fps_limit: None,
+            display: None,
+        }
+    }
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.7,
+            sfx_volume: 1.0,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Clamps every value into its supported range and stamps the current
+    /// version. Non-finite numbers fall back to the default.
+    pub fn sanitized(mut self) -> Self {
+        let defaults = GameSettings::default();
+        self.version = CURRENT_VERSION;
+
+        let c = &mut self.controls;
+        c.mouse_sensitivity = clamp(
+            c.mouse_sensitivity,
+            0.0001,
+            0.05,
+            defaults.controls.mouse_sensitivity,
+        );
+        c.aim_sensitivity_multiplier = clamp(
+            c.aim_sensitivity_multiplier,
+            0.05,
+            2.0,
+            defaults.controls.aim_sensitivity_multiplier,
+        );
+
+        let v = &mut self.video;
+        v.fov = clamp(v.fov, 50.0, 120.0, defaults.video.fov);
+        v.aim_fov = clamp(v.aim_fov, 10.0, v.fov, defaults.video.aim_fov.min(v.fov));
+        v.fps_limit = v.fps_limit.map(|fps| fps.clamp(30, 1000));
+        v.display = v.display.take().map(DisplayMode::sanitized);
+
+        let a = &mut self.audio;
+        a.master_volume = clamp(a.master_volume, 0.0, 1.0, defaults.audio.master_volume);
+        a.music_volume = clamp(a.music_volume, 0.0, 1.0, defaults.audio.music_volume);
+        a.sfx_volume = clamp(a.sfx_volume, 0.0, 1.0, defaults.audio.sfx_volume);
+
+        let cloud = &mut self.cloud;
+        for field in [&mut cloud.endpoint, &mut cloud.auth_token] {
+            *field = field
+                .take()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty());
+        }
+        self.locale = self
+            .locale
+            .take()
+            .and_then(|l| crate::locale::normalize_tag(&l));
+        self.crosshair = self
+            .crosshair
+            .take()
+            .filter(|id| crate::crosshairs::valid_id(id));
+
+        self
+    }
+}
+
+fn clamp(value: f64, min: f64, max: f64, fallback: f64) -> f64 {
+    if value.is_finite() {
+        value.clamp(min, max)
+    } else {
+        fallback
+    }
+}
@@ -0,0 +1,158 @@
+use super::migrate::{migrate, schema_version};
+use super::model::{GameSettings, CURRENT_VERSION};
+use super::SettingsError;
+use crate::fs_atomic::write_atomic;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Runtime;
+
+pub const FILE_NAME: &str = "settings.json";
+pub const BACKUP_SUFFIX: &str = ".bak";
+
+/// Serializes access to `settings.json` so overlapping saves can't race on
+/// the temp file, and remembers the last contents seen on disk so the file
+/// watcher can tell our own writes from external edits.
+#[derive(Default)]
+pub struct SettingsStore {
+    state: Mutex<StoreState>,
+}
+
+#[derive(Default)]
+struct StoreState {
+    last_seen: Option<Vec<u8>>,
+    /// Bumped for every change pushed to the frontend.
+    sequence: u64,
+}
+
+/// An external edit picked up by [`SettingsStore::reload_external`].
+pub struct ExternalChange {
+    pub sequence: u64,
+    pub result: Result<GameSettings, SettingsError>,
+}
+
+impl SettingsStore {
+    pub fn load(&self, path: &Path) -> Result<GameSettings, SettingsError> {
+        let mut state = self.state.lock().unwrap();
+        let (settings, on_disk) = load_or_recover(path)?;
+        state.last_seen = on_disk;
+        Ok(settings)
+    }
+
+    /// Writes `settings` and returns the sequence number of the change.
+    pub fn save(&self, path: &Path, settings: &GameSettings) -> Result<u64, SettingsError> {
+        let mut state = self.state.lock().unwrap();
+        state.last_seen = Some(write(path, settings)?);
+        state.sequence += 1;
+        Ok(state.sequence)
+    }
+
+    /// Re-reads the file after a change notification. Returns `None` when the
+    /// file is gone or its contents match what we last read or wrote, which
+    /// is how saves made through [`SettingsStore::save`] are filtered out.
+    /// Unlike [`SettingsStore::load`] this never rewrites or moves the file;
+    /// the player may be halfway through editing it.
+    pub fn reload_external(&self, path: &Path) -> Option<ExternalChange> {
+        let mut state = self.state.lock().unwrap();
+        let bytes = std::fs::read(path).ok()?;
+        if state.last_seen.as_deref() == Some(bytes.as_slice()) {
+            return None;
+        }
+
+        let result = parse(&bytes).map(|(settings, _)| settings);
+        state.last_seen = Some(bytes);
+        state.sequence += 1;
+        Some(ExternalChange {
+            sequence: state.sequence,
+            result,
+        })
+    }
+}
+
+/// `settings.json` inside the active profile's directory.
+pub fn settings_path<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, SettingsError> {
+    crate::profiles::active_dir(app)
+        .map(|dir| dir.join(FILE_NAME))
+        .map_err(|e| SettingsError::NoConfigDir(e.to_string()))
+}
+
+/// Reads and upgrades the settings file, returning it with the bytes now on
+/// disk. A missing file yields the defaults; an unreadable or unparseable
+/// one is moved aside to `settings.json.bak` first so the player can recover
+/// it by hand.
+fn load_or_recover(path: &Path) -> Result<(GameSettings, Option<Vec<u8>>), SettingsError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((GameSettings::default(), None))
+        }
+        Err(e) => return Err(SettingsError::io(path, e)),
+    };
+
+    match parse(&bytes) {
+        Ok((settings, found)) if found < CURRENT_VERSION => {
+            let written = write(path, &settings)?;
+            Ok((settings, Some(written)))
+        }
+        Ok((settings, _)) => Ok((settings, Some(bytes))),
+        Err(e) => {
+            let backup = backup_path(path);
+            log::warn!("{}; moving it to {:?} and using defaults", e, backup);
+            std::fs::rename(path, &backup).map_err(|e| SettingsError::io(path, e))?;
+            Ok((GameSettings::default(), None))
+        }
+    }
+}
+
+/// Parses and migrates raw file contents, returning the original version.
+fn parse(bytes: &[u8]) -> Result<(GameSettings, u32), SettingsError> {
+    let value = serde_json::from_slice(bytes).map_err(SettingsError::invalid)?;
+    let found = schema_version(&value)?;
+    migrate(value).map(|settings| (settings, found))
+}
+
+fn write(path: &Path, settings: &GameSettings) -> Result<Vec<u8>, SettingsError> {
+    let json = serde_json::to_vec_pretty(settings).map_err(SettingsError::invalid)?;
+    write_atomic(path, &json).map_err(|e| SettingsError::io(path, e))?;
+    Ok(json)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(BACKUP_SUFFIX);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::v1_fixture;
+
+    #[test]
+    fn loading_a_v1_file_rewrites_it_as_current() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        std::fs::write(&path, v1_fixture()).unwrap();
+
+        let settings = SettingsStore::default().load(&path).unwrap();
+        assert_eq!(settings.controls.mouse_sensitivity, 0.004);
+
+        let rewritten: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["version"], CURRENT_VERSION);
+        assert_eq!(rewritten["video"]["fov"], 90.0);
+        assert!(rewritten.get("cameraSensitivity").is_none());
+        assert_eq!(SettingsStore::default().load(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn unparseable_files_are_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        std::fs::write(&path, b"{ not json").unwrap();
+
+        let settings = SettingsStore::default().load(&path).unwrap();
+        assert_eq!(settings, GameSettings::default());
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(backup_path(&path)).unwrap(), b"{ not json");
+    }
+}
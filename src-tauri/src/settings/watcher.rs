@@ -0,0 +1,109 @@
+//! Pushes hand edits of `settings.json` to the frontend.
+//!
+//! The config directory is watched rather than the file itself because
+//! atomic saves replace the file, which would orphan a watch on the old one.
+
+use super::{GameSettings, SettingsError, SettingsStore};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+pub const CHANGED_EVENT: &str = "settings-changed";
+pub const INVALID_EVENT: &str = "settings-invalid";
+
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub settings: GameSettings,
+    /// Increases with every change; lets the frontend drop stale events.
+    pub sequence: u64,
+    /// Label of the window whose `save_settings` caused this, or `None` for
+    /// edits made outside the game or by the backend. Windows ignore events
+    /// they originated.
+    pub origin: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsInvalid {
+    pub kind: &'static str,
+    pub message: String,
+    pub sequence: u64,
+}
+
+impl SettingsInvalid {
+    fn new(error: &SettingsError, sequence: u64) -> Self {
+        Self {
+            kind: error.kind(),
+            message: error.to_string(),
+            sequence,
+        }
+    }
+}
+
+/// Holds the active watcher; replacing it re-points the watch.
+#[derive(Default)]
+pub struct SettingsWatcher {
+    debouncer: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+}
+
+impl SettingsWatcher {
+    /// Starts watching `path`, creating its directory if the game has never
+    /// saved settings before.
+    pub fn watch<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        path: PathBuf,
+    ) -> Result<(), SettingsError> {
+        let dir = path
+            .parent()
+            .map(PathBuf::from)
+            .ok_or_else(|| SettingsError::NoConfigDir(format!("{:?} has no parent", path)))?;
+        std::fs::create_dir_all(&dir).map_err(|e| SettingsError::io(&dir, e))?;
+
+        let app = app.clone();
+        let file_name = path.file_name().map(|n| n.to_os_string());
+        let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+            let Ok(events) = result else {
+                return;
+            };
+            if events
+                .iter()
+                .any(|event| event.path.file_name() == file_name.as_deref())
+            {
+                on_file_changed(&app, &path);
+            }
+        })
+        .map_err(|e| SettingsError::io(&dir, std::io::Error::other(e)))?;
+        debouncer
+            .watcher()
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| SettingsError::io(&dir, std::io::Error::other(e)))?;
+
+        *self.debouncer.lock().unwrap() = Some(debouncer);
+        Ok(())
+    }
+}
+
+fn on_file_changed<R: Runtime>(app: &AppHandle<R>, path: &std::path::Path) {
+    let Some(change) = app.state::<SettingsStore>().reload_external(path) else {
+        return;
+    };
+    let _ = match change.result {
+        Ok(settings) => app.emit(
+            CHANGED_EVENT,
+            SettingsChanged {
+                settings,
+                sequence: change.sequence,
+                origin: None,
+            },
+        ),
+        Err(e) => app.emit(INVALID_EVENT, SettingsInvalid::new(&e, change.sequence)),
+    };
+}
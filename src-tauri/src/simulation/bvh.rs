@@ -0,0 +1,184 @@
+//! Bounding volume hierarchy over primitive bounds, built by median split on
+//! the longest centroid axis. Cheap enough to rebuild every tick for a few
+//! hundred entity hitboxes.
+
+use super::math::{Aabb, RayQuery};
+
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    bounds: Aabb,
+    /// Leaves: first index into `order`. Interior: index of the right child
+    /// (the left child always directly follows its parent).
+    start_or_right: u32,
+    /// Primitive count for leaves, 0 for interior nodes.
+    count: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Primitive indices, grouped so each leaf owns a contiguous run.
+    order: Vec<u32>,
+}
+
+impl Bvh {
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(bounds.len().div_ceil(LEAF_SIZE) * 2),
+            order: (0..bounds.len() as u32).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build_node(bounds, 0, bounds.len());
+        }
+        bvh
+    }
+
+    fn build_node(&mut self, bounds: &[Aabb], start: usize, end: usize) -> usize {
+        let items = &mut self.order[start..end];
+        let node_bounds = items
+            .iter()
+            .fold(Aabb::EMPTY, |acc, &i| acc.union(bounds[i as usize]));
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: node_bounds,
+            start_or_right: start as u32,
+            count: (end - start) as u32,
+        });
+        if items.len() <= LEAF_SIZE {
+            return index;
+        }
+
+        let centroid_bounds = items.iter().fold(Aabb::EMPTY, |acc, &i| {
+            let c = bounds[i as usize].centroid();
+            acc.union(Aabb { min: c, max: c })
+        });
+        let axis = centroid_bounds.longest_axis();
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |&a, &b| {
+            let ca = bounds[a as usize].centroid().axis(axis);
+            let cb = bounds[b as usize].centroid().axis(axis);
+            ca.total_cmp(&cb)
+        });
+
+        self.build_node(bounds, start, start + mid);
+        let right = self.build_node(bounds, start + mid, end);
+        let node = &mut self.nodes[index];
+        node.start_or_right = right as u32;
+        node.count = 0;
+        index
+    }
+
+    /// Nearest primitive along `ray`. `hit` tests primitive `i` against the
+    /// current best distance and returns its distance and payload on a hit.
+    /// Children are visited near-first so most far subtrees are culled.
+    pub fn nearest<T>(
+        &self,
+        ray: &RayQuery,
+        mut hit: impl FnMut(u32, f32) -> Option<(f32, T)>,
+    ) -> Option<(f32, T)> {
+        let mut best: Option<(f32, T)> = None;
+        let mut best_t = ray.max_t;
+        if self.nodes.is_empty() || ray.overlaps(&self.nodes[0].bounds, best_t).is_none() {
+            return None;
+        }
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0usize);
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index];
+            if ray.overlaps(&node.bounds, best_t).is_none() {
+                continue;
+            }
+            if node.count > 0 {
+                let start = node.start_or_right as usize;
+                for &prim in &self.order[start..start + node.count as usize] {
+                    if let Some((t, payload)) = hit(prim, best_t) {
+                        if t <= best_t {
+                            best_t = t;
+                            best = Some((t, payload));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let (left, right) = (index + 1, node.start_or_right as usize);
+            let tl = ray.overlaps(&self.nodes[left].bounds, best_t);
+            let tr = ray.overlaps(&self.nodes[right].bounds, best_t);
+            match (tl, tr) {
+                (Some(tl), Some(tr)) if tl <= tr => stack.extend([right, left]),
+                (Some(_), Some(_)) => stack.extend([left, right]),
+                (Some(_), None) => stack.push(left),
+                (None, Some(_)) => stack.push(right),
+                (None, None) => {}
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Xoshiro256StarStar;
+    use crate::simulation::Vec3;
+
+    fn unit(rng: &mut Xoshiro256StarStar) -> f32 {
+        rng.next_u32() as f32 / u32::MAX as f32
+    }
+
+    fn point(rng: &mut Xoshiro256StarStar, extent: f32) -> Vec3 {
+        Vec3::new(
+            (unit(rng) - 0.5) * extent,
+            (unit(rng) - 0.5) * extent,
+            (unit(rng) - 0.5) * extent,
+        )
+    }
+
+    #[test]
+    fn nearest_matches_a_brute_force_scan() {
+        let mut rng = Xoshiro256StarStar::from_seed(28);
+        let boxes: Vec<Aabb> = (0..2_000)
+            .map(|_| {
+                let min = point(&mut rng, 200.0);
+                let size = Vec3::new(unit(&mut rng), unit(&mut rng), unit(&mut rng)) * 4.0;
+                Aabb {
+                    min,
+                    max: min + size,
+                }
+            })
+            .collect();
+        let bvh = Bvh::build(&boxes);
+
+        let mut hits = 0;
+        for _ in 0..2_000 {
+            let origin = point(&mut rng, 200.0);
+            let Some(dir) = point(&mut rng, 2.0).normalized() else {
+                continue;
+            };
+            let ray = RayQuery::new(origin, dir, 150.0);
+            let expected = boxes
+                .iter()
+                .filter_map(|b| ray.hit_aabb(b, ray.max_t))
+                .map(|h| h.t)
+                .min_by(f32::total_cmp);
+            let found = bvh.nearest(&ray, |i, max_t| {
+                ray.hit_aabb(&boxes[i as usize], max_t).map(|h| (h.t, i))
+            });
+            assert_eq!(found.map(|(t, _)| t), expected);
+            hits += found.is_some() as u32;
+        }
+        // Make sure the comparison isn't vacuous.
+        assert!(hits > 200, "only {} rays hit anything", hits);
+    }
+
+    #[test]
+    fn empty_trees_never_hit() {
+        let ray = RayQuery::new(Vec3::default(), Vec3::new(1.0, 0.0, 0.0), f32::INFINITY);
+        assert!(Bvh::build(&[])
+            .nearest(&ray, |i, _| Some((0.0, i)))
+            .is_none());
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the simulation commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum SimulationError {
+    #[error("Invalid geometry: {0}")]
+    InvalidGeometry(String),
+
+    #[error("Invalid ray {index}: {reason}")]
+    InvalidRay { index: usize, reason: String },
+
+    #[error("Snapshot for tick {tick} is older than the newest, tick {newest}")]
+    StaleSnapshot { tick: u64, newest: u64 },
+
+    #[error("No entity snapshots have been recorded")]
+    NoHistory,
+
+    #[error("Too many {what}: {count} (limit {max})")]
+    TooMany {
+        what: &'static str,
+        count: usize,
+        max: usize,
+    },
+}
+
+impl SimulationError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SimulationError::InvalidGeometry(_) => "invalidGeometry",
+            SimulationError::InvalidRay { .. } => "invalidRay",
+            SimulationError::StaleSnapshot { .. } => "staleSnapshot",
+            SimulationError::NoHistory => "noHistory",
+            SimulationError::TooMany { .. } => "tooMany",
+        }
+    }
+}
+
+impl Serialize for SimulationError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SimulationError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
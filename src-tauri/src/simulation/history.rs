@@ -0,0 +1,231 @@
+//! Recent entity poses for lag compensation. Shots are tested against the
+//! hitboxes as they were at the shooter's tick, interpolated between the two
+//! snapshots either side of it.
+
+use super::world::EntityHitbox;
+use super::SimulationError;
+use std::collections::VecDeque;
+
+/// About one second at the default 64 Hz tick rate.
+pub const WINDOW_TICKS: u64 = 64;
+/// Hitboxes kept across all snapshots. Past this the oldest snapshots are
+/// dropped early, so memory stays bounded however many entities there are.
+const MAX_TOTAL_HITBOXES: usize = 64 * 1024;
+
+struct Snapshot {
+    tick: u64,
+    /// Sorted by `(entity_id, head)` so two snapshots can be merged in step.
+    hitboxes: Vec<EntityHitbox>,
+}
+
+/// The poses to test a rewound shot against.
+pub struct Rewound {
+    pub hitboxes: Vec<EntityHitbox>,
+    /// The (possibly fractional) tick actually used.
+    pub tick: f64,
+    /// The requested tick was outside the buffer and was moved to its edge.
+    pub clamped: bool,
+}
+
+#[derive(Default)]
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    total: usize,
+}
+
+impl History {
+    /// Adds the poses for `tick`, replacing an existing snapshot of the same
+    /// tick. Ticks older than the newest are rejected.
+    pub fn record(
+        &mut self,
+        tick: u64,
+        mut hitboxes: Vec<EntityHitbox>,
+    ) -> Result<(), SimulationError> {
+        if let Some(newest) = self.snapshots.back() {
+            if tick < newest.tick {
+                return Err(SimulationError::StaleSnapshot {
+                    tick,
+                    newest: newest.tick,
+                });
+            }
+            if tick == newest.tick {
+                let replaced = self.snapshots.pop_back().unwrap();
+                self.total -= replaced.hitboxes.len();
+            }
+        }
+
+        hitboxes.sort_by_key(|h| (h.entity_id, h.head));
+        self.total += hitboxes.len();
+        self.snapshots.push_back(Snapshot { tick, hitboxes });
+
+        while let Some(oldest) = self.snapshots.front() {
+            let expired = oldest.tick + WINDOW_TICKS < tick;
+            let over_budget = self.total > MAX_TOTAL_HITBOXES && self.snapshots.len() > 1;
+            if !expired && !over_budget {
+                break;
+            }
+            let dropped = self.snapshots.pop_front().unwrap();
+            self.total -= dropped.hitboxes.len();
+        }
+        Ok(())
+    }
+
+    /// The hitboxes at `tick`, interpolated between the surrounding snapshots
+    /// and clamped to the oldest or newest one outside the buffer.
+    pub fn at(&self, tick: f64) -> Option<Rewound> {
+        let oldest = self.snapshots.front()?;
+        let newest = self.snapshots.back()?;
+        if !tick.is_finite() || tick <= oldest.tick as f64 {
+            return Some(Rewound {
+                hitboxes: oldest.hitboxes.clone(),
+                tick: oldest.tick as f64,
+                clamped: tick != oldest.tick as f64,
+            });
+        }
+        if tick >= newest.tick as f64 {
+            return Some(Rewound {
+                hitboxes: newest.hitboxes.clone(),
+                tick: newest.tick as f64,
+                clamped: tick > newest.tick as f64,
+            });
+        }
+
+        // The first snapshot after `tick`; the one before it is at or before.
+        let after = self.snapshots.partition_point(|s| (s.tick as f64) <= tick);
+        let (a, b) = (&self.snapshots[after - 1], &self.snapshots[after]);
+        let alpha = ((tick - a.tick as f64) / (b.tick - a.tick) as f64) as f32;
+        Some(Rewound {
+            hitboxes: interpolate(&a.hitboxes, &b.hitboxes, alpha),
+            tick,
+            clamped: false,
+        })
+    }
+}
+
+/// Lerps hitboxes matched by `(entity_id, head)` and their order within that
+/// key. An entity missing from the later snapshot keeps its earlier pose; one
+/// missing from the earlier snapshot hadn't spawned yet and is left out.
+fn interpolate(a: &[EntityHitbox], b: &[EntityHitbox], alpha: f32) -> Vec<EntityHitbox> {
+    let key = |h: &EntityHitbox| (h.entity_id, h.head);
+    let mut out = Vec::with_capacity(a.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() {
+        while j < b.len() && key(&b[j]) < key(&a[i]) {
+            j += 1;
+        }
+        if j < b.len() && key(&b[j]) == key(&a[i]) {
+            let (from, to) = (&a[i], &b[j]);
+            out.push(EntityHitbox {
+                min: from.min + (to.min - from.min) * alpha,
+                max: from.max + (to.max - from.max) * alpha,
+                ..*from
+            });
+            j += 1;
+        } else {
+            out.push(a[i]);
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Vec3;
+
+    fn at_x(entity_id: u32, x: f32, head: bool) -> EntityHitbox {
+        EntityHitbox {
+            entity_id,
+            min: Vec3::new(x, 0.0, 0.0),
+            max: Vec3::new(x + 1.0, 1.0, 1.0),
+            head,
+        }
+    }
+
+    fn xs(rewound: &Rewound) -> Vec<(u32, bool, f32)> {
+        rewound
+            .hitboxes
+            .iter()
+            .map(|h| (h.entity_id, h.head, h.min.x))
+            .collect()
+    }
+
+    #[test]
+    fn interpolates_between_the_surrounding_snapshots() {
+        let mut history = History::default();
+        history.record(0, vec![at_x(1, 0.0, false)]).unwrap();
+        history.record(4, vec![at_x(1, 8.0, false)]).unwrap();
+        history.record(8, vec![at_x(1, 0.0, false)]).unwrap();
+
+        let x_at = |tick: f64| history.at(tick).unwrap().hitboxes[0].min.x;
+        assert_eq!(x_at(1.0), 2.0);
+        assert_eq!(x_at(2.5), 5.0);
+        assert_eq!(x_at(4.0), 8.0);
+        assert_eq!(x_at(7.0), 2.0);
+        assert_eq!(history.at(6.0).unwrap().hitboxes[0].max.x, 5.0);
+    }
+
+    #[test]
+    fn matches_hitboxes_by_entity_and_head() {
+        let mut history = History::default();
+        history
+            .record(
+                0,
+                vec![at_x(2, 0.0, true), at_x(1, 0.0, false), at_x(2, 0.0, false)],
+            )
+            .unwrap();
+        // Entity 1 is gone and entity 3 has just spawned.
+        history
+            .record(
+                2,
+                vec![at_x(3, 0.0, false), at_x(2, 4.0, false), at_x(2, 8.0, true)],
+            )
+            .unwrap();
+
+        let rewound = history.at(1.0).unwrap();
+        assert_eq!(
+            xs(&rewound),
+            [(1, false, 0.0), (2, false, 2.0), (2, true, 4.0)]
+        );
+    }
+
+    #[test]
+    fn keeps_about_one_second() {
+        let mut history = History::default();
+        for tick in 0..=100 {
+            history
+                .record(tick, vec![at_x(1, tick as f32, false)])
+                .unwrap();
+        }
+        let oldest = history.at(0.0).unwrap();
+        assert!(oldest.clamped);
+        assert_eq!(oldest.tick, (100 - WINDOW_TICKS) as f64);
+        assert_eq!(history.snapshots.len(), WINDOW_TICKS as usize + 1);
+    }
+
+    #[test]
+    fn memory_stays_bounded_with_many_entities() {
+        let mut history = History::default();
+        let crowd: Vec<EntityHitbox> = (0..10_000).map(|i| at_x(i, 0.0, false)).collect();
+        for tick in 0..20 {
+            history.record(tick, crowd.clone()).unwrap();
+        }
+        assert!(history.total <= MAX_TOTAL_HITBOXES);
+        assert_eq!(history.snapshots.len(), MAX_TOTAL_HITBOXES / 10_000);
+    }
+
+    #[test]
+    fn stale_ticks_are_rejected_and_repeats_replace() {
+        let mut history = History::default();
+        history.record(5, vec![at_x(1, 0.0, false)]).unwrap();
+        history.record(5, vec![at_x(1, 3.0, false)]).unwrap();
+        assert_eq!(history.snapshots.len(), 1);
+        assert_eq!(history.total, 1);
+        assert_eq!(history.at(5.0).unwrap().hitboxes[0].min.x, 3.0);
+        assert!(matches!(
+            history.record(4, Vec::new()),
+            Err(SimulationError::StaleSnapshot { tick: 4, newest: 5 })
+        ));
+    }
+}
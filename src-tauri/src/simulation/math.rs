@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Sub};
+
+/// Serializes as `{ x, y, z }`, so a three.js `Vector3` can be passed as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3 {
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn axis(self, i: usize) -> f32 {
+        match i {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
+    pub fn dot(self, o: Self) -> f32 {
+        self.x * o.x + self.y * o.y + self.z * o.z
+    }
+
+    pub fn cross(self, o: Self) -> Self {
+        Self::new(
+            self.y * o.z - self.z * o.y,
+            self.z * o.x - self.x * o.z,
+            self.x * o.y - self.y * o.x,
+        )
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalized(self) -> Option<Self> {
+        let len = self.length();
+        (len > f32::EPSILON && len.is_finite()).then(|| self * (1.0 / len))
+    }
+
+    pub fn min(self, o: Self) -> Self {
+        Self::new(self.x.min(o.x), self.y.min(o.y), self.z.min(o.z))
+    }
+
+    pub fn max(self, o: Self) -> Self {
+        Self::new(self.x.max(o.x), self.y.max(o.y), self.z.max(o.z))
+    }
+
+    pub fn recip(self) -> Self {
+        Self::new(1.0 / self.x, 1.0 / self.y, 1.0 / self.z)
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Self;
+    fn add(self, o: Self) -> Self {
+        Self::new(self.x + o.x, self.y + o.y, self.z + o.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+    fn sub(self, o: Self) -> Self {
+        Self::new(self.x - o.x, self.y - o.y, self.z - o.z)
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Self;
+    fn mul(self, s: f32) -> Self {
+        Self::new(self.x * s, self.y * s, self.z * s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    };
+
+    pub fn union(self, o: Self) -> Self {
+        Self {
+            min: self.min.min(o.min),
+            max: self.max.max(o.max),
+        }
+    }
+
+    pub fn centroid(self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn longest_axis(self) -> usize {
+        let size = self.max - self.min;
+        if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn is_valid(self) -> bool {
+        self.min.is_finite()
+            && self.max.is_finite()
+            && self.min.x <= self.max.x
+            && self.min.y <= self.max.y
+            && self.min.z <= self.max.z
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Triangle {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+}
+
+impl Triangle {
+    pub fn bounds(&self) -> Aabb {
+        Aabb {
+            min: self.a.min(self.b).min(self.c),
+            max: self.a.max(self.b).max(self.c),
+        }
+    }
+}
+
+/// A normalized ray with its reciprocal direction precomputed for slab tests.
+#[derive(Debug, Clone, Copy)]
+pub struct RayQuery {
+    pub origin: Vec3,
+    pub dir: Vec3,
+    pub inv_dir: Vec3,
+    pub max_t: f32,
+}
+
+/// Where a ray meets a surface: distance along it and the surface normal,
+/// facing back towards the ray.
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceHit {
+    pub t: f32,
+    pub normal: Vec3,
+    /// The ray started inside the shape and was stopped at its origin.
+    pub inside: bool,
+}
+
+impl RayQuery {
+    pub fn new(origin: Vec3, dir: Vec3, max_t: f32) -> Self {
+        Self {
+            origin,
+            dir,
+            inv_dir: dir.recip(),
+            max_t,
+        }
+    }
+
+    /// Slab test. Returns the entry distance and the axis the ray entered
+    /// through, `None` for the axis when the origin is inside the box.
+    fn slab(&self, b: &Aabb, max_t: f32) -> Option<(f32, Option<usize>)> {
+        let mut t_near = f32::NEG_INFINITY;
+        let mut t_far = f32::INFINITY;
+        let mut axis = 0;
+        for i in 0..3 {
+            let t1 = (b.min.axis(i) - self.origin.axis(i)) * self.inv_dir.axis(i);
+            let t2 = (b.max.axis(i) - self.origin.axis(i)) * self.inv_dir.axis(i);
+            // `min`/`max` skip the NaN from a zero direction on a slab edge.
+            let (lo, hi) = (t1.min(t2), t1.max(t2));
+            if lo > t_near {
+                t_near = lo;
+                axis = i;
+            }
+            t_far = t_far.min(hi);
+        }
+        if t_far < t_near.max(0.0) || t_near > max_t {
+            return None;
+        }
+        Some(if t_near < 0.0 {
+            (0.0, None)
+        } else {
+            (t_near, Some(axis))
+        })
+    }
+
+    /// Whether the ray passes through `b` closer than `max_t`.
+    pub fn overlaps(&self, b: &Aabb, max_t: f32) -> Option<f32> {
+        self.slab(b, max_t).map(|(t, _)| t)
+    }
+
+    pub fn hit_aabb(&self, b: &Aabb, max_t: f32) -> Option<SurfaceHit> {
+        let (t, axis) = self.slab(b, max_t)?;
+        Some(match axis {
+            Some(axis) => {
+                let mut normal = Vec3::default();
+                let sign = if self.dir.axis(axis) > 0.0 { -1.0 } else { 1.0 };
+                match axis {
+                    0 => normal.x = sign,
+                    1 => normal.y = sign,
+                    _ => normal.z = sign,
+                }
+                SurfaceHit {
+                    t,
+                    normal,
+                    inside: false,
+                }
+            }
+            None => SurfaceHit {
+                t: 0.0,
+                normal: self.dir * -1.0,
+                inside: true,
+            },
+        })
+    }
+
+    /// Möller–Trumbore, hitting either face.
+    pub fn hit_triangle(&self, tri: &Triangle, max_t: f32) -> Option<SurfaceHit> {
+        const EPS: f32 = 1e-7;
+        let e1 = tri.b - tri.a;
+        let e2 = tri.c - tri.a;
+        let p = self.dir.cross(e2);
+        let det = e1.dot(p);
+        if det.abs() < EPS {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = self.origin - tri.a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let v = self.dir.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(q) * inv_det;
+        if t < 0.0 || t > max_t {
+            return None;
+        }
+        let mut normal = e1.cross(e2).normalized()?;
+        if normal.dot(self.dir) > 0.0 {
+            normal = normal * -1.0;
+        }
+        Some(SurfaceHit {
+            t,
+            normal,
+            inside: false,
+        })
+    }
+}
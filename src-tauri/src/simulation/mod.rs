@@ -0,0 +1,467 @@
+mod bvh;
+mod error;
+mod history;
+mod math;
+mod world;
+
+pub use error::SimulationError;
+pub use math::{Aabb, Triangle, Vec3};
+pub use world::{EntityHitbox, HitResult, WorldGeometry};
+
+use history::History;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use world::{EntitySet, StaticWorld};
+
+pub const MAX_RAYS_PER_BATCH: usize = 1024;
+
+/// Collision data for server-side hit detection. The level and live entity
+/// sets are swapped as a whole, so a raycast holds their locks only long
+/// enough to clone an `Arc`.
+#[derive(Default)]
+pub struct SimulationWorld {
+    world: Mutex<Arc<StaticWorld>>,
+    entities: Mutex<Arc<EntitySet>>,
+    history: Mutex<History>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Needn't be normalized.
+    pub direction: Vec3,
+    /// Defaults to unlimited.
+    #[serde(default)]
+    pub max_distance: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewindResult {
+    pub results: Vec<HitResult>,
+    /// The tick the hitboxes were rewound to.
+    pub tick: f64,
+    /// `clientTick` was outside the recorded history, so the nearest
+    /// snapshot was used instead.
+    pub clamped: bool,
+}
+
+/// Replaces the level's static collision. Returns the primitive count.
+#[tauri::command]
+pub async fn register_world_geometry(
+    sim: State<'_, SimulationWorld>,
+    geometry: WorldGeometry,
+) -> Result<usize, SimulationError> {
+    let count = geometry.aabbs.len() + geometry.triangles.len();
+    let built = tauri::async_runtime::spawn_blocking(move || StaticWorld::build(geometry))
+        .await
+        .map_err(|e| SimulationError::InvalidGeometry(e.to_string()))??;
+    *sim.world.lock().unwrap() = Arc::new(built);
+    Ok(count)
+}
+
+/// Replaces every entity hitbox; call once per tick with current poses.
+#[tauri::command]
+pub async fn register_entities(
+    sim: State<'_, SimulationWorld>,
+    hitboxes: Vec<EntityHitbox>,
+) -> Result<(), SimulationError> {
+    let built = EntitySet::build(hitboxes)?;
+    *sim.entities.lock().unwrap() = Arc::new(built);
+    Ok(())
+}
+
+/// Records the hitboxes for `tick` in the rewind history (about the last
+/// second) and makes them the live set used by [`raycast_batch`].
+#[tauri::command]
+pub async fn record_entity_snapshot(
+    sim: State<'_, SimulationWorld>,
+    tick: u64,
+    positions: Vec<EntityHitbox>,
+) -> Result<(), SimulationError> {
+    sim.record(tick, positions)
+}
+
+/// Casts every ray against the level and entities, returning one result per
+/// ray in order. A ray that hits nothing, or has no direction, is a miss.
+#[tauri::command]
+pub async fn raycast_batch(
+    sim: State<'_, SimulationWorld>,
+    rays: Vec<Ray>,
+) -> Result<Vec<HitResult>, SimulationError> {
+    let world = sim.world.lock().unwrap().clone();
+    let entities = sim.entities.lock().unwrap().clone();
+    cast_all(&world, &entities, &rays)
+}
+
+/// Like [`raycast_batch`], but against entities as they were at
+/// `client_tick`, which may be fractional for a client rendering between
+/// ticks. The level itself is never rewound.
+#[tauri::command]
+pub async fn raycast_at_time(
+    sim: State<'_, SimulationWorld>,
+    rays: Vec<Ray>,
+    client_tick: f64,
+) -> Result<RewindResult, SimulationError> {
+    sim.cast_at(&rays, client_tick)
+}
+
+impl SimulationWorld {
+    fn record(&self, tick: u64, positions: Vec<EntityHitbox>) -> Result<(), SimulationError> {
+        let built = EntitySet::build(positions.clone())?;
+        self.history.lock().unwrap().record(tick, positions)?;
+        *self.entities.lock().unwrap() = Arc::new(built);
+        Ok(())
+    }
+
+    fn cast_at(&self, rays: &[Ray], client_tick: f64) -> Result<RewindResult, SimulationError> {
+        let rewound = self
+            .history
+            .lock()
+            .unwrap()
+            .at(client_tick)
+            .ok_or(SimulationError::NoHistory)?;
+        let entities = EntitySet::build(rewound.hitboxes)?;
+        let world = self.world.lock().unwrap().clone();
+        Ok(RewindResult {
+            results: cast_all(&world, &entities, rays)?,
+            tick: rewound.tick,
+            clamped: rewound.clamped,
+        })
+    }
+}
+
+/// A level and entity set of its own, outside the shared world, so it can
+/// be cast against without disturbing a match in progress.
+pub struct Scene {
+    world: StaticWorld,
+    entities: EntitySet,
+}
+
+impl Scene {
+    pub fn build(
+        geometry: WorldGeometry,
+        hitboxes: Vec<EntityHitbox>,
+    ) -> Result<Self, SimulationError> {
+        Ok(Self {
+            world: StaticWorld::build(geometry)?,
+            entities: EntitySet::build(hitboxes)?,
+        })
+    }
+
+    /// Same rules and limits as [`raycast_batch`].
+    pub fn cast(&self, rays: &[Ray]) -> Result<Vec<HitResult>, SimulationError> {
+        cast_all(&self.world, &self.entities, rays)
+    }
+}
+
+fn cast_all(
+    world: &StaticWorld,
+    entities: &EntitySet,
+    rays: &[Ray],
+) -> Result<Vec<HitResult>, SimulationError> {
+    if rays.len() > MAX_RAYS_PER_BATCH {
+        return Err(SimulationError::TooMany {
+            what: "rays",
+            count: rays.len(),
+            max: MAX_RAYS_PER_BATCH,
+        });
+    }
+    rays.iter()
+        .enumerate()
+        .map(|(index, ray)| {
+            let max_distance = ray.max_distance.unwrap_or(f32::INFINITY);
+            if !ray.origin.is_finite() || max_distance.is_nan() || max_distance < 0.0 {
+                return Err(SimulationError::InvalidRay {
+                    index,
+                    reason: "origin and max distance must be finite and non-negative".to_string(),
+                });
+            }
+            Ok(match ray.direction.normalized() {
+                Some(dir) => world::raycast(world, entities, ray.origin, dir, max_distance),
+                None => HitResult::Miss,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::world::Hit;
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn ray(origin: Vec3, direction: Vec3) -> Ray {
+        Ray {
+            origin,
+            direction,
+            max_distance: None,
+        }
+    }
+
+    fn hitbox(entity_id: u32, min: Vec3, max: Vec3, head: bool) -> EntityHitbox {
+        EntityHitbox {
+            entity_id,
+            min,
+            max,
+            head,
+        }
+    }
+
+    fn expect_hit(result: &HitResult) -> Hit {
+        match result {
+            HitResult::Hit(hit) => *hit,
+            HitResult::Miss => panic!("expected a hit"),
+        }
+    }
+
+    /// A body box with a head box on top, standing on the floor at `(x, z)`.
+    fn player(entity_id: u32, x: f32, z: f32) -> [EntityHitbox; 2] {
+        [
+            hitbox(
+                entity_id,
+                Vec3::new(x - 0.4, 0.0, z - 0.4),
+                Vec3::new(x + 0.4, 1.5, z + 0.4),
+                false,
+            ),
+            hitbox(
+                entity_id,
+                Vec3::new(x - 0.2, 1.5, z - 0.2),
+                Vec3::new(x + 0.2, 1.9, z + 0.2),
+                true,
+            ),
+        ]
+    }
+
+    #[test]
+    fn reports_entity_hits_with_point_and_normal() {
+        let scene = Scene::build(WorldGeometry::default(), player(7, 10.0, 0.0).to_vec()).unwrap();
+        let results = scene
+            .cast(&[
+                ray(Vec3::new(0.0, 1.0, 0.0), Vec3::new(2.0, 0.0, 0.0)),
+                ray(Vec3::new(0.0, 1.7, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+                ray(Vec3::new(0.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+            ])
+            .unwrap();
+
+        let body = expect_hit(&results[0]);
+        assert_eq!(body.entity_id, Some(7));
+        assert_eq!(body.point, Vec3::new(9.6, 1.0, 0.0));
+        assert_eq!(body.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(body.distance, 9.6);
+        assert!(!body.head && !body.started_inside);
+
+        let head = expect_hit(&results[1]);
+        assert!(head.head);
+        assert_eq!(head.distance, 9.8);
+        assert!(matches!(results[2], HitResult::Miss));
+    }
+
+    #[test]
+    fn walls_block_shots() {
+        let wall = Aabb {
+            min: Vec3::new(5.0, 0.0, -5.0),
+            max: Vec3::new(5.5, 3.0, 5.0),
+        };
+        let geometry = WorldGeometry {
+            aabbs: vec![wall],
+            triangles: Vec::new(),
+        };
+        let scene = Scene::build(geometry, player(1, 10.0, 0.0).to_vec()).unwrap();
+        let results = scene
+            .cast(&[
+                ray(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+                // Over the top of the wall.
+                ray(Vec3::new(0.0, 1.7, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+            ])
+            .unwrap();
+
+        let blocked = expect_hit(&results[0]);
+        assert_eq!(blocked.entity_id, None);
+        assert_eq!(blocked.distance, 5.0);
+        assert!(expect_hit(&results[1]).entity_id.is_none());
+
+        let short = Ray {
+            max_distance: Some(4.0),
+            ..ray(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0))
+        };
+        assert!(matches!(scene.cast(&[short]).unwrap()[0], HitResult::Miss));
+    }
+
+    #[test]
+    fn head_boxes_win_ties_with_body_boxes() {
+        // Both boxes present the same face to the ray.
+        let boxes = vec![
+            hitbox(
+                3,
+                Vec3::new(5.0, 0.0, -1.0),
+                Vec3::new(6.0, 2.0, 1.0),
+                false,
+            ),
+            hitbox(3, Vec3::new(5.0, 1.0, -1.0), Vec3::new(6.0, 2.0, 1.0), true),
+        ];
+        let scene = Scene::build(WorldGeometry::default(), boxes).unwrap();
+        let results = scene
+            .cast(&[ray(Vec3::new(0.0, 1.5, 0.0), Vec3::new(1.0, 0.0, 0.0))])
+            .unwrap();
+        let hit = expect_hit(&results[0]);
+        assert!(hit.head);
+        assert_eq!(hit.distance, 5.0);
+    }
+
+    #[test]
+    fn rays_starting_inside_hit_at_distance_zero() {
+        let scene = Scene::build(WorldGeometry::default(), player(2, 0.0, 0.0).to_vec()).unwrap();
+        let origin = Vec3::new(0.0, 1.0, 0.0);
+        let results = scene
+            .cast(&[ray(origin, Vec3::new(0.0, 0.0, 1.0))])
+            .unwrap();
+        let hit = expect_hit(&results[0]);
+        assert!(hit.started_inside);
+        assert_eq!(hit.distance, 0.0);
+        assert_eq!(hit.point, origin);
+    }
+
+    #[test]
+    fn degenerate_and_invalid_rays() {
+        let scene = Scene::build(WorldGeometry::default(), player(2, 5.0, 0.0).to_vec()).unwrap();
+        let results = scene
+            .cast(&[ray(Vec3::new(0.0, 1.0, 0.0), Vec3::default())])
+            .unwrap();
+        assert!(matches!(results[0], HitResult::Miss));
+
+        let nan = ray(Vec3::new(f32::NAN, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(matches!(
+            scene.cast(&[nan]),
+            Err(SimulationError::InvalidRay { index: 0, .. })
+        ));
+        let too_many: Vec<Ray> = (0..=MAX_RAYS_PER_BATCH)
+            .map(|_| ray(Vec3::default(), Vec3::new(1.0, 0.0, 0.0)))
+            .collect();
+        assert!(scene.cast(&too_many).is_err());
+    }
+
+    #[test]
+    fn shotgun_blast_resolves_well_under_a_millisecond() {
+        // 300 players in a 30 x 10 grid, with a floor and a few walls.
+        let hitboxes: Vec<EntityHitbox> = (0..300u32)
+            .flat_map(|i| player(i, (i % 30) as f32 * 3.0 + 5.0, (i / 30) as f32 * 3.0 - 15.0))
+            .collect();
+        let floor = Aabb {
+            min: Vec3::new(-100.0, -1.0, -100.0),
+            max: Vec3::new(100.0, 0.0, 100.0),
+        };
+        let walls = (0..20).map(|i| Aabb {
+            min: Vec3::new(i as f32 * 5.0, 0.0, 20.0),
+            max: Vec3::new(i as f32 * 5.0 + 4.0, 3.0, 21.0),
+        });
+        let geometry = WorldGeometry {
+            aabbs: std::iter::once(floor).chain(walls).collect(),
+            triangles: Vec::new(),
+        };
+        let scene = Scene::build(geometry, hitboxes).unwrap();
+
+        let pellets: Vec<Ray> = (0..12)
+            .map(|i| {
+                let spread = (i as f32 - 5.5) * 0.01;
+                ray(Vec3::new(0.0, 1.6, 0.0), Vec3::new(1.0, spread, spread))
+            })
+            .collect();
+        assert!(scene.cast(&pellets).unwrap().iter().any(|r| matches!(
+            r,
+            HitResult::Hit(Hit {
+                entity_id: Some(_),
+                ..
+            })
+        )));
+
+        // The best of several runs, so a busy test machine doesn't fail this.
+        let best = (0..50)
+            .map(|_| {
+                let start = Instant::now();
+                std::hint::black_box(scene.cast(&pellets).unwrap());
+                start.elapsed()
+            })
+            .min()
+            .unwrap();
+        assert!(
+            best < Duration::from_millis(1),
+            "12 pellets took {:?}",
+            best
+        );
+    }
+
+    /// A 1 x 2 x 1 box centred on `(x, z)`.
+    fn pillar(entity_id: u32, x: f32, z: f32) -> EntityHitbox {
+        hitbox(
+            entity_id,
+            Vec3::new(x - 0.5, 0.0, z - 0.5),
+            Vec3::new(x + 0.5, 2.0, z + 0.5),
+            false,
+        )
+    }
+
+    #[test]
+    fn rewound_shots_hit_interpolated_positions() {
+        // Entity 1 walks along +x and entity 2 along +z, one unit per tick,
+        // between snapshots at ticks 10 and 18.
+        let sim = SimulationWorld::default();
+        sim.record(10, vec![pillar(1, 10.0, 0.0), pillar(2, 30.0, -10.0)])
+            .unwrap();
+        sim.record(18, vec![pillar(1, 18.0, 0.0), pillar(2, 30.0, -2.0)])
+            .unwrap();
+
+        // At tick 13.5 entity 1 spans x 13..14 and entity 2 spans z -7..-6.
+        let rays = [
+            ray(Vec3::new(13.75, 1.0, 10.0), Vec3::new(0.0, 0.0, -1.0)),
+            ray(Vec3::new(30.0, 1.0, 10.0), Vec3::new(0.0, 0.0, -1.0)),
+            // Where entity 1 stands at tick 10, and no longer at 13.5.
+            ray(Vec3::new(10.0, 1.0, 10.0), Vec3::new(0.0, 0.0, -1.0)),
+        ];
+        let rewound = sim.cast_at(&rays, 13.5).unwrap();
+        assert_eq!(rewound.tick, 13.5);
+        assert!(!rewound.clamped);
+
+        let first = expect_hit(&rewound.results[0]);
+        assert_eq!(first.entity_id, Some(1));
+        assert_eq!(first.point, Vec3::new(13.75, 1.0, 0.5));
+        assert_eq!(first.distance, 9.5);
+
+        let second = expect_hit(&rewound.results[1]);
+        assert_eq!(second.entity_id, Some(2));
+        assert_eq!(second.point, Vec3::new(30.0, 1.0, -6.0));
+        assert_eq!(second.distance, 16.0);
+        assert_eq!(second.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        assert!(matches!(rewound.results[2], HitResult::Miss));
+        let at_ten = sim.cast_at(&rays[2..], 10.0).unwrap();
+        assert!(!at_ten.clamped);
+        assert_eq!(expect_hit(&at_ten.results[0]).entity_id, Some(1));
+    }
+
+    #[test]
+    fn rewinds_outside_the_history_are_clamped() {
+        let sim = SimulationWorld::default();
+        assert!(matches!(
+            sim.cast_at(&[], 5.0),
+            Err(SimulationError::NoHistory)
+        ));
+        sim.record(10, vec![pillar(1, 10.0, 0.0)]).unwrap();
+        sim.record(18, vec![pillar(1, 18.0, 0.0)]).unwrap();
+
+        let probe = |x: f32| ray(Vec3::new(x, 1.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        let early = sim.cast_at(&[probe(10.0)], 2.0).unwrap();
+        assert!(early.clamped);
+        assert_eq!(early.tick, 10.0);
+        assert_eq!(expect_hit(&early.results[0]).entity_id, Some(1));
+
+        let late = sim.cast_at(&[probe(18.0)], 40.0).unwrap();
+        assert!(late.clamped);
+        assert_eq!(late.tick, 18.0);
+        assert_eq!(expect_hit(&late.results[0]).entity_id, Some(1));
+
+        assert!(sim.cast_at(&[probe(18.0)], f64::NAN).unwrap().clamped);
+    }
+}
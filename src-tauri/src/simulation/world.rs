@@ -0,0 +1,208 @@
+use super::bvh::Bvh;
+use super::math::{Aabb, RayQuery, SurfaceHit, Triangle, Vec3};
+use super::SimulationError;
+use serde::{Deserialize, Serialize};
+
+const MAX_WORLD_PRIMITIVES: usize = 1_000_000;
+const MAX_ENTITY_HITBOXES: usize = 16_384;
+
+/// Static level collision, replaced wholesale on level load.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldGeometry {
+    #[serde(default)]
+    pub aabbs: Vec<Aabb>,
+    #[serde(default)]
+    pub triangles: Vec<Triangle>,
+}
+
+/// One box of an entity, in world space. Entities usually send a body box
+/// and a head box.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityHitbox {
+    pub entity_id: u32,
+    pub min: Vec3,
+    pub max: Vec3,
+    #[serde(default)]
+    pub head: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hit {
+    /// `None` when the ray was stopped by level geometry.
+    pub entity_id: Option<u32>,
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+    pub head: bool,
+    /// The ray began inside what it hit, which is reported at distance 0.
+    pub started_inside: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum HitResult {
+    Hit(Hit),
+    Miss,
+}
+
+enum WorldPrim {
+    Box(Aabb),
+    Tri(Triangle),
+}
+
+#[derive(Default)]
+pub struct StaticWorld {
+    prims: Vec<WorldPrim>,
+    bvh: Bvh,
+}
+
+impl StaticWorld {
+    pub fn build(geometry: WorldGeometry) -> Result<Self, SimulationError> {
+        let count = geometry.aabbs.len() + geometry.triangles.len();
+        if count > MAX_WORLD_PRIMITIVES {
+            return Err(SimulationError::TooMany {
+                what: "world primitives",
+                count,
+                max: MAX_WORLD_PRIMITIVES,
+            });
+        }
+
+        let mut prims = Vec::with_capacity(count);
+        for (i, b) in geometry.aabbs.into_iter().enumerate() {
+            if !b.is_valid() {
+                return Err(SimulationError::InvalidGeometry(format!(
+                    "box {} has non-finite or inverted bounds",
+                    i
+                )));
+            }
+            prims.push(WorldPrim::Box(b));
+        }
+        for (i, t) in geometry.triangles.into_iter().enumerate() {
+            if !(t.a.is_finite() && t.b.is_finite() && t.c.is_finite()) {
+                return Err(SimulationError::InvalidGeometry(format!(
+                    "triangle {} has non-finite vertices",
+                    i
+                )));
+            }
+            prims.push(WorldPrim::Tri(t));
+        }
+
+        let bounds: Vec<Aabb> = prims
+            .iter()
+            .map(|p| match p {
+                WorldPrim::Box(b) => *b,
+                WorldPrim::Tri(t) => t.bounds(),
+            })
+            .collect();
+        Ok(Self {
+            bvh: Bvh::build(&bounds),
+            prims,
+        })
+    }
+
+    fn raycast(&self, ray: &RayQuery) -> Option<SurfaceHit> {
+        self.bvh
+            .nearest(ray, |i, max_t| {
+                let hit = match &self.prims[i as usize] {
+                    WorldPrim::Box(b) => ray.hit_aabb(b, max_t),
+                    WorldPrim::Tri(t) => ray.hit_triangle(t, max_t),
+                }?;
+                Some((hit.t, hit))
+            })
+            .map(|(_, hit)| hit)
+    }
+}
+
+#[derive(Default)]
+pub struct EntitySet {
+    hitboxes: Vec<EntityHitbox>,
+    bvh: Bvh,
+}
+
+impl EntitySet {
+    pub fn build(hitboxes: Vec<EntityHitbox>) -> Result<Self, SimulationError> {
+        if hitboxes.len() > MAX_ENTITY_HITBOXES {
+            return Err(SimulationError::TooMany {
+                what: "entity hitboxes",
+                count: hitboxes.len(),
+                max: MAX_ENTITY_HITBOXES,
+            });
+        }
+        let bounds: Vec<Aabb> = hitboxes
+            .iter()
+            .map(|h| Aabb {
+                min: h.min,
+                max: h.max,
+            })
+            .collect();
+        if let Some(i) = bounds.iter().position(|b| !b.is_valid()) {
+            return Err(SimulationError::InvalidGeometry(format!(
+                "hitbox of entity {} has non-finite or inverted bounds",
+                hitboxes[i].entity_id
+            )));
+        }
+        Ok(Self {
+            bvh: Bvh::build(&bounds),
+            hitboxes,
+        })
+    }
+
+    /// Nearest hitbox. Where a head box overlaps the body box, the head wins
+    /// ties so a shot entering both at once counts as a headshot.
+    fn raycast(&self, ray: &RayQuery, max_t: f32) -> Option<(SurfaceHit, &EntityHitbox)> {
+        let ray = RayQuery { max_t, ..*ray };
+        self.bvh
+            .nearest(&ray, |i, max_t| {
+                let hitbox = &self.hitboxes[i as usize];
+                let bounds = Aabb {
+                    min: hitbox.min,
+                    max: hitbox.max,
+                };
+                let hit = ray.hit_aabb(&bounds, max_t)?;
+                // Nudge body hits back so an equally near head hit replaces them.
+                let rank = if hitbox.head { hit.t } else { hit.t + 1e-5 };
+                Some((rank, (hit, hitbox)))
+            })
+            .map(|(_, found)| found)
+    }
+}
+
+/// Casts one ray against the level, then against entities no further than
+/// the level hit, so walls block shots.
+pub fn raycast(
+    world: &StaticWorld,
+    entities: &EntitySet,
+    origin: Vec3,
+    dir: Vec3,
+    max_distance: f32,
+) -> HitResult {
+    let ray = RayQuery::new(origin, dir, max_distance);
+    let wall = world.raycast(&ray);
+    let wall_t = wall.map_or(max_distance, |h| h.t);
+
+    let hit = match entities.raycast(&ray, wall_t) {
+        Some((hit, hitbox)) => Hit {
+            entity_id: Some(hitbox.entity_id),
+            point: origin + dir * hit.t,
+            normal: hit.normal,
+            distance: hit.t,
+            head: hitbox.head,
+            started_inside: hit.inside,
+        },
+        None => match wall {
+            Some(hit) => Hit {
+                entity_id: None,
+                point: origin + dir * hit.t,
+                normal: hit.normal,
+                distance: hit.t,
+                head: false,
+                started_inside: hit.inside,
+            },
+            None => return HitResult::Miss,
+        },
+    };
+    HitResult::Hit(hit)
+}
@@ -0,0 +1,45 @@
+//! Recent snapshots either side of the connection can diff against. The
+//! sender keeps what it encoded; the receiver keeps what it decoded. Both
+//! keep the same window, so any baseline the receiver can acknowledge is
+//! still held by the sender for a while.
+
+use super::model::Frame;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// About one second at the default 64 Hz tick rate.
+pub const WINDOW_TICKS: u64 = 64;
+
+#[derive(Default)]
+pub struct Baselines {
+    frames: VecDeque<Arc<Frame>>,
+}
+
+impl Baselines {
+    pub fn newest_tick(&self) -> Option<u64> {
+        self.frames.back().map(|f| f.tick)
+    }
+
+    /// Keeps `frame`, replacing one of the same tick and dropping those that
+    /// have fallen out of the window. The caller ensures it isn't older than
+    /// the newest.
+    pub fn insert(&mut self, frame: Arc<Frame>) {
+        if self.newest_tick() == Some(frame.tick) {
+            self.frames.pop_back();
+        }
+        let tick = frame.tick;
+        self.frames.push_back(frame);
+        while self
+            .frames
+            .front()
+            .is_some_and(|f| f.tick + WINDOW_TICKS < tick)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn get(&self, tick: u64) -> Option<Arc<Frame>> {
+        let i = self.frames.binary_search_by_key(&tick, |f| f.tick).ok()?;
+        Some(self.frames[i].clone())
+    }
+}
@@ -0,0 +1,703 @@
+//! Wire format for entity snapshots.
+//!
+//! Layout (varints are LEB128, signed ones zigzag-encoded first):
+//!
+//! ```text
+//! u8       format version (1)
+//! u8       packet kind: 1 full, 2 delta
+//! varint   tick
+//! varint   base tick                          (delta only)
+//!
+//! full:    varint count, count × entity
+//! delta:   varint count, count × despawned id
+//!          varint count, count × spawned entity
+//!          varint count, count × (id, u8 changed-field mask, changed fields)
+//! ```
+//!
+//! An entity is `id, varint kind, 3 × svarint position, 3 × svarint velocity,
+//! u16 yaw, i16 pitch, varint health, varint flags`. Ids are written in
+//! ascending order, each as its distance from one past the previous id, so
+//! dense ids cost a byte each. In a delta, position and velocity are written as the
+//! difference from the baseline rather than absolute values.
+
+use super::error::malformed;
+use super::model::{Frame, Quantized, MAX_ENTITIES};
+use super::SnapshotError;
+
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+const KIND_FULL: u8 = 1;
+const KIND_DELTA: u8 = 2;
+
+const CHANGED_KIND: u8 = 1 << 0;
+const CHANGED_POSITION: u8 = 1 << 1;
+const CHANGED_VELOCITY: u8 = 1 << 2;
+const CHANGED_ROTATION: u8 = 1 << 3;
+const CHANGED_HEALTH: u8 = 1 << 4;
+const CHANGED_FLAGS: u8 = 1 << 5;
+const ALL_CHANGES: u8 = (1 << 6) - 1;
+
+/// Smallest possible encoded entity, used to reject counts a packet can't
+/// hold before allocating for them.
+const MIN_ENTITY_LEN: usize = 13;
+
+/// What a packet is, read before its body so the caller can find a baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketKind {
+    Full,
+    Delta { base_tick: u64 },
+}
+
+pub fn encode_full(frame: &Frame) -> Vec<u8> {
+    let mut out = Writer::with_capacity(8 + frame.entities.len() * 20);
+    out.u8(SNAPSHOT_VERSION);
+    out.u8(KIND_FULL);
+    out.varint(frame.tick);
+    out.varint(frame.entities.len() as u64);
+    let mut ids = IdWriter::default();
+    for e in &frame.entities {
+        ids.write(&mut out, e.entity_id);
+        write_entity(&mut out, e);
+    }
+    out.0
+}
+
+/// Encodes only what differs between `base` and `frame`: entities that left,
+/// entities that arrived, and the changed fields of the rest.
+pub fn encode_delta(base: &Frame, frame: &Frame) -> Vec<u8> {
+    let mut despawned = Vec::new();
+    let mut spawned = Vec::new();
+    let mut updated = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < base.entities.len() || j < frame.entities.len() {
+        match (base.entities.get(i), frame.entities.get(j)) {
+            (Some(a), Some(b)) if a.entity_id == b.entity_id => {
+                let mask = changes(a, b);
+                if mask != 0 {
+                    updated.push((a, b, mask));
+                }
+                i += 1;
+                j += 1;
+            }
+            (Some(a), Some(b)) if a.entity_id < b.entity_id => {
+                despawned.push(a.entity_id);
+                i += 1;
+            }
+            (Some(a), None) => {
+                despawned.push(a.entity_id);
+                i += 1;
+            }
+            (_, Some(b)) => {
+                spawned.push(b);
+                j += 1;
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    let mut out = Writer::with_capacity(16 + spawned.len() * 20 + updated.len() * 8);
+    out.u8(SNAPSHOT_VERSION);
+    out.u8(KIND_DELTA);
+    out.varint(frame.tick);
+    out.varint(base.tick);
+
+    out.varint(despawned.len() as u64);
+    let mut ids = IdWriter::default();
+    for id in despawned {
+        ids.write(&mut out, id);
+    }
+
+    out.varint(spawned.len() as u64);
+    let mut ids = IdWriter::default();
+    for e in spawned {
+        ids.write(&mut out, e.entity_id);
+        write_entity(&mut out, e);
+    }
+
+    out.varint(updated.len() as u64);
+    let mut ids = IdWriter::default();
+    for (a, b, mask) in updated {
+        ids.write(&mut out, b.entity_id);
+        out.u8(mask);
+        write_changes(&mut out, a, b, mask);
+    }
+    out.0
+}
+
+/// Reads the version, kind and tick of a packet, leaving the reader at the
+/// body.
+pub fn read_header(bytes: &[u8]) -> Result<(PacketKind, u64, Reader<'_>), SnapshotError> {
+    let mut r = Reader { buf: bytes, pos: 0 };
+    let version = r.u8()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(malformed(format!(
+            "snapshot format version {} is not supported",
+            version
+        )));
+    }
+    let kind = r.u8()?;
+    let tick = r.varint()?;
+    let kind = match kind {
+        KIND_FULL => PacketKind::Full,
+        KIND_DELTA => PacketKind::Delta {
+            base_tick: r.varint()?,
+        },
+        other => return Err(malformed(format!("unknown packet kind {}", other))),
+    };
+    Ok((kind, tick, r))
+}
+
+pub fn decode_full(tick: u64, mut r: Reader<'_>) -> Result<Frame, SnapshotError> {
+    let count = r.count(MIN_ENTITY_LEN)?;
+    let mut entities = Vec::with_capacity(count);
+    let mut ids = IdReader::default();
+    for _ in 0..count {
+        let id = ids.read(&mut r)?;
+        entities.push(read_entity(&mut r, id)?);
+    }
+    r.finish()?;
+    Ok(Frame { tick, entities })
+}
+
+/// Rebuilds the frame a delta was encoded from, given the same `base`.
+pub fn decode_delta(base: &Frame, tick: u64, mut r: Reader<'_>) -> Result<Frame, SnapshotError> {
+    let count = r.count(1)?;
+    let mut despawned = Vec::with_capacity(count);
+    let mut ids = IdReader::default();
+    for _ in 0..count {
+        despawned.push(ids.read(&mut r)?);
+    }
+
+    let count = r.count(MIN_ENTITY_LEN)?;
+    let mut spawned = Vec::with_capacity(count);
+    let mut ids = IdReader::default();
+    for _ in 0..count {
+        let id = ids.read(&mut r)?;
+        spawned.push(read_entity(&mut r, id)?);
+    }
+
+    // Updates come last and in id order, so they are read while walking the
+    // baseline rather than buffered.
+    let mut updates = Updates {
+        remaining: r.count(2)?,
+        ids: IdReader::default(),
+        next: None,
+    };
+    updates.advance(&mut r)?;
+
+    let mut entities = Vec::with_capacity(base.entities.len() + spawned.len());
+    let mut despawned = despawned.into_iter().peekable();
+    let mut spawned = spawned.into_iter().peekable();
+    for e in &base.entities {
+        let id = e.entity_id;
+        let missing = despawned.peek().filter(|&&d| d < id).copied();
+        if let Some(missing) = missing.or(updates.next.map(|(u, _)| u).filter(|&u| u < id)) {
+            return Err(not_in_baseline(missing));
+        }
+        while let Some(s) = spawned.next_if(|s| s.entity_id < id) {
+            entities.push(s);
+        }
+        if spawned.peek().is_some_and(|s| s.entity_id == id) {
+            return Err(malformed(format!(
+                "entity {} is already in the baseline",
+                id
+            )));
+        }
+
+        if despawned.next_if_eq(&id).is_some() {
+            if updates.next.is_some_and(|(u, _)| u == id) {
+                return Err(malformed(format!("entity {} updated after despawning", id)));
+            }
+            continue;
+        }
+        match updates.next {
+            Some((u, mask)) if u == id => {
+                entities.push(apply_changes(&mut r, e, mask)?);
+                updates.advance(&mut r)?;
+            }
+            _ => entities.push(*e),
+        }
+    }
+    if let Some(missing) = despawned.next().or(updates.next.map(|(u, _)| u)) {
+        return Err(not_in_baseline(missing));
+    }
+    r.finish()?;
+
+    entities.extend(spawned);
+    if entities.len() > MAX_ENTITIES {
+        return Err(SnapshotError::TooMany {
+            count: entities.len(),
+            max: MAX_ENTITIES,
+        });
+    }
+    Ok(Frame { tick, entities })
+}
+
+fn not_in_baseline(entity_id: u32) -> SnapshotError {
+    malformed(format!("entity {} is not in the baseline", entity_id))
+}
+
+/// The update records of a delta, read one header at a time.
+struct Updates {
+    remaining: usize,
+    ids: IdReader,
+    /// Id and change mask of the record the reader is positioned at.
+    next: Option<(u32, u8)>,
+}
+
+impl Updates {
+    fn advance(&mut self, r: &mut Reader<'_>) -> Result<(), SnapshotError> {
+        if self.remaining == 0 {
+            self.next = None;
+            return Ok(());
+        }
+        self.remaining -= 1;
+        let id = self.ids.read(r)?;
+        let mask = r.u8()?;
+        if mask == 0 || mask & !ALL_CHANGES != 0 {
+            return Err(malformed(format!("bad change mask for entity {}", id)));
+        }
+        self.next = Some((id, mask));
+        Ok(())
+    }
+}
+
+fn changes(a: &Quantized, b: &Quantized) -> u8 {
+    let mut mask = 0;
+    if a.kind != b.kind {
+        mask |= CHANGED_KIND;
+    }
+    if a.position != b.position {
+        mask |= CHANGED_POSITION;
+    }
+    if a.velocity != b.velocity {
+        mask |= CHANGED_VELOCITY;
+    }
+    if (a.yaw, a.pitch) != (b.yaw, b.pitch) {
+        mask |= CHANGED_ROTATION;
+    }
+    if a.health != b.health {
+        mask |= CHANGED_HEALTH;
+    }
+    if a.flags != b.flags {
+        mask |= CHANGED_FLAGS;
+    }
+    mask
+}
+
+fn write_entity(out: &mut Writer, e: &Quantized) {
+    out.varint(e.kind as u64);
+    for v in e.position {
+        out.svarint(v as i64);
+    }
+    for v in e.velocity {
+        out.svarint(v as i64);
+    }
+    out.u16(e.yaw);
+    out.u16(e.pitch as u16);
+    out.varint(e.health as u64);
+    out.varint(e.flags as u64);
+}
+
+fn read_entity(r: &mut Reader<'_>, entity_id: u32) -> Result<Quantized, SnapshotError> {
+    let kind = narrow(r.varint()?, "kind")?;
+    let mut position = [0; 3];
+    for p in &mut position {
+        *p = narrow(r.svarint()?, "position")?;
+    }
+    let mut velocity = [0; 3];
+    for v in &mut velocity {
+        *v = narrow(r.svarint()?, "velocity")?;
+    }
+    Ok(Quantized {
+        entity_id,
+        kind,
+        position,
+        velocity,
+        yaw: r.u16()?,
+        pitch: r.u16()? as i16,
+        health: narrow(r.varint()?, "health")?,
+        flags: narrow(r.varint()?, "flags")?,
+    })
+}
+
+fn write_changes(out: &mut Writer, a: &Quantized, b: &Quantized, mask: u8) {
+    if mask & CHANGED_KIND != 0 {
+        out.varint(b.kind as u64);
+    }
+    if mask & CHANGED_POSITION != 0 {
+        for i in 0..3 {
+            out.svarint(b.position[i] as i64 - a.position[i] as i64);
+        }
+    }
+    if mask & CHANGED_VELOCITY != 0 {
+        for i in 0..3 {
+            out.svarint(b.velocity[i] as i64 - a.velocity[i] as i64);
+        }
+    }
+    if mask & CHANGED_ROTATION != 0 {
+        out.u16(b.yaw);
+        out.u16(b.pitch as u16);
+    }
+    if mask & CHANGED_HEALTH != 0 {
+        out.varint(b.health as u64);
+    }
+    if mask & CHANGED_FLAGS != 0 {
+        out.varint(b.flags as u64);
+    }
+}
+
+/// Reads fields in the order [`write_changes`] wrote them.
+fn apply_changes(r: &mut Reader<'_>, a: &Quantized, mask: u8) -> Result<Quantized, SnapshotError> {
+    let mut e = *a;
+    if mask & CHANGED_KIND != 0 {
+        e.kind = narrow(r.varint()?, "kind")?;
+    }
+    if mask & CHANGED_POSITION != 0 {
+        for p in &mut e.position {
+            let moved = *p as i64 + r.svarint()?;
+            *p = narrow(moved, "position")?;
+        }
+    }
+    if mask & CHANGED_VELOCITY != 0 {
+        for v in &mut e.velocity {
+            let changed = *v as i64 + r.svarint()?;
+            *v = narrow(changed, "velocity")?;
+        }
+    }
+    if mask & CHANGED_ROTATION != 0 {
+        e.yaw = r.u16()?;
+        e.pitch = r.u16()? as i16;
+    }
+    if mask & CHANGED_HEALTH != 0 {
+        e.health = narrow(r.varint()?, "health")?;
+    }
+    if mask & CHANGED_FLAGS != 0 {
+        e.flags = narrow(r.varint()?, "flags")?;
+    }
+    Ok(e)
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    fn svarint(&mut self, v: i64) {
+        self.varint(((v << 1) ^ (v >> 63)) as u64);
+    }
+}
+
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        let v = *self
+            .buf
+            .get(self.pos)
+            .ok_or_else(|| malformed("packet is truncated"))?;
+        self.pos += 1;
+        Ok(v)
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            v |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(malformed("varint is longer than 64 bits"))
+    }
+
+    fn svarint(&mut self) -> Result<i64, SnapshotError> {
+        let v = self.varint()?;
+        Ok((v >> 1) as i64 ^ -((v & 1) as i64))
+    }
+
+    /// A list length, checked against what the rest of the packet could
+    /// hold at `min_len` bytes per item.
+    fn count(&mut self, min_len: usize) -> Result<usize, SnapshotError> {
+        let count = self.varint()?;
+        let room = (self.buf.len() - self.pos) / min_len;
+        if count > MAX_ENTITIES as u64 || count as usize > room {
+            return Err(malformed(format!("list of {} entries does not fit", count)));
+        }
+        Ok(count as usize)
+    }
+
+    fn finish(&self) -> Result<(), SnapshotError> {
+        if self.pos != self.buf.len() {
+            return Err(malformed(format!(
+                "{} trailing bytes",
+                self.buf.len() - self.pos
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn narrow<T: TryFrom<V>, V>(v: V, field: &str) -> Result<T, SnapshotError> {
+    T::try_from(v).map_err(|_| malformed(format!("{} is out of range", field)))
+}
+
+/// Writes ascending ids as gaps.
+#[derive(Default)]
+struct IdWriter {
+    next: u64,
+}
+
+impl IdWriter {
+    fn write(&mut self, out: &mut Writer, id: u32) {
+        out.varint(id as u64 - self.next);
+        self.next = id as u64 + 1;
+    }
+}
+
+#[derive(Default)]
+struct IdReader {
+    next: u64,
+}
+
+impl IdReader {
+    fn read(&mut self, r: &mut Reader<'_>) -> Result<u32, SnapshotError> {
+        let id = self
+            .next
+            .checked_add(r.varint()?)
+            .and_then(|id| u32::try_from(id).ok())
+            .ok_or_else(|| malformed("entity id is out of range"))?;
+        self.next = id as u64 + 1;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Xoshiro256StarStar;
+
+    /// Picks from a few interesting values as often as from the whole range,
+    /// so extremes and small deltas both come up.
+    fn pick_i32(rng: &mut Xoshiro256StarStar) -> i32 {
+        match rng.next_below(4) {
+            0 => [0, 1, -1, i32::MIN, i32::MAX][rng.next_below(5) as usize],
+            1 => rng.next_below(2_000) as i32 - 1_000,
+            _ => rng.next_u32() as i32,
+        }
+    }
+
+    fn random_entity(rng: &mut Xoshiro256StarStar, entity_id: u32) -> Quantized {
+        Quantized {
+            entity_id,
+            kind: rng.next_below(8) as u16,
+            position: [0; 3].map(|_| pick_i32(rng)),
+            velocity: [0; 3].map(|_| pick_i32(rng) as i16),
+            yaw: rng.next_u32() as u16,
+            pitch: rng.next_u32() as i16,
+            health: rng.next_below(201) as u16,
+            flags: pick_i32(rng) as u32,
+        }
+    }
+
+    fn random_frame(rng: &mut Xoshiro256StarStar, tick: u64) -> Frame {
+        let count = rng.next_below(40);
+        let mut id = 0u32;
+        let entities = (0..count)
+            .map(|_| {
+                // Mostly dense ids with the odd large gap.
+                id += if rng.next_below(8) == 0 {
+                    rng.next_below(1 << 20)
+                } else {
+                    rng.next_below(3)
+                };
+                let e = random_entity(rng, id);
+                id += 1;
+                e
+            })
+            .collect();
+        Frame { tick, entities }
+    }
+
+    /// `base` with some entities gone, some new and some changed.
+    fn evolve(rng: &mut Xoshiro256StarStar, base: &Frame) -> Frame {
+        let mut entities: Vec<Quantized> = base
+            .entities
+            .iter()
+            .filter_map(|e| {
+                if rng.next_below(6) == 0 {
+                    return None;
+                }
+                let mut e = *e;
+                if rng.next_below(2) == 0 {
+                    let fresh = random_entity(rng, e.entity_id);
+                    let mask = rng.next_below(64);
+                    if mask & 1 != 0 {
+                        e.kind = fresh.kind;
+                    }
+                    if mask & 2 != 0 {
+                        e.position = fresh.position;
+                    }
+                    if mask & 4 != 0 {
+                        e.velocity = fresh.velocity;
+                    }
+                    if mask & 8 != 0 {
+                        (e.yaw, e.pitch) = (fresh.yaw, fresh.pitch);
+                    }
+                    if mask & 16 != 0 {
+                        e.health = fresh.health;
+                    }
+                    if mask & 32 != 0 {
+                        e.flags = fresh.flags;
+                    }
+                }
+                Some(e)
+            })
+            .collect();
+        for _ in 0..rng.next_below(5) {
+            let id = rng.next_u32() >> rng.next_below(32);
+            if entities.iter().all(|e| e.entity_id != id) {
+                entities.push(random_entity(rng, id));
+            }
+        }
+        entities.sort_unstable_by_key(|e| e.entity_id);
+        Frame {
+            tick: base.tick + 1 + rng.next_below(4) as u64,
+            entities,
+        }
+    }
+
+    fn decode(base: Option<&Frame>, bytes: &[u8]) -> Result<Frame, SnapshotError> {
+        let (kind, tick, body) = read_header(bytes)?;
+        match (kind, base) {
+            (PacketKind::Full, _) => decode_full(tick, body),
+            (PacketKind::Delta { base_tick }, Some(base)) => {
+                assert_eq!(base_tick, base.tick);
+                decode_delta(base, tick, body)
+            }
+            (PacketKind::Delta { .. }, None) => panic!("delta without a baseline"),
+        }
+    }
+
+    #[test]
+    fn random_full_snapshots_round_trip() {
+        let mut rng = Xoshiro256StarStar::from_seed(30);
+        for tick in 0..2_000 {
+            let frame = random_frame(&mut rng, tick * 7);
+            assert_eq!(decode(None, &encode_full(&frame)).unwrap(), frame);
+        }
+    }
+
+    #[test]
+    fn random_deltas_round_trip() {
+        let mut rng = Xoshiro256StarStar::from_seed(31);
+        for _ in 0..200 {
+            let tick = rng.next_below(1_000) as u64;
+            let mut base = random_frame(&mut rng, tick);
+            // Chains of deltas, each against the frame before it, like a
+            // receiver that acknowledges every packet.
+            for _ in 0..20 {
+                let frame = evolve(&mut rng, &base);
+                let delta = encode_delta(&base, &frame);
+                assert_eq!(decode(Some(&base), &delta).unwrap(), frame);
+                base = frame;
+            }
+        }
+    }
+
+    #[test]
+    fn deltas_between_unrelated_frames_round_trip() {
+        let mut rng = Xoshiro256StarStar::from_seed(32);
+        for tick in 1..1_000 {
+            let base = random_frame(&mut rng, tick);
+            let frame = random_frame(&mut rng, tick + 1);
+            let delta = encode_delta(&base, &frame);
+            assert_eq!(decode(Some(&base), &delta).unwrap(), frame);
+        }
+    }
+
+    #[test]
+    fn unchanged_frames_cost_a_header() {
+        let mut rng = Xoshiro256StarStar::from_seed(33);
+        let base = random_frame(&mut rng, 100);
+        let frame = Frame {
+            tick: 101,
+            entities: base.entities.clone(),
+        };
+        let delta = encode_delta(&base, &frame);
+        assert_eq!(delta, [SNAPSHOT_VERSION, KIND_DELTA, 101, 100, 0, 0, 0]);
+        assert_eq!(decode(Some(&base), &delta).unwrap(), frame);
+    }
+
+    #[test]
+    fn damaged_packets_fail_cleanly() {
+        let mut rng = Xoshiro256StarStar::from_seed(34);
+        for _ in 0..200 {
+            let base = random_frame(&mut rng, 10);
+            let frame = evolve(&mut rng, &base);
+            let full = encode_full(&frame);
+            let delta = encode_delta(&base, &frame);
+
+            for len in 0..delta.len() {
+                assert!(decode(Some(&base), &delta[..len]).is_err());
+            }
+            for len in 0..full.len() {
+                assert!(decode(None, &full[..len]).is_err());
+            }
+            // Flipped bits must never panic; most are caught, and a few
+            // decode to some other valid frame.
+            let mut flipped = delta.clone();
+            let at = rng.next_below(flipped.len() as u32) as usize;
+            flipped[at] ^= 1 << rng.next_below(8);
+            if let Ok((PacketKind::Delta { base_tick: 10 }, tick, body)) = read_header(&flipped) {
+                let _ = decode_delta(&base, tick, body);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_deltas_against_the_wrong_baseline() {
+        let mut rng = Xoshiro256StarStar::from_seed(35);
+        let base = random_frame(&mut rng, 1);
+        let mut frame = base.clone();
+        frame.tick = 2;
+        let gone = frame.entities.remove(0);
+        let delta = encode_delta(&base, &frame);
+
+        let other = Frame {
+            tick: 1,
+            entities: Vec::new(),
+        };
+        let (_, tick, body) = read_header(&delta).unwrap();
+        let err = decode_delta(&other, tick, body).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("entity {} is not in the baseline", gone.entity_id)));
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the snapshot commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("Invalid entity {entity_id}: {reason}")]
+    InvalidState { entity_id: u32, reason: String },
+
+    #[error("Malformed snapshot: {0}")]
+    Malformed(String),
+
+    #[error("No baseline for tick {0}; a full snapshot is needed")]
+    UnknownBaseline(u64),
+
+    #[error("Snapshot for tick {tick} is older than the newest, tick {newest}")]
+    StaleTick { tick: u64, newest: u64 },
+
+    #[error("Too many entities: {count} (limit {max})")]
+    TooMany { count: usize, max: usize },
+}
+
+impl SnapshotError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SnapshotError::InvalidState { .. } => "invalidState",
+            SnapshotError::Malformed(_) => "malformed",
+            SnapshotError::UnknownBaseline(_) => "unknownBaseline",
+            SnapshotError::StaleTick { .. } => "staleTick",
+            SnapshotError::TooMany { .. } => "tooMany",
+        }
+    }
+}
+
+impl Serialize for SnapshotError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SnapshotError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+pub(crate) fn malformed(reason: impl Into<String>) -> SnapshotError {
+    SnapshotError::Malformed(reason.into())
+}
@@ -0,0 +1,134 @@
+mod baselines;
+mod codec;
+mod error;
+mod model;
+
+pub use error::SnapshotError;
+pub use model::WorldSnapshot;
+
+use baselines::Baselines;
+use codec::PacketKind;
+use model::Frame;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+/// Baselines for delta-encoding entity snapshots: `sent` on the side that
+/// encodes them, `received` on the side that decodes them.
+#[derive(Default)]
+pub struct SnapshotCodec {
+    sent: Mutex<Baselines>,
+    received: Mutex<Baselines>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedSnapshot {
+    pub tick: u64,
+    /// The packet is a full snapshot rather than a delta, either because one
+    /// was asked for or because the requested baseline was no longer held.
+    pub full: bool,
+    pub bytes: Vec<u8>,
+}
+
+/// Encodes every entity of `state` and keeps it as a baseline for later
+/// deltas.
+#[tauri::command]
+pub async fn encode_snapshot(
+    codec: State<'_, SnapshotCodec>,
+    state: WorldSnapshot,
+) -> Result<EncodedSnapshot, SnapshotError> {
+    let frame = Frame::quantize(&state)?;
+    let mut sent = codec.sent.lock().unwrap();
+    check_newest(&sent, frame.tick)?;
+    let bytes = codec::encode_full(&frame);
+    sent.insert(Arc::new(frame));
+    Ok(EncodedSnapshot {
+        tick: state.tick,
+        full: true,
+        bytes,
+    })
+}
+
+/// Encodes `state` as changes since `base_tick`, the newest snapshot the
+/// receiver has acknowledged. Falls back to a full snapshot when that
+/// baseline was never sent or has aged out.
+#[tauri::command]
+pub async fn encode_delta(
+    codec: State<'_, SnapshotCodec>,
+    base_tick: u64,
+    state: WorldSnapshot,
+) -> Result<EncodedSnapshot, SnapshotError> {
+    let frame = Frame::quantize(&state)?;
+    let mut sent = codec.sent.lock().unwrap();
+    check_newest(&sent, frame.tick)?;
+    let (bytes, full) = match sent.get(base_tick).filter(|b| b.tick < frame.tick) {
+        Some(base) => (codec::encode_delta(&base, &frame), false),
+        None => (codec::encode_full(&frame), true),
+    };
+    sent.insert(Arc::new(frame));
+    Ok(EncodedSnapshot {
+        tick: state.tick,
+        full,
+        bytes,
+    })
+}
+
+/// Decodes a full snapshot and keeps it as a baseline for later deltas.
+#[tauri::command]
+pub async fn decode_snapshot(
+    codec: State<'_, SnapshotCodec>,
+    bytes: Vec<u8>,
+) -> Result<WorldSnapshot, SnapshotError> {
+    let (kind, tick, body) = codec::read_header(&bytes)?;
+    if kind != PacketKind::Full {
+        return Err(SnapshotError::Malformed(
+            "expected a full snapshot, got a delta".to_string(),
+        ));
+    }
+    let frame = codec::decode_full(tick, body)?;
+    Ok(receive(&codec, frame))
+}
+
+/// Applies a delta to the baseline it was encoded against. Full snapshots,
+/// such as the encoder's fallback, are accepted too. Fails with
+/// `unknownBaseline` when the baseline isn't held, in which case the sender
+/// should be asked for a full snapshot.
+#[tauri::command]
+pub async fn apply_delta(
+    codec: State<'_, SnapshotCodec>,
+    bytes: Vec<u8>,
+) -> Result<WorldSnapshot, SnapshotError> {
+    let (kind, tick, body) = codec::read_header(&bytes)?;
+    let frame = match kind {
+        PacketKind::Full => codec::decode_full(tick, body)?,
+        PacketKind::Delta { base_tick } => {
+            let base = codec
+                .received
+                .lock()
+                .unwrap()
+                .get(base_tick)
+                .ok_or(SnapshotError::UnknownBaseline(base_tick))?;
+            codec::decode_delta(&base, tick, body)?
+        }
+    };
+    Ok(receive(&codec, frame))
+}
+
+fn check_newest(baselines: &Baselines, tick: u64) -> Result<(), SnapshotError> {
+    match baselines.newest_tick() {
+        Some(newest) if tick < newest => Err(SnapshotError::StaleTick { tick, newest }),
+        _ => Ok(()),
+    }
+}
+
+/// Keeps a decoded frame as a baseline unless a newer one has already
+/// arrived; packets can be reordered on the way.
+fn receive(codec: &SnapshotCodec, frame: Frame) -> WorldSnapshot {
+    let snapshot = frame.dequantize();
+    let mut received = codec.received.lock().unwrap();
+    if check_newest(&received, frame.tick).is_ok() {
+        received.insert(Arc::new(frame));
+    }
+    snapshot
+}
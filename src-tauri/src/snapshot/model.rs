@@ -0,0 +1,150 @@
+use super::SnapshotError;
+use crate::simulation::Vec3;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+pub const MAX_ENTITIES: usize = 16_384;
+
+/// Positions are stored in 1/512 units (about 2 mm at one unit per metre).
+const POSITION_SCALE: f32 = 512.0;
+/// Velocities in 1/64 units per second, saturating at about ±512.
+const VELOCITY_SCALE: f32 = 64.0;
+/// Yaw wraps over the full turn; pitch covers straight down to straight up.
+const YAW_SCALE: f32 = 65_536.0 / TAU;
+const PITCH_SCALE: f32 = i16::MAX as f32 / FRAC_PI_2;
+
+/// The replicated state of every entity at one tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldSnapshot {
+    pub tick: u64,
+    pub entities: Vec<EntityState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityState {
+    pub entity_id: u32,
+    #[serde(default)]
+    pub kind: u16,
+    pub position: Vec3,
+    #[serde(default)]
+    pub velocity: Vec3,
+    /// Radians; any value, wrapped to `[0, 2π)`.
+    pub yaw: f32,
+    /// Radians, clamped to `[-π/2, π/2]`.
+    pub pitch: f32,
+    #[serde(default)]
+    pub health: u16,
+    /// Game-defined state bits (crouching, reloading, ...).
+    #[serde(default)]
+    pub flags: u32,
+}
+
+/// An entity as it goes over the wire. Snapshots are compared and stored in
+/// this form, so a baseline is exactly what the receiver decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantized {
+    pub entity_id: u32,
+    pub kind: u16,
+    pub position: [i32; 3],
+    pub velocity: [i16; 3],
+    pub yaw: u16,
+    pub pitch: i16,
+    pub health: u16,
+    pub flags: u32,
+}
+
+/// A snapshot in wire form, entities sorted by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub tick: u64,
+    pub entities: Vec<Quantized>,
+}
+
+impl Frame {
+    pub fn quantize(snapshot: &WorldSnapshot) -> Result<Self, SnapshotError> {
+        if snapshot.entities.len() > MAX_ENTITIES {
+            return Err(SnapshotError::TooMany {
+                count: snapshot.entities.len(),
+                max: MAX_ENTITIES,
+            });
+        }
+        let mut entities = snapshot
+            .entities
+            .iter()
+            .map(quantize)
+            .collect::<Result<Vec<_>, _>>()?;
+        entities.sort_unstable_by_key(|e| e.entity_id);
+        if let Some(pair) = entities
+            .windows(2)
+            .find(|w| w[0].entity_id == w[1].entity_id)
+        {
+            return Err(SnapshotError::InvalidState {
+                entity_id: pair[0].entity_id,
+                reason: "appears more than once".to_string(),
+            });
+        }
+        Ok(Self {
+            tick: snapshot.tick,
+            entities,
+        })
+    }
+
+    pub fn dequantize(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            tick: self.tick,
+            entities: self.entities.iter().map(dequantize).collect(),
+        }
+    }
+}
+
+fn quantize(e: &EntityState) -> Result<Quantized, SnapshotError> {
+    let invalid = |reason: &str| SnapshotError::InvalidState {
+        entity_id: e.entity_id,
+        reason: reason.to_string(),
+    };
+    if !(e.position.is_finite() && e.velocity.is_finite()) {
+        return Err(invalid("position and velocity must be finite"));
+    }
+    if !(e.yaw.is_finite() && e.pitch.is_finite()) {
+        return Err(invalid("yaw and pitch must be finite"));
+    }
+
+    let mut position = [0; 3];
+    for (i, p) in position.iter_mut().enumerate() {
+        let scaled = (e.position.axis(i) * POSITION_SCALE).round();
+        if scaled < i32::MIN as f32 || scaled >= i32::MAX as f32 {
+            return Err(invalid("position is outside the encodable range"));
+        }
+        *p = scaled as i32;
+    }
+    // Float-to-int `as` saturates, which is the clamp wanted for velocity.
+    let velocity = [0, 1, 2].map(|i| (e.velocity.axis(i) * VELOCITY_SCALE).round() as i16);
+
+    Ok(Quantized {
+        entity_id: e.entity_id,
+        kind: e.kind,
+        position,
+        velocity,
+        yaw: ((e.yaw.rem_euclid(TAU) * YAW_SCALE).round() as u32 & 0xffff) as u16,
+        pitch: (e.pitch.clamp(-FRAC_PI_2, FRAC_PI_2) * PITCH_SCALE).round() as i16,
+        health: e.health,
+        flags: e.flags,
+    })
+}
+
+fn dequantize(q: &Quantized) -> EntityState {
+    let [px, py, pz] = q.position.map(|v| v as f32 / POSITION_SCALE);
+    let [vx, vy, vz] = q.velocity.map(|v| v as f32 / VELOCITY_SCALE);
+    EntityState {
+        entity_id: q.entity_id,
+        kind: q.kind,
+        position: Vec3::new(px, py, pz),
+        velocity: Vec3::new(vx, vy, vz),
+        yaw: q.yaw as f32 / YAW_SCALE,
+        pitch: q.pitch as f32 / PITCH_SCALE,
+        health: q.health,
+        flags: q.flags,
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the stats commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum StatsError {
+    #[error("Could not locate the profile directory: {0}")]
+    NoProfileDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid stat {key:?}: {reason}")]
+    InvalidStat { key: String, reason: String },
+
+    #[error("Invalid period: {0}")]
+    InvalidPeriod(String),
+}
+
+impl StatsError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        StatsError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StatsError::NoProfileDir(_) => "noProfileDir",
+            StatsError::Io { .. } => "io",
+            StatsError::InvalidStat { .. } => "invalidStat",
+            StatsError::InvalidPeriod(_) => "invalidPeriod",
+        }
+    }
+}
+
+impl Serialize for StatsError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("StatsError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,83 @@
+mod error;
+mod model;
+mod store;
+
+pub use error::StatsError;
+pub use model::{PeriodStats, StatsView};
+pub use store::StatsStore;
+
+use crate::time::now_ms;
+use model::Totals;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{Manager, Runtime, State};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Adds `deltas` to today's bucket. Only touches memory; the buckets are
+/// written out every 30 seconds and on exit.
+#[tauri::command]
+pub async fn record_stat_delta(
+    app: tauri::AppHandle,
+    store: State<'_, StatsStore>,
+    deltas: HashMap<String, f64>,
+) -> Result<(), StatsError> {
+    model::validate(&deltas)?;
+    let day = model::day_of(now_ms());
+    store.with_active(&app, |loaded| loaded.record(day, &deltas))?
+}
+
+/// Lifetime totals for the active profile, with derived values.
+#[tauri::command]
+pub async fn get_stats(
+    app: tauri::AppHandle,
+    store: State<'_, StatsStore>,
+) -> Result<StatsView, StatsError> {
+    let totals = store.with_active(&app, |loaded| loaded.lifetime())?;
+    Ok(StatsView::new(totals))
+}
+
+/// Totals for the UTC days overlapping `since..=until` (Unix ms, `until`
+/// defaulting to now), plus the per-day breakdown.
+#[tauri::command]
+pub async fn get_stats_for_period(
+    app: tauri::AppHandle,
+    store: State<'_, StatsStore>,
+    since: u64,
+    until: Option<u64>,
+) -> Result<PeriodStats, StatsError> {
+    let until = until.unwrap_or_else(now_ms);
+    if since > until {
+        return Err(StatsError::InvalidPeriod(format!(
+            "since ({}) is after until ({})",
+            since, until
+        )));
+    }
+    let range = model::day_of(since)..=model::day_of(until);
+    let days = store.with_active(&app, |loaded| loaded.days(range))?;
+
+    let mut totals = Totals::new();
+    for day in &days {
+        model::add_into(&mut totals, &day.totals);
+    }
+    Ok(PeriodStats {
+        view: StatsView::new(totals),
+        days,
+    })
+}
+
+/// Flushes pending stats every [`FLUSH_INTERVAL`] for the life of the app.
+pub fn start_flush_timer<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        flush(&app);
+    });
+}
+
+/// Writes pending stats now, logging on failure. Called on exit.
+pub fn flush<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Err(e) = app.state::<StatsStore>().flush() {
+        log::error!("Failed to flush stats: {}", e);
+    }
+}
@@ -0,0 +1,109 @@
+use super::StatsError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub type Totals = BTreeMap<String, f64>;
+
+pub const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Keys the backend derives values from. Any other key is stored and summed
+/// as-is so the frontend can add stats without a backend change.
+pub mod keys {
+    pub const SHOTS_FIRED: &str = "shotsFired";
+    pub const SHOTS_HIT: &str = "shotsHit";
+    pub const KILLS: &str = "kills";
+    pub const DEATHS: &str = "deaths";
+    pub const PLAYTIME_MS: &str = "playtimeMs";
+    /// `weaponShots.<weaponId>`; the weapon with the most shots is the favorite.
+    pub const WEAPON_SHOTS_PREFIX: &str = "weaponShots.";
+}
+
+const MAX_KEY_LEN: usize = 64;
+/// Distinct keys per day, so a buggy caller can't grow a bucket unbounded.
+pub const MAX_KEYS_PER_DAY: usize = 256;
+
+/// Totals plus the values derived from them.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsView {
+    pub totals: Totals,
+    /// `shotsHit / shotsFired`, `None` before the first shot.
+    pub accuracy: Option<f64>,
+    /// Kills per death, with zero deaths counted as one.
+    pub kill_death_ratio: f64,
+    pub playtime_ms: f64,
+    pub favorite_weapon: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyStats {
+    /// Midnight UTC at the start of the bucket.
+    pub day_start_ms: u64,
+    pub totals: Totals,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodStats {
+    #[serde(flatten)]
+    pub view: StatsView,
+    pub days: Vec<DailyStats>,
+}
+
+impl StatsView {
+    pub fn new(totals: Totals) -> Self {
+        let get = |key: &str| totals.get(key).copied().unwrap_or(0.0);
+
+        let fired = get(keys::SHOTS_FIRED);
+        let accuracy = (fired > 0.0).then(|| (get(keys::SHOTS_HIT) / fired).clamp(0.0, 1.0));
+        let kill_death_ratio = get(keys::KILLS) / get(keys::DEATHS).max(1.0);
+        let favorite_weapon = totals
+            .iter()
+            .filter_map(|(key, &shots)| {
+                let weapon = key.strip_prefix(keys::WEAPON_SHOTS_PREFIX)?;
+                (shots > 0.0 && !weapon.is_empty()).then_some((weapon, shots))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(weapon, _)| weapon.to_string());
+
+        Self {
+            playtime_ms: get(keys::PLAYTIME_MS),
+            accuracy,
+            kill_death_ratio,
+            favorite_weapon,
+            totals,
+        }
+    }
+}
+
+/// Rejects keys that couldn't have come from a sane caller and non-finite
+/// deltas, which would poison every total they touch.
+pub fn validate(deltas: &HashMap<String, f64>) -> Result<(), StatsError> {
+    for (key, delta) in deltas {
+        let invalid = |reason: &str| StatsError::InvalidStat {
+            key: key.clone(),
+            reason: reason.to_string(),
+        };
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(invalid("key must be 1-64 bytes"));
+        }
+        if key.chars().any(char::is_control) {
+            return Err(invalid("key contains control characters"));
+        }
+        if !delta.is_finite() {
+            return Err(invalid("delta must be a finite number"));
+        }
+    }
+    Ok(())
+}
+
+pub fn day_of(ms: u64) -> u32 {
+    (ms / DAY_MS) as u32
+}
+
+pub fn add_into(totals: &mut Totals, other: &Totals) {
+    for (key, value) in other {
+        *totals.entry(key.clone()).or_insert(0.0) += value;
+    }
+}
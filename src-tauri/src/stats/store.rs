@@ -0,0 +1,145 @@
+use super::model::{self, DailyStats, Totals, DAY_MS, MAX_KEYS_PER_DAY};
+use super::StatsError;
+use crate::fs_atomic::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Runtime;
+
+pub const FILE_NAME: &str = "stats.json";
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Daily buckets keyed by days since the Unix epoch (UTC).
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StatsFile {
+    version: u32,
+    days: BTreeMap<u32, Totals>,
+}
+
+/// The active profile's buckets, held in memory and written back by
+/// [`StatsStore::flush`] rather than on every delta.
+pub struct Loaded {
+    dir: PathBuf,
+    file: StatsFile,
+    dirty: bool,
+}
+
+#[derive(Default)]
+pub struct StatsStore {
+    state: Mutex<Option<Loaded>>,
+}
+
+impl StatsStore {
+    /// Runs `f` against the active profile's stats, flushing the previous
+    /// profile's and loading the new one's if the player switched.
+    pub fn with_active<R: Runtime, T>(
+        &self,
+        app: &tauri::AppHandle<R>,
+        f: impl FnOnce(&mut Loaded) -> T,
+    ) -> Result<T, StatsError> {
+        let dir = crate::profiles::active_dir(app)
+            .map_err(|e| StatsError::NoProfileDir(e.to_string()))?;
+        let mut state = self.state.lock().unwrap();
+        if state.as_ref().is_none_or(|loaded| loaded.dir != dir) {
+            if let Some(previous) = state.as_mut() {
+                if let Err(e) = previous.flush() {
+                    log::error!("{}; stats since the last flush are lost", e);
+                }
+            }
+            *state = Some(Loaded::load(dir)?);
+        }
+        Ok(f(state.as_mut().unwrap()))
+    }
+
+    /// Writes pending deltas to disk. Cheap when nothing changed.
+    pub fn flush(&self) -> Result<(), StatsError> {
+        match self.state.lock().unwrap().as_mut() {
+            Some(loaded) => loaded.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Loaded {
+    /// A missing file starts from zero. One that doesn't parse is moved to
+    /// `stats.json.bak` so the game can keep counting.
+    fn load(dir: PathBuf) -> Result<Self, StatsError> {
+        let path = dir.join(FILE_NAME);
+        let file = match std::fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(file) => file,
+                Err(e) => {
+                    let backup = path.with_extension("json.bak");
+                    log::warn!("Invalid {:?} ({}); moved to {:?}", path, e, backup);
+                    std::fs::rename(&path, &backup).map_err(|e| StatsError::io(&path, e))?;
+                    StatsFile::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => StatsFile::default(),
+            Err(e) => return Err(StatsError::io(&path, e)),
+        };
+        Ok(Self {
+            dir,
+            file,
+            dirty: false,
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), StatsError> {
+        // A profile deleted since it was loaded has nowhere to flush to, and
+        // writing would recreate its directory.
+        if !self.dirty || !self.dir.is_dir() {
+            return Ok(());
+        }
+        let path = self.dir.join(FILE_NAME);
+        self.file.version = FORMAT_VERSION;
+        let json = serde_json::to_vec(&self.file).expect("stats serialize");
+        write_atomic(&path, &json).map_err(|e| StatsError::io(&path, e))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Adds `deltas` to `day`'s bucket. Applies all of them or none.
+    pub fn record(&mut self, day: u32, deltas: &HashMap<String, f64>) -> Result<(), StatsError> {
+        let bucket = self.file.days.entry(day).or_default();
+        let new_keys = deltas.keys().filter(|k| !bucket.contains_key(*k)).count();
+        if bucket.len() + new_keys > MAX_KEYS_PER_DAY {
+            let key = deltas
+                .keys()
+                .find(|k| !bucket.contains_key(*k))
+                .cloned()
+                .unwrap_or_default();
+            return Err(StatsError::InvalidStat {
+                key,
+                reason: format!("more than {} distinct stats in one day", MAX_KEYS_PER_DAY),
+            });
+        }
+        for (key, delta) in deltas {
+            *bucket.entry(key.clone()).or_insert(0.0) += delta;
+        }
+        self.dirty |= !deltas.is_empty();
+        Ok(())
+    }
+
+    pub fn lifetime(&self) -> Totals {
+        let mut totals = Totals::new();
+        for bucket in self.file.days.values() {
+            model::add_into(&mut totals, bucket);
+        }
+        totals
+    }
+
+    pub fn days(&self, range: RangeInclusive<u32>) -> Vec<DailyStats> {
+        self.file
+            .days
+            .range(range)
+            .map(|(&day, totals)| DailyStats {
+                day_start_ms: day as u64 * DAY_MS,
+                totals: totals.clone(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the support bundle command, serialized as
+/// `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum SupportError {
+    #[error("Could not locate the downloads directory: {0}")]
+    NoDownloadDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Bundle path {0:?} must be absolute")]
+    InvalidExportPath(PathBuf),
+}
+
+impl SupportError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        SupportError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        SupportError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SupportError::NoDownloadDir(_) => "noDownloadDir",
+            SupportError::Io { .. } => "io",
+            SupportError::InvalidExportPath(_) => "invalidExportPath",
+        }
+    }
+}
+
+impl Serialize for SupportError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SupportError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
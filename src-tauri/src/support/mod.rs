@@ -0,0 +1,118 @@
+mod bundle;
+mod error;
+mod zip;
+
+pub use error::SupportError;
+
+use crate::time::now_ms;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::path::BaseDirectory;
+use tauri::{Emitter, Manager};
+
+pub const PROGRESS_EVENT: &str = "support-bundle-progress";
+const DEFAULT_MAX_BYTES: u64 = 32 * 1024 * 1024;
+const MAX_BYTES_RANGE: std::ops::RangeInclusive<u64> = 1024 * 1024..=512 * 1024 * 1024;
+
+/// Which pieces go into a bundle; everything, by default.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BundleOptions {
+    pub logs: bool,
+    pub settings: bool,
+    pub crash_reports: bool,
+    pub benchmark: bool,
+    pub asset_verification: bool,
+    pub system_info: bool,
+    /// Cap on the uncompressed contents, 1 MB to 512 MB, 32 MB by default.
+    /// Older logs are left out first to stay under it.
+    pub max_bytes: Option<u64>,
+}
+
+impl Default for BundleOptions {
+    fn default() -> Self {
+        Self {
+            logs: true,
+            settings: true,
+            crash_reports: true,
+            benchmark: true,
+            asset_verification: true,
+            system_info: true,
+            max_bytes: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleProgress {
+    pub done: usize,
+    pub total: usize,
+    pub current: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportBundle {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// Zips logs, settings with credentials redacted, crash reports, the
+/// latest benchmark, the last asset verification and system info for a
+/// bug report. Pieces that are missing or fail to load are listed in the
+/// bundle's `manifest.txt` rather than failing it. Emits
+/// `support-bundle-progress` as entries are compressed.
+///
+/// `path` may be a file or an existing directory; without one the bundle
+/// goes to the downloads directory.
+#[tauri::command]
+pub async fn create_support_bundle(
+    app: tauri::AppHandle,
+    include: Option<BundleOptions>,
+    path: Option<String>,
+) -> Result<SupportBundle, SupportError> {
+    let options = include.unwrap_or_default();
+    let created_at_ms = now_ms();
+    let default_name = format!("fps-game-support-{}.zip", created_at_ms);
+    let target = match path.map(PathBuf::from) {
+        Some(path) if !path.is_absolute() => return Err(SupportError::InvalidExportPath(path)),
+        Some(path) if path.is_dir() => path.join(default_name),
+        Some(path) => path,
+        None => app
+            .path()
+            .resolve(&default_name, BaseDirectory::Download)
+            .map_err(|e| SupportError::NoDownloadDir(e.to_string()))?,
+    };
+
+    let task_target = target.clone();
+    let size_bytes = tauri::async_runtime::spawn_blocking(move || {
+        let max_bytes = options
+            .max_bytes
+            .unwrap_or(DEFAULT_MAX_BYTES)
+            .clamp(*MAX_BYTES_RANGE.start(), *MAX_BYTES_RANGE.end());
+        let mut contents = bundle::gather(&app, &options);
+        if options.logs {
+            crate::logging::flush(&app);
+            bundle::add_logs(&mut contents, crate::logging::log_files(&app), max_bytes);
+        }
+        let zip = bundle::write(contents, created_at_ms, |progress| {
+            let _ = app.emit(PROGRESS_EVENT, progress);
+        })
+        .map_err(|e| SupportError::io(&target, e))?;
+        crate::fs_atomic::write_atomic(&target, &zip).map_err(|e| SupportError::io(&target, e))?;
+        Ok::<_, SupportError>(zip.len() as u64)
+    })
+    .await
+    .map_err(|e| SupportError::task(&task_target, e))??;
+
+    log::info!(
+        "Support bundle written to {:?} ({} bytes)",
+        task_target,
+        size_bytes
+    );
+    Ok(SupportBundle {
+        path: task_target.to_string_lossy().into_owned(),
+        size_bytes,
+    })
+}
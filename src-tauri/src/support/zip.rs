@@ -0,0 +1,215 @@
+//! Just enough of the ZIP format to write a bundle: deflated entries with
+//! UTF-8 names, no ZIP64, built in memory.
+
+use crate::time::UtcDateTime;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const VERSION: u16 = 20;
+const FLAG_UTF8: u16 = 0x0800;
+const METHOD_DEFLATE: u16 = 8;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    offset: u32,
+}
+
+pub struct ZipWriter {
+    out: Vec<u8>,
+    entries: Vec<Entry>,
+    /// DOS date and time every entry is stamped with.
+    time: u16,
+    date: u16,
+}
+
+impl ZipWriter {
+    /// `timestamp_ms` is the modification time given to every entry.
+    pub fn new(timestamp_ms: u64) -> Self {
+        let (date, time) = dos_date_time(timestamp_ms);
+        Self {
+            out: Vec::new(),
+            entries: Vec::new(),
+            time,
+            date,
+        }
+    }
+
+    pub fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        let too_big = || io::Error::other(format!("{} is too large for a zip without ZIP64", name));
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc32fast::hash(data),
+            compressed: u32::try_from(compressed.len()).map_err(|_| too_big())?,
+            size: u32::try_from(data.len()).map_err(|_| too_big())?,
+            offset: u32::try_from(self.out.len()).map_err(|_| too_big())?,
+        };
+
+        let out = &mut self.out;
+        out.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        out.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        out.extend_from_slice(&self.time.to_le_bytes());
+        out.extend_from_slice(&self.date.to_le_bytes());
+        out.extend_from_slice(&entry.crc.to_le_bytes());
+        out.extend_from_slice(&entry.compressed.to_le_bytes());
+        out.extend_from_slice(&entry.size.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&compressed);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Appends the central directory and returns the archive.
+    pub fn finish(mut self) -> io::Result<Vec<u8>> {
+        let too_big = || io::Error::other("the zip is too large without ZIP64");
+        let directory_offset = u32::try_from(self.out.len()).map_err(|_| too_big())?;
+        let out = &mut self.out;
+        for entry in &self.entries {
+            out.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&VERSION.to_le_bytes());
+            out.extend_from_slice(&VERSION.to_le_bytes());
+            out.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+            out.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+            out.extend_from_slice(&self.time.to_le_bytes());
+            out.extend_from_slice(&self.date.to_le_bytes());
+            out.extend_from_slice(&entry.crc.to_le_bytes());
+            out.extend_from_slice(&entry.compressed.to_le_bytes());
+            out.extend_from_slice(&entry.size.to_le_bytes());
+            out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk number, internal and external
+            // attributes.
+            out.extend_from_slice(&[0u8; 12]);
+            out.extend_from_slice(&entry.offset.to_le_bytes());
+            out.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_len = u32::try_from(out.len()).map_err(|_| too_big())? - directory_offset;
+        let count = u16::try_from(self.entries.len()).map_err(|_| too_big())?;
+        out.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&directory_len.to_le_bytes());
+        out.extend_from_slice(&directory_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        Ok(self.out)
+    }
+}
+
+/// MS-DOS date and time in UTC, clamped to the format's 1980 epoch.
+fn dos_date_time(timestamp_ms: u64) -> (u16, u16) {
+    let t = UtcDateTime::from_ms(timestamp_ms);
+    if t.year < 1980 {
+        return ((1 << 5) | 1, 0);
+    }
+    let date = (((t.year - 1980).min(127) as u16) << 9) | ((t.month as u16) << 5) | t.day as u16;
+    let time = ((t.hour as u16) << 11) | ((t.minute as u16) << 5) | (t.second as u16 / 2);
+    (date, time)
+}
+
+/// Reads an archive back through its central directory, checking each
+/// entry against its local header and its CRC, for tests.
+#[cfg(test)]
+pub(crate) fn read_entries(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+    use std::io::Read;
+    let u16_at = |at: usize| u16::from_le_bytes([zip[at], zip[at + 1]]);
+    let u32_at = |at: usize| u32::from_le_bytes(zip[at..at + 4].try_into().unwrap());
+
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(end), END_OF_CENTRAL_DIRECTORY);
+    let count = u16_at(end + 10) as usize;
+    assert_eq!(u16_at(end + 8) as usize, count);
+    let directory_len = u32_at(end + 12) as usize;
+    let mut at = u32_at(end + 16) as usize;
+    assert_eq!(at + directory_len, end);
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let central = at;
+        assert_eq!(u32_at(central), CENTRAL_HEADER);
+        let name_len = u16_at(central + 28) as usize;
+        let name = &zip[central + 46..central + 46 + name_len];
+        let crc = u32_at(central + 16);
+        let (compressed, size) = (u32_at(central + 20), u32_at(central + 24));
+        let offset = u32_at(central + 42) as usize;
+        at += 46 + name_len;
+
+        // The local header must say the same as the directory.
+        assert_eq!(u32_at(offset), LOCAL_HEADER);
+        assert_eq!(u16_at(offset + 8), METHOD_DEFLATE);
+        assert_eq!(
+            zip[offset + 10..offset + 14],
+            zip[at - 46 - name_len + 12..][..4]
+        );
+        assert_eq!(
+            (
+                u32_at(offset + 14),
+                u32_at(offset + 18),
+                u32_at(offset + 22)
+            ),
+            (crc, compressed, size)
+        );
+        assert_eq!(u16_at(offset + 26) as usize, name_len);
+        assert_eq!(&zip[offset + 30..offset + 30 + name_len], name);
+
+        let start = offset + 30 + name_len + u16_at(offset + 28) as usize;
+        let mut data = Vec::new();
+        flate2::read::DeflateDecoder::new(&zip[start..start + compressed as usize])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), size as usize);
+        assert_eq!(crc32fast::hash(&data), crc);
+        entries.push((String::from_utf8(name.to_vec()).unwrap(), data));
+    }
+    assert_eq!(at, end);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_read_back_through_the_central_directory() {
+        let big: Vec<u8> = (0..100_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect();
+        let entries = [
+            ("manifest.txt", b"hello\n".to_vec()),
+            ("logs/fps-game.log", big),
+            ("empty.json", Vec::new()),
+            ("crashes/caf\u{e9}.json", b"{}".to_vec()),
+        ];
+        let mut zip = ZipWriter::new(1_700_000_000_000);
+        for (name, data) in &entries {
+            zip.add(name, data).unwrap();
+        }
+        let read = read_entries(&zip.finish().unwrap());
+        let expected: Vec<_> = entries
+            .iter()
+            .map(|(n, d)| (n.to_string(), d.clone()))
+            .collect();
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn dos_times_are_utc_and_clamped_to_1980() {
+        // 2023-11-14 22:13:20 UTC.
+        let (date, time) = dos_date_time(1_700_000_000_000);
+        assert_eq!(date, (43 << 9) | (11 << 5) | 14);
+        assert_eq!(time, (22 << 11) | (13 << 5) | 10);
+        assert_eq!(dos_date_time(0), ((1 << 5) | 1, 0));
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// What the hardware probe found. Anything the platform wouldn't tell us
+/// is `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub cpu_model: Option<String>,
+    pub physical_cores: Option<u32>,
+    pub logical_cores: Option<u32>,
+    pub total_ram_bytes: Option<u64>,
+    /// The adapter the game most likely renders on: the boot display on
+    /// Linux, the one with the most memory on Windows.
+    pub gpu_name: Option<String>,
+    /// Dedicated video memory as the driver reports it. Often unknown for
+    /// integrated GPUs, which share system memory.
+    pub vram_bytes: Option<u64>,
+    pub os_version: Option<String>,
+}
+
+/// Fills in everything the platform exposes cheaply. Never fails; a probe
+/// that errors just leaves its fields empty.
+pub fn probe() -> SystemInfo {
+    let mut info = platform_probe();
+    if info.logical_cores.is_none() {
+        info.logical_cores = std::thread::available_parallelism()
+            .ok()
+            .map(|n| n.get() as u32);
+    }
+    info
+}
+
+#[cfg(target_os = "linux")]
+fn platform_probe() -> SystemInfo {
+    super::linux::probe()
+}
+
+#[cfg(windows)]
+fn platform_probe() -> SystemInfo {
+    super::windows::probe()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn platform_probe() -> SystemInfo {
+    SystemInfo {
+        os_version: Some(std::env::consts::OS.to_string()),
+        ..SystemInfo::default()
+    }
+}
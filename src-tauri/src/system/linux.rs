@@ -0,0 +1,143 @@
+//! Linux: everything comes from `/proc` and `/sys`, so no probe needs
+//! extra permissions or spawns a process.
+
+use super::info::SystemInfo;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const DRM_DIR: &str = "/sys/class/drm";
+
+pub fn probe() -> SystemInfo {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let (gpu_name, vram_bytes) = gpu();
+    SystemInfo {
+        cpu_model: field(&cpuinfo, "model name").map(str::to_string),
+        physical_cores: physical_cores(&cpuinfo),
+        logical_cores: Some(
+            cpuinfo
+                .lines()
+                .filter(|l| l.starts_with("processor"))
+                .count() as u32,
+        )
+        .filter(|&n| n > 0),
+        total_ram_bytes: fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|m| {
+                field(&m, "MemTotal")?
+                    .strip_suffix(" kB")?
+                    .trim()
+                    .parse()
+                    .ok()
+            })
+            .map(|kb: u64| kb * 1024),
+        gpu_name,
+        vram_bytes,
+        os_version: os_version(),
+    }
+}
+
+/// The value of the first `key: value` line.
+fn field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    text.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        (k.trim() == key).then(|| v.trim())
+    })
+}
+
+/// Distinct (package, core) pairs. Absent on some ARM kernels.
+fn physical_cores(cpuinfo: &str) -> Option<u32> {
+    let mut cores = HashSet::new();
+    let mut package = None;
+    for line in cpuinfo.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "physical id" => package = Some(value.trim()),
+            "core id" => {
+                cores.insert((package, value.trim()));
+            }
+            _ => {}
+        }
+    }
+    Some(cores.len() as u32).filter(|&n| n > 0)
+}
+
+fn os_version() -> Option<String> {
+    let release = fs::read_to_string("/etc/os-release").ok();
+    let name = release.as_deref().and_then(|r| {
+        let line = r.lines().find(|l| l.starts_with("PRETTY_NAME="))?;
+        Some(line["PRETTY_NAME=".len()..].trim_matches('"').to_string())
+    });
+    let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|k| k.trim().to_string());
+    match (name, kernel) {
+        (Some(name), Some(kernel)) => Some(format!("{} (kernel {})", name, kernel)),
+        (name, kernel) => name.or(kernel),
+    }
+}
+
+/// Name and VRAM of the boot display adapter, or of the first one.
+fn gpu() -> (Option<String>, Option<u64>) {
+    let Ok(dir) = fs::read_dir(DRM_DIR) else {
+        return (None, None);
+    };
+    let mut cards: Vec<_> = dir
+        .flatten()
+        .map(|e| e.path())
+        // `card0-HDMI-A-1` and the like are connectors, not adapters.
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("card") && !n.contains('-'))
+        })
+        .collect();
+    cards.sort();
+    let boot = cards
+        .iter()
+        .find(|c| read_trimmed(&c.join("device/boot_vga")).as_deref() == Some("1"))
+        .or(cards.first());
+    let Some(card) = boot else {
+        return (None, None);
+    };
+    let device = card.join("device");
+    let vram = read_trimmed(&device.join("mem_info_vram_total")).and_then(|v| v.parse().ok());
+    (gpu_name(&device), vram)
+}
+
+/// The NVIDIA driver names its GPUs under `/proc`; for the rest, vendor
+/// and kernel driver are as specific as `/sys` gets without a PCI id
+/// database.
+fn gpu_name(device: &Path) -> Option<String> {
+    let address = fs::canonicalize(device).ok()?;
+    let address = address.file_name()?.to_str()?;
+    let nvidia = Path::new("/proc/driver/nvidia/gpus")
+        .join(address)
+        .join("information");
+    if let Some(model) = fs::read_to_string(nvidia)
+        .ok()
+        .and_then(|i| field(&i, "Model").map(str::to_string))
+    {
+        return Some(model);
+    }
+
+    let vendor = match read_trimmed(&device.join("vendor"))?.as_str() {
+        "0x10de" => "NVIDIA",
+        "0x1002" => "AMD",
+        "0x8086" => "Intel",
+        other => return Some(format!("GPU {}", other)),
+    };
+    let driver = fs::read_link(device.join("driver"))
+        .ok()
+        .and_then(|d| d.file_name()?.to_str().map(str::to_string));
+    Some(match driver {
+        Some(driver) => format!("{} ({})", vendor, driver),
+        None => vendor.to_string(),
+    })
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
@@ -0,0 +1,44 @@
+mod info;
+#[cfg(target_os = "linux")]
+mod linux;
+mod preset;
+#[cfg(windows)]
+mod windows;
+
+pub use info::SystemInfo;
+pub use preset::PresetSuggestion;
+
+use std::sync::OnceLock;
+use tauri::{Manager, Runtime, State};
+
+/// The hardware doesn't change while the game runs, so it's probed once.
+#[derive(Default)]
+pub struct SystemState {
+    info: OnceLock<SystemInfo>,
+}
+
+impl SystemState {
+    fn info(&self) -> &SystemInfo {
+        self.info.get_or_init(info::probe)
+    }
+}
+
+/// The cached probe for backend modules.
+pub fn info<R: Runtime>(app: &tauri::AppHandle<R>) -> SystemInfo {
+    app.state::<SystemState>().info().clone()
+}
+
+/// CPU, memory, GPU and OS details. Fields the platform doesn't expose are
+/// `null`; this never fails.
+#[tauri::command(async)]
+pub fn get_system_info(system: State<'_, SystemState>) -> SystemInfo {
+    system.info().clone()
+}
+
+/// The graphics preset this machine should start on, with the reasoning
+/// per resource. Unknown hardware counts as middling, so it never suggests
+/// more than `medium` on a guess.
+#[tauri::command(async)]
+pub fn suggest_graphics_preset(system: State<'_, SystemState>) -> PresetSuggestion {
+    preset::suggest(system.info())
+}
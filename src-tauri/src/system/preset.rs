@@ -0,0 +1,110 @@
+//! Picks a starting graphics preset from the hardware. Each resource maps
+//! to the best preset it can carry and the machine gets the lowest of
+//! those, so one weak part holds the rest back.
+
+use super::info::SystemInfo;
+use crate::settings::GraphicsQuality;
+use serde::Serialize;
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Thresholds per resource, ascending: the last row whose minimum is met
+/// wins. Tune these, not the code below.
+/// RAM rows sit a little under the nominal sizes: firmware and integrated
+/// GPUs keep some back, so a 16 GB machine can report 14.
+const RAM_GB: &[(f64, GraphicsQuality)] = &[
+    (0.0, GraphicsQuality::Low),
+    (7.0, GraphicsQuality::Medium),
+    (14.0, GraphicsQuality::High),
+    (28.0, GraphicsQuality::Ultra),
+];
+const VRAM_GB: &[(f64, GraphicsQuality)] = &[
+    (0.0, GraphicsQuality::Low),
+    (3.0, GraphicsQuality::Medium),
+    (6.0, GraphicsQuality::High),
+    (10.0, GraphicsQuality::Ultra),
+];
+const CPU_CORES: &[(f64, GraphicsQuality)] = &[
+    (0.0, GraphicsQuality::Low),
+    (4.0, GraphicsQuality::Medium),
+    (6.0, GraphicsQuality::High),
+    (8.0, GraphicsQuality::Ultra),
+];
+
+/// What a resource the probe couldn't measure allows.
+const UNKNOWN: GraphicsQuality = GraphicsQuality::Medium;
+
+/// Machines reporting less than this are counted as having no dedicated
+/// VRAM: it's an integrated GPU's fixed carve-out, not its real budget.
+const MIN_DEDICATED_VRAM_GB: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetSuggestion {
+    pub preset: GraphicsQuality,
+    /// One line per resource, like `"8 GB RAM → medium"`.
+    pub reasons: Vec<String>,
+}
+
+pub fn suggest(info: &SystemInfo) -> PresetSuggestion {
+    let ram = info.total_ram_bytes.map(|b| (b as f64 / GB).round());
+    let vram = info
+        .vram_bytes
+        .map(|b| b as f64 / GB)
+        .filter(|&gb| gb >= MIN_DEDICATED_VRAM_GB);
+    let cores = info.physical_cores.or(info.logical_cores).map(f64::from);
+
+    let ratings = [
+        rate(ram, RAM_GB, |gb| format!("{} GB RAM", gb), "RAM"),
+        rate(vram, VRAM_GB, |gb| format!("{:.1} GB VRAM", gb), "VRAM"),
+        rate(
+            cores,
+            CPU_CORES,
+            |n| format!("{} CPU cores", n),
+            "CPU core count",
+        ),
+    ];
+    PresetSuggestion {
+        preset: ratings
+            .iter()
+            .map(|(preset, _)| *preset)
+            .min_by_key(|p| rank(*p))
+            .unwrap_or(UNKNOWN),
+        reasons: ratings.into_iter().map(|(_, reason)| reason).collect(),
+    }
+}
+
+fn rate(
+    value: Option<f64>,
+    table: &[(f64, GraphicsQuality)],
+    describe: impl Fn(f64) -> String,
+    name: &str,
+) -> (GraphicsQuality, String) {
+    let Some(value) = value else {
+        return (UNKNOWN, format!("{} unknown → {}", name, label(UNKNOWN)));
+    };
+    let preset = table
+        .iter()
+        .rev()
+        .find(|(min, _)| value >= *min)
+        .map_or(GraphicsQuality::Low, |(_, preset)| *preset);
+    (preset, format!("{} → {}", describe(value), label(preset)))
+}
+
+fn rank(preset: GraphicsQuality) -> u8 {
+    match preset {
+        GraphicsQuality::Low => 0,
+        GraphicsQuality::Medium => 1,
+        GraphicsQuality::High => 2,
+        GraphicsQuality::Ultra => 3,
+    }
+}
+
+fn label(preset: GraphicsQuality) -> &'static str {
+    match preset {
+        GraphicsQuality::Low => "low",
+        GraphicsQuality::Medium => "medium",
+        GraphicsQuality::High => "high",
+        GraphicsQuality::Ultra => "ultra",
+    }
+}
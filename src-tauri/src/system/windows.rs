@@ -0,0 +1,210 @@
+//! Windows: the registry for names and video memory, which is what
+//! `dxdiag` shows, and a few kernel32 calls for the rest.
+
+use super::info::SystemInfo;
+use std::ffi::c_void;
+
+const HKEY_LOCAL_MACHINE: isize = 0x8000_0002_u32 as i32 as isize;
+const RRF_RT_REG_SZ: u32 = 0x0000_0002;
+const RRF_RT_REG_BINARY: u32 = 0x0000_0008;
+const RRF_RT_REG_DWORD: u32 = 0x0000_0010;
+const RRF_RT_REG_QWORD: u32 = 0x0000_0040;
+const ERROR_SUCCESS: i32 = 0;
+const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+const RELATION_PROCESSOR_CORE: u32 = 0;
+
+const CPU_KEY: &str = r"HARDWARE\DESCRIPTION\System\CentralProcessor\0";
+/// The display adapter device class; each adapter is a numbered subkey.
+const DISPLAY_CLASS_KEY: &str =
+    r"SYSTEM\CurrentControlSet\Control\Class\{4d36e968-e325-11ce-bfc1-08002be10318}";
+const MAX_ADAPTERS: u32 = 16;
+
+#[repr(C)]
+struct MemoryStatusEx {
+    length: u32,
+    memory_load: u32,
+    total_phys: u64,
+    avail_phys: u64,
+    total_page_file: u64,
+    avail_page_file: u64,
+    total_virtual: u64,
+    avail_virtual: u64,
+    avail_extended_virtual: u64,
+}
+
+#[repr(C)]
+struct OsVersionInfoW {
+    size: u32,
+    major: u32,
+    minor: u32,
+    build: u32,
+    platform_id: u32,
+    csd_version: [u16; 128],
+}
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegGetValueW(
+        key: isize,
+        sub_key: *const u16,
+        value: *const u16,
+        flags: u32,
+        kind: *mut u32,
+        data: *mut c_void,
+        size: *mut u32,
+    ) -> i32;
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GlobalMemoryStatusEx(status: *mut MemoryStatusEx) -> i32;
+    fn GetLogicalProcessorInformationEx(relation: u32, buffer: *mut u8, length: *mut u32) -> i32;
+    fn GetLastError() -> u32;
+}
+
+#[link(name = "ntdll")]
+extern "system" {
+    fn RtlGetVersion(info: *mut OsVersionInfoW) -> i32;
+}
+
+pub fn probe() -> SystemInfo {
+    let (gpu_name, vram_bytes) = gpu();
+    SystemInfo {
+        cpu_model: reg_string(CPU_KEY, "ProcessorNameString"),
+        physical_cores: physical_cores(),
+        logical_cores: None,
+        total_ram_bytes: total_ram(),
+        gpu_name,
+        vram_bytes,
+        os_version: os_version(),
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+/// Reads a value of one of the `flags` types into a byte buffer.
+fn reg_value(key: &str, value: &str, flags: u32) -> Option<Vec<u8>> {
+    let (key, value) = (wide(key), wide(value));
+    let mut data = vec![0u8; 512];
+    let mut size = data.len() as u32;
+    // SAFETY: both names are NUL-terminated, and `size` is `data`'s length.
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key.as_ptr(),
+            value.as_ptr(),
+            flags,
+            std::ptr::null_mut(),
+            data.as_mut_ptr().cast(),
+            &mut size,
+        )
+    };
+    (status == ERROR_SUCCESS).then(|| {
+        data.truncate(size as usize);
+        data
+    })
+}
+
+fn reg_string(key: &str, value: &str) -> Option<String> {
+    let bytes = reg_value(key, value, RRF_RT_REG_SZ)?;
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// Integers of up to 8 bytes, however the driver chose to store them.
+fn reg_number(key: &str, value: &str) -> Option<u64> {
+    let bytes = reg_value(
+        key,
+        value,
+        RRF_RT_REG_QWORD | RRF_RT_REG_DWORD | RRF_RT_REG_BINARY,
+    )?;
+    let mut buf = [0u8; 8];
+    let len = bytes.len().min(8);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    Some(u64::from_le_bytes(buf)).filter(|&n| n > 0)
+}
+
+/// The adapter with the most video memory, which skips the basic display
+/// driver and favours a discrete GPU over an integrated one.
+fn gpu() -> (Option<String>, Option<u64>) {
+    (0..MAX_ADAPTERS)
+        .map(|i| format!(r"{}\{:04}", DISPLAY_CLASS_KEY, i))
+        .filter_map(|key| {
+            let name = reg_string(&key, "DriverDesc")?;
+            // The 64-bit value is only written by newer drivers; the older
+            // one wraps at 4 GB.
+            let vram = reg_number(&key, "HardwareInformation.qwMemorySize")
+                .or_else(|| reg_number(&key, "HardwareInformation.MemorySize"));
+            Some((name, vram))
+        })
+        .max_by_key(|(_, vram)| vram.unwrap_or(0))
+        .map_or((None, None), |(name, vram)| (Some(name), vram))
+}
+
+fn physical_cores() -> Option<u32> {
+    let mut length = 0u32;
+    // SAFETY: a null buffer with length 0 only asks for the size needed.
+    let ok = unsafe {
+        GetLogicalProcessorInformationEx(RELATION_PROCESSOR_CORE, std::ptr::null_mut(), &mut length)
+    };
+    // SAFETY: no preconditions.
+    if ok != 0 || unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+        return None;
+    }
+    let mut buffer = vec![0u8; length as usize];
+    // SAFETY: `buffer` is `length` bytes.
+    if unsafe {
+        GetLogicalProcessorInformationEx(RELATION_PROCESSOR_CORE, buffer.as_mut_ptr(), &mut length)
+    } == 0
+    {
+        return None;
+    }
+    // Variable-size records, each starting with a u32 relationship and a
+    // u32 size; there's one per physical core.
+    let mut cores = 0;
+    let mut offset = 0;
+    while offset + 8 <= length as usize {
+        let size = u32::from_le_bytes(buffer[offset + 4..offset + 8].try_into().unwrap());
+        if size == 0 {
+            break;
+        }
+        cores += 1;
+        offset += size as usize;
+    }
+    Some(cores).filter(|&n| n > 0)
+}
+
+fn total_ram() -> Option<u64> {
+    // SAFETY: all-zero is a valid MEMORYSTATUSEX.
+    let mut status: MemoryStatusEx = unsafe { std::mem::zeroed() };
+    status.length = std::mem::size_of::<MemoryStatusEx>() as u32;
+    // SAFETY: `status` is writable and its length is set.
+    (unsafe { GlobalMemoryStatusEx(&mut status) } != 0).then_some(status.total_phys)
+}
+
+/// `GetVersionEx` lies to unmanifested programs, so this asks ntdll.
+fn os_version() -> Option<String> {
+    // SAFETY: all-zero is a valid OSVERSIONINFOW.
+    let mut info: OsVersionInfoW = unsafe { std::mem::zeroed() };
+    info.size = std::mem::size_of::<OsVersionInfoW>() as u32;
+    // SAFETY: `info` is writable and its size is set.
+    if unsafe { RtlGetVersion(&mut info) } != 0 {
+        return None;
+    }
+    // Windows 11 still reports itself as 10.0; only the build tells.
+    let name = if info.major == 10 && info.build >= 22000 {
+        "Windows 11"
+    } else {
+        "Windows"
+    };
+    Some(format!(
+        "{} {}.{}.{}",
+        name, info.major, info.minor, info.build
+    ))
+}
@@ -0,0 +1,87 @@
+//! Wall-clock timestamps as stored in files and records: milliseconds since
+//! the Unix epoch, and their UTC calendar date for names and log lines.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn now_ms() -> u64 {
+    to_ms(SystemTime::now())
+}
+
+/// Milliseconds since the epoch, or 0 for a time before it.
+pub fn to_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A UTC date and time of day, to the millisecond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millis: u32,
+}
+
+impl UtcDateTime {
+    pub fn from_ms(ms: u64) -> Self {
+        let secs = ms / 1000;
+        // Days since 1970 to a civil date (Howard Hinnant's algorithm).
+        let z = (secs / 86_400) as i64 + 719_468;
+        let era = z / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        Self {
+            year: yoe + era * 400 + i64::from(month <= 2),
+            month: month as u32,
+            day: day as u32,
+            hour: ((secs / 3600) % 24) as u32,
+            minute: ((secs / 60) % 60) as u32,
+            second: (secs % 60) as u32,
+            millis: (ms % 1000) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(ms: u64) -> (i64, u32, u32, u32, u32, u32, u32) {
+        let t = UtcDateTime::from_ms(ms);
+        (t.year, t.month, t.day, t.hour, t.minute, t.second, t.millis)
+    }
+
+    #[test]
+    fn converts_to_civil_dates() {
+        assert_eq!(date(0), (1970, 1, 1, 0, 0, 0, 0));
+        assert_eq!(date(1_772_388_273_512), (2026, 3, 1, 18, 4, 33, 512));
+        assert_eq!(date(315_532_799_999), (1979, 12, 31, 23, 59, 59, 999));
+    }
+
+    #[test]
+    fn handles_leap_years() {
+        assert_eq!(date(951_782_400_000), (2000, 2, 29, 0, 0, 0, 0));
+        assert_eq!(date(1_709_251_199_999), (2024, 2, 29, 23, 59, 59, 999));
+        // 2100 is not a leap year.
+        assert_eq!(date(4_107_542_399_999), (2100, 2, 28, 23, 59, 59, 999));
+        assert_eq!(date(4_107_542_400_000), (2100, 3, 1, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn times_before_the_epoch_are_zero() {
+        let before = UNIX_EPOCH - std::time::Duration::from_secs(1);
+        assert_eq!(to_ms(before), 0);
+        assert_eq!(
+            to_ms(UNIX_EPOCH + std::time::Duration::from_millis(1500)),
+            1500
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Streaming an update to `<cache>/updates/<version>/`. The download goes
+//! to a `.part` file that survives interruptions and is resumed with a
+//! `Range` request when the server supports it, and only becomes the real
+//! file once its size and sha256 match the manifest.
+
+use super::manifest::{require_https, AvailableUpdate};
+use super::UpdateError;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the connection may go quiet before the download counts as
+/// interrupted.
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub bytes: u64,
+    pub total: u64,
+    /// Averaged over this attempt, so a resumed download isn't credited
+    /// with the bytes it already had.
+    pub bytes_per_sec: u64,
+}
+
+/// The last path segment of the download URL, or `fps-game-<version>`.
+fn file_name(update: &AvailableUpdate) -> String {
+    let from_url = reqwest::Url::parse(&update.build.url).ok().and_then(|url| {
+        url.path_segments()?
+            .next_back()
+            .filter(|s| {
+                !s.is_empty()
+                    && !s.starts_with('.')
+                    && s.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+            })
+            .map(str::to_string)
+    });
+    from_url.unwrap_or_else(|| format!("fps-game-{}", update.version))
+}
+
+pub fn target_path(dir: &Path, update: &AvailableUpdate) -> PathBuf {
+    dir.join(&update.version).join(file_name(update))
+}
+
+fn part_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+/// Downloads `update` unless it's already there, and returns its path.
+pub async fn download(
+    update: &AvailableUpdate,
+    dir: &Path,
+    mut progress: impl FnMut(DownloadProgress),
+) -> Result<PathBuf, UpdateError> {
+    let target = target_path(dir, update);
+    if target.is_file() {
+        if verify(&target, update).await.is_ok() {
+            return Ok(target);
+        }
+        let _ = std::fs::remove_file(&target);
+    }
+    let part = part_path(&target);
+    if let Some(parent) = part.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| UpdateError::io(parent, e))?;
+    }
+
+    let have = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    if have > update.build.size {
+        let _ = std::fs::remove_file(&part);
+    }
+    if have != update.build.size {
+        fetch(update, &part, &mut progress).await?;
+    }
+
+    if let Err(e) = verify(&part, update).await {
+        if matches!(
+            e,
+            UpdateError::SizeMismatch { .. } | UpdateError::ChecksumMismatch { .. }
+        ) {
+            let _ = std::fs::remove_file(&part);
+        }
+        return Err(e);
+    }
+    std::fs::rename(&part, &target).map_err(|e| UpdateError::io(&target, e))?;
+    Ok(target)
+}
+
+/// Fills `part` up to the update's size, continuing from what it holds.
+async fn fetch(
+    update: &AvailableUpdate,
+    part: &Path,
+    progress: &mut impl FnMut(DownloadProgress),
+) -> Result<(), UpdateError> {
+    let url = require_https(&update.build.url)?;
+    let total = update.build.size;
+    let http = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(STALL_TIMEOUT)
+        .build()
+        .map_err(UpdateError::network)?;
+
+    let mut offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+    let mut request = http.get(url.clone());
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await.map_err(UpdateError::network)?;
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // What we have doesn't line up with the server's file; start over.
+        offset = 0;
+        response = http.get(url).send().await.map_err(UpdateError::network)?;
+    }
+    let mut response = response.error_for_status().map_err(UpdateError::network)?;
+    let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+        && resumes_at(&response) == Some(offset);
+    if !resumed {
+        offset = 0;
+    }
+
+    let mut file = open_part(part, resumed)?;
+    let mut bytes = offset;
+    let started = Instant::now();
+    let mut reported = Instant::now();
+    while let Some(chunk) = response.chunk().await.map_err(UpdateError::network)? {
+        bytes += chunk.len() as u64;
+        if bytes > total {
+            drop(file);
+            let _ = std::fs::remove_file(part);
+            return Err(UpdateError::SizeMismatch {
+                expected: total,
+                actual: bytes,
+            });
+        }
+        file.write_all(&chunk)
+            .map_err(|e| UpdateError::io(part, e))?;
+        if reported.elapsed() >= PROGRESS_INTERVAL {
+            progress(snapshot(bytes, total, bytes - offset, started));
+            reported = Instant::now();
+        }
+    }
+    file.sync_all().map_err(|e| UpdateError::io(part, e))?;
+    progress(snapshot(bytes, total, bytes - offset, started));
+    Ok(())
+}
+
+fn open_part(part: &Path, append: bool) -> Result<File, UpdateError> {
+    let mut options = OpenOptions::new();
+    if append {
+        options.append(true);
+    } else {
+        options.write(true).create(true).truncate(true);
+    }
+    options.open(part).map_err(|e| UpdateError::io(part, e))
+}
+
+/// The first byte of a `206`'s `Content-Range: bytes <start>-<end>/<size>`.
+fn resumes_at(response: &reqwest::Response) -> Option<u64> {
+    let range = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    range
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn snapshot(bytes: u64, total: u64, this_attempt: u64, started: Instant) -> DownloadProgress {
+    let secs = started.elapsed().as_secs_f64();
+    DownloadProgress {
+        bytes,
+        total,
+        bytes_per_sec: if secs > 0.0 {
+            (this_attempt as f64 / secs) as u64
+        } else {
+            0
+        },
+    }
+}
+
+async fn verify(path: &Path, update: &AvailableUpdate) -> Result<(), UpdateError> {
+    let task_path = path.to_path_buf();
+    let (sha256, size) = tauri::async_runtime::spawn_blocking(move || {
+        crate::assets::hash_file(&task_path).map_err(|e| UpdateError::io(&task_path, e))
+    })
+    .await
+    .map_err(|e| UpdateError::task(path, e))??;
+    if size != update.build.size {
+        return Err(UpdateError::SizeMismatch {
+            expected: update.build.size,
+            actual: size,
+        });
+    }
+    if sha256 != update.build.sha256 {
+        return Err(UpdateError::ChecksumMismatch {
+            expected: update.build.sha256.clone(),
+            actual: sha256,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Errors surfaced by the update commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateError {
+    #[error("Invalid update channel {0:?}")]
+    InvalidChannel(String),
+
+    #[error("Refusing to fetch {0:?} over plain HTTP")]
+    InsecureUrl(String),
+
+    #[error("The update server didn't answer in time; check the connection")]
+    Timeout,
+
+    #[error("Update request failed: {reason}")]
+    Network { status: Option<u16>, reason: String },
+
+    #[error("Invalid update manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("No build of version {version} for {platform}")]
+    UnsupportedPlatform { version: String, platform: String },
+
+    #[error("No update to download; check for updates first")]
+    NoUpdate,
+
+    #[error("An update is already downloading")]
+    Busy,
+
+    #[error("Could not locate the cache directory: {0}")]
+    NoCacheDir(String),
+
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("The download was {actual} bytes, expected {expected}; it was deleted")]
+    SizeMismatch { expected: u64, actual: u64 },
+
+    #[error("The download's checksum was {actual}, expected {expected}; it was deleted")]
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl UpdateError {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        UpdateError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn task(path: impl Into<PathBuf>, error: impl std::fmt::Display) -> Self {
+        UpdateError::io(path, std::io::Error::other(error.to_string()))
+    }
+
+    pub(crate) fn network(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return UpdateError::Timeout;
+        }
+        UpdateError::Network {
+            status: e.status().map(|s| s.as_u16()),
+            reason: e.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UpdateError::InvalidChannel(_) => "invalidChannel",
+            UpdateError::InsecureUrl(_) => "insecureUrl",
+            UpdateError::Timeout => "timeout",
+            UpdateError::Network { .. } => "network",
+            UpdateError::InvalidManifest(_) => "invalidManifest",
+            UpdateError::UnsupportedPlatform { .. } => "unsupportedPlatform",
+            UpdateError::NoUpdate => "noUpdate",
+            UpdateError::Busy => "busy",
+            UpdateError::NoCacheDir(_) => "noCacheDir",
+            UpdateError::Io { .. } => "io",
+            UpdateError::SizeMismatch { .. } => "sizeMismatch",
+            UpdateError::ChecksumMismatch { .. } => "checksumMismatch",
+        }
+    }
+}
+
+impl Serialize for UpdateError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("UpdateError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
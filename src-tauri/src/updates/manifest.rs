@@ -0,0 +1,134 @@
+//! The per-channel manifest the update server publishes:
+//!
+//! ```json
+//! {
+//!   "version": "1.4.0",
+//!   "notes": "Fixes and a new map",
+//!   "platforms": {
+//!     "windows-x86_64": { "url": "https://…/fps-game-1.4.0-setup.exe", "sha256": "…", "size": 81234567 }
+//!   }
+//! }
+//! ```
+
+use super::UpdateError;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Keeps an offline check from hanging the settings screen.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_MANIFEST_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub platforms: BTreeMap<String, PlatformBuild>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformBuild {
+    pub url: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// A newer version with a build for this platform, as found by the last
+/// check.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableUpdate {
+    pub version: String,
+    pub notes: Option<String>,
+    #[serde(flatten)]
+    pub build: PlatformBuild,
+}
+
+/// `windows-x86_64`, `linux-aarch64`...
+pub fn platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+pub fn require_https(url: &str) -> Result<reqwest::Url, UpdateError> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == "https" => Ok(parsed),
+        Ok(_) => Err(UpdateError::InsecureUrl(url.to_string())),
+        Err(e) => Err(UpdateError::InvalidManifest(format!(
+            "bad URL {:?}: {}",
+            url, e
+        ))),
+    }
+}
+
+/// Accepts a leading `v`, as tags often have one.
+pub fn parse_version(version: &str) -> Result<Version, UpdateError> {
+    Version::parse(version.trim().trim_start_matches('v'))
+        .map_err(|e| UpdateError::InvalidManifest(format!("bad version {:?}: {}", version, e)))
+}
+
+pub async fn fetch(url: reqwest::Url) -> Result<Manifest, UpdateError> {
+    let http = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(CHECK_TIMEOUT)
+        .build()
+        .map_err(UpdateError::network)?;
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(UpdateError::network)?;
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_MANIFEST_BYTES as u64)
+    {
+        return Err(UpdateError::InvalidManifest("too large".to_string()));
+    }
+    let body = response.bytes().await.map_err(UpdateError::network)?;
+    if body.len() > MAX_MANIFEST_BYTES {
+        return Err(UpdateError::InvalidManifest("too large".to_string()));
+    }
+    serde_json::from_slice(&body).map_err(|e| UpdateError::InvalidManifest(e.to_string()))
+}
+
+/// The update `manifest` offers over `current`, if it's newer. Pre-releases
+/// order below their release, as semver says, so `1.4.0-beta.2` is offered
+/// to `1.3.9` but not to `1.4.0`.
+pub fn newer(
+    manifest: Manifest,
+    current: &Version,
+) -> Result<Option<AvailableUpdate>, UpdateError> {
+    let latest = parse_version(&manifest.version)?;
+    if latest <= *current {
+        return Ok(None);
+    }
+    let platform = platform();
+    let build = manifest.platforms.get(&platform).cloned().ok_or_else(|| {
+        UpdateError::UnsupportedPlatform {
+            version: latest.to_string(),
+            platform,
+        }
+    })?;
+    require_https(&build.url)?;
+    let valid_hash =
+        build.sha256.len() == 64 && build.sha256.chars().all(|c| c.is_ascii_hexdigit());
+    if !valid_hash {
+        return Err(UpdateError::InvalidManifest(format!(
+            "bad sha256 {:?}",
+            build.sha256
+        )));
+    }
+    Ok(Some(AvailableUpdate {
+        version: latest.to_string(),
+        notes: manifest.notes,
+        build: PlatformBuild {
+            sha256: build.sha256.to_ascii_lowercase(),
+            ..build
+        },
+    }))
+}
@@ -0,0 +1,169 @@
+mod download;
+mod error;
+mod manifest;
+
+pub use error::UpdateError;
+pub use manifest::AvailableUpdate;
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+pub const PROGRESS_EVENT: &str = "update-download-progress";
+const DEFAULT_CHANNEL: &str = "stable";
+/// Where `<channel>.json` manifests live. Release builds set
+/// `FPS_GAME_UPDATE_URL` to the real server.
+const MANIFEST_BASE_URL: &str = match option_env!("FPS_GAME_UPDATE_URL") {
+    Some(url) => url,
+    None => "https://updates.example.com/fps-game",
+};
+const DIR_NAME: &str = "updates";
+
+#[derive(Default)]
+pub struct UpdateState {
+    /// What the last check found, for `download_update`.
+    available: Mutex<Option<AvailableUpdate>>,
+    downloaded: Mutex<Option<DownloadedUpdate>>,
+    downloading: AtomicBool,
+}
+
+/// Clears the downloading flag however the download ends.
+struct DownloadGuard<'a>(&'a AtomicBool);
+
+impl Drop for DownloadGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheck {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    /// The build to download, when there is one.
+    pub update: Option<AvailableUpdate>,
+}
+
+/// A verified download, ready to install.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadedUpdate {
+    pub version: String,
+    pub notes: Option<String>,
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+/// Fetches the manifest for `channel` (`stable` by default) and compares
+/// its version with this build's. Gives up after ten seconds without an
+/// answer rather than waiting on a dead connection.
+#[tauri::command]
+pub async fn check_for_updates(
+    app: tauri::AppHandle,
+    updates: State<'_, UpdateState>,
+    channel: Option<String>,
+) -> Result<UpdateCheck, UpdateError> {
+    let channel = channel.unwrap_or_else(|| DEFAULT_CHANNEL.to_string());
+    let valid = !channel.is_empty()
+        && channel.len() <= 32
+        && channel
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(UpdateError::InvalidChannel(channel));
+    }
+    let url = manifest::require_https(&format!(
+        "{}/{}.json",
+        MANIFEST_BASE_URL.trim_end_matches('/'),
+        channel
+    ))?;
+
+    let fetched = manifest::fetch(url).await?;
+    let latest_version = manifest::parse_version(&fetched.version)?.to_string();
+    let current = &app.package_info().version;
+    let update = manifest::newer(fetched, current)?;
+    *updates.available.lock().unwrap() = update.clone();
+    log::info!(
+        "Update check on {}: running {}, latest {}",
+        channel,
+        current,
+        latest_version
+    );
+    Ok(UpdateCheck {
+        current_version: current.to_string(),
+        latest_version,
+        update_available: update.is_some(),
+        update,
+    })
+}
+
+/// Downloads the update the last check found, emitting
+/// `update-download-progress`, and returns it once its size and sha256
+/// match the manifest. A mismatched download is deleted. An interrupted
+/// one is kept and resumed by the next call, if the server takes ranges.
+#[tauri::command]
+pub async fn download_update(
+    app: tauri::AppHandle,
+    updates: State<'_, UpdateState>,
+) -> Result<DownloadedUpdate, UpdateError> {
+    let update = updates
+        .available
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or(UpdateError::NoUpdate)?;
+    if updates.downloading.swap(true, Ordering::AcqRel) {
+        return Err(UpdateError::Busy);
+    }
+    let _guard = DownloadGuard(&updates.downloading);
+
+    let dir = updates_dir(&app)?;
+    let result = download::download(&update, &dir, |progress| {
+        let _ = app.emit(PROGRESS_EVENT, progress);
+    })
+    .await;
+    let path = match result {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Update {} download failed: {}", update.version, e);
+            return Err(e);
+        }
+    };
+
+    let downloaded = DownloadedUpdate {
+        version: update.version,
+        notes: update.notes,
+        path: path.to_string_lossy().into_owned(),
+        size_bytes: update.build.size,
+        sha256: update.build.sha256,
+    };
+    log::info!("Update {} downloaded to {:?}", downloaded.version, path);
+    *updates.downloaded.lock().unwrap() = Some(downloaded.clone());
+    Ok(downloaded)
+}
+
+/// The update downloaded this session, if its file is still there, so the
+/// UI can offer to install it on exit.
+#[tauri::command]
+pub fn get_downloaded_update(updates: State<'_, UpdateState>) -> Option<DownloadedUpdate> {
+    let mut downloaded = updates.downloaded.lock().unwrap();
+    if downloaded
+        .as_ref()
+        .is_some_and(|d| !PathBuf::from(&d.path).is_file())
+    {
+        *downloaded = None;
+    }
+    downloaded.clone()
+}
+
+fn updates_dir(app: &tauri::AppHandle) -> Result<PathBuf, UpdateError> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| UpdateError::NoCacheDir(e.to_string()))
+}
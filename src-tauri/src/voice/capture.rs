@@ -0,0 +1,396 @@
+//! The capture thread. cpal's streams aren't `Send` on every platform, so
+//! the device is opened, run and closed on the thread that encodes what it
+//! delivers. The audio callback only copies samples onto a queue: it never
+//! blocks and never panics, whatever happens to the device.
+
+use super::dsp::{self, Framer, Meter, Resampler};
+use super::VoiceError;
+use crate::net::{NetError, UdpSender};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, StreamError};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TryRecvError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const FRAME_EVENT: &str = "voice-frame";
+pub const LEVEL_EVENT: &str = "voice-level";
+pub const DEVICE_LOST_EVENT: &str = "voice-device-lost";
+
+const LEVEL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the thread wakes without input to notice a stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// A device that delivers nothing for this long counts as lost; some
+/// backends just go quiet when one is unplugged.
+const SILENCE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Callback buffers waiting for the thread. Past this the newest are
+/// dropped, a glitch being better than a blocked audio callback.
+const MAX_QUEUED_BUFFERS: usize = 64;
+/// Far more than a 20 ms frame at the highest bitrate needs, and small
+/// enough to go out in one datagram.
+const MAX_PACKET_LEN: usize = 1000;
+
+/// One encoded frame, as a `voice-frame` payload. Each datagram sent to a
+/// socket instead is the sequence as a big-endian `u32`, then the packet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceFrame {
+    /// Counts up by one per frame sent, so receivers can spot gaps.
+    pub sequence: u32,
+    pub packet: Vec<u8>,
+}
+
+/// The `voice-level` payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceLevel {
+    /// RMS of the input over the last interval, 0 to 1.
+    pub rms: f32,
+    pub transmitting: bool,
+}
+
+/// The `voice-device-lost` payload. Capture has stopped when it's sent.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceLost {
+    pub device: String,
+    pub reason: String,
+}
+
+pub enum Output {
+    Events,
+    Socket(UdpSender),
+    /// The socket was closed under us. Frames are dropped; the meter keeps
+    /// running.
+    Closed,
+}
+
+enum Input {
+    Samples(Vec<f32>),
+    Lost(String),
+}
+
+pub struct Capture {
+    pub device: String,
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Capture {
+    /// Opens `device` (the default without one) and starts encoding. The
+    /// device is opened before this returns, so a bad one fails here rather
+    /// than in a later event.
+    pub fn spawn<R: Runtime>(
+        app: AppHandle<R>,
+        transmitting: Arc<AtomicBool>,
+        device: Option<String>,
+        encoder: opus::Encoder,
+        output: Output,
+    ) -> Result<Self, VoiceError> {
+        let (stop, stopped) = mpsc::channel();
+        let (ready, opened) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("voice-capture".into())
+            .spawn(move || {
+                let (queue, input) = mpsc::sync_channel(MAX_QUEUED_BUFFERS);
+                let (stream, name, rate, channels) = match open(device.as_deref(), queue) {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready.send(Ok(name.clone()));
+                let mut pipeline = Pipeline {
+                    channels,
+                    resampler: Resampler::new(rate),
+                    framer: Framer::default(),
+                    meter: Meter::default(),
+                    encoder,
+                    output,
+                    sequence: 0,
+                    mono: Vec::new(),
+                    resampled: Vec::new(),
+                };
+                if let Some(reason) = pipeline.run(&app, &transmitting, &input, &stopped) {
+                    log::warn!("Lost input device {:?}: {}", name, reason);
+                    let _ = app.emit(
+                        DEVICE_LOST_EVENT,
+                        DeviceLost {
+                            device: name,
+                            reason,
+                        },
+                    );
+                }
+                drop(stream);
+            })
+            .map_err(VoiceError::Spawn)?;
+
+        match opened.recv() {
+            Ok(Ok(device)) => Ok(Self {
+                device,
+                stop,
+                thread,
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err(VoiceError::Device("the capture thread exited".to_string()))
+            }
+        }
+    }
+
+    /// Whether capture ended by itself, the device having been lost.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Stops capture and closes the device, which takes at most one poll.
+    pub fn stop(self) {
+        drop(self.stop);
+        let _ = self.thread.join();
+    }
+}
+
+/// Opens the device and starts its stream, returning the stream with the
+/// device's name, sample rate and channel count.
+fn open(
+    name: Option<&str>,
+    queue: SyncSender<Input>,
+) -> Result<(cpal::Stream, String, u32, usize), VoiceError> {
+    let host = cpal::default_host();
+    let device = match name {
+        None => host
+            .default_input_device()
+            .ok_or(VoiceError::NoInputDevice)?,
+        Some(name) => host
+            .input_devices()
+            .map_err(VoiceError::device)?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| VoiceError::UnknownDevice(name.to_string()))?,
+    };
+    let name = device.name().map_err(VoiceError::device)?;
+    let supported = device.default_input_config().map_err(VoiceError::device)?;
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build::<f32>(&device, &config, queue),
+        SampleFormat::I16 => build::<i16>(&device, &config, queue),
+        SampleFormat::U16 => build::<u16>(&device, &config, queue),
+        SampleFormat::I32 => build::<i32>(&device, &config, queue),
+        SampleFormat::U8 => build::<u8>(&device, &config, queue),
+        other => return Err(VoiceError::Device(format!("{} samples", other))),
+    }
+    .map_err(VoiceError::device)?;
+    stream.play().map_err(VoiceError::device)?;
+    log::info!(
+        "Capturing voice from {:?} at {} Hz, {} channels",
+        name,
+        config.sample_rate.0,
+        config.channels
+    );
+    Ok((
+        stream,
+        name,
+        config.sample_rate.0,
+        config.channels.max(1) as usize,
+    ))
+}
+
+fn build<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: SyncSender<Input>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let errors = queue.clone();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            let samples = data.iter().map(|s| s.to_sample::<f32>()).collect();
+            let _ = queue.try_send(Input::Samples(samples));
+        },
+        move |e| match e {
+            StreamError::DeviceNotAvailable => {
+                let _ = errors.try_send(Input::Lost(e.to_string()));
+            }
+            // Overruns and the like; the stream carries on.
+            StreamError::BackendSpecific { err } => log::warn!("Voice input: {}", err),
+        },
+        None,
+    )
+}
+
+struct Pipeline {
+    channels: usize,
+    resampler: Resampler,
+    framer: Framer,
+    meter: Meter,
+    encoder: opus::Encoder,
+    output: Output,
+    sequence: u32,
+    mono: Vec<f32>,
+    resampled: Vec<f32>,
+}
+
+impl Pipeline {
+    /// Runs until stopped, or until the device goes, returning why.
+    fn run<R: Runtime>(
+        &mut self,
+        app: &AppHandle<R>,
+        transmitting: &AtomicBool,
+        input: &mpsc::Receiver<Input>,
+        stopped: &mpsc::Receiver<()>,
+    ) -> Option<String> {
+        let mut last_input = Instant::now();
+        let mut next_level = Instant::now() + LEVEL_INTERVAL;
+        loop {
+            let gate = transmitting.load(Ordering::Relaxed);
+            match input.recv_timeout(POLL_INTERVAL) {
+                Ok(Input::Samples(samples)) => {
+                    last_input = Instant::now();
+                    self.process(app, &samples, gate);
+                }
+                Ok(Input::Lost(reason)) => return Some(reason),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Some("the input stream closed".to_string())
+                }
+            }
+            if !matches!(stopped.try_recv(), Err(TryRecvError::Empty)) {
+                return None;
+            }
+            if last_input.elapsed() >= SILENCE_TIMEOUT {
+                return Some(format!("no audio for {} s", SILENCE_TIMEOUT.as_secs_f32()));
+            }
+            if Instant::now() >= next_level {
+                let level = VoiceLevel {
+                    rms: self.meter.take(),
+                    transmitting: gate,
+                };
+                let _ = app.emit(LEVEL_EVENT, level);
+                next_level = Instant::now() + LEVEL_INTERVAL;
+            }
+        }
+    }
+
+    fn process<R: Runtime>(&mut self, app: &AppHandle<R>, samples: &[f32], gate: bool) {
+        self.mono.clear();
+        dsp::downmix(samples, self.channels, &mut self.mono);
+        self.resampled.clear();
+        self.resampler.process(&self.mono, &mut self.resampled);
+        // The meter runs with the gate closed too, for the settings page.
+        self.meter.push(&self.resampled);
+        if !gate {
+            self.framer.clear();
+            return;
+        }
+        self.framer.push(&self.resampled);
+        while let Some(frame) = self.framer.next_frame() {
+            match self.encoder.encode_vec_float(&frame, MAX_PACKET_LEN) {
+                Ok(packet) => self.send(app, packet),
+                Err(e) => log::warn!("Could not encode a voice frame: {}", e),
+            }
+        }
+    }
+
+    fn send<R: Runtime>(&mut self, app: &AppHandle<R>, packet: Vec<u8>) {
+        let sequence = self.sequence;
+        self.sequence = self.sequence.wrapping_add(1);
+        match &self.output {
+            Output::Events => {
+                let _ = app.emit(FRAME_EVENT, VoiceFrame { sequence, packet });
+            }
+            Output::Socket(socket) => {
+                let mut datagram = sequence.to_be_bytes().to_vec();
+                datagram.extend_from_slice(&packet);
+                match socket.send(&datagram) {
+                    Ok(()) => {}
+                    Err(NetError::UnknownSocket(handle)) => {
+                        log::warn!("Voice socket {} was closed; no longer sending", handle);
+                        self.output = Output::Closed;
+                    }
+                    Err(e) => log::debug!("Voice frame {} not sent: {}", sequence, e),
+                }
+            }
+            Output::Closed => {}
+        }
+    }
+}
+
+/// An input device as `list_input_devices` reports it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputDevice {
+    pub name: String,
+    pub is_default: bool,
+    /// The device's preferred format, when it will say.
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+}
+
+pub fn input_devices() -> Result<Vec<InputDevice>, VoiceError> {
+    let host = cpal::default_host();
+    let default = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host.input_devices().map_err(VoiceError::device)?;
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let config = device.default_input_config().ok();
+            Some(InputDevice {
+                is_default: default.as_deref() == Some(name.as_str()),
+                sample_rate: config.as_ref().map(|c| c.sample_rate().0),
+                channels: config.as_ref().map(|c| c.channels()),
+                name,
+            })
+        })
+        .collect())
+}
+
+/// A 48 kHz mono encoder tuned for speech.
+pub fn encoder(bitrate: u32) -> Result<opus::Encoder, VoiceError> {
+    let err = |e: opus::Error| VoiceError::Encoder(e.to_string());
+    let mut encoder = opus::Encoder::new(
+        dsp::SAMPLE_RATE,
+        opus::Channels::Mono,
+        opus::Application::Voip,
+    )
+    .map_err(err)?;
+    encoder
+        .set_bitrate(opus::Bitrate::Bits(bitrate as i32))
+        .map_err(err)?;
+    Ok(encoder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_encode_to_small_packets_that_decode_to_20_ms() {
+        let mut encoder = encoder(24_000).unwrap();
+        let mut decoder = opus::Decoder::new(dsp::SAMPLE_RATE, opus::Channels::Mono).unwrap();
+        let tone: Vec<f32> = (0..dsp::FRAME_LEN * 10)
+            .map(|i| 0.5 * (i as f32 * 440.0 * std::f32::consts::TAU / 48_000.0).sin())
+            .collect();
+        let mut decoded = vec![0.0; dsp::FRAME_LEN];
+        for frame in tone.chunks(dsp::FRAME_LEN) {
+            let packet = encoder.encode_vec_float(frame, MAX_PACKET_LEN).unwrap();
+            // 24 kbps is 60 bytes a frame, give or take.
+            assert!(packet.len() < 200, "{} bytes", packet.len());
+            let len = decoder.decode_float(&packet, &mut decoded, false).unwrap();
+            assert_eq!(len, dsp::FRAME_LEN);
+        }
+        let mut meter = Meter::default();
+        meter.push(&decoded);
+        assert!((meter.take() - 0.5 * std::f32::consts::FRAC_1_SQRT_2).abs() < 0.1);
+    }
+}
@@ -0,0 +1,192 @@
+//! Getting whatever the microphone delivers into 20 ms frames of 48 kHz
+//! mono, which is what the encoder takes.
+
+pub const SAMPLE_RATE: u32 = 48_000;
+/// 20 ms at 48 kHz.
+pub const FRAME_LEN: usize = 960;
+
+/// Averages each frame of `channels` interleaved samples into one,
+/// appending to `out`.
+pub fn downmix(input: &[f32], channels: usize, out: &mut Vec<f32>) {
+    if channels <= 1 {
+        out.extend_from_slice(input);
+        return;
+    }
+    out.extend(
+        input
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+    );
+}
+
+/// Linear interpolation to 48 kHz, like `audio::decode::resample` but
+/// carried across buffers so their joins don't click. Fine for speech;
+/// a 48 kHz device, the common case, passes straight through.
+pub struct Resampler {
+    /// Input samples per output sample.
+    step: f64,
+    /// Where the next output sample falls, with `previous` at 0 and the
+    /// next buffer starting at 1.
+    position: f64,
+    previous: f32,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32) -> Self {
+        Self {
+            step: input_rate as f64 / SAMPLE_RATE as f64,
+            position: 1.0,
+            previous: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if self.step == 1.0 {
+            out.extend_from_slice(input);
+            return;
+        }
+        let Some(&last) = input.last() else {
+            return;
+        };
+        let at = |i: usize| if i == 0 { self.previous } else { input[i - 1] };
+        let end = input.len() as f64;
+        while self.position < end {
+            let index = self.position as usize;
+            let t = (self.position - index as f64) as f32;
+            let (a, b) = (at(index), at(index + 1));
+            out.push(a + (b - a) * t);
+            self.position += self.step;
+        }
+        self.position -= end;
+        self.previous = last;
+    }
+}
+
+/// Collects samples into whole frames.
+#[derive(Default)]
+pub struct Framer {
+    pending: Vec<f32>,
+}
+
+impl Framer {
+    pub fn push(&mut self, samples: &[f32]) {
+        self.pending.extend_from_slice(samples);
+    }
+
+    pub fn next_frame(&mut self) -> Option<Vec<f32>> {
+        (self.pending.len() >= FRAME_LEN).then(|| self.pending.drain(..FRAME_LEN).collect())
+    }
+
+    /// Drops a partial frame, so speech after a push-to-talk release
+    /// doesn't start with what came before it.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// The RMS of everything pushed since it was last taken, for the level
+/// meter.
+#[derive(Default)]
+pub struct Meter {
+    sum_of_squares: f64,
+    count: u64,
+}
+
+impl Meter {
+    pub fn push(&mut self, samples: &[f32]) {
+        self.sum_of_squares += samples.iter().map(|s| (*s as f64).powi(2)).sum::<f64>();
+        self.count += samples.len() as u64;
+    }
+
+    /// 0 when nothing was pushed.
+    pub fn take(&mut self) -> f32 {
+        let rms = match self.count {
+            0 => 0.0,
+            n => (self.sum_of_squares / n as f64).sqrt() as f32,
+        };
+        *self = Self::default();
+        rms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(rate: u32, hz: f64, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f64::consts::PI * hz * i as f64 / rate as f64).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn downmixing_averages_the_channels() {
+        let mut out = Vec::new();
+        downmix(&[1.0, 0.0, 0.5, -0.5, -1.0, -1.0], 2, &mut out);
+        assert_eq!(out, [0.5, 0.0, -1.0]);
+        downmix(&[0.25; 3], 1, &mut out);
+        assert_eq!(out.len(), 6);
+    }
+
+    #[test]
+    fn resampling_hits_48_khz_whatever_the_buffer_sizes() {
+        for (rate, chunk) in [(44_100, 441), (44_100, 1), (16_000, 160), (96_000, 1024)] {
+            let input = sine(rate, 440.0, rate as usize);
+            let mut resampler = Resampler::new(rate);
+            let mut out = Vec::new();
+            for buffer in input.chunks(chunk) {
+                resampler.process(buffer, &mut out);
+            }
+            assert!(
+                out.len().abs_diff(SAMPLE_RATE as usize) <= 3,
+                "{} Hz gave {} samples",
+                rate,
+                out.len()
+            );
+            // Still the same tone: compare against one made at 48 kHz,
+            // leaving out the last sample, which waits on the next buffer.
+            let expected = sine(SAMPLE_RATE, 440.0, out.len());
+            let worst = out
+                .iter()
+                .zip(&expected)
+                .take(out.len() - 1)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            assert!(worst < 0.01, "{} Hz is off by {}", rate, worst);
+        }
+    }
+
+    #[test]
+    fn resampling_at_48_khz_changes_nothing() {
+        let input = sine(SAMPLE_RATE, 1000.0, 500);
+        let mut out = Vec::new();
+        Resampler::new(SAMPLE_RATE).process(&input, &mut out);
+        assert_eq!(out, input);
+    }
+
+    #[test]
+    fn frames_are_whole_and_the_remainder_waits() {
+        let mut framer = Framer::default();
+        framer.push(&vec![0.5; FRAME_LEN * 2 + 100]);
+        assert_eq!(framer.next_frame().unwrap().len(), FRAME_LEN);
+        assert!(framer.next_frame().is_some());
+        assert!(framer.next_frame().is_none());
+        framer.push(&vec![0.5; FRAME_LEN - 100]);
+        assert!(framer.next_frame().is_some());
+
+        framer.push(&[0.5; 10]);
+        framer.clear();
+        framer.push(&vec![0.5; FRAME_LEN - 1]);
+        assert!(framer.next_frame().is_none());
+    }
+
+    #[test]
+    fn the_meter_reports_rms_per_interval() {
+        let mut meter = Meter::default();
+        assert_eq!(meter.take(), 0.0);
+        meter.push(&sine(SAMPLE_RATE, 1000.0, 4800));
+        assert!((meter.take() - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-3);
+        meter.push(&[0.5, -0.5]);
+        assert_eq!(meter.take(), 0.5);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::net::NetError;
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the voice commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum VoiceError {
+    #[error("Voice capture is already running")]
+    AlreadyRunning,
+
+    #[error("Voice capture is not running")]
+    NotRunning,
+
+    #[error("There is no input device")]
+    NoInputDevice,
+
+    #[error("No input device named {0:?}")]
+    UnknownDevice(String),
+
+    #[error("Input device error: {0}")]
+    Device(String),
+
+    #[error("Bitrate {value} is outside {min}..={max}")]
+    InvalidBitrate { value: u32, min: u32, max: u32 },
+
+    #[error("Opus encoder error: {0}")]
+    Encoder(String),
+
+    #[error(transparent)]
+    Net(#[from] NetError),
+
+    #[error("Failed to start the voice capture thread: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    #[error("Background voice task failed: {0}")]
+    Task(String),
+}
+
+impl VoiceError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            VoiceError::AlreadyRunning => "alreadyRunning",
+            VoiceError::NotRunning => "notRunning",
+            VoiceError::NoInputDevice => "noInputDevice",
+            VoiceError::UnknownDevice(_) => "unknownDevice",
+            VoiceError::Device(_) => "device",
+            VoiceError::InvalidBitrate { .. } => "invalidBitrate",
+            VoiceError::Encoder(_) => "encoder",
+            VoiceError::Net(e) => e.kind(),
+            VoiceError::Spawn(_) => "spawn",
+            VoiceError::Task(_) => "task",
+        }
+    }
+
+    pub(crate) fn device(e: impl ToString) -> Self {
+        VoiceError::Device(e.to_string())
+    }
+}
+
+impl Serialize for VoiceError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("VoiceError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,132 @@
+mod capture;
+mod dsp;
+mod error;
+
+pub use capture::InputDevice;
+pub use error::VoiceError;
+
+use capture::{Capture, Output};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{Manager, Runtime, State};
+
+const DEFAULT_BITRATE: u32 = 24_000;
+const BITRATE_RANGE: std::ops::RangeInclusive<u32> = 6_000..=128_000;
+
+/// Microphone capture for voice chat, encoded to Opus natively rather than
+/// through the webview's `getUserMedia`.
+#[derive(Default)]
+pub struct VoiceState {
+    /// The push-to-talk gate, kept across captures.
+    transmitting: Arc<AtomicBool>,
+    capture: Mutex<Option<Capture>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VoiceOptions {
+    /// A name from `list_input_devices`; the system default without one.
+    pub device: Option<String>,
+    /// Opus bitrate in bits per second, 6000 to 128000; 24000 by default.
+    pub bitrate: Option<u32>,
+    pub output: VoiceOutput,
+}
+
+/// Where encoded frames go.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum VoiceOutput {
+    /// `voice-frame` events.
+    #[default]
+    Events,
+    /// Unreliable datagrams from an `open_udp_socket` handle to `peer`
+    /// (`ip:port`), skipping the round trip through the webview.
+    Udp { handle: u32, peer: String },
+}
+
+/// Opens the input device and starts encoding 20 ms frames of 48 kHz mono
+/// Opus while `set_voice_transmitting` has the gate open. `voice-level`
+/// reports the input's RMS every 100 ms either way, for the mic meter. If
+/// the device goes away, capture stops with a `voice-device-lost` event.
+/// Returns the device's name.
+#[tauri::command]
+pub async fn start_voice_capture(
+    app: tauri::AppHandle,
+    options: Option<VoiceOptions>,
+) -> Result<String, VoiceError> {
+    let options = options.unwrap_or_default();
+    let bitrate = options.bitrate.unwrap_or(DEFAULT_BITRATE);
+    if !BITRATE_RANGE.contains(&bitrate) {
+        return Err(VoiceError::InvalidBitrate {
+            value: bitrate,
+            min: *BITRATE_RANGE.start(),
+            max: *BITRATE_RANGE.end(),
+        });
+    }
+    let output = match &options.output {
+        VoiceOutput::Events => Output::Events,
+        VoiceOutput::Udp { handle, peer } => {
+            Output::Socket(crate::net::udp_sender(&app, *handle, peer)?)
+        }
+    };
+    let encoder = capture::encoder(bitrate)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let voice = app.state::<VoiceState>();
+        let mut capture = voice.capture.lock().unwrap();
+        // One that lost its device has already stopped, and is replaced.
+        match capture.take() {
+            Some(finished) if finished.is_finished() => finished.stop(),
+            Some(running) => {
+                *capture = Some(running);
+                return Err(VoiceError::AlreadyRunning);
+            }
+            None => {}
+        }
+        let started = Capture::spawn(
+            app.clone(),
+            voice.transmitting.clone(),
+            options.device,
+            encoder,
+            output,
+        )?;
+        let device = started.device.clone();
+        *capture = Some(started);
+        Ok(device)
+    })
+    .await
+    .map_err(|e| VoiceError::Task(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn stop_voice_capture(app: tauri::AppHandle) -> Result<(), VoiceError> {
+    let capture = app.state::<VoiceState>().capture.lock().unwrap().take();
+    let capture = capture.ok_or(VoiceError::NotRunning)?;
+    // Joining waits out at most one poll.
+    let _ = tauri::async_runtime::spawn_blocking(move || capture.stop()).await;
+    Ok(())
+}
+
+/// The input devices, for picking one in the settings.
+#[tauri::command]
+pub async fn list_input_devices() -> Result<Vec<InputDevice>, VoiceError> {
+    tauri::async_runtime::spawn_blocking(capture::input_devices)
+        .await
+        .map_err(|e| VoiceError::Task(e.to_string()))?
+}
+
+/// Opens or closes the push-to-talk gate. Frames are only encoded and sent
+/// while it's open; it starts closed.
+#[tauri::command]
+pub fn set_voice_transmitting(voice: State<'_, VoiceState>, transmitting: bool) {
+    voice.transmitting.store(transmitting, Ordering::Relaxed);
+}
+
+/// Stops capture. Called on exit.
+pub fn shutdown<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let capture = app.state::<VoiceState>().capture.lock().unwrap().take();
+    if let Some(capture) = capture {
+        capture.stop();
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Serialize, Serializer};
+
+/// Errors surfaced by the weapon commands, serialized as `{ kind, message }`.
+#[derive(Debug, thiserror::Error)]
+pub enum WeaponError {
+    #[error("Invalid weapon id {0:?}")]
+    InvalidId(String),
+
+    #[error("No weapon named {0:?}")]
+    NotFound(String),
+
+    #[error("Weapon {id:?} failed validation: {first}")]
+    Invalid { id: String, first: String },
+}
+
+impl WeaponError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WeaponError::InvalidId(_) => "invalidId",
+            WeaponError::NotFound(_) => "notFound",
+            WeaponError::Invalid { .. } => "invalid",
+        }
+    }
+}
+
+impl Serialize for WeaponError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("WeaponError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,50 @@
+//! `inherits = "<id>"`: a file only lists what differs from its parent.
+
+use std::collections::BTreeMap;
+use toml::{Table, Value};
+
+pub const KEY: &str = "inherits";
+
+/// Lays `child` over `parent`. Tables merge key by key; anything else,
+/// arrays included, replaces the parent's value outright.
+fn overlay(parent: &mut Table, child: Table) {
+    for (key, value) in child {
+        match (parent.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(over)) => overlay(base, over),
+            (_, value) => {
+                parent.insert(key, value);
+            }
+        }
+    }
+}
+
+/// `id`'s table with its whole `inherits` chain applied, without the
+/// `inherits` key itself.
+pub fn resolve(tables: &BTreeMap<String, Table>, id: &str) -> Result<Table, String> {
+    let mut chain = vec![id];
+    let mut at = id;
+    while let Some(parent) = tables[at].get(KEY) {
+        let parent = parent
+            .as_str()
+            .ok_or_else(|| format!("{} must be a weapon id", KEY))?;
+        if chain.contains(&parent) {
+            chain.push(parent);
+            return Err(format!("has an inheritance cycle: {}", chain.join(" -> ")));
+        }
+        if !tables.contains_key(parent) {
+            return Err(format!(
+                "inherits from {:?}, which is missing or didn't parse",
+                parent
+            ));
+        }
+        chain.push(parent);
+        at = parent;
+    }
+
+    let mut merged = Table::new();
+    for id in chain.iter().rev() {
+        overlay(&mut merged, tables[*id].clone());
+    }
+    merged.remove(KEY);
+    Ok(merged)
+}
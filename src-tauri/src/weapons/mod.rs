@@ -0,0 +1,220 @@
+mod error;
+mod inherit;
+mod model;
+mod validate;
+
+pub use error::WeaponError;
+pub use model::WeaponDef;
+pub use validate::WeaponIssue;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::Runtime;
+
+#[cfg(debug_assertions)]
+pub const CHANGED_EVENT: &str = "weapon-defs-changed";
+const BUNDLED: &str = "bundled";
+const SUBDIR: &str = "weapons";
+const EXTENSION: &str = "toml";
+const MAX_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponDefs {
+    /// Every weapon that passed validation, by id.
+    pub weapons: Vec<WeaponDef>,
+    /// Why the others were left out.
+    pub errors: Vec<WeaponIssue>,
+}
+
+/// A weapon file before inheritance is applied.
+struct Raw {
+    source: String,
+    path: PathBuf,
+    table: toml::Table,
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Directories that may hold weapon files, lowest priority first: the
+/// bundled ones, then each enabled mod's `data/weapons/` in load order.
+fn weapon_dirs<R: Runtime>(app: &tauri::AppHandle<R>) -> Vec<(String, Vec<PathBuf>)> {
+    let kind = crate::assets::AssetKind::Data;
+    let bundled = crate::assets::asset_roots(app, kind)
+        .into_iter()
+        .map(|root| root.join(SUBDIR))
+        .collect();
+    let mut dirs = vec![(BUNDLED.to_string(), bundled)];
+    for (id, root) in crate::mods::enabled_roots(app) {
+        dirs.push((id, vec![root.join(kind.dir()).join(SUBDIR)]));
+    }
+    dirs
+}
+
+fn files_in(dir: &Path) -> BTreeMap<String, PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_str()?.to_string();
+            valid_id(&id).then_some((id, path))
+        })
+        .collect()
+}
+
+fn file_error(id: &str, message: String) -> WeaponIssue {
+    WeaponIssue {
+        weapon: id.to_string(),
+        path: None,
+        message,
+    }
+}
+
+/// Reads every weapon file, a mod's replacing a bundled one with the same
+/// id. Bundled roots are the same files in different install layouts, so
+/// only the first with a given id counts.
+fn read_all<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    errors: &mut Vec<WeaponIssue>,
+) -> BTreeMap<String, Raw> {
+    let mut found = BTreeMap::new();
+    for (source, roots) in weapon_dirs(app) {
+        let mut from_source = BTreeMap::new();
+        for root in roots.iter().rev() {
+            from_source.extend(files_in(root));
+        }
+        found.extend(
+            from_source
+                .into_iter()
+                .map(|(id, path)| (id, (source.clone(), path))),
+        );
+    }
+
+    let mut raw = BTreeMap::new();
+    for (id, (source, path)) in found {
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse::<toml::Table>().map_err(|e| e.to_string()));
+        match parsed {
+            Ok(table) => {
+                raw.insert(
+                    id,
+                    Raw {
+                        source,
+                        path,
+                        table,
+                    },
+                );
+            }
+            Err(e) => {
+                errors.push(file_error(&id, format!("{:?}: {}", path, e)));
+            }
+        }
+    }
+    raw
+}
+
+/// Every weapon file resolved and validated. A weapon with a problem is
+/// listed in `errors` instead of `weapons`, one bad file never failing the
+/// rest. Inheriting from a weapon that parses but fails validation is
+/// fine, since the child may override whatever was wrong.
+fn load_all<R: Runtime>(app: &tauri::AppHandle<R>) -> WeaponDefs {
+    let mut defs = WeaponDefs::default();
+    let raw = read_all(app, &mut defs.errors);
+    let tables: BTreeMap<String, toml::Table> = raw
+        .iter()
+        .map(|(id, r)| (id.clone(), r.table.clone()))
+        .collect();
+
+    for (id, r) in &raw {
+        let def = inherit::resolve(&tables, id).and_then(|merged| {
+            toml::Value::Table(merged)
+                .try_into::<WeaponDef>()
+                .map_err(|e| e.to_string())
+        });
+        let mut def = match def {
+            Ok(def) => def,
+            Err(e) => {
+                defs.errors
+                    .push(file_error(id, format!("{:?}: {}", r.path, e)));
+                continue;
+            }
+        };
+        def.id = id.clone();
+        def.source = r.source.clone();
+        let issues = validate::validate(&def, |kind, name| {
+            crate::assets::resolve_source(app, kind, name).is_ok()
+        });
+        if issues.is_empty() {
+            defs.weapons.push(def);
+        } else {
+            defs.errors.extend(issues);
+        }
+    }
+    for issue in &defs.errors {
+        log::warn!(
+            "Weapon {} {}: {}",
+            issue.weapon,
+            issue.path.as_deref().unwrap_or(""),
+            issue.message
+        );
+    }
+    defs
+}
+
+/// Called by the dev asset watcher when something under
+/// `resources/data/weapons` changes.
+#[cfg(debug_assertions)]
+pub fn reload<R: Runtime>(app: &tauri::AppHandle<R>) {
+    use tauri::Emitter;
+
+    let defs = load_all(app);
+    log::info!(
+        "Reloaded weapon definitions: {} ok, {} error(s)",
+        defs.weapons.len(),
+        defs.errors.len()
+    );
+    let _ = app.emit(CHANGED_EVENT, defs);
+}
+
+/// Every weapon in `resources/data/weapons/` or an enabled mod's
+/// `data/weapons/`, with `inherits` applied. Weapons that fail validation
+/// are left out and their problems listed in `errors`. In dev builds,
+/// editing a file emits `weapon-defs-changed` with the reloaded set.
+#[tauri::command]
+pub async fn get_weapon_defs(app: tauri::AppHandle) -> WeaponDefs {
+    load_all(&app)
+}
+
+/// Weapon `id`, failing with its first problem if it didn't validate.
+#[tauri::command]
+pub async fn get_weapon_def(app: tauri::AppHandle, id: String) -> Result<WeaponDef, WeaponError> {
+    if !valid_id(&id) {
+        return Err(WeaponError::InvalidId(id));
+    }
+    let defs = load_all(&app);
+    if let Some(def) = defs.weapons.into_iter().find(|def| def.id == id) {
+        return Ok(def);
+    }
+    match defs.errors.into_iter().find(|issue| issue.weapon == id) {
+        Some(issue) => Err(WeaponError::Invalid {
+            id,
+            first: match issue.path {
+                Some(path) => format!("{}: {}", path, issue.message),
+                None => issue.message,
+            },
+        }),
+        None => Err(WeaponError::NotFound(id)),
+    }
+}
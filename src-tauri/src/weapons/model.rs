@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+fn one() -> f32 {
+    1.0
+}
+
+fn single_shot() -> u32 {
+    1
+}
+
+/// A weapon's stats after inheritance, as read from
+/// `resources/data/weapons/<id>.toml`. Files use snake_case keys; the
+/// frontend gets camelCase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct WeaponDef {
+    /// The file stem; not written in the file itself.
+    #[serde(default, skip_deserializing)]
+    pub id: String,
+    /// `bundled` or the id of the mod providing the file.
+    #[serde(default, skip_deserializing)]
+    pub source: String,
+    pub name: String,
+    /// Per bullet, before the headshot multiplier.
+    pub damage: f32,
+    #[serde(default = "one")]
+    pub headshot_multiplier: f32,
+    /// Rounds per second.
+    pub fire_rate: f32,
+    pub magazine_size: u32,
+    pub reload_seconds: f32,
+    /// Shots fired per trigger pull; 1 for semi-automatic and automatic
+    /// weapons.
+    #[serde(default = "single_shot")]
+    pub burst_length: u32,
+    /// Camera kick per shot of a burst as `[yaw, pitch]` in degrees, one
+    /// entry per shot.
+    pub recoil_pattern: Vec<[f32; 2]>,
+    pub spread: Spread,
+    pub sounds: WeaponSounds,
+}
+
+/// Bullet spread cone, in degrees.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct Spread {
+    pub base: f32,
+    pub max: f32,
+    /// Added by each shot, up to `max`.
+    pub per_shot: f32,
+    /// Recovered per second while not firing.
+    pub recovery: f32,
+}
+
+/// Audio assets, relative to `resources/audio`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct WeaponSounds {
+    pub fire: String,
+    #[serde(default)]
+    pub reload: Option<String>,
+    #[serde(default)]
+    pub empty: Option<String>,
+    #[serde(default)]
+    pub equip: Option<String>,
+}
+
+impl WeaponSounds {
+    /// Every sound with the key it was given under.
+    pub fn all(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("fire", Some(&self.fire)),
+            ("reload", self.reload.as_ref()),
+            ("empty", self.empty.as_ref()),
+            ("equip", self.equip.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(key, name)| Some((key, name?.as_str())))
+    }
+}
@@ -0,0 +1,102 @@
+use super::model::WeaponDef;
+use crate::assets::AssetKind;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponIssue {
+    pub weapon: String,
+    /// The key at fault, e.g. `spread.max`; `None` for problems with the
+    /// file as a whole.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+struct Checker<'a> {
+    weapon: &'a str,
+    issues: Vec<WeaponIssue>,
+}
+
+impl Checker<'_> {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.issues.push(WeaponIssue {
+            weapon: self.weapon.to_string(),
+            path: Some(path.to_string()),
+            message: message.into(),
+        });
+    }
+
+    fn at_least(&mut self, path: &str, value: f32, min: f32) {
+        if !(value.is_finite() && value >= min) {
+            self.error(path, format!("must be at least {}, not {}", min, value));
+        }
+    }
+
+    fn positive(&mut self, path: &str, value: f32) {
+        if !(value.is_finite() && value > 0.0) {
+            self.error(path, format!("must be more than 0, not {}", value));
+        }
+    }
+}
+
+/// Everything wrong with `def`; empty when it's fit to use. `resolves`
+/// says whether an asset exists.
+pub fn validate(
+    def: &WeaponDef,
+    mut resolves: impl FnMut(AssetKind, &str) -> bool,
+) -> Vec<WeaponIssue> {
+    let mut c = Checker {
+        weapon: &def.id,
+        issues: Vec::new(),
+    };
+    if def.name.trim().is_empty() {
+        c.error("name", "must not be empty");
+    }
+    c.at_least("damage", def.damage, 0.0);
+    c.positive("headshot_multiplier", def.headshot_multiplier);
+    c.positive("fire_rate", def.fire_rate);
+    if def.magazine_size == 0 {
+        c.error("magazine_size", "must be at least 1");
+    }
+    c.at_least("reload_seconds", def.reload_seconds, 0.0);
+
+    if def.burst_length == 0 {
+        c.error("burst_length", "must be at least 1");
+    } else if def.burst_length > def.magazine_size {
+        c.error(
+            "burst_length",
+            format!("is more than magazine_size ({})", def.magazine_size),
+        );
+    }
+    if def.recoil_pattern.len() != def.burst_length as usize {
+        c.error(
+            "recoil_pattern",
+            format!(
+                "has {} entries but burst_length is {}",
+                def.recoil_pattern.len(),
+                def.burst_length
+            ),
+        );
+    }
+    for (i, kick) in def.recoil_pattern.iter().enumerate() {
+        if !kick.iter().all(|v| v.is_finite()) {
+            c.error(&format!("recoil_pattern[{}]", i), "must be finite");
+        }
+    }
+
+    let spread = &def.spread;
+    c.at_least("spread.base", spread.base, 0.0);
+    c.at_least("spread.max", spread.max, spread.base.max(0.0));
+    c.at_least("spread.per_shot", spread.per_shot, 0.0);
+    c.at_least("spread.recovery", spread.recovery, 0.0);
+
+    for (key, name) in def.sounds.all() {
+        if !resolves(AssetKind::Audio, name) {
+            c.error(
+                &format!("sounds.{}", key),
+                format!("audio asset {:?} was not found", name),
+            );
+        }
+    }
+    c.issues
+}
@@ -61,10 +61,10 @@ export class SoundManager implements SoundManagerApi {
             try {
                 console.log(`Loading BGM from Rust: ${filename}`);
                 // Call Rust command to get raw bytes
-                const data = await invoke<number[]>('load_audio_asset', { filename });
+                const { bytes } = await invoke<{ bytes: number[]; resolvedPath: string; durationMs: number }>('load_audio_asset', { filename });
                 
                 // Convert number[] (Vec<u8>) to Uint8Array/ArrayBuffer
-                const arrayBuffer = Uint8Array.from(data).buffer;
+                const arrayBuffer = Uint8Array.from(bytes).buffer;
                 
                 // Decode audio data
                 const audioBuffer = await this.audioContext.decodeAudioData(arrayBuffer);